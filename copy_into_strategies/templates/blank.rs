@@ -0,0 +1,90 @@
+// Пустая стратегия: цикл событий, параметры и остановка - без торговой логики
+
+use crossbeam::channel::Receiver;
+use serde::Deserialize;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Params {
+    /// Alias ключей из keystore ядра
+    account: String,
+}
+
+/// Ядро проверяет params запуска по этой схеме: опечатка в имени поля - ошибка, а не Default
+#[no_mangle]
+pub extern "C" fn params_schema() -> *const std::os::raw::c_char {
+    cr#"{
+        "type": "object",
+        "properties": {
+            "account": { "type": "string", "description": "Alias ключей из keystore ядра" }
+        }
+    }"#
+    .as_ptr()
+}
+
+#[no_mangle]
+pub extern "C" fn run(
+    rx_ptr: *mut Receiver<CEvent>,
+    _place_order: PlaceOrderFn,
+    _cancel_order: CancelOrderFn,
+    config: StrategyConfig,
+) -> i32 {
+    config.init_logging();
+    let rx = unsafe { &*rx_ptr };
+    let params: Params = match config.parse_params() {
+        Ok(p) => p,
+        Err(e) => {
+            log_error(&format!("Bad params: {}", e));
+            return 1;
+        }
+    };
+    log_info(&format!("🚀 Started on {} (account '{}')", config.symbol(), params.account));
+
+    let mode = config.recv_mode();
+    let mut events = 0u64;
+    while !config.should_stop() {
+        let Some(event) = config.recv_event(rx, mode) else { continue };
+        events += 1;
+        // GET /metrics ядра: strategy_events_total{instance_id=...}
+        config.emit_metric("events", 1.0, METRIC_COUNTER);
+
+        match event.event_type {
+            EVENT_BOOK_TICKER => {
+                let _bt = unsafe { &event.data.book_ticker };
+            }
+            EVENT_TRADE => {
+                let _trade = unsafe { &event.data.trade };
+            }
+            EVENT_ORDER_UPDATE => {
+                let _update = unsafe { &event.data.order_update };
+            }
+            EVENT_ACCOUNT_UPDATE => {
+                let _update = unsafe { &event.data.account_update };
+            }
+            EVENT_FUNDING_RATE => {
+                let _rate = unsafe { &event.data.funding_rate };
+            }
+            EVENT_SIGNAL => {
+                let _signal = unsafe { &event.data.signal };
+            }
+            EVENT_TIMER => {
+                let _timer = unsafe { &event.data.timer };
+            }
+            EVENT_USER_DATA_UNKNOWN => {
+                // Событие user data, которое ядро не разбирает (name_str, payload_str)
+                let _unknown = unsafe { &event.data.unknown_user_data };
+            }
+            EVENT_ANALYTICS => {
+                // Спред и дисбалансы от ядра, если включён config.analytics
+                let _analytics = unsafe { &event.data.analytics };
+            }
+            EVENT_OPEN_INTEREST => {
+                let _oi = unsafe { &event.data.open_interest };
+            }
+            _ => {}
+        }
+    }
+
+    log_info(&format!("🛑 Stopped after {} events", events));
+    0
+}
@@ -0,0 +1,137 @@
+// Входит маркетом за seconds_before до funding и выходит через exit_delay_ms после, каждый раз
+
+use crossbeam::channel::Receiver;
+use serde::Deserialize;
+use std::ffi::CString;
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct Params {
+    /// Alias ключей из keystore ядра
+    account: String,
+    /// "BUY" - получаем funding при отрицательной ставке, "SELL" - при положительной
+    side: String,
+    quantity: f64,
+    seconds_before: i64,
+    exit_delay_ms: i64,
+    /// false - один цикл и остановка
+    repeat: bool,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            account: "main".to_string(),
+            side: "SELL".to_string(),
+            quantity: 0.001,
+            seconds_before: 5,
+            exit_delay_ms: 100,
+            repeat: true,
+        }
+    }
+}
+
+unsafe extern "C" fn on_order(result: OrderResult) {
+    if result.success {
+        log_info(&format!("✅ Order #{} accepted", result.order_id));
+    } else {
+        log_warn(&format!("❌ Order failed: {}", result.error_code));
+    }
+}
+
+enum Stage {
+    /// Ждём время входа перед funding
+    WaitEntry { funding_ms: i64 },
+    /// В позиции, ждём время выхода
+    WaitExit { funding_ms: i64 },
+    Done,
+}
+
+#[no_mangle]
+pub extern "C" fn run(
+    rx_ptr: *mut Receiver<CEvent>,
+    place_order: PlaceOrderFn,
+    _cancel_order: CancelOrderFn,
+    config: StrategyConfig,
+) -> i32 {
+    config.init_logging();
+    let rx = unsafe { &*rx_ptr };
+    let params: Params = match config.parse_params() {
+        Ok(p) => p,
+        Err(e) => {
+            log_error(&format!("Bad params: {}", e));
+            return 1;
+        }
+    };
+
+    let entry_side = params.side.to_uppercase();
+    let exit_side = if entry_side == "BUY" { "SELL" } else { "BUY" };
+    let api_key = CString::new(params.account.clone()).unwrap_or_default();
+    let secret_key = CString::new("").unwrap();
+    let symbol = CString::new(config.symbol()).unwrap_or_default();
+
+    let market = |side: &str| {
+        let side = CString::new(side).unwrap_or_default();
+        unsafe {
+            place_order(
+                api_key.as_ptr(),
+                secret_key.as_ptr(),
+                symbol.as_ptr(),
+                0.0,
+                params.quantity,
+                side.as_ptr(),
+                1,
+                on_order,
+            );
+        }
+    };
+
+    // Время биржи: на live - часы со смещением до Binance, на replay - время записи
+    let exchange_now = || config.now_ms() + config.time_offset_ms();
+
+    // Ближайший funding, ко входу в который ещё успеваем
+    let mut stage = match config.next_funding_after(config.symbol(), exchange_now() + params.seconds_before * 1000) {
+        Some(funding_ms) => Stage::WaitEntry { funding_ms },
+        None => {
+            log_error(&format!("No funding time for {}", config.symbol()));
+            return 1;
+        }
+    };
+    let mode = config.recv_mode();
+
+    while !config.should_stop() {
+        // События не нужны, recv_event служит таймером (~100ms) и держит канал пустым
+        let _ = config.recv_event(rx, mode);
+        let now = exchange_now();
+
+        stage = match stage {
+            Stage::WaitEntry { funding_ms } if now >= funding_ms - params.seconds_before * 1000 => {
+                log_info(&format!("📥 Entry {} {} ({} ms to funding)", entry_side, params.quantity, funding_ms - now));
+                market(&entry_side);
+                Stage::WaitExit { funding_ms }
+            }
+            Stage::WaitExit { funding_ms } if now >= funding_ms + params.exit_delay_ms => {
+                log_info(&format!("📤 Exit {} {}", exit_side, params.quantity));
+                market(exit_side);
+                if !params.repeat {
+                    Stage::Done
+                } else {
+                    // Календарь ядра знает интервал символа (1h/4h/8h)
+                    match config.next_funding_after(config.symbol(), funding_ms) {
+                        Some(next) => Stage::WaitEntry { funding_ms: next },
+                        None => Stage::Done,
+                    }
+                }
+            }
+            Stage::Done => break,
+            other => other,
+        };
+    }
+
+    if let Stage::WaitExit { .. } = stage {
+        log_warn("⚠️ Stopped while in position, closing");
+        market(exit_side);
+    }
+    log_info("🛑 Stopped");
+    0
+}
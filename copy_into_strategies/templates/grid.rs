@@ -0,0 +1,154 @@
+// Сетка лимиток вокруг первой mid-цены: после исполнения ставит встречный ордер на шаг дальше
+
+use crossbeam::channel::Receiver;
+use serde::Deserialize;
+use std::ffi::CString;
+use std::sync::Mutex;
+
+/// id выставленных уровней из callback'ов: снимаем их при остановке
+static ORDER_IDS: Mutex<Vec<i64>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct Params {
+    /// Alias ключей из keystore ядра
+    account: String,
+    /// Уровней с каждой стороны
+    levels: u32,
+    /// Шаг сетки, б.п. от центра
+    step_bps: f64,
+    /// Объём на уровень
+    quantity: f64,
+    tick_size: f64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            account: "main".to_string(),
+            levels: 5,
+            step_bps: 10.0,
+            quantity: 0.001,
+            tick_size: 0.1,
+        }
+    }
+}
+
+unsafe extern "C" fn on_order(result: OrderResult) {
+    if result.success {
+        ORDER_IDS.lock().unwrap().push(result.order_id);
+    } else {
+        log_warn(&format!("❌ Grid order failed: {}", result.error_code));
+    }
+}
+
+unsafe extern "C" fn on_cancel(result: OrderResult) {
+    if !result.success {
+        log_warn(&format!("❌ Cancel failed: {}", result.error_code));
+    }
+}
+
+struct Grid<'a> {
+    params: &'a Params,
+    place_order: PlaceOrderFn,
+    api_key: CString,
+    secret_key: CString,
+    symbol: CString,
+    step: f64,
+}
+
+impl Grid<'_> {
+    fn round(&self, price: f64) -> f64 {
+        (price / self.params.tick_size).round() * self.params.tick_size
+    }
+
+    fn place(&self, side: &str, price: f64) {
+        let side_c = CString::new(side).unwrap_or_default();
+        let price = self.round(price);
+        unsafe {
+            (self.place_order)(
+                self.api_key.as_ptr(),
+                self.secret_key.as_ptr(),
+                self.symbol.as_ptr(),
+                price,
+                self.params.quantity,
+                side_c.as_ptr(),
+                0,
+                on_order,
+            );
+        }
+        log_info(&format!("📝 {} {} @ {:.2}", side, self.params.quantity, price));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn run(
+    rx_ptr: *mut Receiver<CEvent>,
+    place_order: PlaceOrderFn,
+    cancel_order: CancelOrderFn,
+    config: StrategyConfig,
+) -> i32 {
+    config.init_logging();
+    let rx = unsafe { &*rx_ptr };
+    let params: Params = match config.parse_params() {
+        Ok(p) => p,
+        Err(e) => {
+            log_error(&format!("Bad params: {}", e));
+            return 1;
+        }
+    };
+
+    let mut grid: Option<Grid> = None;
+    let mode = config.recv_mode();
+
+    while !config.should_stop() {
+        let Some(event) = config.recv_event(rx, mode) else { continue };
+        if !event.symbol_str().eq_ignore_ascii_case(config.symbol()) {
+            continue;
+        }
+
+        match (event.event_type, &grid) {
+            // Первая котировка задаёт центр сетки
+            (EVENT_BOOK_TICKER, None) => {
+                let mid = unsafe { event.data.book_ticker.mid_price() };
+                let g = Grid {
+                    params: &params,
+                    place_order,
+                    api_key: CString::new(params.account.clone()).unwrap_or_default(),
+                    secret_key: CString::new("").unwrap(),
+                    symbol: CString::new(config.symbol()).unwrap_or_default(),
+                    step: mid * params.step_bps / 10_000.0,
+                };
+                log_info(&format!("🚀 Grid around {:.2}: {} levels, step {:.2}", mid, params.levels, g.step));
+                for i in 1..=params.levels {
+                    g.place("BUY", mid - g.step * i as f64);
+                    g.place("SELL", mid + g.step * i as f64);
+                }
+                grid = Some(g);
+            }
+            // Исполненный уровень заменяем встречным на шаг дальше
+            (EVENT_ORDER_UPDATE, Some(g)) => {
+                let update = unsafe { &event.data.order_update };
+                if update.status != 2 {
+                    continue;
+                }
+                ORDER_IDS.lock().unwrap().retain(|id| *id != update.order_id);
+                if update.side == 0 {
+                    g.place("SELL", update.avg_price + g.step);
+                } else {
+                    g.place("BUY", update.avg_price - g.step);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(g) = &grid {
+        let ids = std::mem::take(&mut *ORDER_IDS.lock().unwrap());
+        for id in ids {
+            unsafe { cancel_order(g.api_key.as_ptr(), g.secret_key.as_ptr(), g.symbol.as_ptr(), id, on_cancel) };
+        }
+    }
+    log_info("🛑 Stopped");
+    0
+}
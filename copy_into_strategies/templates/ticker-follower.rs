@@ -0,0 +1,136 @@
+// Держит лимитный BUY на offset_bps под bid и переставляет его, когда bid уходит дальше requote_bps
+
+use crossbeam::channel::Receiver;
+use serde::Deserialize;
+use std::ffi::CString;
+use std::sync::atomic::{AtomicI64, Ordering};
+
+/// id нашего ордера из callback'а (0 - нет или ещё нет ответа)
+static ORDER_ID: AtomicI64 = AtomicI64::new(0);
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct Params {
+    /// Alias ключей из keystore ядра
+    account: String,
+    quantity: f64,
+    /// Насколько ниже bid стоит ордер, б.п.
+    offset_bps: f64,
+    /// Сдвиг bid от цены последней перестановки, после которого переставляем, б.п.
+    requote_bps: f64,
+    tick_size: f64,
+    /// Не чаще раза в N ms
+    min_requote_ms: i64,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            account: "main".to_string(),
+            quantity: 0.001,
+            offset_bps: 20.0,
+            requote_bps: 5.0,
+            tick_size: 0.1,
+            min_requote_ms: 500,
+        }
+    }
+}
+
+unsafe extern "C" fn on_place(result: OrderResult) {
+    if result.success {
+        ORDER_ID.store(result.order_id, Ordering::Relaxed);
+    } else {
+        log_warn(&format!("❌ Place failed: {}", result.error_code));
+    }
+}
+
+unsafe extern "C" fn on_cancel(result: OrderResult) {
+    if !result.success {
+        log_warn(&format!("❌ Cancel failed: {}", result.error_code));
+    }
+}
+
+fn round_to_tick(price: f64, tick: f64) -> f64 {
+    (price / tick).floor() * tick
+}
+
+#[no_mangle]
+pub extern "C" fn run(
+    rx_ptr: *mut Receiver<CEvent>,
+    place_order: PlaceOrderFn,
+    cancel_order: CancelOrderFn,
+    config: StrategyConfig,
+) -> i32 {
+    config.init_logging();
+    let rx = unsafe { &*rx_ptr };
+    let params: Params = match config.parse_params() {
+        Ok(p) => p,
+        Err(e) => {
+            log_error(&format!("Bad params: {}", e));
+            return 1;
+        }
+    };
+
+    let api_key = CString::new(params.account.clone()).unwrap_or_default();
+    let secret_key = CString::new("").unwrap();
+    let symbol = CString::new(config.symbol()).unwrap_or_default();
+    let side = CString::new("BUY").unwrap();
+    log_info(&format!("🚀 Following {} bid, {} bps below", config.symbol(), params.offset_bps));
+
+    // bid, от которого выставлен текущий ордер (0 - ордера нет)
+    let mut quoted_bid = 0.0;
+    let mut last_requote_ms = 0i64;
+    let mode = config.recv_mode();
+
+    while !config.should_stop() {
+        let Some(event) = config.recv_event(rx, mode) else { continue };
+        if event.event_type != EVENT_BOOK_TICKER {
+            continue;
+        }
+        let bt = unsafe { &event.data.book_ticker };
+        if !bt.symbol_str().eq_ignore_ascii_case(config.symbol()) {
+            continue;
+        }
+
+        let moved_bps = if quoted_bid > 0.0 {
+            (bt.bid_price - quoted_bid).abs() / quoted_bid * 10_000.0
+        } else {
+            f64::INFINITY
+        };
+        let now = config.now_ms();
+        if moved_bps < params.requote_bps || now - last_requote_ms < params.min_requote_ms {
+            continue;
+        }
+
+        // Снимаем прошлый ордер и ставим новый
+        let prev = ORDER_ID.swap(0, Ordering::Relaxed);
+        if prev > 0 {
+            unsafe { cancel_order(api_key.as_ptr(), secret_key.as_ptr(), symbol.as_ptr(), prev, on_cancel) };
+        }
+
+        let price = round_to_tick(bt.bid_price * (1.0 - params.offset_bps / 10_000.0), params.tick_size);
+        unsafe {
+            place_order(
+                api_key.as_ptr(),
+                secret_key.as_ptr(),
+                symbol.as_ptr(),
+                price,
+                params.quantity,
+                side.as_ptr(),
+                0,
+                on_place,
+            );
+        }
+        log_info(&format!("📝 Requote: bid {:.2} -> BUY {} @ {:.2}", bt.bid_price, params.quantity, price));
+        quoted_bid = bt.bid_price;
+        last_requote_ms = now;
+    }
+
+    // Не оставляем ордер висеть после остановки
+    let last = ORDER_ID.swap(0, Ordering::Relaxed);
+    if last > 0 {
+        unsafe { cancel_order(api_key.as_ptr(), secret_key.as_ptr(), symbol.as_ptr(), last, on_cancel) };
+    }
+    log_info("🛑 Stopped");
+    0
+}
@@ -0,0 +1,65 @@
+// src/affinity.rs
+
+use serde::Deserialize;
+use std::future::Future;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+/// Привязка горячих потоков к ядрам CPU (номера ядер как в /proc/cpuinfo).
+/// null - поток не закреплён, планировщик ОС двигает его как хочет.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AffinityConfig {
+    /// Reader market data WS (парсинг + fan-out в стратегии)
+    pub market_data: Option<usize>,
+    /// Соединения trade WS (подпись + запись ордеров)
+    pub trade_ws: Option<usize>,
+}
+
+// ═══════════════════════════════════════════════════════════
+// ЗАКРЕПЛЕНИЕ
+// ═══════════════════════════════════════════════════════════
+
+/// Есть ли такое ядро на этой машине
+pub fn is_valid(core: usize) -> bool {
+    core_affinity::get_core_ids().is_some_and(|ids| ids.iter().any(|c| c.id == core))
+}
+
+/// Закрепляет текущий поток за ядром
+pub fn pin_current(core: usize) -> bool {
+    let pinned = is_valid(core) && core_affinity::set_for_current(core_affinity::CoreId { id: core });
+    if !pinned {
+        tracing::warn!("📌 Failed to pin thread to core {}", core);
+    }
+    pinned
+}
+
+/// Запускает задачу: без ядра - обычный tokio::spawn, с ядром - отдельный поток
+/// с current_thread runtime, закреплённый за ядром. Всё, что задача спаунит,
+/// остаётся на этом же потоке.
+pub fn spawn_pinned<F>(name: &str, core: Option<usize>, future: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let Some(core) = core else {
+        tokio::spawn(future);
+        return;
+    };
+
+    let thread_name = name.to_string();
+    std::thread::Builder::new()
+        .name(thread_name.clone())
+        .spawn(move || {
+            if pin_current(core) {
+                tracing::info!("📌 '{}' pinned to core {}", thread_name, core);
+            }
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("Failed to build pinned runtime")
+                .block_on(future);
+        })
+        .expect("Failed to spawn pinned thread");
+}
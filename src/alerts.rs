@@ -0,0 +1,63 @@
+// src/alerts.rs
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+// ═══════════════════════════════════════════════════════════
+// ALERTS
+// ═══════════════════════════════════════════════════════════
+
+const MAX_ALERTS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertLevel {
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub level: AlertLevel,
+    /// Подсистема-источник: "trade", "risk", ...
+    pub source: String,
+    pub message: String,
+    /// Unix ms
+    pub time: i64,
+}
+
+/// Последние алерты (кольцевой буфер)
+static ALERTS: OnceLock<Mutex<VecDeque<Alert>>> = OnceLock::new();
+
+fn buffer() -> &'static Mutex<VecDeque<Alert>> {
+    ALERTS.get_or_init(|| Mutex::new(VecDeque::with_capacity(MAX_ALERTS)))
+}
+
+/// Регистрирует алерт и пишет его в лог
+pub fn emit(level: AlertLevel, source: &str, message: impl Into<String>) {
+    let message = message.into();
+
+    match level {
+        AlertLevel::Warning => tracing::warn!("🔔 [{}] {}", source, message),
+        AlertLevel::Critical => tracing::error!("🚨 [{}] {}", source, message),
+    }
+
+    let alert = Alert {
+        level,
+        source: source.to_string(),
+        message,
+        time: chrono::Utc::now().timestamp_millis(),
+    };
+
+    let mut buf = buffer().lock().unwrap();
+    if buf.len() >= MAX_ALERTS {
+        buf.pop_front();
+    }
+    buf.push_back(alert);
+}
+
+/// Последние алерты, новые первыми
+pub fn recent() -> Vec<Alert> {
+    buffer().lock().unwrap().iter().rev().cloned().collect()
+}
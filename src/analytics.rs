@@ -0,0 +1,182 @@
+// src/analytics.rs
+
+use dashmap::DashMap;
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+use crate::ffi_types::{CAnalytics, CBookTicker, CEvent, CEventData, CTrade, EVENT_ANALYTICS};
+
+/// Короткое и длинное окно потока сделок, ms
+const SHORT_WINDOW_MS: i64 = 1_000;
+const LONG_WINDOW_MS: i64 = 5_000;
+
+// ═══════════════════════════════════════════════════════════
+// АНАЛИТИКА MARKET DATA
+// ═══════════════════════════════════════════════════════════
+//
+// Спред, дисбаланс стакана и потока сделок нужны почти каждой простой
+// стратегии, и каждая держит для них свой VecDeque. Здесь они считаются
+// один раз в reader market data и уходят стратегиям событием
+// EVENT_ANALYTICS по символу - после bookTicker или trade, который их
+// изменил. Окна идут по времени биржи ("E"). Recorder пишет только
+// исходные bookTicker/trade: в replay EVENT_ANALYTICS не приходит.
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AnalyticsConfig {
+    /// Считать и рассылать EVENT_ANALYTICS
+    pub enabled: bool,
+    /// Не чаще одного события на символ за столько ms (0 - на каждое обновление)
+    pub min_interval_ms: u64,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self { enabled: false, min_interval_ms: 100 }
+    }
+}
+
+/// Сделки за последние span_ms с суммами по сторонам
+struct FlowWindow {
+    span_ms: i64,
+    /// (время биржи ms, qty со знаком: + покупка тейкера, - продажа)
+    trades: VecDeque<(i64, f64)>,
+    buy: f64,
+    sell: f64,
+}
+
+impl FlowWindow {
+    fn new(span_ms: i64) -> Self {
+        Self { span_ms, trades: VecDeque::new(), buy: 0.0, sell: 0.0 }
+    }
+
+    fn push(&mut self, time: i64, qty: f64) {
+        if qty > 0.0 {
+            self.buy += qty;
+        } else {
+            self.sell -= qty;
+        }
+        self.trades.push_back((time, qty));
+    }
+
+    fn evict(&mut self, now: i64) {
+        while let Some(&(time, qty)) = self.trades.front() {
+            if time > now - self.span_ms {
+                break;
+            }
+            if qty > 0.0 {
+                self.buy -= qty;
+            } else {
+                self.sell += qty;
+            }
+            self.trades.pop_front();
+        }
+        if self.trades.is_empty() {
+            // Не копим ошибку округления сумм
+            self.buy = 0.0;
+            self.sell = 0.0;
+        }
+    }
+
+    fn volume(&self) -> f64 {
+        self.buy + self.sell
+    }
+
+    /// (buy - sell) / (buy + sell), 0 - сделок нет
+    fn imbalance(&self) -> f64 {
+        let volume = self.volume();
+        if volume > 0.0 { (self.buy - self.sell) / volume } else { 0.0 }
+    }
+}
+
+struct SymbolState {
+    book: Option<CBookTicker>,
+    short: FlowWindow,
+    long: FlowWindow,
+    /// Время биржи последнего события, ms
+    now: i64,
+    /// Время биржи последнего EVENT_ANALYTICS, ms
+    emitted_at: Option<i64>,
+}
+
+impl SymbolState {
+    fn new() -> Self {
+        Self {
+            book: None,
+            short: FlowWindow::new(SHORT_WINDOW_MS),
+            long: FlowWindow::new(LONG_WINDOW_MS),
+            now: 0,
+            emitted_at: None,
+        }
+    }
+
+    fn advance(&mut self, time: i64) {
+        self.now = self.now.max(time);
+        self.short.evict(self.now);
+        self.long.evict(self.now);
+    }
+}
+
+/// Состояние аналитики reader market data
+pub struct Analytics {
+    config: AnalyticsConfig,
+    symbols: DashMap<String, SymbolState>,
+}
+
+impl Analytics {
+    /// None - аналитика выключена
+    pub fn new(config: AnalyticsConfig) -> Option<Self> {
+        config.enabled.then(|| Self { config, symbols: DashMap::new() })
+    }
+
+    /// Новая вершина стакана. Some - событие для стратегий (seq не проставлен)
+    pub fn on_book(&self, book: &CBookTicker, received_at_ns: u64) -> Option<CEvent> {
+        let mut state = self.symbols.entry(book.symbol_str().to_string()).or_insert_with(SymbolState::new);
+        state.book = Some(*book);
+        state.advance(book.time);
+        self.emit(&mut state, book, received_at_ns)
+    }
+
+    /// Новая сделка. Без стакана по символу события нет: спред не из чего считать
+    pub fn on_trade(&self, trade: &CTrade, received_at_ns: u64) -> Option<CEvent> {
+        let mut state = self.symbols.entry(trade.symbol_str().to_string()).or_insert_with(SymbolState::new);
+        state.short.push(trade.time, trade.qty);
+        state.long.push(trade.time, trade.qty);
+        state.advance(trade.time);
+        let book = state.book?;
+        self.emit(&mut state, &book, received_at_ns)
+    }
+
+    fn emit(&self, state: &mut SymbolState, book: &CBookTicker, received_at_ns: u64) -> Option<CEvent> {
+        if let Some(at) = state.emitted_at {
+            if state.now - at < self.config.min_interval_ms as i64 {
+                return None;
+            }
+        }
+        state.emitted_at = Some(state.now);
+
+        let mid_price = book.mid_price();
+        let depth = book.bid_qty + book.ask_qty;
+        Some(CEvent {
+            event_type: EVENT_ANALYTICS,
+            data: CEventData {
+                analytics: CAnalytics {
+                    symbol: book.symbol,
+                    symbol_len: book.symbol_len,
+                    spread_bps: if mid_price > 0.0 { book.spread() / mid_price * 10_000.0 } else { 0.0 },
+                    mid_price,
+                    book_imbalance: if depth > 0.0 { (book.bid_qty - book.ask_qty) / depth } else { 0.0 },
+                    flow_imbalance_1s: state.short.imbalance(),
+                    flow_imbalance_5s: state.long.imbalance(),
+                    volume_1s: state.short.volume(),
+                    volume_5s: state.long.volume(),
+                    trades_1s: state.short.trades.len() as u32,
+                    trades_5s: state.long.trades.len() as u32,
+                    time: state.now,
+                },
+            },
+            received_at_ns,
+            seq: 0,
+        })
+    }
+}
@@ -0,0 +1,314 @@
+// src/audit.rs
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::{mpsc, OnceLock};
+
+use crate::user_data::key_id;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AuditConfig {
+    /// Выключен по умолчанию: журнал пишет каждый запрос к бирже
+    pub enabled: bool,
+    pub dir: String,
+    /// Ротация при достижении размера
+    pub max_file_mb: u64,
+    /// Сколько ротированных файлов хранить (0 = все)
+    pub max_files: usize,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: "./data/audit".to_string(),
+            max_file_mb: 100,
+            max_files: 30,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ЗАПИСИ
+// ═══════════════════════════════════════════════════════════
+
+enum Entry {
+    /// Подписанный запрос, ушедший в trade WS
+    Request {
+        time: i64,
+        id: String,
+        instance_id: Option<String>,
+        payload: String,
+    },
+    /// Ответ биржи (или синтетическая ошибка при дисконнекте)
+    Response {
+        time: i64,
+        id: String,
+        instance_id: Option<String>,
+        response: Value,
+    },
+    /// Запрос отклонён локально и на биржу не ушёл
+    Rejected {
+        time: i64,
+        id: Option<String>,
+        instance_id: Option<String>,
+        method: &'static str,
+        params: Value,
+        code: i32,
+        message: String,
+    },
+}
+
+impl Entry {
+    fn to_json(&self) -> Value {
+        match self {
+            Entry::Request { time, id, instance_id, payload } => {
+                let mut msg: Value = serde_json::from_str(payload).unwrap_or(Value::Null);
+                // apiKey целиком в аудит не пишем
+                let key = msg["params"]["apiKey"].as_str().map(key_id);
+                if let (Some(params), Some(key)) = (msg["params"].as_object_mut(), key) {
+                    params.insert("apiKey".into(), Value::String(key));
+                }
+                json!({
+                    "time": time,
+                    "event": "request",
+                    "id": id,
+                    "instance_id": instance_id,
+                    "method": msg["method"],
+                    "params": msg["params"],
+                })
+            }
+            Entry::Response { time, id, instance_id, response } => json!({
+                "time": time,
+                "event": "response",
+                "id": id,
+                "instance_id": instance_id,
+                "status": response["status"],
+                "result": response.get("result"),
+                "error": response.get("error"),
+            }),
+            Entry::Rejected { time, id, instance_id, method, params, code, message } => json!({
+                "time": time,
+                "event": "rejected",
+                "id": id,
+                "instance_id": instance_id,
+                "method": method,
+                "params": params,
+                "error": { "code": code, "msg": message },
+            }),
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// AUDIT LOG
+// ═══════════════════════════════════════════════════════════
+
+static AUDIT: OnceLock<mpsc::Sender<Entry>> = OnceLock::new();
+
+tokio::task_local! {
+    /// instance_id, от имени которого выполняется запрос к бирже.
+    /// Выставляется в place_order/cancel_order на время async-задачи.
+    pub static AUDIT_INSTANCE: Option<String>;
+}
+
+/// instance_id текущей задачи (None - вне place_order/cancel_order)
+pub fn instance() -> Option<String> {
+    AUDIT_INSTANCE.try_with(|i| i.clone()).ok().flatten()
+}
+
+/// Запускает поток записи. Без вызова init аудит выключен.
+pub fn init(config: &AuditConfig) -> anyhow::Result<()> {
+    if !config.enabled {
+        tracing::info!("📜 Audit log disabled");
+        return Ok(());
+    }
+
+    let mut writer = Writer::open(config)?;
+    let (tx, rx) = mpsc::channel::<Entry>();
+
+    std::thread::Builder::new()
+        .name("audit-writer".into())
+        .spawn(move || {
+            while let Ok(entry) = rx.recv() {
+                writer.write(&entry);
+                for entry in rx.try_iter() {
+                    writer.write(&entry);
+                }
+                writer.flush();
+            }
+        })?;
+
+    AUDIT.set(tx).ok();
+    tracing::info!("📜 Audit log at '{}'", config.dir);
+    Ok(())
+}
+
+fn send(entry: Entry) {
+    if let Some(tx) = AUDIT.get() {
+        let _ = tx.send(entry);
+    }
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+pub fn enabled() -> bool {
+    AUDIT.get().is_some()
+}
+
+/// Подписанный запрос перед отправкой в WS
+pub fn request(id: &str, instance_id: Option<String>, payload: &str) {
+    send(Entry::Request {
+        time: now_ms(),
+        id: id.to_string(),
+        instance_id,
+        payload: payload.to_string(),
+    });
+}
+
+/// Ответ на запрос (instance_id запоминается при отправке)
+pub fn response(id: &str, instance_id: Option<String>, response: &Value) {
+    if AUDIT.get().is_none() {
+        return;
+    }
+    send(Entry::Response {
+        time: now_ms(),
+        id: id.to_string(),
+        instance_id,
+        response: response.clone(),
+    });
+}
+
+/// Запрос, отклонённый ядром до отправки (risk, circuit breaker, rate limiter).
+/// params - параметры ордера, apiKey уже сокращён до key_id
+pub fn rejected(
+    id: Option<&str>,
+    instance_id: Option<String>,
+    method: &'static str,
+    params: Value,
+    code: i32,
+    message: &str,
+) {
+    send(Entry::Rejected {
+        time: now_ms(),
+        id: id.map(str::to_string),
+        instance_id,
+        method,
+        params,
+        code,
+        message: message.to_string(),
+    });
+}
+
+// ═══════════════════════════════════════════════════════════
+// WRITER
+// ═══════════════════════════════════════════════════════════
+
+/// Append-only JSONL с ротацией по размеру:
+/// audit.jsonl -> audit-YYYYMMDD-HHMMSS.jsonl
+struct Writer {
+    dir: PathBuf,
+    file: BufWriter<File>,
+    size: u64,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl Writer {
+    fn open(config: &AuditConfig) -> anyhow::Result<Self> {
+        let dir = PathBuf::from(&config.dir);
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join("audit.jsonl");
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+
+        Ok(Self {
+            dir,
+            file: BufWriter::new(file),
+            size,
+            max_bytes: config.max_file_mb.max(1) * 1024 * 1024,
+            max_files: config.max_files,
+        })
+    }
+
+    fn write(&mut self, entry: &Entry) {
+        let mut line = entry.to_json().to_string();
+        line.push('\n');
+
+        if let Err(e) = self.file.write_all(line.as_bytes()) {
+            tracing::error!("📜 Audit write failed: {}", e);
+            return;
+        }
+        self.size += line.len() as u64;
+
+        if self.size >= self.max_bytes {
+            if let Err(e) = self.rotate() {
+                tracing::error!("📜 Audit rotation failed: {}", e);
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Err(e) = self.file.flush() {
+            tracing::error!("📜 Audit flush failed: {}", e);
+        }
+    }
+
+    fn rotate(&mut self) -> anyhow::Result<()> {
+        self.file.flush()?;
+
+        let current = self.dir.join("audit.jsonl");
+        // Миллисекунды и счётчик: две ротации подряд не перезапишут одна другую
+        let stamp = chrono::Utc::now().format("%Y%m%d-%H%M%S-%3f").to_string();
+        let mut target = self.dir.join(format!("audit-{}.jsonl", stamp));
+        let mut n = 0;
+        while target.exists() {
+            n += 1;
+            target = self.dir.join(format!("audit-{}_{}.jsonl", stamp, n));
+        }
+        fs::rename(&current, target)?;
+
+        let file = OpenOptions::new().create(true).append(true).open(&current)?;
+        self.file = BufWriter::new(file);
+        self.size = 0;
+
+        self.prune()?;
+        tracing::info!("📜 Audit log rotated");
+        Ok(())
+    }
+
+    /// Удаляет самые старые ротированные файлы сверх max_files
+    fn prune(&self) -> anyhow::Result<()> {
+        if self.max_files == 0 {
+            return Ok(());
+        }
+
+        let mut rotated: Vec<PathBuf> = fs::read_dir(&self.dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("audit-") && n.ends_with(".jsonl"))
+            })
+            .collect();
+
+        // Имена содержат время - лексикографический порядок хронологический
+        rotated.sort();
+        let excess = rotated.len().saturating_sub(self.max_files);
+        for path in rotated.into_iter().take(excess) {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
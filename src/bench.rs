@@ -0,0 +1,200 @@
+// src/bench.rs
+
+use crossbeam::channel::{bounded, Receiver};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::fanout::{self, OverflowPolicy};
+use crate::ffi_types::{pack_str, CBookTicker, CEvent, CEventData, EVENT_BOOK_TICKER};
+use crate::latency;
+
+/// Верхняя граница размера прогона
+const MAX_EVENTS: u64 = 50_000_000;
+
+/// Сколько замеров задержки храним на одного потребителя (остальные - с шагом)
+const MAX_SAMPLES: u64 = 1_000_000;
+
+/// Символ синтетических событий: стратегии его не торгуют
+const BENCH_SYMBOL: &str = "BENCHUSDT";
+
+/// Один прогон за раз: события идут через общий fan-out
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+// ═══════════════════════════════════════════════════════════
+// ЗАПРОС / ОТВЕТ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BenchRequest {
+    pub events: u64,
+    /// Событий в секунду; None - сколько успеет producer
+    pub rate: Option<u64>,
+    /// Сколько no-op стратегий подписать на fan-out
+    pub consumers: usize,
+    pub channel_capacity: usize,
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for BenchRequest {
+    fn default() -> Self {
+        Self {
+            events: 1_000_000,
+            rate: None,
+            consumers: 1,
+            channel_capacity: 8192,
+            overflow: OverflowPolicy::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConsumerResult {
+    pub received: u64,
+    pub dropped: u64,
+    /// received_at_ns -> событие вынуто из канала no-op стратегией
+    pub p50_ns: u64,
+    pub p90_ns: u64,
+    pub p99_ns: u64,
+    pub p999_ns: u64,
+    pub max_ns: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchResult {
+    pub events: u64,
+    pub elapsed_ms: f64,
+    /// Скорость publish, событий/с
+    pub throughput: f64,
+    pub consumers: Vec<ConsumerResult>,
+}
+
+// ═══════════════════════════════════════════════════════════
+// ПРОГОН
+// ═══════════════════════════════════════════════════════════
+
+/// Гонит синтетические bookTicker через fanout::publish в каналы no-op потребителей.
+/// Блокирующий: вызывать из spawn_blocking. Замеры попадают и в /api/latency
+/// (стадии fanout/enqueue), поэтому запускать без live-стратегий
+pub fn run(req: &BenchRequest) -> anyhow::Result<BenchResult> {
+    if req.events == 0 || req.events > MAX_EVENTS {
+        anyhow::bail!("events must be 1..={}", MAX_EVENTS);
+    }
+    if !(1..=64).contains(&req.consumers) {
+        anyhow::bail!("consumers must be 1..=64");
+    }
+    if req.channel_capacity == 0 {
+        anyhow::bail!("channel_capacity must be > 0");
+    }
+    if RUNNING.swap(true, Ordering::AcqRel) {
+        anyhow::bail!("Benchmark is already running");
+    }
+    let result = run_inner(req);
+    RUNNING.store(false, Ordering::Release);
+    result
+}
+
+fn run_inner(req: &BenchRequest) -> anyhow::Result<BenchResult> {
+    let step = req.events.div_ceil(MAX_SAMPLES);
+
+    let mut subs = Vec::new();
+    let mut workers = Vec::new();
+    for n in 0..req.consumers {
+        let (tx, rx) = bounded::<CEvent>(req.channel_capacity);
+        let id = format!("bench-{}", n);
+        subs.push(fanout::subscribe(&id, tx, &rx, req.overflow, Arc::new(AtomicU64::new(0)), None));
+        workers.push(std::thread::Builder::new().name(id).spawn(move || consume(rx, step))?);
+    }
+
+    tracing::info!("🏎️ Benchmark: {} events, {} consumers, rate={:?}", req.events, req.consumers, req.rate);
+    let (symbol, symbol_len) = pack_str::<16>(BENCH_SYMBOL);
+    let interval = req.rate.filter(|r| *r > 0).map(|r| Duration::from_secs_f64(1.0 / r as f64));
+    let started = Instant::now();
+
+    for i in 0..req.events {
+        if let Some(interval) = interval {
+            let due = started + interval.mul_f64(i as f64);
+            while Instant::now() < due {
+                std::hint::spin_loop();
+            }
+        }
+        let price = 100.0 + (i % 100) as f64 * 0.01;
+        fanout::publish(CEvent {
+            event_type: EVENT_BOOK_TICKER,
+            data: CEventData {
+                book_ticker: CBookTicker {
+                    symbol,
+                    symbol_len,
+                    bid_price: price,
+                    ask_price: price + 0.01,
+                    bid_qty: 1.0,
+                    ask_qty: 1.0,
+                    time: 0,
+                },
+            },
+            received_at_ns: latency::now_ns(),
+            seq: i + 1,
+        });
+    }
+    let elapsed = started.elapsed();
+
+    // Отписка роняет последний Sender - потребители дочитывают очередь и выходят
+    let dropped: Vec<u64> = subs.iter().map(|s| s.dropped()).collect();
+    for sub in &subs {
+        fanout::unsubscribe(sub);
+    }
+    drop(subs);
+
+    let consumers = workers
+        .into_iter()
+        .zip(dropped)
+        .map(|(worker, dropped)| {
+            let (received, samples) = worker.join().unwrap_or_default();
+            summarize(received, dropped, samples)
+        })
+        .collect();
+
+    let result = BenchResult {
+        events: req.events,
+        elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+        throughput: req.events as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        consumers,
+    };
+    tracing::info!("🏁 Benchmark done: {:.0} events/s", result.throughput);
+    Ok(result)
+}
+
+/// No-op стратегия: только вынимает события и меряет задержку каждого step-го
+fn consume(rx: Receiver<CEvent>, step: u64) -> (u64, Vec<u64>) {
+    let mut received = 0u64;
+    let mut samples = Vec::with_capacity(MAX_SAMPLES as usize);
+    for event in rx.iter() {
+        if received.is_multiple_of(step) {
+            samples.push(latency::now_ns().saturating_sub(event.received_at_ns));
+        }
+        received += 1;
+    }
+    (received, samples)
+}
+
+fn summarize(received: u64, dropped: u64, mut samples: Vec<u64>) -> ConsumerResult {
+    samples.sort_unstable();
+    let percentile = |q: f64| -> u64 {
+        if samples.is_empty() {
+            return 0;
+        }
+        let rank = ((samples.len() as f64 * q).ceil() as usize).clamp(1, samples.len());
+        samples[rank - 1]
+    };
+    ConsumerResult {
+        received,
+        dropped,
+        p50_ns: percentile(0.5),
+        p90_ns: percentile(0.9),
+        p99_ns: percentile(0.99),
+        p999_ns: percentile(0.999),
+        max_ns: samples.last().copied().unwrap_or(0),
+    }
+}
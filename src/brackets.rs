@@ -0,0 +1,1019 @@
+// src/brackets.rs
+
+use dashmap::DashMap;
+use serde::Serialize;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock};
+use tokio::sync::broadcast;
+
+use crate::alerts::{self, AlertLevel};
+use crate::exchange_trade::ExchangeTrade;
+use crate::ffi_types::CBracket;
+use crate::keystore::{self, Credentials};
+use crate::notifications::{self, NotifyKind};
+use crate::order_errors;
+use crate::orders::{OrderManager, OrderState};
+use crate::paper;
+use crate::strategies::order::{current_instance, route_cancel, route_order};
+use crate::strategies::{intents, logs, replay};
+use crate::user_data::{RawOrderUpdate, UserDataEvent, UserDataManager, UserDataUpdate};
+
+/// Некорректные параметры bracket
+pub const ERR_BRACKET_INVALID: i32 = -9610;
+/// Paper, replay и dry run: стоп-ордеров там нет
+pub const ERR_BRACKET_UNSUPPORTED: i32 = -9611;
+
+/// Завершённых bracket в памяти (для GET /api/brackets)
+const KEEP_FINISHED: usize = 200;
+
+/// Объёмы меньше считаются равными
+const QTY_EPS: f64 = 1e-12;
+
+/// Вход без ответа биржи (обрыв, таймаут), которого user data не показал
+/// за это время, считается не выставленным
+const UNCONFIRMED_ENTRY: std::time::Duration = std::time::Duration::from_secs(120);
+
+// ═══════════════════════════════════════════════════════════
+// ТИПЫ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BracketState {
+    /// Вход отправлен или стоит в книге
+    Entry,
+    /// Вход исполнен (хотя бы частично), TP и SL стоят на исполненный объём
+    Protected,
+    /// Сработал TP или SL, второй снят
+    Closed,
+    Canceled,
+    /// Вход отклонён или защитный ордер не выставился (тогда остальные
+    /// ордера сняты, позиция закрыта market) - подробности в error
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Entry,
+    TakeProfit,
+    StopLoss,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Leg {
+    pub order_id: Option<i64>,
+    /// TP - лимитная цена, SL - stopPrice
+    pub price: f64,
+    pub quantity: f64,
+    pub filled_qty: f64,
+    /// Статус биржи (PENDING до ответа)
+    pub status: String,
+    /// Объём, на который выставлен текущий ордер; отличие от quantity - нужна замена
+    #[serde(skip)]
+    live_qty: f64,
+    /// Исполнено заменёнными ордерами ноги
+    #[serde(skip)]
+    replaced_filled: f64,
+}
+
+impl Leg {
+    fn new(price: f64, quantity: f64) -> Self {
+        Self {
+            order_id: None,
+            price,
+            quantity,
+            filled_qty: 0.0,
+            status: "PENDING".to_string(),
+            live_qty: quantity,
+            replaced_filled: 0.0,
+        }
+    }
+
+    fn is_open(&self) -> bool {
+        matches!(self.status.as_str(), "PENDING" | "NEW" | "PARTIALLY_FILLED")
+    }
+
+    /// Выставленный ордер меньше нужного: он снимается с учёта (его отмену
+    /// user data уже не обработает), нога ждёт новый ордер на остаток.
+    /// Some((старый orderId, объём нового)). Ордер без ответа биржи
+    /// заменяется, когда ответ придёт
+    fn take_for_replace(&mut self) -> Option<(i64, f64)> {
+        let order_id = self.order_id.filter(|_| self.is_open() && (self.quantity - self.live_qty).abs() > QTY_EPS)?;
+        ORDERS.remove(&order_id);
+        self.replaced_filled = self.filled_qty;
+        self.order_id = None;
+        self.status = "PENDING".to_string();
+        self.live_qty = self.quantity;
+        Some((order_id, self.quantity - self.filled_qty))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BracketInfo {
+    pub id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance_id: Option<String>,
+    pub symbol: String,
+    pub side: String,
+    pub entry_type: &'static str,
+    pub tp_pct: f64,
+    pub sl_pct: f64,
+    pub state: BracketState,
+    pub entry: Leg,
+    /// Средняя цена входа - от неё считаются TP и SL
+    pub entry_avg_price: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub take_profit: Option<Leg>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_loss: Option<Leg>,
+    /// Какой защитный ордер сработал
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit: Option<Role>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    /// Исполнение входа, которым bracket уже распорядился: защитил TP и SL
+    /// или закрыл market. Исполнение сверх него после завершения bracket
+    /// закрывается market (см. take_unhandled_fill)
+    #[serde(skip)]
+    handled_qty: f64,
+}
+
+impl BracketInfo {
+    fn is_active(&self) -> bool {
+        matches!(self.state, BracketState::Entry | BracketState::Protected)
+    }
+
+    /// Ответ биржи на вход. true - bracket завершился (снят), пока вход был
+    /// в пути: вход ему больше не нужен и его надо снять
+    fn on_entry_ack(&mut self, order_id: i64) -> bool {
+        self.entry.order_id = Some(order_id);
+        if self.entry.status == "PENDING" {
+            self.entry.status = "NEW".to_string();
+        }
+        !self.is_active() && self.entry.is_open()
+    }
+
+    /// Исполнение входа уже завершённого bracket (отмена входа не успела):
+    /// объём сверх учтённого, который остался бы позицией без TP и SL
+    fn take_unhandled_fill(&mut self, filled: f64) -> Option<f64> {
+        let extra = filled - self.handled_qty;
+        if extra <= QTY_EPS {
+            return None;
+        }
+        self.handled_qty = filled;
+        self.updated_at = now_ms();
+        Some(extra)
+    }
+
+    fn leg_mut(&mut self, role: Role) -> Option<&mut Leg> {
+        match role {
+            Role::Entry => Some(&mut self.entry),
+            Role::TakeProfit => self.take_profit.as_mut(),
+            Role::StopLoss => self.stop_loss.as_mut(),
+        }
+    }
+
+    /// Открытые ордера bracket с известным orderId
+    fn open_orders(&self) -> Vec<i64> {
+        [Some(&self.entry), self.take_profit.as_ref(), self.stop_loss.as_ref()]
+            .into_iter()
+            .flatten()
+            .filter(|leg| leg.is_open())
+            .filter_map(|leg| leg.order_id)
+            .collect()
+    }
+
+    /// Открытая входом позиция за вычетом исполненного TP и SL
+    fn position_qty(&self) -> f64 {
+        let exits: f64 = [self.take_profit.as_ref(), self.stop_loss.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(|leg| leg.filled_qty)
+            .sum();
+        (self.entry.filled_qty - exits).max(0.0)
+    }
+}
+
+struct Bracket {
+    info: Mutex<BracketInfo>,
+    creds: Arc<Credentials>,
+    tick_size: f64,
+}
+
+// ═══════════════════════════════════════════════════════════
+// РЕЕСТР
+// ═══════════════════════════════════════════════════════════
+
+// Вход уходит обычным путём ордера инстанса (breaker, risk). Исполнение
+// входа ядро видит в user data и с первого же исполнения ставит reduce-only
+// TP (LIMIT) и SL (STOP_MARKET) от средней цены входа; следующие исполнения
+// частично исполненного лимитного входа заменяют их ордерами на весь
+// исполненный объём. Исполнение одного снимает другой и остаток входа.
+// Если защитный ордер не выставился, позиция без стопа не остаётся:
+// остальные ордера снимаются, позиция закрывается market. Защита живёт
+// в ядре: после остановки инстанса TP и SL остаются на бирже и
+// продолжают отслеживаться.
+
+static BRACKETS: LazyLock<DashMap<u64, Arc<Bracket>>> = LazyLock::new(DashMap::new);
+
+/// orderId -> (bracket, роль ордера)
+static ORDERS: LazyLock<DashMap<i64, (u64, Role)>> = LazyLock::new(DashMap::new);
+
+/// clientOrderId входа, чей orderId ещё неизвестен -> bracket. После обрыва
+/// вход мог встать и исполниться: тогда его узнаём по user data
+static ENTRY_CIDS: LazyLock<DashMap<String, u64>> = LazyLock::new(DashMap::new);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+static TRADE: OnceLock<Arc<ExchangeTrade>> = OnceLock::new();
+static ORDER_MANAGER: OnceLock<Arc<OrderManager>> = OnceLock::new();
+static USER_DATA: OnceLock<Arc<UserDataManager>> = OnceLock::new();
+
+pub fn init(trade: Arc<ExchangeTrade>, orders: Arc<OrderManager>, user_data: Arc<UserDataManager>) {
+    tokio::spawn(user_data_loop(user_data.updates_tx.subscribe()));
+    TRADE.set(trade).ok();
+    ORDER_MANAGER.set(orders).ok();
+    USER_DATA.set(user_data).ok();
+}
+
+pub struct BracketParams {
+    pub symbol: String,
+    pub side: String,
+    pub quantity: f64,
+    /// 0 = LIMIT, 1 = MARKET
+    pub entry_type: u8,
+    pub price: f64,
+    pub tp_pct: f64,
+    pub sl_pct: f64,
+    /// Шаг цены символа, 0 - без округления
+    pub tick_size: f64,
+}
+
+/// Отправляет вход. Ok - id bracket, Err - (код, причина)
+pub fn place(creds: Arc<Credentials>, instance_id: Option<String>, params: BracketParams) -> Result<u64, (i32, String)> {
+    let params = validate(params).map_err(|e| (ERR_BRACKET_INVALID, e))?;
+    if let Some(id) = instance_id.as_deref() {
+        if paper::account(id).is_some() || intents::is_active(id) || replay::clock(id).is_some() {
+            return Err((ERR_BRACKET_UNSUPPORTED, "Brackets are only available on live instances".to_string()));
+        }
+    }
+
+    // Исполнение входа и защитных ордеров приходит только из user data
+    if let Some(user_data) = USER_DATA.get() {
+        if !user_data.has_stream(&creds.api_key) {
+            if let Err(e) = user_data.start_stream(&creds.api_key) {
+                tracing::warn!("🎯 Failed to start user data stream for bracket: {}", e);
+            }
+        }
+    }
+
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let now = now_ms();
+    let market = params.entry_type == 1;
+    let info = BracketInfo {
+        id,
+        instance_id: instance_id.clone(),
+        symbol: params.symbol.clone(),
+        side: params.side.clone(),
+        entry_type: if market { "MARKET" } else { "LIMIT" },
+        tp_pct: params.tp_pct,
+        sl_pct: params.sl_pct,
+        state: BracketState::Entry,
+        entry: Leg::new(if market { 0.0 } else { params.price }, params.quantity),
+        entry_avg_price: 0.0,
+        take_profit: None,
+        stop_loss: None,
+        exit: None,
+        error: None,
+        created_at: now,
+        updated_at: now,
+        handled_qty: 0.0,
+    };
+    let bracket = Arc::new(Bracket { info: Mutex::new(info), creds: creds.clone(), tick_size: params.tick_size });
+    BRACKETS.insert(id, bracket.clone());
+    prune();
+
+    let message = format!(
+        "Bracket #{}: {} {} {} {}, TP {}%, SL {}%",
+        id, params.side, params.quantity, params.symbol,
+        if market { "MARKET".to_string() } else { format!("@ {}", params.price) },
+        params.tp_pct, params.sl_pct,
+    );
+    tracing::info!("🎯 {} [{}]", message, instance_id.as_deref().unwrap_or("api"));
+    if let Some(instance_id) = instance_id.as_deref() {
+        logs::push(instance_id, logs::LOG_INFO, &message);
+    }
+
+    let entry_cid = ORDER_MANAGER.get().map(|o| o.next_client_id(instance_id.as_deref()));
+    if let Some(cid) = &entry_cid {
+        ENTRY_CIDS.insert(cid.clone(), id);
+    }
+    route_order(
+        creds, instance_id, &params.symbol, &params.side, params.entry_type, params.price, params.quantity,
+        entry_cid.clone(),
+        move |result| {
+            if !result.success {
+                match entry_cid {
+                    Some(cid) if order_errors::is_status_unknown(result.error_code) => {
+                        await_entry(bracket, cid, result.error_code);
+                    }
+                    _ => {
+                        if let Some(cid) = &entry_cid {
+                            ENTRY_CIDS.remove(cid);
+                        }
+                        fail(&bracket, format!("Entry rejected ({})", result.error_code));
+                    }
+                }
+                return;
+            }
+            // Вход, который user data показал раньше ответа, уже разобран в on_update
+            let first_seen = entry_cid.as_ref().is_none_or(|cid| ENTRY_CIDS.remove(cid).is_some());
+            let stale = {
+                let mut info = bracket.info.lock().unwrap();
+                ORDERS.insert(result.order_id, (info.id, Role::Entry));
+                info.on_entry_ack(result.order_id)
+            };
+            // Bracket сняли до ответа: orderId входа cancel() ещё не знал
+            if stale && first_seen {
+                cancel_order(&bracket, result.order_id);
+            }
+            // Исполнение могло прийти из user data раньше ответа на order.place
+            let order = ORDER_MANAGER.get().and_then(|o| o.get_by_order_id(result.order_id));
+            if let Some(order) = order {
+                if order.filled_qty > 0.0 {
+                    on_entry_fill(&bracket, order.filled_qty, order.avg_price);
+                } else if order.state.is_terminal() {
+                    on_entry_unfilled(&bracket);
+                }
+            }
+        },
+    );
+    Ok(id)
+}
+
+/// Ответа на вход нет (обрыв, таймаут): биржа могла его принять. Bracket
+/// остаётся в Entry, вход узнаётся по clientOrderId из user data; не
+/// появился за UNCONFIRMED_ENTRY - bracket завершается ошибкой
+fn await_entry(bracket: Arc<Bracket>, cid: String, error_code: i32) {
+    let (id, instance_id) = {
+        let info = bracket.info.lock().unwrap();
+        (info.id, info.instance_id.clone())
+    };
+    let message = format!(
+        "Bracket #{} entry status unknown ({}), waiting for it in user data",
+        id, error_code,
+    );
+    tracing::warn!("🎯 {}", message);
+    if let Some(instance_id) = instance_id.as_deref() {
+        logs::push(instance_id, logs::LOG_WARN, &message);
+    }
+    tokio::spawn(async move {
+        tokio::time::sleep(UNCONFIRMED_ENTRY).await;
+        let unseen = ENTRY_CIDS.remove(&cid).is_some();
+        if unseen && bracket.info.lock().unwrap().state == BracketState::Entry {
+            fail(&bracket, format!("Entry status unknown ({}) and it never showed up in user data", error_code));
+        }
+    });
+}
+
+/// Снимает вход и защитные ордера. Открытая позиция остаётся как есть.
+/// Вход без ответа биржи снимается, когда ответ придёт; если он успеет
+/// исполниться, этот объём закрывается market.
+/// None - bracket нет, Some(false) - он уже завершён
+pub fn cancel(id: u64) -> Option<bool> {
+    let bracket = BRACKETS.get(&id)?.clone();
+    let open: Vec<i64> = {
+        let mut info = bracket.info.lock().unwrap();
+        if !matches!(info.state, BracketState::Entry | BracketState::Protected) {
+            return Some(false);
+        }
+        info.state = BracketState::Canceled;
+        info.updated_at = now_ms();
+        info.open_orders()
+    };
+    for order_id in open {
+        cancel_order(&bracket, order_id);
+    }
+    tracing::info!("🎯 Bracket #{} canceled", id);
+    Some(true)
+}
+
+pub fn get(id: u64) -> Option<BracketInfo> {
+    let bracket = BRACKETS.get(&id)?.clone();
+    let info = bracket.info.lock().unwrap().clone();
+    Some(info)
+}
+
+/// Новые первыми. instance_id = None - все
+pub fn list(instance_id: Option<&str>) -> Vec<BracketInfo> {
+    let mut list: Vec<BracketInfo> = BRACKETS
+        .iter()
+        .map(|b| b.info.lock().unwrap().clone())
+        .filter(|b| instance_id.is_none() || b.instance_id.as_deref() == instance_id)
+        .collect();
+    list.sort_by_key(|b| std::cmp::Reverse(b.id));
+    list
+}
+
+fn prune() {
+    let mut finished: Vec<u64> = BRACKETS
+        .iter()
+        .filter(|b| !matches!(b.info.lock().unwrap().state, BracketState::Entry | BracketState::Protected))
+        .map(|b| *b.key())
+        .collect();
+    if finished.len() <= KEEP_FINISHED {
+        return;
+    }
+    finished.sort_unstable();
+    for id in &finished[..finished.len() - KEEP_FINISHED] {
+        if let Some((_, bracket)) = BRACKETS.remove(id) {
+            let info = bracket.info.lock().unwrap();
+            for leg in [Some(&info.entry), info.take_profit.as_ref(), info.stop_loss.as_ref()].into_iter().flatten() {
+                if let Some(order_id) = leg.order_id {
+                    ORDERS.remove(&order_id);
+                }
+            }
+        }
+    }
+}
+
+fn validate(mut p: BracketParams) -> Result<BracketParams, String> {
+    p.symbol = p.symbol.trim().to_uppercase();
+    p.side = p.side.trim().to_uppercase();
+    if p.symbol.is_empty() || !p.symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!("Invalid symbol '{}'", p.symbol));
+    }
+    if p.side != "BUY" && p.side != "SELL" {
+        return Err(format!("Invalid side '{}'", p.side));
+    }
+    // NaN тоже не проходит
+    let positive = |v: f64| v > 0.0 && v.is_finite();
+    let non_negative = |v: f64| v >= 0.0 && v.is_finite();
+    if !positive(p.quantity) {
+        return Err("quantity must be > 0".to_string());
+    }
+    if p.entry_type > 1 || (p.entry_type == 0 && !positive(p.price)) {
+        return Err("LIMIT entry requires price > 0".to_string());
+    }
+    if !non_negative(p.tp_pct) || !non_negative(p.sl_pct) || (p.tp_pct == 0.0 && p.sl_pct == 0.0) {
+        return Err("tp_pct and sl_pct must be >= 0, at least one > 0".to_string());
+    }
+    if p.sl_pct >= 100.0 {
+        return Err("sl_pct must be < 100".to_string());
+    }
+    if !non_negative(p.tick_size) {
+        return Err("tick_size must be >= 0".to_string());
+    }
+    Ok(p)
+}
+
+// ═══════════════════════════════════════════════════════════
+// ИСПОЛНЕНИЕ
+// ═══════════════════════════════════════════════════════════
+
+async fn user_data_loop(mut rx: broadcast::Receiver<UserDataUpdate>) {
+    loop {
+        match rx.recv().await {
+            Ok(update) => {
+                if let UserDataEvent::OrderTradeUpdate(u) = &update.event {
+                    on_update(u);
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                tracing::warn!("⚠️ Brackets lagged by {} user data updates", n);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+fn on_update(u: &RawOrderUpdate) {
+    let mut first_seen = false;
+    let order = ORDERS.get(&u.order_id).map(|e| *e).or_else(|| {
+        // Вход, ответ на который не пришёл или ещё в пути
+        let (_, id) = ENTRY_CIDS.remove(&u.client_order_id)?;
+        ORDERS.insert(u.order_id, (id, Role::Entry));
+        first_seen = true;
+        Some((id, Role::Entry))
+    });
+    let Some((id, role)) = order else { return };
+    let Some(bracket) = BRACKETS.get(&id).map(|b| b.clone()) else { return };
+    let filled: f64 = u.cum_filled_qty.parse().unwrap_or(0.0);
+    let stale = {
+        let mut info = bracket.info.lock().unwrap();
+        let Some(leg) = info.leg_mut(role) else { return };
+        leg.order_id.get_or_insert(u.order_id);
+        leg.status = u.status.clone();
+        leg.filled_qty = leg.replaced_filled + filled;
+        info.updated_at = now_ms();
+        first_seen && !info.is_active() && !u.is_terminal()
+    };
+    // Вход bracket, снятого до ответа биржи: cancel() его orderId не знал
+    if stale {
+        cancel_order(&bracket, u.order_id);
+    }
+    match role {
+        Role::Entry if filled > 0.0 => on_entry_fill(&bracket, filled, u.avg_price.parse().unwrap_or(0.0)),
+        Role::Entry if u.is_terminal() => on_entry_unfilled(&bracket),
+        Role::TakeProfit | Role::StopLoss if u.is_terminal() => on_exit_closed(&bracket, role, u.status == "FILLED"),
+        _ => {}
+    }
+}
+
+/// Вход закрылся без исполнений
+fn on_entry_unfilled(bracket: &Bracket) {
+    let mut info = bracket.info.lock().unwrap();
+    if info.state == BracketState::Entry {
+        info.state = BracketState::Canceled;
+        info.updated_at = now_ms();
+    }
+}
+
+/// Исполнение входа (filled - всего исполнено). Первое ставит TP и SL на
+/// исполненный объём, следующие заменяют их ордерами на новый объём
+fn on_entry_fill(bracket: &Arc<Bracket>, filled: f64, avg_price: f64) {
+    let (instance_id, symbol, exit_side, first, tp, sl, replaced, to_send) = {
+        let mut info = bracket.info.lock().unwrap();
+        if filled <= 0.0 {
+            return;
+        }
+        if !info.is_active() {
+            let late = info.take_unhandled_fill(filled);
+            drop(info);
+            if let Some(quantity) = late {
+                close_late_fill(bracket, quantity);
+            }
+            return;
+        }
+        if avg_price <= 0.0 {
+            return;
+        }
+        // Это исполнение уже защищено (повтор события или ответ после user data)
+        let covered = info.take_profit.as_ref().or(info.stop_loss.as_ref()).map_or(0.0, |leg| leg.quantity);
+        if filled <= covered + QTY_EPS {
+            return;
+        }
+        info.entry.filled_qty = filled;
+        info.entry_avg_price = avg_price;
+        let buy = info.side == "BUY";
+        // Для long TP выше входа и SL ниже, для short - наоборот
+        let sign = if buy { 1.0 } else { -1.0 };
+        let tp = (info.tp_pct > 0.0).then(|| round_tick(avg_price * (1.0 + sign * info.tp_pct / 100.0), bracket.tick_size));
+        let sl = (info.sl_pct > 0.0).then(|| round_tick(avg_price * (1.0 - sign * info.sl_pct / 100.0), bracket.tick_size));
+        let first = info.state == BracketState::Entry;
+
+        let mut replaced = Vec::new();
+        let mut to_send = Vec::new();
+        for (role, price) in [(Role::TakeProfit, tp), (Role::StopLoss, sl)] {
+            let Some(price) = price else { continue };
+            match info.leg_mut(role) {
+                Some(leg) => {
+                    leg.price = price;
+                    leg.quantity = filled;
+                    if let Some((old, quantity)) = leg.take_for_replace() {
+                        replaced.push(old);
+                        to_send.push((role, price, quantity));
+                    }
+                }
+                None => {
+                    let leg = Some(Leg::new(price, filled));
+                    if role == Role::TakeProfit { info.take_profit = leg } else { info.stop_loss = leg }
+                    to_send.push((role, price, filled));
+                }
+            }
+        }
+        info.state = BracketState::Protected;
+        info.handled_qty = filled;
+        info.updated_at = now_ms();
+        let exit_side = if buy { "SELL" } else { "BUY" };
+        (info.instance_id.clone(), info.symbol.clone(), exit_side, first, tp, sl, replaced, to_send)
+    };
+
+    let id = bracket.info.lock().unwrap().id;
+    let message = if first {
+        format!(
+            "Bracket #{} entry filled {} @ {}: TP {}, SL {}",
+            id, filled, avg_price,
+            tp.map_or("-".to_string(), |p| p.to_string()),
+            sl.map_or("-".to_string(), |p| p.to_string()),
+        )
+    } else {
+        format!("Bracket #{} entry filled {} @ {}: protection resized", id, filled, avg_price)
+    };
+    tracing::info!("🎯 {}", message);
+    if let Some(instance_id) = instance_id.as_deref() {
+        logs::push(instance_id, logs::LOG_INFO, &message);
+    }
+
+    for order_id in replaced {
+        cancel_order(bracket, order_id);
+    }
+    for (role, price, quantity) in to_send {
+        send_exit(bracket.clone(), role, &symbol, exit_side, price, quantity);
+    }
+}
+
+/// Вход исполнился после завершения bracket: TP и SL на этот объём никто
+/// не поставит, поэтому он закрывается reduce-only market
+fn close_late_fill(bracket: &Arc<Bracket>, quantity: f64) {
+    let (id, instance_id, symbol, exit_side) = {
+        let info = bracket.info.lock().unwrap();
+        let exit_side = if info.side == "BUY" { "SELL" } else { "BUY" };
+        (info.id, info.instance_id.clone(), info.symbol.clone(), exit_side)
+    };
+    flatten(bracket, &symbol, exit_side, quantity);
+
+    let message = format!("Bracket #{} entry filled {} {} after it finished, closing at market", id, quantity, symbol);
+    tracing::warn!("🎯 {}", message);
+    if let Some(instance_id) = instance_id.as_deref() {
+        logs::push(instance_id, logs::LOG_WARN, &message);
+    }
+}
+
+/// TP или SL закрылся: исполненный снимает второй и остаток входа. Снятый
+/// не ядром - тоже, иначе оставшийся reduce-only ордер закрыл бы позицию,
+/// открытую позже
+fn on_exit_closed(bracket: &Arc<Bracket>, role: Role, filled: bool) {
+    let (open, message, instance_id) = {
+        let mut info = bracket.info.lock().unwrap();
+        if info.state != BracketState::Protected {
+            return;
+        }
+        let open = info.open_orders();
+        let name = if role == Role::TakeProfit { "Take profit" } else { "Stop loss" };
+        let message = if filled {
+            info.state = BracketState::Closed;
+            info.exit = Some(role);
+            format!("Bracket #{} closed: {} filled", info.id, name.to_lowercase())
+        } else {
+            info.state = BracketState::Canceled;
+            format!("Bracket #{} canceled: {} closed unfilled", info.id, name.to_lowercase())
+        };
+        info.updated_at = now_ms();
+        (open, message, info.instance_id.clone())
+    };
+
+    tracing::info!("🎯 {}", message);
+    if let Some(instance_id) = instance_id.as_deref() {
+        logs::push(instance_id, logs::LOG_INFO, &message);
+    }
+    for order_id in open {
+        cancel_order(bracket, order_id);
+    }
+}
+
+/// Защитный ордер: reduce-only, мимо risk (позицию он только уменьшает)
+fn send_exit(bracket: Arc<Bracket>, role: Role, symbol: &str, side: &str, price: f64, quantity: f64) {
+    let Some(trade) = TRADE.get().cloned() else { return };
+    let instance_id = bracket.info.lock().unwrap().instance_id.clone();
+    let api_key = bracket.creds.api_key.clone();
+    // STOP_MARKET в order manager - тип 2
+    let order_type = if role == Role::StopLoss { 2 } else { 0 };
+    let client_order_id = ORDER_MANAGER.get().map(|orders| {
+        let cid = orders.next_client_id(instance_id.as_deref());
+        orders.on_request(&cid, instance_id.as_deref(), &api_key, symbol, side, order_type, price, quantity);
+        cid
+    });
+
+    let (symbol, side) = (symbol.to_string(), side.to_string());
+    tokio::spawn(async move {
+        let creds = bracket.creds.clone();
+        let cid = client_order_id.clone();
+        let handler = bracket.clone();
+        let exit = (symbol.clone(), side.clone());
+        let on_resp = move |resp: serde_json::Value| {
+            let order_id = resp["result"]["orderId"].as_i64();
+            match order_id {
+                Some(order_id) => {
+                    let status = resp["result"]["status"].as_str().unwrap_or("NEW");
+                    if let (Some(orders), Some(cid)) = (ORDER_MANAGER.get(), cid.as_deref()) {
+                        orders.on_ack(cid, order_id, status);
+                    }
+                    let active = {
+                        let mut info = handler.info.lock().unwrap();
+                        ORDERS.insert(order_id, (info.id, role));
+                        if let Some(leg) = info.leg_mut(role) {
+                            leg.order_id = Some(order_id);
+                            if leg.status == "PENDING" {
+                                leg.status = status.to_string();
+                            }
+                        }
+                        info.state == BracketState::Protected
+                    };
+                    // Bracket завершился, пока ордер был в пути: ордер ему больше не нужен
+                    if !active {
+                        cancel_order(&handler, order_id);
+                        return;
+                    }
+                    // Закрытие могло прийти из user data раньше ответа
+                    let closed = ORDER_MANAGER
+                        .get()
+                        .and_then(|o| o.get_by_order_id(order_id))
+                        .filter(|o| o.state.is_terminal());
+                    if let Some(order) = closed {
+                        on_exit_closed(&handler, role, order.state == OrderState::Filled);
+                        return;
+                    }
+                    // Вход исполнился дальше, пока ордер был в пути
+                    let replace = handler.info.lock().unwrap().leg_mut(role)
+                        .and_then(|leg| leg.take_for_replace().map(|r| (r, leg.price)));
+                    if let Some(((old, quantity), price)) = replace {
+                        cancel_order(&handler, old);
+                        send_exit(handler.clone(), role, &exit.0, &exit.1, price, quantity);
+                    }
+                }
+                None => {
+                    let code = resp["error"]["code"].as_i64().unwrap_or(-1) as i32;
+                    let reason = resp["error"]["msg"].as_str().unwrap_or("no orderId in response");
+                    if let (Some(orders), Some(cid)) = (ORDER_MANAGER.get(), cid.as_deref()) {
+                        orders.on_ack_error(cid, code);
+                    }
+                    if let Some(leg) = handler.info.lock().unwrap().leg_mut(role) {
+                        leg.status = "REJECTED".to_string();
+                    }
+                    let name = if role == Role::TakeProfit { "take profit" } else { "stop loss" };
+                    unprotected(&handler, format!("Failed to place {} ({}): {}", name, code, reason));
+                }
+            }
+        };
+
+        if role == Role::StopLoss {
+            trade
+                .send_stop_market(&creds.api_key, &creds.secret_key, &symbol, price, quantity, &side, client_order_id.as_deref(), on_resp)
+                .await;
+        } else {
+            trade
+                .send_reduce_only_limit(&creds.api_key, &creds.secret_key, &symbol, price, quantity, &side, client_order_id.as_deref(), on_resp)
+                .await;
+        }
+    });
+}
+
+fn cancel_order(bracket: &Bracket, order_id: i64) {
+    let (instance_id, symbol, id) = {
+        let info = bracket.info.lock().unwrap();
+        (info.instance_id.clone(), info.symbol.clone(), info.id)
+    };
+    route_cancel((*bracket.creds).clone(), instance_id, &symbol, order_id, move |result| {
+        if !result.success {
+            tracing::warn!("🎯 Bracket #{} failed to cancel order {} ({})", id, order_id, result.error_code);
+        }
+    });
+}
+
+/// Вход не прошёл: позиции нет
+fn fail(bracket: &Bracket, error: String) {
+    let (id, instance_id) = {
+        let mut info = bracket.info.lock().unwrap();
+        info.state = BracketState::Failed;
+        info.error = Some(error.clone());
+        info.updated_at = now_ms();
+        (info.id, info.instance_id.clone())
+    };
+    let message = format!("Bracket #{} failed: {}", id, error);
+    tracing::warn!("🎯 {}", message);
+    if let Some(instance_id) = instance_id.as_deref() {
+        logs::push(instance_id, logs::LOG_ERROR, &message);
+    }
+    notifications::notify(NotifyKind::OrderRejected, instance_id.as_deref(), message);
+}
+
+/// Защитный ордер не выставился. Позицию без стопа не оставляем: остальные
+/// ордера bracket снимаются, исполненный объём закрывается reduce-only market
+fn unprotected(bracket: &Arc<Bracket>, error: String) {
+    let (id, instance_id, symbol, exit_side, open, quantity) = {
+        let mut info = bracket.info.lock().unwrap();
+        if info.state != BracketState::Protected {
+            return;
+        }
+        info.state = BracketState::Failed;
+        info.error = Some(error.clone());
+        info.handled_qty = info.handled_qty.max(info.entry.filled_qty);
+        info.updated_at = now_ms();
+        let exit_side = if info.side == "BUY" { "SELL" } else { "BUY" };
+        (info.id, info.instance_id.clone(), info.symbol.clone(), exit_side, info.open_orders(), info.position_qty())
+    };
+    for order_id in open {
+        cancel_order(bracket, order_id);
+    }
+    if quantity > QTY_EPS {
+        flatten(bracket, &symbol, exit_side, quantity);
+    }
+
+    let message = format!("Bracket #{} failed: {}; closing {} {} at market", id, error, quantity, symbol);
+    tracing::error!("🎯 {}", message);
+    if let Some(instance_id) = instance_id.as_deref() {
+        logs::push(instance_id, logs::LOG_ERROR, &message);
+    }
+    alerts::emit(AlertLevel::Critical, "brackets", message.clone());
+    notifications::notify(NotifyKind::OrderRejected, instance_id.as_deref(), message);
+}
+
+/// Reduce-only MARKET на остаток позиции bracket, мимо risk и breaker
+fn flatten(bracket: &Arc<Bracket>, symbol: &str, side: &str, quantity: f64) {
+    let Some(trade) = TRADE.get().cloned() else { return };
+    let (id, instance_id) = {
+        let info = bracket.info.lock().unwrap();
+        (info.id, info.instance_id.clone())
+    };
+    let creds = bracket.creds.clone();
+    let client_order_id = ORDER_MANAGER.get().map(|orders| {
+        let cid = orders.next_client_id(instance_id.as_deref());
+        orders.on_request(&cid, instance_id.as_deref(), &creds.api_key, symbol, side, 1, 0.0, quantity);
+        cid
+    });
+
+    let (symbol, side) = (symbol.to_string(), side.to_string());
+    tokio::spawn(async move {
+        let cid = client_order_id.clone();
+        let on_resp = move |resp: serde_json::Value| match resp["result"]["orderId"].as_i64() {
+            Some(order_id) => {
+                let status = resp["result"]["status"].as_str().unwrap_or("NEW");
+                if let (Some(orders), Some(cid)) = (ORDER_MANAGER.get(), cid.as_deref()) {
+                    orders.on_ack(cid, order_id, status);
+                }
+            }
+            None => {
+                let code = resp["error"]["code"].as_i64().unwrap_or(-1) as i32;
+                let reason = resp["error"]["msg"].as_str().unwrap_or("no orderId in response");
+                if let (Some(orders), Some(cid)) = (ORDER_MANAGER.get(), cid.as_deref()) {
+                    orders.on_ack_error(cid, code);
+                }
+                alerts::emit(
+                    AlertLevel::Critical,
+                    "brackets",
+                    format!("Bracket #{} failed to close its position ({}): {}", id, code, reason),
+                );
+            }
+        };
+        trade
+            .send_reduce_only_market(&creds.api_key, &creds.secret_key, &symbol, quantity, &side, client_order_id.as_deref(), on_resp)
+            .await;
+    });
+}
+
+fn round_tick(price: f64, tick_size: f64) -> f64 {
+    if tick_size > 0.0 {
+        (price / tick_size).round() * tick_size
+    } else {
+        price
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// FFI
+// ═══════════════════════════════════════════════════════════
+
+/// Вход + TP + SL одним вызовом от имени текущего инстанса.
+/// Возвращает id bracket (> 0) или ERR_BRACKET_*; отказ самого входа
+/// приходит в лог инстанса и в GET /api/brackets/{id}
+pub unsafe extern "C" fn place_bracket(
+    api_key: *const c_char,
+    secret_key: *const c_char,
+    bracket: *const CBracket,
+) -> i64 {
+    if api_key.is_null() || secret_key.is_null() || bracket.is_null() {
+        return ERR_BRACKET_INVALID as i64;
+    }
+    let (Ok(api_key), Ok(secret_key)) = (CStr::from_ptr(api_key).to_str(), CStr::from_ptr(secret_key).to_str()) else {
+        return ERR_BRACKET_INVALID as i64;
+    };
+    let b = &*bracket;
+    let Some(symbol) = b.symbol_str() else {
+        return ERR_BRACKET_INVALID as i64;
+    };
+    let side = match b.side {
+        0 => "BUY",
+        1 => "SELL",
+        _ => return ERR_BRACKET_INVALID as i64,
+    };
+    let params = BracketParams {
+        symbol: symbol.to_string(),
+        side: side.to_string(),
+        quantity: b.quantity,
+        entry_type: b.entry_type,
+        price: b.price,
+        tp_pct: b.tp_pct,
+        sl_pct: b.sl_pct,
+        tick_size: b.tick_size,
+    };
+
+    let instance_id = current_instance();
+    let creds = Arc::new(keystore::resolve(api_key, secret_key));
+    match place(creds, instance_id.clone(), params) {
+        Ok(id) => id as i64,
+        Err((code, message)) => {
+            if let Some(instance_id) = instance_id.as_deref() {
+                logs::push(instance_id, logs::LOG_WARN, &format!("Bracket rejected: {}", message));
+            }
+            code as i64
+        }
+    }
+}
+
+/// Снимает вход и защитные ордера bracket текущего инстанса.
+/// false - bracket чужой, неизвестен или уже завершён
+pub extern "C" fn cancel_bracket(bracket_id: i64) -> bool {
+    let Ok(id) = u64::try_from(bracket_id) else { return false };
+    let owned = BRACKETS
+        .get(&id)
+        .is_some_and(|b| b.info.lock().unwrap().instance_id == current_instance());
+    owned && cancel(id) == Some(true)
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bracket() -> u64 {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let info = BracketInfo {
+            id,
+            instance_id: None,
+            symbol: "BTCUSDT".to_string(),
+            side: "BUY".to_string(),
+            entry_type: "LIMIT",
+            tp_pct: 1.0,
+            sl_pct: 1.0,
+            state: BracketState::Entry,
+            entry: Leg::new(100.0, 2.0),
+            entry_avg_price: 0.0,
+            take_profit: None,
+            stop_loss: None,
+            exit: None,
+            error: None,
+            created_at: 0,
+            updated_at: 0,
+            handled_qty: 0.0,
+        };
+        let creds = Arc::new(Credentials { api_key: "key".to_string(), secret_key: "secret".to_string() });
+        BRACKETS.insert(id, Arc::new(Bracket { info: Mutex::new(info), creds, tick_size: 0.0 }));
+        id
+    }
+
+    fn with_info<T>(id: u64, f: impl FnOnce(&mut BracketInfo) -> T) -> T {
+        let bracket = BRACKETS.get(&id).unwrap().clone();
+        let mut info = bracket.info.lock().unwrap();
+        f(&mut info)
+    }
+
+    #[test]
+    fn entry_acked_after_cancel_is_canceled() {
+        let id = bracket();
+        assert_eq!(cancel(id), Some(true));
+
+        assert!(with_info(id, |info| info.on_entry_ack(42)));
+        assert_eq!(get(id).unwrap().entry.order_id, Some(42));
+    }
+
+    #[test]
+    fn entry_acked_on_active_bracket_is_kept() {
+        let id = bracket();
+        assert!(!with_info(id, |info| info.on_entry_ack(42)));
+
+        // Вход исполнился и закрылся раньше ответа на order.place
+        let id = bracket();
+        assert_eq!(cancel(id), Some(true));
+        assert!(!with_info(id, |info| {
+            info.entry.status = "FILLED".to_string();
+            info.on_entry_ack(42)
+        }));
+    }
+
+    #[test]
+    fn entry_fill_after_cancel_is_closed_once() {
+        let id = bracket();
+        assert_eq!(cancel(id), Some(true));
+
+        with_info(id, |info| {
+            assert_eq!(info.take_unhandled_fill(0.5), Some(0.5));
+            // Повтор события и следующее исполнение
+            assert_eq!(info.take_unhandled_fill(0.5), None);
+            assert_eq!(info.take_unhandled_fill(2.0), Some(1.5));
+        });
+    }
+
+    #[test]
+    fn protected_quantity_is_not_closed_after_cancel() {
+        let id = bracket();
+        with_info(id, |info| {
+            info.state = BracketState::Protected;
+            info.handled_qty = 1.0;
+        });
+        assert_eq!(cancel(id), Some(true));
+
+        with_info(id, |info| {
+            assert_eq!(info.take_unhandled_fill(1.0), None);
+            assert_eq!(info.take_unhandled_fill(1.5), Some(0.5));
+        });
+    }
+}
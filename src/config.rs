@@ -0,0 +1,94 @@
+// src/config.rs
+
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::affinity::AffinityConfig;
+use crate::analytics::AnalyticsConfig;
+use crate::audit::AuditConfig;
+use crate::endpoints::EndpointsConfig;
+use crate::execution::ExecutionConfig;
+use crate::exchange_trade::{RestFallbackConfig, TradeWsConfig};
+use crate::funding::FundingConfig;
+use crate::history::HistoryConfig;
+use crate::keystore::KeystoreConfig;
+use crate::kv::KvConfig;
+use crate::latency::LatencyConfig;
+use crate::net::NetConfig;
+use crate::notifications::NotificationsConfig;
+use crate::open_interest::OpenInterestConfig;
+use crate::paper::PaperConfig;
+use crate::rate_limit::RateLimitConfig;
+use crate::recorder::RecorderConfig;
+use crate::risk::RiskConfig;
+use crate::scheduler::SchedulerConfig;
+use crate::strategies::breaker::BreakerConfig;
+use crate::strategies::capacity::CapacityConfig;
+use crate::strategies::dedup::DedupConfig;
+use crate::strategies::runs::RunsConfig;
+use crate::strategies::storage::CompileConfig;
+use crate::strategies::watchdog::WatchdogConfig;
+use crate::time_sync::TimeSyncConfig;
+use crate::volume_profile::VolumeProfileConfig;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ ЯДРА
+// ═══════════════════════════════════════════════════════════
+
+/// Конфигурация ядра (config.json рядом с бинарником)
+///
+/// Все секции опциональны - отсутствующие поля берутся по умолчанию.
+/// Путь можно переопределить через переменную окружения HFT_CONFIG.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CoreConfig {
+    pub risk: RiskConfig,
+    pub rate_limits: RateLimitConfig,
+    pub circuit_breaker: BreakerConfig,
+    pub dedup: DedupConfig,
+    pub capacity: CapacityConfig,
+    pub watchdog: WatchdogConfig,
+    pub history: HistoryConfig,
+    pub audit: AuditConfig,
+    pub keystore: KeystoreConfig,
+    pub time_sync: TimeSyncConfig,
+    pub trade_ws: TradeWsConfig,
+    pub rest_fallback: RestFallbackConfig,
+    pub affinity: AffinityConfig,
+    pub net: NetConfig,
+    pub endpoints: EndpointsConfig,
+    pub recorder: RecorderConfig,
+    pub paper: PaperConfig,
+    pub compile: CompileConfig,
+    pub scheduler: SchedulerConfig,
+    pub funding: FundingConfig,
+    pub notifications: NotificationsConfig,
+    pub kv: KvConfig,
+    pub execution: ExecutionConfig,
+    pub latency: LatencyConfig,
+    pub analytics: AnalyticsConfig,
+    pub volume_profile: VolumeProfileConfig,
+    pub open_interest: OpenInterestConfig,
+    pub runs: RunsConfig,
+}
+
+impl CoreConfig {
+    pub fn load() -> anyhow::Result<Self> {
+        let path = std::env::var("HFT_CONFIG").unwrap_or_else(|_| "config.json".to_string());
+        Self::load_from(&path)
+    }
+
+    pub fn load_from(path: &str) -> anyhow::Result<Self> {
+        if !Path::new(path).exists() {
+            tracing::info!("⚙️ Config '{}' not found, using defaults", path);
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        let config: Self = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid config '{}': {}", path, e))?;
+
+        tracing::info!("⚙️ Config loaded from '{}'", path);
+        Ok(config)
+    }
+}
@@ -0,0 +1,419 @@
+// src/data.rs
+
+use chrono::{Datelike, NaiveDate};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Cursor, Read};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::LazyLock;
+use std::time::Duration;
+use zip::ZipArchive;
+
+use crate::ffi_types::{pack_str, CBookTicker, CEvent, CEventData, CTrade, Sequencer, EVENT_BOOK_TICKER, EVENT_TRADE};
+use crate::recorder::{self, RecordWriter};
+
+/// Архивы USDT-M фьючерсов на Binance Vision
+const VISION_URL: &str = "https://data.binance.vision/data/futures/um";
+
+/// Больше дней за одну загрузку не берём: bookTicker по BTCUSDT - сотни MB в день
+const MAX_DAYS: i64 = 31;
+
+/// Сколько завершённых загрузок помним для GET /api/data/downloads
+const MAX_JOBS: usize = 100;
+
+// ═══════════════════════════════════════════════════════════
+// ЗАПРОС
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DataKind {
+    AggTrades,
+    BookTicker,
+    /// Свечи превращаются в 4 синтетических трейда: open, high/low, low/high, close
+    Klines,
+    /// Месячные CSV, сохраняются как есть: в формате записи для них нет события
+    FundingRate,
+}
+
+impl DataKind {
+    fn name(self) -> &'static str {
+        match self {
+            DataKind::AggTrades => "aggTrades",
+            DataKind::BookTicker => "bookTicker",
+            DataKind::Klines => "klines",
+            DataKind::FundingRate => "fundingRate",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DownloadRequest {
+    pub symbol: String,
+    /// YYYY-MM-DD, включительно
+    pub from: String,
+    pub to: String,
+    #[serde(default = "default_kinds")]
+    pub kinds: Vec<DataKind>,
+    /// Интервал для klines
+    #[serde(default = "default_kline_interval")]
+    pub kline_interval: String,
+}
+
+fn default_kinds() -> Vec<DataKind> {
+    vec![DataKind::BookTicker, DataKind::AggTrades]
+}
+
+fn default_kline_interval() -> String {
+    "1m".to_string()
+}
+
+// ═══════════════════════════════════════════════════════════
+// ЗАГРУЗКИ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadJob {
+    pub id: u64,
+    pub symbol: String,
+    pub from: String,
+    pub to: String,
+    pub kinds: Vec<DataKind>,
+    pub status: JobStatus,
+    pub days_total: u64,
+    pub days_done: u64,
+    /// Событий записано во все файлы
+    pub events: u64,
+    /// Файлы относительно каталога записей (для source.replay)
+    pub files: Vec<String>,
+    /// Дни/месяцы, которых нет в архиве
+    pub missing: Vec<String>,
+    pub error: Option<String>,
+    pub started_at: i64,
+    pub finished_at: Option<i64>,
+}
+
+static JOBS: LazyLock<DashMap<u64, DownloadJob>> = LazyLock::new(DashMap::new);
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+pub fn list() -> Vec<DownloadJob> {
+    let mut jobs: Vec<_> = JOBS.iter().map(|j| j.clone()).collect();
+    jobs.sort_by_key(|j| std::cmp::Reverse(j.id));
+    jobs
+}
+
+pub fn get(id: u64) -> Option<DownloadJob> {
+    JOBS.get(&id).map(|j| j.clone())
+}
+
+/// Проверяет запрос и запускает загрузку в фоне
+pub fn start(req: DownloadRequest) -> anyhow::Result<DownloadJob> {
+    if recorder::dir().is_none() {
+        anyhow::bail!("Recorder is not initialized");
+    }
+    let symbol = req.symbol.to_uppercase();
+    if symbol.is_empty() || !symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+        anyhow::bail!("Invalid symbol '{}'", req.symbol);
+    }
+    let from = parse_date(&req.from)?;
+    let to = parse_date(&req.to)?;
+    let days = (to - from).num_days() + 1;
+    if !(1..=MAX_DAYS).contains(&days) {
+        anyhow::bail!("Date range must be 1..={} days", MAX_DAYS);
+    }
+    if req.kinds.is_empty() {
+        anyhow::bail!("No data kinds requested");
+    }
+    if !req.kline_interval.chars().all(|c| c.is_ascii_alphanumeric()) {
+        anyhow::bail!("Invalid kline interval '{}'", req.kline_interval);
+    }
+
+    prune_jobs();
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let job = DownloadJob {
+        id,
+        symbol: symbol.clone(),
+        from: req.from.clone(),
+        to: req.to.clone(),
+        kinds: req.kinds.clone(),
+        status: JobStatus::Running,
+        days_total: days as u64,
+        days_done: 0,
+        events: 0,
+        files: Vec::new(),
+        missing: Vec::new(),
+        error: None,
+        started_at: chrono::Utc::now().timestamp_millis(),
+        finished_at: None,
+    };
+    JOBS.insert(id, job.clone());
+
+    tracing::info!("📥 Download #{} {} {}..{} {:?}", id, symbol, from, to, req.kinds);
+    tokio::spawn(async move {
+        let result = run(id, &symbol, from, to, &req).await;
+        if let Some(mut job) = JOBS.get_mut(&id) {
+            job.finished_at = Some(chrono::Utc::now().timestamp_millis());
+            match result {
+                Ok(()) => {
+                    job.status = JobStatus::Done;
+                    tracing::info!("📥 Download #{} done: {} files, {} events", id, job.files.len(), job.events);
+                }
+                Err(e) => {
+                    job.status = JobStatus::Failed;
+                    job.error = Some(e.to_string());
+                    tracing::warn!("📥 Download #{} failed: {}", id, e);
+                }
+            }
+        }
+    });
+
+    Ok(job)
+}
+
+fn parse_date(s: &str) -> anyhow::Result<NaiveDate> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| anyhow::anyhow!("Invalid date '{}', expected YYYY-MM-DD", s))
+}
+
+/// Забывает самые старые завершённые загрузки
+fn prune_jobs() {
+    let mut finished: Vec<u64> = JOBS.iter().filter(|j| j.status != JobStatus::Running).map(|j| j.id).collect();
+    if finished.len() < MAX_JOBS {
+        return;
+    }
+    finished.sort_unstable();
+    for id in &finished[..finished.len() + 1 - MAX_JOBS] {
+        JOBS.remove(id);
+    }
+}
+
+async fn run(id: u64, symbol: &str, from: NaiveDate, to: NaiveDate, req: &DownloadRequest) -> anyhow::Result<()> {
+    let dir = recorder::dir().ok_or_else(|| anyhow::anyhow!("Recorder is not initialized"))?;
+    let client = reqwest::Client::new();
+    let event_kinds: Vec<DataKind> = req.kinds.iter().copied().filter(|k| *k != DataKind::FundingRate).collect();
+
+    for date in from.iter_days().take_while(|d| *d <= to) {
+        let day = date.format("%Y-%m-%d").to_string();
+
+        let mut archives = Vec::new();
+        for kind in &event_kinds {
+            let url = match kind {
+                DataKind::Klines => format!(
+                    "{}/daily/klines/{sym}/{iv}/{sym}-{iv}-{day}.zip",
+                    VISION_URL, sym = symbol, iv = req.kline_interval, day = day
+                ),
+                _ => format!("{}/daily/{k}/{sym}/{sym}-{k}-{day}.zip", VISION_URL, k = kind.name(), sym = symbol, day = day),
+            };
+            match fetch(&client, &url).await? {
+                Some(bytes) => archives.push((*kind, bytes)),
+                None => update(id, |j| j.missing.push(format!("{} {}", kind.name(), day))),
+            }
+        }
+
+        if !archives.is_empty() {
+            let file = format!("{}/{}.vision.bin", symbol, day);
+            let path = dir.join(&file);
+            let sym = symbol.to_string();
+            let events = tokio::task::spawn_blocking(move || convert_day(&path, &sym, archives)).await??;
+            update(id, |j| {
+                j.events += events;
+                j.files.push(file);
+            });
+        }
+        update(id, |j| j.days_done += 1);
+    }
+
+    if req.kinds.contains(&DataKind::FundingRate) {
+        let mut month = NaiveDate::from_ymd_opt(from.year(), from.month(), 1).unwrap_or(from);
+        while month <= to {
+            let ym = month.format("%Y-%m").to_string();
+            let url = format!("{}/monthly/fundingRate/{sym}/{sym}-fundingRate-{ym}.zip", VISION_URL, sym = symbol, ym = ym);
+            match fetch(&client, &url).await? {
+                Some(bytes) => {
+                    let file = format!("{}/fundingRate-{}.csv", symbol, ym);
+                    let path = dir.join(&file);
+                    tokio::task::spawn_blocking(move || extract_csv(&path, bytes)).await??;
+                    update(id, |j| j.files.push(file));
+                }
+                None => update(id, |j| j.missing.push(format!("fundingRate {}", ym))),
+            }
+            month = month.checked_add_months(chrono::Months::new(1)).unwrap_or(NaiveDate::MAX);
+        }
+    }
+
+    Ok(())
+}
+
+fn update(id: u64, f: impl FnOnce(&mut DownloadJob)) {
+    if let Some(mut job) = JOBS.get_mut(&id) {
+        f(&mut job);
+    }
+}
+
+/// None - архива нет (404: день ещё не выложен или символ тогда не торговался)
+async fn fetch(client: &reqwest::Client, url: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    let resp = client.get(url).timeout(Duration::from_secs(300)).send().await?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    let bytes = resp.error_for_status()?.bytes().await?;
+    Ok(Some(bytes.to_vec()))
+}
+
+// ═══════════════════════════════════════════════════════════
+// КОНВЕРТАЦИЯ
+// ═══════════════════════════════════════════════════════════
+
+/// Сливает CSV всех видов за день в один файл записи по времени
+fn convert_day(path: &Path, symbol: &str, archives: Vec<(DataKind, Vec<u8>)>) -> anyhow::Result<u64> {
+    let mut zips = Vec::new();
+    for (kind, bytes) in archives {
+        zips.push((kind, ZipArchive::new(Cursor::new(bytes))?));
+    }
+
+    let mut sources = Vec::new();
+    for (kind, zip) in zips.iter_mut() {
+        sources.push(CsvSource::new(*kind, symbol, BufReader::new(zip.by_index(0)?)));
+    }
+    let mut heads = sources.iter_mut().map(|s| s.next()).collect::<anyhow::Result<Vec<_>>>()?;
+
+    let seqs = Sequencer::default();
+    let mut writer = RecordWriter::create(path)?;
+    // Каждый CSV отсортирован по времени - k-way merge по голове каждого
+    while let Some(i) = heads
+        .iter()
+        .enumerate()
+        .filter_map(|(i, h)| h.map(|e| (i, e.received_at_ns)))
+        .min_by_key(|(_, t)| *t)
+        .map(|(i, _)| i)
+    {
+        let mut event = heads[i].take().expect("head is set");
+        seqs.stamp(&mut event);
+        writer.write(&event)?;
+        heads[i] = sources[i].next()?;
+    }
+    Ok(writer.finish()?)
+}
+
+fn extract_csv(path: &Path, bytes: Vec<u8>) -> anyhow::Result<()> {
+    let mut zip = ZipArchive::new(Cursor::new(bytes))?;
+    let mut entry = zip.by_index(0)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::io::copy(&mut entry, &mut std::fs::File::create(path)?)?;
+    Ok(())
+}
+
+/// Строки одного CSV из архива -> CEvent
+struct CsvSource<R: Read> {
+    kind: DataKind,
+    symbol: ([u8; 16], u8),
+    lines: std::io::Lines<BufReader<R>>,
+    /// Синтетические трейды текущей свечи
+    pending: VecDeque<CEvent>,
+}
+
+impl<R: Read> CsvSource<R> {
+    fn new(kind: DataKind, symbol: &str, reader: BufReader<R>) -> Self {
+        Self { kind, symbol: pack_str::<16>(symbol), lines: reader.lines(), pending: VecDeque::new() }
+    }
+
+    fn next(&mut self) -> anyhow::Result<Option<CEvent>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+            let Some(line) = self.lines.next().transpose()? else { return Ok(None) };
+            let cols: Vec<&str> = line.trim().split(',').collect();
+            // Заголовок (в новых архивах) и пустые строки
+            if cols.first().is_none_or(|c| c.parse::<i64>().is_err()) {
+                continue;
+            }
+            self.parse(&cols).map_err(|e| anyhow::anyhow!("{} row '{}': {}", self.kind.name(), line, e))?;
+        }
+    }
+
+    fn parse(&mut self, cols: &[&str]) -> anyhow::Result<()> {
+        let col = |i: usize| cols.get(i).copied().ok_or_else(|| anyhow::anyhow!("missing column {}", i));
+        let f = |i: usize| -> anyhow::Result<f64> { Ok(col(i)?.parse()?) };
+        let t = |i: usize| -> anyhow::Result<i64> { Ok(col(i)?.parse()?) };
+        let (symbol, symbol_len) = self.symbol;
+
+        match self.kind {
+            // agg_trade_id,price,quantity,first_trade_id,last_trade_id,transact_time,is_buyer_maker
+            DataKind::AggTrades => {
+                let time = t(5)?;
+                let mut qty = f(2)?;
+                if col(6)?.eq_ignore_ascii_case("true") {
+                    qty = -qty;
+                }
+                self.pending.push_back(trade(symbol, symbol_len, f(1)?, qty, time));
+            }
+            // update_id,best_bid_price,best_bid_qty,best_ask_price,best_ask_qty,transaction_time,event_time
+            DataKind::BookTicker => {
+                let event_time = t(6)?;
+                self.pending.push_back(CEvent {
+                    event_type: EVENT_BOOK_TICKER,
+                    data: CEventData {
+                        book_ticker: CBookTicker {
+                            symbol,
+                            symbol_len,
+                            bid_price: f(1)?,
+                            bid_qty: f(2)?,
+                            ask_price: f(3)?,
+                            ask_qty: f(4)?,
+                            time: t(5)?,
+                        },
+                    },
+                    received_at_ns: to_ns(event_time),
+                    seq: 0,
+                });
+            }
+            // open_time,open,high,low,close,volume,close_time,...
+            DataKind::Klines => {
+                let (open_time, close_time) = (t(0)?, t(6)?);
+                let (open, high, low, close) = (f(1)?, f(2)?, f(3)?, f(4)?);
+                let qty = f(5)? / 4.0;
+                // Растущая свеча обычно сначала ходит вниз, падающая - вверх
+                let path = if close >= open { [open, low, high, close] } else { [open, high, low, close] };
+                let times = [open_time, open_time + (close_time - open_time) / 3, open_time + (close_time - open_time) * 2 / 3, close_time];
+                let qty = if close >= open { qty } else { -qty };
+                for (price, time) in path.into_iter().zip(times) {
+                    self.pending.push_back(trade(symbol, symbol_len, price, qty, time));
+                }
+            }
+            DataKind::FundingRate => anyhow::bail!("funding rates are not events"),
+        }
+        Ok(())
+    }
+}
+
+fn trade(symbol: [u8; 16], symbol_len: u8, price: f64, qty: f64, time: i64) -> CEvent {
+    CEvent {
+        event_type: EVENT_TRADE,
+        data: CEventData { trade: CTrade { symbol, symbol_len, price, qty, time } },
+        received_at_ns: to_ns(time),
+        seq: 0,
+    }
+}
+
+/// Время архива -> ns. Часть архивов Binance перешла на микросекунды
+fn to_ns(time: i64) -> u64 {
+    let time = time.max(0) as u64;
+    if time > 100_000_000_000_000 {
+        time * 1_000
+    } else {
+        time * 1_000_000
+    }
+}
+
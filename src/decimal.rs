@@ -0,0 +1,136 @@
+// src/decimal.rs
+
+use std::fmt::Write;
+
+use crate::symbols;
+
+// ═══════════════════════════════════════════════════════════
+// ЦЕНЫ И КОЛИЧЕСТВА ДЛЯ BINANCE
+// ═══════════════════════════════════════════════════════════
+//
+// ryu печатает кратчайшее представление f64: для 1000PEPE это "1e-7" или
+// "1.2345e-5", а после арифметики стратегии - "0.30000000000000004".
+// Binance такие строки отклоняет (-1100 / -1111), поэтому price и quantity
+// уходят только обычной десятичной записью без экспоненты.
+//
+// Точность берётся из tickSize/stepSize символа, закэшированных из
+// exchangeInfo (symbols.rs): price и quantity округляются до их числа
+// знаков. Пока exchangeInfo не загружен или символа в нём нет, округляем
+// до MAX_DECIMALS знаков (больше у фьючерсов Binance не бывает) - это
+// срезает шум float, не трогая реальные знаки цены. Кратность tickSize
+// (тик 0.5, 10) не подгоняется: направление округления решает стратегия.
+
+/// Максимум знаков после запятой у price/quantity на Binance Futures
+pub const MAX_DECIMALS: usize = 8;
+
+/// Цена для ордера по символу: с точностью tickSize
+pub fn price(symbol: &str, value: f64) -> String {
+    match symbols::precision(symbol) {
+        Some(p) => format_with(value, p.price),
+        None => format(value),
+    }
+}
+
+/// Количество для ордера по символу: с точностью stepSize
+pub fn qty(symbol: &str, value: f64) -> String {
+    match symbols::precision(symbol) {
+        Some(p) => format_with(value, p.qty),
+        None => format(value),
+    }
+}
+
+/// Десятичная запись без экспоненты и хвостовых нулей: 1e-7 -> "0.0000001"
+pub fn format(value: f64) -> String {
+    format_with(value, MAX_DECIMALS)
+}
+
+/// То же с явной точностью (по tickSize/stepSize символа)
+pub fn format_with(value: f64, decimals: usize) -> String {
+    let mut out = String::with_capacity(24);
+    let _ = write!(out, "{:.*}", decimals, value);
+
+    if out.contains('.') {
+        let trimmed = out.trim_end_matches('0').trim_end_matches('.').len();
+        out.truncate(trimmed);
+    }
+    // -0.000000001 округляется в "-0"
+    if out == "-0" {
+        out.remove(0);
+    }
+    out
+}
+
+/// Знаков после запятой в шаге из exchangeInfo: "0.00000010" -> 7, "1" -> 0
+pub fn step_decimals(step: &str) -> usize {
+    match step.split_once('.') {
+        Some((_, frac)) => frac.trim_end_matches('0').len(),
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_prices() {
+        assert_eq!(format(65000.0), "65000");
+        assert_eq!(format(65000.5), "65000.5");
+        assert_eq!(format(0.01), "0.01");
+        assert_eq!(format(1.0), "1");
+        assert_eq!(format(0.0), "0");
+    }
+
+    #[test]
+    fn tiny_prices_have_no_exponent() {
+        // 1000PEPEUSDT: tickSize 0.0000001
+        assert_eq!(format(1e-7), "0.0000001");
+        assert_eq!(format(0.0000123), "0.0000123");
+        assert_eq!(format(1.234e-5), "0.00001234");
+        assert_eq!(format(0.00000001), "0.00000001");
+        assert!(!format(3.5e-6).contains('e'));
+    }
+
+    #[test]
+    fn large_quantities_have_no_exponent() {
+        // 1000PEPE торгуется миллионами контрактов
+        assert_eq!(format(1e7), "10000000");
+        assert_eq!(format(12_345_678.0), "12345678");
+        assert_eq!(format(1e21), "1000000000000000000000");
+    }
+
+    #[test]
+    fn float_noise_is_trimmed() {
+        assert_eq!(format(0.1 + 0.2), "0.3");
+        assert_eq!(format(0.0000011 * 3.0), "0.0000033");
+        assert_eq!(format(100.0 * 1.1), "110");
+    }
+
+    #[test]
+    fn explicit_precision() {
+        assert_eq!(format_with(0.123456, 3), "0.123");
+        assert_eq!(format_with(0.0000126, 6), "0.000013");
+        assert_eq!(format_with(42.0, 0), "42");
+    }
+
+    #[test]
+    fn step_size_decimals() {
+        assert_eq!(step_decimals("0.00000010"), 7);
+        assert_eq!(step_decimals("0.10"), 1);
+        assert_eq!(step_decimals("1"), 0);
+        assert_eq!(step_decimals("10.000"), 0);
+    }
+
+    #[test]
+    fn unknown_symbol_falls_back_to_max_decimals() {
+        assert_eq!(price("NOSUCHUSDT", 0.000000123456), "0.00000012");
+        assert_eq!(qty("NOSUCHUSDT", 1.5), "1.5");
+    }
+
+    #[test]
+    fn negative_zero_is_zero() {
+        assert_eq!(format(-0.0), "0");
+        assert_eq!(format(-1e-12), "0");
+        assert_eq!(format(-1.5), "-1.5");
+    }
+}
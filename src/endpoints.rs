@@ -0,0 +1,232 @@
+// src/endpoints.rs
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::{LazyLock, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+
+use crate::net;
+
+/// Таймаут одного замера (TCP или WS handshake)
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Вес нового замера в сглаженном значении
+const EWMA_ALPHA: f64 = 0.3;
+
+/// Переключаемся, только если кандидат быстрее текущего больше чем на столько:
+/// иначе шум замеров гонял бы соединение между почти равными хостами
+const SWITCH_MARGIN: f64 = 0.9;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EndpointsConfig {
+    /// false - всегда первый кандидат из списка
+    pub enabled: bool,
+    /// Период замеров
+    pub interval_secs: u64,
+    /// Кандидаты для market data WS. fstream-mm - только для аккаунтов
+    /// market maker программы, добавляйте его в конфиг сами
+    pub market_data: Vec<String>,
+    /// Кандидаты для trade WS API
+    pub trade: Vec<String>,
+}
+
+impl Default for EndpointsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_secs: 60,
+            market_data: vec!["wss://fstream.binance.com/ws".to_string()],
+            trade: vec!["wss://ws-fapi.binance.com/ws-fapi/v1".to_string()],
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// СОСТОЯНИЕ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointKind {
+    MarketData,
+    Trade,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointStats {
+    pub url: String,
+    pub kind: EndpointKind,
+    /// Последний замер TCP connect
+    pub tcp_ms: Option<f64>,
+    /// Последний замер TCP + TLS + WS upgrade
+    pub handshake_ms: Option<f64>,
+    /// Сглаженный handshake, по нему выбирается лучший
+    pub handshake_avg_ms: Option<f64>,
+    /// Unix ms последнего замера
+    pub last_probe: Option<i64>,
+    pub last_error: Option<String>,
+    pub probes: u64,
+    pub failures: u64,
+    /// Сейчас выбран для новых подключений
+    pub selected: bool,
+}
+
+impl EndpointStats {
+    fn new(url: &str, kind: EndpointKind) -> Self {
+        Self {
+            url: url.to_string(),
+            kind,
+            tcp_ms: None,
+            handshake_ms: None,
+            handshake_avg_ms: None,
+            last_probe: None,
+            last_error: None,
+            probes: 0,
+            failures: 0,
+            selected: false,
+        }
+    }
+
+    fn healthy(&self) -> bool {
+        self.last_error.is_none() && self.handshake_avg_ms.is_some()
+    }
+}
+
+static CONFIG: OnceLock<EndpointsConfig> = OnceLock::new();
+
+/// url -> статистика; порядок кандидатов - в CONFIG
+static STATS: LazyLock<DashMap<String, EndpointStats>> = LazyLock::new(DashMap::new);
+
+/// Выбранный url по типу (меняется только при переподключении)
+static SELECTED: LazyLock<DashMap<EndpointKind, String>> = LazyLock::new(DashMap::new);
+
+fn candidates(kind: EndpointKind) -> &'static [String] {
+    let Some(config) = CONFIG.get() else { return &[] };
+    match kind {
+        EndpointKind::MarketData => &config.market_data,
+        EndpointKind::Trade => &config.trade,
+    }
+}
+
+/// Регистрирует кандидатов и запускает фоновые замеры
+pub fn init(config: EndpointsConfig) {
+    for (kind, urls) in [(EndpointKind::MarketData, &config.market_data), (EndpointKind::Trade, &config.trade)] {
+        for url in urls {
+            STATS.insert(url.clone(), EndpointStats::new(url, kind));
+        }
+    }
+
+    let enabled = config.enabled && config.interval_secs > 0;
+    let interval = Duration::from_secs(config.interval_secs.max(1));
+    let _ = CONFIG.set(config);
+
+    if enabled {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                probe_all().await;
+            }
+        });
+    }
+}
+
+/// URL для нового подключения: самый быстрый здоровый кандидат,
+/// без замеров - первый из конфига, без конфига - fallback
+pub fn select(kind: EndpointKind, fallback: &str) -> String {
+    let candidates = candidates(kind);
+    let Some(first) = candidates.first() else {
+        return fallback.to_string();
+    };
+
+    let current = SELECTED.get(&kind).map(|u| u.clone()).unwrap_or_else(|| first.clone());
+    let score = |url: &str| {
+        STATS.get(url).filter(|s| s.healthy()).and_then(|s| s.handshake_avg_ms)
+    };
+
+    let best = candidates
+        .iter()
+        .filter_map(|url| score(url).map(|ms| (url, ms)))
+        .min_by(|a, b| a.1.total_cmp(&b.1));
+
+    let chosen = match (best, score(&current)) {
+        (Some((url, ms)), Some(current_ms)) if ms < current_ms * SWITCH_MARGIN => url.clone(),
+        (Some((url, _)), None) => url.clone(),
+        _ => current,
+    };
+
+    if SELECTED.insert(kind, chosen.clone()).is_some_and(|prev| prev != chosen) {
+        tracing::info!("🛰️ Switching {:?} endpoint to {}", kind, chosen);
+    }
+    chosen
+}
+
+pub fn snapshot() -> Vec<EndpointStats> {
+    let Some(config) = CONFIG.get() else { return Vec::new() };
+    config
+        .market_data
+        .iter()
+        .chain(&config.trade)
+        .filter_map(|url| STATS.get(url).map(|s| s.clone()))
+        .map(|mut s| {
+            s.selected = SELECTED.get(&s.kind).is_some_and(|u| *u == s.url);
+            s
+        })
+        .collect()
+}
+
+// ═══════════════════════════════════════════════════════════
+// ЗАМЕРЫ
+// ═══════════════════════════════════════════════════════════
+
+pub async fn probe_all() {
+    let urls: Vec<String> = STATS.iter().map(|e| e.key().clone()).collect();
+    let results = futures_util::future::join_all(urls.iter().map(|url| probe(url))).await;
+
+    let now = chrono::Utc::now().timestamp_millis();
+    for (url, result) in urls.iter().zip(results) {
+        let Some(mut stats) = STATS.get_mut(url) else { continue };
+        stats.probes += 1;
+        stats.last_probe = Some(now);
+        match result {
+            Ok((tcp_ms, handshake_ms)) => {
+                stats.tcp_ms = Some(tcp_ms);
+                stats.handshake_ms = Some(handshake_ms);
+                stats.handshake_avg_ms = Some(match stats.handshake_avg_ms {
+                    Some(avg) => avg + EWMA_ALPHA * (handshake_ms - avg),
+                    None => handshake_ms,
+                });
+                stats.last_error = None;
+            }
+            Err(e) => {
+                stats.failures += 1;
+                stats.last_error = Some(e.to_string());
+            }
+        }
+    }
+}
+
+/// (TCP connect, полный WS handshake) в ms
+async fn probe(url: &str) -> anyhow::Result<(f64, f64)> {
+    let uri: axum::http::Uri = url.parse()?;
+    let host = uri.host().ok_or_else(|| anyhow::anyhow!("No host in '{}'", url))?;
+    let port = uri.port_u16().unwrap_or(if uri.scheme_str() == Some("wss") { 443 } else { 80 });
+
+    let start = Instant::now();
+    let tcp = tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect((host, port))).await??;
+    let tcp_ms = start.elapsed().as_secs_f64() * 1000.0;
+    drop(tcp);
+
+    let start = Instant::now();
+    let (mut ws, _) = tokio::time::timeout(PROBE_TIMEOUT, net::connect(url)).await??;
+    let handshake_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let _ = ws.close(None).await;
+
+    Ok((tcp_ms, handshake_ms))
+}
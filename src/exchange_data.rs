@@ -1,12 +1,25 @@
 // src/exchange_data.rs
 
-use tokio::sync::{mpsc, Mutex, broadcast};
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use dashmap::DashMap;
+use tokio::sync::{mpsc, oneshot, Mutex, broadcast};
+use tokio_tungstenite::tungstenite::Message;
 use futures_util::{SinkExt, StreamExt};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use simd_json::serde as simd_serde;
-use std::{sync::Arc, time::{Instant, SystemTime}};
-use crate::ffi_types::{CEvent, CEventData, CBookTicker, CTrade};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::{sync::Arc, time::Instant};
+use crate::affinity;
+use crate::analytics::{Analytics, AnalyticsConfig};
+use crate::endpoints::{self, EndpointKind};
+use crate::fanout;
+use crate::ffi_types::{CEvent, CEventData, CBookTicker, CTrade, Sequencer};
+use crate::latency::{self, Stage};
+use crate::net;
+use crate::raw_capture;
+use crate::recorder;
+use crate::strategies::order::time_offset_ms;
+use crate::volume_profile;
 
 // ═══════════════════════════════════════════════════════════
 // УДАЛЯЕМ старый Event enum!
@@ -44,37 +57,234 @@ struct RawTrade {
     time: i64,
 }
 
+/// Поток market data по символу (значения - как EVENT_* его событий)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StreamKind {
+    BookTicker,
+    Trade,
+}
+
+impl StreamKind {
+    const ALL: [StreamKind; 2] = [StreamKind::BookTicker, StreamKind::Trade];
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::BookTicker),
+            1 => Some(Self::Trade),
+            _ => None,
+        }
+    }
+
+    fn stream_name(self) -> &'static str {
+        match self {
+            Self::BookTicker => "bookTicker",
+            Self::Trade => "trade",
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// СТАТИСТИКА ПОТОКОВ
+// ═══════════════════════════════════════════════════════════
+
+/// Счётчики одного потока символа. Пишет только reader market WS
+#[derive(Default)]
+struct StreamCounters {
+    messages: AtomicU64,
+    parse_errors: AtomicU64,
+    /// received_at_ns последнего события
+    last_ns: AtomicU64,
+    /// Секунда (Unix) текущего окна и число событий в нём
+    window_sec: AtomicU64,
+    window_count: AtomicU64,
+    /// Событий за последнюю завершённую секунду
+    last_rate: AtomicU64,
+}
+
+impl StreamCounters {
+    fn on_message(&self, received_at_ns: u64) {
+        self.messages.fetch_add(1, Ordering::Relaxed);
+        self.last_ns.store(received_at_ns, Ordering::Relaxed);
+        let sec = received_at_ns / 1_000_000_000;
+        let window = self.window_sec.load(Ordering::Relaxed);
+        if sec == window {
+            self.window_count.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        // Пропущенные секунды без событий - значит, темп за прошлую секунду 0
+        let rate = if sec == window + 1 { self.window_count.load(Ordering::Relaxed) } else { 0 };
+        self.last_rate.store(rate, Ordering::Relaxed);
+        self.window_sec.store(sec, Ordering::Relaxed);
+        self.window_count.store(1, Ordering::Relaxed);
+    }
+
+    /// Событий в секунду: последняя завершённая секунда, 0 если поток замолчал
+    fn rate(&self, now_sec: u64) -> u64 {
+        let window = self.window_sec.load(Ordering::Relaxed);
+        match now_sec.saturating_sub(window) {
+            0 => self.last_rate.load(Ordering::Relaxed),
+            1 => self.window_count.load(Ordering::Relaxed),
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Default)]
+struct SymbolCounters {
+    book_ticker: StreamCounters,
+    trade: StreamCounters,
+}
+
+impl SymbolCounters {
+    fn get(&self, stream: StreamKind) -> &StreamCounters {
+        match stream {
+            StreamKind::BookTicker => &self.book_ticker,
+            StreamKind::Trade => &self.trade,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamStats {
+    pub symbol: String,
+    pub stream: StreamKind,
+    pub messages: u64,
+    pub rate_per_sec: u64,
+    /// Unix ms последнего события, None - событий ещё не было
+    pub last_event_ms: Option<i64>,
+    /// Сколько ms назад пришло последнее событие
+    pub age_ms: Option<i64>,
+    pub parse_errors: u64,
+    /// Держатели подписки ("api", инстансы). 0 - подписки нет, события
+    /// приходят по старой подписке или до отписки
+    pub subscribers: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketDataStats {
+    pub connected: bool,
+    pub uptime_secs: u64,
+    pub streams: Vec<StreamStats>,
+}
+
+/// Символ из сырого сообщения ("s":"BTCUSDT") - для ошибок разбора
+fn raw_symbol(txt: &str) -> Option<&str> {
+    let start = txt.find("\"s\":\"")? + 5;
+    let len = txt[start..].find('"')?;
+    Some(&txt[start..start + len]).filter(|s| !s.is_empty() && s.len() <= 20)
+}
+
+/// Держатель подписок из HTTP API (/subscribe/*, recorder)
+pub const API_HOLDER: &str = "api";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamSubscription {
+    pub symbol: String,
+    pub stream: StreamKind,
+    /// "api" или instance_id стратегий
+    pub holders: Vec<String>,
+}
+
+/// Ответ биржи на SUBSCRIBE/UNSUBSCRIBE для того, кто ждёт подтверждения
+type Reply = oneshot::Sender<Result<(), CommandError>>;
+
 #[derive(Debug)]
 pub enum Command {
-    SubscribeBookticker(String),
-    UnsubscribeBookticker(String),
-    SubscribeTrades(String),
-    UnsubscribeTrades(String),
+    Subscribe(String, StreamKind, Option<Reply>),
+    Unsubscribe(String, StreamKind, Option<Reply>),
     ListSubscriptions,
 }
 
+/// Сколько HTTP-запрос ждёт ответа биржи на SUBSCRIBE/UNSUBSCRIBE
+const COMMAND_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub enum CommandError {
+    /// Биржа ответила ошибкой ({"error": {"code", "msg"}, "id"})
+    Rejected { code: i64, msg: String },
+    /// Ответа нет: сокет не подключён, оборвался или молчит
+    NoResponse(&'static str),
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Rejected { code, msg } => write!(f, "Rejected by exchange ({}): {}", code, msg),
+            Self::NoResponse(reason) => write!(f, "No response from exchange: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// Команда, ушедшая в market WS и ждущая ответа с тем же id
+struct PendingCommand {
+    /// "SUBSCRIBE btcusdt@trade" - для логов
+    what: String,
+    /// Для SUBSCRIBE: подписку, отклонённую биржей, снимаем у держателей
+    subscribe: Option<(String, StreamKind)>,
+    reply: Option<Reply>,
+}
+
+/// Что биржа считает подписанным (ответ LIST_SUBSCRIPTIONS)
+#[derive(Debug, Clone, Serialize)]
+pub struct ExchangeSubscriptions {
+    pub streams: Vec<String>,
+    /// Unix ms ответа
+    pub time: i64,
+}
+
+#[allow(dead_code)]
 pub struct ExchangeData {
     ws_url: String,
     is_connected: Arc<Mutex<bool>>,
-    cmd_tx: mpsc::Sender<Command>,
+    cmd_tx: mpsc::UnboundedSender<Command>,
+    /// (symbol, поток) -> кто держит подписку. Бирже уходит SUBSCRIBE на первого
+    /// держателя и UNSUBSCRIBE после последнего, чтобы стратегии и API
+    /// не снимали подписки друг у друга
+    holders: std::sync::Mutex<BTreeMap<(String, StreamKind), BTreeSet<String>>>,
     pub event_tx: broadcast::Sender<CEvent>,  // ← теперь CEvent!
+    /// seq по symbol/stream, чтобы потребители видели потерянные события
+    seqs: Sequencer,
     start_time: Instant,
+    /// SYMBOL -> счётчики потоков
+    stats: DashMap<String, SymbolCounters>,
+    /// id команды в market WS (у каждой свой, иначе ответы не различить)
+    next_id: AtomicU64,
+    pending: DashMap<u64, PendingCommand>,
+    /// Последний ответ LIST_SUBSCRIPTIONS (им же пингуем молчащий сокет)
+    listed: std::sync::Mutex<Option<ExchangeSubscriptions>>,
+    /// None - EVENT_ANALYTICS выключены (config.analytics)
+    analytics: Option<Analytics>,
 }
 
 impl ExchangeData {
-    pub fn new(ws_url: String, event_tx: broadcast::Sender<CEvent>) -> Arc<Self> {
-        let (cmd_tx, cmd_rx) = mpsc::channel(32);
+    pub fn new(
+        ws_url: String,
+        event_tx: broadcast::Sender<CEvent>,
+        cpu_core: Option<usize>,
+        analytics: AnalyticsConfig,
+    ) -> Arc<Self> {
+        let (cmd_tx, cmd_rx) = mpsc::unbounded_channel();
         
         let manager = Arc::new(Self {
             ws_url: ws_url.clone(),
             event_tx,
             is_connected: Arc::new(Mutex::new(false)),
             cmd_tx,
+            holders: std::sync::Mutex::new(BTreeMap::new()),
+            seqs: Sequencer::default(),
             start_time: Instant::now(),
+            stats: DashMap::new(),
+            next_id: AtomicU64::new(1),
+            pending: DashMap::new(),
+            listed: std::sync::Mutex::new(None),
+            analytics: Analytics::new(analytics),
         });
         
         let manager_clone = manager.clone();
-        tokio::spawn(async move {
+        affinity::spawn_pinned("market-data", cpu_core, async move {
             manager_clone.run_socket(ws_url, cmd_rx).await;
         });
         
@@ -84,12 +294,14 @@ impl ExchangeData {
     async fn run_socket(
         self: Arc<Self>,
         ws_url: String,
-        mut cmd_rx: mpsc::Receiver<Command>
+        mut cmd_rx: mpsc::UnboundedReceiver<Command>
     ) {
         loop {
+            // Лучший по замерам хост выбирается заново при каждом подключении
+            let ws_url = endpoints::select(EndpointKind::MarketData, &ws_url);
             tracing::info!("Trying to connect...");
-            match connect_async(&ws_url).await {
-                Ok((mut ws, _)) => {
+            match net::connect(&ws_url).await {
+                Ok((ws, _)) => {
                     tracing::info!("Connected to {ws_url}");
                     *self.is_connected.lock().await = true;
                     let (mut write, mut read) = ws.split();
@@ -119,11 +331,9 @@ impl ExchangeData {
                                     tracing::warn!("Stream ended");
                                     break;
                                 }
-                                Err(_) => {
-                                    // Timeout - отправляем ping
-                                    if cmd_tx.send(Command::ListSubscriptions).await.is_err() {
-                                        break;
-                                    }
+                                // Timeout - отправляем ping, выходим если канал закрыт
+                                Err(_) if cmd_tx.send(Command::ListSubscriptions).is_err() => {
+                                    break;
                                 }
                                 _ => {}
                             }
@@ -135,7 +345,7 @@ impl ExchangeData {
                             tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
                             continue;
                         }
-                        let msg = Self::command_to_json(cmd);
+                        let msg = self.command_to_json(cmd);
                         if write.send(Message::Text(msg)).await.is_err() {
                             break;
                         }
@@ -143,6 +353,7 @@ impl ExchangeData {
 
                     let _ = reader.await;
                     *self.is_connected.lock().await = false;
+                    self.fail_pending("market WS disconnected");
                     tracing::info!("Reconnecting in 3 sec...");
                     tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
                 }
@@ -158,10 +369,9 @@ impl ExchangeData {
     async fn handle_text(&self, mut txt: String) {
         // для вывода в консоль
         // let start = tokio::time::Instant::now();
-        let received_at_ns = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap()
-            .as_nanos() as u64;
+        let received_at_ns = latency::now_ns();
+        // simd_json парсит на месте - исходный текст для recorder копируем заранее
+        let raw = recorder::wants_raw().then(|| txt.clone());
         
         // ═══════════════════════════════════════════════════════════
         // СРАЗУ КОНВЕРТИРУЕМ В C-ТИПЫ!
@@ -176,7 +386,7 @@ impl ExchangeData {
                     let len = bytes.len().min(15);
                     symbol[..len].copy_from_slice(&bytes[..len]);
                     
-                    let c_event = CEvent {
+                    let mut c_event = CEvent {
                         event_type: 0,
                         data: CEventData {
                             book_ticker: CBookTicker {
@@ -190,7 +400,9 @@ impl ExchangeData {
                             }
                         },
                         received_at_ns,
+                        seq: 0,
                     };
+                    self.seqs.stamp(&mut c_event);
                     
                     // let book = unsafe { &c_event.data.book_ticker };
                     // println!(
@@ -201,9 +413,28 @@ impl ExchangeData {
                     // );
 
                     // Отправляем C-тип
+                    let parsed_ns = latency::now_ns();
+                    latency::record(Stage::Parse, parsed_ns.saturating_sub(received_at_ns));
+                    self.count(&bt.symbol, StreamKind::BookTicker, received_at_ns);
+                    if bt.time > 0 {
+                        // Время биржи на момент чтения: локальные часы + offset
+                        let server_ns = received_at_ns as i64 + time_offset_ms() * 1_000_000;
+                        latency::record(Stage::BookTickerArrival, (server_ns - bt.time * 1_000_000).max(0) as u64);
+                    }
+                    fanout::publish(c_event);
+                    let broadcast_ns = latency::now_ns();
                     let _ = self.event_tx.send(c_event);
+                    latency::record_since(Stage::Broadcast, broadcast_ns);
+                    recorder::record(&c_event, raw.as_deref());
+                    if let Some(analytics) = &self.analytics {
+                        self.publish_derived(analytics.on_book(unsafe { &c_event.data.book_ticker }, received_at_ns));
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("BookTicker parse error: {e:?}");
+                    self.count_error(raw.as_deref().unwrap_or(&txt), StreamKind::BookTicker);
+                    raw_capture::capture("market", format!("bookTicker: {e:?}"), raw.as_deref().unwrap_or(&txt), raw.is_some());
                 }
-                Err(e) => tracing::error!("BookTicker parse error: {e:?}"),
             }
         } else if txt.contains("\"trade\"") {
             match unsafe { simd_serde::from_str::<RawTrade>(txt.as_mut_str()) } {
@@ -218,7 +449,7 @@ impl ExchangeData {
                         qty = -qty;
                     }
                     
-                    let c_event = CEvent {
+                    let mut c_event = CEvent {
                         event_type: 1,
                         data: CEventData {
                             trade: CTrade {
@@ -229,8 +460,10 @@ impl ExchangeData {
                                 time: t.time,
                             }
                         },
-                        received_at_ns
+                        received_at_ns,
+                        seq: 0,
                     };
+                    self.seqs.stamp(&mut c_event);
                     
                     // let trade = unsafe { &c_event.data.trade };
                     // let side = if trade.qty > 0.0 { "BUY" } else { "SELL" };
@@ -242,60 +475,288 @@ impl ExchangeData {
                     //     side
                     // );
 
+                    let parsed_ns = latency::now_ns();
+                    latency::record(Stage::Parse, parsed_ns.saturating_sub(received_at_ns));
+                    self.count(&t.symbol, StreamKind::Trade, received_at_ns);
+                    fanout::publish(c_event);
+                    let broadcast_ns = latency::now_ns();
                     let _ = self.event_tx.send(c_event);
+                    latency::record_since(Stage::Broadcast, broadcast_ns);
+                    recorder::record(&c_event, raw.as_deref());
+                    volume_profile::on_trade(unsafe { &c_event.data.trade });
+                    if let Some(analytics) = &self.analytics {
+                        self.publish_derived(analytics.on_trade(unsafe { &c_event.data.trade }, received_at_ns));
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Trade parse error: {e:?}");
+                    self.count_error(raw.as_deref().unwrap_or(&txt), StreamKind::Trade);
+                    raw_capture::capture("market", format!("trade: {e:?}"), raw.as_deref().unwrap_or(&txt), raw.is_some());
                 }
-                Err(e) => tracing::error!("Trade parse error: {e:?}"),
             }
+        } else if txt.contains("\"id\"") {
+            self.handle_response(&txt);
         }
     }
 
-    fn command_to_json(cmd: Command) -> String {
-        let msg = match cmd {
-            Command::SubscribeBookticker(sym) => serde_json::json!({
-                "method": "SUBSCRIBE",
-                "params": [format!("{sym}@bookTicker")],
-                "id": 1
-            }),
-            Command::UnsubscribeBookticker(sym) => serde_json::json!({
-                "method": "UNSUBSCRIBE",
-                "params": [format!("{sym}@bookTicker")],
-                "id": 1
-            }),
-            Command::SubscribeTrades(sym) => serde_json::json!({
-                "method": "SUBSCRIBE",
-                "params": [format!("{sym}@trade")],
-                "id": 1
-            }),
-            Command::UnsubscribeTrades(sym) => serde_json::json!({
-                "method": "UNSUBSCRIBE",
-                "params": [format!("{sym}@trade")],
-                "id": 1
-            }),
-            Command::ListSubscriptions => serde_json::json!({
-                "method": "LIST_SUBSCRIPTIONS",
-                "id": 1
-            }),
+    /// Событие, посчитанное из market data (EVENT_ANALYTICS): после исходного,
+    /// со своим seq, в recorder не пишется
+    fn publish_derived(&self, event: Option<CEvent>) {
+        let Some(mut event) = event else { return };
+        self.seqs.stamp(&mut event);
+        fanout::publish(event);
+        let _ = self.event_tx.send(event);
+    }
+
+    fn count(&self, symbol: &str, stream: StreamKind, received_at_ns: u64) {
+        match self.stats.get(symbol) {
+            Some(counters) => counters.get(stream).on_message(received_at_ns),
+            None => self.stats.entry(symbol.to_string()).or_default().get(stream).on_message(received_at_ns),
+        }
+    }
+
+    /// simd_json разбирает на месте: после ошибки txt может быть испорчен,
+    /// тогда ошибка уходит в символ "?"
+    fn count_error(&self, txt: &str, stream: StreamKind) {
+        let symbol = raw_symbol(txt).unwrap_or("?").to_uppercase();
+        self.stats.entry(symbol).or_default().get(stream).parse_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Потоки с событиями и текущие подписки, по символу
+    pub async fn stats(&self) -> MarketDataStats {
+        let now_ns = latency::now_ns();
+        let holders: BTreeMap<(String, StreamKind), usize> = self.holders.lock().unwrap()
+            .iter()
+            .map(|((symbol, stream), set)| ((symbol.to_uppercase(), *stream), set.len()))
+            .collect();
+
+        let mut streams = Vec::new();
+        for entry in self.stats.iter() {
+            for stream in StreamKind::ALL {
+                let c = entry.value().get(stream);
+                let messages = c.messages.load(Ordering::Relaxed);
+                let parse_errors = c.parse_errors.load(Ordering::Relaxed);
+                let subscribers = holders.get(&(entry.key().clone(), stream)).copied().unwrap_or(0);
+                if messages == 0 && parse_errors == 0 && subscribers == 0 {
+                    continue;
+                }
+                let last_ns = c.last_ns.load(Ordering::Relaxed);
+                streams.push(StreamStats {
+                    symbol: entry.key().clone(),
+                    stream,
+                    messages,
+                    rate_per_sec: c.rate(now_ns / 1_000_000_000),
+                    last_event_ms: (last_ns > 0).then_some((last_ns / 1_000_000) as i64),
+                    age_ms: (last_ns > 0).then(|| (now_ns.saturating_sub(last_ns) / 1_000_000) as i64),
+                    parse_errors,
+                    subscribers,
+                });
+            }
+        }
+        // Подписки, по которым не пришло ни одного события
+        for ((symbol, stream), subscribers) in holders {
+            if !streams.iter().any(|s| s.symbol == symbol && s.stream == stream) {
+                streams.push(StreamStats {
+                    symbol,
+                    stream,
+                    messages: 0,
+                    rate_per_sec: 0,
+                    last_event_ms: None,
+                    age_ms: None,
+                    parse_errors: 0,
+                    subscribers,
+                });
+            }
+        }
+        streams.sort_by(|a, b| (&a.symbol, a.stream).cmp(&(&b.symbol, b.stream)));
+
+        MarketDataStats {
+            connected: *self.is_connected.lock().await,
+            uptime_secs: self.start_time.elapsed().as_secs(),
+            streams,
+        }
+    }
+
+    /// JSON команды с новым id; ответ на неё найдёт handle_response
+    fn command_to_json(&self, cmd: Command) -> String {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (method, params, pending) = match cmd {
+            Command::Subscribe(sym, stream, reply) => {
+                let name = format!("{sym}@{}", stream.stream_name());
+                let pending = PendingCommand {
+                    what: format!("SUBSCRIBE {name}"),
+                    subscribe: Some((sym, stream)),
+                    reply,
+                };
+                ("SUBSCRIBE", Some(name), pending)
+            }
+            Command::Unsubscribe(sym, stream, reply) => {
+                let name = format!("{sym}@{}", stream.stream_name());
+                let pending = PendingCommand { what: format!("UNSUBSCRIBE {name}"), subscribe: None, reply };
+                ("UNSUBSCRIBE", Some(name), pending)
+            }
+            Command::ListSubscriptions => {
+                let pending = PendingCommand { what: "LIST_SUBSCRIPTIONS".to_string(), subscribe: None, reply: None };
+                ("LIST_SUBSCRIPTIONS", None, pending)
+            }
+        };
+        self.pending.insert(id, pending);
+
+        let msg = match params {
+            Some(stream) => serde_json::json!({ "method": method, "params": [stream], "id": id }),
+            None => serde_json::json!({ "method": method, "id": id }),
         };
         msg.to_string()
     }
 
-    pub async fn subscribe_bookticker(&self, symbol: &str) -> anyhow::Result<()> {
-        self.cmd_tx.send(Command::SubscribeBookticker(symbol.to_lowercase())).await?;
-        Ok(())
+    /// Ответ на команду: {"result": ..., "id": N} или {"error": {...}, "id": N}
+    fn handle_response(&self, txt: &str) {
+        let Ok(v) = serde_json::from_str::<serde_json::Value>(txt) else { return };
+        let Some((_, pending)) = v["id"].as_u64().and_then(|id| self.pending.remove(&id)) else { return };
+
+        let result = match v.get("error") {
+            Some(error) => {
+                let code = error["code"].as_i64().unwrap_or(0);
+                let msg = error["msg"].as_str().unwrap_or("unknown error").to_string();
+                tracing::warn!("📡 {} rejected ({}): {}", pending.what, code, msg);
+                // Подписки на бирже нет - держатели не должны думать, что она есть
+                if let Some(key) = &pending.subscribe {
+                    self.holders.lock().unwrap().remove(key);
+                }
+                Err(CommandError::Rejected { code, msg })
+            }
+            None => {
+                if let Some(streams) = v["result"].as_array() {
+                    *self.listed.lock().unwrap() = Some(ExchangeSubscriptions {
+                        streams: streams.iter().filter_map(|s| s.as_str().map(str::to_string)).collect(),
+                        time: chrono::Utc::now().timestamp_millis(),
+                    });
+                }
+                Ok(())
+            }
+        };
+        if let Some(reply) = pending.reply {
+            let _ = reply.send(result);
+        }
     }
 
-    pub async fn subscribe_trades(&self, symbol: &str) -> anyhow::Result<()> {
-        self.cmd_tx.send(Command::SubscribeTrades(symbol.to_lowercase())).await?;
-        Ok(())
+    /// Ответов на команды прошлой сессии уже не будет
+    fn fail_pending(&self, reason: &'static str) {
+        let ids: Vec<u64> = self.pending.iter().map(|e| *e.key()).collect();
+        for id in ids {
+            if let Some((_, PendingCommand { reply: Some(reply), .. })) = self.pending.remove(&id) {
+                let _ = reply.send(Err(CommandError::NoResponse(reason)));
+            }
+        }
     }
 
-    pub async fn unsubscribe_bookticker(&self, symbol: &str) -> anyhow::Result<()> {
-        self.cmd_tx.send(Command::UnsubscribeBookticker(symbol.to_lowercase())).await?;
-        Ok(())
+    /// Потоки, подписанные на бирже по последнему LIST_SUBSCRIPTIONS
+    pub fn exchange_subscriptions(&self) -> Option<ExchangeSubscriptions> {
+        self.listed.lock().unwrap().clone()
+    }
+
+    /// Подписка от имени holder (повторная от того же holder ничего не меняет)
+    pub fn acquire(&self, holder: &str, symbol: &str, stream: StreamKind) -> anyhow::Result<()> {
+        self.acquire_with(holder, symbol, stream, None)
+    }
+
+    /// acquire с ответом биржи на SUBSCRIBE. Если подписка уже была, ждать нечего
+    pub async fn acquire_confirmed(&self, holder: &str, symbol: &str, stream: StreamKind) -> Result<(), CommandError> {
+        let (tx, rx) = oneshot::channel();
+        self.acquire_with(holder, symbol, stream, Some(tx))
+            .map_err(|_| CommandError::NoResponse("market WS task stopped"))?;
+        Self::confirmation(rx).await
     }
 
-    pub async fn unsubscribe_trades(&self, symbol: &str) -> anyhow::Result<()> {
-        self.cmd_tx.send(Command::UnsubscribeTrades(symbol.to_lowercase())).await?;
+    fn acquire_with(&self, holder: &str, symbol: &str, stream: StreamKind, reply: Option<Reply>) -> anyhow::Result<()> {
+        let symbol = symbol.to_lowercase();
+        let mut holders = self.holders.lock().unwrap();
+        let key = (symbol.clone(), stream);
+        if !holders.contains_key(&key) {
+            self.cmd_tx.send(Command::Subscribe(symbol, stream, reply))?;
+        } else if let Some(reply) = reply {
+            let _ = reply.send(Ok(()));
+        }
+        holders.entry(key).or_default().insert(holder.to_string());
         Ok(())
     }
+
+    /// Снимает подписку holder'а. false - он её не держал
+    pub fn release(&self, holder: &str, symbol: &str, stream: StreamKind) -> anyhow::Result<bool> {
+        self.release_with(holder, symbol, stream, None)
+    }
+
+    /// release с ответом биржи на UNSUBSCRIBE (если он вообще ушёл)
+    async fn release_confirmed(&self, holder: &str, symbol: &str, stream: StreamKind) -> Result<(), CommandError> {
+        let (tx, rx) = oneshot::channel();
+        match self.release_with(holder, symbol, stream, Some(tx)) {
+            Ok(true) => Self::confirmation(rx).await,
+            Ok(false) => Ok(()),
+            Err(_) => Err(CommandError::NoResponse("market WS task stopped")),
+        }
+    }
+
+    fn release_with(&self, holder: &str, symbol: &str, stream: StreamKind, reply: Option<Reply>) -> anyhow::Result<bool> {
+        let key = (symbol.to_lowercase(), stream);
+        let mut holders = self.holders.lock().unwrap();
+        let Some(set) = holders.get_mut(&key) else { return Ok(false) };
+        if !set.remove(holder) {
+            return Ok(false);
+        }
+        if set.is_empty() {
+            holders.remove(&key);
+            self.cmd_tx.send(Command::Unsubscribe(key.0, stream, reply))?;
+        } else if let Some(reply) = reply {
+            let _ = reply.send(Ok(()));
+        }
+        Ok(true)
+    }
+
+    async fn confirmation(rx: oneshot::Receiver<Result<(), CommandError>>) -> Result<(), CommandError> {
+        match tokio::time::timeout(COMMAND_TIMEOUT, rx).await {
+            Ok(Ok(result)) => result,
+            // Команда потерялась вместе с соединением
+            Ok(Err(_)) => Err(CommandError::NoResponse("market WS not connected")),
+            Err(_) => Err(CommandError::NoResponse("timed out")),
+        }
+    }
+
+    /// Все подписки holder'а (инстанс остановился)
+    pub fn release_all(&self, holder: &str) {
+        let held: Vec<_> = self.holders.lock().unwrap()
+            .iter()
+            .filter(|(_, set)| set.contains(holder))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for (symbol, stream) in held {
+            let _ = self.release(holder, &symbol, stream);
+        }
+    }
+
+    pub fn subscriptions(&self) -> Vec<StreamSubscription> {
+        self.holders.lock().unwrap()
+            .iter()
+            .map(|((symbol, stream), set)| StreamSubscription {
+                symbol: symbol.to_uppercase(),
+                stream: *stream,
+                holders: set.iter().cloned().collect(),
+            })
+            .collect()
+    }
+
+    pub async fn subscribe_bookticker(&self, symbol: &str) -> Result<(), CommandError> {
+        self.acquire_confirmed(API_HOLDER, symbol, StreamKind::BookTicker).await
+    }
+
+    pub async fn subscribe_trades(&self, symbol: &str) -> Result<(), CommandError> {
+        self.acquire_confirmed(API_HOLDER, symbol, StreamKind::Trade).await
+    }
+
+    pub async fn unsubscribe_bookticker(&self, symbol: &str) -> Result<(), CommandError> {
+        self.release_confirmed(API_HOLDER, symbol, StreamKind::BookTicker).await
+    }
+
+    pub async fn unsubscribe_trades(&self, symbol: &str) -> Result<(), CommandError> {
+        self.release_confirmed(API_HOLDER, symbol, StreamKind::Trade).await
+    }
 }
\ No newline at end of file
@@ -1,37 +1,201 @@
 use chrono::Utc;
 use futures_util::{SinkExt, StreamExt};
 use hmac::{Hmac, Mac};
-use ryu::Buffer;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use simd_json::serde as simd_serde;
 use tokio::{
     select,
-    sync::{broadcast, mpsc, oneshot},
-    time::{interval, sleep, timeout, Duration},
+    sync::{broadcast, mpsc, oneshot, watch},
+    time::{interval, sleep, timeout, Duration, Instant},
 };
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
 
 use std::{
-    collections::{BTreeMap, VecDeque},
+    collections::{BTreeMap, HashMap, VecDeque},
     sync::{
-        atomic::{AtomicBool, AtomicU64, Ordering},
-        Arc,
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, OnceLock, Weak,
     },
 };
 
-use dashmap::{DashMap, DashSet};
+use dashmap::DashMap;
 use sha2::Sha256;
 type HmacSha256 = Hmac<Sha256>;
 
 use std::sync::atomic::AtomicI64;
 
+use crate::affinity;
+use crate::audit;
+use crate::decimal;
+use crate::endpoints::{self, EndpointKind};
+use crate::keystore;
+use crate::latency::{self, Stage};
+use crate::net;
+use crate::rate_limit::{Cost, RateLimitConfig, RateLimiter};
+use crate::risk::ERR_KILL_SWITCH;
+use crate::strategies::order::risk_manager;
+use crate::user_data::key_id;
+
+const RECONNECT_MIN_BACKOFF: Duration = Duration::from_secs(2);
+const RECONNECT_MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// recvWindow подписанных запросов по умолчанию
+const DEFAULT_RECV_WINDOW_MS: u64 = 5000;
+
+/// Больше Binance не принимает
+const MAX_RECV_WINDOW_MS: u64 = 60_000;
+
+/// Запрос простоял в очереди (или backlog переподключения) дольше TTL
+/// и на биржу не ушёл
+pub const ERR_ORDER_EXPIRED: i32 = -9202;
+
+/// Соединение закрылось, ответ на запрос не получен (мог и исполниться)
+pub const ERR_DISCONNECTED: i32 = -9700;
+
+/// Не удалось собрать или подписать запрос
+pub const ERR_SIGN_FAILED: i32 = -9701;
+
+/// REST fallback не дошёл до биржи (сеть, TLS, таймаут)
+pub const ERR_REST_FAILED: i32 = -9702;
+
+/// Запрос ушёл в сокет, ответа нет дольше request_timeout_ms (мог и исполниться)
+pub const ERR_REQUEST_TIMEOUT: i32 = -9703;
+
+/// Trade WS останавливается: новый ордер не принят или не отправлен из очереди
+pub const ERR_SHUTTING_DOWN: i32 = -9704;
+
+/// Как часто искать запросы без ответа
+const TIMEOUT_SWEEP_EVERY: Duration = Duration::from_millis(250);
+
+// ─────────────────────────── Конфиг ───────────────────────────
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TradeWsConfig {
+    /// Размер пула trade WS соединений (ордера распределяются по кругу)
+    pub connections: usize,
+    pub isolation: TradeWsIsolation,
+    /// recvWindow подписанных запросов, ms (до 60000). Timestamp ставится
+    /// при записи в сокет, так что ожидание в очереди его не съедает
+    pub recv_window_ms: u64,
+    /// recvWindow отдельных ключей: alias из keystore или первые 8 символов api_key
+    pub recv_window_by_key: HashMap<String, u64>,
+    /// Сколько новый ордер может ждать отправки (очередь, переподключение).
+    /// Подпись свежая в любом случае, но рынок за это время ушёл. 0 - без срока
+    pub order_ttl_ms: u64,
+    /// То же для отмен, reduce-only и стопов: позднее снижение риска лучше,
+    /// чем никакого, по умолчанию без срока
+    pub exit_ttl_ms: u64,
+    /// Сколько ждать ответа на запрос, записанный в сокет. Потом callback
+    /// получает ERR_REQUEST_TIMEOUT, а запрос забывается. 0 - ждать вечно
+    pub request_timeout_ms: u64,
+    /// Сколько при остановке ждать ответов на отправленные запросы,
+    /// прежде чем закрыть сокеты
+    pub drain_timeout_ms: u64,
+}
+
+impl Default for TradeWsConfig {
+    fn default() -> Self {
+        Self {
+            connections: 2,
+            isolation: TradeWsIsolation::Shared,
+            recv_window_ms: DEFAULT_RECV_WINDOW_MS,
+            recv_window_by_key: HashMap::new(),
+            order_ttl_ms: DEFAULT_RECV_WINDOW_MS,
+            exit_ttl_ms: 0,
+            request_timeout_ms: 10_000,
+            drain_timeout_ms: 3_000,
+        }
+    }
+}
+
+/// Как аккаунты делят trade WS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeWsIsolation {
+    /// Один пул на все api_key
+    Shared,
+    /// Свой пул из connections соединений на каждый api_key (создаётся
+    /// при первом запросе ключа): обрыв или бан соединений одного
+    /// аккаунта не задерживает ордера других
+    PerKey,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RestFallbackConfig {
+    /// Отправлять ордера через REST, пока trade WS недоступен. Выключено
+    /// по умолчанию: после обрыва статус ордера, ушедшего в сокет,
+    /// неизвестен, и повтор по REST может выставить его второй раз. REST
+    /// несёт тот же newClientOrderId, что и WS, но Binance отклоняет
+    /// повтор id, только пока первый ордер открыт - уже исполненный
+    /// MARKET это не остановит
+    pub enabled: bool,
+    pub base_url: String,
+}
+
+impl Default for RestFallbackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_url: "https://fapi.binance.com".to_string(),
+        }
+    }
+}
+
 // ─────────────────────────── События ───────────────────────────
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub enum Event {
     Raw(Value),
 }
 
+// ─────────────────────── Подготовленный ордер ───────────────────────
+/// order.place без цены: параметры собираются и форматируются один раз
+/// (staged-шаблоны стратегий), при отправке добавляются цена,
+/// clientOrderId, apiKey, timestamp и подпись
+#[derive(Debug)]
+pub struct PreparedOrder {
+    pub symbol: String,
+    pub side: &'static str,
+    /// false - MARKET
+    pub limit: bool,
+    pub qty: f64,
+    params: BTreeMap<&'static str, String>,
+}
+
+impl PreparedOrder {
+    pub fn new(symbol: &str, side: &'static str, limit: bool, qty: f64) -> Self {
+        let symbol = symbol.to_uppercase();
+        let mut params: BTreeMap<&str, String> = BTreeMap::new();
+        params.insert("positionSide", "BOTH".to_string());
+        params.insert("quantity", decimal::qty(&symbol, qty));
+        params.insert("side", side.to_string());
+        params.insert("symbol", symbol.clone());
+        if limit {
+            params.insert("timeInForce", "GTC".to_string());
+            params.insert("type", "LIMIT".to_string());
+        } else {
+            params.insert("type", "MARKET".to_string());
+        }
+        Self { symbol, side, limit, qty, params }
+    }
+
+    /// Параметры с ценой и clientOrderId (без apiKey, timestamp и подписи)
+    fn params(&self, price: f64, client_order_id: Option<&str>) -> BTreeMap<&'static str, String> {
+        let mut p = self.params.clone();
+        if self.limit {
+            p.insert("price", decimal::price(&self.symbol, price));
+        }
+        if let Some(cid) = client_order_id {
+            p.insert("newClientOrderId", cid.to_string());
+        }
+        p
+    }
+}
+
 // ─────────────────────────── Команды ───────────────────────────
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone)]
 pub enum Command {
     SendLimitOrder {
@@ -41,6 +205,7 @@ pub enum Command {
         price: f64,
         qty: f64,
         side: String,
+        client_order_id: Option<String>,
     },
     SendMarketOrder {
         api_key: String,
@@ -48,6 +213,7 @@ pub enum Command {
         symbol: String,
         qty: f64,
         side: String,
+        client_order_id: Option<String>,
     },
     CancelLimitOrder {
         api_key: String,
@@ -55,16 +221,226 @@ pub enum Command {
         symbol: String,
         order_id: String,
     },
+    /// Reduce-only LIMIT GTC (тейк-профит bracket)
+    SendReduceOnlyLimit {
+        api_key: String,
+        secret_key: String,
+        symbol: String,
+        price: f64,
+        qty: f64,
+        side: String,
+        client_order_id: Option<String>,
+    },
+    /// Reduce-only MARKET (закрытие позиции по учёту ядра)
+    SendReduceOnlyMarket {
+        api_key: String,
+        secret_key: String,
+        symbol: String,
+        qty: f64,
+        side: String,
+        client_order_id: Option<String>,
+    },
+    /// Reduce-only STOP_MARKET (стоп-лосс bracket)
+    SendStopMarket {
+        api_key: String,
+        secret_key: String,
+        symbol: String,
+        stop_price: f64,
+        qty: f64,
+        side: String,
+        client_order_id: Option<String>,
+    },
+    /// LIMIT или MARKET из PreparedOrder
+    SendPreparedOrder {
+        api_key: String,
+        secret_key: String,
+        order: Arc<PreparedOrder>,
+        price: f64,
+        client_order_id: Option<String>,
+    },
+}
+
+impl Command {
+    fn api_key(&self) -> &str {
+        match self {
+            Command::SendLimitOrder { api_key, .. }
+            | Command::SendMarketOrder { api_key, .. }
+            | Command::CancelLimitOrder { api_key, .. }
+            | Command::SendReduceOnlyLimit { api_key, .. }
+            | Command::SendReduceOnlyMarket { api_key, .. }
+            | Command::SendStopMarket { api_key, .. }
+            | Command::SendPreparedOrder { api_key, .. } => api_key,
+        }
+    }
+
+    /// Метод WS API запроса
+    fn method(&self) -> &'static str {
+        match self {
+            Command::CancelLimitOrder { .. } => "order.cancel",
+            _ => "order.place",
+        }
+    }
+
+    /// Отмена и reduce-only/стоп: снижают риск, в очереди идут первыми
+    fn is_exit(&self) -> bool {
+        !matches!(
+            self,
+            Command::SendLimitOrder { .. } | Command::SendMarketOrder { .. } | Command::SendPreparedOrder { .. }
+        )
+    }
+
+    /// Метод и параметры для audit log (без секретов, apiKey сокращён)
+    fn audit_params(&self) -> (&'static str, Value) {
+        match self {
+            Command::SendLimitOrder { api_key, symbol, price, qty, side, client_order_id, .. } => ("order.place", json!({
+                "apiKey": key_id(api_key),
+                "symbol": symbol,
+                "side": side,
+                "type": "LIMIT",
+                "price": price,
+                "quantity": qty,
+                "newClientOrderId": client_order_id,
+            })),
+            Command::SendMarketOrder { api_key, symbol, qty, side, client_order_id, .. } => ("order.place", json!({
+                "apiKey": key_id(api_key),
+                "symbol": symbol,
+                "side": side,
+                "type": "MARKET",
+                "quantity": qty,
+                "newClientOrderId": client_order_id,
+            })),
+            Command::CancelLimitOrder { api_key, symbol, order_id, .. } => ("order.cancel", json!({
+                "apiKey": key_id(api_key),
+                "symbol": symbol,
+                "orderId": order_id,
+            })),
+            Command::SendReduceOnlyLimit { api_key, symbol, price, qty, side, client_order_id, .. } => ("order.place", json!({
+                "apiKey": key_id(api_key),
+                "symbol": symbol,
+                "side": side,
+                "type": "LIMIT",
+                "price": price,
+                "quantity": qty,
+                "reduceOnly": true,
+                "newClientOrderId": client_order_id,
+            })),
+            Command::SendReduceOnlyMarket { api_key, symbol, qty, side, client_order_id, .. } => ("order.place", json!({
+                "apiKey": key_id(api_key),
+                "symbol": symbol,
+                "side": side,
+                "type": "MARKET",
+                "quantity": qty,
+                "reduceOnly": true,
+                "newClientOrderId": client_order_id,
+            })),
+            Command::SendStopMarket { api_key, symbol, stop_price, qty, side, client_order_id, .. } => ("order.place", json!({
+                "apiKey": key_id(api_key),
+                "symbol": symbol,
+                "side": side,
+                "type": "STOP_MARKET",
+                "stopPrice": stop_price,
+                "quantity": qty,
+                "reduceOnly": true,
+                "newClientOrderId": client_order_id,
+            })),
+            Command::SendPreparedOrder { api_key, order, price, client_order_id, .. } => ("order.place", json!({
+                "apiKey": key_id(api_key),
+                "symbol": order.symbol,
+                "side": order.side,
+                "type": if order.limit { "LIMIT" } else { "MARKET" },
+                "price": order.limit.then_some(*price),
+                "quantity": order.qty,
+                "newClientOrderId": client_order_id,
+            })),
+        }
+    }
+
+    /// Вес запроса в WS API (order.place: 0 weight + 1 order, order.cancel: 1 weight)
+    fn cost(&self) -> Cost {
+        match self {
+            Command::SendLimitOrder { .. }
+            | Command::SendMarketOrder { .. }
+            | Command::SendReduceOnlyLimit { .. }
+            | Command::SendReduceOnlyMarket { .. }
+            | Command::SendStopMarket { .. }
+            | Command::SendPreparedOrder { .. } => Cost { weight: 0, orders: 1 },
+            Command::CancelLimitOrder { .. } => Cost { weight: 1, orders: 0 },
+        }
+    }
+}
+
+// ─────────────────────────── Трассировка ───────────────────────────
+/// Отметки времени одного запроса по стадиям (ns, 0 - стадия не пройдена).
+/// Заполняется задачей соединения и читателем сокета
+#[derive(Debug, Default)]
+pub struct WireTrace {
+    queued_at_ns: AtomicU64,
+    sign_ns: AtomicU64,
+    write_started_ns: AtomicU64,
+    written_at_ns: AtomicU64,
+    acked_at_ns: AtomicU64,
+}
+
+/// Длительности стадий в мс; None - стадии не было (например, ушёл через REST)
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct WireTimings {
+    /// send_command -> задача соединения взяла запрос
+    pub queue_ms: Option<f64>,
+    /// Сборка JSON и HMAC-подпись
+    pub sign_ms: Option<f64>,
+    /// Запись фрейма в сокет
+    pub ws_write_ms: Option<f64>,
+    /// Фрейм записан -> ответ биржи прочитан
+    pub ack_ms: Option<f64>,
+}
+
+impl WireTrace {
+    pub fn timings(&self) -> WireTimings {
+        let ms = |from: u64, to: u64| (from > 0 && to >= from).then(|| (to - from) as f64 / 1e6);
+        let queued = self.queued_at_ns.load(Ordering::Relaxed);
+        let write_started = self.write_started_ns.load(Ordering::Relaxed);
+        let written = self.written_at_ns.load(Ordering::Relaxed);
+        let sign_ns = self.sign_ns.load(Ordering::Relaxed);
+        WireTimings {
+            queue_ms: ms(queued, write_started.saturating_sub(sign_ns)),
+            sign_ms: (write_started > 0).then(|| sign_ns as f64 / 1e6),
+            ws_write_ms: ms(write_started, written),
+            ack_ms: ms(written, self.acked_at_ns.load(Ordering::Relaxed)),
+        }
+    }
 }
 
 // ─────────────────────────── Внутренние типы ───────────────────────────
 type Callback = Arc<dyn Fn(Value) + Send + Sync + 'static>;
-type SharedStr = Arc<String>;
+
+struct Pending {
+    callback: Callback,
+    api_key: Arc<str>,
+    /// Для audit log: от чьего имени ушёл запрос
+    instance_id: Option<String>,
+    /// Для переотправки через REST, если WS send не прошёл
+    cmd: Arc<Command>,
+    trace: Option<Arc<WireTrace>>,
+    /// Момент вызова send_command (для order_ack)
+    queued_at_ns: u64,
+}
 
 #[derive(Debug)]
 struct Outbound {
     id: String,
-    payload: SharedStr,
+    /// Подписывается задачей соединения прямо перед записью в сокет
+    cmd: Arc<Command>,
+    /// Момент вызова send_command (для latency)
+    queued_at_ns: u64,
+    trace: Option<Arc<WireTrace>>,
+    /// Выход/снижение риска: идёт в сокет раньше обычных запросов
+    priority: bool,
+}
+
+tokio::task_local! {
+    /// Приоритет запросов, поставленных в очередь внутри scope
+    /// (place_priority_order стратегии)
+    pub static PRIORITY: bool;
 }
 
 #[derive(Debug)]
@@ -72,57 +448,423 @@ enum Ctrl {
     Pong(Vec<u8>),
 }
 
+/// Одно соединение из пула trade WS
+struct Connection {
+    index: usize,
+    /// Для логов: "#0" или "AAAABBBB#0" у пула ключа
+    name: String,
+    connected: AtomicBool,
+    out_tx: mpsc::Sender<Outbound>,
+    /// Приоритетная очередь: сокет разбирает её первой
+    priority_tx: mpsc::Sender<Outbound>,
+    /// Запросов в backlog переподключения
+    backlog: AtomicUsize,
+    /// Ушли в этот сокет, ответ ещё не пришёл: id -> (метод, момент записи, ns)
+    inflight_ids: DashMap<String, (&'static str, u64)>,
+}
+
+/// Пул trade WS: общий или одного api_key. Перекидывать запросы при
+/// обрыве можно только внутри пула
+struct Pool {
+    connections: Vec<Arc<Connection>>,
+    next_conn: AtomicUsize,
+}
+
+impl Connection {
+    fn lane(&self, priority: bool) -> &mpsc::Sender<Outbound> {
+        if priority { &self.priority_tx } else { &self.out_tx }
+    }
+}
+
+impl Pool {
+    /// Следующее живое соединение по кругу (кроме exclude)
+    fn pick_connection(&self, exclude: Option<usize>) -> Option<&Arc<Connection>> {
+        let n = self.connections.len();
+        let start = self.next_conn.fetch_add(1, Ordering::Relaxed);
+        (0..n)
+            .map(|i| &self.connections[(start + i) % n])
+            .find(|c| Some(c.index) != exclude && c.connected.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionQueue {
+    pub name: String,
+    pub connected: bool,
+    /// Ждут записи в сокет
+    pub queued: usize,
+    pub priority_queued: usize,
+    /// Ждут переподключения
+    pub backlog: usize,
+    /// Ушли, ответа ещё нет
+    pub inflight: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeQueueStats {
+    pub connections: Vec<ConnectionQueue>,
+    pub order_ttl_ms: u64,
+    pub exit_ttl_ms: u64,
+    pub expired: u64,
+    pub replayed: u64,
+    /// Запросов, ждущих ответа (в очередях, backlog и в сокетах)
+    pub outstanding: usize,
+    pub request_timeout_ms: u64,
+    /// Закрыто по request_timeout_ms без ответа биржи
+    pub timed_out: u64,
+}
+
+/// Итог остановки trade WS
+#[derive(Debug, Clone, Serialize)]
+pub struct DrainReport {
+    pub reason: String,
+    /// Ждали ответа в момент остановки
+    pub outstanding: usize,
+    /// Новые ордера, снятые из очередей без отправки
+    pub cancelled: u64,
+    /// Так и не получили ответа: завершены ERR_SHUTTING_DOWN
+    pub unresolved: Vec<UnresolvedRequest>,
+    pub waited_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UnresolvedRequest {
+    pub id: String,
+    pub method: &'static str,
+    pub instance_id: Option<String>,
+}
+
+/// Результат синхронизации времени
+#[derive(Debug, Clone, Copy)]
+pub struct TimeSample {
+    pub offset_ms: i64,
+    pub rtt_ms: i64,
+}
+
+#[allow(dead_code)]
 pub struct ExchangeTrade {
     ws_url: String,
     // ← УБРАЛИ api_key и secret_key
 
-    is_connected: AtomicBool,
-
-    // Очереди
-    out_tx: mpsc::Sender<Outbound>,
-    ctrl_tx: mpsc::Sender<Ctrl>,
+    /// Для запуска пулов per_key из &self
+    me: Weak<Self>,
+    ws: TradeWsConfig,
+    cpu_core: Option<usize>,
+    /// Пул соединений (round-robin) при isolation = shared
+    shared: OnceLock<Arc<Pool>>,
+    /// api_key -> пул при isolation = per_key
+    key_pools: DashMap<String, Arc<Pool>>,
+    /// Выброшено по TTL / отправлено из backlog после переподключения
+    expired: AtomicU64,
+    replayed: AtomicU64,
+    timed_out: AtomicU64,
+    /// Остановка: новые ордера не принимаются, сокеты закрываются
+    draining: AtomicBool,
+    /// Снято из очередей при остановке или kill switch
+    held_back: AtomicU64,
+    close_tx: watch::Sender<bool>,
 
     pub event_tx: broadcast::Sender<Event>,
 
-    pending: DashMap<String, Callback>,
-    inflight_ids: DashSet<String>,
+    pending: DashMap<String, Pending>,
     id_counter: AtomicU64,
     
     time_offset_ms: AtomicI64,
+
+    rate_limiter: Arc<RateLimiter>,
+
+    /// base_url REST API, если fallback включён
+    rest_url: Option<String>,
+    http: reqwest::Client,
 }
 
 impl ExchangeTrade {
     // ═══════════════════════════════════════════════════════════
     // ИЗМЕНЕНИЕ: new() БЕЗ api_key и secret_key
     // ═══════════════════════════════════════════════════════════
-    pub fn new(ws_url: String) -> Arc<Self> {
-        let (out_tx, out_rx) = mpsc::channel::<Outbound>(8192);
-        let (ctrl_tx, ctrl_rx) = mpsc::channel::<Ctrl>(256);
+    pub fn new(
+        ws_url: String,
+        ws: TradeWsConfig,
+        rate_limits: RateLimitConfig,
+        rest_fallback: RestFallbackConfig,
+        cpu_core: Option<usize>,
+    ) -> Arc<Self> {
         let (event_tx, _) = broadcast::channel::<Event>(2048);
+        let isolation = ws.isolation;
+        let mut ws = ws;
+        ws.recv_window_ms = Self::checked_recv_window("default", ws.recv_window_ms);
+        for (name, ms) in ws.recv_window_by_key.iter_mut() {
+            *ms = Self::checked_recv_window(name, *ms);
+        }
 
-        let mgr = Arc::new(Self {
-            ws_url: ws_url.clone(),
+        let mgr = Arc::new_cyclic(|me| Self {
+            ws_url,
             // ← УБРАЛИ
-            is_connected: AtomicBool::new(false),
-            out_tx,
-            ctrl_tx,
+            me: me.clone(),
+            ws,
+            cpu_core,
+            shared: OnceLock::new(),
+            key_pools: DashMap::new(),
+            expired: AtomicU64::new(0),
+            replayed: AtomicU64::new(0),
+            timed_out: AtomicU64::new(0),
+            draining: AtomicBool::new(false),
+            held_back: AtomicU64::new(0),
+            close_tx: watch::channel(false).0,
             event_tx,
             pending: DashMap::new(),
-            inflight_ids: DashSet::new(),
             id_counter: AtomicU64::new(0),
             time_offset_ms: AtomicI64::new(0),
+            rate_limiter: Arc::new(RateLimiter::new(rate_limits)),
+            rest_url: rest_fallback.enabled.then_some(rest_fallback.base_url),
+            http: reqwest::Client::new(),
         });
 
-        {
-            let mgr_clone = mgr.clone();
+        if mgr.ws.request_timeout_ms > 0 {
+            let me = Arc::downgrade(&mgr);
             tokio::spawn(async move {
-                mgr_clone.run_socket(ws_url, out_rx, ctrl_rx).await;
+                let mut tick = interval(TIMEOUT_SWEEP_EVERY);
+                loop {
+                    tick.tick().await;
+                    let Some(mgr) = me.upgrade() else { break };
+                    mgr.sweep_timeouts();
+                }
             });
         }
 
+        if isolation == TradeWsIsolation::PerKey {
+            tracing::info!("🔒 Trade WS isolated per API key");
+            return mgr;
+        }
+        let _ = mgr.shared.set(mgr.start_pool(None));
         mgr
     }
 
+    fn checked_recv_window(name: &str, ms: u64) -> u64 {
+        let checked = ms.clamp(1, MAX_RECV_WINDOW_MS);
+        if checked != ms {
+            tracing::warn!("⚠️ recvWindow {}ms for '{}' out of range, using {}ms", ms, name, checked);
+        }
+        checked
+    }
+
+    /// recvWindow ключа: recv_window_by_key по alias или key_id, иначе общий
+    fn recv_window(&self, api_key: &str) -> u64 {
+        if self.ws.recv_window_by_key.is_empty() {
+            return self.ws.recv_window_ms;
+        }
+        let id = key_id(api_key);
+        self.ws.recv_window_by_key
+            .iter()
+            .find(|(name, _)| **name == id || keystore::account(name).is_some_and(|c| c.api_key == api_key))
+            .map(|(_, ms)| *ms)
+            .unwrap_or(self.ws.recv_window_ms)
+    }
+
+    /// Создаёт пул и запускает его соединения. api_key = None - общий пул
+    fn start_pool(&self, api_key: Option<&str>) -> Arc<Pool> {
+        let prefix = api_key.map(key_id).unwrap_or_default();
+        let mut connections = Vec::new();
+        let mut receivers = Vec::new();
+        for index in 0..self.ws.connections.max(1) {
+            let (out_tx, out_rx) = mpsc::channel::<Outbound>(8192);
+            let (priority_tx, priority_rx) = mpsc::channel::<Outbound>(1024);
+            connections.push(Arc::new(Connection {
+                index,
+                name: format!("{}#{}", prefix, index),
+                connected: AtomicBool::new(false),
+                out_tx,
+                priority_tx,
+                backlog: AtomicUsize::new(0),
+                inflight_ids: DashMap::new(),
+            }));
+            receivers.push((out_rx, priority_rx));
+        }
+        let pool = Arc::new(Pool { connections, next_conn: AtomicUsize::new(0) });
+
+        let Some(mgr) = self.me.upgrade() else { return pool };
+        for (conn, (out_rx, priority_rx)) in pool.connections.iter().cloned().zip(receivers) {
+            let mgr = mgr.clone();
+            let pool = pool.clone();
+            let ws_url = self.ws_url.clone();
+            let name = match api_key {
+                Some(_) => format!("trade-ws-{}-{}", prefix, conn.index),
+                None => format!("trade-ws-{}", conn.index),
+            };
+            affinity::spawn_pinned(&name, self.cpu_core, async move {
+                mgr.run_socket(pool, conn, ws_url, out_rx, priority_rx).await;
+            });
+        }
+        pool
+    }
+
+    /// Глубина очередей trade WS по соединениям
+    fn pools(&self) -> Vec<Arc<Pool>> {
+        let mut pools: Vec<Arc<Pool>> = self.shared.get().cloned().into_iter().collect();
+        pools.extend(self.key_pools.iter().map(|e| e.value().clone()));
+        pools
+    }
+
+    pub fn queue_stats(&self) -> TradeQueueStats {
+        let connections = self.pools().iter()
+            .flat_map(|pool| pool.connections.iter())
+            .map(|c| ConnectionQueue {
+                name: c.name.clone(),
+                connected: c.connected.load(Ordering::Relaxed),
+                queued: c.out_tx.max_capacity() - c.out_tx.capacity(),
+                priority_queued: c.priority_tx.max_capacity() - c.priority_tx.capacity(),
+                backlog: c.backlog.load(Ordering::Relaxed),
+                inflight: c.inflight_ids.len(),
+            })
+            .collect();
+
+        TradeQueueStats {
+            connections,
+            order_ttl_ms: self.ws.order_ttl_ms,
+            exit_ttl_ms: self.ws.exit_ttl_ms,
+            expired: self.expired.load(Ordering::Relaxed),
+            replayed: self.replayed.load(Ordering::Relaxed),
+            outstanding: self.pending.len(),
+            request_timeout_ms: self.ws.request_timeout_ms,
+            timed_out: self.timed_out.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Запросы, на которые биржа не ответила за request_timeout_ms после
+    /// записи в сокет: без этого callback висел бы в pending вечно, а
+    /// стратегия так и не узнала бы исход. Поздний ответ уйдёт в event_tx
+    fn sweep_timeouts(&self) {
+        let timeout_ns = self.ws.request_timeout_ms * 1_000_000;
+        let now_ns = latency::now_ns();
+
+        for pool in self.pools() {
+            for conn in &pool.connections {
+                let stale: Vec<String> = conn.inflight_ids
+                    .iter()
+                    .filter(|e| now_ns.saturating_sub(e.value().1) > timeout_ns)
+                    .map(|e| e.key().clone())
+                    .collect();
+
+                for id in stale {
+                    conn.inflight_ids.remove(&id);
+                    let msg = format!("No response after {}ms", self.ws.request_timeout_ms);
+                    if self.fail_pending(&id, ERR_REQUEST_TIMEOUT, msg) {
+                        self.timed_out.fetch_add(1, Ordering::Relaxed);
+                        tracing::warn!(
+                            "⌛ No response for {} on {} after {}ms, giving up",
+                            id, conn.name, self.ws.request_timeout_ms
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Завершает ожидающий запрос ошибкой ядра. false - ответ уже пришёл
+    fn fail_pending(&self, id: &str, code: i32, msg: String) -> bool {
+        let Some((id, p)) = self.pending.remove(id) else { return false };
+        Self::fail(id, p, code, msg);
+        true
+    }
+
+    /// Ответ с ошибкой запросу, уже снятому из pending
+    fn fail(id: String, p: Pending, code: i32, msg: String) {
+        let v = json!({
+            "id": id,
+            "error": { "code": code, "msg": msg }
+        });
+        audit::response(&id, p.instance_id.clone(), &v);
+        tokio::spawn(async move {
+            (p.callback)(v);
+        });
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // ОСТАНОВКА
+    // ═══════════════════════════════════════════════════════════
+
+    /// Почему запрос нельзя отправлять сейчас. Снижение риска (отмены,
+    /// reduce-only, стопы) проходит всегда: после kill switch ими
+    /// закрывают позиции, а при остановке им лучше уйти, чем нет
+    fn hold_back(&self, cmd: &Command) -> Option<(i32, &'static str)> {
+        if cmd.is_exit() {
+            return None;
+        }
+        if self.draining.load(Ordering::Relaxed) {
+            return Some((ERR_SHUTTING_DOWN, "Trade WS is shutting down"));
+        }
+        if risk_manager().is_some_and(|risk| risk.kill_switch_active()) {
+            return Some((ERR_KILL_SWITCH, "Kill switch is active"));
+        }
+        None
+    }
+
+    /// Останавливает trade WS: новые ордера отклоняются, ещё не ушедшие
+    /// из очередей снимаются, снижение риска дописывается. Ответов на уже
+    /// отправленное ждём до drain_timeout_ms, остальное завершаем
+    /// ERR_SHUTTING_DOWN и закрываем сокеты кадром Close
+    pub async fn drain(&self, reason: &str) -> DrainReport {
+        let started = Instant::now();
+        let held_back = self.held_back.load(Ordering::Relaxed);
+        self.draining.store(true, Ordering::SeqCst);
+
+        let outstanding = self.pending.len();
+        tracing::warn!("🛑 Trade WS draining ({}): {} requests outstanding", reason, outstanding);
+
+        let deadline = started + Duration::from_millis(self.ws.drain_timeout_ms);
+        while !self.pending.is_empty() && Instant::now() < deadline {
+            sleep(Duration::from_millis(20)).await;
+        }
+
+        let unresolved: Vec<UnresolvedRequest> = self.pending
+            .iter()
+            .map(|e| UnresolvedRequest {
+                id: e.key().clone(),
+                method: e.cmd.method(),
+                instance_id: e.instance_id.clone(),
+            })
+            .collect();
+        for r in &unresolved {
+            self.fail_pending(&r.id, ERR_SHUTTING_DOWN, "Trade WS closed before response".to_string());
+            tracing::warn!(
+                "🛑 {} {} [{}] unresolved at shutdown, check order state on exchange",
+                r.method, r.id, r.instance_id.as_deref().unwrap_or("-")
+            );
+        }
+
+        let _ = self.close_tx.send(true);
+
+        let report = DrainReport {
+            reason: reason.to_string(),
+            outstanding,
+            cancelled: self.held_back.load(Ordering::Relaxed) - held_back,
+            unresolved,
+            waited_ms: started.elapsed().as_millis() as u64,
+        };
+        tracing::info!(
+            "🛑 Trade WS drained in {}ms: {} cancelled in queue, {} unresolved",
+            report.waited_ms, report.cancelled, report.unresolved.len()
+        );
+        report
+    }
+
+    /// Пул для запроса ключа: общий или свой (запускается при первом запросе)
+    fn pool_for(&self, api_key: &str) -> Arc<Pool> {
+        if let Some(shared) = self.shared.get() {
+            return shared.clone();
+        }
+        if let Some(pool) = self.key_pools.get(api_key) {
+            return pool.clone();
+        }
+        self.key_pools
+            .entry(api_key.to_string())
+            .or_insert_with(|| {
+                tracing::info!("🔒 Trade WS pool for '{}' started", key_id(api_key));
+                self.start_pool(Some(api_key))
+            })
+            .clone()
+    }
+
     fn next_id(&self) -> String {
         let n = self.id_counter.fetch_add(1, Ordering::Relaxed) + 1;
         format!("req-{n}")
@@ -130,23 +872,42 @@ impl ExchangeTrade {
 
     async fn run_socket(
         self: Arc<Self>,
+        pool: Arc<Pool>,
+        conn: Arc<Connection>,
         ws_url: String,
         mut out_rx: mpsc::Receiver<Outbound>,
-        mut ctrl_rx: mpsc::Receiver<Ctrl>,
+        mut priority_rx: mpsc::Receiver<Outbound>,
     ) {
+        let (ctrl_tx, mut ctrl_rx) = mpsc::channel::<Ctrl>(256);
+        let mut close_rx = self.close_tx.subscribe();
         let mut backlog: VecDeque<Outbound> = VecDeque::new();
+        let mut backoff = RECONNECT_MIN_BACKOFF;
 
         loop {
-            tracing::info!("Trying to connect trade WS: {}", ws_url);
-            match connect_async(&ws_url).await {
+            if *close_rx.borrow() {
+                tracing::info!("Trade WS {} closed", conn.name);
+                return;
+            }
+
+            // Во время бана не переподключаемся - каждая попытка продлевает бан
+            if let Some(left) = self.rate_limiter.cooloff_remaining() {
+                tracing::warn!("⏳ Trade WS in cooloff, reconnecting in {}ms", left.as_millis());
+                sleep(left).await;
+            }
+
+            let ws_url = endpoints::select(EndpointKind::Trade, &ws_url);
+            tracing::info!("Trying to connect trade WS {}: {}", conn.name, ws_url);
+            match net::connect(&ws_url).await {
                 Ok((ws, _resp)) => {
-                    tracing::info!("Connected to {}", ws_url);
-                    self.is_connected.store(true, Ordering::Relaxed);
+                    tracing::info!("Connected trade WS {} to {}", conn.name, ws_url);
+                    backoff = RECONNECT_MIN_BACKOFF;
+                    conn.connected.store(true, Ordering::Relaxed);
                     let (mut write, mut read) = ws.split();
 
                     let (done_tx, mut done_rx) = oneshot::channel::<()>();
                     let reader_mgr = self.clone();
-                    let reader_ctrl_tx = self.ctrl_tx.clone();
+                    let reader_conn = conn.clone();
+                    let reader_ctrl_tx = ctrl_tx.clone();
 
                     tokio::spawn(async move {
                         loop {
@@ -154,7 +915,7 @@ impl ExchangeTrade {
                                 Ok(Some(Ok(msg))) => {
                                     match msg {
                                         Message::Text(txt) => {
-                                            reader_mgr.handle_text(txt).await;
+                                            reader_mgr.handle_text(&reader_conn, txt).await;
                                         }
                                         Message::Binary(_) => {}
                                         Message::Ping(data) => {
@@ -186,13 +947,22 @@ impl ExchangeTrade {
                     let mut connected = true;
 
                     while let Some(ob) = backlog.pop_front() {
-                        match write.send(Message::Text((*ob.payload).clone())).await {
+                        conn.backlog.store(backlog.len(), Ordering::Relaxed);
+                        let Some(payload) = self.sign_traced(&ob) else { continue };
+                        let sent = write.send(Message::Text(payload)).await;
+                        Self::trace_written(&ob);
+                        match sent {
                             Ok(_) => {
-                                self.inflight_ids.insert(ob.id.clone());
+                                latency::record_since(Stage::OrderToWire, ob.queued_at_ns);
+                                conn.inflight_ids.insert(ob.id.clone(), (ob.cmd.method(), latency::now_ns()));
+                                self.replayed.fetch_add(1, Ordering::Relaxed);
                             }
                             Err(e) => {
                                 tracing::error!("WS send(backlog) error: {}", e);
-                                backlog.push_front(ob);
+                                if let Some(ob) = self.reroute(&pool, &conn, ob) {
+                                    backlog.push_front(ob);
+                                }
+                                conn.backlog.store(backlog.len(), Ordering::Relaxed);
                                 connected = false;
                                 break;
                             }
@@ -201,6 +971,11 @@ impl ExchangeTrade {
 
                     while connected {
                         select! {
+                            _ = close_rx.changed() => {
+                                let _ = write.send(Message::Close(None)).await;
+                                connected = false;
+                            }
+
                             _ = &mut done_rx => {
                                 tracing::warn!("Reader finished");
                                 connected = false;
@@ -222,16 +997,23 @@ impl ExchangeTrade {
                                 }
                             }
 
-                            msg = out_rx.recv() => {
+                            msg = Self::next_outbound(&mut priority_rx, &mut out_rx) => {
                                 match msg {
                                     Some(ob) => {
-                                        match write.send(Message::Text((*ob.payload).clone())).await {
+                                        let Some(payload) = self.sign_traced(&ob) else { continue };
+                                        let sent = write.send(Message::Text(payload)).await;
+                                        Self::trace_written(&ob);
+                                        match sent {
                                             Ok(_) => {
-                                                self.inflight_ids.insert(ob.id.clone());
+                                                latency::record_since(Stage::OrderToWire, ob.queued_at_ns);
+                                                conn.inflight_ids.insert(ob.id.clone(), (ob.cmd.method(), latency::now_ns()));
                                             }
                                             Err(e) => {
                                                 tracing::error!("WS send error: {}", e);
-                                                backlog.push_front(ob);
+                                                if let Some(ob) = self.reroute(&pool, &conn, ob) {
+                                                    backlog.push_front(ob);
+                                                    conn.backlog.store(backlog.len(), Ordering::Relaxed);
+                                                }
                                                 connected = false;
                                             }
                                         }
@@ -245,39 +1027,207 @@ impl ExchangeTrade {
                         }
                     }
 
-                    self.is_connected.store(false, Ordering::Relaxed);
-                    self.fail_inflight_on_disconnect().await;
+                    conn.connected.store(false, Ordering::Relaxed);
+
+                    // Всё, что успело встать в очередь этого сокета, уводим на живые
+                    while let Ok(ob) = priority_rx.try_recv().or_else(|_| out_rx.try_recv()) {
+                        if let Some(ob) = self.reroute(&pool, &conn, ob) {
+                            Self::push_backlog(&mut backlog, ob);
+                        }
+                    }
+                    self.sweep_backlog(&conn, &mut backlog);
+                    self.fail_over_inflight(&pool, &conn).await;
 
                     tracing::info!("Reconnecting in 2s...");
                     sleep(Duration::from_secs(2)).await;
                 }
                 Err(e) => {
-                    self.is_connected.store(false, Ordering::Relaxed);
-                    tracing::error!("WS connect error: {:?}", e);
-                    sleep(Duration::from_secs(2)).await;
+                    conn.connected.store(false, Ordering::Relaxed);
+                    // Без REST fallback запросы ждут здесь: просроченные не держим
+                    while let Ok(ob) = priority_rx.try_recv().or_else(|_| out_rx.try_recv()) {
+                        Self::push_backlog(&mut backlog, ob);
+                    }
+                    self.sweep_backlog(&conn, &mut backlog);
+                    self.check_connect_ban(&e);
+                    tracing::error!("WS connect error: {:?}, retry in {}s", e, backoff.as_secs());
+                    sleep(backoff).await;
+                    backoff = (backoff * 2).min(RECONNECT_MAX_BACKOFF);
+                }
+            }
+        }
+    }
+
+    /// Следующий запрос в сокет: приоритетная очередь всегда первой
+    async fn next_outbound(
+        priority_rx: &mut mpsc::Receiver<Outbound>,
+        out_rx: &mut mpsc::Receiver<Outbound>,
+    ) -> Option<Outbound> {
+        select! {
+            biased;
+            Some(ob) = priority_rx.recv() => Some(ob),
+            msg = out_rx.recv() => msg,
+        }
+    }
+
+    /// Приоритетные запросы встают в backlog перед обычными, сохраняя порядок
+    fn push_backlog(backlog: &mut VecDeque<Outbound>, ob: Outbound) {
+        let at = match ob.priority {
+            true => backlog.iter().position(|o| !o.priority).unwrap_or(backlog.len()),
+            false => backlog.len(),
+        };
+        backlog.insert(at, ob);
+    }
+
+    /// Выбрасывает из backlog запросы старше TTL
+    fn sweep_backlog(&self, conn: &Connection, backlog: &mut VecDeque<Outbound>) {
+        backlog.retain(|ob| !self.expire(ob));
+        conn.backlog.store(backlog.len(), Ordering::Relaxed);
+    }
+
+    /// sign() с замером для трассируемых запросов
+    fn sign_traced(&self, ob: &Outbound) -> Option<String> {
+        let Some(trace) = &ob.trace else { return self.sign(ob) };
+        let started = latency::now_ns();
+        let payload = self.sign(ob);
+        let now = latency::now_ns();
+        trace.sign_ns.store(now - started, Ordering::Relaxed);
+        trace.write_started_ns.store(now, Ordering::Relaxed);
+        payload
+    }
+
+    fn trace_written(ob: &Outbound) {
+        if let Some(trace) = &ob.trace {
+            trace.written_at_ns.store(latency::now_ns(), Ordering::Relaxed);
+        }
+    }
+
+    /// Подписывает запрос. Вызывается задачей соединения прямо перед записью
+    /// в сокет: send_command только ставит команду в очередь, а timestamp
+    /// свежий и для запросов, дождавшихся переподключения в backlog.
+    fn sign(&self, ob: &Outbound) -> Option<String> {
+        if self.expire(ob) {
+            return None;
+        }
+        if let Some((code, msg)) = self.hold_back(&ob.cmd) {
+            self.held_back.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!("🛑 {} held back: {}", ob.id, msg);
+            self.fail_pending(&ob.id, code, msg.to_string());
+            return None;
+        }
+        let Some(payload) = self.build_message_for_cmd(&ob.cmd, &ob.id) else {
+            tracing::error!("Build message failed for id={}", ob.id);
+            if let Some((id, p)) = self.pending.remove(&ob.id) {
+                let v = json!({
+                    "id": id,
+                    "error": { "code": ERR_SIGN_FAILED, "msg": "Failed to build request" }
+                });
+                audit::response(&id, p.instance_id.clone(), &v);
+                tokio::spawn(async move {
+                    (p.callback)(v);
+                });
+            }
+            return None;
+        };
+
+        if audit::enabled() {
+            let instance_id = self.pending.get(&ob.id).and_then(|p| p.instance_id.clone());
+            audit::request(&ob.id, instance_id, &payload);
+        }
+        Some(payload)
+    }
+
+    /// Запрос старше TTL завершается ERR_ORDER_EXPIRED вместо отправки
+    fn expire(&self, ob: &Outbound) -> bool {
+        let ttl_ms = if ob.cmd.is_exit() { self.ws.exit_ttl_ms } else { self.ws.order_ttl_ms };
+        let age_ms = latency::now_ns().saturating_sub(ob.queued_at_ns) / 1_000_000;
+        if ttl_ms == 0 || age_ms <= ttl_ms {
+            return false;
+        }
+
+        self.expired.fetch_add(1, Ordering::Relaxed);
+        tracing::warn!("⌛ {} expired after {}ms in queue (ttl {}ms), not sent", ob.id, age_ms, ttl_ms);
+        self.fail_pending(&ob.id, ERR_ORDER_EXPIRED, format!("Expired after {}ms in queue", age_ms));
+        true
+    }
+
+    /// Запрос, не ушедший в сокет from: на другое живое соединение пула,
+    /// иначе через REST. Some - перенаправить некуда, запрос ждёт
+    /// переподключения в backlog
+    fn reroute(&self, pool: &Pool, from: &Connection, ob: Outbound) -> Option<Outbound> {
+        let ob = match pool.pick_connection(Some(from.index)) {
+            Some(conn) => {
+                let id = ob.id.clone();
+                match conn.lane(ob.priority).try_send(ob) {
+                    Ok(()) => {
+                        tracing::warn!("↪️ {} moved from trade WS {} to {}", id, from.name, conn.name);
+                        return None;
+                    }
+                    Err(e) => e.into_inner(),
                 }
             }
+            None => ob,
+        };
+
+        if self.rest_url.is_some() {
+            if let Some((id, p)) = self.pending.remove(&ob.id) {
+                self.send_rest(id, p);
+            }
+            return None;
         }
+        Some(ob)
     }
 
-    async fn fail_inflight_on_disconnect(&self) {
-        let ids: Vec<String> = self.inflight_ids.iter().map(|id| id.clone()).collect();
+    /// HTTP 418/429 на handshake - бан соединений, уходим в cooloff
+    fn check_connect_ban(&self, e: &WsError) {
+        let WsError::Http(resp) = e else { return };
+
+        // Retry-After в секундах
+        let retry_after = resp.headers()
+            .get("Retry-After")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(Duration::from_secs);
+
+        match resp.status().as_u16() {
+            418 => self.rate_limiter.enter_cooloff(true, retry_after, "connection ban (418)"),
+            429 => self.rate_limiter.enter_cooloff(false, retry_after, "connection rate limit (429)"),
+            _ => {}
+        }
+    }
+
+    /// Ответы на запросы, ушедшие в упавший сокет, уже не придут.
+    /// Отмену безопасно повторить через другое соединение; ордер - нет
+    /// (мог исполниться), его завершаем ошибкой, статус придёт из user data.
+    async fn fail_over_inflight(&self, pool: &Pool, conn: &Connection) {
+        let ids: Vec<String> = conn.inflight_ids.iter().map(|e| e.key().clone()).collect();
         for id in ids {
-            self.inflight_ids.remove(&id);
-            if let Some((_k, cb)) = self.pending.remove(&id) {
-                let id_cl = id.clone();
+            conn.inflight_ids.remove(&id);
+
+            let cancel = self.pending
+                .get(&id)
+                .map(|p| p.cmd.clone())
+                .filter(|cmd| matches!(**cmd, Command::CancelLimitOrder { .. }));
+            if let Some(cmd) = cancel {
+                let ob = Outbound { id: id.clone(), cmd, queued_at_ns: latency::now_ns(), trace: None, priority: true };
+                if self.reroute(pool, conn, ob).is_none() {
+                    continue;
+                }
+            }
+
+            if let Some((_k, p)) = self.pending.remove(&id) {
+                let v = json!({
+                    "id": id,
+                    "error": { "code": ERR_DISCONNECTED, "msg": "Connection closed" }
+                });
+                audit::response(&id, p.instance_id.clone(), &v);
                 tokio::spawn(async move {
-                    let v = json!({
-                        "id": id_cl,
-                        "error": { "code": "Disconnected", "message": "Connection closed" }
-                    });
-                    (cb)(v);
+                    (p.callback)(v);
                 });
             }
         }
     }
 
-    async fn handle_text(&self, txt: String) {
+    async fn handle_text(&self, conn: &Connection, txt: String) {
         let mut bytes = txt.into_bytes();
         let parsed = simd_serde::from_slice::<Value>(&mut bytes)
             .or_else(|_| serde_json::from_slice::<Value>(&bytes));
@@ -291,12 +1241,20 @@ impl ExchangeTrade {
         };
 
         if let Some(id) = Self::extract_id(&v) {
-            if self.inflight_ids.remove(&id).is_some() {
+            if let Some((_, (method, written_at_ns))) = conn.inflight_ids.remove(&id) {
+                latency::record_trade(method, latency::now_ns().saturating_sub(written_at_ns));
                 tracing::trace!("Ack for id={}", id);
             }
-            if let Some((_k, cb)) = self.pending.remove(&id) {
+            if let Some((_k, p)) = self.pending.remove(&id) {
+                let acked_at_ns = latency::now_ns();
+                latency::record(Stage::OrderAck, acked_at_ns.saturating_sub(p.queued_at_ns));
+                if let Some(trace) = &p.trace {
+                    trace.acked_at_ns.store(acked_at_ns, Ordering::Relaxed);
+                }
+                self.rate_limiter.on_response(&p.api_key, &v);
+                audit::response(&id, p.instance_id.clone(), &v);
                 tokio::spawn(async move {
-                    (cb)(v);
+                    (p.callback)(v);
                 });
                 return;
             }
@@ -325,105 +1283,42 @@ impl ExchangeTrade {
         let adjusted_time_ms = local_time_ms + offset;
         
         let ts = adjusted_time_ms.to_string();
+        let recv_window = self.recv_window(cmd.api_key()).to_string();
 
         match cmd {
-            Command::SendLimitOrder { api_key, secret_key, symbol, price, qty, side } => {
-                let mut buf_price = Buffer::new();
-                let mut buf_qty = Buffer::new();
-                let price_str = buf_price.format(*price);
-                let qty_str = buf_qty.format(*qty);
-
+            Command::SendLimitOrder { api_key, secret_key, symbol, price, qty, side, client_order_id } => {
                 let mut p: BTreeMap<&str, String> = BTreeMap::new();
-                p.insert("apiKey", api_key.clone());
+                if let Some(cid) = client_order_id {
+                    p.insert("newClientOrderId", cid.clone());
+                }
                 p.insert("positionSide", "BOTH".to_string());
-                p.insert("price", price_str.to_string());
-                p.insert("quantity", qty_str.to_string());
-                p.insert("recvWindow", "5000".to_string());
+                p.insert("price", decimal::price(symbol, *price));
+                p.insert("quantity", decimal::qty(symbol, *qty));
                 p.insert("side", side.to_uppercase());
                 p.insert("symbol", symbol.to_uppercase());
                 p.insert("timeInForce", "GTC".to_string());
-                p.insert("timestamp", ts.clone());
                 p.insert("type", "LIMIT".to_string());
-
-                let query = p.iter()
-                    .map(|(k, v)| format!("{k}={v}"))
-                    .collect::<Vec<_>>()
-                    .join("&");
-
-                let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes()).ok()?;
-                mac.update(query.as_bytes());
-                let signature = hex::encode(mac.finalize().into_bytes());
-
-                let mut params_json = serde_json::Map::new();
-                params_json.insert("apiKey".into(), Value::String(api_key.clone()));
-                params_json.insert("positionSide".into(), Value::String("BOTH".into()));
-                params_json.insert("price".into(), Value::Number(serde_json::Number::from_f64(*price)?));
-                params_json.insert("quantity".into(), Value::Number(serde_json::Number::from_f64(*qty)?));
-                params_json.insert("recvWindow".into(), Value::String("5000".into()));
-                params_json.insert("side".into(), Value::String(side.to_uppercase()));
-                params_json.insert("symbol".into(), Value::String(symbol.to_uppercase()));
-                params_json.insert("timeInForce".into(), Value::String("GTC".into()));
-                params_json.insert("timestamp".into(), Value::String(ts));
-                params_json.insert("type".into(), Value::String("LIMIT".into()));
-                params_json.insert("signature".into(), Value::String(signature));
-
-                let msg_json = json!({
-                    "id": id,
-                    "method": "order.place",
-                    "params": params_json
-                });
-
-                Some(msg_json.to_string())
+                Self::signed_place_message(id, api_key, secret_key, p, ts, recv_window)
             }
 
-            Command::SendMarketOrder { api_key, secret_key, symbol, qty, side } => {
-                let mut buf_qty = Buffer::new();
-                let qty_str = buf_qty.format(*qty);
-
+            Command::SendMarketOrder { api_key, secret_key, symbol, qty, side, client_order_id } => {
                 let mut p: BTreeMap<&str, String> = BTreeMap::new();
-                p.insert("apiKey", api_key.clone());
+                if let Some(cid) = client_order_id {
+                    p.insert("newClientOrderId", cid.clone());
+                }
                 p.insert("positionSide", "BOTH".to_string());
-                p.insert("quantity", qty_str.to_string());
-                p.insert("recvWindow", "5000".to_string());
+                p.insert("quantity", decimal::qty(symbol, *qty));
                 p.insert("side", side.to_uppercase());
                 p.insert("symbol", symbol.to_uppercase());
-                p.insert("timestamp", ts.clone());
                 p.insert("type", "MARKET".to_string());
-
-                let query = p.iter()
-                    .map(|(k, v)| format!("{k}={v}"))
-                    .collect::<Vec<_>>()
-                    .join("&");
-
-                let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes()).ok()?;
-                mac.update(query.as_bytes());
-                let signature = hex::encode(mac.finalize().into_bytes());
-
-                let mut params_json = serde_json::Map::new();
-                params_json.insert("apiKey".into(), Value::String(api_key.clone()));
-                params_json.insert("positionSide".into(), Value::String("BOTH".into()));
-                params_json.insert("quantity".into(), Value::Number(serde_json::Number::from_f64(*qty)?));
-                params_json.insert("recvWindow".into(), Value::String("5000".into()));
-                params_json.insert("side".into(), Value::String(side.to_uppercase()));
-                params_json.insert("symbol".into(), Value::String(symbol.to_uppercase()));
-                params_json.insert("timestamp".into(), Value::String(ts));
-                params_json.insert("type".into(), Value::String("MARKET".into()));
-                params_json.insert("signature".into(), Value::String(signature));
-
-                let msg_json = json!({
-                    "id": id,
-                    "method": "order.place",
-                    "params": params_json
-                });
-
-                Some(msg_json.to_string())
+                Self::signed_place_message(id, api_key, secret_key, p, ts, recv_window)
             }
 
             Command::CancelLimitOrder { api_key, secret_key, symbol, order_id } => {
                 let mut p: BTreeMap<&str, String> = BTreeMap::new();
                 p.insert("apiKey", api_key.clone());
                 p.insert("orderId", order_id.clone());
-                p.insert("recvWindow", "5000".to_string());
+                p.insert("recvWindow", recv_window);
                 p.insert("symbol", symbol.to_uppercase());
                 p.insert("timestamp", ts);
 
@@ -449,28 +1344,300 @@ impl ExchangeTrade {
                 });
                 Some(msg_json.to_string())
             }
+
+            Command::SendReduceOnlyLimit { api_key, secret_key, symbol, price, qty, side, client_order_id } => {
+                let mut p = Self::protective_params(symbol, side, *qty, client_order_id.as_deref());
+                p.insert("price", decimal::price(symbol, *price));
+                p.insert("timeInForce", "GTC".to_string());
+                p.insert("type", "LIMIT".to_string());
+                Self::signed_place_message(id, api_key, secret_key, p, ts, recv_window)
+            }
+
+            Command::SendReduceOnlyMarket { api_key, secret_key, symbol, qty, side, client_order_id } => {
+                let mut p = Self::protective_params(symbol, side, *qty, client_order_id.as_deref());
+                p.insert("type", "MARKET".to_string());
+                Self::signed_place_message(id, api_key, secret_key, p, ts, recv_window)
+            }
+
+            Command::SendStopMarket { api_key, secret_key, symbol, stop_price, qty, side, client_order_id } => {
+                let mut p = Self::protective_params(symbol, side, *qty, client_order_id.as_deref());
+                p.insert("stopPrice", decimal::price(symbol, *stop_price));
+                p.insert("type", "STOP_MARKET".to_string());
+                Self::signed_place_message(id, api_key, secret_key, p, ts, recv_window)
+            }
+
+            Command::SendPreparedOrder { api_key, secret_key, order, price, client_order_id } => {
+                let p = order.params(*price, client_order_id.as_deref());
+                Self::signed_place_message(id, api_key, secret_key, p, ts, recv_window)
+            }
         }
     }
 
+    /// Общие параметры reduce-only ордеров (без apiKey и timestamp)
+    fn protective_params(symbol: &str, side: &str, qty: f64, client_order_id: Option<&str>) -> BTreeMap<&'static str, String> {
+        let mut p: BTreeMap<&str, String> = BTreeMap::new();
+        if let Some(cid) = client_order_id {
+            p.insert("newClientOrderId", cid.to_string());
+        }
+        p.insert("positionSide", "BOTH".to_string());
+        p.insert("quantity", decimal::qty(symbol, qty));
+        p.insert("reduceOnly", "true".to_string());
+        p.insert("side", side.to_uppercase());
+        p.insert("symbol", symbol.to_uppercase());
+        p
+    }
+
+    /// order.place со строковыми параметрами: подпись по отсортированному query.
+    /// В JSON уходят ровно те же строки, что и в подпись, иначе Binance
+    /// пересобирает query по-своему и отвечает -1022
+    fn signed_place_message(
+        id: &str,
+        api_key: &str,
+        secret_key: &str,
+        mut p: BTreeMap<&str, String>,
+        ts: String,
+        recv_window: String,
+    ) -> Option<String> {
+        p.insert("apiKey", api_key.to_string());
+        p.insert("recvWindow", recv_window);
+        p.insert("timestamp", ts);
+
+        let query = p.iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes()).ok()?;
+        mac.update(query.as_bytes());
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        let mut params_json = serde_json::Map::new();
+        for (k, v) in p.into_iter() {
+            params_json.insert(k.to_string(), Value::String(v));
+        }
+        params_json.insert("signature".to_string(), Value::String(signature));
+
+        let msg_json = json!({
+            "id": id,
+            "method": "order.place",
+            "params": Value::Object(params_json)
+        });
+        Some(msg_json.to_string())
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // REST FALLBACK
+    // ═══════════════════════════════════════════════════════════
+
+    /// Подписанный REST-запрос: метод, путь и query со signature
+    fn build_rest_request(&self, cmd: &Command) -> Option<(reqwest::Method, &'static str, String)> {
+        let ts = (Utc::now().timestamp_millis() + self.time_offset_ms.load(Ordering::Relaxed)).to_string();
+
+        // В REST apiKey уходит заголовком X-MBX-APIKEY и в подпись не входит
+        let mut p: BTreeMap<&str, String> = BTreeMap::new();
+        let (method, secret_key) = match cmd {
+            Command::SendLimitOrder { secret_key, symbol, price, qty, side, client_order_id, .. } => {
+                if let Some(cid) = client_order_id {
+                    p.insert("newClientOrderId", cid.clone());
+                }
+                p.insert("positionSide", "BOTH".to_string());
+                p.insert("price", decimal::price(symbol, *price));
+                p.insert("quantity", decimal::qty(symbol, *qty));
+                p.insert("side", side.to_uppercase());
+                p.insert("symbol", symbol.to_uppercase());
+                p.insert("timeInForce", "GTC".to_string());
+                p.insert("type", "LIMIT".to_string());
+                (reqwest::Method::POST, secret_key)
+            }
+            Command::SendMarketOrder { secret_key, symbol, qty, side, client_order_id, .. } => {
+                if let Some(cid) = client_order_id {
+                    p.insert("newClientOrderId", cid.clone());
+                }
+                p.insert("positionSide", "BOTH".to_string());
+                p.insert("quantity", decimal::qty(symbol, *qty));
+                p.insert("side", side.to_uppercase());
+                p.insert("symbol", symbol.to_uppercase());
+                p.insert("type", "MARKET".to_string());
+                (reqwest::Method::POST, secret_key)
+            }
+            Command::CancelLimitOrder { secret_key, symbol, order_id, .. } => {
+                p.insert("orderId", order_id.clone());
+                p.insert("symbol", symbol.to_uppercase());
+                (reqwest::Method::DELETE, secret_key)
+            }
+            Command::SendReduceOnlyLimit { secret_key, symbol, price, qty, side, client_order_id, .. } => {
+                p = Self::protective_params(symbol, side, *qty, client_order_id.as_deref());
+                p.insert("price", decimal::price(symbol, *price));
+                p.insert("timeInForce", "GTC".to_string());
+                p.insert("type", "LIMIT".to_string());
+                (reqwest::Method::POST, secret_key)
+            }
+            Command::SendReduceOnlyMarket { secret_key, symbol, qty, side, client_order_id, .. } => {
+                p = Self::protective_params(symbol, side, *qty, client_order_id.as_deref());
+                p.insert("type", "MARKET".to_string());
+                (reqwest::Method::POST, secret_key)
+            }
+            Command::SendStopMarket { secret_key, symbol, stop_price, qty, side, client_order_id, .. } => {
+                p = Self::protective_params(symbol, side, *qty, client_order_id.as_deref());
+                p.insert("stopPrice", decimal::price(symbol, *stop_price));
+                p.insert("type", "STOP_MARKET".to_string());
+                (reqwest::Method::POST, secret_key)
+            }
+            Command::SendPreparedOrder { secret_key, order, price, client_order_id, .. } => {
+                p = order.params(*price, client_order_id.as_deref());
+                (reqwest::Method::POST, secret_key)
+            }
+        };
+        p.insert("recvWindow", self.recv_window(cmd.api_key()).to_string());
+        p.insert("timestamp", ts);
+
+        let query = p.iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes()).ok()?;
+        mac.update(query.as_bytes());
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        Some((method, "/fapi/v1/order", format!("{query}&signature={signature}")))
+    }
+
+    /// Отправляет запрос через POST/DELETE /fapi/v1/order.
+    /// Ответ приводится к формату WS API, callback вызывается так же, как для WS.
+    fn send_rest(&self, id: String, p: Pending) {
+        // Запрос уже снят из pending: без ответа здесь callback не вызовется никогда
+        let Some(base_url) = self.rest_url.as_deref() else {
+            Self::fail(id, p, ERR_REST_FAILED, "REST fallback is disabled".to_string());
+            return;
+        };
+        let Some((method, path, query)) = self.build_rest_request(&p.cmd) else {
+            tracing::error!("Build REST request failed for id={}", id);
+            Self::fail(id, p, ERR_SIGN_FAILED, "Failed to build request".to_string());
+            return;
+        };
+
+        tracing::warn!("↪️ Trade WS unavailable, sending {} via REST", id);
+
+        let request = self.http
+            .request(method, format!("{base_url}{path}?{query}"))
+            .header("X-MBX-APIKEY", p.cmd.api_key())
+            .timeout(Duration::from_secs(5));
+        let rate_limiter = self.rate_limiter.clone();
+
+        tokio::spawn(async move {
+            let v = match request.send().await {
+                Ok(resp) => {
+                    let status = resp.status().as_u16();
+                    let body: Value = resp.json().await.unwrap_or(Value::Null);
+                    if status == 200 {
+                        json!({ "id": id, "status": status, "result": body })
+                    } else {
+                        // Тело ошибки REST: {"code": -2019, "msg": "..."}
+                        json!({ "id": id, "status": status, "error": body })
+                    }
+                }
+                // URL с подписью в ответ не отдаём
+                Err(e) => json!({
+                    "id": id,
+                    "error": { "code": ERR_REST_FAILED, "msg": e.without_url().to_string() }
+                }),
+            };
+
+            latency::record_since(Stage::OrderAck, p.queued_at_ns);
+            rate_limiter.on_response(&p.api_key, &v);
+            audit::response(&id, p.instance_id.clone(), &v);
+            (p.callback)(v);
+        });
+    }
+
     // ═══════════════════════════════════════════════════════════
     // PUBLIC API
     // ═══════════════════════════════════════════════════════════
 
     pub async fn send_command<F>(&self, cmd: Command, callback: F)
+    where
+        F: Fn(Value) + Send + Sync + 'static,
+    {
+        self.enqueue(cmd, None, callback).await;
+    }
+
+    /// send_command с поэтапными замерами (подпись, запись в сокет, ответ биржи)
+    pub async fn send_command_traced<F>(&self, cmd: Command, trace: Arc<WireTrace>, callback: F)
+    where
+        F: Fn(Value) + Send + Sync + 'static,
+    {
+        self.enqueue(cmd, Some(trace), callback).await;
+    }
+
+    async fn enqueue<F>(&self, cmd: Command, trace: Option<Arc<WireTrace>>, callback: F)
     where
         F: Fn(Value) + Send + Sync + 'static,
     {
         let id = self.next_id();
-        let Some(payload_str) = self.build_message_for_cmd(&cmd, &id) else {
-            tracing::error!("Build message failed for id={}", id);
+        let queued_at_ns = latency::now_ns();
+        if let Some(trace) = &trace {
+            trace.queued_at_ns.store(queued_at_ns, Ordering::Relaxed);
+        }
+
+        if let Some((code, msg)) = self.hold_back(&cmd) {
+            let (method, params) = cmd.audit_params();
+            audit::rejected(Some(&id), audit::instance(), method, params, code, msg);
+            let v = json!({
+                "id": id,
+                "error": { "code": code, "msg": msg }
+            });
+            tokio::spawn(async move {
+                callback(v);
+            });
             return;
+        }
+
+        // Бюджет резервируем до подписи: при ожидании в очереди timestamp не устареет
+        if let Err(reject) = self.rate_limiter.acquire(cmd.api_key(), cmd.cost()).await {
+            tracing::warn!("⏳ Request {} dropped by rate limiter: {}", id, reject.message);
+            let (method, params) = cmd.audit_params();
+            audit::rejected(Some(&id), audit::instance(), method, params, reject.code, &reject.message);
+            let v = json!({
+                "id": id,
+                "error": { "code": reject.code, "msg": reject.message }
+            });
+            tokio::spawn(async move {
+                callback(v);
+            });
+            return;
+        }
+
+        // Подпись и JSON - в задаче соединения, здесь только постановка в очередь
+        let cmd = Arc::new(cmd);
+        let pending = Pending {
+            callback: Arc::new(callback),
+            api_key: Arc::from(cmd.api_key()),
+            instance_id: audit::instance(),
+            cmd: cmd.clone(),
+            trace: trace.clone(),
+            queued_at_ns,
         };
 
-        let payload: SharedStr = Arc::new(payload_str);
+        let pool = self.pool_for(cmd.api_key());
+        let conn = match pool.pick_connection(None) {
+            Some(conn) => conn,
+            // Все сокеты переподключаются - не ждём, отправляем через REST
+            None if self.rest_url.is_some() => {
+                self.send_rest(id, pending);
+                return;
+            }
+            // Иначе ждём переподключения в очереди любого соединения
+            None => {
+                let n = pool.next_conn.fetch_add(1, Ordering::Relaxed);
+                &pool.connections[n % pool.connections.len()]
+            }
+        };
 
-        self.pending.insert(id.clone(), Arc::new(callback));
+        self.pending.insert(id.clone(), pending);
 
-        if let Err(e) = self.out_tx.send(Outbound { id, payload }).await {
+        let priority = cmd.is_exit() || PRIORITY.try_with(|p| *p).unwrap_or(false);
+        if let Err(e) = conn.lane(priority).send(Outbound { id, cmd, queued_at_ns, trace, priority }).await {
             tracing::error!("Outbound channel send error: {}", e);
         }
     }
@@ -478,6 +1645,7 @@ impl ExchangeTrade {
     // ═══════════════════════════════════════════════════════════
     // ИЗМЕНЕНИЕ: send_limit_order принимает api_key и secret_key
     // ═══════════════════════════════════════════════════════════
+    #[allow(clippy::too_many_arguments)]
     pub async fn send_limit_order<F>(
         &self,
         api_key: &str,
@@ -486,6 +1654,7 @@ impl ExchangeTrade {
         price: f64,
         size: f64,
         side: &str,
+        client_order_id: Option<&str>,
         callback: F,
     ) where
         F: Fn(Value) + Send + Sync + 'static,
@@ -498,12 +1667,14 @@ impl ExchangeTrade {
                 price,
                 qty: size,
                 side: side.to_string(),
+                client_order_id: client_order_id.map(str::to_string),
             },
             callback,
         )
         .await;
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn send_market_order<F>(
         &self,
         api_key: &str,
@@ -511,6 +1682,7 @@ impl ExchangeTrade {
         symbol: &str,
         size: f64,
         side: &str,
+        client_order_id: Option<&str>,
         callback: F,
     ) where
         F: Fn(Value) + Send + Sync + 'static,
@@ -522,6 +1694,120 @@ impl ExchangeTrade {
                 symbol: symbol.to_string(),
                 qty: size,
                 side: side.to_string(),
+                client_order_id: client_order_id.map(str::to_string),
+            },
+            callback,
+        )
+        .await;
+    }
+
+    /// Ордер из PreparedOrder: к готовым параметрам добавляются цена и clientOrderId
+    pub async fn send_prepared_order<F>(
+        &self,
+        api_key: &str,
+        secret_key: &str,
+        order: Arc<PreparedOrder>,
+        price: f64,
+        client_order_id: Option<&str>,
+        callback: F,
+    ) where
+        F: Fn(Value) + Send + Sync + 'static,
+    {
+        self.send_command(
+            Command::SendPreparedOrder {
+                api_key: api_key.to_string(),
+                secret_key: secret_key.to_string(),
+                order,
+                price,
+                client_order_id: client_order_id.map(str::to_string),
+            },
+            callback,
+        )
+        .await;
+    }
+
+    /// Reduce-only LIMIT: закрывает позицию, но не открывает новую
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_reduce_only_limit<F>(
+        &self,
+        api_key: &str,
+        secret_key: &str,
+        symbol: &str,
+        price: f64,
+        size: f64,
+        side: &str,
+        client_order_id: Option<&str>,
+        callback: F,
+    ) where
+        F: Fn(Value) + Send + Sync + 'static,
+    {
+        self.send_command(
+            Command::SendReduceOnlyLimit {
+                api_key: api_key.to_string(),
+                secret_key: secret_key.to_string(),
+                symbol: symbol.to_string(),
+                price,
+                qty: size,
+                side: side.to_string(),
+                client_order_id: client_order_id.map(str::to_string),
+            },
+            callback,
+        )
+        .await;
+    }
+
+    /// Reduce-only MARKET: закрывает позицию целиком или частично
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_reduce_only_market<F>(
+        &self,
+        api_key: &str,
+        secret_key: &str,
+        symbol: &str,
+        size: f64,
+        side: &str,
+        client_order_id: Option<&str>,
+        callback: F,
+    ) where
+        F: Fn(Value) + Send + Sync + 'static,
+    {
+        self.send_command(
+            Command::SendReduceOnlyMarket {
+                api_key: api_key.to_string(),
+                secret_key: secret_key.to_string(),
+                symbol: symbol.to_string(),
+                qty: size,
+                side: side.to_string(),
+                client_order_id: client_order_id.map(str::to_string),
+            },
+            callback,
+        )
+        .await;
+    }
+
+    /// Reduce-only STOP_MARKET: market по достижении stop_price (цена последней сделки)
+    #[allow(clippy::too_many_arguments)]
+    pub async fn send_stop_market<F>(
+        &self,
+        api_key: &str,
+        secret_key: &str,
+        symbol: &str,
+        stop_price: f64,
+        size: f64,
+        side: &str,
+        client_order_id: Option<&str>,
+        callback: F,
+    ) where
+        F: Fn(Value) + Send + Sync + 'static,
+    {
+        self.send_command(
+            Command::SendStopMarket {
+                api_key: api_key.to_string(),
+                secret_key: secret_key.to_string(),
+                symbol: symbol.to_string(),
+                stop_price,
+                qty: size,
+                side: side.to_string(),
+                client_order_id: client_order_id.map(str::to_string),
             },
             callback,
         )
@@ -557,23 +1843,53 @@ impl ExchangeTrade {
     /// Синхронизирует локальное время с сервером Binance
     /// 
     /// Алгоритм:
-    /// 1. Запрашивает GET https://fapi.binance.com/fapi/v1/time
-    /// 2. Получает {"serverTime": 1700000000000}
-    /// 3. Вычисляет offset = server_time - local_time
+    /// 1. samples раз запрашивает GET https://fapi.binance.com/fapi/v1/time
+    /// 2. Для каждого ответа {"serverTime": 1700000000000} вычисляет
+    ///    offset = server_time - local_time (с поправкой на половину RTT)
+    /// 3. Берёт медиану по offset - один медленный ответ не сбивает результат
     /// 4. Сохраняет offset в AtomicI64
     /// 
     /// Пример:
     /// - Local time:  1700000001500 (ваше время)
     /// - Server time: 1700000000000 (Binance время)
     /// - Offset:      -1500ms       (на столько уменьшаем timestamp в запросах)
-    pub async fn sync_time(&self) -> anyhow::Result<i64> {
-        tracing::info!("⏰ Syncing time with Binance server...");
+    pub async fn sync_time(&self, samples: usize) -> anyhow::Result<TimeSample> {
+        tracing::debug!("⏰ Syncing time with Binance server...");
+        
+        let client = reqwest::Client::new();
+        let mut measured = Vec::with_capacity(samples.max(1));
+        
+        for _ in 0..samples.max(1) {
+            match Self::sample_time(&client).await {
+                Ok(sample) => measured.push(sample),
+                // Первый замер не прошёл - биржа недоступна, не ждём остальные таймауты
+                Err(e) if measured.is_empty() => return Err(e),
+                Err(e) => tracing::debug!("⏰ Time sample failed: {}", e),
+            }
+        }
+        
+        measured.sort_by_key(|s| s.offset_ms);
+        let median = measured[measured.len() / 2];
+        
+        // Сохраняем в AtomicI64 (thread-safe)
+        self.time_offset_ms.store(median.offset_ms, Ordering::Relaxed);
+        
+        tracing::debug!(
+            "⏰ Time synchronized | offset={}ms latency={}ms samples={}",
+            median.offset_ms,
+            median.rtt_ms,
+            measured.len()
+        );
         
+        Ok(median)
+    }
+    
+    /// Один замер offset
+    async fn sample_time(client: &reqwest::Client) -> anyhow::Result<TimeSample> {
         // Запоминаем время ДО запроса (для учёта network latency)
         let t0 = Utc::now().timestamp_millis();
         
         // Выполняем HTTP GET запрос
-        let client = reqwest::Client::new();
         let resp = client
             .get("https://fapi.binance.com/fapi/v1/time")
             .timeout(std::time::Duration::from_secs(5))
@@ -607,20 +1923,10 @@ impl ExchangeTrade {
         let local_time_when_server_responded = t0 + network_delay / 2;
         
         // Offset = сколько нужно ДОБАВИТЬ к локальному времени чтобы получить серверное
-        let offset = server_time - local_time_when_server_responded;
-        
-        // Сохраняем в AtomicI64 (thread-safe)
-        self.time_offset_ms.store(offset, Ordering::Relaxed);
-        
-        tracing::info!(
-            "✅ Time synchronized | local={} server={} offset={}ms latency={}ms",
-            local_time_when_server_responded,
-            server_time,
-            offset,
-            network_delay
-        );
-        
-        Ok(offset)
+        Ok(TimeSample {
+            offset_ms: server_time - local_time_when_server_responded,
+            rtt_ms: network_delay,
+        })
     }
     
     /// Установить offset вручную (для тестирования)
@@ -629,8 +1935,98 @@ impl ExchangeTrade {
         tracing::info!("⏰ Time offset manually set to {}ms", offset_ms);
     }
     
+    pub fn rate_limiter(&self) -> Arc<RateLimiter> {
+        self.rate_limiter.clone()
+    }
+
     /// Получить текущий offset
     pub fn get_time_offset(&self) -> i64 {
         self.time_offset_ms.load(Ordering::Relaxed)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "test-secret";
+
+    /// Собирает order.place и пересчитывает подпись по тому, что лежит в JSON
+    fn place(price: f64, qty: f64) -> serde_json::Map<String, Value> {
+        let mut p: BTreeMap<&str, String> = BTreeMap::new();
+        p.insert("positionSide", "BOTH".to_string());
+        p.insert("price", decimal::format(price));
+        p.insert("quantity", decimal::format(qty));
+        p.insert("side", "BUY".to_string());
+        p.insert("symbol", "1000PEPEUSDT".to_string());
+        p.insert("timeInForce", "GTC".to_string());
+        p.insert("type", "LIMIT".to_string());
+
+        let msg = ExchangeTrade::signed_place_message("1", "key", SECRET, p, "1700000000000".into(), "5000".into())
+            .expect("message");
+        let msg: Value = serde_json::from_str(&msg).unwrap();
+        msg["params"].as_object().unwrap().clone()
+    }
+
+    fn resign(params: &serde_json::Map<String, Value>) -> String {
+        let signed: BTreeMap<&str, &str> = params.iter()
+            .filter(|(k, _)| k.as_str() != "signature")
+            .map(|(k, v)| (k.as_str(), v.as_str().expect("string param")))
+            .collect();
+        let query = signed.iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let mut mac = HmacSha256::new_from_slice(SECRET.as_bytes()).unwrap();
+        mac.update(query.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    #[test]
+    fn json_carries_signed_strings() {
+        let cases = [
+            (1e-7, 1e7),
+            (0.0000123, 12_345_678.0),
+            (0.1 + 0.2, 0.001),
+            (65000.5, 0.1 * 3.0),
+            (0.0000011 * 3.0, 1e21),
+        ];
+
+        for (price, qty) in cases {
+            let params = place(price, qty);
+            assert_eq!(params["price"], Value::String(decimal::format(price)));
+            assert_eq!(params["quantity"], Value::String(decimal::format(qty)));
+            assert_eq!(params["signature"].as_str().unwrap(), resign(&params), "price {price} qty {qty}");
+        }
+    }
+
+    #[test]
+    fn no_exponent_or_noise_in_params() {
+        let params = place(1e-7, 0.1 + 0.2);
+        assert_eq!(params["price"], "0.0000001");
+        assert_eq!(params["quantity"], "0.3");
+
+        for (k, v) in &params {
+            let v = v.as_str().unwrap_or_else(|| panic!("{k} is not a string"));
+            if k != "signature" && k != "apiKey" {
+                assert!(!v.contains("e-") && !v.contains("e+"), "{k}={v}");
+            }
+        }
+    }
+
+    #[test]
+    fn protective_params_match_signature() {
+        let mut p = ExchangeTrade::protective_params("1000pepeusdt", "sell", 0.1 + 0.7, Some("cid-1"));
+        p.insert("stopPrice", decimal::format(0.0000089));
+        p.insert("type", "STOP_MARKET".to_string());
+
+        let msg = ExchangeTrade::signed_place_message("2", "key", SECRET, p, "1700000000000".into(), "5000".into())
+            .unwrap();
+        let msg: Value = serde_json::from_str(&msg).unwrap();
+        let params = msg["params"].as_object().unwrap();
+
+        assert_eq!(params["quantity"], "0.8");
+        assert_eq!(params["stopPrice"], "0.0000089");
+        assert_eq!(params["signature"].as_str().unwrap(), resign(params));
+    }
+}
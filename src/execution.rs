@@ -0,0 +1,716 @@
+// src/execution.rs
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::{oneshot, Notify};
+
+use crate::ffi_types::CAlgoOrder;
+use crate::keystore::{self, Credentials};
+use crate::orders::OrderManager;
+use crate::paper;
+use crate::strategies::order::{current_instance, route_cancel, route_order, OrderResult};
+use crate::strategies::{logs, replay};
+
+/// Некорректные параметры алгоритма
+pub const ERR_ALGO_INVALID: i32 = -9600;
+/// Превышен execution.max_active
+pub const ERR_ALGO_LIMIT: i32 = -9601;
+/// Инстанс на replay: алгоритмы работают по часам ядра
+pub const ERR_ALGO_UNSUPPORTED: i32 = -9602;
+
+const ORDER_LIMIT: u8 = 0;
+const ORDER_MARKET: u8 = 1;
+
+/// Ответа на дочерний ордер нет дольше - алгоритм останавливается
+const CHILD_TIMEOUT: Duration = Duration::from_secs(10);
+
+const QTY_EPSILON: f64 = 1e-9;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ExecutionConfig {
+    /// Одновременно работающих алгоритмов
+    pub max_active: usize,
+    /// Частей TWAP
+    pub max_slices: u32,
+    /// Как часто iceberg проверяет исполнение текущего клипа, ms
+    pub iceberg_poll_ms: u64,
+    /// Завершённых алгоритмов в памяти (для GET /api/execute)
+    pub keep_finished: usize,
+}
+
+impl Default for ExecutionConfig {
+    fn default() -> Self {
+        Self {
+            max_active: 32,
+            max_slices: 1000,
+            iceberg_poll_ms: 100,
+            keep_finished: 200,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ТИПЫ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "algo", rename_all = "lowercase")]
+pub enum AlgoKind {
+    /// Равные части через равные интервалы, первая - сразу
+    Twap { duration_secs: u64, slices: u32 },
+    /// Лимитные клипы по clip_qty: следующий - после полного исполнения предыдущего
+    Iceberg { clip_qty: f64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlgoParams {
+    pub symbol: String,
+    pub side: String,
+    pub quantity: f64,
+    /// TWAP: нет - части MARKET, есть - LIMIT по этой цене. Iceberg: обязательна
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+    /// Шаг количества символа: части округляются вниз, остаток уходит в последнюю
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step: Option<f64>,
+    #[serde(flatten)]
+    pub kind: AlgoKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlgoState {
+    Running,
+    /// Все части отправлены (лимитные части TWAP могут ещё стоять в книге)
+    Done,
+    Canceled,
+    /// Отказ или таймаут дочернего ордера, подробности в error
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChildOrder {
+    /// -1 - ответа биржи ещё нет
+    pub order_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_order_id: Option<String>,
+    pub order_type: &'static str,
+    pub price: f64,
+    pub quantity: f64,
+    pub filled_qty: f64,
+    pub open: bool,
+    /// Unix ms
+    pub time: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AlgoInfo {
+    pub id: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance_id: Option<String>,
+    #[serde(flatten)]
+    pub params: AlgoParams,
+    pub state: AlgoState,
+    /// Отправлено дочерними ордерами
+    pub placed_qty: f64,
+    pub filled_qty: f64,
+    pub children: Vec<ChildOrder>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct AlgoReject {
+    pub code: i32,
+    pub message: String,
+}
+
+impl AlgoReject {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// РЕЕСТР
+// ═══════════════════════════════════════════════════════════
+
+// Родительский ордер живёт в ядре: задача tokio режет его на дочерние
+// и отправляет их обычным путём ордера (paper, dry run, breaker, risk,
+// order manager) от имени инстанса. Стратегия видит дочерние ордера
+// в своих ORDER_UPDATE, как если бы ставила их сама.
+
+struct Algo {
+    info: Mutex<AlgoInfo>,
+    creds: Arc<Credentials>,
+    canceled: AtomicBool,
+    wake: Notify,
+}
+
+static ALGOS: LazyLock<DashMap<u64, Arc<Algo>>> = LazyLock::new(DashMap::new);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+static CONFIG: OnceLock<ExecutionConfig> = OnceLock::new();
+
+static ORDER_MANAGER: OnceLock<Arc<OrderManager>> = OnceLock::new();
+
+pub fn init(config: ExecutionConfig, orders: Arc<OrderManager>) {
+    CONFIG.set(config).ok();
+    ORDER_MANAGER.set(orders).ok();
+}
+
+fn config() -> ExecutionConfig {
+    CONFIG.get().cloned().unwrap_or_default()
+}
+
+/// Проверяет параметры и запускает алгоритм
+pub fn start(
+    creds: Arc<Credentials>,
+    instance_id: Option<String>,
+    mut params: AlgoParams,
+) -> Result<AlgoInfo, AlgoReject> {
+    let config = config();
+    validate(&mut params, &config).map_err(|e| AlgoReject::new(ERR_ALGO_INVALID, e))?;
+
+    if instance_id.as_deref().and_then(replay::clock).is_some() {
+        return Err(AlgoReject::new(ERR_ALGO_UNSUPPORTED, "Algo orders are not available on replay"));
+    }
+    let active = ALGOS.iter().filter(|a| a.info.lock().unwrap().state == AlgoState::Running).count();
+    if active >= config.max_active {
+        return Err(AlgoReject::new(ERR_ALGO_LIMIT, format!("Too many active algos (max {})", config.max_active)));
+    }
+
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let now = now_ms();
+    let info = AlgoInfo {
+        id,
+        instance_id,
+        params,
+        state: AlgoState::Running,
+        placed_qty: 0.0,
+        filled_qty: 0.0,
+        children: Vec::new(),
+        error: None,
+        created_at: now,
+        updated_at: now,
+    };
+    let algo = Arc::new(Algo {
+        info: Mutex::new(info.clone()),
+        creds,
+        canceled: AtomicBool::new(false),
+        wake: Notify::new(),
+    });
+    ALGOS.insert(id, algo.clone());
+    prune(config.keep_finished);
+
+    let message = format!("Algo #{} started: {}", id, describe(&info.params));
+    tracing::info!("🧩 {} [{}]", message, info.instance_id.as_deref().unwrap_or("api"));
+    if let Some(instance_id) = info.instance_id.as_deref() {
+        logs::push(instance_id, logs::LOG_INFO, &message);
+    }
+
+    tokio::spawn(run(algo));
+    Ok(info)
+}
+
+/// None - алгоритма нет, Some(false) - он уже завершён
+pub fn cancel(id: u64) -> Option<bool> {
+    let algo = ALGOS.get(&id)?.clone();
+    if algo.info.lock().unwrap().state != AlgoState::Running {
+        return Some(false);
+    }
+    algo.canceled.store(true, Ordering::Relaxed);
+    algo.wake.notify_one();
+    Some(true)
+}
+
+/// Останавливает алгоритмы инстанса (при его остановке)
+pub fn cancel_instance(instance_id: &str) {
+    let ids: Vec<u64> = ALGOS
+        .iter()
+        .filter(|a| {
+            let info = a.info.lock().unwrap();
+            info.state == AlgoState::Running && info.instance_id.as_deref() == Some(instance_id)
+        })
+        .map(|a| *a.key())
+        .collect();
+    for id in &ids {
+        cancel(*id);
+    }
+    if !ids.is_empty() {
+        tracing::info!("🧩 Canceling {} algos of '{}'", ids.len(), instance_id);
+    }
+}
+
+pub fn get(id: u64) -> Option<AlgoInfo> {
+    let algo = ALGOS.get(&id)?.clone();
+    refresh(&algo);
+    let info = algo.info.lock().unwrap().clone();
+    Some(info)
+}
+
+/// Новые первыми. instance_id = None - все
+pub fn list(instance_id: Option<&str>) -> Vec<AlgoInfo> {
+    let algos: Vec<Arc<Algo>> = ALGOS.iter().map(|a| a.value().clone()).collect();
+    let mut list: Vec<AlgoInfo> = algos
+        .iter()
+        .filter(|a| instance_id.is_none() || a.info.lock().unwrap().instance_id.as_deref() == instance_id)
+        .map(|a| {
+            refresh(a);
+            a.info.lock().unwrap().clone()
+        })
+        .collect();
+    list.sort_by_key(|a| std::cmp::Reverse(a.id));
+    list
+}
+
+/// Вытесняет самые старые завершённые алгоритмы сверх keep_finished
+fn prune(keep_finished: usize) {
+    let mut finished: Vec<u64> = ALGOS
+        .iter()
+        .filter(|a| a.info.lock().unwrap().state != AlgoState::Running)
+        .map(|a| *a.key())
+        .collect();
+    if finished.len() <= keep_finished {
+        return;
+    }
+    finished.sort_unstable();
+    for id in &finished[..finished.len() - keep_finished] {
+        ALGOS.remove(id);
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ПАРАМЕТРЫ
+// ═══════════════════════════════════════════════════════════
+
+fn validate(params: &mut AlgoParams, config: &ExecutionConfig) -> Result<(), String> {
+    params.symbol = params.symbol.trim().to_uppercase();
+    params.side = params.side.trim().to_uppercase();
+    if params.symbol.is_empty() || !params.symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!("Invalid symbol '{}'", params.symbol));
+    }
+    if params.side != "BUY" && params.side != "SELL" {
+        return Err(format!("Invalid side '{}'", params.side));
+    }
+    // NaN тоже не проходит
+    let positive = |v: f64| v > 0.0 && v.is_finite();
+    if !positive(params.quantity) {
+        return Err("quantity must be > 0".to_string());
+    }
+    if params.price.is_some_and(|p| !positive(p)) {
+        return Err("price must be > 0".to_string());
+    }
+    if params.step.is_some_and(|s| !positive(s)) {
+        return Err("step must be > 0".to_string());
+    }
+    let step = params.step;
+
+    match &mut params.kind {
+        AlgoKind::Twap { slices, .. } => {
+            if *slices == 0 || *slices > config.max_slices {
+                return Err(format!("slices must be 1..={}", config.max_slices));
+            }
+            if round_down(params.quantity / *slices as f64, step) <= QTY_EPSILON {
+                return Err("Slice quantity rounds to zero, use fewer slices".to_string());
+            }
+        }
+        AlgoKind::Iceberg { clip_qty } => {
+            if params.price.is_none() {
+                return Err("Iceberg requires a limit price".to_string());
+            }
+            *clip_qty = round_down(*clip_qty, step);
+            if !positive(*clip_qty) || *clip_qty > params.quantity {
+                return Err("clip_qty must be in (0, quantity] after rounding to step".to_string());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Вниз до шага (с допуском на погрешность деления)
+fn round_down(quantity: f64, step: Option<f64>) -> f64 {
+    match step {
+        Some(step) => ((quantity / step) + QTY_EPSILON).floor() * step,
+        None => quantity,
+    }
+}
+
+/// Остаток последней части - к ближайшему шагу
+fn round_nearest(quantity: f64, step: Option<f64>) -> f64 {
+    match step {
+        Some(step) => (quantity / step).round() * step,
+        None => quantity,
+    }
+}
+
+/// "TWAP BUY 1 BTCUSDT in 10 slices over 600s" для логов
+fn describe(params: &AlgoParams) -> String {
+    let order = format!("{} {} {}", params.side, params.quantity, params.symbol);
+    match (&params.kind, params.price) {
+        (AlgoKind::Twap { duration_secs, slices }, None) => {
+            format!("TWAP {} in {} slices over {}s", order, slices, duration_secs)
+        }
+        (AlgoKind::Twap { duration_secs, slices }, Some(price)) => {
+            format!("TWAP {} @ {} in {} slices over {}s", order, price, slices, duration_secs)
+        }
+        (AlgoKind::Iceberg { clip_qty }, price) => {
+            format!("Iceberg {} @ {} by {}", order, price.unwrap_or(0.0), clip_qty)
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ИСПОЛНЕНИЕ
+// ═══════════════════════════════════════════════════════════
+
+async fn run(algo: Arc<Algo>) {
+    let (instance_id, params) = {
+        let info = algo.info.lock().unwrap();
+        (info.instance_id.clone(), info.params.clone())
+    };
+
+    let result = match params.kind {
+        AlgoKind::Twap { duration_secs, slices } => twap(&algo, &params, duration_secs, slices).await,
+        AlgoKind::Iceberg { clip_qty } => iceberg(&algo, &params, clip_qty).await,
+    };
+
+    let canceled = algo.canceled.load(Ordering::Relaxed);
+    if canceled || result.is_err() {
+        cancel_children(&algo, &instance_id, &params.symbol).await;
+    }
+    refresh(&algo);
+
+    let (state, message) = {
+        let mut info = algo.info.lock().unwrap();
+        info.state = match (&result, canceled) {
+            (Err(_), _) => AlgoState::Failed,
+            (Ok(_), true) => AlgoState::Canceled,
+            (Ok(_), false) => AlgoState::Done,
+        };
+        info.error = result.err();
+        info.updated_at = now_ms();
+        let message = format!(
+            "Algo #{} {:?}: placed {}, filled {} of {}{}",
+            info.id, info.state, info.placed_qty, info.filled_qty, info.params.quantity,
+            info.error.as_deref().map(|e| format!(" ({})", e)).unwrap_or_default(),
+        );
+        (info.state, message)
+    };
+
+    if state == AlgoState::Failed {
+        tracing::warn!("🧩 {}", message);
+    } else {
+        tracing::info!("🧩 {}", message);
+    }
+    if let Some(instance_id) = instance_id.as_deref() {
+        let level = if state == AlgoState::Failed { logs::LOG_WARN } else { logs::LOG_INFO };
+        logs::push(instance_id, level, &message);
+    }
+}
+
+async fn twap(algo: &Algo, params: &AlgoParams, duration_secs: u64, slices: u32) -> Result<(), String> {
+    let interval = Duration::from_secs(duration_secs) / slices;
+    let (order_type, price) = match params.price {
+        Some(price) => (ORDER_LIMIT, price),
+        None => (ORDER_MARKET, 0.0),
+    };
+    let slice = round_down(params.quantity / slices as f64, params.step);
+
+    for i in 0..slices {
+        if (i > 0 && !wait(algo, interval).await) || algo.canceled.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let quantity = if i + 1 == slices {
+            round_nearest(params.quantity - slice * (slices - 1) as f64, params.step)
+        } else {
+            slice
+        };
+        place(algo, params, order_type, price, quantity).await?;
+    }
+    Ok(())
+}
+
+async fn iceberg(algo: &Algo, params: &AlgoParams, clip_qty: f64) -> Result<(), String> {
+    let poll = Duration::from_millis(config().iceberg_poll_ms.max(1));
+    let price = params.price.unwrap_or_default();
+    let mut remaining = params.quantity;
+
+    while remaining > QTY_EPSILON {
+        if algo.canceled.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let clip = if remaining - clip_qty > QTY_EPSILON {
+            clip_qty
+        } else {
+            round_nearest(remaining, params.step)
+        };
+        let index = place(algo, params, ORDER_LIMIT, price, clip).await?;
+
+        // Ждём, пока клип закроется
+        loop {
+            if !wait(algo, poll).await {
+                return Ok(());
+            }
+            refresh(algo);
+            let child = algo.info.lock().unwrap().children[index].clone();
+            if child.open {
+                continue;
+            }
+            if child.filled_qty + QTY_EPSILON < clip {
+                return Err(format!(
+                    "Clip {} closed with {} of {} filled",
+                    child.order_id, child.filled_qty, clip
+                ));
+            }
+            remaining -= clip;
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Дочерний ордер обычным путём ордера инстанса. Ok - индекс в children.
+/// Ордер попадает в children по clientOrderId до отправки: если ответ
+/// придёт после CHILD_TIMEOUT, ордер снимет cancel_late
+async fn place(algo: &Algo, params: &AlgoParams, order_type: u8, price: f64, quantity: f64) -> Result<usize, String> {
+    let (algo_id, instance_id) = {
+        let info = algo.info.lock().unwrap();
+        (info.id, info.instance_id.clone())
+    };
+    let client_order_id = ORDER_MANAGER.get().map(|orders| orders.next_client_id(instance_id.as_deref()));
+    let index = {
+        let mut info = algo.info.lock().unwrap();
+        info.children.push(ChildOrder {
+            order_id: -1,
+            client_order_id: client_order_id.clone(),
+            order_type: if order_type == ORDER_MARKET { "MARKET" } else { "LIMIT" },
+            price,
+            quantity,
+            filled_qty: 0.0,
+            open: true,
+            time: now_ms(),
+        });
+        info.children.len() - 1
+    };
+
+    let (tx, mut rx) = oneshot::channel::<OrderResult>();
+    route_order(
+        algo.creds.clone(), instance_id.clone(), &params.symbol, &params.side, order_type, price, quantity,
+        client_order_id, move |result| {
+            let _ = tx.send(result);
+        },
+    );
+
+    let result = match tokio::time::timeout(CHILD_TIMEOUT, &mut rx).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(_)) => {
+            algo.info.lock().unwrap().children[index].open = false;
+            return Err("Child order result lost".to_string());
+        }
+        Err(_) => {
+            let creds = (*algo.creds).clone();
+            tokio::spawn(cancel_late(algo_id, index, creds, instance_id, params.symbol.clone(), rx));
+            return Err(format!("No response to child order in {}s", CHILD_TIMEOUT.as_secs()));
+        }
+    };
+
+    let mut info = algo.info.lock().unwrap();
+    if !result.success {
+        info.children[index].open = false;
+        return Err(format!("Child order rejected ({})", result.error_code));
+    }
+    info.children[index].order_id = result.order_id;
+    info.placed_qty += quantity;
+    info.updated_at = now_ms();
+    Ok(index)
+}
+
+/// Ответ на дочерний ордер пришёл после CHILD_TIMEOUT: алгоритм уже
+/// остановлен, выставленный ордер снимается
+async fn cancel_late(
+    algo_id: u64,
+    index: usize,
+    creds: Credentials,
+    instance_id: Option<String>,
+    symbol: String,
+    rx: oneshot::Receiver<OrderResult>,
+) {
+    let Ok(result) = rx.await else { return };
+    let algo = ALGOS.get(&algo_id).map(|a| a.value().clone());
+    if !result.success {
+        if let Some(algo) = &algo {
+            algo.info.lock().unwrap().children[index].open = false;
+        }
+        return;
+    }
+    if let Some(algo) = &algo {
+        algo.info.lock().unwrap().children[index].order_id = result.order_id;
+    }
+
+    let message = format!("Child order {} of algo #{} acknowledged after timeout, canceling", result.order_id, algo_id);
+    tracing::warn!("🧩 {} [{}]", message, instance_id.as_deref().unwrap_or("api"));
+    if let Some(id) = instance_id.as_deref() {
+        logs::push(id, logs::LOG_WARN, &message);
+    }
+    let order_id = result.order_id;
+    route_cancel(creds, instance_id, &symbol, order_id, move |result| {
+        if result.success {
+            if let Some(algo) = &algo {
+                algo.info.lock().unwrap().children[index].open = false;
+            }
+        } else {
+            tracing::error!("🧩 Failed to cancel late child order {} of algo #{} ({})", order_id, algo_id, result.error_code);
+        }
+    });
+}
+
+/// Снимает стоящие дочерние ордера (отмена или сбой алгоритма)
+async fn cancel_children(algo: &Algo, instance_id: &Option<String>, symbol: &str) {
+    refresh(algo);
+    // Ордера без ответа биржи снимет cancel_late
+    let open: Vec<i64> = algo.info.lock().unwrap().children.iter()
+        .filter(|c| c.open && c.order_id >= 0)
+        .map(|c| c.order_id)
+        .collect();
+
+    for order_id in open {
+        let (tx, rx) = oneshot::channel::<OrderResult>();
+        route_cancel((*algo.creds).clone(), instance_id.clone(), symbol, order_id, move |result| {
+            let _ = tx.send(result);
+        });
+        match tokio::time::timeout(CHILD_TIMEOUT, rx).await {
+            Ok(Ok(result)) if result.success => {
+                let mut info = algo.info.lock().unwrap();
+                if let Some(child) = info.children.iter_mut().find(|c| c.order_id == order_id) {
+                    child.open = false;
+                }
+            }
+            _ => tracing::warn!("🧩 Failed to cancel child order {} of algo #{}", order_id, algo.info.lock().unwrap().id),
+        }
+    }
+}
+
+/// false - алгоритм отменён
+async fn wait(algo: &Algo, duration: Duration) -> bool {
+    tokio::select! {
+        _ = tokio::time::sleep(duration) => {}
+        _ = algo.wake.notified() => {}
+    }
+    !algo.canceled.load(Ordering::Relaxed)
+}
+
+/// Подтягивает исполнение стоящих дочерних ордеров
+fn refresh(algo: &Algo) {
+    let mut info = algo.info.lock().unwrap();
+    let instance_id = info.instance_id.clone();
+    let mut changed = false;
+    for child in info.children.iter_mut().filter(|c| c.open) {
+        // Paper исполняет ордер целиком, отменённые execution помечены сразу
+        if let Some(account) = instance_id.as_deref().and_then(paper::account) {
+            if child.order_id >= 0 && !account.is_open(child.order_id) {
+                child.open = false;
+                child.filled_qty = child.quantity;
+                changed = true;
+            }
+            continue;
+        }
+        // До ответа биржи ордер виден только по clientOrderId; dry run пишет
+        // намерения под своими id
+        let order = ORDER_MANAGER.get().and_then(|o| {
+            child.client_order_id.as_deref().and_then(|cid| o.get(cid))
+                .or_else(|| (child.order_id >= 0).then(|| o.get_by_order_id(child.order_id)).flatten())
+        });
+        let Some(order) = order else { continue };
+        let open = !order.state.is_terminal();
+        if open != child.open || order.filled_qty != child.filled_qty {
+            child.open = open;
+            child.filled_qty = order.filled_qty;
+            changed = true;
+        }
+    }
+    if changed {
+        info.filled_qty = info.children.iter().map(|c| c.filled_qty).sum();
+        info.updated_at = now_ms();
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// FFI
+// ═══════════════════════════════════════════════════════════
+
+/// Запускает алгоритм от имени текущего инстанса.
+/// Возвращает id алгоритма (> 0) или ERR_ALGO_*
+pub unsafe extern "C" fn place_algo_order(
+    api_key: *const c_char,
+    secret_key: *const c_char,
+    order: *const CAlgoOrder,
+) -> i64 {
+    if api_key.is_null() || secret_key.is_null() || order.is_null() {
+        return ERR_ALGO_INVALID as i64;
+    }
+    let (Ok(api_key), Ok(secret_key)) = (CStr::from_ptr(api_key).to_str(), CStr::from_ptr(secret_key).to_str()) else {
+        return ERR_ALGO_INVALID as i64;
+    };
+    let order = &*order;
+    let Some(symbol) = order.symbol_str() else {
+        return ERR_ALGO_INVALID as i64;
+    };
+    let side = match order.side {
+        0 => "BUY",
+        1 => "SELL",
+        _ => return ERR_ALGO_INVALID as i64,
+    };
+    let kind = match order.algo {
+        0 => AlgoKind::Twap { duration_secs: order.duration_secs, slices: order.slices },
+        1 => AlgoKind::Iceberg { clip_qty: order.clip_qty },
+        _ => return ERR_ALGO_INVALID as i64,
+    };
+    let params = AlgoParams {
+        symbol: symbol.to_string(),
+        side: side.to_string(),
+        quantity: order.quantity,
+        price: (order.price != 0.0).then_some(order.price),
+        step: (order.step != 0.0).then_some(order.step),
+        kind,
+    };
+
+    let instance_id = current_instance();
+    let creds = Arc::new(keystore::resolve(api_key, secret_key));
+    match start(creds, instance_id.clone(), params) {
+        Ok(info) => info.id as i64,
+        Err(reject) => {
+            if let Some(instance_id) = instance_id.as_deref() {
+                logs::push(instance_id, logs::LOG_WARN, &format!("Algo order rejected: {}", reject.message));
+            }
+            reject.code as i64
+        }
+    }
+}
+
+/// Отменяет алгоритм текущего инстанса; стоящие дочерние ордера снимаются.
+/// false - алгоритм чужой, неизвестен или уже завершён
+pub extern "C" fn cancel_algo_order(algo_id: i64) -> bool {
+    let Ok(id) = u64::try_from(algo_id) else { return false };
+    let owned = ALGOS
+        .get(&id)
+        .is_some_and(|a| a.info.lock().unwrap().instance_id == current_instance());
+    owned && cancel(id) == Some(true)
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
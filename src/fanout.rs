@@ -0,0 +1,255 @@
+// src/fanout.rs
+
+use crossbeam::channel::{bounded, Receiver, SendTimeoutError, Sender, TrySendError};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::Duration;
+
+use crate::ffi_types::{CEvent, EVENT_ACCOUNT_UPDATE, EVENT_ORDER_UPDATE, EVENT_USER_DATA_UNKNOWN};
+use crate::latency::{self, Stage};
+use crate::paper::PaperAccount;
+
+// ═══════════════════════════════════════════════════════════
+// FAN-OUT
+// ═══════════════════════════════════════════════════════════
+
+// Горячий путь market data: reader WS сам кладёт CEvent в канал каждой
+// стратегии (crossbeam bounded - кольцевой буфер, его Receiver и есть ABI run()).
+// Без промежуточного async bridge нет лишнего пробуждения задачи и копии.
+// broadcast остаётся для медленных потребителей: HTTP/WS, risk, pnl.
+
+/// Сколько OverflowPolicy::Block ждёт места, прежде чем всё-таки выбросить событие:
+/// зависшая стратегия не должна навсегда занять ретранслятор
+const BLOCK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Очередь ретранслятора OverflowPolicy::Block: переполнилась - событие теряется
+const RELAY_CAPACITY: usize = 65_536;
+
+/// Что делать, когда канал стратегии полон
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverflowPolicy {
+    /// Выбросить новое событие (стратегия доработает старые)
+    #[default]
+    DropNewest,
+    /// Выбросить самое старое из очереди, новое положить
+    DropOldest,
+    /// Ждать, пока стратегия освободит место. Ждёт отдельный поток-ретранслятор
+    /// со своей очередью, reader и остальные стратегии не задерживаются.
+    /// Лишний переход между потоками - только для аналитики
+    Block,
+}
+
+/// Канал одной стратегии
+pub struct Subscriber {
+    instance_id: String,
+    tx: Sender<CEvent>,
+    /// Копия Receiver стратегии - только для DropOldest
+    rx: Option<Receiver<CEvent>>,
+    /// Очередь ретранслятора - только для Block: ждёт места он, а не reader
+    relay: Option<Sender<CEvent>>,
+    policy: OverflowPolicy,
+    /// received_at_ns последнего тика, положенного в канал (для tick -> order)
+    last_tick: Arc<AtomicU64>,
+    received: Arc<AtomicU64>,
+    dropped: Arc<AtomicU64>,
+    /// Сигналов, отправленных этому подписчику (seq следующего)
+    signals: AtomicU64,
+    /// Paper-инстанс: рыночные события исполняют его ордера в симуляторе,
+    /// события реального счёта ему не доставляются
+    paper: Option<Arc<PaperAccount>>,
+}
+
+impl Subscriber {
+    pub fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    pub fn last_tick(&self) -> &Arc<AtomicU64> {
+        &self.last_tick
+    }
+
+    /// Событий, положенных в канал стратегии
+    pub fn received(&self) -> u64 {
+        self.received.load(Ordering::Relaxed)
+    }
+
+    /// Событий, потерянных из-за переполнения канала
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Событий в очереди стратегии сейчас (с очередью ретранслятора)
+    pub fn queued(&self) -> usize {
+        self.tx.len() + self.relay.as_ref().map_or(0, |relay| relay.len())
+    }
+
+    /// Кладёт событие по политике канала, false - событие (или вытесненное) потеряно.
+    /// Никогда не ждёт: Block ждёт в ретрансляторе
+    fn deliver(&self, event: CEvent) -> bool {
+        // Все события Block идут через ретранслятор, иначе обгонят его очередь
+        if let Some(relay) = &self.relay {
+            return !matches!(relay.try_send(event), Err(TrySendError::Full(_)));
+        }
+        match self.tx.try_send(event) {
+            Ok(()) => true,
+            // Стратегия завершилась, отписка придёт из run_strategy
+            Err(TrySendError::Disconnected(_)) => true,
+            Err(TrySendError::Full(event)) => match (self.policy, &self.rx) {
+                (OverflowPolicy::DropOldest, Some(rx)) => {
+                    let _ = rx.try_recv();
+                    let _ = self.tx.try_send(event);
+                    false
+                }
+                _ => false,
+            },
+        }
+    }
+
+    /// Событие только этому подписчику (сигнал извне), seq - номер сигнала.
+    /// false - не влезло в канал
+    pub fn send_signal(&self, mut event: CEvent) -> bool {
+        event.seq = self.signals.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.deliver(event) {
+            self.received.fetch_add(1, Ordering::Relaxed);
+            true
+        } else {
+            self.on_dropped();
+            false
+        }
+    }
+
+    fn on_dropped(&self) {
+        count_dropped(&self.instance_id, &self.dropped);
+    }
+}
+
+fn count_dropped(instance_id: &str, dropped: &AtomicU64) {
+    let dropped = dropped.fetch_add(1, Ordering::Relaxed) + 1;
+    if dropped.is_multiple_of(1000) {
+        tracing::warn!("⚠️ '{}' lagging: {} dropped", instance_id, dropped);
+    }
+}
+
+/// Поток OverflowPolicy::Block: перекладывает события из своей очереди в канал
+/// стратегии, ожидая места до BLOCK_TIMEOUT. Завершается вместе с подписчиком
+/// (очередь закрыта) или стратегией (канал закрыт)
+fn spawn_relay(
+    instance_id: &str,
+    tx: Sender<CEvent>,
+    received: Arc<AtomicU64>,
+    dropped: Arc<AtomicU64>,
+) -> std::io::Result<Sender<CEvent>> {
+    let (relay_tx, relay_rx) = bounded::<CEvent>(RELAY_CAPACITY);
+    let name = instance_id.to_string();
+    std::thread::Builder::new()
+        .name(format!("relay-{}", instance_id))
+        .spawn(move || {
+            while let Ok(event) = relay_rx.recv() {
+                match tx.send_timeout(event, BLOCK_TIMEOUT) {
+                    Ok(()) => {}
+                    // Событие уже посчитано полученным, но в канал не попало
+                    Err(SendTimeoutError::Timeout(_)) => {
+                        received.fetch_sub(1, Ordering::Relaxed);
+                        count_dropped(&name, &dropped);
+                    }
+                    Err(SendTimeoutError::Disconnected(_)) => break,
+                }
+            }
+        })?;
+    Ok(relay_tx)
+}
+
+/// Снимок списка подписчиков: publish только читает,
+/// подписка/отписка (редкие) подменяют список целиком
+static SUBSCRIBERS: LazyLock<RwLock<Arc<[Arc<Subscriber>]>>> =
+    LazyLock::new(|| RwLock::new(Arc::from(Vec::new())));
+
+/// Текущие подписчики (для метрик)
+pub fn subscribers() -> Arc<[Arc<Subscriber>]> {
+    SUBSCRIBERS.read().unwrap().clone()
+}
+
+/// Регистрирует канал стратегии. Handle нужен для unsubscribe
+pub fn subscribe(
+    instance_id: &str,
+    tx: Sender<CEvent>,
+    rx: &Receiver<CEvent>,
+    policy: OverflowPolicy,
+    last_tick: Arc<AtomicU64>,
+    paper: Option<Arc<PaperAccount>>,
+) -> Arc<Subscriber> {
+    let received = Arc::new(AtomicU64::new(0));
+    let dropped = Arc::new(AtomicU64::new(0));
+    let relay = match policy {
+        OverflowPolicy::Block => match spawn_relay(instance_id, tx.clone(), received.clone(), dropped.clone()) {
+            Ok(relay) => Some(relay),
+            Err(e) => {
+                tracing::error!("❌ Relay thread for '{}' failed, dropping newest on overflow: {}", instance_id, e);
+                None
+            }
+        },
+        _ => None,
+    };
+    let sub = Arc::new(Subscriber {
+        instance_id: instance_id.to_string(),
+        tx,
+        rx: (policy == OverflowPolicy::DropOldest).then(|| rx.clone()),
+        relay,
+        policy,
+        last_tick,
+        received,
+        dropped,
+        signals: AtomicU64::new(0),
+        paper,
+    });
+
+    let mut list = SUBSCRIBERS.write().unwrap();
+    let mut next: Vec<_> = list.iter().cloned().collect();
+    next.push(sub.clone());
+    *list = next.into();
+
+    tracing::debug!("🔀 Fan-out '{}' subscribed ({} total)", instance_id, list.len());
+    sub
+}
+
+pub fn unsubscribe(sub: &Arc<Subscriber>) {
+    let mut list = SUBSCRIBERS.write().unwrap();
+    let next: Vec<_> = list.iter().filter(|s| !Arc::ptr_eq(s, sub)).cloned().collect();
+    *list = next.into();
+
+    tracing::debug!("🔀 Fan-out '{}' unsubscribed ({} dropped)", sub.instance_id, sub.dropped.load(Ordering::Relaxed));
+}
+
+/// Раздаёт событие всем стратегиям. Не блокирует: OverflowPolicy::Block ждёт в ретрансляторе
+pub fn publish(event: CEvent) {
+    let start_ns = latency::now_ns();
+    let list = SUBSCRIBERS.read().unwrap().clone();
+
+    for sub in list.iter() {
+        if sub.paper.is_some() && matches!(event.event_type, EVENT_ORDER_UPDATE | EVENT_ACCOUNT_UPDATE | EVENT_USER_DATA_UNKNOWN) {
+            continue;
+        }
+        if sub.deliver(event) {
+            latency::record_since(Stage::Enqueue, event.received_at_ns);
+            sub.last_tick.store(event.received_at_ns, Ordering::Relaxed);
+            sub.received.fetch_add(1, Ordering::Relaxed);
+        } else {
+            sub.on_dropped();
+        }
+        if let Some(paper) = &sub.paper {
+            for fill in paper.on_market(&event) {
+                if sub.deliver(fill) {
+                    sub.received.fetch_add(1, Ordering::Relaxed);
+                } else {
+                    sub.on_dropped();
+                }
+            }
+        }
+    }
+
+    if !list.is_empty() {
+        latency::record_since(Stage::Fanout, start_ns);
+    }
+}
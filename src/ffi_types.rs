@@ -1,19 +1,54 @@
 // src/ffi_types.rs
 
+use dashmap::DashMap;
+
 /// C-совместимый Event для FFI и broadcast
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct CEvent {
-    pub event_type: u8,  // 0 = BookTicker, 1 = Trade
+    pub event_type: u8,  // 0 = BookTicker, 1 = Trade, 2 = OrderUpdate, 3 = AccountUpdate
     pub data: CEventData,
     pub received_at_ns: u64,
+    /// Номер события в потоке (event_type + символ): 1, 2, 3 ...
+    /// Пропуск номера - событие потеряно по дороге к потребителю
+    pub seq: u64,
 }
 
+/// Версия ABI стратегий (copy_into_strategies/types.rs: strategy_abi_version).
+/// Менять при любом изменении раскладки CEvent/StrategyConfig или сигнатуры run
+pub const STRATEGY_ABI_VERSION: u32 = 1;
+
+pub const EVENT_BOOK_TICKER: u8 = 0;
+pub const EVENT_TRADE: u8 = 1;
+pub const EVENT_ORDER_UPDATE: u8 = 2;
+pub const EVENT_ACCOUNT_UPDATE: u8 = 3;
+pub const EVENT_FUNDING_RATE: u8 = 4;
+pub const EVENT_SIGNAL: u8 = 5;
+pub const EVENT_TIMER: u8 = 6;
+pub const EVENT_USER_DATA_UNKNOWN: u8 = 7;
+pub const EVENT_ANALYTICS: u8 = 8;
+pub const EVENT_OPEN_INTEREST: u8 = 9;
+
+/// Максимум payload сигнала: CCustomSignal не должен быть больше COrderUpdate,
+/// иначе изменится размер CEventData (и ABI)
+pub const SIGNAL_PAYLOAD_LEN: usize = 128;
+
+/// Начало JSON неизвестного события user data (CUnknownUserData)
+pub const UNKNOWN_PAYLOAD_LEN: usize = 96;
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub union CEventData {
     pub book_ticker: CBookTicker,
     pub trade: CTrade,
+    pub order_update: COrderUpdate,
+    pub account_update: CAccountUpdate,
+    pub funding_rate: CFundingRate,
+    pub signal: CCustomSignal,
+    pub timer: CTimer,
+    pub unknown_user_data: CUnknownUserData,
+    pub analytics: CAnalytics,
+    pub open_interest: COpenInterest,
 }
 
 impl std::fmt::Debug for CEventData {
@@ -45,6 +80,252 @@ pub struct CTrade {
     pub time: i64,
 }
 
+/// ORDER_TRADE_UPDATE из user data stream
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct COrderUpdate {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub client_order_id: [u8; 36],
+    pub client_order_id_len: u8,
+    pub order_id: i64,
+    pub side: u8,          // 0 = BUY, 1 = SELL
+    pub order_type: u8,    // 0 = LIMIT, 1 = MARKET, 2 = STOP_MARKET, 3 = TAKE_PROFIT_MARKET, 255 = другое
+    pub exec_type: u8,     // 0 = NEW, 1 = TRADE, 2 = CANCELED, 3 = EXPIRED, 4 = AMENDMENT, 255 = другое
+    pub status: u8,        // 0 = NEW, 1 = PARTIALLY_FILLED, 2 = FILLED, 3 = CANCELED, 4 = EXPIRED, 5 = REJECTED, 255 = другое
+    pub reduce_only: bool,
+    pub price: f64,
+    pub orig_qty: f64,
+    pub last_filled_qty: f64,
+    pub last_filled_price: f64,
+    pub cum_filled_qty: f64,
+    pub avg_price: f64,
+    pub commission: f64,
+    pub realized_pnl: f64,
+    pub time: i64,
+}
+
+/// ACCOUNT_UPDATE из user data stream (одно событие на каждую позицию)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CAccountUpdate {
+    pub symbol: [u8; 16],  // пустой если в апдейте нет позиций
+    pub symbol_len: u8,
+    pub reason: u8,        // 0 = ORDER, 1 = FUNDING_FEE, 255 = другое
+    pub position_amt: f64,
+    pub entry_price: f64,
+    pub unrealized_pnl: f64,
+    pub wallet_balance: f64,   // USDT
+    pub balance_change: f64,   // USDT, без учёта PnL и комиссий
+    pub time: i64,
+}
+
+/// Ставка funding пересекла порог сканера (config.funding.threshold), в любую сторону
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CFundingRate {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub above: bool,           // true - |ставка| >= порога, false - опустилась ниже
+    pub funding_rate: f64,     // прогноз на ближайший funding (0.0001 = 0.01%)
+    pub threshold: f64,
+    pub mark_price: f64,
+    pub next_funding_time: i64,  // unix ms, время биржи
+    pub time: i64,
+}
+
+/// Сигнал извне (POST /api/instances/:id/signal) - только этому инстансу.
+/// seq - номер сигнала инстанса: пропуск значит, что сигнал не влез в канал
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CCustomSignal {
+    pub payload: [u8; SIGNAL_PAYLOAD_LEN],  // JSON как прислали, UTF-8
+    pub payload_len: u16,
+    pub time: i64,             // unix ms приёма ядром
+}
+
+const _: () = assert!(std::mem::size_of::<CCustomSignal>() <= std::mem::size_of::<COrderUpdate>());
+
+/// Сработал таймер стратегии (schedule_timer). Время - часы стратегии (now_ns):
+/// на replay таймер срабатывает по времени записи
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CTimer {
+    pub timer_id: u64,
+    pub due_ns: i64,           // на когда был заведён, Unix ns
+    pub fired_ns: i64,         // когда поставлен в канал, Unix ns
+}
+
+/// Событие user data, которое ядро не разбирает (не ORDER_TRADE_UPDATE /
+/// ACCOUNT_UPDATE): имя из "e" и начало JSON - стратегия хотя бы видит, что пропускает
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CUnknownUserData {
+    pub name: [u8; 32],
+    pub name_len: u8,
+    pub truncated: bool,       // payload обрезан, как JSON не разберётся
+    pub payload: [u8; UNKNOWN_PAYLOAD_LEN],
+    pub payload_len: u16,
+    pub time: i64,             // "E" события, unix ms
+}
+
+const _: () = assert!(std::mem::size_of::<CUnknownUserData>() <= std::mem::size_of::<COrderUpdate>());
+
+/// Метрики символа от аналитики market data (config.analytics), после
+/// bookTicker или trade, который их изменил. Окна - по времени биржи
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CAnalytics {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub spread_bps: f64,       // (ask - bid) / mid, б.п.
+    pub mid_price: f64,
+    pub book_imbalance: f64,   // (bid_qty - ask_qty) / (bid_qty + ask_qty), -1..1
+    pub flow_imbalance_1s: f64,  // (buy - sell) / (buy + sell) объёма тейкеров, -1..1
+    pub flow_imbalance_5s: f64,
+    pub volume_1s: f64,
+    pub volume_5s: f64,
+    pub trades_1s: u32,
+    pub trades_5s: u32,
+    pub time: i64,             // unix ms, время биржи последнего учтённого события
+}
+
+const _: () = assert!(std::mem::size_of::<CAnalytics>() <= std::mem::size_of::<COrderUpdate>());
+
+/// Опрос open interest и long/short ratio топ-трейдеров (config.open_interest).
+/// Нули - значение ещё не получено
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct COpenInterest {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub open_interest: f64,    // в базовой монете
+    pub open_interest_change_percent: f64,  // с прошлого опроса
+    pub long_short_ratio: f64, // long / short позиций топ-трейдеров
+    pub long_account: f64,     // доля long, 0..1
+    pub short_account: f64,
+    pub ratio_time: i64,       // unix ms, начало периода ratio
+    pub time: i64,             // unix ms, время биржи open interest
+}
+
+const _: () = assert!(std::mem::size_of::<COpenInterest>() <= std::mem::size_of::<COrderUpdate>());
+
+/// Снимок ордера из order manager (FFI-запрос get_orders)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct COrder {
+    pub client_order_id: [u8; 36],
+    pub client_order_id_len: u8,
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub order_id: i64,     // -1 пока нет ответа биржи
+    pub side: u8,          // 0 = BUY, 1 = SELL
+    pub order_type: u8,    // 0 = LIMIT, 1 = MARKET, 2 = STOP_MARKET
+    pub state: u8,         // 0 = PENDING, 1 = NEW, 2 = PARTIALLY_FILLED, 3 = FILLED, 4 = CANCELED, 5 = EXPIRED, 6 = REJECTED, 7 = FAILED, 8 = STALE
+    pub price: f64,
+    pub orig_qty: f64,
+    pub filled_qty: f64,
+    pub avg_price: f64,
+    pub error_code: i32,
+    pub created_at: i64,   // unix ms
+    pub updated_at: i64,   // unix ms
+}
+
+/// Снимок VWAP символа за окно профиля объёма (FFI-запрос get_vwap)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CVwap {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub vwap: f64,
+    pub volume: f64,
+    pub buy_volume: f64,   // объём покупок тейкеров
+    pub sell_volume: f64,
+    pub poc_price: f64,    // цена с наибольшим объёмом
+    pub high: f64,
+    pub low: f64,
+    pub trades: u32,
+    pub window_ms: i64,
+    pub time: i64,         // unix ms, время биржи последней сделки
+}
+
+/// Уровень footprint: объём по одной цене (FFI-запрос get_volume_profile)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CVolumeLevel {
+    pub price: f64,
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+    pub trades: u32,
+}
+
+/// Шаблон ордера для stage_orders: всё, кроме цены, известно заранее
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct COrderTemplate {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub side: u8,          // 0 = BUY, 1 = SELL
+    pub order_type: u8,    // 0 = LIMIT, 1 = MARKET
+    pub quantity: f64,
+}
+
+/// Родительский ордер для place_algo_order: ядро режет его на дочерние
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CAlgoOrder {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub side: u8,          // 0 = BUY, 1 = SELL
+    pub algo: u8,          // 0 = TWAP, 1 = ICEBERG
+    pub slices: u32,       // TWAP: число частей
+    pub duration_secs: u64, // TWAP: на сколько растянуть
+    pub quantity: f64,
+    pub price: f64,        // TWAP: 0 = MARKET, иначе LIMIT; ICEBERG: цена клипов
+    pub clip_qty: f64,     // ICEBERG: видимый объём
+    pub step: f64,         // шаг количества символа, 0 = без округления
+}
+
+/// Вход + TP + SL для place_bracket: защита ставится от средней цены входа
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CBracket {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub side: u8,          // 0 = BUY, 1 = SELL
+    pub entry_type: u8,    // 0 = LIMIT, 1 = MARKET
+    pub quantity: f64,
+    pub price: f64,        // цена входа LIMIT
+    pub tp_pct: f64,       // TP в % от входа, 0 = без TP
+    pub sl_pct: f64,       // SL в % от входа, 0 = без SL
+    pub tick_size: f64,    // шаг цены символа, 0 = без округления
+}
+
+/// Счётчики seq по потокам (event_type + символ)
+#[derive(Default)]
+pub struct Sequencer {
+    last: DashMap<(u8, [u8; 16]), u64>,
+}
+
+impl Sequencer {
+    /// Проставляет event.seq следующим номером его потока
+    pub fn stamp(&self, event: &mut CEvent) {
+        let (symbol, _) = pack_str::<16>(event.symbol_str());
+        let mut last = self.last.entry((event.event_type, symbol)).or_insert(0);
+        *last += 1;
+        event.seq = *last;
+    }
+}
+
+/// Копирует строку в фиксированный буфер, возвращает (буфер, длина)
+pub fn pack_str<const N: usize>(s: &str) -> ([u8; N], u8) {
+    let mut buf = [0u8; N];
+    let bytes = s.as_bytes();
+    let len = bytes.len().min(N - 1);
+    buf[..len].copy_from_slice(&bytes[..len]);
+    (buf, len as u8)
+}
+
 // Удобные методы для работы с C-типами
 impl CBookTicker {
     pub fn symbol_str(&self) -> &str {
@@ -72,4 +353,296 @@ impl CTrade {
     pub fn side(&self) -> &str {
         if self.qty > 0.0 { "BUY" } else { "SELL" }
     }
-}
\ No newline at end of file
+}
+
+impl COrderUpdate {
+    pub fn symbol_str(&self) -> &str {
+        unsafe {
+            std::str::from_utf8_unchecked(&self.symbol[..self.symbol_len as usize])
+        }
+    }
+
+    pub fn client_order_id_str(&self) -> &str {
+        unsafe {
+            std::str::from_utf8_unchecked(&self.client_order_id[..self.client_order_id_len as usize])
+        }
+    }
+}
+
+impl COrderTemplate {
+    pub fn symbol_str(&self) -> Option<&str> {
+        let len = (self.symbol_len as usize).min(self.symbol.len());
+        std::str::from_utf8(&self.symbol[..len]).ok()
+    }
+}
+
+impl CAlgoOrder {
+    pub fn symbol_str(&self) -> Option<&str> {
+        let len = (self.symbol_len as usize).min(self.symbol.len());
+        std::str::from_utf8(&self.symbol[..len]).ok()
+    }
+}
+
+impl CBracket {
+    pub fn symbol_str(&self) -> Option<&str> {
+        let len = (self.symbol_len as usize).min(self.symbol.len());
+        std::str::from_utf8(&self.symbol[..len]).ok()
+    }
+}
+
+impl CAccountUpdate {
+    pub fn symbol_str(&self) -> &str {
+        unsafe {
+            std::str::from_utf8_unchecked(&self.symbol[..self.symbol_len as usize])
+        }
+    }
+}
+
+impl CFundingRate {
+    pub fn symbol_str(&self) -> &str {
+        unsafe {
+            std::str::from_utf8_unchecked(&self.symbol[..self.symbol_len as usize])
+        }
+    }
+}
+
+impl CAnalytics {
+    pub fn symbol_str(&self) -> &str {
+        unsafe {
+            std::str::from_utf8_unchecked(&self.symbol[..self.symbol_len as usize])
+        }
+    }
+}
+
+impl COpenInterest {
+    pub fn symbol_str(&self) -> &str {
+        unsafe {
+            std::str::from_utf8_unchecked(&self.symbol[..self.symbol_len as usize])
+        }
+    }
+}
+
+impl CCustomSignal {
+    /// None - payload длиннее буфера
+    pub fn new(payload: &str, time: i64) -> Option<Self> {
+        let bytes = payload.as_bytes();
+        if bytes.len() > SIGNAL_PAYLOAD_LEN {
+            return None;
+        }
+        let mut buf = [0u8; SIGNAL_PAYLOAD_LEN];
+        buf[..bytes.len()].copy_from_slice(bytes);
+        Some(Self { payload: buf, payload_len: bytes.len() as u16, time })
+    }
+
+    pub fn payload_str(&self) -> &str {
+        let len = (self.payload_len as usize).min(SIGNAL_PAYLOAD_LEN);
+        std::str::from_utf8(&self.payload[..len]).unwrap_or("")
+    }
+}
+
+impl CUnknownUserData {
+    /// JSON длиннее буфера обрезается по границе символа (truncated = true)
+    pub fn new(name: &str, raw: &str, time: i64) -> Self {
+        let (name, name_len) = pack_str::<32>(name);
+        let mut len = raw.len().min(UNKNOWN_PAYLOAD_LEN);
+        while !raw.is_char_boundary(len) {
+            len -= 1;
+        }
+        let mut payload = [0u8; UNKNOWN_PAYLOAD_LEN];
+        payload[..len].copy_from_slice(&raw.as_bytes()[..len]);
+        Self {
+            name,
+            name_len,
+            truncated: len < raw.len(),
+            payload,
+            payload_len: len as u16,
+            time,
+        }
+    }
+
+    pub fn name_str(&self) -> &str {
+        let len = (self.name_len as usize).min(self.name.len());
+        std::str::from_utf8(&self.name[..len]).unwrap_or("")
+    }
+
+    pub fn payload_str(&self) -> &str {
+        let len = (self.payload_len as usize).min(UNKNOWN_PAYLOAD_LEN);
+        std::str::from_utf8(&self.payload[..len]).unwrap_or("")
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// JSON (для внешних потребителей, /ws/events)
+// ═══════════════════════════════════════════════════════════
+
+impl CEvent {
+    /// Имя типа для фильтра ?types=
+    pub fn type_name(&self) -> &'static str {
+        match self.event_type {
+            EVENT_BOOK_TICKER => "bookTicker",
+            EVENT_TRADE => "trade",
+            EVENT_ORDER_UPDATE => "orderUpdate",
+            EVENT_ACCOUNT_UPDATE => "accountUpdate",
+            EVENT_FUNDING_RATE => "fundingRate",
+            EVENT_SIGNAL => "signal",
+            EVENT_TIMER => "timer",
+            EVENT_USER_DATA_UNKNOWN => "userDataUnknown",
+            EVENT_ANALYTICS => "analytics",
+            EVENT_OPEN_INTEREST => "openInterest",
+            _ => "unknown",
+        }
+    }
+
+    pub fn symbol_str(&self) -> &str {
+        unsafe {
+            match self.event_type {
+                EVENT_BOOK_TICKER => self.data.book_ticker.symbol_str(),
+                EVENT_TRADE => self.data.trade.symbol_str(),
+                EVENT_ORDER_UPDATE => self.data.order_update.symbol_str(),
+                EVENT_ACCOUNT_UPDATE => self.data.account_update.symbol_str(),
+                EVENT_FUNDING_RATE => self.data.funding_rate.symbol_str(),
+                EVENT_ANALYTICS => self.data.analytics.symbol_str(),
+                EVENT_OPEN_INTEREST => self.data.open_interest.symbol_str(),
+                _ => "",
+            }
+        }
+    }
+
+    pub fn as_json(&self) -> serde_json::Value {
+        use serde_json::json;
+
+        let data = unsafe {
+            match self.event_type {
+                EVENT_BOOK_TICKER => {
+                    let t = &self.data.book_ticker;
+                    json!({
+                        "symbol": t.symbol_str(),
+                        "bid_price": t.bid_price,
+                        "bid_qty": t.bid_qty,
+                        "ask_price": t.ask_price,
+                        "ask_qty": t.ask_qty,
+                        "time": t.time,
+                    })
+                }
+                EVENT_TRADE => {
+                    let t = &self.data.trade;
+                    json!({
+                        "symbol": t.symbol_str(),
+                        "price": t.price,
+                        "qty": t.qty.abs(),
+                        "side": t.side(),
+                        "time": t.time,
+                    })
+                }
+                EVENT_ORDER_UPDATE => {
+                    let o = &self.data.order_update;
+                    json!({
+                        "symbol": o.symbol_str(),
+                        "client_order_id": o.client_order_id_str(),
+                        "order_id": o.order_id,
+                        "side": if o.side == 1 { "SELL" } else { "BUY" },
+                        "order_type": o.order_type,
+                        "exec_type": o.exec_type,
+                        "status": o.status,
+                        "reduce_only": o.reduce_only,
+                        "price": o.price,
+                        "orig_qty": o.orig_qty,
+                        "last_filled_qty": o.last_filled_qty,
+                        "last_filled_price": o.last_filled_price,
+                        "cum_filled_qty": o.cum_filled_qty,
+                        "avg_price": o.avg_price,
+                        "commission": o.commission,
+                        "realized_pnl": o.realized_pnl,
+                        "time": o.time,
+                    })
+                }
+                EVENT_ACCOUNT_UPDATE => {
+                    let a = &self.data.account_update;
+                    json!({
+                        "symbol": a.symbol_str(),
+                        "reason": a.reason,
+                        "position_amt": a.position_amt,
+                        "entry_price": a.entry_price,
+                        "unrealized_pnl": a.unrealized_pnl,
+                        "wallet_balance": a.wallet_balance,
+                        "balance_change": a.balance_change,
+                        "time": a.time,
+                    })
+                }
+                EVENT_FUNDING_RATE => {
+                    let f = &self.data.funding_rate;
+                    json!({
+                        "symbol": f.symbol_str(),
+                        "above": f.above,
+                        "funding_rate": f.funding_rate,
+                        "threshold": f.threshold,
+                        "mark_price": f.mark_price,
+                        "next_funding_time": f.next_funding_time,
+                        "time": f.time,
+                    })
+                }
+                EVENT_SIGNAL => {
+                    let s = &self.data.signal;
+                    json!({
+                        "payload": serde_json::from_str::<serde_json::Value>(s.payload_str()).unwrap_or_default(),
+                        "time": s.time,
+                    })
+                }
+                EVENT_TIMER => {
+                    let t = &self.data.timer;
+                    json!({
+                        "timer_id": t.timer_id,
+                        "due_ns": t.due_ns,
+                        "fired_ns": t.fired_ns,
+                    })
+                }
+                EVENT_USER_DATA_UNKNOWN => {
+                    let u = &self.data.unknown_user_data;
+                    json!({
+                        "name": u.name_str(),
+                        "payload": u.payload_str(),
+                        "truncated": u.truncated,
+                        "time": u.time,
+                    })
+                }
+                EVENT_ANALYTICS => {
+                    let a = &self.data.analytics;
+                    json!({
+                        "symbol": a.symbol_str(),
+                        "spread_bps": a.spread_bps,
+                        "mid_price": a.mid_price,
+                        "book_imbalance": a.book_imbalance,
+                        "flow_imbalance_1s": a.flow_imbalance_1s,
+                        "flow_imbalance_5s": a.flow_imbalance_5s,
+                        "volume_1s": a.volume_1s,
+                        "volume_5s": a.volume_5s,
+                        "trades_1s": a.trades_1s,
+                        "trades_5s": a.trades_5s,
+                        "time": a.time,
+                    })
+                }
+                EVENT_OPEN_INTEREST => {
+                    let o = &self.data.open_interest;
+                    json!({
+                        "symbol": o.symbol_str(),
+                        "open_interest": o.open_interest,
+                        "open_interest_change_percent": o.open_interest_change_percent,
+                        "long_short_ratio": o.long_short_ratio,
+                        "long_account": o.long_account,
+                        "short_account": o.short_account,
+                        "ratio_time": o.ratio_time,
+                        "time": o.time,
+                    })
+                }
+                _ => serde_json::Value::Null,
+            }
+        };
+
+        json!({
+            "type": self.type_name(),
+            "received_at_ns": self.received_at_ns,
+            "seq": self.seq,
+            "data": data,
+        })
+    }
+}
@@ -0,0 +1,34 @@
+// src/files.rs
+
+use std::io::Write;
+use std::path::Path;
+
+/// Атомарная замена файла: пишем во временный рядом и переименовываем,
+/// чтобы сбой посреди записи не оставил полфайла. Каталог создаётся
+pub fn write_atomic(path: &str, bytes: &[u8]) -> std::io::Result<()> {
+    replace(path, bytes, false)
+}
+
+/// То же для файла, доступного только владельцу (ключи)
+pub fn write_atomic_private(path: &str, bytes: &[u8]) -> std::io::Result<()> {
+    replace(path, bytes, true)
+}
+
+fn replace(path: &str, bytes: &[u8], private: bool) -> std::io::Result<()> {
+    if let Some(dir) = Path::new(path).parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    let tmp = format!("{}.tmp", path);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if private {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    #[cfg(not(unix))]
+    let _ = private;
+    options.open(&tmp)?.write_all(bytes)?;
+    std::fs::rename(&tmp, path)
+}
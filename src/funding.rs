@@ -0,0 +1,369 @@
+// src/funding.rs
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{LazyLock, OnceLock};
+use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast;
+
+use crate::fanout;
+use crate::ffi_types::{pack_str, CEvent, CEventData, CFundingRate, Sequencer, EVENT_FUNDING_RATE};
+
+const PREMIUM_INDEX_URL: &str = "https://fapi.binance.com/fapi/v1/premiumIndex";
+/// Только символы с нестандартным интервалом (1h, 4h); остальные - 8h
+const FUNDING_INFO_URL: &str = "https://fapi.binance.com/fapi/v1/fundingInfo";
+
+/// fundingInfo меняется редко: раз в столько секунд
+const FUNDING_INFO_INTERVAL_SECS: u64 = 600;
+
+/// premiumIndex по всем символам - вес 10, чаще не опрашиваем
+const MIN_REFRESH_SECS: u64 = 5;
+
+/// Интервал funding по умолчанию на Binance Futures
+pub const DEFAULT_INTERVAL_MS: i64 = 8 * 3_600_000;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct FundingConfig {
+    /// Как часто обновляем время и ставки funding (одним запросом по всем символам)
+    pub refresh_secs: u64,
+    /// Порог |ставки| для сканера (0.001 = 0.1%). 0 - сканер не сигналит
+    pub threshold: f64,
+    /// Рассылать пересечения порога стратегиям (EVENT_FUNDING_RATE)
+    pub events: bool,
+    /// POST JSON на этот URL при пересечении порога
+    pub webhook: Option<String>,
+}
+
+impl Default for FundingConfig {
+    fn default() -> Self {
+        Self {
+            refresh_secs: 60,
+            threshold: 0.001,
+            events: false,
+            webhook: None,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// КАЛЕНДАРЬ
+// ═══════════════════════════════════════════════════════════
+//
+// Время следующего funding берётся с биржи, а не выводится из "00:00/08:00/16:00":
+// у части символов интервал 4h или 1h, и биржа его меняет. Между обновлениями
+// прошедший funding сдвигается на интервал, так что сразу после расчёта
+// отдаётся уже следующий.
+
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    /// nextFundingTime из premiumIndex, Unix ms (время биржи)
+    next_funding_time: i64,
+    funding_rate: Option<f64>,
+    mark_price: Option<f64>,
+    /// Unix ms последнего обновления с биржи
+    updated_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FundingInfo {
+    pub symbol: String,
+    /// Unix ms (время биржи)
+    pub next_funding_time: i64,
+    pub interval_hours: i64,
+    /// Ставка, по которой пройдёт ближайший funding (lastFundingRate)
+    pub funding_rate: Option<f64>,
+    pub mark_price: Option<f64>,
+    pub updated_at: i64,
+}
+
+/// SYMBOL -> последние данные premiumIndex
+static CALENDAR: LazyLock<DashMap<String, Slot>> = LazyLock::new(DashMap::new);
+
+/// SYMBOL -> интервал, ms (только символы с нестандартным интервалом)
+static INTERVALS: LazyLock<DashMap<String, i64>> = LazyLock::new(DashMap::new);
+
+/// Интервал funding по символу, ms
+pub fn interval_ms(symbol: &str) -> i64 {
+    INTERVALS.get(&symbol.to_uppercase()).map_or(DEFAULT_INTERVAL_MS, |i| *i)
+}
+
+/// Ближайший funding по символу на момент now_ms (время биржи, Unix ms)
+pub fn next(symbol: &str, now_ms: i64) -> Option<FundingInfo> {
+    let symbol = symbol.to_uppercase();
+    let slot = *CALENDAR.get(&symbol)?;
+    let interval = interval_ms(&symbol);
+
+    let mut next_funding_time = slot.next_funding_time;
+    if next_funding_time <= now_ms {
+        next_funding_time += ((now_ms - next_funding_time) / interval + 1) * interval;
+    }
+    Some(FundingInfo {
+        symbol,
+        next_funding_time,
+        interval_hours: interval / 3_600_000,
+        funding_rate: slot.funding_rate,
+        mark_price: slot.mark_price,
+        updated_at: slot.updated_at,
+    })
+}
+
+/// Время следующего funding по символу, если уже загружено
+pub fn next_funding_time(symbol: &str) -> Option<i64> {
+    next(symbol, chrono::Utc::now().timestamp_millis()).map(|f| f.next_funding_time)
+}
+
+/// Весь календарь, ближайшие funding первыми
+pub fn list() -> Vec<FundingInfo> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let mut list: Vec<_> = CALENDAR.iter().filter_map(|s| next(s.key(), now)).collect();
+    list.sort_by(|a, b| a.next_funding_time.cmp(&b.next_funding_time).then_with(|| a.symbol.cmp(&b.symbol)));
+    list
+}
+
+/// Граница funding после now_ms при интервале от полуночи UTC (для replay,
+/// где nextFundingTime с биржи на момент записи неизвестен)
+pub fn next_boundary(symbol: &str, now_ms: i64) -> i64 {
+    let interval = interval_ms(symbol);
+    (now_ms / interval + 1) * interval
+}
+
+// ═══════════════════════════════════════════════════════════
+// СКАНЕР
+// ═══════════════════════════════════════════════════════════
+//
+// После каждого обновления сравнивает |ставку| USDT-M перпетуалов с порогом.
+// Пересечение (в любую сторону) уходит стратегиям как EVENT_FUNDING_RATE
+// и/или на webhook - по нему funding-стратегии выбирают символ.
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FundingRate {
+    #[serde(flatten)]
+    pub info: FundingInfo,
+    /// Ставка в пересчёте на год, % (ставка * выплат в год * 100)
+    pub annualized_percent: Option<f64>,
+    pub above_threshold: bool,
+}
+
+/// Порог сканера из конфига
+static THRESHOLD: OnceLock<f64> = OnceLock::new();
+
+/// SYMBOL -> |ставка| >= порога на прошлом обновлении
+static ABOVE: LazyLock<DashMap<String, bool>> = LazyLock::new(DashMap::new);
+
+fn threshold() -> f64 {
+    THRESHOLD.get().copied().unwrap_or(0.0)
+}
+
+/// Бессрочный контракт с маржой в USDT (delivery-контракты без funding отсеяны раньше)
+fn is_usdt_perp(symbol: &str) -> bool {
+    symbol.ends_with("USDT")
+}
+
+/// USDT-M перпетуалы по убыванию |ставки|
+pub fn rates() -> Vec<FundingRate> {
+    let threshold = threshold();
+    let mut rates: Vec<_> = list()
+        .into_iter()
+        .filter(|f| is_usdt_perp(&f.symbol))
+        .map(|info| FundingRate {
+            annualized_percent: info.funding_rate.map(|r| r * 24.0 / info.interval_hours.max(1) as f64 * 365.0 * 100.0),
+            above_threshold: threshold > 0.0 && info.funding_rate.is_some_and(|r| r.abs() >= threshold),
+            info,
+        })
+        .collect();
+    rates.sort_by(|a, b| {
+        let abs = |r: &FundingRate| r.info.funding_rate.map_or(0.0, f64::abs);
+        abs(b).total_cmp(&abs(a)).then_with(|| a.info.symbol.cmp(&b.info.symbol))
+    });
+    rates
+}
+
+struct Scanner {
+    config: FundingConfig,
+    event_tx: broadcast::Sender<CEvent>,
+    seqs: Sequencer,
+    client: reqwest::Client,
+}
+
+impl Scanner {
+    /// Пересечения порога после обновления ставок
+    fn scan(&self) {
+        let threshold = self.config.threshold;
+        if threshold <= 0.0 {
+            return;
+        }
+        let now = chrono::Utc::now().timestamp_millis();
+        for slot in CALENDAR.iter().filter(|s| is_usdt_perp(s.key())) {
+            let Some(rate) = slot.funding_rate else { continue };
+            let above = rate.abs() >= threshold;
+            // Первое наблюдение сигналит только выше порога
+            let was_above = ABOVE.insert(slot.key().clone(), above).unwrap_or(false);
+            if above == was_above {
+                continue;
+            }
+            let Some(info) = next(slot.key(), now) else { continue };
+            tracing::info!(
+                "💸 Funding {} {} threshold: {:.4}% (threshold {:.4}%)",
+                info.symbol, if above { "above" } else { "below" }, rate * 100.0, threshold * 100.0,
+            );
+            if self.config.events {
+                self.publish(&info, rate, above);
+            }
+            if let Some(url) = &self.config.webhook {
+                self.notify(url, &info, rate, above);
+            }
+        }
+    }
+
+    fn publish(&self, info: &FundingInfo, rate: f64, above: bool) {
+        let (symbol, symbol_len) = pack_str::<16>(&info.symbol);
+        let received_at_ns = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64;
+        let mut event = CEvent {
+            event_type: EVENT_FUNDING_RATE,
+            data: CEventData {
+                funding_rate: CFundingRate {
+                    symbol,
+                    symbol_len,
+                    above,
+                    funding_rate: rate,
+                    threshold: self.config.threshold,
+                    mark_price: info.mark_price.unwrap_or(0.0),
+                    next_funding_time: info.next_funding_time,
+                    time: info.updated_at,
+                },
+            },
+            received_at_ns,
+            seq: 0,
+        };
+        self.seqs.stamp(&mut event);
+        fanout::publish(event);
+        let _ = self.event_tx.send(event);
+    }
+
+    fn notify(&self, url: &str, info: &FundingInfo, rate: f64, above: bool) {
+        let body = serde_json::json!({
+            "symbol": info.symbol,
+            "above": above,
+            "funding_rate": rate,
+            "threshold": self.config.threshold,
+            "mark_price": info.mark_price,
+            "next_funding_time": info.next_funding_time,
+            "interval_hours": info.interval_hours,
+            "time": info.updated_at,
+        });
+        let request = self.client.post(url).timeout(Duration::from_secs(5)).json(&body);
+        let symbol = info.symbol.clone();
+        tokio::spawn(async move {
+            if let Err(e) = request.send().await.and_then(|r| r.error_for_status()) {
+                tracing::warn!("💸 Funding webhook for {} failed: {}", symbol, e);
+            }
+        });
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ОБНОВЛЕНИЕ
+// ═══════════════════════════════════════════════════════════
+
+/// Фоновое обновление из GET /fapi/v1/premiumIndex и /fapi/v1/fundingInfo
+/// со сканером ставок после каждого обновления
+pub fn spawn_refresh(config: FundingConfig, event_tx: broadcast::Sender<CEvent>) {
+    THRESHOLD.set(config.threshold).ok();
+    let refresh_secs = config.refresh_secs.max(MIN_REFRESH_SECS);
+    let scanner = Scanner {
+        config,
+        event_tx,
+        seqs: Sequencer::default(),
+        client: reqwest::Client::new(),
+    };
+
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        let mut interval = tokio::time::interval(Duration::from_secs(refresh_secs));
+        let info_every = (FUNDING_INFO_INTERVAL_SECS / refresh_secs).max(1);
+        let mut tick = 0u64;
+        loop {
+            interval.tick().await;
+            if tick.is_multiple_of(info_every) {
+                if let Err(e) = refresh_intervals(&client).await {
+                    tracing::warn!("💸 Funding intervals refresh failed: {}", e);
+                }
+            }
+            tick = tick.wrapping_add(1);
+            match refresh(&client).await {
+                Ok(n) => {
+                    tracing::debug!("💸 Funding times refreshed: {} symbols", n);
+                    scanner.scan();
+                }
+                Err(e) => tracing::warn!("💸 Funding times refresh failed: {}", e),
+            }
+        }
+    });
+}
+
+async fn fetch(client: &reqwest::Client, url: &str) -> anyhow::Result<Vec<serde_json::Value>> {
+    let list: serde_json::Value = client
+        .get(url)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    match list {
+        serde_json::Value::Array(items) => Ok(items),
+        _ => anyhow::bail!("Unexpected response from {}", url),
+    }
+}
+
+async fn refresh(client: &reqwest::Client) -> anyhow::Result<usize> {
+    let items = fetch(client, PREMIUM_INDEX_URL).await?;
+    let now = chrono::Utc::now().timestamp_millis();
+
+    for item in items {
+        let (Some(symbol), Some(time)) = (item["symbol"].as_str(), item["nextFundingTime"].as_i64()) else {
+            continue;
+        };
+        // 0 - у символа нет funding (например, delivery-контракты)
+        if time > 0 {
+            let number = |key: &str| item[key].as_str().and_then(|v| v.parse::<f64>().ok());
+            CALENDAR.insert(symbol.to_string(), Slot {
+                next_funding_time: time,
+                funding_rate: number("lastFundingRate"),
+                mark_price: number("markPrice"),
+                updated_at: now,
+            });
+        }
+    }
+    Ok(CALENDAR.len())
+}
+
+async fn refresh_intervals(client: &reqwest::Client) -> anyhow::Result<()> {
+    let items = fetch(client, FUNDING_INFO_URL).await?;
+
+    let fresh: HashMap<String, i64> = items
+        .iter()
+        .filter_map(|item| {
+            let symbol = item["symbol"].as_str()?;
+            let hours = item["fundingIntervalHours"].as_i64().filter(|h| *h > 0)?;
+            Some((symbol.to_string(), hours * 3_600_000))
+        })
+        .collect();
+
+    // Символ, вернувшийся к 8h, из fundingInfo пропадает
+    INTERVALS.retain(|symbol, _| fresh.contains_key(symbol));
+    for (symbol, interval) in fresh {
+        INTERVALS.insert(symbol, interval);
+    }
+    Ok(())
+}
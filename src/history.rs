@@ -0,0 +1,615 @@
+// src/history.rs
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{mpsc, Arc, Mutex};
+use tokio::sync::broadcast;
+
+use crate::orders::OrderManager;
+use crate::strategies::runs::{RunOutcome, RunRecord};
+use crate::user_data::{key_id, RawOrderUpdate, UserDataEvent, UserDataUpdate};
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    pub enabled: bool,
+    pub path: String,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "./data/history.db".to_string(),
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// СТРОКИ
+// ═══════════════════════════════════════════════════════════
+
+/// Ack / cancel / expire ордера (exec_type != TRADE)
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderEventRow {
+    pub time: i64,
+    pub key: String,
+    pub instance_id: Option<String>,
+    pub symbol: String,
+    pub client_order_id: String,
+    pub order_id: i64,
+    pub exec_type: String,
+    pub status: String,
+    pub side: String,
+    pub order_type: String,
+    pub price: f64,
+    pub qty: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FillRow {
+    pub time: i64,
+    pub key: String,
+    pub instance_id: Option<String>,
+    pub symbol: String,
+    pub client_order_id: String,
+    pub order_id: i64,
+    pub side: String,
+    pub price: f64,
+    pub qty: f64,
+    pub commission: f64,
+    pub commission_asset: String,
+    pub realized_pnl: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FundingRow {
+    pub time: i64,
+    pub key: String,
+    /// None если в событии несколько позиций
+    pub symbol: Option<String>,
+    pub asset: String,
+    pub amount: f64,
+}
+
+/// Сумма realized PnL / комиссий за период (по instance_id или ключу)
+#[derive(Debug, Clone, Serialize)]
+pub struct RealizedRow {
+    pub id: String,
+    pub realized_pnl: f64,
+    /// Только комиссии в USDT
+    pub fees: f64,
+    pub fills: u64,
+}
+
+/// Агрегат fills за UTC-день
+#[derive(Debug, Clone)]
+pub struct DailyFillsRow {
+    /// YYYY-MM-DD
+    pub day: String,
+    pub symbol: String,
+    pub instance_id: Option<String>,
+    pub fills: u64,
+    pub realized_pnl: f64,
+    pub fees: f64,
+    pub wins: u64,
+    pub losses: u64,
+}
+
+/// Funding в USDT за UTC-день
+#[derive(Debug, Clone)]
+pub struct DailyFundingRow {
+    pub day: String,
+    pub symbol: Option<String>,
+    pub received: f64,
+    /// Отрицательное число
+    pub paid: f64,
+}
+
+enum Row {
+    OrderEvent(OrderEventRow),
+    Fill(FillRow),
+    Funding(FundingRow),
+    Run(Box<RunRecord>),
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct HistoryQuery {
+    #[serde(default)]
+    pub instance: Option<String>,
+    #[serde(default)]
+    pub symbol: Option<String>,
+    /// Unix ms, включительно
+    #[serde(default)]
+    pub from: Option<i64>,
+    /// Unix ms, не включительно
+    #[serde(default)]
+    pub to: Option<i64>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+const DEFAULT_LIMIT: u32 = 1000;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS order_events (
+    id INTEGER PRIMARY KEY,
+    time INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    instance_id TEXT,
+    symbol TEXT NOT NULL,
+    client_order_id TEXT NOT NULL,
+    order_id INTEGER NOT NULL,
+    exec_type TEXT NOT NULL,
+    status TEXT NOT NULL,
+    side TEXT NOT NULL,
+    order_type TEXT NOT NULL,
+    price REAL NOT NULL,
+    qty REAL NOT NULL
+);
+CREATE TABLE IF NOT EXISTS fills (
+    id INTEGER PRIMARY KEY,
+    time INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    instance_id TEXT,
+    symbol TEXT NOT NULL,
+    client_order_id TEXT NOT NULL,
+    order_id INTEGER NOT NULL,
+    side TEXT NOT NULL,
+    price REAL NOT NULL,
+    qty REAL NOT NULL,
+    commission REAL NOT NULL,
+    commission_asset TEXT NOT NULL,
+    realized_pnl REAL NOT NULL
+);
+CREATE TABLE IF NOT EXISTS funding (
+    id INTEGER PRIMARY KEY,
+    time INTEGER NOT NULL,
+    key TEXT NOT NULL,
+    symbol TEXT,
+    asset TEXT NOT NULL,
+    amount REAL NOT NULL
+);
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    instance_id TEXT NOT NULL,
+    strategy_id TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    mode TEXT NOT NULL,
+    started_at INTEGER NOT NULL,
+    stopped_at INTEGER NOT NULL,
+    outcome TEXT NOT NULL,
+    exit_code INTEGER,
+    reason TEXT,
+    orders_placed INTEGER NOT NULL,
+    orders_filled INTEGER NOT NULL,
+    orders_rejected INTEGER NOT NULL,
+    realized_pnl REAL,
+    fees REAL
+);
+CREATE INDEX IF NOT EXISTS idx_order_events_time ON order_events(time);
+CREATE INDEX IF NOT EXISTS idx_fills_time ON fills(time);
+CREATE INDEX IF NOT EXISTS idx_fills_instance ON fills(instance_id, time);
+CREATE INDEX IF NOT EXISTS idx_funding_time ON funding(time);
+CREATE INDEX IF NOT EXISTS idx_runs_strategy ON runs(strategy_id, stopped_at);
+";
+
+// ═══════════════════════════════════════════════════════════
+// HISTORY
+// ═══════════════════════════════════════════════════════════
+
+/// Журнал исполнений в SQLite.
+///
+/// Пишет acks, fills, отмены и funding из user data stream, а также
+/// завершённые запуски инстансов (runs.rs). Запись идёт в отдельном потоке
+/// пачками, чтобы не блокировать runtime.
+pub struct History {
+    conn: Arc<Mutex<Connection>>,
+    row_tx: mpsc::Sender<Row>,
+}
+
+impl History {
+    pub fn open(
+        path: &str,
+        orders: Arc<OrderManager>,
+        user_rx: broadcast::Receiver<UserDataUpdate>,
+    ) -> anyhow::Result<Arc<Self>> {
+        if let Some(dir) = Path::new(path).parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(SCHEMA)?;
+
+        let conn = Arc::new(Mutex::new(conn));
+        let (row_tx, row_rx) = mpsc::channel::<Row>();
+
+        {
+            let conn = conn.clone();
+            std::thread::Builder::new()
+                .name("history-writer".into())
+                .spawn(move || Self::writer_loop(conn, row_rx))?;
+        }
+
+        tokio::spawn(Self::user_data_loop(orders, user_rx, row_tx.clone()));
+
+        tracing::info!("🗄️ History database at '{}'", path);
+        Ok(Arc::new(Self { conn, row_tx }))
+    }
+
+    /// Завершённый запуск инстанса - в очередь записи
+    pub fn record_run(&self, run: RunRecord) {
+        if self.row_tx.send(Row::Run(Box::new(run))).is_err() {
+            tracing::error!("🗄️ History writer stopped");
+        }
+    }
+
+    /// Запуски сразу, в обход очереди: при остановке ядра поток записи
+    /// уже не успеет
+    pub fn write_runs(&self, runs: Vec<RunRecord>) -> anyhow::Result<()> {
+        let batch: Vec<Row> = runs.into_iter().map(|r| Row::Run(Box::new(r))).collect();
+        Ok(Self::write_batch(&mut self.conn.lock().unwrap(), &batch)?)
+    }
+
+    async fn user_data_loop(
+        orders: Arc<OrderManager>,
+        mut rx: broadcast::Receiver<UserDataUpdate>,
+        row_tx: mpsc::Sender<Row>,
+    ) {
+        loop {
+            match rx.recv().await {
+                Ok(update) => {
+                    for row in Self::to_rows(&orders, &update) {
+                        if row_tx.send(row).is_err() {
+                            tracing::error!("🗄️ History writer stopped");
+                            return;
+                        }
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("🗄️ History lagged {} user data events", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    fn to_rows(orders: &OrderManager, update: &UserDataUpdate) -> Vec<Row> {
+        let key = key_id(&update.api_key);
+
+        match &update.event {
+            UserDataEvent::OrderTradeUpdate(o) => {
+                let instance_id = orders.get(&o.client_order_id).and_then(|r| r.instance_id);
+                vec![Self::order_row(o, key, instance_id)]
+            }
+
+            UserDataEvent::AccountUpdate(a) if a.reason == "FUNDING_FEE" => {
+                let symbol = match a.positions.as_slice() {
+                    [p] => Some(p.symbol.clone()),
+                    _ => None,
+                };
+                a.balances
+                    .iter()
+                    .filter_map(|b| {
+                        let amount: f64 = b.balance_change.parse().ok()?;
+                        (amount != 0.0).then(|| Row::Funding(FundingRow {
+                            time: a.event_time,
+                            key: key.clone(),
+                            symbol: symbol.clone(),
+                            asset: b.asset.clone(),
+                            amount,
+                        }))
+                    })
+                    .collect()
+            }
+
+            _ => Vec::new(),
+        }
+    }
+
+    fn order_row(o: &RawOrderUpdate, key: String, instance_id: Option<String>) -> Row {
+        if o.is_fill() {
+            return Row::Fill(FillRow {
+                time: o.trade_time,
+                key,
+                instance_id,
+                symbol: o.symbol.clone(),
+                client_order_id: o.client_order_id.clone(),
+                order_id: o.order_id,
+                side: o.side.clone(),
+                price: o.last_price(),
+                qty: o.last_qty(),
+                commission: o.commission.as_deref().and_then(|n| n.parse().ok()).unwrap_or(0.0),
+                commission_asset: o.commission_asset.clone().unwrap_or_default(),
+                realized_pnl: o.realized(),
+            });
+        }
+
+        Row::OrderEvent(OrderEventRow {
+            time: o.event_time,
+            key,
+            instance_id,
+            symbol: o.symbol.clone(),
+            client_order_id: o.client_order_id.clone(),
+            order_id: o.order_id,
+            exec_type: o.exec_type.clone(),
+            status: o.status.clone(),
+            side: o.side.clone(),
+            order_type: o.order_type.clone(),
+            price: o.price.parse().unwrap_or(0.0),
+            qty: o.orig_qty.parse().unwrap_or(0.0),
+        })
+    }
+
+    fn writer_loop(conn: Arc<Mutex<Connection>>, rx: mpsc::Receiver<Row>) {
+        while let Ok(first) = rx.recv() {
+            // Всё, что накопилось, пишем одной транзакцией
+            let mut batch = vec![first];
+            batch.extend(rx.try_iter());
+
+            let mut conn = conn.lock().unwrap();
+            if let Err(e) = Self::write_batch(&mut conn, &batch) {
+                tracing::error!("🗄️ History write failed ({} rows): {}", batch.len(), e);
+            }
+        }
+    }
+
+    fn write_batch(conn: &mut Connection, batch: &[Row]) -> rusqlite::Result<()> {
+        let tx = conn.transaction()?;
+        for row in batch {
+            match row {
+                Row::OrderEvent(r) => {
+                    tx.execute(
+                        "INSERT INTO order_events (time, key, instance_id, symbol, client_order_id, order_id, exec_type, status, side, order_type, price, qty)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                        params![r.time, r.key, r.instance_id, r.symbol, r.client_order_id, r.order_id, r.exec_type, r.status, r.side, r.order_type, r.price, r.qty],
+                    )?;
+                }
+                Row::Fill(r) => {
+                    tx.execute(
+                        "INSERT INTO fills (time, key, instance_id, symbol, client_order_id, order_id, side, price, qty, commission, commission_asset, realized_pnl)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                        params![r.time, r.key, r.instance_id, r.symbol, r.client_order_id, r.order_id, r.side, r.price, r.qty, r.commission, r.commission_asset, r.realized_pnl],
+                    )?;
+                }
+                Row::Funding(r) => {
+                    tx.execute(
+                        "INSERT INTO funding (time, key, symbol, asset, amount) VALUES (?1, ?2, ?3, ?4, ?5)",
+                        params![r.time, r.key, r.symbol, r.asset, r.amount],
+                    )?;
+                }
+                Row::Run(r) => {
+                    tx.execute(
+                        "INSERT INTO runs (instance_id, strategy_id, symbol, mode, started_at, stopped_at, outcome, exit_code, reason, orders_placed, orders_filled, orders_rejected, realized_pnl, fees)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                        params![
+                            r.instance_id, r.strategy_id, r.symbol, r.mode, r.started_at, r.stopped_at,
+                            r.outcome.as_str(), r.exit_code, r.reason, r.orders_placed as i64,
+                            r.orders_filled as i64, r.orders_rejected as i64, r.realized_pnl, r.fees,
+                        ],
+                    )?;
+                }
+            }
+        }
+        tx.commit()
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // ЗАПРОСЫ
+    // ═══════════════════════════════════════════════════════════
+
+    pub fn fills(&self, q: &HistoryQuery) -> anyhow::Result<Vec<FillRow>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT time, key, instance_id, symbol, client_order_id, order_id, side, price, qty, commission, commission_asset, realized_pnl
+             FROM fills
+             WHERE (?1 IS NULL OR instance_id = ?1)
+               AND (?2 IS NULL OR symbol = ?2)
+               AND time >= ?3 AND time < ?4
+             ORDER BY time DESC
+             LIMIT ?5",
+        )?;
+
+        let rows = stmt.query_map(
+            params![
+                q.instance,
+                q.symbol.as_ref().map(|s| s.to_uppercase()),
+                q.from.unwrap_or(0),
+                q.to.unwrap_or(i64::MAX),
+                q.limit.unwrap_or(DEFAULT_LIMIT),
+            ],
+            |r| {
+                Ok(FillRow {
+                    time: r.get(0)?,
+                    key: r.get(1)?,
+                    instance_id: r.get(2)?,
+                    symbol: r.get(3)?,
+                    client_order_id: r.get(4)?,
+                    order_id: r.get(5)?,
+                    side: r.get(6)?,
+                    price: r.get(7)?,
+                    qty: r.get(8)?,
+                    commission: r.get(9)?,
+                    commission_asset: r.get(10)?,
+                    realized_pnl: r.get(11)?,
+                })
+            },
+        )?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    pub fn funding(&self, q: &HistoryQuery) -> anyhow::Result<Vec<FundingRow>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT time, key, symbol, asset, amount
+             FROM funding
+             WHERE (?1 IS NULL OR symbol = ?1)
+               AND time >= ?2 AND time < ?3
+             ORDER BY time DESC
+             LIMIT ?4",
+        )?;
+
+        let rows = stmt.query_map(
+            params![
+                q.symbol.as_ref().map(|s| s.to_uppercase()),
+                q.from.unwrap_or(0),
+                q.to.unwrap_or(i64::MAX),
+                q.limit.unwrap_or(DEFAULT_LIMIT),
+            ],
+            |r| {
+                Ok(FundingRow {
+                    time: r.get(0)?,
+                    key: r.get(1)?,
+                    symbol: r.get(2)?,
+                    asset: r.get(3)?,
+                    amount: r.get(4)?,
+                })
+            },
+        )?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Realized PnL (realizedProfit биржи) и комиссии за период.
+    /// by_instance = false - группировка по ключу.
+    pub fn realized(&self, from: i64, to: i64, by_instance: bool) -> anyhow::Result<Vec<RealizedRow>> {
+        let group = if by_instance { "instance_id" } else { "key" };
+        let sql = format!(
+            "SELECT {group}, SUM(realized_pnl), SUM(CASE WHEN commission_asset = 'USDT' THEN commission ELSE 0 END), COUNT(*)
+             FROM fills
+             WHERE {group} IS NOT NULL AND time >= ?1 AND time < ?2
+             GROUP BY {group}
+             ORDER BY {group}"
+        );
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params![from, to], |r| {
+            Ok(RealizedRow {
+                id: r.get(0)?,
+                realized_pnl: r.get(1)?,
+                fees: r.get(2)?,
+                fills: r.get::<_, i64>(3)? as u64,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Funding в USDT за период по ключам
+    pub fn funding_by_key(&self, from: i64, to: i64) -> anyhow::Result<Vec<(String, f64)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT key, SUM(amount) FROM funding
+             WHERE asset = 'USDT' AND time >= ?1 AND time < ?2
+             GROUP BY key",
+        )?;
+        let rows = stmt.query_map(params![from, to], |r| Ok((r.get(0)?, r.get(1)?)))?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Fills, сгруппированные по UTC-дню, символу и инстансу
+    pub fn daily_fills(&self, from: i64, to: i64) -> anyhow::Result<Vec<DailyFillsRow>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT date(time / 1000, 'unixepoch') AS day, symbol, instance_id,
+                    COUNT(*),
+                    SUM(realized_pnl),
+                    SUM(CASE WHEN commission_asset = 'USDT' THEN commission ELSE 0 END),
+                    SUM(CASE WHEN realized_pnl > 0 THEN 1 ELSE 0 END),
+                    SUM(CASE WHEN realized_pnl < 0 THEN 1 ELSE 0 END)
+             FROM fills
+             WHERE time >= ?1 AND time < ?2
+             GROUP BY day, symbol, instance_id
+             ORDER BY day, symbol, instance_id",
+        )?;
+        let rows = stmt.query_map(params![from, to], |r| {
+            Ok(DailyFillsRow {
+                day: r.get(0)?,
+                symbol: r.get(1)?,
+                instance_id: r.get(2)?,
+                fills: r.get::<_, i64>(3)? as u64,
+                realized_pnl: r.get(4)?,
+                fees: r.get(5)?,
+                wins: r.get::<_, i64>(6)? as u64,
+                losses: r.get::<_, i64>(7)? as u64,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Funding в USDT по UTC-дню и символу
+    pub fn daily_funding(&self, from: i64, to: i64) -> anyhow::Result<Vec<DailyFundingRow>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT date(time / 1000, 'unixepoch') AS day, symbol,
+                    SUM(CASE WHEN amount > 0 THEN amount ELSE 0 END),
+                    SUM(CASE WHEN amount < 0 THEN amount ELSE 0 END)
+             FROM funding
+             WHERE asset = 'USDT' AND time >= ?1 AND time < ?2
+             GROUP BY day, symbol
+             ORDER BY day, symbol",
+        )?;
+        let rows = stmt.query_map(params![from, to], |r| {
+            Ok(DailyFundingRow {
+                day: r.get(0)?,
+                symbol: r.get(1)?,
+                received: r.get(2)?,
+                paid: r.get(3)?,
+            })
+        })?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// Завершённые запуски, новые первыми
+    pub fn runs(&self, strategy_id: Option<&str>, symbol: Option<&str>, limit: usize) -> anyhow::Result<Vec<RunRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT instance_id, strategy_id, symbol, mode, started_at, stopped_at, outcome, exit_code, reason,
+                    orders_placed, orders_filled, orders_rejected, realized_pnl, fees
+             FROM runs
+             WHERE (?1 IS NULL OR strategy_id = ?1)
+               AND (?2 IS NULL OR symbol = ?2)
+             ORDER BY stopped_at DESC, id DESC
+             LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(
+            params![strategy_id, symbol.map(|s| s.to_uppercase()), limit.min(i64::MAX as usize) as i64],
+            |r| {
+                let outcome: String = r.get(6)?;
+                Ok(RunRecord {
+                    instance_id: r.get(0)?,
+                    strategy_id: r.get(1)?,
+                    symbol: r.get(2)?,
+                    mode: r.get(3)?,
+                    started_at: r.get(4)?,
+                    stopped_at: r.get(5)?,
+                    outcome: RunOutcome::parse(&outcome).ok_or_else(|| rusqlite::Error::FromSqlConversionFailure(
+                        6,
+                        rusqlite::types::Type::Text,
+                        format!("unknown run outcome '{}'", outcome).into(),
+                    ))?,
+                    exit_code: r.get(7)?,
+                    reason: r.get(8)?,
+                    orders_placed: r.get::<_, i64>(9)? as u64,
+                    orders_filled: r.get::<_, i64>(10)? as u64,
+                    orders_rejected: r.get::<_, i64>(11)? as u64,
+                    realized_pnl: r.get(12)?,
+                    fees: r.get(13)?,
+                })
+            },
+        )?;
+
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+}
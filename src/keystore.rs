@@ -0,0 +1,266 @@
+// src/keystore.rs
+
+use anyhow::Context;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::files;
+use crate::user_data::key_id;
+
+const NONCE_LEN: usize = 12;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct KeystoreConfig {
+    /// Зашифрованное хранилище (alias -> ключи)
+    pub path: String,
+    /// Мастер-ключ: 64 hex-символа в переменной окружения HFT_MASTER_KEY,
+    /// иначе читается (или создаётся) этот файл. Он должен лежать вне
+    /// каталога хранилища: копия каталога с ключами не должна содержать
+    /// и то, чем они расшифровываются. Без обоих keystore выключен:
+    /// алиасы не работают, ключи передаются в запросах как есть
+    pub master_key_path: Option<String>,
+}
+
+impl Default for KeystoreConfig {
+    fn default() -> Self {
+        Self {
+            path: "./data/keys.json".to_string(),
+            master_key_path: None,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ТИПЫ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Clone)]
+pub struct Credentials {
+    pub api_key: String,
+    pub secret_key: String,
+}
+
+/// Запись на диске: hex(nonce || ciphertext), alias - associated data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredKey {
+    api_key: String,
+    secret_key: String,
+    created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyInfo {
+    pub alias: String,
+    /// Первые 8 символов api_key
+    pub key: String,
+    pub created_at: i64,
+}
+
+// ═══════════════════════════════════════════════════════════
+// KEYSTORE
+// ═══════════════════════════════════════════════════════════
+
+/// Хранилище Binance-ключей под алиасами.
+///
+/// Секреты шифруются ChaCha20-Poly1305 и на диск попадают только
+/// в зашифрованном виде. Стратегии и HTTP-запросы передают alias
+/// вместо api_key, ядро подставляет ключи при отправке ордера.
+pub struct Keystore {
+    cipher: ChaCha20Poly1305,
+    path: String,
+    keys: DashMap<String, (Credentials, i64)>,
+    /// Сериализует запись файла
+    file_lock: Mutex<()>,
+}
+
+static KEYSTORE: OnceLock<Arc<Keystore>> = OnceLock::new();
+
+/// None - мастер-ключ не настроен, keystore выключен. Ошибка - ключ
+/// настроен, но негоден, или хранилище не расшифровывается
+pub fn init(config: &KeystoreConfig) -> anyhow::Result<Option<Arc<Keystore>>> {
+    let Some(master) = load_master_key(config)? else {
+        return Ok(None);
+    };
+    let keystore = Arc::new(Keystore::open(config, master)?);
+    KEYSTORE.set(keystore.clone()).ok();
+    Ok(Some(keystore))
+}
+
+/// Ключи по alias (None - keystore не открыт или alias неизвестен)
+pub fn account(alias: &str) -> Option<Credentials> {
+    KEYSTORE.get()?.get(alias)
+}
+
+/// Подставляет ключи, если вместо api_key передан alias.
+/// Иначе возвращает переданные значения как есть.
+pub fn resolve(api_key: &str, secret_key: &str) -> Credentials {
+    KEYSTORE
+        .get()
+        .and_then(|k| k.get(api_key))
+        .unwrap_or_else(|| Credentials {
+            api_key: api_key.to_string(),
+            secret_key: secret_key.to_string(),
+        })
+}
+
+impl Keystore {
+    fn open(config: &KeystoreConfig, master: [u8; 32]) -> anyhow::Result<Self> {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&master));
+
+        let keystore = Self {
+            cipher,
+            path: config.path.clone(),
+            keys: DashMap::new(),
+            file_lock: Mutex::new(()),
+        };
+
+        if Path::new(&config.path).exists() {
+            let content = std::fs::read_to_string(&config.path)?;
+            let stored: BTreeMap<String, StoredKey> = serde_json::from_str(&content)
+                .with_context(|| format!("Invalid keystore '{}'", config.path))?;
+
+            for (alias, s) in stored {
+                let credentials = Credentials {
+                    api_key: keystore.decrypt(&alias, &s.api_key)?,
+                    secret_key: keystore.decrypt(&alias, &s.secret_key)?,
+                };
+                keystore.keys.insert(alias, (credentials, s.created_at));
+            }
+        }
+
+        tracing::info!("🔐 Keystore loaded: {} keys", keystore.keys.len());
+        Ok(keystore)
+    }
+
+    pub fn get(&self, alias: &str) -> Option<Credentials> {
+        self.keys.get(alias).map(|e| e.0.clone())
+    }
+
+    pub fn contains(&self, alias: &str) -> bool {
+        self.keys.contains_key(alias)
+    }
+
+    pub fn list(&self) -> Vec<KeyInfo> {
+        let mut list: Vec<KeyInfo> = self.keys
+            .iter()
+            .map(|e| KeyInfo {
+                alias: e.key().clone(),
+                key: key_id(&e.value().0.api_key),
+                created_at: e.value().1,
+            })
+            .collect();
+        list.sort_by(|a, b| a.alias.cmp(&b.alias));
+        list
+    }
+
+    /// Добавляет или заменяет ключи под alias
+    pub fn put(&self, alias: &str, credentials: Credentials) -> anyhow::Result<KeyInfo> {
+        if alias.is_empty() || !alias.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            anyhow::bail!("Alias must be non-empty and contain only [A-Za-z0-9_-]");
+        }
+        if credentials.api_key.is_empty() || credentials.secret_key.is_empty() {
+            anyhow::bail!("api_key and secret_key are required");
+        }
+
+        let created_at = chrono::Utc::now().timestamp();
+        let info = KeyInfo {
+            alias: alias.to_string(),
+            key: key_id(&credentials.api_key),
+            created_at,
+        };
+
+        self.keys.insert(alias.to_string(), (credentials, created_at));
+        self.save()?;
+
+        tracing::info!("🔐 Key '{}' stored ({})", alias, info.key);
+        Ok(info)
+    }
+
+    pub fn remove(&self, alias: &str) -> anyhow::Result<()> {
+        if self.keys.remove(alias).is_none() {
+            anyhow::bail!("Key '{}' not found", alias);
+        }
+        self.save()?;
+        tracing::info!("🔐 Key '{}' removed", alias);
+        Ok(())
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        let _guard = self.file_lock.lock().unwrap();
+
+        let mut stored = BTreeMap::new();
+        for e in self.keys.iter() {
+            let (credentials, created_at) = e.value();
+            stored.insert(e.key().clone(), StoredKey {
+                api_key: self.encrypt(e.key(), &credentials.api_key)?,
+                secret_key: self.encrypt(e.key(), &credentials.secret_key)?,
+                created_at: *created_at,
+            });
+        }
+
+        files::write_atomic_private(&self.path, serde_json::to_string_pretty(&stored)?.as_bytes())?;
+        Ok(())
+    }
+
+    fn encrypt(&self, alias: &str, plain: &str) -> anyhow::Result<String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher
+            .encrypt(&nonce, Payload { msg: plain.as_bytes(), aad: alias.as_bytes() })
+            .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&ciphertext);
+        Ok(hex::encode(out))
+    }
+
+    fn decrypt(&self, alias: &str, encoded: &str) -> anyhow::Result<String> {
+        let bytes = hex::decode(encoded)?;
+        if bytes.len() <= NONCE_LEN {
+            anyhow::bail!("Key '{}' is corrupted", alias);
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let plain = self.cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: alias.as_bytes() })
+            .map_err(|_| anyhow::anyhow!("Cannot decrypt key '{}': wrong master key?", alias))?;
+        Ok(String::from_utf8(plain)?)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// МАСТЕР-КЛЮЧ
+// ═══════════════════════════════════════════════════════════
+
+fn load_master_key(config: &KeystoreConfig) -> anyhow::Result<Option<[u8; 32]>> {
+    let dir = Path::new(&config.path).parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let encoded = match (std::env::var("HFT_MASTER_KEY"), config.master_key_path.as_deref()) {
+        (Ok(v), _) => v,
+        (Err(_), None) => return Ok(None),
+        (Err(_), Some(path)) if std::path::absolute(path)?.starts_with(std::path::absolute(dir)?) => anyhow::bail!(
+            "keystore.master_key_path '{}' is inside the keystore directory {:?}: move the key out of it",
+            path, dir
+        ),
+        (Err(_), Some(path)) if Path::new(path).exists() => std::fs::read_to_string(path)?,
+        (Err(_), Some(path)) => {
+            let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+            files::write_atomic_private(path, hex::encode(key).as_bytes())?;
+            tracing::warn!("🔐 Generated new master key at '{}' - back it up separately from the keystore", path);
+            hex::encode(key)
+        }
+    };
+
+    let bytes = hex::decode(encoded.trim()).context("Master key must be hex")?;
+    bytes
+        .try_into()
+        .map(Some)
+        .map_err(|_| anyhow::anyhow!("Master key must be 32 bytes (64 hex chars)"))
+}
@@ -0,0 +1,237 @@
+// src/kv.rs
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::files;
+
+/// Изменения пишутся на диск не чаще раза в столько
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+pub const MAX_KEY_LEN: usize = 128;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct KvConfig {
+    /// Сохранять состояние на диск (иначе живёт до перезапуска ядра)
+    pub persist: bool,
+    /// Файл состояния (JSON)
+    pub path: String,
+    /// Ключей на стратегию
+    pub max_keys: usize,
+    /// Размер значения, байт
+    pub max_value_bytes: usize,
+}
+
+impl Default for KvConfig {
+    fn default() -> Self {
+        Self {
+            persist: true,
+            path: "./strategies/kv.json".to_string(),
+            max_keys: 256,
+            max_value_bytes: 4096,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ХРАНИЛИЩЕ
+// ═══════════════════════════════════════════════════════════
+
+type Entries = BTreeMap<String, String>;
+
+/// strategy_id -> ключи. Общее для всех инстансов стратегии
+static STORE: LazyLock<DashMap<String, Entries>> = LazyLock::new(DashMap::new);
+
+/// instance_id -> копия состояния стратегии для replay и dry run:
+/// читают реальное состояние, но пишут только в свою копию
+static SANDBOXES: LazyLock<DashMap<String, Entries>> = LazyLock::new(DashMap::new);
+
+static CONFIG: OnceLock<KvConfig> = OnceLock::new();
+
+static DIRTY: AtomicBool = AtomicBool::new(false);
+
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KvScope {
+    pub strategy_id: String,
+    pub keys: usize,
+    pub bytes: usize,
+}
+
+/// Загружает сохранённое состояние и запускает фоновую запись
+pub fn init(config: KvConfig) -> anyhow::Result<()> {
+    if config.persist && Path::new(&config.path).exists() {
+        let content = std::fs::read_to_string(&config.path)?;
+        let stored: BTreeMap<String, Entries> = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid KV file '{}': {}", config.path, e))?;
+        for (strategy_id, entries) in stored {
+            STORE.insert(strategy_id, entries);
+        }
+    }
+    tracing::info!("🗃️ KV store: {} strategies{}", STORE.len(), if config.persist { "" } else { " (in-memory)" });
+
+    if config.persist {
+        tokio::spawn(async {
+            loop {
+                tokio::time::sleep(FLUSH_INTERVAL).await;
+                if DIRTY.swap(false, Ordering::Relaxed) {
+                    if let Err(e) = flush() {
+                        DIRTY.store(true, Ordering::Relaxed);
+                        tracing::error!("❌ KV flush failed: {}", e);
+                    }
+                }
+            }
+        });
+    }
+    CONFIG.set(config).ok();
+    Ok(())
+}
+
+/// Записывает несохранённые изменения при остановке ядра: фоновая запись
+/// раз в FLUSH_INTERVAL последние из них не застанет
+pub fn shutdown() {
+    if config().persist && DIRTY.swap(false, Ordering::Relaxed) {
+        if let Err(e) = flush() {
+            tracing::error!("❌ KV flush failed: {}", e);
+        }
+    }
+}
+
+fn config() -> KvConfig {
+    CONFIG.get().cloned().unwrap_or_default()
+}
+
+/// Стратегия инстанса: "strategy:SYMBOL" -> "strategy"
+fn strategy_of(instance_id: &str) -> &str {
+    instance_id.split(':').next().unwrap_or(instance_id)
+}
+
+fn check(entries: &Entries, key: &str, value: &str) -> anyhow::Result<()> {
+    let config = config();
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        anyhow::bail!("Key must be 1..={} bytes", MAX_KEY_LEN);
+    }
+    if value.len() > config.max_value_bytes {
+        anyhow::bail!("Value is {} bytes, max {}", value.len(), config.max_value_bytes);
+    }
+    if !entries.contains_key(key) && entries.len() >= config.max_keys {
+        anyhow::bail!("Too many keys (max {})", config.max_keys);
+    }
+    Ok(())
+}
+
+// ═══════════════════════════════════════════════════════════
+// ДОСТУП ИЗ СТРАТЕГИИ (по instance_id)
+// ═══════════════════════════════════════════════════════════
+
+pub fn get(instance_id: &str, key: &str) -> Option<String> {
+    if let Some(sandbox) = SANDBOXES.get(instance_id) {
+        return sandbox.get(key).cloned();
+    }
+    STORE.get(strategy_of(instance_id))?.get(key).cloned()
+}
+
+pub fn set(instance_id: &str, key: &str, value: &str) -> anyhow::Result<()> {
+    if let Some(mut sandbox) = SANDBOXES.get_mut(instance_id) {
+        check(&sandbox, key, value)?;
+        sandbox.insert(key.to_string(), value.to_string());
+        return Ok(());
+    }
+    put(strategy_of(instance_id), key, value)
+}
+
+pub fn delete(instance_id: &str, key: &str) -> bool {
+    if let Some(mut sandbox) = SANDBOXES.get_mut(instance_id) {
+        return sandbox.remove(key).is_some();
+    }
+    remove(strategy_of(instance_id), key)
+}
+
+/// Изолирует инстанс (replay, dry run) от общего состояния стратегии
+pub fn sandbox(instance_id: &str) {
+    let entries = STORE.get(strategy_of(instance_id)).map(|e| e.clone()).unwrap_or_default();
+    SANDBOXES.insert(instance_id.to_string(), entries);
+}
+
+/// Отбрасывает копию состояния инстанса после его остановки
+pub fn release(instance_id: &str) {
+    SANDBOXES.remove(instance_id);
+}
+
+// ═══════════════════════════════════════════════════════════
+// ДОСТУП ИЗ API (по strategy_id)
+// ═══════════════════════════════════════════════════════════
+
+pub fn list() -> Vec<KvScope> {
+    let mut list: Vec<_> = STORE
+        .iter()
+        .map(|e| KvScope {
+            strategy_id: e.key().clone(),
+            keys: e.len(),
+            bytes: e.iter().map(|(k, v)| k.len() + v.len()).sum(),
+        })
+        .collect();
+    list.sort_by(|a, b| a.strategy_id.cmp(&b.strategy_id));
+    list
+}
+
+pub fn entries(strategy_id: &str) -> Option<Entries> {
+    STORE.get(strategy_id).map(|e| e.clone())
+}
+
+pub fn put(strategy_id: &str, key: &str, value: &str) -> anyhow::Result<()> {
+    let mut entries = STORE.entry(strategy_id.to_string()).or_default();
+    if let Err(e) = check(&entries, key, value) {
+        drop(entries);
+        STORE.remove_if(strategy_id, |_, e| e.is_empty());
+        return Err(e);
+    }
+    entries.insert(key.to_string(), value.to_string());
+    DIRTY.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+pub fn remove(strategy_id: &str, key: &str) -> bool {
+    let Some(mut entries) = STORE.get_mut(strategy_id) else { return false };
+    let removed = entries.remove(key).is_some();
+    if entries.is_empty() {
+        drop(entries);
+        STORE.remove_if(strategy_id, |_, e| e.is_empty());
+    }
+    if removed {
+        DIRTY.store(true, Ordering::Relaxed);
+    }
+    removed
+}
+
+/// Всё состояние стратегии (при удалении стратегии или вручную)
+pub fn clear(strategy_id: &str) -> bool {
+    let removed = STORE.remove(strategy_id).is_some();
+    if removed {
+        DIRTY.store(true, Ordering::Relaxed);
+    }
+    removed
+}
+
+fn flush() -> anyhow::Result<()> {
+    let config = config();
+    let _guard = FILE_LOCK.lock().unwrap();
+    let stored: BTreeMap<String, Entries> = STORE
+        .iter()
+        .map(|e| (e.key().clone(), e.value().clone()))
+        .collect();
+
+    files::write_atomic(&config.path, serde_json::to_string_pretty(&stored)?.as_bytes())?;
+    Ok(())
+}
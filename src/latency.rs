@@ -0,0 +1,420 @@
+// src/latency.rs
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicI64, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock};
+use std::time::SystemTime;
+
+use crate::alerts::{self, AlertLevel};
+use crate::notifications::{self, NotifyKind};
+
+/// Корзины по степеням двойки в микросекундах: [0,1), [1,2), [2,4) ... [2^30, ∞)
+const BUCKETS: usize = 32;
+
+// ═══════════════════════════════════════════════════════════
+// СТАДИИ
+// ═══════════════════════════════════════════════════════════
+
+/// Стадии пути tick -> order. Все метки - wall clock в ns
+/// (та же шкала, что и CEvent.received_at_ns).
+#[derive(Debug, Clone, Copy)]
+pub enum Stage {
+    /// Чтение из market WS -> CEvent готов
+    Parse,
+    /// CEvent готов -> broadcast::send вернулся
+    Broadcast,
+    /// Раздача CEvent по каналам всех стратегий (fan-out целиком)
+    Fanout,
+    /// Чтение из WS -> событие положено в канал стратегии
+    Enqueue,
+    /// Последний тик, переданный стратегии -> вызов place_order
+    TickToOrder,
+    /// send_command -> подписанный запрос записан в trade WS
+    OrderToWire,
+    /// Время события bookTicker на бирже -> чтение из market WS
+    /// (с поправкой на offset часов)
+    BookTickerArrival,
+    /// send_command -> ответ биржи на order.place / order.cancel
+    OrderAck,
+}
+
+impl Stage {
+    const ALL: [Stage; 8] = [
+        Stage::Parse,
+        Stage::Broadcast,
+        Stage::Fanout,
+        Stage::Enqueue,
+        Stage::TickToOrder,
+        Stage::OrderToWire,
+        Stage::BookTickerArrival,
+        Stage::OrderAck,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Stage::Parse => "parse",
+            Stage::Broadcast => "broadcast",
+            Stage::Fanout => "fanout",
+            Stage::Enqueue => "enqueue",
+            Stage::TickToOrder => "tick_to_order",
+            Stage::OrderToWire => "order_to_wire",
+            Stage::BookTickerArrival => "book_ticker_arrival",
+            Stage::OrderAck => "order_ack",
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// БЮДЖЕТЫ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LatencyConfig {
+    /// Порог стадии в ms: {"book_ticker_arrival": 50, "order_ack": 300}.
+    /// Пусто - бюджеты выключены
+    pub budgets_ms: HashMap<String, f64>,
+    /// Сколько превышений подряд переводят стадию в degraded
+    pub breaches: u32,
+    /// Сколько секунд без превышений нужно для восстановления
+    pub recover_secs: u64,
+}
+
+impl Default for LatencyConfig {
+    fn default() -> Self {
+        Self { budgets_ms: HashMap::new(), breaches: 3, recover_secs: 30 }
+    }
+}
+
+/// Состояние бюджета стадии: только atomics, проверка идёт на горячем пути
+struct Budget {
+    limit_ns: u64,
+    breaches: u32,
+    recover_ms: i64,
+    consecutive: AtomicU32,
+    last_ns: AtomicU64,
+    /// Unix ms, 0 - не в degraded
+    degraded_since: AtomicI64,
+    last_breach: AtomicI64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DegradedStage {
+    pub stage: &'static str,
+    pub budget_ms: f64,
+    /// Последний замер стадии
+    pub last_ms: f64,
+    /// Unix ms
+    pub since: i64,
+}
+
+impl Budget {
+    fn observe(&self, stage: Stage, ns: u64) {
+        self.last_ns.store(ns, Ordering::Relaxed);
+        if ns <= self.limit_ns {
+            self.consecutive.store(0, Ordering::Relaxed);
+            let since = self.degraded_since.load(Ordering::Relaxed);
+            if since == 0 {
+                return;
+            }
+            let now = chrono::Utc::now().timestamp_millis();
+            if now - self.last_breach.load(Ordering::Relaxed) >= self.recover_ms
+                && self.degraded_since.compare_exchange(since, 0, Ordering::Relaxed, Ordering::Relaxed).is_ok()
+            {
+                let message = format!("Latency '{}' back within budget after {}s", stage.name(), (now - since) / 1000);
+                tracing::info!("🟢 {}", message);
+                notifications::notify(NotifyKind::LatencyDegraded, None, message);
+            }
+            return;
+        }
+
+        let now = chrono::Utc::now().timestamp_millis();
+        self.last_breach.store(now, Ordering::Relaxed);
+        let count = self.consecutive.fetch_add(1, Ordering::Relaxed) + 1;
+        if count >= self.breaches
+            && self.degraded_since.compare_exchange(0, now, Ordering::Relaxed, Ordering::Relaxed).is_ok()
+        {
+            let message = format!(
+                "Latency '{}' {:.1}ms exceeds budget {}ms ({} in a row)",
+                stage.name(), ns as f64 / 1e6, self.limit_ns as f64 / 1e6, count,
+            );
+            alerts::emit(AlertLevel::Warning, "latency", message.clone());
+            notifications::notify(NotifyKind::LatencyDegraded, None, message);
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ГИСТОГРАММА
+// ═══════════════════════════════════════════════════════════
+
+/// Lock-free гистограмма: запись - несколько atomic add на горячем пути
+struct Histogram {
+    buckets: [AtomicU64; BUCKETS],
+    count: AtomicU64,
+    sum_ns: AtomicU64,
+    max_ns: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StageStats {
+    pub stage: &'static str,
+    pub count: u64,
+    pub mean_us: f64,
+    /// Перцентили - верхняя граница корзины (точность x2)
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+    pub p999_us: u64,
+    pub max_us: f64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+            count: AtomicU64::new(0),
+            sum_ns: AtomicU64::new(0),
+            max_ns: AtomicU64::new(0),
+        }
+    }
+
+    fn record(&self, ns: u64) {
+        let us = ns / 1000;
+        let idx = ((u64::BITS - us.leading_zeros()) as usize).min(BUCKETS - 1);
+        self.buckets[idx].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_ns.fetch_add(ns, Ordering::Relaxed);
+        self.max_ns.fetch_max(ns, Ordering::Relaxed);
+    }
+
+    fn reset(&self) {
+        for b in &self.buckets {
+            b.store(0, Ordering::Relaxed);
+        }
+        self.count.store(0, Ordering::Relaxed);
+        self.sum_ns.store(0, Ordering::Relaxed);
+        self.max_ns.store(0, Ordering::Relaxed);
+    }
+
+    fn stats(&self, name: &'static str) -> StageStats {
+        let counts: Vec<u64> = self.buckets.iter().map(|b| b.load(Ordering::Relaxed)).collect();
+        let total: u64 = counts.iter().sum();
+
+        let percentile = |q: f64| -> u64 {
+            if total == 0 {
+                return 0;
+            }
+            let rank = ((total as f64) * q).ceil() as u64;
+            let mut seen = 0;
+            for (i, c) in counts.iter().enumerate() {
+                seen += c;
+                if seen >= rank {
+                    return 1u64 << i;
+                }
+            }
+            1u64 << (BUCKETS - 1)
+        };
+
+        let count = self.count.load(Ordering::Relaxed);
+        let sum_ns = self.sum_ns.load(Ordering::Relaxed);
+
+        StageStats {
+            stage: name,
+            count,
+            mean_us: if count > 0 { sum_ns as f64 / count as f64 / 1000.0 } else { 0.0 },
+            p50_us: percentile(0.5),
+            p90_us: percentile(0.9),
+            p99_us: percentile(0.99),
+            p999_us: percentile(0.999),
+            max_us: self.max_ns.load(Ordering::Relaxed) as f64 / 1000.0,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ГЛОБАЛЬНЫЕ МЕТРИКИ
+// ═══════════════════════════════════════════════════════════
+
+static HISTOGRAMS: LazyLock<Vec<Histogram>> =
+    LazyLock::new(|| Stage::ALL.iter().map(|_| Histogram::new()).collect());
+
+/// Бюджеты по индексу стадии (None - без бюджета)
+static BUDGETS: OnceLock<Vec<Option<Budget>>> = OnceLock::new();
+
+/// instance_id -> received_at_ns последнего тика, переданного стратегии
+static LAST_TICK: LazyLock<DashMap<String, Arc<AtomicU64>>> = LazyLock::new(DashMap::new);
+
+pub fn init(config: LatencyConfig) {
+    for name in config.budgets_ms.keys() {
+        if !Stage::ALL.iter().any(|s| s.name() == name) {
+            tracing::warn!("⏱️ Unknown latency stage '{}' in budgets_ms, ignored", name);
+        }
+    }
+    let budgets = Stage::ALL
+        .iter()
+        .map(|stage| {
+            let ms = config.budgets_ms.get(stage.name()).copied().filter(|ms| *ms > 0.0)?;
+            Some(Budget {
+                limit_ns: (ms * 1e6) as u64,
+                breaches: config.breaches.max(1),
+                recover_ms: (config.recover_secs * 1000) as i64,
+                consecutive: AtomicU32::new(0),
+                last_ns: AtomicU64::new(0),
+                degraded_since: AtomicI64::new(0),
+                last_breach: AtomicI64::new(0),
+            })
+        })
+        .collect();
+    BUDGETS.set(budgets).ok();
+}
+
+pub fn now_ns() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_nanos() as u64
+}
+
+/// Длительность стадии от метки start_ns до текущего момента
+pub fn record_since(stage: Stage, start_ns: u64) {
+    record(stage, now_ns().saturating_sub(start_ns));
+}
+
+pub fn record(stage: Stage, ns: u64) {
+    HISTOGRAMS[stage as usize].record(ns);
+    if let Some(budget) = BUDGETS.get().and_then(|b| b[stage as usize].as_ref()) {
+        budget.observe(stage, ns);
+    }
+}
+
+/// Стадии, которые сейчас не укладываются в бюджет
+pub fn degraded() -> Vec<DegradedStage> {
+    let Some(budgets) = BUDGETS.get() else { return Vec::new() };
+    Stage::ALL
+        .iter()
+        .zip(budgets)
+        .filter_map(|(stage, budget)| {
+            let budget = budget.as_ref()?;
+            let since = budget.degraded_since.load(Ordering::Relaxed);
+            (since != 0).then(|| DegradedStage {
+                stage: stage.name(),
+                budget_ms: budget.limit_ns as f64 / 1e6,
+                last_ms: budget.last_ns.load(Ordering::Relaxed) as f64 / 1e6,
+                since,
+            })
+        })
+        .collect()
+}
+
+/// Счётчик последнего тика инстанса (его обновляет fan-out)
+pub fn register_instance(instance_id: &str) -> Arc<AtomicU64> {
+    let last = Arc::new(AtomicU64::new(0));
+    LAST_TICK.insert(instance_id.to_string(), last.clone());
+    last
+}
+
+/// Снимает регистрацию, если инстанс не перезапущен с тем же id
+pub fn unregister_instance(instance_id: &str, last: &Arc<AtomicU64>) {
+    LAST_TICK.remove_if(instance_id, |_, current| Arc::ptr_eq(current, last));
+}
+
+/// Фиксирует tick -> order для инстанса, если он уже получал тики
+pub fn record_order(instance_id: &str) {
+    let Some(last) = LAST_TICK.get(instance_id).map(|t| t.load(Ordering::Relaxed)) else { return };
+    if last > 0 {
+        record_since(Stage::TickToOrder, last);
+    }
+}
+
+pub fn snapshot() -> Vec<StageStats> {
+    Stage::ALL
+        .iter()
+        .map(|&stage| HISTOGRAMS[stage as usize].stats(stage.name()))
+        .collect()
+}
+
+pub fn reset() {
+    for h in HISTOGRAMS.iter() {
+        h.reset();
+    }
+    for m in TRADE.iter() {
+        m.total.reset();
+        m.recent.lock().unwrap().clear();
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// TRADE WS ПО МЕТОДАМ
+// ═══════════════════════════════════════════════════════════
+//
+// order_ack считается от send_command и включает очередь и подпись.
+// Здесь - чистый round trip: запись запроса в сокет -> ответ с тем же id,
+// отдельно для order.place и order.cancel. Перцентили по последним
+// TRADE_WINDOW ответам точные, а не по корзинам x2: так видно, держится
+// ли путь ордера в единицах миллисекунд прямо сейчас.
+
+const TRADE_WINDOW: usize = 1024;
+
+struct MethodLatency {
+    total: Histogram,
+    recent: Mutex<VecDeque<u64>>,
+}
+
+static TRADE: LazyLock<DashMap<&'static str, MethodLatency>> = LazyLock::new(DashMap::new);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeMethodStats {
+    pub method: &'static str,
+    /// Замеров в окне
+    pub window: usize,
+    pub p50_us: f64,
+    pub p90_us: f64,
+    pub p99_us: f64,
+    pub max_us: f64,
+    /// С запуска (или reset), корзины x2 как в /api/latency
+    pub total: StageStats,
+}
+
+pub fn record_trade(method: &'static str, ns: u64) {
+    let entry = TRADE.entry(method).or_insert_with(|| MethodLatency {
+        total: Histogram::new(),
+        recent: Mutex::new(VecDeque::with_capacity(TRADE_WINDOW)),
+    });
+    entry.total.record(ns);
+    let mut recent = entry.recent.lock().unwrap();
+    if recent.len() >= TRADE_WINDOW {
+        recent.pop_front();
+    }
+    recent.push_back(ns);
+}
+
+pub fn trade_snapshot() -> Vec<TradeMethodStats> {
+    let mut stats: Vec<TradeMethodStats> = TRADE
+        .iter()
+        .map(|m| {
+            let mut window: Vec<u64> = m.recent.lock().unwrap().iter().copied().collect();
+            window.sort_unstable();
+            let percentile = |q: f64| -> f64 {
+                if window.is_empty() {
+                    return 0.0;
+                }
+                let rank = ((window.len() as f64) * q).ceil() as usize;
+                window[rank.clamp(1, window.len()) - 1] as f64 / 1000.0
+            };
+            TradeMethodStats {
+                method: m.key(),
+                window: window.len(),
+                p50_us: percentile(0.5),
+                p90_us: percentile(0.9),
+                p99_us: percentile(0.99),
+                max_us: window.last().copied().unwrap_or(0) as f64 / 1000.0,
+                total: m.total.stats(m.key()),
+            }
+        })
+        .collect();
+    stats.sort_by_key(|s| s.method);
+    stats
+}
@@ -0,0 +1,111 @@
+// src/lifecycle.rs
+
+use serde::Serialize;
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+// ═══════════════════════════════════════════════════════════
+// СОБЫТИЯ ЖИЗНЕННОГО ЦИКЛА
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecycleKind {
+    Started,
+    /// Стратегия завершилась с кодом 0 (в т.ч. по stop)
+    Stopped,
+    /// Ненулевой код выхода или паника
+    Crashed,
+    Restarted,
+    /// Blue-green swap: instance_id - новый инстанс, message - какой заменён
+    Swapped,
+    /// Watchdog: стратегия перестала забирать события
+    Stalled,
+    CompileFinished,
+}
+
+impl LifecycleKind {
+    /// Имя SSE-события
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Started => "started",
+            Self::Stopped => "stopped",
+            Self::Crashed => "crashed",
+            Self::Restarted => "restarted",
+            Self::Swapped => "swapped",
+            Self::Stalled => "stalled",
+            Self::CompileFinished => "compile_finished",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LifecycleEvent {
+    pub kind: LifecycleKind,
+    pub strategy_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// Для compile_finished
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub success: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Unix ms
+    pub time: i64,
+}
+
+impl LifecycleEvent {
+    pub fn new(kind: LifecycleKind, strategy_id: &str) -> Self {
+        Self {
+            kind,
+            strategy_id: strategy_id.to_string(),
+            instance_id: None,
+            exit_code: None,
+            success: None,
+            message: None,
+            time: chrono::Utc::now().timestamp_millis(),
+        }
+    }
+
+    pub fn instance(mut self, instance_id: &str) -> Self {
+        self.instance_id = Some(instance_id.to_string());
+        self
+    }
+
+    pub fn exit_code(mut self, code: Option<i32>) -> Self {
+        self.exit_code = code;
+        self
+    }
+
+    pub fn success(mut self, success: bool) -> Self {
+        self.success = Some(success);
+        self
+    }
+
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ШИНА
+// ═══════════════════════════════════════════════════════════
+
+static BUS: OnceLock<broadcast::Sender<LifecycleEvent>> = OnceLock::new();
+
+fn bus() -> &'static broadcast::Sender<LifecycleEvent> {
+    BUS.get_or_init(|| broadcast::channel(256).0)
+}
+
+/// Публикует событие (без подписчиков просто теряется)
+pub fn emit(event: LifecycleEvent) {
+    tracing::debug!("🔄 Lifecycle {:?} '{}'", event.kind, event.instance_id.as_deref().unwrap_or(&event.strategy_id));
+    let _ = bus().send(event);
+}
+
+pub fn subscribe() -> broadcast::Receiver<LifecycleEvent> {
+    bus().subscribe()
+}
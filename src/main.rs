@@ -1,28 +1,78 @@
 // src/main.rs
 
+use anyhow::Context;
 use axum::{
-    routing::{post, get},
+    routing::post,
     extract::{Json, State},
     Router,
     http::StatusCode,
 };
 use serde::{Deserialize, Serialize};
-use tokio::{sync::broadcast, time::{Duration, Instant}};
+use tokio::{select, sync::broadcast, time::Duration};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use serde_json::Value;
 
+mod affinity;
+mod alerts;
+mod analytics;
+mod audit;
+mod config;
 mod ffi_types;
+mod funding;
+mod endpoints;
 mod exchange_data;
 mod exchange_trade;
+mod execution;
+mod fanout;
+mod files;
+mod history;
+mod keystore;
+mod kv;
+mod latency;
+mod lifecycle;
+mod net;
+mod notifications;
+mod open_interest;
+mod order_errors;
+mod orders;
+mod pnl;
+mod recorder;
+mod paper;
+mod data;
+mod decimal;
+mod bench;
+mod brackets;
+mod positions;
+mod raw_capture;
+mod reports;
+mod rate_limit;
+mod redact;
+mod risk;
 mod routes;
+mod scheduler;
 mod strategies;
+mod symbols;
+mod time_sync;
+mod user_data;
+mod volume_profile;
 
-use crate::exchange_data::ExchangeData;
+use crate::config::CoreConfig;
+use crate::exchange_data::{CommandError, ExchangeData};
 use crate::exchange_trade::ExchangeTrade;
-use crate::routes::strategy::{self, AppState};
+use crate::risk::RiskManager;
+use crate::routes::AppState;
 use crate::strategies::{StrategyStorage, StrategyRunner};
-use crate::strategies::init_trading;
+use crate::strategies::{init_trading, init_risk, init_breaker, init_orders};
+use crate::order_errors::OrderError;
+use crate::orders::OrderManager;
+use crate::history::History;
+use crate::pnl::PnlTracker;
+use crate::strategies::breaker::CircuitBreaker;
+use crate::strategies::compile::CompileQueue;
+use crate::scheduler::Scheduler;
+use crate::time_sync::TimeSync;
+use crate::user_data::UserDataManager;
 use crate::ffi_types::CEvent;
 
 // ═══════════════════════════════════════════════════════════
@@ -36,7 +86,12 @@ struct TickerRequest {
 
 #[derive(Deserialize)]
 struct TestOrderRequest {
+    /// Вместо api_key/secret_key можно передать alias из keystore
+    #[serde(default)]
+    account: Option<String>,
+    #[serde(default)]
     api_key: String,
+    #[serde(default)]
     secret_key: String,
     symbol: String,
     price: f64,
@@ -46,7 +101,12 @@ struct TestOrderRequest {
 
 #[derive(Deserialize)]
 struct CancelOrderRequest {
+    /// Вместо api_key/secret_key можно передать alias из keystore
+    #[serde(default)]
+    account: Option<String>,
+    #[serde(default)]
     api_key: String,
+    #[serde(default)]
     secret_key: String,
     symbol: String,
     order_id: String,
@@ -58,29 +118,18 @@ struct OrderResponse {
     message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     order_id: Option<i64>,
+    /// code / category / retryable - как в OrderResult стратегии
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<OrderError>,
     #[serde(skip_serializing_if = "Option::is_none")]
     data: Option<Value>,
 }
 
-#[derive(Serialize)]
-struct OrderPingResponse {
-    success: bool,
-    ping_ms: f64,
-    order_place_ms: f64,
-    order_cancel_ms: f64,
-    total_ms: f64,
-    order_id: Option<i64>,
-    test_price: f64,
-    current_bid: f64,
-    message: String,
-}
-
 /// Контекст для data/trade роутов
 #[derive(Clone)]
 struct DataContext {
     data_manager: Arc<ExchangeData>,
     trade_manager: Arc<ExchangeTrade>,
-    event_broadcaster: broadcast::Sender<CEvent>,
 }
 
 // ═══════════════════════════════════════════════════════════
@@ -88,12 +137,31 @@ struct DataContext {
 // ═══════════════════════════════════════════════════════════
 
 #[tokio::main]
-async fn main() {
+async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt()
         .with_target(false)
         .compact()
         .init();
 
+    let config = CoreConfig::load().expect("Failed to load config");
+
+    if let Err(e) = audit::init(&config.audit) {
+        tracing::error!("❌ Audit log disabled, failed to open '{}': {}", config.audit.dir, e);
+    }
+
+    let keystore = keystore::init(&config.keystore).context("Failed to open keystore")?;
+    if keystore.is_none() {
+        tracing::warn!("⚠️ Keystore disabled: set HFT_MASTER_KEY or keystore.master_key_path to use account aliases");
+    }
+    net::init(config.net.clone());
+    endpoints::init(config.endpoints.clone());
+    paper::init(config.paper.clone());
+    latency::init(config.latency.clone());
+    volume_profile::init(config.volume_profile.clone());
+    if let Err(e) = recorder::init(&config.recorder) {
+        tracing::error!("❌ Recorder disabled, failed to open '{}': {}", config.recorder.dir, e);
+    }
+
     let (event_tx, _) = broadcast::channel::<CEvent>(10000);
 
     // ═══════════════════════════════════════════════════════════
@@ -102,58 +170,123 @@ async fn main() {
     
     let data_manager = ExchangeData::new(
         "wss://fstream.binance.com/ws".to_string(), 
-        event_tx.clone()
+        event_tx.clone(),
+        config.affinity.market_data,
+        config.analytics.clone(),
     );
+    strategies::streams::init(data_manager.clone());
 
     // ═══════════════════════════════════════════════════════════
     // TRADE MANAGER
     // ═══════════════════════════════════════════════════════════
     
     let trade_manager = ExchangeTrade::new(
-        "wss://ws-fapi.binance.com/ws-fapi/v1".to_string()
+        "wss://ws-fapi.binance.com/ws-fapi/v1".to_string(),
+        config.trade_ws.clone(),
+        config.rate_limits.clone(),
+        config.rest_fallback.clone(),
+        config.affinity.trade_ws,
     );
     
-    match trade_manager.sync_time().await {
-        Ok(offset) => {
-            if offset.abs() > 1000 {
-                tracing::warn!("⚠️ Large time offset: {}ms", offset);
-            } else {
-                tracing::info!("✅ Time offset: {}ms", offset);
-            }
-        }
-        Err(e) => {
-            tracing::error!("❌ Time sync failed: {}", e);
-            trade_manager.set_time_offset(-1000);
-        }
-    }
+    let time_sync = TimeSync::start(trade_manager.clone(), config.time_sync.clone()).await;
+    funding::spawn_refresh(config.funding.clone(), event_tx.clone());
+    open_interest::spawn_poller(config.open_interest.clone(), data_manager.clone(), event_tx.clone());
+    symbols::spawn_refresh();
+    notifications::init(config.notifications.clone(), event_tx.subscribe());
+    kv::init(config.kv.clone()).expect("Failed to load KV store");
 
     init_trading(trade_manager.clone());
 
+    // ═══════════════════════════════════════════════════════════
+    // USER DATA, RISK & ORDERS
+    // ═══════════════════════════════════════════════════════════
+
+    let user_data = UserDataManager::new(
+        "https://fapi.binance.com".to_string(),
+        "wss://fstream.binance.com/ws".to_string(),
+        event_tx.clone(),
+    );
+
+    let risk = RiskManager::new(
+        config.risk.clone(),
+        event_tx.subscribe(),
+        user_data.updates_tx.subscribe(),
+    );
+
+    init_risk(risk.clone());
+
+    let orders = OrderManager::new(user_data.clone());
+    init_orders(orders.clone());
+    strategies::intents::init(orders.clone(), event_tx.subscribe());
+    execution::init(config.execution.clone(), orders.clone());
+    brackets::init(trade_manager.clone(), orders.clone(), user_data.clone());
+    positions::init(trade_manager.clone(), orders.clone(), user_data.clone());
+
+    let pnl = PnlTracker::new(orders.clone(), event_tx.subscribe(), user_data.updates_tx.subscribe());
+
+    let history = if config.history.enabled {
+        match History::open(&config.history.path, orders.clone(), user_data.updates_tx.subscribe()) {
+            Ok(h) => Some(h),
+            Err(e) => {
+                tracing::error!("❌ History disabled, failed to open '{}': {}", config.history.path, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    strategies::runs::init(config.runs.clone(), pnl.clone(), history.clone());
+
     // ═══════════════════════════════════════════════════════════
     // STRATEGY STORAGE & RUNNER
     // ═══════════════════════════════════════════════════════════
     
     let storage = Arc::new(
-        StrategyStorage::new("./strategies/db")
+        StrategyStorage::new("./strategies/db", &config.compile)
             .expect("Failed to create strategy storage")
     );
+    let compiler = CompileQueue::new(storage.clone(), &config.compile);
+    strategies::disk::spawn_janitor(storage.clone(), config.compile.disk.clone());
+    
+    let breaker = CircuitBreaker::new(config.circuit_breaker.clone());
+    init_breaker(breaker.clone());
+    strategies::dedup::init(config.dedup.clone());
+    strategies::capacity::init(config.capacity.clone());
     
-    let runner = StrategyRunner::new();
+    let runner = StrategyRunner::new(breaker, config.watchdog.clone());
+
+    let scheduler = Scheduler::new(&config.scheduler, runner.clone(), storage.clone(), compiler.clone())
+        .expect("Failed to load schedules");
 
     // ═══════════════════════════════════════════════════════════
     // STATES
     // ═══════════════════════════════════════════════════════════
     
+    let rate_limiter = trade_manager.rate_limiter();
+
+    let market = data_manager.clone();
+
     let data_state = Arc::new(DataContext { 
         data_manager, 
-        trade_manager, 
-        event_broadcaster: event_tx.clone(),
+        trade_manager: trade_manager.clone(),
     });
     
     let strategy_state = AppState {
         storage,
         runner,
         event_tx,
+        risk,
+        user_data,
+        rate_limiter,
+        orders,
+        history,
+        pnl,
+        keystore,
+        time_sync,
+        market,
+        trade: trade_manager.clone(),
+        compiler,
+        scheduler,
     };
 
     // ═══════════════════════════════════════════════════════════
@@ -167,19 +300,121 @@ async fn main() {
         .route("/unsubscribe/trades", post(unsubscribe_trades))
         .route("/order/test", post(test_order))
         .route("/order/cancel", post(cancel_order))
-        .route("/ping/order", get(ping_order))
         // .route("/login", post(login_session))
         .with_state(data_state);
     
+    let api_routes = Router::new()
+        .merge(routes::strategy::routes(strategy_state.clone()))
+        .merge(routes::risk::routes(strategy_state.clone()))
+        .merge(routes::user_data::routes(strategy_state.clone()))
+        .merge(routes::rate_limit::routes(strategy_state.clone()))
+        .merge(routes::alerts::routes(strategy_state.clone()))
+        .merge(routes::orders::routes(strategy_state.clone()))
+        .merge(routes::history::routes(strategy_state.clone()))
+        .merge(routes::pnl::routes(strategy_state.clone()))
+        .merge(routes::reports::routes(strategy_state.clone()))
+        .merge(routes::latency::routes(strategy_state.clone()))
+        .merge(routes::events::api_routes(strategy_state.clone()))
+        .merge(routes::keys::routes(strategy_state.clone()))
+        .merge(routes::time::routes(strategy_state.clone()))
+        .merge(routes::endpoints::routes(strategy_state.clone()))
+        .merge(routes::recorder::routes(strategy_state.clone()))
+        .merge(routes::data::routes(strategy_state.clone()))
+        .merge(routes::bench::routes(strategy_state.clone()))
+        .merge(routes::ping::routes(strategy_state.clone()))
+        .merge(routes::compile::routes(strategy_state.clone()))
+        .merge(routes::schedules::routes(strategy_state.clone()))
+        .merge(routes::funding::routes(strategy_state.clone()))
+        .merge(routes::kv::routes(strategy_state.clone()))
+        .merge(routes::streams::routes(strategy_state.clone()))
+        .merge(routes::execution::routes(strategy_state.clone()))
+        .merge(routes::brackets::routes(strategy_state.clone()))
+        .merge(routes::positions::routes(strategy_state.clone()))
+        .merge(routes::debug::routes(strategy_state.clone()))
+        .merge(routes::trade_ws::routes(strategy_state.clone()))
+        .merge(routes::groups::routes(strategy_state.clone()))
+        .merge(routes::analytics::routes(strategy_state.clone()));
+
     let app = Router::new()
         .merge(data_routes)
-        .nest("/api", strategy::routes(strategy_state));
+        .merge(routes::events::routes(strategy_state.clone()))
+        .merge(routes::health::routes(strategy_state.clone()))
+        .merge(routes::metrics::routes(strategy_state.clone()))
+        .nest("/api", api_routes);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await.unwrap();
     tracing::info!("🚀 Server running on http://0.0.0.0:8080");
     tracing::info!("📚 Strategy API at /api/strategies");
-    tracing::info!("📊 Instances API at /api/instances");
-    axum::serve(listener, app).await.unwrap();
+    tracing::info!("📊 Instances API at /api/instances, past runs at /api/instances/history, limits at /api/capacity");
+    tracing::info!("🧹 Strategy disk usage at /api/disk");
+    tracing::info!("🛡️ Risk API at /api/risk");
+    tracing::info!("⏳ Rate limits at /api/ratelimits");
+    tracing::info!("🔔 Alerts at /api/alerts");
+    tracing::info!("📒 Orders at /api/orders");
+    tracing::info!("🗄️ History at /api/history");
+    tracing::info!("💰 PnL at /api/pnl");
+    tracing::info!("📈 Reports at /api/reports/daily");
+    tracing::info!("⏱️ Latency at /api/latency");
+    tracing::info!("⏱️ Trade WS round trip at /api/latency/trade");
+    tracing::info!("🩺 Health at /healthz");
+    tracing::info!("📏 Prometheus metrics at /metrics");
+    tracing::info!("📡 Event stream at /ws/events");
+    tracing::info!("🔄 Lifecycle events at /api/events/sse");
+    tracing::info!("🔐 Keystore at /api/keys");
+    tracing::info!("⏰ Time sync at /api/time");
+    tracing::info!("🛰️ Endpoints at /api/endpoints");
+    tracing::info!("🎙️ Recorder at /api/recorder");
+    tracing::info!("📥 Historical data at /api/data");
+    tracing::info!("🏎️ Benchmark at /api/bench/run");
+    tracing::info!("🏓 Order ping at /api/ping/order");
+    tracing::info!("📦 Compile jobs at /api/compile-jobs");
+    tracing::info!("🗓️ Schedules at /api/schedules");
+    tracing::info!("💸 Funding calendar at /api/funding/next, rates at /api/funding/rates");
+    tracing::info!("🗃️ Strategy KV store at /api/kv");
+    tracing::info!("📡 Market data subscriptions at /api/streams (exchange view at /api/streams/exchange), stats at /api/marketdata/stats");
+    tracing::info!("🧩 Algo execution at /api/execute");
+    tracing::info!("🎯 Bracket orders at /api/brackets");
+    tracing::info!("📐 Positions at /api/positions");
+    tracing::info!("🧪 Unparsed WS messages at /api/debug/rawmessages");
+    tracing::info!("👥 Accounts overview at /api/userdata/overview");
+    tracing::info!("📤 Trade WS queues at /api/tradews/queues");
+    tracing::info!("🗂️ Instance groups at /api/groups");
+    tracing::info!("📊 VWAP and volume profile at /api/analytics/vwap, open interest at /api/analytics/open-interest");
+    // Не with_graceful_shutdown: SSE и /ws/events держат соединения вечно
+    select! {
+        res = axum::serve(listener, app) => res.unwrap(),
+        _ = shutdown_signal() => {}
+    }
+
+    // Запросы, уже ушедшие на биржу, дожидаются ответа, сокеты закрываются кадром Close
+    trade_manager.drain("shutdown").await;
+    kv::shutdown();
+    strategies::runs::shutdown();
+    Ok(())
+}
+
+/// Ctrl+C или SIGTERM (docker stop, systemd)
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sig) => {
+                sig.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    tracing::warn!("🛑 Shutdown signal received");
 }
 
 // ═══════════════════════════════════════════════════════════
@@ -191,17 +426,19 @@ async fn test_order(
     Json(req): Json<TestOrderRequest>,
 ) -> (StatusCode, Json<OrderResponse>) {
     tracing::info!("📝 Test order: {} {} {} @ {}", req.side, req.quantity, req.symbol, req.price);
+    let creds = keystore::resolve(req.account.as_deref().unwrap_or(&req.api_key), &req.secret_key);
 
     let (tx, rx) = tokio::sync::oneshot::channel();
     let tx = Arc::new(Mutex::new(Some(tx)));
 
     app.trade_manager
         .send_market_order(
-            &req.api_key,
-            &req.secret_key,
+            &creds.api_key,
+            &creds.secret_key,
             &req.symbol,
             req.quantity,
             &req.side,
+            None,
             move |resp: Value| {
                 let tx_clone = tx.clone();
                 tokio::spawn(async move {
@@ -215,13 +452,14 @@ async fn test_order(
 
     match tokio::time::timeout(Duration::from_secs(10), rx).await {
         Ok(Ok(response)) => {
-            if let Some(error) = response.get("error") {
+            if let Some(error) = OrderError::from_response(&response) {
                 return (
                     StatusCode::BAD_REQUEST,
                     Json(OrderResponse {
                         success: false,
-                        message: format!("Order failed: {}", error),
+                        message: format!("Order failed: {}", error.description),
                         order_id: None,
+                        error: Some(error),
                         data: Some(response),
                     }),
                 );
@@ -234,6 +472,7 @@ async fn test_order(
                     success: true,
                     message: "Order created".to_string(),
                     order_id,
+                    error: None,
                     data: Some(response),
                 }),
             )
@@ -244,6 +483,7 @@ async fn test_order(
                 success: false,
                 message: "Channel closed".to_string(),
                 order_id: None,
+                error: None,
                 data: None,
             }),
         ),
@@ -253,6 +493,7 @@ async fn test_order(
                 success: false,
                 message: "Timeout (10s)".to_string(),
                 order_id: None,
+                error: None,
                 data: None,
             }),
         ),
@@ -264,14 +505,15 @@ async fn cancel_order(
     Json(req): Json<CancelOrderRequest>,
 ) -> (StatusCode, Json<OrderResponse>) {
     tracing::info!("🗑️ Cancel order: {} {}", req.symbol, req.order_id);
+    let creds = keystore::resolve(req.account.as_deref().unwrap_or(&req.api_key), &req.secret_key);
 
     let (tx, rx) = tokio::sync::oneshot::channel();
     let tx = Arc::new(Mutex::new(Some(tx)));
 
     app.trade_manager
         .cancel_limit_order(
-            &req.api_key,
-            &req.secret_key,
+            &creds.api_key,
+            &creds.secret_key,
             &req.symbol,
             &req.order_id,
             move |resp: Value| {
@@ -287,13 +529,14 @@ async fn cancel_order(
 
     match tokio::time::timeout(Duration::from_secs(10), rx).await {
         Ok(Ok(response)) => {
-            if let Some(error) = response.get("error") {
+            if let Some(error) = OrderError::from_response(&response) {
                 return (
                     StatusCode::BAD_REQUEST,
                     Json(OrderResponse {
                         success: false,
-                        message: format!("Cancel failed: {}", error),
+                        message: format!("Cancel failed: {}", error.description),
                         order_id: None,
+                        error: Some(error),
                         data: Some(response),
                     }),
                 );
@@ -305,6 +548,7 @@ async fn cancel_order(
                     success: true,
                     message: "Order cancelled".to_string(),
                     order_id: None,
+                    error: None,
                     data: Some(response),
                 }),
             )
@@ -315,6 +559,7 @@ async fn cancel_order(
                 success: false,
                 message: "Internal error".to_string(),
                 order_id: None,
+                error: None,
                 data: None,
             }),
         ),
@@ -324,6 +569,7 @@ async fn cancel_order(
                 success: false,
                 message: "Timeout".to_string(),
                 order_id: None,
+                error: None,
                 data: None,
             }),
         ),
@@ -334,43 +580,58 @@ async fn cancel_order(
 // DATA ROUTES
 // ═══════════════════════════════════════════════════════════
 
+/// Отказ биржи - 400 (например, неизвестный символ), нет ответа - 503
+fn command_error(e: CommandError) -> (StatusCode, String) {
+    let status = match e {
+        CommandError::Rejected { .. } => StatusCode::BAD_REQUEST,
+        CommandError::NoResponse(_) => StatusCode::SERVICE_UNAVAILABLE,
+    };
+    (status, format!("Error: {e}"))
+}
+
 async fn subscribe_bookticker(
     State(app): State<Arc<DataContext>>,
     Json(req): Json<TickerRequest>,
-) -> String {
+) -> (StatusCode, String) {
+    if let Err(e) = symbols::check(&req.ticker) {
+        return (StatusCode::BAD_REQUEST, format!("Error: {e}"));
+    }
     match app.data_manager.subscribe_bookticker(&req.ticker).await {
-        Ok(_) => format!("Subscribed to {}", req.ticker),
-        Err(e) => format!("Error: {e}"),
+        Ok(_) => (StatusCode::OK, format!("Subscribed to {}", req.ticker)),
+        Err(e) => command_error(e),
     }
 }
 
 async fn unsubscribe_bookticker(
     State(app): State<Arc<DataContext>>,
     Json(req): Json<TickerRequest>,
-) -> String {
+) -> (StatusCode, String) {
     match app.data_manager.unsubscribe_bookticker(&req.ticker).await {
-        Ok(_) => format!("Unsubscribed from {}", req.ticker),
-        Err(e) => format!("Error: {e}"),
+        Ok(_) => (StatusCode::OK, format!("Unsubscribed from {}", req.ticker)),
+        Err(e) => command_error(e),
     }
 }
 
 async fn subscribe_trades(
     State(app): State<Arc<DataContext>>,
     Json(req): Json<TickerRequest>,
-) -> String {
+) -> (StatusCode, String) {
+    if let Err(e) = symbols::check(&req.ticker) {
+        return (StatusCode::BAD_REQUEST, format!("Error: {e}"));
+    }
     match app.data_manager.subscribe_trades(&req.ticker).await {
-        Ok(_) => format!("Subscribed to {}", req.ticker),
-        Err(e) => format!("Error: {e}"),
+        Ok(_) => (StatusCode::OK, format!("Subscribed to {}", req.ticker)),
+        Err(e) => command_error(e),
     }
 }
 
 async fn unsubscribe_trades(
     State(app): State<Arc<DataContext>>,
     Json(req): Json<TickerRequest>,
-) -> String {
+) -> (StatusCode, String) {
     match app.data_manager.unsubscribe_trades(&req.ticker).await {
-        Ok(_) => format!("Unsubscribed from {}", req.ticker),
-        Err(e) => format!("Error: {e}"),
+        Ok(_) => (StatusCode::OK, format!("Unsubscribed from {}", req.ticker)),
+        Err(e) => command_error(e),
     }
 }
 
@@ -415,154 +676,3 @@ async fn unsubscribe_trades(
 //             .await;
 //     }
 // }
-
-
-// ═══════════════════════════════════════════════════════════
-// PING ORDER
-// ═══════════════════════════════════════════════════════════
-
-async fn ping_order(
-    State(app): State<Arc<DataContext>>,
-) -> (StatusCode, Json<OrderPingResponse>) {
-    tracing::info!("🏓 Starting order ping test...");
-    
-    // 1. Получаем текущую цену
-    let mut event_rx = app.event_broadcaster.subscribe();
-    
-    let current_bid = loop {
-        match tokio::time::timeout(Duration::from_secs(5), event_rx.recv()).await {
-            Ok(Ok(event)) if event.event_type == 0 => {
-                let bt = unsafe { &event.data.book_ticker };
-                let symbol = unsafe {
-                    std::str::from_utf8_unchecked(&bt.symbol[..bt.symbol_len as usize])
-                };
-                
-                if symbol.eq_ignore_ascii_case("solusdt") {
-                    break bt.bid_price;
-                }
-            }
-            Ok(Ok(_)) => continue,
-            Ok(Err(_)) => {
-                return (StatusCode::INTERNAL_SERVER_ERROR, Json(OrderPingResponse {
-                    success: false,
-                    ping_ms: 0.0, order_place_ms: 0.0, order_cancel_ms: 0.0, total_ms: 0.0,
-                    order_id: None, test_price: 0.0, current_bid: 0.0,
-                    message: "Event channel closed".to_string(),
-                }));
-            }
-            Err(_) => {
-                return (StatusCode::REQUEST_TIMEOUT, Json(OrderPingResponse {
-                    success: false,
-                    ping_ms: 0.0, order_place_ms: 0.0, order_cancel_ms: 0.0, total_ms: 0.0,
-                    order_id: None, test_price: 0.0, current_bid: 0.0,
-                    message: "No SOLUSDT data. Subscribe first.".to_string(),
-                }));
-            }
-        }
-    };
-    
-    // 2. Безопасная цена (50% от bid)
-    let safe_price = (current_bid * 0.5 * 100.0).round() / 100.0;
-    tracing::info!("Bid: ${:.2}, Test price: ${:.2}", current_bid, safe_price);
-    
-    // 3. Выставляем ордер
-    let (place_tx, place_rx) = tokio::sync::oneshot::channel();
-    let place_tx = Arc::new(Mutex::new(Some(place_tx)));
-    let start_place = Instant::now();
-    
-    app.trade_manager
-        .send_limit_order(
-            "YOUR_API_KEY",
-            "YOUR_SECRET",
-            "SOLUSDT",
-            safe_price,
-            0.1,
-            "BUY",
-            move |resp: Value| {
-                let tx = place_tx.clone();
-                tokio::spawn(async move {
-                    if let Some(sender) = tx.lock().await.take() {
-                        let _ = sender.send(resp);
-                    }
-                });
-            },
-        )
-        .await;
-    
-    let (order_id, order_place_ms) = match tokio::time::timeout(Duration::from_secs(10), place_rx).await {
-        Ok(Ok(response)) => {
-            let elapsed = start_place.elapsed().as_secs_f64() * 1000.0;
-            
-            if response.get("error").is_some() {
-                return (StatusCode::BAD_REQUEST, Json(OrderPingResponse {
-                    success: false, ping_ms: elapsed, order_place_ms: elapsed,
-                    order_cancel_ms: 0.0, total_ms: elapsed, order_id: None,
-                    test_price: safe_price, current_bid,
-                    message: format!("Order failed: {}", response),
-                }));
-            }
-            
-            match response["result"]["orderId"].as_i64() {
-                Some(id) => (id, elapsed),
-                None => return (StatusCode::INTERNAL_SERVER_ERROR, Json(OrderPingResponse {
-                    success: false, ping_ms: elapsed, order_place_ms: elapsed,
-                    order_cancel_ms: 0.0, total_ms: elapsed, order_id: None,
-                    test_price: safe_price, current_bid,
-                    message: "No orderId".to_string(),
-                })),
-            }
-        }
-        _ => return (StatusCode::REQUEST_TIMEOUT, Json(OrderPingResponse {
-            success: false, ping_ms: 10000.0, order_place_ms: 10000.0,
-            order_cancel_ms: 0.0, total_ms: 10000.0, order_id: None,
-            test_price: safe_price, current_bid,
-            message: "Timeout".to_string(),
-        })),
-    };
-    
-    tracing::info!("✅ Order placed in {:.2}ms | ID: {}", order_place_ms, order_id);
-    
-    // 4. Отменяем ордер
-    let (cancel_tx, cancel_rx) = tokio::sync::oneshot::channel();
-    let cancel_tx = Arc::new(Mutex::new(Some(cancel_tx)));
-    let start_cancel = Instant::now();
-    
-    app.trade_manager
-        .cancel_limit_order(
-            "YOUR_API_KEY",
-            "YOUR_SECRET",
-            "SOLUSDT",
-            &order_id.to_string(),
-            move |resp: Value| {
-                let tx = cancel_tx.clone();
-                tokio::spawn(async move {
-                    if let Some(sender) = tx.lock().await.take() {
-                        let _ = sender.send(resp);
-                    }
-                });
-            },
-        )
-        .await;
-    
-    let order_cancel_ms = match tokio::time::timeout(Duration::from_secs(10), cancel_rx).await {
-        Ok(Ok(_)) => start_cancel.elapsed().as_secs_f64() * 1000.0,
-        _ => 10000.0,
-    };
-    
-    tracing::info!("✅ Cancelled in {:.2}ms", order_cancel_ms);
-    
-    // 5. Результат
-    let total_ms = order_place_ms + order_cancel_ms;
-    
-    (StatusCode::OK, Json(OrderPingResponse {
-        success: true,
-        ping_ms: order_place_ms,
-        order_place_ms,
-        order_cancel_ms,
-        total_ms,
-        order_id: Some(order_id),
-        test_price: safe_price,
-        current_bid,
-        message: format!("Latency: {:.2}ms", order_place_ms),
-    }))
-}
\ No newline at end of file
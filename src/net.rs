@@ -0,0 +1,119 @@
+// src/net.rs
+
+use rustls::client::Resumption;
+use rustls::{ClientConfig, RootCertStore};
+use serde::Deserialize;
+use std::sync::{Arc, OnceLock};
+use tokio::net::{TcpSocket, TcpStream};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::Response;
+use tokio_tungstenite::tungstenite::Error as WsError;
+use tokio_tungstenite::{client_async_tls_with_config, Connector, MaybeTlsStream, WebSocketStream};
+
+pub type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+/// Параметры сокетов для всех WS-соединений с биржей
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NetConfig {
+    /// Отключить Nagle: маленькие фреймы (ордера) уходят сразу
+    pub tcp_nodelay: bool,
+    /// SO_RCVBUF, байт (null - по умолчанию ОС)
+    pub recv_buffer: Option<u32>,
+    /// SO_SNDBUF, байт (null - по умолчанию ОС)
+    pub send_buffer: Option<u32>,
+    /// Переиспользовать TLS-сессии при переподключении (короче handshake)
+    pub tls_session_resumption: bool,
+}
+
+impl Default for NetConfig {
+    fn default() -> Self {
+        Self {
+            tcp_nodelay: true,
+            recv_buffer: None,
+            send_buffer: None,
+            tls_session_resumption: true,
+        }
+    }
+}
+
+static CONFIG: OnceLock<NetConfig> = OnceLock::new();
+
+/// Общий TLS-конфиг: кэш сессий живёт в нём, поэтому он один на все соединения
+static TLS: OnceLock<Arc<ClientConfig>> = OnceLock::new();
+
+pub fn init(config: NetConfig) {
+    let _ = CONFIG.set(config);
+}
+
+fn config() -> &'static NetConfig {
+    CONFIG.get_or_init(NetConfig::default)
+}
+
+fn tls_config() -> Arc<ClientConfig> {
+    TLS.get_or_init(|| {
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+
+        let mut tls = ClientConfig::builder()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        if !config().tls_session_resumption {
+            tls.resumption = Resumption::disabled();
+        }
+        Arc::new(tls)
+    })
+    .clone()
+}
+
+// ═══════════════════════════════════════════════════════════
+// CONNECT
+// ═══════════════════════════════════════════════════════════
+
+/// Замена tokio_tungstenite::connect_async с настройкой сокета из NetConfig
+pub async fn connect(url: &str) -> Result<(WsStream, Response), WsError> {
+    let request = url.into_client_request()?;
+    let uri = request.uri();
+    let host = uri.host().ok_or(WsError::Url(
+        tokio_tungstenite::tungstenite::error::UrlError::NoHostName,
+    ))?;
+    let port = uri.port_u16().unwrap_or(match uri.scheme_str() {
+        Some("wss") => 443,
+        _ => 80,
+    });
+
+    let stream = connect_tcp(host, port).await?;
+    client_async_tls_with_config(request, stream, None, Some(Connector::Rustls(tls_config()))).await
+}
+
+/// Первый адрес хоста, к которому удалось подключиться
+async fn connect_tcp(host: &str, port: u16) -> std::io::Result<TcpStream> {
+    let config = config();
+    let mut last_err = None;
+
+    for addr in tokio::net::lookup_host((host, port)).await? {
+        let socket = if addr.is_ipv4() { TcpSocket::new_v4()? } else { TcpSocket::new_v6()? };
+        if let Some(size) = config.recv_buffer {
+            socket.set_recv_buffer_size(size)?;
+        }
+        if let Some(size) = config.send_buffer {
+            socket.set_send_buffer_size(size)?;
+        }
+
+        match socket.connect(addr).await {
+            Ok(stream) => {
+                stream.set_nodelay(config.tcp_nodelay)?;
+                return Ok(stream);
+            }
+            Err(e) => last_err = Some(e),
+        }
+    }
+
+    Err(last_err.unwrap_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, format!("No addresses for {}", host))
+    }))
+}
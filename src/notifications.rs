@@ -0,0 +1,247 @@
+// src/notifications.rs
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::{LazyLock, OnceLock};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc};
+
+use crate::ffi_types::{CEvent, EVENT_ACCOUNT_UPDATE};
+use crate::lifecycle::{self, LifecycleKind};
+
+/// CAccountUpdate.reason: начисление/списание funding
+const REASON_FUNDING_FEE: u8 = 1;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+/// Что уведомляет
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotifyKind {
+    OrderFilled,
+    /// Отказ breaker'а, risk или биржи (paper не уведомляет)
+    OrderRejected,
+    InstanceCrashed,
+    KillSwitch,
+    FundingReceived,
+    /// Стадия вышла за бюджет latency или вернулась в него
+    LatencyDegraded,
+    /// Из стратегии через config.notify()
+    Custom,
+}
+
+impl NotifyKind {
+    fn emoji(self) -> &'static str {
+        match self {
+            Self::OrderFilled => "✅",
+            Self::OrderRejected => "❌",
+            Self::InstanceCrashed => "💥",
+            Self::KillSwitch => "🚨",
+            Self::FundingReceived => "💸",
+            Self::LatencyDegraded => "🐢",
+            Self::Custom => "📣",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SinkKind {
+    /// Сообщение в чат через Bot API
+    Telegram { bot_token: String, chat_id: String },
+    /// POST JSON с уведомлением
+    Webhook { url: String },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SinkConfig {
+    #[serde(flatten)]
+    pub kind: SinkKind,
+    /// Какие уведомления слать в этот канал. Пусто - все
+    #[serde(default)]
+    pub events: Vec<NotifyKind>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    pub sinks: Vec<SinkConfig>,
+    /// Не чаще одного custom-уведомления от инстанса за столько ms
+    pub custom_min_interval_ms: u64,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            sinks: Vec::new(),
+            custom_min_interval_ms: 1000,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// УВЕДОМЛЕНИЯ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Notification {
+    pub kind: NotifyKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance_id: Option<String>,
+    pub message: String,
+    /// Unix ms
+    pub time: i64,
+}
+
+impl Notification {
+    fn text(&self) -> String {
+        match &self.instance_id {
+            Some(id) => format!("{} [{}] {}", self.kind.emoji(), id, self.message),
+            None => format!("{} {}", self.kind.emoji(), self.message),
+        }
+    }
+}
+
+/// Очередь доставки. Не инициализирована - каналов нет, уведомления не собираются
+static QUEUE: OnceLock<mpsc::UnboundedSender<Notification>> = OnceLock::new();
+
+static CUSTOM_MIN_INTERVAL_MS: OnceLock<i64> = OnceLock::new();
+
+/// instance_id -> Unix ms последнего custom-уведомления
+static LAST_CUSTOM: LazyLock<DashMap<String, i64>> = LazyLock::new(DashMap::new);
+
+/// Запускает доставку и подписки на crash инстансов и funding
+pub fn init(config: NotificationsConfig, event_rx: broadcast::Receiver<CEvent>) {
+    if config.sinks.is_empty() {
+        return;
+    }
+    let (tx, rx) = mpsc::unbounded_channel();
+    if QUEUE.set(tx).is_err() {
+        return;
+    }
+    CUSTOM_MIN_INTERVAL_MS.set(config.custom_min_interval_ms as i64).ok();
+    tracing::info!("📣 Notifications: {} sinks", config.sinks.len());
+
+    tokio::spawn(deliver_loop(config.sinks, rx));
+    tokio::spawn(lifecycle_loop(lifecycle::subscribe()));
+    tokio::spawn(funding_loop(event_rx));
+}
+
+pub fn is_enabled() -> bool {
+    QUEUE.get().is_some()
+}
+
+/// Ставит уведомление в очередь (не блокирует)
+pub fn notify(kind: NotifyKind, instance_id: Option<&str>, message: impl Into<String>) {
+    let Some(queue) = QUEUE.get() else { return };
+    let _ = queue.send(Notification {
+        kind,
+        instance_id: instance_id.map(str::to_string),
+        message: message.into(),
+        time: chrono::Utc::now().timestamp_millis(),
+    });
+}
+
+/// Уведомление от стратегии. false - отброшено (чаще custom_min_interval_ms)
+pub fn custom(instance_id: &str, message: &str) -> bool {
+    if !is_enabled() {
+        return false;
+    }
+    let now = chrono::Utc::now().timestamp_millis();
+    let min_interval = CUSTOM_MIN_INTERVAL_MS.get().copied().unwrap_or(0);
+    let mut last = LAST_CUSTOM.entry(instance_id.to_string()).or_insert(i64::MIN / 2);
+    if now - *last < min_interval {
+        return false;
+    }
+    *last = now;
+    drop(last);
+    notify(NotifyKind::Custom, Some(instance_id), message);
+    true
+}
+
+// ═══════════════════════════════════════════════════════════
+// ИСТОЧНИКИ
+// ═══════════════════════════════════════════════════════════
+
+async fn lifecycle_loop(mut rx: broadcast::Receiver<lifecycle::LifecycleEvent>) {
+    loop {
+        let event = match rx.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        if event.kind != LifecycleKind::Crashed {
+            continue;
+        }
+        let reason = match (event.exit_code, &event.message) {
+            (_, Some(message)) => message.clone(),
+            (Some(code), None) => format!("exit code {}", code),
+            (None, None) => "unknown reason".to_string(),
+        };
+        let id = event.instance_id.as_deref().unwrap_or(&event.strategy_id);
+        notify(NotifyKind::InstanceCrashed, Some(id), format!("Instance crashed: {}", reason));
+    }
+}
+
+async fn funding_loop(mut rx: broadcast::Receiver<CEvent>) {
+    // ACCOUNT_UPDATE приходит по событию на каждую позицию - уведомляем один раз
+    let mut last_time = 0i64;
+    loop {
+        let event = match rx.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        if event.event_type != EVENT_ACCOUNT_UPDATE {
+            continue;
+        }
+        let a = unsafe { &event.data.account_update };
+        if a.reason != REASON_FUNDING_FEE || a.time == last_time {
+            continue;
+        }
+        last_time = a.time;
+        let verb = if a.balance_change >= 0.0 { "received" } else { "paid" };
+        notify(
+            NotifyKind::FundingReceived,
+            None,
+            format!("Funding {} {:.4} USDT, wallet {:.2} USDT", verb, a.balance_change.abs(), a.wallet_balance),
+        );
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ДОСТАВКА
+// ═══════════════════════════════════════════════════════════
+
+async fn deliver_loop(sinks: Vec<SinkConfig>, mut rx: mpsc::UnboundedReceiver<Notification>) {
+    let client = reqwest::Client::new();
+    while let Some(notification) = rx.recv().await {
+        for sink in &sinks {
+            if !sink.events.is_empty() && !sink.events.contains(&notification.kind) {
+                continue;
+            }
+            if let Err(e) = send(&client, &sink.kind, &notification).await {
+                tracing::warn!("📣 Notification delivery failed: {}", e);
+            }
+        }
+    }
+}
+
+async fn send(client: &reqwest::Client, sink: &SinkKind, notification: &Notification) -> anyhow::Result<()> {
+    let request = match sink {
+        SinkKind::Telegram { bot_token, chat_id } => client
+            .post(format!("https://api.telegram.org/bot{}/sendMessage", bot_token))
+            .json(&serde_json::json!({ "chat_id": chat_id, "text": notification.text() })),
+        SinkKind::Webhook { url } => client.post(url).json(notification),
+    };
+    // URL Telegram содержит токен бота - в ошибку он попасть не должен
+    request
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| anyhow::anyhow!("{}", e.without_url()))?;
+    Ok(())
+}
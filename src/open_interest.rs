@@ -0,0 +1,236 @@
+// src/open_interest.rs
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, SystemTime};
+use tokio::sync::broadcast;
+
+use crate::exchange_data::ExchangeData;
+use crate::fanout;
+use crate::ffi_types::{pack_str, CEvent, CEventData, COpenInterest, Sequencer, EVENT_OPEN_INTEREST};
+
+const OPEN_INTEREST_URL: &str = "https://fapi.binance.com/fapi/v1/openInterest";
+const TOP_LONG_SHORT_URL: &str = "https://fapi.binance.com/futures/data/topLongShortPositionRatio";
+
+/// Запросы идут по каждому символу: чаще не опрашиваем
+const MIN_INTERVAL_SECS: u64 = 10;
+
+/// Периоды, которые принимает topLongShortPositionRatio
+const PERIODS: &[&str] = &["5m", "15m", "30m", "1h", "2h", "4h", "6h", "12h", "1d"];
+
+// ═══════════════════════════════════════════════════════════
+// OPEN INTEREST И LONG/SHORT
+// ═══════════════════════════════════════════════════════════
+//
+// Открытый интерес и соотношение long/short топ-трейдеров по позициям -
+// мера перекоса толпы: стратегия не входит туда, где все уже в одну сторону.
+// Данных нет в WS, поэтому REST-опрос с интервалом; после каждого опроса
+// символа стратегиям уходит EVENT_OPEN_INTEREST, HTTP - /api/analytics/open-interest.
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct OpenInterestConfig {
+    pub enabled: bool,
+    /// Период опроса, секунд
+    pub interval_secs: u64,
+    /// Символы. Пусто - все, на которые есть подписка market data
+    pub symbols: Vec<String>,
+    /// Период long/short ratio: 5m, 15m, 30m, 1h, 2h, 4h, 6h, 12h, 1d
+    pub period: String,
+    /// Рассылать стратегиям EVENT_OPEN_INTEREST
+    pub events: bool,
+}
+
+impl Default for OpenInterestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 60,
+            symbols: Vec::new(),
+            period: "5m".to_string(),
+            events: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenInterest {
+    pub symbol: String,
+    /// В базовой монете
+    pub open_interest: f64,
+    /// Изменение с прошлого опроса, %. None - первый опрос
+    pub open_interest_change_percent: Option<f64>,
+    /// Время биржи openInterest, unix ms
+    pub open_interest_time: i64,
+    /// long / short позиций топ-трейдеров. None - ещё не получен
+    pub long_short_ratio: Option<f64>,
+    /// Доли long и short позиций (в сумме 1)
+    pub long_account: Option<f64>,
+    pub short_account: Option<f64>,
+    pub period: String,
+    /// Начало периода ratio, unix ms
+    pub ratio_time: Option<i64>,
+    /// Unix ms последнего опроса
+    pub updated_at: i64,
+}
+
+/// SYMBOL -> последний опрос
+static LATEST: LazyLock<DashMap<String, OpenInterest>> = LazyLock::new(DashMap::new);
+
+pub fn get(symbol: &str) -> Option<OpenInterest> {
+    LATEST.get(&symbol.to_uppercase()).map(|o| o.clone())
+}
+
+/// Все опрошенные символы, по алфавиту
+pub fn list() -> Vec<OpenInterest> {
+    let mut list: Vec<_> = LATEST.iter().map(|o| o.clone()).collect();
+    list.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+    list
+}
+
+// ═══════════════════════════════════════════════════════════
+// ОПРОС
+// ═══════════════════════════════════════════════════════════
+
+struct Poller {
+    config: OpenInterestConfig,
+    market: Arc<ExchangeData>,
+    event_tx: broadcast::Sender<CEvent>,
+    seqs: Sequencer,
+    client: reqwest::Client,
+}
+
+impl Poller {
+    fn symbols(&self) -> BTreeSet<String> {
+        if !self.config.symbols.is_empty() {
+            return self.config.symbols.iter().map(|s| s.to_uppercase()).collect();
+        }
+        self.market.subscriptions().into_iter().map(|s| s.symbol).collect()
+    }
+
+    async fn poll(&self, symbol: &str) -> anyhow::Result<OpenInterest> {
+        let oi: serde_json::Value = self.client
+            .get(OPEN_INTEREST_URL)
+            .query(&[("symbol", symbol)])
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let open_interest = oi["openInterest"].as_str().and_then(|v| v.parse::<f64>().ok())
+            .ok_or_else(|| anyhow::anyhow!("Unexpected openInterest response: {}", oi))?;
+
+        // Ratio вторичен: без него отдаём хотя бы open interest
+        let ratio = match self.ratio(symbol).await {
+            Ok(ratio) => ratio,
+            Err(e) => {
+                tracing::debug!("🧭 Long/short ratio for {} failed: {}", symbol, e);
+                None
+            }
+        };
+        let number = |item: &serde_json::Value, key: &str| item[key].as_str().and_then(|v| v.parse::<f64>().ok());
+        let previous = LATEST.get(symbol).map(|o| o.open_interest).filter(|p| *p > 0.0);
+
+        Ok(OpenInterest {
+            symbol: symbol.to_string(),
+            open_interest,
+            open_interest_change_percent: previous.map(|p| (open_interest - p) / p * 100.0),
+            open_interest_time: oi["time"].as_i64().unwrap_or(0),
+            long_short_ratio: ratio.as_ref().and_then(|r| number(r, "longShortRatio")),
+            long_account: ratio.as_ref().and_then(|r| number(r, "longAccount")),
+            short_account: ratio.as_ref().and_then(|r| number(r, "shortAccount")),
+            period: self.config.period.clone(),
+            ratio_time: ratio.as_ref().and_then(|r| r["timestamp"].as_i64()),
+            updated_at: chrono::Utc::now().timestamp_millis(),
+        })
+    }
+
+    /// Последняя запись topLongShortPositionRatio
+    async fn ratio(&self, symbol: &str) -> anyhow::Result<Option<serde_json::Value>> {
+        let list: serde_json::Value = self.client
+            .get(TOP_LONG_SHORT_URL)
+            .query(&[("symbol", symbol), ("period", self.config.period.as_str()), ("limit", "1")])
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        match list {
+            serde_json::Value::Array(mut items) => Ok(items.pop()),
+            _ => anyhow::bail!("Unexpected response from {}", TOP_LONG_SHORT_URL),
+        }
+    }
+
+    fn publish(&self, info: &OpenInterest) {
+        let (symbol, symbol_len) = pack_str::<16>(&info.symbol);
+        let received_at_ns = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64;
+        let mut event = CEvent {
+            event_type: EVENT_OPEN_INTEREST,
+            data: CEventData {
+                open_interest: COpenInterest {
+                    symbol,
+                    symbol_len,
+                    open_interest: info.open_interest,
+                    open_interest_change_percent: info.open_interest_change_percent.unwrap_or(0.0),
+                    long_short_ratio: info.long_short_ratio.unwrap_or(0.0),
+                    long_account: info.long_account.unwrap_or(0.0),
+                    short_account: info.short_account.unwrap_or(0.0),
+                    ratio_time: info.ratio_time.unwrap_or(0),
+                    time: info.open_interest_time,
+                },
+            },
+            received_at_ns,
+            seq: 0,
+        };
+        self.seqs.stamp(&mut event);
+        fanout::publish(event);
+        let _ = self.event_tx.send(event);
+    }
+}
+
+/// Фоновый опрос openInterest и topLongShortPositionRatio
+pub fn spawn_poller(mut config: OpenInterestConfig, market: Arc<ExchangeData>, event_tx: broadcast::Sender<CEvent>) {
+    if !config.enabled {
+        return;
+    }
+    if !PERIODS.contains(&config.period.as_str()) {
+        tracing::warn!("🧭 Unknown open_interest.period '{}', using 5m", config.period);
+        config.period = "5m".to_string();
+    }
+    let interval_secs = config.interval_secs.max(MIN_INTERVAL_SECS);
+    let poller = Poller {
+        config,
+        market,
+        event_tx,
+        seqs: Sequencer::default(),
+        client: reqwest::Client::new(),
+    };
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            let symbols = poller.symbols();
+            // Отписанные символы не показываем устаревшими значениями
+            LATEST.retain(|symbol, _| symbols.contains(symbol));
+            for symbol in &symbols {
+                match poller.poll(symbol).await {
+                    Ok(info) => {
+                        if poller.config.events {
+                            poller.publish(&info);
+                        }
+                        LATEST.insert(symbol.clone(), info);
+                    }
+                    Err(e) => tracing::warn!("🧭 Open interest for {} failed: {}", symbol, e),
+                }
+            }
+        }
+    });
+}
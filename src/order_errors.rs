@@ -0,0 +1,166 @@
+// src/order_errors.rs
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::exchange_trade::{
+    ERR_DISCONNECTED, ERR_ORDER_EXPIRED, ERR_REQUEST_TIMEOUT, ERR_REST_FAILED, ERR_SHUTTING_DOWN, ERR_SIGN_FAILED,
+};
+use crate::rate_limit::{ERR_IP_BANNED, ERR_RATE_LIMITED};
+use crate::strategies::capacity::ERR_THROUGHPUT_LIMIT;
+
+// ═══════════════════════════════════════════════════════════
+// КЛАССИФИКАЦИЯ ОШИБОК ОРДЕРОВ
+// ═══════════════════════════════════════════════════════════
+//
+// Один и тот же error_code приходит в HTTP-ответ /order/* и в OrderResult
+// стратегии. Категория и retryable считаются только по коду, так что
+// copy_into_strategies/types.rs повторяет эту таблицу (error_category,
+// OrderResult::retryable) без изменения ABI. Меняя одну - меняй обе.
+
+/// Ответ без orderId и без error (order.rs)
+pub const ERR_NO_ORDER_ID: i32 = -9998;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// Связь с биржей: disconnect, таймаут, перегрузка
+    Network = 1,
+    /// Лимиты запросов Binance, локальный rate limiter или потолок ордеров ядра
+    RateLimit = 2,
+    /// timestamp вне recvWindow
+    Timestamp = 3,
+    /// Ключ, подпись, права
+    Auth = 4,
+    /// Неверные параметры запроса
+    InvalidRequest = 5,
+    /// Фильтры символа: tickSize, stepSize, minNotional
+    Filter = 6,
+    /// Не хватает маржи или баланса
+    Margin = 7,
+    /// Биржа приняла запрос, но отклонила ордер
+    Rejected = 8,
+    /// Ордер не найден (отмена уже закрытого)
+    UnknownOrder = 9,
+    /// Риск-лимиты ядра
+    Risk = 10,
+    /// Ядро не отправило ордер: breaker, карантин, дубль, очередь, paper
+    Local = 11,
+    Unknown = 255,
+}
+
+pub fn category(code: i32) -> ErrorCategory {
+    use ErrorCategory::*;
+    match code {
+        -1001 | -1006 | -1007 | -1008 => Network,
+        ERR_DISCONNECTED | ERR_REST_FAILED | ERR_REQUEST_TIMEOUT => Network,
+        -1003 | -1015 | ERR_RATE_LIMITED | ERR_IP_BANNED | ERR_THROUGHPUT_LIMIT => RateLimit,
+        -1021 => Timestamp,
+        -1002 | -1022 | -2014 | -2015 | ERR_SIGN_FAILED => Auth,
+        -1013 | -1111 | -4014 | -4023 | -4164 => Filter,
+        -1199..=-1100 | -1014 | -1016 => InvalidRequest,
+        -2018 | -2019 | -2027 | -2028 => Margin,
+        -2011 | -2013 => UnknownOrder,
+        -2010 | -2020 | -2021 | -2022 | -5021 | -5022 => Rejected,
+        -4999..=-4000 => InvalidRequest,
+        -9199..=-9100 => Risk,
+        -9699..=-9202 | ERR_SHUTTING_DOWN => Local,
+        _ => Unknown,
+    }
+}
+
+/// Повтор того же запроса может пройти. Только там, где ордер точно не
+/// исполнился: коды с неизвестным статусом (is_status_unknown) не повторяем
+pub fn is_retryable(code: i32) -> bool {
+    matches!(code, -1001 | -1003 | -1008 | -1015 | -1021 | ERR_RATE_LIMITED | ERR_THROUGHPUT_LIMIT)
+}
+
+/// Ответа нет или он неполный: биржа могла принять ордер. Исход решают
+/// user data или опрос статуса, а не сама ошибка
+pub fn is_status_unknown(code: i32) -> bool {
+    matches!(code, -1006 | -1007 | ERR_DISCONNECTED | ERR_REQUEST_TIMEOUT)
+}
+
+pub fn explain(code: i32) -> &'static str {
+    match code {
+        -1001 => "Exchange disconnected internally, request not processed",
+        -1003 => "Too many requests, request weight limit hit",
+        -1006 => "Unexpected response from exchange, order status unknown",
+        -1007 => "Exchange backend timeout, order status unknown",
+        -1008 => "Exchange overloaded",
+        -1015 => "Too many new orders",
+        -1021 => "Timestamp outside recvWindow, check clock sync",
+        -1002 | -2015 => "API key rejected: invalid key, IP or permissions",
+        -1022 => "Invalid signature",
+        -2014 => "API key format invalid",
+        -1013 => "Order violates a symbol filter",
+        -1111 => "Price or quantity has too many decimals for this symbol",
+        -4014 => "Price is not a multiple of tickSize",
+        -4023 => "Quantity is not a multiple of stepSize",
+        -4164 => "Order notional below the symbol minimum",
+        -1102 => "Mandatory parameter missing or malformed",
+        -1106 => "Parameter sent when not required",
+        -1121 => "Invalid symbol",
+        -2018 => "Balance insufficient",
+        -2019 => "Margin insufficient",
+        -2027 | -2028 => "Position exceeds the maximum for current leverage",
+        -2011 => "Cancel rejected, order is not open",
+        -2013 => "Order does not exist",
+        -2010 => "New order rejected by exchange",
+        -2020 => "Order cannot be filled",
+        -2021 => "Order would immediately trigger",
+        -2022 => "Reduce-only order rejected",
+        -5021 => "FOK order cannot be filled completely",
+        -5022 => "Post-only order would take liquidity",
+        ERR_RATE_LIMITED => "Blocked by local rate limiter",
+        ERR_IP_BANNED => "IP is banned by exchange, waiting for ban to expire",
+        ERR_THROUGHPUT_LIMIT => "Core-wide orders per second limit reached",
+        ERR_ORDER_EXPIRED => "Expired in the core queue before sending",
+        ERR_DISCONNECTED => "Connection closed before response, order may have been placed",
+        ERR_SIGN_FAILED => "Failed to build or sign request",
+        ERR_REST_FAILED => "REST request failed before reaching exchange",
+        ERR_REQUEST_TIMEOUT => "No response from exchange in time, order status unknown",
+        ERR_SHUTTING_DOWN => "Core is shutting down, order not sent",
+        ERR_NO_ORDER_ID => "Exchange response has no orderId",
+        _ => match category(code) {
+            ErrorCategory::Risk => "Rejected by core risk limits",
+            ErrorCategory::Local => "Rejected by core before sending",
+            ErrorCategory::InvalidRequest => "Invalid request parameters",
+            _ => "Unknown error",
+        },
+    }
+}
+
+/// Ошибка ордера в стабильном виде для HTTP API
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderError {
+    pub code: i32,
+    pub category: ErrorCategory,
+    pub retryable: bool,
+    pub description: &'static str,
+    /// msg из ответа биржи или ядра как есть
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exchange_msg: Option<String>,
+}
+
+impl OrderError {
+    pub fn from_code(code: i32) -> Self {
+        Self {
+            code,
+            category: category(code),
+            retryable: is_retryable(code),
+            description: explain(code),
+            exchange_msg: None,
+        }
+    }
+
+    /// Ошибка из ответа WS API / REST ({"error": {"code", "msg"}}) или None
+    pub fn from_response(resp: &Value) -> Option<Self> {
+        let error = resp.get("error")?;
+        let code = error["code"].as_i64().unwrap_or(-1) as i32;
+        let mut err = Self::from_code(code);
+        err.exchange_msg = error["msg"].as_str().map(str::to_string);
+        Some(err)
+    }
+}
@@ -0,0 +1,705 @@
+// src/orders.rs
+
+use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+use crate::ffi_types::{pack_str, COrder};
+use crate::notifications::{self, NotifyKind};
+use crate::order_errors;
+use crate::strategies::stats;
+use crate::user_data::{key_id, RawOrderUpdate, UserDataEvent, UserDataManager, UserDataUpdate};
+
+/// Сколько завершённых ордеров держим в памяти
+const MAX_TERMINAL_ORDERS: usize = 10_000;
+
+/// Открытый ордер ключа без user data stream без изменений дольше - Stale
+const STALE_AFTER_MS: i64 = 60 * 60 * 1000;
+
+/// Ордер со статусом, неизвестным после ошибки order.place: если user data
+/// stream ключа за это время его не показал, на биржу он не попал
+const UNCONFIRMED_AFTER_MS: i64 = 60 * 1000;
+
+/// Как часто ищем такие ордера
+const STALE_SWEEP_EVERY: std::time::Duration = std::time::Duration::from_secs(60);
+
+// ═══════════════════════════════════════════════════════════
+// ТИПЫ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderState {
+    /// Отправлен, ответа ещё нет (или ответ - обрыв/таймаут, error_code задан)
+    Pending,
+    New,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    Expired,
+    Rejected,
+    /// Биржа вернула ошибку на order.place, или ордер с неизвестным
+    /// после обрыва статусом так и не появился в user data
+    Failed,
+    /// Чем кончился, неизвестно: у ключа нет user data stream, а ордер
+    /// не менялся дольше STALE_AFTER_MS. Запись освобождается как завершённая
+    Stale,
+}
+
+impl OrderState {
+    fn from_binance(status: &str) -> Option<Self> {
+        match status {
+            "NEW" => Some(Self::New),
+            "PARTIALLY_FILLED" => Some(Self::PartiallyFilled),
+            "FILLED" => Some(Self::Filled),
+            "CANCELED" => Some(Self::Canceled),
+            "EXPIRED" | "EXPIRED_IN_MATCH" => Some(Self::Expired),
+            "REJECTED" => Some(Self::Rejected),
+            _ => None,
+        }
+    }
+
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            Self::Filled | Self::Canceled | Self::Expired | Self::Rejected | Self::Failed | Self::Stale
+        )
+    }
+
+    /// Порядок в жизненном цикле: состояние не может откатиться назад
+    /// (ORDER_TRADE_UPDATE часто приходит раньше ответа на order.place)
+    fn rank(self) -> u8 {
+        match self {
+            Self::Pending => 0,
+            Self::New => 1,
+            Self::PartiallyFilled => 2,
+            _ => 3,
+        }
+    }
+
+    /// Код для COrder.state
+    fn code(self) -> u8 {
+        match self {
+            Self::Pending => 0,
+            Self::New => 1,
+            Self::PartiallyFilled => 2,
+            Self::Filled => 3,
+            Self::Canceled => 4,
+            Self::Expired => 5,
+            Self::Rejected => 6,
+            Self::Failed => 7,
+            Self::Stale => 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Transition {
+    pub state: OrderState,
+    /// Unix ms
+    pub time: i64,
+}
+
+/// Рынок в момент ордера dry-run инстанса
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct MarketContext {
+    /// Последний bookTicker (None - по символу ещё не было котировок)
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    /// Цена последней сделки
+    pub last_trade: Option<f64>,
+    /// Возраст bookTicker, ms
+    pub quote_age_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderRecord {
+    pub client_order_id: String,
+    pub order_id: Option<i64>,
+    pub instance_id: Option<String>,
+    /// Первые 8 символов api_key
+    pub key: String,
+    pub symbol: String,
+    pub side: String,
+    pub order_type: String,
+    pub price: f64,
+    pub orig_qty: f64,
+    pub state: OrderState,
+    pub filled_qty: f64,
+    pub avg_price: f64,
+    pub commission: f64,
+    pub realized_pnl: f64,
+    pub error_code: Option<i32>,
+    /// Намерение dry-run инстанса: на биржу не отправлялся
+    pub dry_run: bool,
+    /// Рынок в момент намерения (только dry run)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<MarketContext>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub transitions: Vec<Transition>,
+}
+
+impl OrderRecord {
+    fn set_state(&mut self, state: OrderState, time: i64) -> bool {
+        if self.state.is_terminal() || state.rank() < self.state.rank() || state == self.state {
+            return false;
+        }
+        self.state = state;
+        self.updated_at = time;
+        self.transitions.push(Transition { state, time });
+        true
+    }
+
+    fn to_c(&self) -> COrder {
+        let (client_order_id, client_order_id_len) = pack_str::<36>(&self.client_order_id);
+        let (symbol, symbol_len) = pack_str::<16>(&self.symbol);
+        COrder {
+            client_order_id,
+            client_order_id_len,
+            symbol,
+            symbol_len,
+            order_id: self.order_id.unwrap_or(-1),
+            side: if self.side == "SELL" { 1 } else { 0 },
+            order_type: match self.order_type.as_str() {
+                "MARKET" => 1,
+                "STOP_MARKET" => 2,
+                _ => 0,
+            },
+            state: self.state.code(),
+            price: self.price,
+            orig_qty: self.orig_qty,
+            filled_qty: self.filled_qty,
+            avg_price: self.avg_price,
+            error_code: self.error_code.unwrap_or(0),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct OrderFilter<'a> {
+    pub instance_id: Option<&'a str>,
+    pub symbol: Option<&'a str>,
+    pub open_only: bool,
+}
+
+impl OrderFilter<'_> {
+    fn matches(&self, o: &OrderRecord) -> bool {
+        if let Some(instance_id) = self.instance_id {
+            let owned = match o.instance_id.as_deref() {
+                Some(id) => id == instance_id,
+                // Ордер прошлой сессии ядра: инстанс узнаём по тегу в clientOrderId
+                None => client_tag(&o.client_order_id) == Some(instance_tag(instance_id).as_str()),
+            };
+            if !owned {
+                return false;
+            }
+        }
+        if let Some(symbol) = self.symbol {
+            if !o.symbol.eq_ignore_ascii_case(symbol) {
+                return false;
+            }
+        }
+        !(self.open_only && o.state.is_terminal())
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ORDER MANAGER
+// ═══════════════════════════════════════════════════════════
+
+/// Префикс clientOrderId ордеров ядра
+const CLIENT_ID_PREFIX: &str = "hft-";
+/// Тег ордеров без инстанса (HTTP API)
+const NO_INSTANCE_TAG: &str = "0";
+
+/// Короткий стабильный тег инстанса для clientOrderId (FNV-1a, 8 hex):
+/// instance_id целиком в 36 символов Binance не помещается
+pub fn instance_tag(instance_id: &str) -> String {
+    let hash = instance_id
+        .bytes()
+        .fold(0x811c9dc5u32, |h, b| (h ^ b as u32).wrapping_mul(0x01000193));
+    format!("{:08x}", hash)
+}
+
+/// Тег из clientOrderId вида hft-{tag}-{session}-{n}
+fn client_tag(client_order_id: &str) -> Option<&str> {
+    let rest = client_order_id.strip_prefix(CLIENT_ID_PREFIX)?;
+    let mut parts = rest.split('-');
+    let tag = parts.next()?;
+    (parts.count() == 2).then_some(tag)
+}
+
+/// Жизненный цикл ордеров, выставленных через place_order.
+///
+/// Ключ - clientOrderId, который генерирует ядро: по нему склеиваются
+/// ответ на order.place и события ORDER_TRADE_UPDATE из user data stream.
+pub struct OrderManager {
+    orders: DashMap<String, OrderRecord>,
+    /// orderId биржи -> clientOrderId
+    by_order_id: DashMap<i64, String>,
+    /// Завершённые ордера в порядке завершения (для вытеснения старых)
+    terminal: Mutex<VecDeque<String>>,
+    session: String,
+    counter: AtomicU64,
+    /// Тег clientOrderId -> instance_id (инстансы, ставившие ордера в этой сессии)
+    tags: DashMap<String, String>,
+    user_data: Arc<UserDataManager>,
+}
+
+impl OrderManager {
+    pub fn new(user_data: Arc<UserDataManager>) -> Arc<Self> {
+        let user_rx = user_data.updates_tx.subscribe();
+        let manager = Arc::new(Self {
+            orders: DashMap::new(),
+            by_order_id: DashMap::new(),
+            terminal: Mutex::new(VecDeque::new()),
+            session: chrono::Utc::now().timestamp().to_string(),
+            counter: AtomicU64::new(0),
+            tags: DashMap::new(),
+            user_data,
+        });
+
+        {
+            let manager = manager.clone();
+            tokio::spawn(async move { manager.user_data_loop(user_rx).await });
+        }
+        {
+            let manager = manager.clone();
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(STALE_SWEEP_EVERY).await;
+                    manager.expire_stale();
+                }
+            });
+        }
+
+        manager
+    }
+
+    /// Уникальный clientOrderId (Binance: до 36 символов, [.A-Z:/a-z0-9_-]):
+    /// hft-{тег инстанса}-{сессия}-{n}. По тегу ордер узнаётся и после
+    /// перезапуска ядра, и в интерфейсе Binance
+    pub fn next_client_id(&self, instance_id: Option<&str>) -> String {
+        let n = self.counter.fetch_add(1, Ordering::Relaxed) + 1;
+        let tag = match instance_id {
+            Some(id) => {
+                let tag = instance_tag(id);
+                if !self.tags.contains_key(&tag) {
+                    self.tags.insert(tag.clone(), id.to_string());
+                }
+                tag
+            }
+            None => NO_INSTANCE_TAG.to_string(),
+        };
+        format!("{}{}-{}-{}", CLIENT_ID_PREFIX, tag, self.session, n)
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // ЖИЗНЕННЫЙ ЦИКЛ
+    // ═══════════════════════════════════════════════════════════
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn on_request(
+        &self,
+        client_order_id: &str,
+        instance_id: Option<&str>,
+        api_key: &str,
+        symbol: &str,
+        side: &str,
+        order_type: u8,
+        price: f64,
+        qty: f64,
+    ) {
+        let now = now_ms();
+        let record = OrderRecord {
+            client_order_id: client_order_id.to_string(),
+            order_id: None,
+            instance_id: instance_id.map(str::to_string),
+            key: key_id(api_key),
+            symbol: symbol.to_uppercase(),
+            side: side.to_uppercase(),
+            order_type: match order_type {
+                1 => "MARKET",
+                2 => "STOP_MARKET",
+                _ => "LIMIT",
+            }.to_string(),
+            price,
+            orig_qty: qty,
+            state: OrderState::Pending,
+            filled_qty: 0.0,
+            avg_price: 0.0,
+            commission: 0.0,
+            realized_pnl: 0.0,
+            error_code: None,
+            dry_run: false,
+            context: None,
+            created_at: now,
+            updated_at: now,
+            transitions: vec![Transition { state: OrderState::Pending, time: now }],
+        };
+        self.orders.insert(client_order_id.to_string(), record);
+    }
+
+    /// Успешный ответ на order.place
+    pub fn on_ack(&self, client_order_id: &str, order_id: i64, status: &str) {
+        self.by_order_id.insert(order_id, client_order_id.to_string());
+
+        let Some(mut o) = self.orders.get_mut(client_order_id) else { return };
+        o.order_id = Some(order_id);
+        let state = OrderState::from_binance(status).unwrap_or(OrderState::New);
+        let closed = o.set_state(state, now_ms()) && state.is_terminal();
+        drop(o);
+
+        if closed {
+            self.retire(client_order_id);
+        }
+    }
+
+    /// Ошибка на order.place (ответ биржи или дисконнект). После обрыва или
+    /// таймаута ордер мог встать: он остаётся Pending с error_code, исход
+    /// решают user data или expire_stale
+    pub fn on_ack_error(&self, client_order_id: &str, error_code: i32) {
+        let Some(mut o) = self.orders.get_mut(client_order_id) else { return };
+        o.error_code = Some(error_code);
+        if order_errors::is_status_unknown(error_code) {
+            o.updated_at = now_ms();
+            return;
+        }
+        let closed = o.set_state(OrderState::Failed, now_ms());
+        drop(o);
+
+        if closed {
+            self.retire(client_order_id);
+        }
+    }
+
+    /// Успешный ответ на order.cancel. false - ордер не найден или уже завершён
+    pub fn on_cancel_ack(&self, order_id: i64) -> bool {
+        let Some(client_order_id) = self.by_order_id.get(&order_id).map(|c| c.clone()) else { return false };
+        let Some(mut o) = self.orders.get_mut(&client_order_id) else { return false };
+        let closed = o.set_state(OrderState::Canceled, now_ms());
+        drop(o);
+
+        if closed {
+            self.retire(&client_order_id);
+        }
+        closed
+    }
+
+    /// Намерение dry-run инстанса: сразу NEW с синтетическим orderId
+    pub fn on_dry_run(&self, client_order_id: &str, order_id: i64, context: MarketContext) {
+        if let Some(mut o) = self.orders.get_mut(client_order_id) {
+            o.dry_run = true;
+            o.context = Some(context);
+        }
+        self.on_ack(client_order_id, order_id, "NEW");
+    }
+
+    /// Закрывает открытые намерения остановленного dry-run инстанса
+    /// (биржа их не отменит, а open=true иначе показывал бы их вечно)
+    pub fn expire_dry_run(&self, instance_id: &str) -> usize {
+        let now = now_ms();
+        let expired: Vec<String> = self.orders
+            .iter_mut()
+            .filter(|o| o.dry_run && o.instance_id.as_deref() == Some(instance_id))
+            .filter_map(|mut o| o.set_state(OrderState::Expired, now).then(|| o.client_order_id.clone()))
+            .collect();
+        for cid in &expired {
+            self.retire(cid);
+        }
+        expired.len()
+    }
+
+    /// Без user data stream биржа не сообщит, чем кончился ордер: такие
+    /// ордера иначе висели бы открытыми и не вытеснялись никогда. Со stream
+    /// ордер с неизвестным после ошибки статусом, которого stream так и не
+    /// показал, считается не принятым
+    fn expire_stale(&self) {
+        let now = now_ms();
+        let mut stale = Vec::new();
+        let mut failed = Vec::new();
+        for mut o in self.orders.iter_mut() {
+            if o.dry_run || o.state.is_terminal() {
+                continue;
+            }
+            let age = now - o.updated_at;
+            let (state, list) = if !self.user_data.has_stream_id(&o.key) {
+                if age <= STALE_AFTER_MS {
+                    continue;
+                }
+                (OrderState::Stale, &mut stale)
+            } else if o.state == OrderState::Pending && o.error_code.is_some() && age > UNCONFIRMED_AFTER_MS {
+                (OrderState::Failed, &mut failed)
+            } else {
+                continue;
+            };
+            if o.set_state(state, now) {
+                list.push(o.client_order_id.clone());
+            }
+        }
+        for cid in stale.iter().chain(&failed) {
+            self.retire(cid);
+        }
+        if !stale.is_empty() {
+            tracing::warn!("📒 {} open orders marked stale: no user data stream for their keys", stale.len());
+        }
+        if !failed.is_empty() {
+            tracing::warn!("📒 {} orders with unknown status never showed up in user data, marked failed", failed.len());
+        }
+    }
+
+    fn on_update(&self, api_key: &str, u: &RawOrderUpdate) {
+        if !self.orders.contains_key(&u.client_order_id) && !self.adopt(api_key, u) {
+            // Ордер выставлен не через ядро
+            return;
+        }
+        let Some(mut o) = self.orders.get_mut(&u.client_order_id) else { return };
+
+        if o.order_id.is_none() {
+            o.order_id = Some(u.order_id);
+            self.by_order_id.insert(u.order_id, u.client_order_id.clone());
+        }
+
+        if u.is_fill() {
+            o.filled_qty = u.cum_filled_qty.parse().unwrap_or(o.filled_qty);
+            o.avg_price = u.avg_price.parse().unwrap_or(o.avg_price);
+            o.commission += u.fee_usdt();
+            o.realized_pnl += u.realized();
+            o.updated_at = u.event_time;
+        }
+
+        let state = OrderState::from_binance(&u.status);
+        let closed = match state {
+            Some(state) => o.set_state(state, u.event_time) && state.is_terminal(),
+            None => false,
+        };
+        let filled = (closed && state == Some(OrderState::Filled)).then(|| {
+            let message = format!("Filled {} {} {} @ {}", o.side, o.filled_qty, o.symbol, o.avg_price);
+            (o.instance_id.clone(), message)
+        });
+        drop(o);
+
+        if let Some((instance_id, message)) = filled {
+            stats::order_filled(instance_id.as_deref());
+            notifications::notify(NotifyKind::OrderFilled, instance_id.as_deref(), message);
+        }
+        if closed {
+            self.retire(&u.client_order_id);
+        }
+    }
+
+    /// Ордер ядра из прошлой сессии (перезапуск с ордерами в стакане):
+    /// заводим запись по событию биржи, инстанс - по тегу, если он уже ставил ордера
+    fn adopt(&self, api_key: &str, u: &RawOrderUpdate) -> bool {
+        let Some(tag) = client_tag(&u.client_order_id) else { return false };
+        let instance_id = self.tags.get(tag).map(|id| id.clone());
+        let now = now_ms();
+        let record = OrderRecord {
+            client_order_id: u.client_order_id.clone(),
+            order_id: Some(u.order_id),
+            instance_id,
+            key: key_id(api_key),
+            symbol: u.symbol.clone(),
+            side: u.side.clone(),
+            order_type: u.order_type.clone(),
+            price: u.price.parse().unwrap_or(0.0),
+            orig_qty: u.orig_qty.parse().unwrap_or(0.0),
+            state: OrderState::New,
+            filled_qty: 0.0,
+            avg_price: 0.0,
+            commission: 0.0,
+            realized_pnl: 0.0,
+            error_code: None,
+            dry_run: false,
+            context: None,
+            created_at: now,
+            updated_at: now,
+            transitions: vec![Transition { state: OrderState::New, time: now }],
+        };
+        self.by_order_id.insert(u.order_id, u.client_order_id.clone());
+        self.orders.insert(u.client_order_id.clone(), record);
+        tracing::info!("📒 Adopted order {} from a previous session", u.client_order_id);
+        true
+    }
+
+    /// Ставит завершённый ордер в очередь на вытеснение
+    fn retire(&self, client_order_id: &str) {
+        let mut terminal = self.terminal.lock().unwrap();
+        terminal.push_back(client_order_id.to_string());
+
+        while terminal.len() > MAX_TERMINAL_ORDERS {
+            let Some(old) = terminal.pop_front() else { break };
+            if let Some((_, o)) = self.orders.remove(&old) {
+                if let Some(order_id) = o.order_id {
+                    self.by_order_id.remove(&order_id);
+                }
+            }
+        }
+    }
+
+    async fn user_data_loop(self: Arc<Self>, mut rx: broadcast::Receiver<UserDataUpdate>) {
+        loop {
+            match rx.recv().await {
+                Ok(update) => {
+                    if let UserDataEvent::OrderTradeUpdate(o) = &update.event {
+                        self.on_update(&update.api_key, o);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("📒 Order manager lagged {} user data events", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // ЗАПРОСЫ
+    // ═══════════════════════════════════════════════════════════
+
+    pub fn get(&self, client_order_id: &str) -> Option<OrderRecord> {
+        self.orders.get(client_order_id).map(|o| o.clone())
+    }
+
+    pub fn get_by_order_id(&self, order_id: i64) -> Option<OrderRecord> {
+        let client_order_id = self.by_order_id.get(&order_id)?.clone();
+        self.get(&client_order_id)
+    }
+
+    /// Ордера по фильтру, новые первыми
+    pub fn list(&self, filter: &OrderFilter) -> Vec<OrderRecord> {
+        let mut orders: Vec<OrderRecord> = self.orders
+            .iter()
+            .filter(|o| filter.matches(o.value()))
+            .map(|o| o.value().clone())
+            .collect();
+        orders.sort_by_key(|o| std::cmp::Reverse(o.created_at));
+        orders
+    }
+
+    /// Ордера по фильтру в C-представлении (для FFI)
+    pub fn list_c(&self, filter: &OrderFilter) -> Vec<COrder> {
+        let mut orders: Vec<(i64, COrder)> = self.orders
+            .iter()
+            .filter(|o| filter.matches(o.value()))
+            .map(|o| (o.created_at, o.to_c()))
+            .collect();
+        orders.sort_by_key(|(created_at, _)| std::cmp::Reverse(*created_at));
+        orders.into_iter().map(|(_, o)| o).collect()
+    }
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> Arc<OrderManager> {
+        let (event_tx, _) = broadcast::channel(1);
+        OrderManager::new(UserDataManager::new(String::new(), String::new(), event_tx))
+    }
+
+    fn request(orders: &OrderManager) -> String {
+        let cid = orders.next_client_id(Some("inst"));
+        orders.on_request(&cid, Some("inst"), "key", "btcusdt", "buy", 0, 100.0, 1.0);
+        cid
+    }
+
+    fn update(cid: &str, order_id: i64, status: &str, filled: &str) -> RawOrderUpdate {
+        let mut u: RawOrderUpdate = serde_json::from_value(serde_json::json!({
+            "s": "BTCUSDT", "c": cid, "S": "BUY", "o": "LIMIT", "q": "1", "p": "100", "ap": "100",
+            "x": if filled == "0" { "NEW" } else { "TRADE" }, "X": status, "i": order_id,
+            "l": filled, "z": filled, "L": "100", "T": 0,
+        }))
+        .unwrap();
+        u.event_time = now_ms();
+        u
+    }
+
+    fn state(orders: &OrderManager, cid: &str) -> OrderState {
+        orders.get(cid).unwrap().state
+    }
+
+    #[tokio::test]
+    async fn client_id_carries_instance_tag() {
+        let orders = manager();
+        let cid = orders.next_client_id(Some("inst"));
+
+        assert!(cid.len() <= 36);
+        assert_eq!(client_tag(&cid), Some(instance_tag("inst").as_str()));
+        assert_ne!(orders.next_client_id(Some("inst")), cid);
+    }
+
+    #[tokio::test]
+    async fn ack_after_fill_does_not_reopen_order() {
+        let orders = manager();
+        let cid = request(&orders);
+
+        // MARKET/IOC: FILLED из user data раньше ответа на order.place
+        orders.on_update("key", &update(&cid, 7, "FILLED", "1"));
+        orders.on_ack(&cid, 7, "NEW");
+
+        let o = orders.get(&cid).unwrap();
+        assert_eq!(o.state, OrderState::Filled);
+        assert_eq!(o.filled_qty, 1.0);
+        assert_eq!(orders.get_by_order_id(7).unwrap().client_order_id, cid);
+    }
+
+    #[tokio::test]
+    async fn unknown_status_keeps_order_pending() {
+        let orders = manager();
+        let cid = request(&orders);
+
+        orders.on_ack_error(&cid, -9700);
+        assert_eq!(state(&orders, &cid), OrderState::Pending);
+        assert_eq!(orders.get(&cid).unwrap().error_code, Some(-9700));
+
+        // Ордер всё-таки встал
+        orders.on_update("key", &update(&cid, 8, "NEW", "0"));
+        assert_eq!(state(&orders, &cid), OrderState::New);
+
+        let rejected = request(&orders);
+        orders.on_ack_error(&rejected, -2019);
+        assert_eq!(state(&orders, &rejected), OrderState::Failed);
+    }
+
+    #[tokio::test]
+    async fn cancel_ack_closes_open_order_once() {
+        let orders = manager();
+        let cid = request(&orders);
+        orders.on_ack(&cid, 9, "NEW");
+
+        assert!(orders.on_cancel_ack(9));
+        assert!(!orders.on_cancel_ack(9));
+        assert_eq!(state(&orders, &cid), OrderState::Canceled);
+    }
+
+    #[tokio::test]
+    async fn orders_without_user_data_go_stale() {
+        let orders = manager();
+        let old = request(&orders);
+        let fresh = request(&orders);
+        let filled = request(&orders);
+        orders.on_ack(&filled, 10, "FILLED");
+        for cid in [&old, &filled] {
+            orders.orders.get_mut(cid).unwrap().updated_at -= STALE_AFTER_MS + 1;
+        }
+
+        orders.expire_stale();
+
+        assert_eq!(state(&orders, &old), OrderState::Stale);
+        assert_eq!(state(&orders, &fresh), OrderState::Pending);
+        assert_eq!(state(&orders, &filled), OrderState::Filled);
+        let open = orders.list(&OrderFilter { instance_id: Some("inst"), open_only: true, ..Default::default() });
+        assert_eq!(open.len(), 1);
+    }
+}
@@ -0,0 +1,355 @@
+// src/paper.rs
+
+use crossbeam::channel::Sender;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex, OnceLock};
+
+use crate::ffi_types::{pack_str, CEvent, CEventData, COrderUpdate, Sequencer, EVENT_BOOK_TICKER, EVENT_ORDER_UPDATE, EVENT_TRADE};
+use crate::latency;
+use crate::strategies::order::OrderResult;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PaperConfig {
+    /// Комиссия за исполнение лимитного ордера из книги, bps
+    pub maker_fee_bps: f64,
+    /// Комиссия market и пересекающих спред лимитных ордеров, bps
+    pub taker_fee_bps: f64,
+    /// Проскальзывание market-ордера от лучшей цены, bps
+    pub slippage_bps: f64,
+}
+
+impl Default for PaperConfig {
+    fn default() -> Self {
+        Self {
+            maker_fee_bps: 2.0,
+            taker_fee_bps: 5.0,
+            slippage_bps: 0.0,
+        }
+    }
+}
+
+static CONFIG: OnceLock<PaperConfig> = OnceLock::new();
+
+pub fn init(config: PaperConfig) {
+    CONFIG.set(config).ok();
+}
+
+/// Некорректные сторона/цена/количество
+pub const ERR_PAPER_INVALID: i32 = -9500;
+/// Отмена ордера, которого нет в книге симулятора
+pub const ERR_PAPER_UNKNOWN_ORDER: i32 = -9501;
+/// Market-ордер до первого bookTicker по символу
+pub const ERR_PAPER_NO_PRICE: i32 = -9502;
+
+// Коды COrderUpdate
+const SIDE_BUY: u8 = 0;
+const SIDE_SELL: u8 = 1;
+const EXEC_NEW: u8 = 0;
+const EXEC_TRADE: u8 = 1;
+const EXEC_CANCELED: u8 = 2;
+const STATUS_NEW: u8 = 0;
+const STATUS_FILLED: u8 = 2;
+const STATUS_CANCELED: u8 = 3;
+
+// ═══════════════════════════════════════════════════════════
+// СЧЁТ
+// ═══════════════════════════════════════════════════════════
+
+struct PaperOrder {
+    order_id: i64,
+    symbol: String,
+    side: u8,
+    order_type: u8,
+    price: f64,
+    quantity: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PaperPosition {
+    /// > 0 long, < 0 short
+    pub amount: f64,
+    pub entry_price: f64,
+}
+
+/// Итоги счёта для InstanceInfo
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PaperStats {
+    pub open_orders: usize,
+    pub fills: u64,
+    pub volume: f64,
+    pub fees: f64,
+    pub realized_pnl: f64,
+    pub positions: HashMap<String, PaperPosition>,
+}
+
+#[derive(Default)]
+struct Book {
+    next_order_id: i64,
+    open: Vec<PaperOrder>,
+    /// SYMBOL -> (bid, ask)
+    touch: HashMap<String, (f64, f64)>,
+    /// received_at_ns последнего рыночного события (время replay)
+    clock_ns: u64,
+    stats: PaperStats,
+}
+
+/// Симулятор биржи одного инстанса: лимитные ордера стоят в книге и
+/// исполняются по bookTicker/trade, market - по лучшей цене с проскальзыванием.
+/// Исполнение всегда полное: очередь в стакане и объём уровня не моделируются.
+pub struct PaperAccount {
+    config: PaperConfig,
+    /// Канал стратегии для ORDER_TRADE_UPDATE, возникших при place/cancel
+    tx: Sender<CEvent>,
+    /// true - время событий из часов ядра (paper на live), false - из записи (replay)
+    realtime: bool,
+    book: Mutex<Book>,
+    seqs: Sequencer,
+}
+
+impl PaperAccount {
+    fn new(tx: Sender<CEvent>, realtime: bool) -> Self {
+        Self {
+            config: CONFIG.get().cloned().unwrap_or_default(),
+            tx,
+            realtime,
+            book: Mutex::new(Book { next_order_id: 1, ..Default::default() }),
+            seqs: Sequencer::default(),
+        }
+    }
+
+    pub fn place(&self, symbol: &str, side: &str, order_type: u8, price: f64, quantity: f64) -> OrderResult {
+        let side = match side {
+            "BUY" => SIDE_BUY,
+            "SELL" => SIDE_SELL,
+            _ => return reject(ERR_PAPER_INVALID),
+        };
+        // NaN тоже не проходит
+        let positive = |v: f64| v > 0.0;
+        if !positive(quantity) || (order_type == 0 && !positive(price)) {
+            return reject(ERR_PAPER_INVALID);
+        }
+
+        let mut book = self.book.lock().unwrap();
+        let symbol = symbol.to_uppercase();
+        let touch = book.touch.get(&symbol).copied();
+        if order_type == 1 && touch.is_none() {
+            return reject(ERR_PAPER_NO_PRICE);
+        }
+
+        let order_id = book.next_order_id;
+        book.next_order_id += 1;
+        let order = PaperOrder { order_id, symbol, side, order_type, price, quantity };
+
+        let mut events = vec![self.update(&book, &order, EXEC_NEW, STATUS_NEW, 0.0, 0.0)];
+
+        // Market и пересекающий спред limit исполняются сразу как taker
+        let taker_price = touch.and_then(|(bid, ask)| match (order_type, side) {
+            (1, SIDE_BUY) => Some(ask * (1.0 + self.config.slippage_bps / 10_000.0)),
+            (1, _) => Some(bid * (1.0 - self.config.slippage_bps / 10_000.0)),
+            (_, SIDE_BUY) => (ask <= price).then_some(ask),
+            _ => (bid >= price).then_some(bid),
+        });
+        match taker_price {
+            Some(fill_price) => events.push(self.fill(&mut book, &order, fill_price, self.config.taker_fee_bps)),
+            None => book.open.push(order),
+        }
+        drop(book);
+
+        for event in events {
+            self.emit(event);
+        }
+        OrderResult { success: true, order_id, error_code: 0 }
+    }
+
+    pub fn cancel(&self, order_id: i64) -> OrderResult {
+        let mut book = self.book.lock().unwrap();
+        let Some(index) = book.open.iter().position(|o| o.order_id == order_id) else {
+            return reject(ERR_PAPER_UNKNOWN_ORDER);
+        };
+        let order = book.open.remove(index);
+        let event = self.update(&book, &order, EXEC_CANCELED, STATUS_CANCELED, 0.0, 0.0);
+        drop(book);
+
+        self.emit(event);
+        OrderResult { success: true, order_id, error_code: 0 }
+    }
+
+    /// Рыночное событие: обновляет лучшие цены и исполняет стоящие ордера.
+    /// Возвращает ORDER_TRADE_UPDATE, которые нужно доставить стратегии после самого события
+    pub fn on_market(&self, event: &CEvent) -> Vec<CEvent> {
+        let mut book = self.book.lock().unwrap();
+        book.clock_ns = event.received_at_ns;
+
+        // (symbol, buy исполняется при цене <=, sell - при цене >=)
+        let (symbol, buy_at, sell_at) = match event.event_type {
+            EVENT_BOOK_TICKER => {
+                let t = unsafe { &event.data.book_ticker };
+                let symbol = t.symbol_str().to_string();
+                book.touch.insert(symbol.clone(), (t.bid_price, t.ask_price));
+                (symbol, t.ask_price, t.bid_price)
+            }
+            EVENT_TRADE => {
+                let t = unsafe { &event.data.trade };
+                (t.symbol_str().to_string(), t.price, t.price)
+            }
+            _ => return Vec::new(),
+        };
+
+        let mut fills = Vec::new();
+        let mut i = 0;
+        while i < book.open.len() {
+            let o = &book.open[i];
+            let hit = o.symbol == symbol
+                && match o.side {
+                    SIDE_BUY => buy_at <= o.price,
+                    _ => sell_at >= o.price,
+                };
+            if hit {
+                let order = book.open.remove(i);
+                fills.push(self.fill(&mut book, &order, order.price, self.config.maker_fee_bps));
+            } else {
+                i += 1;
+            }
+        }
+        fills
+    }
+
+    /// Ордер ещё стоит в книге (в симуляторе исполнение всегда полное)
+    pub fn is_open(&self, order_id: i64) -> bool {
+        self.book.lock().unwrap().open.iter().any(|o| o.order_id == order_id)
+    }
+
+    /// Позиция по символу (> 0 long, < 0 short)
+    pub fn position(&self, symbol: &str) -> f64 {
+        self.book.lock().unwrap().stats.positions.get(symbol).map(|p| p.amount).unwrap_or(0.0)
+    }
+
+    pub fn stats(&self) -> PaperStats {
+        let book = self.book.lock().unwrap();
+        let mut stats = book.stats.clone();
+        stats.open_orders = book.open.len();
+        stats
+    }
+
+    /// Полное исполнение: позиция, комиссия, реализованный PnL
+    fn fill(&self, book: &mut Book, order: &PaperOrder, price: f64, fee_bps: f64) -> CEvent {
+        let signed = if order.side == SIDE_BUY { order.quantity } else { -order.quantity };
+        let commission = order.quantity * price * fee_bps / 10_000.0;
+
+        let pos = book.stats.positions.entry(order.symbol.clone()).or_default();
+        let mut realized = 0.0;
+        if pos.amount == 0.0 || pos.amount.signum() == signed.signum() {
+            let amount = pos.amount + signed;
+            pos.entry_price = (pos.entry_price * pos.amount.abs() + price * order.quantity) / amount.abs();
+            pos.amount = amount;
+        } else {
+            let closed = order.quantity.min(pos.amount.abs());
+            realized = closed * (price - pos.entry_price) * pos.amount.signum();
+            pos.amount += signed;
+            if pos.amount.abs() < 1e-12 {
+                *pos = PaperPosition::default();
+            } else if pos.amount.signum() == signed.signum() {
+                // Перевернулись: остаток открыт по цене исполнения
+                pos.entry_price = price;
+            }
+        }
+
+        let stats = &mut book.stats;
+        stats.fills += 1;
+        stats.volume += order.quantity * price;
+        stats.fees += commission;
+        stats.realized_pnl += realized;
+
+        let mut event = self.update(book, order, EXEC_TRADE, STATUS_FILLED, order.quantity, price);
+        let u = unsafe { &mut event.data.order_update };
+        u.cum_filled_qty = order.quantity;
+        u.avg_price = price;
+        u.commission = commission;
+        u.realized_pnl = realized;
+        event
+    }
+
+    fn update(&self, book: &Book, order: &PaperOrder, exec_type: u8, status: u8, last_qty: f64, last_price: f64) -> CEvent {
+        let now_ns = if self.realtime || book.clock_ns == 0 { latency::now_ns() } else { book.clock_ns };
+        let (symbol, symbol_len) = pack_str::<16>(&order.symbol);
+        let (client_order_id, client_order_id_len) = pack_str::<36>(&format!("paper-{}", order.order_id));
+
+        let mut event = CEvent {
+            event_type: EVENT_ORDER_UPDATE,
+            data: CEventData {
+                order_update: COrderUpdate {
+                    symbol,
+                    symbol_len,
+                    client_order_id,
+                    client_order_id_len,
+                    order_id: order.order_id,
+                    side: order.side,
+                    order_type: order.order_type,
+                    exec_type,
+                    status,
+                    reduce_only: false,
+                    price: order.price,
+                    orig_qty: order.quantity,
+                    last_filled_qty: last_qty,
+                    last_filled_price: last_price,
+                    cum_filled_qty: 0.0,
+                    avg_price: 0.0,
+                    commission: 0.0,
+                    realized_pnl: 0.0,
+                    time: (now_ns / 1_000_000) as i64,
+                },
+            },
+            received_at_ns: now_ns,
+            seq: 0,
+        };
+        self.seqs.stamp(&mut event);
+        event
+    }
+
+    /// Вызывается из потока стратегии (place/cancel): ждать места в
+    /// собственном канале нельзя, при переполнении событие теряется
+    fn emit(&self, event: CEvent) {
+        if self.tx.try_send(event).is_err() {
+            tracing::warn!("⚠️ Paper order update #{} dropped: strategy channel full", unsafe {
+                event.data.order_update.order_id
+            });
+        }
+    }
+}
+
+fn reject(error_code: i32) -> OrderResult {
+    OrderResult { success: false, order_id: -1, error_code }
+}
+
+// ═══════════════════════════════════════════════════════════
+// РЕЕСТР
+// ═══════════════════════════════════════════════════════════
+
+/// instance_id -> счёт симулятора
+static ACCOUNTS: LazyLock<DashMap<String, Arc<PaperAccount>>> = LazyLock::new(DashMap::new);
+
+/// С этого момента ордера инстанса уходят в симулятор
+pub fn register(instance_id: &str, tx: Sender<CEvent>, realtime: bool) -> Arc<PaperAccount> {
+    let account = Arc::new(PaperAccount::new(tx, realtime));
+    ACCOUNTS.insert(instance_id.to_string(), account.clone());
+    account
+}
+
+pub fn unregister(instance_id: &str) -> Option<Arc<PaperAccount>> {
+    ACCOUNTS.remove(instance_id).map(|(_, a)| a)
+}
+
+/// Счёт симулятора, если инстанс не торгует на бирже
+pub fn account(instance_id: &str) -> Option<Arc<PaperAccount>> {
+    if ACCOUNTS.is_empty() {
+        return None;
+    }
+    ACCOUNTS.get(instance_id).map(|a| a.clone())
+}
@@ -0,0 +1,288 @@
+// src/pnl.rs
+
+use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::ffi_types::{CEvent, EVENT_BOOK_TICKER, EVENT_TRADE};
+use crate::orders::OrderManager;
+use crate::user_data::{key_id, RawOrderUpdate, UserDataEvent, UserDataUpdate};
+
+// ═══════════════════════════════════════════════════════════
+// ТИПЫ
+// ═══════════════════════════════════════════════════════════
+
+/// Позиция и PnL по одному символу (средняя цена входа, USDT)
+#[derive(Debug, Clone, Copy, Default)]
+struct Book {
+    qty: f64,
+    avg_entry: f64,
+    realized: f64,
+    fees: f64,
+    fills: u64,
+}
+
+impl Book {
+    /// Применяет исполнение. qty со знаком: BUY > 0, SELL < 0
+    fn apply_fill(&mut self, qty: f64, price: f64, fee: f64) {
+        self.fees += fee;
+        self.fills += 1;
+
+        if self.qty == 0.0 || self.qty.signum() == qty.signum() {
+            // Наращиваем позицию
+            let total = self.qty.abs() + qty.abs();
+            self.avg_entry = (self.avg_entry * self.qty.abs() + price * qty.abs()) / total;
+            self.qty += qty;
+            return;
+        }
+
+        // Сокращаем / переворачиваем
+        let closed = qty.abs().min(self.qty.abs());
+        self.realized += closed * (price - self.avg_entry) * self.qty.signum();
+        self.qty += qty;
+
+        if self.qty.abs() < 1e-12 {
+            self.qty = 0.0;
+            self.avg_entry = 0.0;
+        } else if self.qty.signum() == qty.signum() {
+            // Перевернулись - остаток открыт по цене исполнения
+            self.avg_entry = price;
+        }
+    }
+
+    fn unrealized(&self, last_price: Option<f64>) -> f64 {
+        match last_price {
+            Some(p) if self.qty != 0.0 => self.qty * (p - self.avg_entry),
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SymbolPnl {
+    pub symbol: String,
+    pub position_qty: f64,
+    pub avg_entry: f64,
+    pub last_price: Option<f64>,
+    pub realized_pnl: f64,
+    pub fees: f64,
+    pub unrealized_pnl: f64,
+    pub fills: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PnlSummary {
+    /// instance_id или первые 8 символов api_key
+    pub id: String,
+    pub realized_pnl: f64,
+    pub fees: f64,
+    /// Funding известен только на уровне ключа
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub funding: Option<f64>,
+    pub unrealized_pnl: f64,
+    pub net_pnl: f64,
+    pub fills: u64,
+    pub symbols: Vec<SymbolPnl>,
+}
+
+impl PnlSummary {
+    fn finish(mut self) -> Self {
+        self.net_pnl = self.realized_pnl - self.fees + self.funding.unwrap_or(0.0) + self.unrealized_pnl;
+        self
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// PNL TRACKER
+// ═══════════════════════════════════════════════════════════
+
+/// Realized/unrealized PnL по инстансам и ключам с момента запуска ядра.
+///
+/// Исполнения атрибутируются инстансу через order manager (clientOrderId).
+/// Realized считается по средней цене входа собственных fills инстанса,
+/// unrealized - по последней цене из market data (bookTicker mid / trade).
+pub struct PnlTracker {
+    orders: Arc<OrderManager>,
+    /// (instance_id, symbol) -> Book
+    instances: DashMap<(String, String), Book>,
+    /// (key_id, symbol) -> Book
+    keys: DashMap<(String, String), Book>,
+    /// key_id -> funding (USDT)
+    key_funding: DashMap<String, f64>,
+    last_price: DashMap<String, f64>,
+}
+
+impl PnlTracker {
+    pub fn new(
+        orders: Arc<OrderManager>,
+        market_rx: broadcast::Receiver<CEvent>,
+        user_rx: broadcast::Receiver<UserDataUpdate>,
+    ) -> Arc<Self> {
+        let tracker = Arc::new(Self {
+            orders,
+            instances: DashMap::new(),
+            keys: DashMap::new(),
+            key_funding: DashMap::new(),
+            last_price: DashMap::new(),
+        });
+
+        {
+            let tracker = tracker.clone();
+            tokio::spawn(async move { tracker.market_loop(market_rx).await });
+        }
+        {
+            let tracker = tracker.clone();
+            tokio::spawn(async move { tracker.user_data_loop(user_rx).await });
+        }
+
+        tracker
+    }
+
+    async fn market_loop(self: Arc<Self>, mut rx: broadcast::Receiver<CEvent>) {
+        loop {
+            match rx.recv().await {
+                Ok(event) => unsafe {
+                    match event.event_type {
+                        EVENT_BOOK_TICKER => {
+                            let t = &event.data.book_ticker;
+                            self.last_price.insert(t.symbol_str().to_string(), t.mid_price());
+                        }
+                        EVENT_TRADE => {
+                            let t = &event.data.trade;
+                            self.last_price.insert(t.symbol_str().to_string(), t.price);
+                        }
+                        _ => {}
+                    }
+                },
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    async fn user_data_loop(self: Arc<Self>, mut rx: broadcast::Receiver<UserDataUpdate>) {
+        loop {
+            match rx.recv().await {
+                Ok(update) => self.on_user_data(&update),
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("💰 PnL tracker lagged {} user data events", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    fn on_user_data(&self, update: &UserDataUpdate) {
+        let key = key_id(&update.api_key);
+
+        match &update.event {
+            UserDataEvent::OrderTradeUpdate(o) if o.is_fill() => {
+                self.on_fill(&key, o);
+            }
+
+            UserDataEvent::AccountUpdate(a) if a.reason == "FUNDING_FEE" => {
+                let amount: f64 = a.balances
+                    .iter()
+                    .filter(|b| b.asset == "USDT")
+                    .filter_map(|b| b.balance_change.parse::<f64>().ok())
+                    .sum();
+                *self.key_funding.entry(key).or_default() += amount;
+            }
+
+            _ => {}
+        }
+    }
+
+    fn on_fill(&self, key: &str, o: &RawOrderUpdate) {
+        let qty = if o.side == "SELL" { -o.last_qty() } else { o.last_qty() };
+        let price = o.last_price();
+        let fee = o.fee_usdt();
+
+        self.keys
+            .entry((key.to_string(), o.symbol.clone()))
+            .or_default()
+            .apply_fill(qty, price, fee);
+
+        if let Some(instance_id) = self.orders.get(&o.client_order_id).and_then(|r| r.instance_id) {
+            self.instances
+                .entry((instance_id, o.symbol.clone()))
+                .or_default()
+                .apply_fill(qty, price, fee);
+        }
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // ЗАПРОСЫ
+    // ═══════════════════════════════════════════════════════════
+
+    fn summarize(&self, books: &DashMap<(String, String), Book>) -> BTreeMap<String, PnlSummary> {
+        let mut out: BTreeMap<String, PnlSummary> = BTreeMap::new();
+
+        for entry in books.iter() {
+            let ((id, symbol), book) = (entry.key(), entry.value());
+            let last_price = self.last_price.get(symbol).map(|p| *p);
+            let unrealized = book.unrealized(last_price);
+
+            let s = out.entry(id.clone()).or_insert_with(|| PnlSummary {
+                id: id.clone(),
+                ..Default::default()
+            });
+            s.realized_pnl += book.realized;
+            s.fees += book.fees;
+            s.unrealized_pnl += unrealized;
+            s.fills += book.fills;
+            s.symbols.push(SymbolPnl {
+                symbol: symbol.clone(),
+                position_qty: book.qty,
+                avg_entry: book.avg_entry,
+                last_price,
+                realized_pnl: book.realized,
+                fees: book.fees,
+                unrealized_pnl: unrealized,
+                fills: book.fills,
+            });
+        }
+
+        out
+    }
+
+    pub fn instance(&self, instance_id: &str) -> Option<PnlSummary> {
+        self.summarize(&self.instances)
+            .remove(instance_id)
+            .map(PnlSummary::finish)
+    }
+
+    pub fn instances(&self) -> Vec<PnlSummary> {
+        self.summarize(&self.instances)
+            .into_values()
+            .map(PnlSummary::finish)
+            .collect()
+    }
+
+    pub fn keys(&self) -> Vec<PnlSummary> {
+        let mut keys = self.summarize(&self.keys);
+        for f in self.key_funding.iter() {
+            keys.entry(f.key().clone())
+                .or_insert_with(|| PnlSummary { id: f.key().clone(), ..Default::default() })
+                .funding = Some(*f.value());
+        }
+        keys.into_values().map(PnlSummary::finish).collect()
+    }
+
+    /// Текущий unrealized по инстансу / ключу (для отчётов из истории)
+    pub fn unrealized_by_instance(&self) -> BTreeMap<String, f64> {
+        self.summarize(&self.instances)
+            .into_iter()
+            .map(|(id, s)| (id, s.unrealized_pnl))
+            .collect()
+    }
+
+    pub fn unrealized_by_key(&self) -> BTreeMap<String, f64> {
+        self.summarize(&self.keys)
+            .into_iter()
+            .map(|(id, s)| (id, s.unrealized_pnl))
+            .collect()
+    }
+}
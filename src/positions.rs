@@ -0,0 +1,409 @@
+// src/positions.rs
+
+use dashmap::DashMap;
+use serde::Serialize;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::collections::VecDeque;
+use std::sync::{Arc, LazyLock, Mutex, OnceLock};
+use tokio::sync::broadcast;
+
+use crate::exchange_trade::ExchangeTrade;
+use crate::keystore::{self, Credentials};
+use crate::notifications::{self, NotifyKind};
+use crate::orders::OrderManager;
+use crate::paper;
+use crate::strategies::order::{current_instance, invoke_callback, route_order, OrderCallback, OrderResult};
+use crate::strategies::{intents, logs, quarantine};
+use crate::user_data::{
+    key_id, RawAccountUpdate, RawOrderUpdate, RawPositionRisk, UserDataEvent, UserDataManager, UserDataUpdate,
+};
+
+/// Столько последних id сделок по символу помним, чтобы не учесть исполнение дважды
+const RECENT_TRADES: usize = 256;
+
+/// Пауза перед повтором неудачного запроса позиций по REST
+const SEED_RETRY_MS: i64 = 10_000;
+
+// ═══════════════════════════════════════════════════════════
+// ТИПЫ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Default)]
+struct Position {
+    /// > 0 long, < 0 short
+    amount: f64,
+    entry_price: f64,
+    /// Из последнего ACCOUNT_UPDATE или снимка REST
+    unrealized_pnl: f64,
+    /// Время биржи последнего учтённого события (ms)
+    as_of: i64,
+    /// Исполнения не позже этого времени (ms) уже есть в amount: updateTime
+    /// снимка REST или время ACCOUNT_UPDATE, задавшего позицию целиком
+    settled_at: i64,
+    /// Id уже учтённых сделок, старые первыми
+    trades: VecDeque<i64>,
+}
+
+/// Снимок позиций ключа по REST
+#[derive(Debug, Clone, Copy)]
+enum Seed {
+    Pending,
+    Done,
+    /// Неудача (unix ms), повтор через SEED_RETRY_MS
+    Failed(i64),
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PositionInfo {
+    pub account: String,
+    pub symbol: String,
+    pub amount: f64,
+    pub entry_price: f64,
+    pub unrealized_pnl: f64,
+    pub updated_at: i64,
+}
+
+// ═══════════════════════════════════════════════════════════
+// РЕЕСТР
+// ═══════════════════════════════════════════════════════════
+
+// Позиция (one-way, positionSide BOTH) по ключу и символу. При запуске
+// учёта ключа позиции по всем символам берутся из REST positionRisk (нужен
+// secret), дальше ACCOUNT_UPDATE задаёт позицию символа целиком, а
+// исполнения из ORDER_TRADE_UPDATE двигают её сразу, не дожидаясь снимка.
+// Исполнение учитывается один раз по id сделки; исполнения не позже
+// updateTime снимка REST или времени ACCOUNT_UPDATE в них уже есть. Пока нет ни снимка REST, ни
+// ACCOUNT_UPDATE по символу, позиция неизвестна - это не то же, что 0.
+
+/// (api_key, SYMBOL) -> позиция
+static POSITIONS: LazyLock<DashMap<(String, String), Position>> = LazyLock::new(DashMap::new);
+
+/// api_key -> состояние снимка REST
+static SEEDS: LazyLock<DashMap<String, Seed>> = LazyLock::new(DashMap::new);
+
+static TRADE: OnceLock<Arc<ExchangeTrade>> = OnceLock::new();
+static ORDER_MANAGER: OnceLock<Arc<OrderManager>> = OnceLock::new();
+static USER_DATA: OnceLock<Arc<UserDataManager>> = OnceLock::new();
+
+pub fn init(trade: Arc<ExchangeTrade>, orders: Arc<OrderManager>, user_data: Arc<UserDataManager>) {
+    tokio::spawn(user_data_loop(user_data.updates_tx.subscribe()));
+    TRADE.set(trade).ok();
+    ORDER_MANAGER.set(orders).ok();
+    USER_DATA.set(user_data).ok();
+}
+
+/// Позиция ключа по символу. None - ещё неизвестна: нет ни снимка REST,
+/// ни ACCOUNT_UPDATE по символу
+pub fn get(api_key: &str, symbol: &str) -> Option<f64> {
+    if let Some(pos) = POSITIONS.get(&(api_key.to_string(), symbol.to_uppercase())) {
+        return Some(pos.amount);
+    }
+    // В снимке REST символа нет - позиции по нему нет
+    matches!(SEEDS.get(api_key).as_deref(), Some(Seed::Done)).then_some(0.0)
+}
+
+/// Ненулевые позиции, ?account= - только этого alias из keystore
+pub fn list(api_key: Option<&str>) -> Vec<PositionInfo> {
+    let mut out: Vec<PositionInfo> = POSITIONS
+        .iter()
+        .filter(|e| e.value().amount != 0.0)
+        .filter(|e| api_key.is_none_or(|k| e.key().0 == k))
+        .map(|e| PositionInfo {
+            account: key_id(&e.key().0),
+            symbol: e.key().1.clone(),
+            amount: e.value().amount,
+            entry_price: e.value().entry_price,
+            unrealized_pnl: e.value().unrealized_pnl,
+            updated_at: e.value().as_of,
+        })
+        .collect();
+    out.sort_by(|a, b| (&a.account, &a.symbol).cmp(&(&b.account, &b.symbol)));
+    out
+}
+
+/// Позиция видна только при запущенном user data stream ключа.
+/// С secret при первом обращении запрашивается снимок позиций по REST
+fn watch(creds: &Credentials) {
+    let Some(user_data) = USER_DATA.get() else { return };
+    if !user_data.has_stream(&creds.api_key) {
+        if let Err(e) = user_data.start_stream(&creds.api_key) {
+            tracing::warn!("📐 Failed to start user data stream for positions: {}", e);
+        }
+    }
+    if !creds.secret_key.is_empty() {
+        seed(user_data.clone(), creds);
+    }
+}
+
+/// Запрашивает позиции ключа по REST, если снимка ещё нет
+fn seed(user_data: Arc<UserDataManager>, creds: &Credentials) {
+    let now = chrono::Utc::now().timestamp_millis();
+    {
+        let mut state = SEEDS.entry(creds.api_key.clone()).or_insert(Seed::Failed(0));
+        match *state {
+            Seed::Pending | Seed::Done => return,
+            Seed::Failed(at) if now - at < SEED_RETRY_MS => return,
+            Seed::Failed(_) => *state = Seed::Pending,
+        }
+    }
+
+    let creds = creds.clone();
+    tokio::spawn(async move {
+        match user_data.position_risk(&creds.api_key, &creds.secret_key).await {
+            Ok(list) => {
+                on_snapshot(&creds.api_key, &list);
+                SEEDS.insert(creds.api_key.clone(), Seed::Done);
+                tracing::info!("📐 Positions of '{}' loaded from REST", key_id(&creds.api_key));
+            }
+            Err(e) => {
+                SEEDS.insert(creds.api_key.clone(), Seed::Failed(chrono::Utc::now().timestamp_millis()));
+                tracing::warn!("📐 Failed to load positions of '{}': {}", key_id(&creds.api_key), e);
+            }
+        }
+    });
+}
+
+// ═══════════════════════════════════════════════════════════
+// УЧЁТ
+// ═══════════════════════════════════════════════════════════
+
+async fn user_data_loop(mut rx: broadcast::Receiver<UserDataUpdate>) {
+    loop {
+        match rx.recv().await {
+            Ok(update) => match &update.event {
+                UserDataEvent::AccountUpdate(a) => on_account(&update.api_key, a),
+                UserDataEvent::OrderTradeUpdate(o) if o.is_fill() => on_fill(&update.api_key, o),
+                _ => {}
+            },
+            Err(broadcast::error::RecvError::Lagged(n)) => {
+                tracing::warn!("⚠️ Positions lagged by {} user data updates", n);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+fn on_snapshot(api_key: &str, list: &[RawPositionRisk]) {
+    for p in list {
+        if p.position_side != "BOTH" && !p.position_side.is_empty() {
+            continue;
+        }
+        let mut pos = POSITIONS.entry((api_key.to_string(), p.symbol.clone())).or_default();
+        // Поток успел принести более свежее состояние
+        if pos.as_of > p.update_time {
+            continue;
+        }
+        pos.amount = p.position_amt.parse().unwrap_or(0.0);
+        pos.entry_price = p.entry_price.parse().unwrap_or(0.0);
+        pos.unrealized_pnl = p.unrealized_pnl.parse().unwrap_or(0.0);
+        pos.as_of = p.update_time;
+        pos.settled_at = p.update_time;
+    }
+}
+
+fn on_account(api_key: &str, a: &RawAccountUpdate) {
+    for p in &a.positions {
+        if p.position_side != "BOTH" && !p.position_side.is_empty() {
+            continue;
+        }
+        let mut pos = POSITIONS.entry((api_key.to_string(), p.symbol.clone())).or_default();
+        if a.event_time < pos.as_of {
+            continue;
+        }
+        pos.amount = p.position_amt.parse().unwrap_or(0.0);
+        pos.entry_price = p.entry_price.parse().unwrap_or(0.0);
+        pos.unrealized_pnl = p.unrealized_pnl.parse().unwrap_or(0.0);
+        pos.as_of = a.event_time;
+        // Binance не упорядочивает ACCOUNT_UPDATE и ORDER_TRADE_UPDATE: исполнение,
+        // пришедшее после снимка с ним, не должно сдвинуть позицию второй раз
+        pos.settled_at = a.event_time;
+    }
+}
+
+fn on_fill(api_key: &str, o: &RawOrderUpdate) {
+    let qty = o.last_qty();
+    if qty <= 0.0 {
+        return;
+    }
+    let key = (api_key.to_string(), o.symbol.clone());
+    let mut pos = match POSITIONS.get_mut(&key) {
+        Some(pos) => pos,
+        // Снимок REST без символа - позиции по нему не было
+        None if matches!(SEEDS.get(api_key).as_deref(), Some(Seed::Done)) => POSITIONS.entry(key).or_default(),
+        // Позиция неизвестна: её задаст ACCOUNT_UPDATE по этому исполнению
+        None => return,
+    };
+    if o.trade_time <= pos.settled_at || pos.trades.contains(&o.trade_id) {
+        return;
+    }
+    pos.trades.push_back(o.trade_id);
+    if pos.trades.len() > RECENT_TRADES {
+        pos.trades.pop_front();
+    }
+    let signed = if o.side == "BUY" { qty } else { -qty };
+    let price = o.last_price();
+    let amount = pos.amount + signed;
+
+    pos.entry_price = if amount.abs() < 1e-12 {
+        0.0
+    } else if pos.amount == 0.0 || pos.amount.signum() != amount.signum() {
+        // Открытие или переворот: остаток открыт по цене этой сделки
+        price
+    } else if signed.signum() == pos.amount.signum() {
+        (pos.entry_price * pos.amount.abs() + price * qty) / amount.abs()
+    } else {
+        pos.entry_price
+    };
+    pos.amount = if amount.abs() < 1e-12 { 0.0 } else { amount };
+    pos.as_of = o.trade_time;
+}
+
+// ═══════════════════════════════════════════════════════════
+// ЗАКРЫТИЕ
+// ═══════════════════════════════════════════════════════════
+
+/// Закрывает позицию по символу market-ордером ровно на её размер.
+/// Live - reduce-only мимо circuit breaker и risk: он позицию только
+/// уменьшает и должен проходить и при сработавшем kill switch.
+/// Paper - позиция счёта симулятора, dry run - намерение в order manager.
+/// Возвращает размер ордера; 0.0 - позиции нет или она неизвестна,
+/// ордер не отправлен и on_result не вызывается
+pub fn close(
+    creds: Arc<Credentials>,
+    instance_id: Option<String>,
+    symbol: &str,
+    on_result: impl FnOnce(OrderResult) + Send + 'static,
+) -> f64 {
+    let symbol = symbol.to_uppercase();
+    let paper = instance_id.as_deref().and_then(paper::account);
+    let amount = match &paper {
+        Some(account) => account.position(&symbol),
+        None => {
+            watch(&creds);
+            match get(&creds.api_key, &symbol) {
+                Some(amount) => amount,
+                None => {
+                    let message = format!("Position {} is unknown yet, nothing to close", symbol);
+                    tracing::warn!("📐 {} [{}]", message, instance_id.as_deref().unwrap_or("api"));
+                    if let Some(id) = instance_id.as_deref() {
+                        logs::push(id, logs::LOG_WARN, &message);
+                    }
+                    return 0.0;
+                }
+            }
+        }
+    };
+    if amount == 0.0 {
+        return 0.0;
+    }
+    let side = if amount > 0.0 { "SELL" } else { "BUY" };
+    let quantity = amount.abs();
+
+    let message = format!("Closing position {} {}: {} {} MARKET", symbol, amount, side, quantity);
+    tracing::info!("📐 {} [{}]", message, instance_id.as_deref().unwrap_or("api"));
+    if let Some(id) = instance_id.as_deref() {
+        logs::push(id, logs::LOG_INFO, &message);
+    }
+
+    // Paper, dry run и карантин обрабатывает общий путь ордера
+    let simulated = instance_id.as_deref().is_some_and(|id| intents::is_active(id) || quarantine::contains(id));
+    if paper.is_some() || simulated {
+        route_order(creds, instance_id, &symbol, side, 1, 0.0, quantity, None, on_result);
+        return quantity;
+    }
+
+    let Some(trade) = TRADE.get().cloned() else { return 0.0 };
+    let api_key = creds.api_key.clone();
+    let client_order_id = ORDER_MANAGER.get().map(|orders| {
+        let cid = orders.next_client_id(instance_id.as_deref());
+        orders.on_request(&cid, instance_id.as_deref(), &api_key, &symbol, side, 1, 0.0, quantity);
+        cid
+    });
+
+    let side = side.to_string();
+    let on_result = Mutex::new(Some(on_result));
+    tokio::spawn(async move {
+        let cid = client_order_id.clone();
+        let order_desc = format!("{} {} {} (close)", side, quantity, symbol);
+        let on_resp = move |resp: serde_json::Value| {
+            let result = match resp["result"]["orderId"].as_i64() {
+                Some(order_id) => {
+                    let status = resp["result"]["status"].as_str().unwrap_or("NEW");
+                    if let (Some(orders), Some(cid)) = (ORDER_MANAGER.get(), cid.as_deref()) {
+                        orders.on_ack(cid, order_id, status);
+                    }
+                    OrderResult { success: true, order_id, error_code: 0 }
+                }
+                None => {
+                    let code = resp["error"]["code"].as_i64().unwrap_or(-1) as i32;
+                    let reason = resp["error"]["msg"].as_str().unwrap_or("no orderId in response");
+                    if let (Some(orders), Some(cid)) = (ORDER_MANAGER.get(), cid.as_deref()) {
+                        orders.on_ack_error(cid, code);
+                    }
+                    notifications::notify(
+                        NotifyKind::OrderRejected,
+                        instance_id.as_deref(),
+                        format!("Order {} rejected ({}): {}", order_desc, code, reason),
+                    );
+                    OrderResult { success: false, order_id: -1, error_code: code }
+                }
+            };
+            if let Some(on_result) = on_result.lock().unwrap().take() {
+                on_result(result);
+            }
+        };
+        trade
+            .send_reduce_only_market(&creds.api_key, &creds.secret_key, &symbol, quantity, &side, client_order_id.as_deref(), on_resp)
+            .await;
+    });
+    quantity
+}
+
+// ═══════════════════════════════════════════════════════════
+// FFI
+// ═══════════════════════════════════════════════════════════
+
+/// Позиция по символу: у paper-инстанса - счёта симулятора,
+/// иначе ключа (вместо api_key можно передать alias из keystore).
+/// NaN - позиция ещё неизвестна
+pub unsafe extern "C" fn get_position(api_key: *const c_char, symbol: *const c_char) -> f64 {
+    if api_key.is_null() || symbol.is_null() {
+        return f64::NAN;
+    }
+    let (Ok(api_key), Ok(symbol)) = (CStr::from_ptr(api_key).to_str(), CStr::from_ptr(symbol).to_str()) else {
+        return f64::NAN;
+    };
+    if let Some(account) = current_instance().as_deref().and_then(paper::account) {
+        return account.position(&symbol.to_uppercase());
+    }
+    let creds = keystore::resolve(api_key, "");
+    watch(&creds);
+    get(&creds.api_key, symbol).unwrap_or(f64::NAN)
+}
+
+/// Закрывает позицию по символу от имени текущего инстанса (см. close).
+/// Результат ордера приходит в callback, как у place_order
+pub unsafe extern "C" fn close_position(
+    api_key: *const c_char,
+    secret_key: *const c_char,
+    symbol: *const c_char,
+    callback: OrderCallback,
+) -> f64 {
+    if api_key.is_null() || secret_key.is_null() || symbol.is_null() {
+        return 0.0;
+    }
+    let (Ok(api_key), Ok(secret_key), Ok(symbol)) = (
+        CStr::from_ptr(api_key).to_str(),
+        CStr::from_ptr(secret_key).to_str(),
+        CStr::from_ptr(symbol).to_str(),
+    ) else {
+        return 0.0;
+    };
+    let instance_id = current_instance();
+    let callback_instance = instance_id.clone();
+    let creds = Arc::new(keystore::resolve(api_key, secret_key));
+    close(creds, instance_id, symbol, move |result| {
+        invoke_callback(&callback_instance, callback, result);
+    })
+}
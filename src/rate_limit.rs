@@ -0,0 +1,502 @@
+// src/rate_limit.rs
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::alerts::{self, AlertLevel};
+use crate::user_data::key_id;
+
+// ═══════════════════════════════════════════════════════════
+// КОДЫ ОШИБОК (локальные отказы, не Binance)
+// ═══════════════════════════════════════════════════════════
+
+pub const ERR_RATE_LIMITED: i32 = -9200;
+pub const ERR_IP_BANNED: i32 = -9201;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitMode {
+    /// Ждать освобождения бюджета (не дольше max_queue_ms)
+    #[default]
+    Queue,
+    /// Сразу отклонять запрос
+    Shed,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    /// ORDERS / 10 SECOND на аккаунт
+    pub orders_per_10s: u32,
+    /// ORDERS / 1 MINUTE на аккаунт
+    pub orders_per_minute: u32,
+    /// REQUEST_WEIGHT / 1 MINUTE на IP
+    pub weight_per_minute: u32,
+    /// Доля лимита Binance, которую разрешено использовать (0..1]
+    pub headroom: f64,
+    pub mode: LimitMode,
+    /// Максимальное ожидание в режиме Queue
+    pub max_queue_ms: u64,
+    /// Пауза после 429 / -1003 / -1015, если биржа не прислала retryAfter
+    pub cooloff_ms: u64,
+    /// Пауза после 418 (бан IP), если биржа не прислала retryAfter
+    pub ban_cooloff_ms: u64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            orders_per_10s: 300,
+            orders_per_minute: 1200,
+            weight_per_minute: 2400,
+            headroom: 0.8,
+            mode: LimitMode::Queue,
+            max_queue_ms: 1000,
+            cooloff_ms: 10_000,
+            ban_cooloff_ms: 120_000,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ТИПЫ
+// ═══════════════════════════════════════════════════════════
+
+/// Стоимость запроса в единицах лимитов Binance
+#[derive(Debug, Clone, Copy)]
+pub struct Cost {
+    pub weight: u32,
+    pub orders: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimitReject {
+    pub code: i32,
+    pub message: String,
+}
+
+/// Элемент массива `rateLimits` из ответа WS API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerRateLimit {
+    pub rate_limit_type: String,
+    pub interval: String,
+    pub interval_num: u64,
+    pub limit: u64,
+    #[serde(default)]
+    pub count: u64,
+}
+
+impl ServerRateLimit {
+    fn window_secs(&self) -> u64 {
+        let unit = match self.interval.as_str() {
+            "SECOND" => 1,
+            "MINUTE" => 60,
+            "HOUR" => 3600,
+            "DAY" => 86400,
+            _ => 0,
+        };
+        unit * self.interval_num
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BucketStatus {
+    pub limit: f64,
+    pub available: f64,
+    pub window_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyRateStatus {
+    pub key: String,
+    pub orders_10s: BucketStatus,
+    pub orders_1m: BucketStatus,
+    pub cooldown_ms: Option<u64>,
+    pub rejected: u64,
+    pub server: Vec<ServerRateLimit>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RateLimitStatus {
+    pub mode: LimitMode,
+    pub headroom: f64,
+    pub weight: BucketStatus,
+    pub ip_cooldown_ms: Option<u64>,
+    pub ip_banned: bool,
+    pub cooloff_reason: Option<String>,
+    pub server: Vec<ServerRateLimit>,
+    pub keys: Vec<KeyRateStatus>,
+}
+
+// ═══════════════════════════════════════════════════════════
+// TOKEN BUCKET
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    window_secs: u64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: f64, window_secs: u64) -> Self {
+        Self { capacity, tokens: capacity, window_secs, last: Instant::now() }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        let rate = self.capacity / self.window_secs as f64;
+        self.tokens = (self.tokens + elapsed * rate).min(self.capacity);
+        self.last = now;
+    }
+
+    /// Сколько ждать до появления n токенов (ZERO - можно сейчас)
+    fn wait_for(&self, n: f64) -> Duration {
+        if self.tokens >= n {
+            return Duration::ZERO;
+        }
+        let rate = self.capacity / self.window_secs as f64;
+        Duration::from_secs_f64((n - self.tokens) / rate)
+    }
+
+    /// Синхронизация с фактическим счётчиком биржи.
+    /// Бюджет только уменьшаем: локально учтены запросы, которых биржа ещё не видела.
+    fn sync(&mut self, limit: f64, used: f64) {
+        self.capacity = limit;
+        self.tokens = self.tokens.min(limit - used).max(0.0);
+    }
+
+    fn status(&self) -> BucketStatus {
+        BucketStatus {
+            limit: self.capacity,
+            available: self.tokens.max(0.0),
+            window_secs: self.window_secs,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct KeyBuckets {
+    orders_10s: TokenBucket,
+    orders_1m: TokenBucket,
+    cooldown_until: Option<Instant>,
+    server: Vec<ServerRateLimit>,
+}
+
+#[derive(Debug)]
+struct IpState {
+    weight: TokenBucket,
+    cooldown_until: Option<Instant>,
+    banned: bool,
+    reason: Option<String>,
+    server: Vec<ServerRateLimit>,
+}
+
+// ═══════════════════════════════════════════════════════════
+// RATE LIMITER
+// ═══════════════════════════════════════════════════════════
+
+/// Ограничитель запросов к WS API.
+///
+/// Локальный token bucket на каждый api_key (ORDERS) и общий на IP
+/// (REQUEST_WEIGHT). После каждого ответа бюджет подтягивается к счётчикам
+/// из `rateLimits`, а 429/418 включают cooloff до `retryAfter`: пока он
+/// активен, запросы сразу отклоняются локально и не уходят на биржу.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    ip: Mutex<IpState>,
+    keys: DashMap<String, KeyBuckets>,
+    rejected: DashMap<String, AtomicU64>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let weight = config.weight_per_minute as f64 * config.headroom;
+        Self {
+            ip: Mutex::new(IpState {
+                weight: TokenBucket::new(weight, 60),
+                cooldown_until: None,
+                banned: false,
+                reason: None,
+                server: Vec::new(),
+            }),
+            keys: DashMap::new(),
+            rejected: DashMap::new(),
+            config,
+        }
+    }
+
+    fn new_key_buckets(&self) -> KeyBuckets {
+        KeyBuckets {
+            orders_10s: TokenBucket::new(self.config.orders_per_10s as f64 * self.config.headroom, 10),
+            orders_1m: TokenBucket::new(self.config.orders_per_minute as f64 * self.config.headroom, 60),
+            cooldown_until: None,
+            server: Vec::new(),
+        }
+    }
+
+    /// Резервирует бюджет под запрос. В режиме Queue ждёт не дольше max_queue_ms.
+    pub async fn acquire(&self, api_key: &str, cost: Cost) -> Result<(), RateLimitReject> {
+        let deadline = Instant::now() + Duration::from_millis(self.config.max_queue_ms);
+
+        loop {
+            let wait = self.try_acquire(api_key, cost).inspect_err(|_| {
+                self.count_reject(api_key);
+            })?;
+
+            if wait.is_zero() {
+                return Ok(());
+            }
+
+            if self.config.mode == LimitMode::Shed || Instant::now() + wait > deadline {
+                self.count_reject(api_key);
+                return Err(RateLimitReject {
+                    code: ERR_RATE_LIMITED,
+                    message: format!("Rate limit budget exhausted, retry in {}ms", wait.as_millis()),
+                });
+            }
+
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Ok(ZERO) - токены списаны, Ok(wait) - нужно подождать, Err - cooloff
+    fn try_acquire(&self, api_key: &str, cost: Cost) -> Result<Duration, RateLimitReject> {
+        let now = Instant::now();
+
+        let mut ip = self.ip.lock().unwrap();
+        if let Some(until) = ip.cooldown_until {
+            if until > now {
+                let reason = ip.reason.as_deref().unwrap_or("rate limit");
+                return Err(RateLimitReject {
+                    code: if ip.banned { ERR_IP_BANNED } else { ERR_RATE_LIMITED },
+                    message: format!("Cooling off after Binance {}, {}ms left", reason, (until - now).as_millis()),
+                });
+            }
+            ip.cooldown_until = None;
+            ip.banned = false;
+            ip.reason = None;
+        }
+
+        let mut key = self.keys
+            .entry(api_key.to_string())
+            .or_insert_with(|| self.new_key_buckets());
+
+        if let Some(until) = key.cooldown_until {
+            if until > now {
+                return Err(RateLimitReject {
+                    code: ERR_RATE_LIMITED,
+                    message: format!("Cooling off after order rate limit (-1015), {}ms left", (until - now).as_millis()),
+                });
+            }
+            key.cooldown_until = None;
+        }
+
+        ip.weight.refill(now);
+        key.orders_10s.refill(now);
+        key.orders_1m.refill(now);
+
+        let weight = cost.weight as f64;
+        let orders = cost.orders as f64;
+
+        let wait = ip.weight.wait_for(weight)
+            .max(key.orders_10s.wait_for(orders))
+            .max(key.orders_1m.wait_for(orders));
+
+        if wait.is_zero() {
+            ip.weight.tokens -= weight;
+            key.orders_10s.tokens -= orders;
+            key.orders_1m.tokens -= orders;
+        }
+
+        Ok(wait)
+    }
+
+    fn count_reject(&self, api_key: &str) {
+        self.rejected
+            .entry(api_key.to_string())
+            .or_insert_with(|| AtomicU64::new(0))
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Обработка ответа WS API: синхронизация счётчиков и реакция на 429/418
+    pub fn on_response(&self, api_key: &str, v: &Value) {
+        let now = Instant::now();
+
+        if let Some(limits) = v.get("rateLimits") {
+            let limits: Vec<ServerRateLimit> = serde_json::from_value(limits.clone()).unwrap_or_default();
+            self.sync(api_key, limits);
+        }
+
+        let status = v["status"].as_u64().unwrap_or(200);
+        let code = v["error"]["code"].as_i64().unwrap_or(0);
+
+        // retryAfter - момент окончания блокировки (unix ms)
+        let retry_after = v["error"]["data"]["retryAfter"].as_i64().map(|ts| {
+            let left = ts - chrono::Utc::now().timestamp_millis();
+            Duration::from_millis(left.max(0) as u64)
+        });
+
+        match (status, code) {
+            (418, _) => {
+                self.enter_cooloff(true, retry_after, "IP ban (418)");
+            }
+            (429, _) | (_, -1003) => {
+                self.enter_cooloff(false, retry_after, "request weight limit (-1003)");
+            }
+            (_, -1015) => {
+                let pause = retry_after.unwrap_or(Duration::from_millis(self.config.cooloff_ms));
+                if let Some(mut key) = self.keys.get_mut(api_key) {
+                    key.cooldown_until = Some(now + pause);
+                    key.orders_10s.tokens = 0.0;
+                }
+                alerts::emit(
+                    AlertLevel::Warning,
+                    "trade",
+                    format!(
+                        "Order rate limit (-1015) for key {}, cooling off {}ms",
+                        key_id(api_key),
+                        pause.as_millis()
+                    ),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    /// Включает cooloff для всего IP. `pause = None` - длительность из конфига.
+    /// Алерт шлём только при входе в cooloff или его продлении.
+    pub fn enter_cooloff(&self, banned: bool, pause: Option<Duration>, reason: &str) {
+        let default_ms = if banned { self.config.ban_cooloff_ms } else { self.config.cooloff_ms };
+        let pause = pause.unwrap_or(Duration::from_millis(default_ms));
+        let until = Instant::now() + pause;
+
+        let mut ip = self.ip.lock().unwrap();
+        if ip.cooldown_until.is_some_and(|u| u >= until) {
+            return;
+        }
+        ip.cooldown_until = Some(until);
+        ip.banned |= banned;
+        ip.reason = Some(reason.to_string());
+        ip.weight.tokens = 0.0;
+        drop(ip);
+
+        alerts::emit(
+            if banned { AlertLevel::Critical } else { AlertLevel::Warning },
+            "trade",
+            format!("Binance {}, cooling off {}ms", reason, pause.as_millis()),
+        );
+    }
+
+    /// Сколько осталось до конца cooloff по IP
+    pub fn cooloff_remaining(&self) -> Option<Duration> {
+        let now = Instant::now();
+        self.ip.lock().unwrap()
+            .cooldown_until
+            .filter(|u| *u > now)
+            .map(|u| u - now)
+    }
+
+    fn sync(&self, api_key: &str, limits: Vec<ServerRateLimit>) {
+        let headroom = self.config.headroom;
+        let mut weight_limits = Vec::new();
+        let mut order_limits = Vec::new();
+
+        for l in limits {
+            let limit = l.limit as f64 * headroom;
+            let used = l.count as f64;
+            match (l.rate_limit_type.as_str(), l.window_secs()) {
+                ("REQUEST_WEIGHT", 60) => {
+                    let mut ip = self.ip.lock().unwrap();
+                    ip.weight.refill(Instant::now());
+                    ip.weight.sync(limit, used);
+                    weight_limits.push(l);
+                }
+                ("ORDERS", window) => {
+                    let mut key = self.keys
+                        .entry(api_key.to_string())
+                        .or_insert_with(|| self.new_key_buckets());
+                    let now = Instant::now();
+                    match window {
+                        10 => {
+                            key.orders_10s.refill(now);
+                            key.orders_10s.sync(limit, used);
+                        }
+                        60 => {
+                            key.orders_1m.refill(now);
+                            key.orders_1m.sync(limit, used);
+                        }
+                        _ => {}
+                    }
+                    order_limits.push(l);
+                }
+                _ => weight_limits.push(l),
+            }
+        }
+
+        if !weight_limits.is_empty() {
+            self.ip.lock().unwrap().server = weight_limits;
+        }
+        if !order_limits.is_empty() {
+            if let Some(mut key) = self.keys.get_mut(api_key) {
+                key.server = order_limits;
+            }
+        }
+    }
+
+    pub fn status(&self) -> RateLimitStatus {
+        let now = Instant::now();
+        let remaining = |until: Option<Instant>| {
+            until
+                .filter(|u| *u > now)
+                .map(|u| (u - now).as_millis() as u64)
+        };
+
+        let mut ip = self.ip.lock().unwrap();
+        ip.weight.refill(now);
+
+        let keys = self.keys
+            .iter_mut()
+            .map(|mut entry| {
+                let api_key = entry.key().clone();
+                let b = entry.value_mut();
+                b.orders_10s.refill(now);
+                b.orders_1m.refill(now);
+                KeyRateStatus {
+                    key: key_id(&api_key),
+                    orders_10s: b.orders_10s.status(),
+                    orders_1m: b.orders_1m.status(),
+                    cooldown_ms: remaining(b.cooldown_until),
+                    rejected: self.rejected
+                        .get(&api_key)
+                        .map(|r| r.load(Ordering::Relaxed))
+                        .unwrap_or(0),
+                    server: b.server.clone(),
+                }
+            })
+            .collect();
+
+        let ip_cooldown_ms = remaining(ip.cooldown_until);
+        RateLimitStatus {
+            mode: self.config.mode,
+            headroom: self.config.headroom,
+            weight: ip.weight.status(),
+            ip_banned: ip.banned && ip_cooldown_ms.is_some(),
+            cooloff_reason: ip.reason.clone().filter(|_| ip_cooldown_ms.is_some()),
+            ip_cooldown_ms,
+            server: ip.server.clone(),
+            keys,
+        }
+    }
+}
@@ -0,0 +1,75 @@
+// src/raw_capture.rs
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+// ═══════════════════════════════════════════════════════════
+// СООБЩЕНИЯ, КОТОРЫЕ НЕ РАЗОБРАЛИСЬ
+// ═══════════════════════════════════════════════════════════
+//
+// Новый вариант сообщения Binance ломает разбор тихо: событие просто
+// пропадает. Сырой текст последних таких сообщений держим в кольцевом
+// буфере (GET /api/debug/rawmessages), чтобы разобраться без отладчика.
+
+const MAX_MESSAGES: usize = 100;
+
+/// Длиннее обрезаем: буфер не должен съедать память на мусорных кадрах
+const MAX_PAYLOAD: usize = 8192;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RawMessage {
+    /// "market" | "user_data"
+    pub source: &'static str,
+    pub error: String,
+    pub payload: String,
+    /// false - simd-json разбирает на месте и мог изменить экранированные
+    /// строки; точная копия есть, только пока пишет recorder
+    pub exact: bool,
+    pub truncated: bool,
+    /// Unix ms
+    pub time: i64,
+}
+
+static MESSAGES: OnceLock<Mutex<VecDeque<RawMessage>>> = OnceLock::new();
+
+fn buffer() -> &'static Mutex<VecDeque<RawMessage>> {
+    MESSAGES.get_or_init(|| Mutex::new(VecDeque::with_capacity(MAX_MESSAGES)))
+}
+
+pub fn capture(source: &'static str, error: impl ToString, payload: &str, exact: bool) {
+    let truncated = payload.len() > MAX_PAYLOAD;
+    let payload = if truncated {
+        let mut end = MAX_PAYLOAD;
+        while !payload.is_char_boundary(end) {
+            end -= 1;
+        }
+        &payload[..end]
+    } else {
+        payload
+    };
+
+    let message = RawMessage {
+        source,
+        error: error.to_string(),
+        payload: payload.to_string(),
+        exact,
+        truncated,
+        time: chrono::Utc::now().timestamp_millis(),
+    };
+
+    let mut buf = buffer().lock().unwrap();
+    if buf.len() >= MAX_MESSAGES {
+        buf.pop_front();
+    }
+    buf.push_back(message);
+}
+
+/// Последние сообщения, новые первыми
+pub fn recent() -> Vec<RawMessage> {
+    buffer().lock().unwrap().iter().rev().cloned().collect()
+}
+
+pub fn clear() {
+    buffer().lock().unwrap().clear();
+}
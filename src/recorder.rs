@@ -0,0 +1,488 @@
+// src/recorder.rs
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, LazyLock, OnceLock};
+
+use crate::ffi_types::{pack_str, CBookTicker, CEvent, CEventData, CTrade, EVENT_BOOK_TICKER, EVENT_TRADE};
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RecorderConfig {
+    /// Файлы: {dir}/{SYMBOL}/{YYYY-MM-DD}.bin (день по UTC)
+    pub dir: String,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            dir: "./data/recordings".to_string(),
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ФОРМАТ
+// ═══════════════════════════════════════════════════════════
+
+// Файл: MAGIC, затем записи подряд (все числа little-endian):
+//   kind u8 | received_at_ns u64 | ...
+//   kind = 0 (CEvent): seq u64 | event_type u8 | symbol_len u8 | symbol
+//     bookTicker: bid_price f64 | ask_price f64 | bid_qty f64 | ask_qty f64 | time i64
+//     trade:      price f64 | qty f64 | time i64
+//   kind = 1 (сырой JSON из WS): len u32 | bytes
+
+const MAGIC: &[u8; 8] = b"HFTREC01";
+const KIND_EVENT: u8 = 0;
+const KIND_RAW: u8 = 1;
+
+/// Запись файла. Сырой JSON при чтении пропускается - нужен только для разбора вручную
+pub enum Record {
+    Event(CEvent),
+    Raw { received_at_ns: u64 },
+}
+
+impl Record {
+    pub fn received_at_ns(&self) -> u64 {
+        match self {
+            Record::Event(e) => e.received_at_ns,
+            Record::Raw { received_at_ns, .. } => *received_at_ns,
+        }
+    }
+}
+
+fn encode_event(event: &CEvent, out: &mut Vec<u8>) {
+    out.push(KIND_EVENT);
+    out.extend_from_slice(&event.received_at_ns.to_le_bytes());
+    out.extend_from_slice(&event.seq.to_le_bytes());
+    out.push(event.event_type);
+
+    let symbol = event.symbol_str().as_bytes();
+    out.push(symbol.len() as u8);
+    out.extend_from_slice(symbol);
+
+    let (floats, time): (&[f64], i64) = unsafe {
+        match event.event_type {
+            EVENT_BOOK_TICKER => {
+                let b = &event.data.book_ticker;
+                (&[b.bid_price, b.ask_price, b.bid_qty, b.ask_qty], b.time)
+            }
+            _ => {
+                let t = &event.data.trade;
+                (&[t.price, t.qty], t.time)
+            }
+        }
+    };
+    for f in floats {
+        out.extend_from_slice(&f.to_le_bytes());
+    }
+    out.extend_from_slice(&time.to_le_bytes());
+}
+
+fn encode_raw(received_at_ns: u64, text: &str, out: &mut Vec<u8>) {
+    out.push(KIND_RAW);
+    out.extend_from_slice(&received_at_ns.to_le_bytes());
+    out.extend_from_slice(&(text.len() as u32).to_le_bytes());
+    out.extend_from_slice(text.as_bytes());
+}
+
+/// Запись файла целиком из одного потока (конвертеры архивов, не live).
+/// Существующий файл перезаписывается
+pub struct RecordWriter {
+    inner: BufWriter<File>,
+    buf: Vec<u8>,
+    events: u64,
+}
+
+impl RecordWriter {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut inner = BufWriter::with_capacity(1 << 16, File::create(path)?);
+        inner.write_all(MAGIC)?;
+        Ok(Self { inner, buf: Vec::with_capacity(64), events: 0 })
+    }
+
+    pub fn write(&mut self, event: &CEvent) -> io::Result<()> {
+        self.buf.clear();
+        encode_event(event, &mut self.buf);
+        self.events += 1;
+        self.inner.write_all(&self.buf)
+    }
+
+    /// Сбрасывает буфер, возвращает число записанных событий
+    pub fn finish(mut self) -> io::Result<u64> {
+        self.inner.flush()?;
+        Ok(self.events)
+    }
+}
+
+/// Последовательное чтение записанного файла
+pub struct RecordReader<R: Read> {
+    inner: R,
+}
+
+impl RecordReader<BufReader<File>> {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let mut inner = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 8];
+        inner.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            anyhow::bail!("'{}' is not a recording", path.display());
+        }
+        Ok(Self { inner })
+    }
+}
+
+impl<R: Read> RecordReader<R> {
+    fn u8(&mut self) -> io::Result<u8> {
+        let mut b = [0u8; 1];
+        self.inner.read_exact(&mut b)?;
+        Ok(b[0])
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        let mut b = [0u8; 8];
+        self.inner.read_exact(&mut b)?;
+        Ok(u64::from_le_bytes(b))
+    }
+
+    fn f64(&mut self) -> io::Result<f64> {
+        self.u64().map(f64::from_bits)
+    }
+
+    fn read_record(&mut self, kind: u8) -> io::Result<Record> {
+        let received_at_ns = self.u64()?;
+        match kind {
+            KIND_EVENT => {
+                let seq = self.u64()?;
+                let event_type = self.u8()?;
+                let len = self.u8()? as usize;
+                let mut buf = vec![0u8; len];
+                self.inner.read_exact(&mut buf)?;
+                let (symbol, symbol_len) = pack_str::<16>(&String::from_utf8_lossy(&buf));
+
+                let data = match event_type {
+                    EVENT_BOOK_TICKER => CEventData {
+                        book_ticker: CBookTicker {
+                            symbol,
+                            symbol_len,
+                            bid_price: self.f64()?,
+                            ask_price: self.f64()?,
+                            bid_qty: self.f64()?,
+                            ask_qty: self.f64()?,
+                            time: self.u64()? as i64,
+                        },
+                    },
+                    EVENT_TRADE => CEventData {
+                        trade: CTrade {
+                            symbol,
+                            symbol_len,
+                            price: self.f64()?,
+                            qty: self.f64()?,
+                            time: self.u64()? as i64,
+                        },
+                    },
+                    other => {
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown event type {}", other)))
+                    }
+                };
+                Ok(Record::Event(CEvent { event_type, data, received_at_ns, seq }))
+            }
+            KIND_RAW => {
+                let mut len = [0u8; 4];
+                self.inner.read_exact(&mut len)?;
+                let len = u32::from_le_bytes(len) as u64;
+                if io::copy(&mut (&mut self.inner).take(len), &mut io::sink())? < len {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                Ok(Record::Raw { received_at_ns })
+            }
+            other => Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown record kind {}", other))),
+        }
+    }
+}
+
+impl<R: Read> Iterator for RecordReader<R> {
+    type Item = io::Result<Record>;
+
+    /// None - конец файла (в том числе недописанная последняя запись)
+    fn next(&mut self) -> Option<Self::Item> {
+        let kind = match self.u8() {
+            Ok(k) => k,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return None,
+            Err(e) => return Some(Err(e)),
+        };
+        match self.read_record(kind) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => None,
+            result => Some(result),
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// СЕССИИ
+// ═══════════════════════════════════════════════════════════
+
+struct Session {
+    raw: bool,
+    started_at: i64,
+    events: AtomicU64,
+    raw_messages: AtomicU64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub symbol: String,
+    /// Пишется ли сырой JSON
+    pub raw: bool,
+    pub started_at: i64,
+    pub events: u64,
+    pub raw_messages: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordingFile {
+    pub symbol: String,
+    /// Путь относительно dir (так его принимает replay)
+    pub file: String,
+    pub size: u64,
+}
+
+enum Msg {
+    Write { symbol: String, received_at_ns: u64, bytes: Vec<u8> },
+    Close(String),
+}
+
+static DIR: OnceLock<PathBuf> = OnceLock::new();
+static WRITER: OnceLock<mpsc::Sender<Msg>> = OnceLock::new();
+
+/// SYMBOL -> активная запись
+static SESSIONS: LazyLock<DashMap<String, Arc<Session>>> = LazyLock::new(DashMap::new);
+
+/// Сколько сессий пишут сырой JSON: reader копирует текст до парсинга только если > 0
+static RAW_SESSIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Запускает поток записи. Без вызова init start() возвращает ошибку
+pub fn init(config: &RecorderConfig) -> anyhow::Result<()> {
+    let dir = PathBuf::from(&config.dir);
+    fs::create_dir_all(&dir)?;
+
+    let (tx, rx) = mpsc::channel::<Msg>();
+    let writer_dir = dir.clone();
+    std::thread::Builder::new()
+        .name("recorder".into())
+        .spawn(move || {
+            let mut files = Files { dir: writer_dir, open: HashMap::new() };
+            while let Ok(msg) = rx.recv() {
+                files.handle(msg);
+                for msg in rx.try_iter() {
+                    files.handle(msg);
+                }
+                files.flush();
+            }
+        })?;
+
+    DIR.set(dir).ok();
+    WRITER.set(tx).ok();
+    Ok(())
+}
+
+pub fn dir() -> Option<&'static Path> {
+    DIR.get().map(PathBuf::as_path)
+}
+
+/// Путь к записи по имени из files() ("BTCUSDT/2024-01-01.bin").
+/// Только внутри каталога записей
+pub fn resolve(file: &str) -> anyhow::Result<PathBuf> {
+    let dir = dir().ok_or_else(|| anyhow::anyhow!("Recorder is not initialized"))?;
+    let rel = Path::new(file);
+    if !rel.components().all(|c| matches!(c, std::path::Component::Normal(_))) {
+        anyhow::bail!("Invalid recording name '{}'", file);
+    }
+    let path = dir.join(rel);
+    if !path.is_file() {
+        anyhow::bail!("Recording '{}' not found", file);
+    }
+    Ok(path)
+}
+
+fn info(symbol: &str, s: &Session) -> SessionInfo {
+    SessionInfo {
+        symbol: symbol.to_string(),
+        raw: s.raw,
+        started_at: s.started_at,
+        events: s.events.load(Ordering::Relaxed),
+        raw_messages: s.raw_messages.load(Ordering::Relaxed),
+    }
+}
+
+pub fn start(symbol: &str, raw: bool) -> anyhow::Result<SessionInfo> {
+    if WRITER.get().is_none() {
+        anyhow::bail!("Recorder is not initialized");
+    }
+    let symbol = symbol.to_uppercase();
+    if SESSIONS.contains_key(&symbol) {
+        anyhow::bail!("'{}' is already being recorded", symbol);
+    }
+
+    let session = Arc::new(Session {
+        raw,
+        started_at: chrono::Utc::now().timestamp_millis(),
+        events: AtomicU64::new(0),
+        raw_messages: AtomicU64::new(0),
+    });
+    if raw {
+        RAW_SESSIONS.fetch_add(1, Ordering::Relaxed);
+    }
+    SESSIONS.insert(symbol.clone(), session.clone());
+
+    tracing::info!("🎙️ Recording {} (raw={})", symbol, raw);
+    Ok(info(&symbol, &session))
+}
+
+pub fn stop(symbol: &str) -> Option<SessionInfo> {
+    let symbol = symbol.to_uppercase();
+    let (_, session) = SESSIONS.remove(&symbol)?;
+    if session.raw {
+        RAW_SESSIONS.fetch_sub(1, Ordering::Relaxed);
+    }
+    if let Some(tx) = WRITER.get() {
+        let _ = tx.send(Msg::Close(symbol.clone()));
+    }
+
+    tracing::info!("🎙️ Recording {} stopped", symbol);
+    Some(info(&symbol, &session))
+}
+
+pub fn list() -> Vec<SessionInfo> {
+    SESSIONS.iter().map(|e| info(e.key(), e.value())).collect()
+}
+
+/// Записанные файлы по всем символам
+pub fn files() -> Vec<RecordingFile> {
+    let Some(dir) = dir() else { return Vec::new() };
+    let mut out = Vec::new();
+    for symbol_dir in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let symbol = symbol_dir.file_name().to_string_lossy().to_string();
+        for file in fs::read_dir(symbol_dir.path()).into_iter().flatten().flatten() {
+            let name = file.file_name().to_string_lossy().to_string();
+            if !name.ends_with(".bin") {
+                continue;
+            }
+            out.push(RecordingFile {
+                file: format!("{}/{}", symbol, name),
+                symbol: symbol.clone(),
+                size: file.metadata().map(|m| m.len()).unwrap_or(0),
+            });
+        }
+    }
+    out.sort_by(|a, b| a.file.cmp(&b.file));
+    out
+}
+
+/// Нужен ли reader'у текст сообщения до парсинга
+pub fn wants_raw() -> bool {
+    RAW_SESSIONS.load(Ordering::Relaxed) > 0
+}
+
+/// Горячий путь: событие (и, если включено, исходный JSON) от market data reader
+pub fn record(event: &CEvent, raw: Option<&str>) {
+    if SESSIONS.is_empty() || !matches!(event.event_type, EVENT_BOOK_TICKER | EVENT_TRADE) {
+        return;
+    }
+    let Some(session) = SESSIONS.get(event.symbol_str()).map(|s| s.clone()) else { return };
+    let Some(tx) = WRITER.get() else { return };
+
+    let mut bytes = Vec::with_capacity(64);
+    encode_event(event, &mut bytes);
+    session.events.fetch_add(1, Ordering::Relaxed);
+
+    if let Some(text) = raw.filter(|_| session.raw) {
+        encode_raw(event.received_at_ns, text, &mut bytes);
+        session.raw_messages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let _ = tx.send(Msg::Write {
+        symbol: event.symbol_str().to_string(),
+        received_at_ns: event.received_at_ns,
+        bytes,
+    });
+}
+
+// ═══════════════════════════════════════════════════════════
+// ЗАПИСЬ
+// ═══════════════════════════════════════════════════════════
+
+struct Files {
+    dir: PathBuf,
+    /// SYMBOL -> (день, файл)
+    open: HashMap<String, (String, BufWriter<File>)>,
+}
+
+impl Files {
+    fn handle(&mut self, msg: Msg) {
+        match msg {
+            Msg::Write { symbol, received_at_ns, bytes } => {
+                let day = chrono::DateTime::from_timestamp_nanos(received_at_ns as i64)
+                    .format("%Y-%m-%d")
+                    .to_string();
+
+                // Новый день - новый файл
+                if self.open.get(&symbol).is_none_or(|(d, _)| *d != day) {
+                    match self.open_file(&symbol, &day) {
+                        Ok(file) => {
+                            if let Some((_, mut old)) = self.open.insert(symbol.clone(), (day, file)) {
+                                let _ = old.flush();
+                            }
+                        }
+                        Err(e) => {
+                            tracing::error!("❌ Recorder failed to open file for {}: {}", symbol, e);
+                            return;
+                        }
+                    }
+                }
+
+                if let Some((_, file)) = self.open.get_mut(&symbol) {
+                    if let Err(e) = file.write_all(&bytes) {
+                        tracing::error!("❌ Recorder write failed for {}: {}", symbol, e);
+                    }
+                }
+            }
+            Msg::Close(symbol) => {
+                if let Some((_, mut file)) = self.open.remove(&symbol) {
+                    let _ = file.flush();
+                }
+            }
+        }
+    }
+
+    fn open_file(&self, symbol: &str, day: &str) -> io::Result<BufWriter<File>> {
+        let dir = self.dir.join(symbol);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.bin", day));
+
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let mut writer = BufWriter::with_capacity(1 << 16, file);
+        if fs::metadata(&path)?.len() == 0 {
+            writer.write_all(MAGIC)?;
+        }
+        Ok(writer)
+    }
+
+    fn flush(&mut self) {
+        for (_, file) in self.open.values_mut() {
+            let _ = file.flush();
+        }
+    }
+}
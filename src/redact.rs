@@ -0,0 +1,143 @@
+// src/redact.rs
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// Имена секретных полей в нормализованном виде (lowercase, без '_' и '-'):
+/// api_key, apiKey, secret_key, secretKey, ...
+const SECRET_FIELDS: &[&str] = &[
+    "apikey",
+    "secretkey",
+    "secret",
+    "apisecret",
+    "privatekey",
+    "password",
+    "passphrase",
+];
+
+fn is_secret_field(name: &str) -> bool {
+    let normalized: String = name
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    SECRET_FIELDS.contains(&normalized.as_str())
+}
+
+/// "***" + первые 8 hex sha256: по маске можно сверить ключ,
+/// но нельзя его восстановить
+pub fn mask(secret: &str) -> String {
+    let hash = hex::encode(Sha256::digest(secret.as_bytes()));
+    format!("***{}", &hash[..8])
+}
+
+/// Копия JSON, в которой значения секретных полей (на любой глубине) замаскированы
+pub fn value(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| {
+                    let v = match v {
+                        Value::String(s) if is_secret_field(k) => Value::String(mask(s)),
+                        Value::Null => Value::Null,
+                        _ if is_secret_field(k) => Value::String("***".into()),
+                        _ => self::value(v),
+                    };
+                    (k.clone(), v)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(self::value).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Строковые значения секретных полей (на любой глубине)
+fn collect_secrets<'a>(value: &'a Value, out: &mut Vec<&'a str>) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                match v {
+                    Value::String(s) if is_secret_field(k) && !s.is_empty() => out.push(s),
+                    _ => collect_secrets(v, out),
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_secrets(v, out)),
+        _ => {}
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ПАРАМЕТРЫ ЗАПУСКА
+// ═══════════════════════════════════════════════════════════
+//
+// Параметры хранятся только в этом виде: Serialize, Debug и Display отдают
+// копию с масками секретов, поэтому ни ответ API, ни лог, ни файл на диске не
+// получают секреты случайно. Исходные значения - через expose(), только
+// для передачи стратегии.
+
+#[derive(Clone, Default, PartialEq)]
+pub struct Params(Value);
+
+impl Params {
+    /// Исходные параметры (с секретами)
+    pub fn expose(&self) -> &Value {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> Value {
+        self.0
+    }
+
+    pub fn redacted(&self) -> Value {
+        value(&self.0)
+    }
+
+    pub fn has_secrets(&self) -> bool {
+        let mut secrets = Vec::new();
+        collect_secrets(&self.0, &mut secrets);
+        !secrets.is_empty()
+    }
+
+    /// Текст (ошибка, строка лога), в котором значения секретов заменены масками
+    pub fn scrub(&self, text: &str) -> String {
+        let mut secrets = Vec::new();
+        collect_secrets(&self.0, &mut secrets);
+        // Длинные первыми: секрет может содержать другой секрет
+        secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+        secrets.iter().fold(text.to_string(), |text, s| text.replace(s, &mask(s)))
+    }
+}
+
+impl From<Value> for Params {
+    fn from(raw: Value) -> Self {
+        Self(raw)
+    }
+}
+
+impl Serialize for Params {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.redacted().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Params {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Value::deserialize(deserializer).map(Self)
+    }
+}
+
+impl fmt::Debug for Params {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.redacted())
+    }
+}
+
+impl fmt::Display for Params {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.redacted())
+    }
+}
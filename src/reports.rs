@@ -0,0 +1,94 @@
+// src/reports.rs
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::history::History;
+
+// ═══════════════════════════════════════════════════════════
+// ДНЕВНОЙ ОТЧЁТ
+// ═══════════════════════════════════════════════════════════
+
+/// Строка дневного отчёта: UTC-день × символ × инстанс (все суммы в USDT)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DailyReportRow {
+    pub date: String,
+    pub symbol: String,
+    /// None - ордера не через ядро и funding (он приходит на аккаунт)
+    pub instance_id: Option<String>,
+    pub fills: u64,
+    pub gross_pnl: f64,
+    pub fees: f64,
+    pub funding_received: f64,
+    pub funding_paid: f64,
+    pub net_pnl: f64,
+    pub wins: u64,
+    pub losses: u64,
+    /// Доля прибыльных закрывающих fills, None если закрытий не было
+    pub win_rate: Option<f64>,
+}
+
+const CSV_HEADER: &str = "date,symbol,instance_id,fills,gross_pnl,fees,funding_received,funding_paid,net_pnl,wins,losses,win_rate";
+
+/// Собирает дневной отчёт из сохранённых fills и funding
+pub fn daily(history: &History, from: i64, to: i64) -> anyhow::Result<Vec<DailyReportRow>> {
+    let mut rows: BTreeMap<(String, String, Option<String>), DailyReportRow> = BTreeMap::new();
+
+    for f in history.daily_fills(from, to)? {
+        let key = (f.day.clone(), f.symbol.clone(), f.instance_id.clone());
+        let row = rows.entry(key).or_default();
+        row.date = f.day;
+        row.symbol = f.symbol;
+        row.instance_id = f.instance_id;
+        row.fills = f.fills;
+        row.gross_pnl = f.realized_pnl;
+        row.fees = f.fees;
+        row.wins = f.wins;
+        row.losses = f.losses;
+    }
+
+    for f in history.daily_funding(from, to)? {
+        let symbol = f.symbol.unwrap_or_default();
+        let row = rows.entry((f.day.clone(), symbol.clone(), None)).or_default();
+        row.date = f.day;
+        row.symbol = symbol;
+        row.funding_received += f.received;
+        row.funding_paid += f.paid;
+    }
+
+    Ok(rows
+        .into_values()
+        .map(|mut r| {
+            r.net_pnl = r.gross_pnl - r.fees + r.funding_received + r.funding_paid;
+            let closes = r.wins + r.losses;
+            r.win_rate = (closes > 0).then(|| r.wins as f64 / closes as f64);
+            r
+        })
+        .collect())
+}
+
+pub fn to_csv(rows: &[DailyReportRow]) -> String {
+    let mut out = String::with_capacity(64 * (rows.len() + 1));
+    out.push_str(CSV_HEADER);
+    out.push('\n');
+
+    for r in rows {
+        out.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{}\n",
+            r.date,
+            r.symbol,
+            r.instance_id.as_deref().unwrap_or(""),
+            r.fills,
+            r.gross_pnl,
+            r.fees,
+            r.funding_received,
+            r.funding_paid,
+            r.net_pnl,
+            r.wins,
+            r.losses,
+            r.win_rate.map(|w| w.to_string()).unwrap_or_default(),
+        ));
+    }
+
+    out
+}
@@ -0,0 +1,940 @@
+// src/risk.rs
+
+use dashmap::{DashMap, DashSet};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+use crate::ffi_types::{CEvent, EVENT_BOOK_TICKER, EVENT_TRADE};
+use crate::notifications::{self, NotifyKind};
+use crate::strategies::manager::base_slot;
+use crate::user_data::{key_id, UserDataEvent, UserDataUpdate};
+
+// ═══════════════════════════════════════════════════════════
+// КОДЫ ОШИБОК (локальные отказы, не Binance)
+// ═══════════════════════════════════════════════════════════
+
+pub const ERR_KILL_SWITCH: i32 = -9100;
+pub const ERR_MAX_NOTIONAL: i32 = -9101;
+pub const ERR_MAX_POSITION: i32 = -9102;
+pub const ERR_MAX_OPEN_ORDERS: i32 = -9103;
+pub const ERR_DAILY_LOSS: i32 = -9104;
+pub const ERR_NO_REFERENCE_PRICE: i32 = -9105;
+
+/// Сколько закрытых order_id помним (см. RecentlyClosed)
+const RECENTLY_CLOSED: usize = 4096;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+/// Лимиты. None = без ограничения.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskLimits {
+    /// Максимальный нотионал одного ордера (USDT)
+    pub max_order_notional: Option<f64>,
+    /// Максимальная чистая позиция по символу (в контрактах). Позиция и
+    /// ордера в полёте считаются по всему api_key и для лимитов инстанса:
+    /// ядро не делит позицию ключа между инстансами, так что инстансы на
+    /// одном ключе и символе делят этот лимит
+    pub max_position_qty: Option<f64>,
+    /// Максимум одновременно открытых ордеров
+    pub max_open_orders: Option<usize>,
+    /// Максимальный реализованный убыток за UTC-сутки (USDT, положительное число)
+    pub max_daily_loss: Option<f64>,
+}
+
+impl RiskLimits {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let values = [
+            ("max_order_notional", self.max_order_notional),
+            ("max_position_qty", self.max_position_qty),
+            ("max_daily_loss", self.max_daily_loss),
+        ];
+        for (name, value) in values {
+            if value.is_some_and(|v| !v.is_finite() || v <= 0.0) {
+                anyhow::bail!("{} must be a positive number", name);
+            }
+        }
+        if self.max_open_orders == Some(0) {
+            anyhow::bail!("max_open_orders must be at least 1");
+        }
+        Ok(())
+    }
+
+    /// Заданные поля other поверх этих
+    fn overlay(&self, other: &RiskLimits) -> RiskLimits {
+        RiskLimits {
+            max_order_notional: other.max_order_notional.or(self.max_order_notional),
+            max_position_qty: other.max_position_qty.or(self.max_position_qty),
+            max_open_orders: other.max_open_orders.or(self.max_open_orders),
+            max_daily_loss: other.max_daily_loss.or(self.max_daily_loss),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RiskConfig {
+    /// Лимиты для ключей без индивидуальных настроек
+    pub defaults: RiskLimits,
+    /// Индивидуальные лимиты по api_key
+    pub keys: HashMap<String, RiskLimits>,
+    /// Индивидуальные лимиты по instance_id
+    pub instances: HashMap<String, RiskLimits>,
+    /// Любое нарушение лимита включает kill switch
+    pub kill_switch_on_violation: bool,
+}
+
+// ═══════════════════════════════════════════════════════════
+// ТИПЫ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone)]
+pub struct RiskReject {
+    pub code: i32,
+    pub message: String,
+}
+
+impl RiskReject {
+    fn new(code: i32, message: impl Into<String>) -> Self {
+        Self { code, message: message.into() }
+    }
+}
+
+/// Реализованный PnL за текущие UTC-сутки
+#[derive(Debug, Clone, Copy, Default)]
+struct DailyPnl {
+    day: i64,
+    realized: f64,
+}
+
+impl DailyPnl {
+    fn add(&mut self, day: i64, pnl: f64) {
+        if self.day != day {
+            self.day = day;
+            self.realized = 0.0;
+        }
+        self.realized += pnl;
+    }
+
+    fn today(&self, day: i64) -> f64 {
+        if self.day == day { self.realized } else { 0.0 }
+    }
+}
+
+/// Недавно закрытые order_id. Терминальный ORDER_TRADE_UPDATE (FILLED у
+/// MARKET/IOC) часто приходит раньше ack: без этого ack со статусом NEW
+/// вернул бы мёртвый ордер в открытые, и его никто бы не убрал
+#[derive(Default)]
+struct RecentlyClosed {
+    order: VecDeque<i64>,
+    ids: HashSet<i64>,
+}
+
+impl RecentlyClosed {
+    fn insert(&mut self, order_id: i64) {
+        if !self.ids.insert(order_id) {
+            return;
+        }
+        self.order.push_back(order_id);
+        if self.order.len() > RECENTLY_CLOSED {
+            if let Some(old) = self.order.pop_front() {
+                self.ids.remove(&old);
+            }
+        }
+    }
+
+    fn contains(&self, order_id: i64) -> bool {
+        self.ids.contains(&order_id)
+    }
+}
+
+/// Остаток принятого ордера, ещё не ставший позицией
+struct OpenExposure {
+    api_key: String,
+    symbol: String,
+    buy: bool,
+    remaining: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KillSwitchState {
+    pub active: bool,
+    pub reason: Option<String>,
+    pub since: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScopeStats {
+    pub id: String,
+    pub open_orders: usize,
+    pub daily_realized_pnl: f64,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub positions: HashMap<String, f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskStatus {
+    pub kill_switch: KillSwitchState,
+    pub kill_switch_on_violation: bool,
+    pub defaults: RiskLimits,
+    pub keys: HashMap<String, RiskLimits>,
+    pub instances: HashMap<String, RiskLimits>,
+    pub key_stats: Vec<ScopeStats>,
+    pub instance_stats: Vec<ScopeStats>,
+}
+
+// ═══════════════════════════════════════════════════════════
+// RISK MANAGER
+// ═══════════════════════════════════════════════════════════
+
+pub struct RiskManager {
+    defaults: Mutex<RiskLimits>,
+    key_limits: DashMap<String, RiskLimits>,
+    /// Лимиты, ордера и PnL инстанса - по id без слота blue-green (base_slot):
+    /// swap не сбрасывает ни лимиты, ни убыток за день
+    instance_limits: DashMap<String, RiskLimits>,
+    /// Лимиты из запроса запуска поверх instance_limits, снимаются по его
+    /// завершении. Отдельно, чтобы не затирать лимиты, заданные через /api/risk.
+    /// По точному id: у каждого слота свой запуск
+    run_limits: DashMap<String, RiskLimits>,
+    kill_switch_on_violation: bool,
+
+    kill_switch: AtomicBool,
+    kill_reason: Mutex<Option<(String, i64)>>,
+
+    /// Последняя цена по символу (mid bookTicker / цена сделки)
+    last_price: DashMap<String, f64>,
+    /// (api_key, symbol) -> позиция
+    positions: DashMap<(String, String), f64>,
+    /// (api_key, symbol) -> (BUY, SELL): объём ордеров в полёте и в стакане.
+    /// Пачка ордеров за один round-trip иначе прошла бы лимит позиции целиком
+    exposure: DashMap<(String, String), (f64, f64)>,
+    /// order_id -> его доля в exposure (снимается исполнением и закрытием)
+    order_exposure: DashMap<i64, OpenExposure>,
+    /// api_key -> открытые order_id
+    key_open_orders: DashMap<String, DashSet<i64>>,
+    /// api_key -> ордера, прошедшие проверку и ещё без ответа биржи.
+    /// Без них стратегия успела бы за один round-trip выставить сколько угодно
+    key_in_flight: DashMap<String, usize>,
+    /// instance_id -> открытые order_id
+    instance_open_orders: DashMap<String, DashSet<i64>>,
+    /// instance_id -> ордера в полёте (см. key_in_flight)
+    instance_in_flight: DashMap<String, usize>,
+    /// order_id -> instance_id (без слота)
+    order_owner: DashMap<i64, String>,
+    recently_closed: Mutex<RecentlyClosed>,
+    key_pnl: DashMap<String, DailyPnl>,
+    instance_pnl: DashMap<String, DailyPnl>,
+}
+
+impl RiskManager {
+    pub fn new(
+        config: RiskConfig,
+        market_rx: broadcast::Receiver<CEvent>,
+        user_rx: broadcast::Receiver<UserDataUpdate>,
+    ) -> Arc<Self> {
+        let risk = Arc::new(Self {
+            defaults: Mutex::new(config.defaults),
+            key_limits: config.keys.into_iter().collect(),
+            instance_limits: config.instances.into_iter().map(|(id, l)| (base_slot(&id).to_string(), l)).collect(),
+            run_limits: DashMap::new(),
+            kill_switch_on_violation: config.kill_switch_on_violation,
+            kill_switch: AtomicBool::new(false),
+            kill_reason: Mutex::new(None),
+            last_price: DashMap::new(),
+            positions: DashMap::new(),
+            exposure: DashMap::new(),
+            order_exposure: DashMap::new(),
+            key_open_orders: DashMap::new(),
+            key_in_flight: DashMap::new(),
+            instance_open_orders: DashMap::new(),
+            instance_in_flight: DashMap::new(),
+            order_owner: DashMap::new(),
+            recently_closed: Mutex::new(RecentlyClosed::default()),
+            key_pnl: DashMap::new(),
+            instance_pnl: DashMap::new(),
+        });
+
+        {
+            let risk = risk.clone();
+            tokio::spawn(async move { risk.market_loop(market_rx).await });
+        }
+        {
+            let risk = risk.clone();
+            tokio::spawn(async move { risk.user_data_loop(user_rx).await });
+        }
+
+        risk
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // ПРОВЕРКА ОРДЕРА
+    // ═══════════════════════════════════════════════════════════
+
+    /// Pre-trade проверка. Вызывается из place_order до подписи.
+    /// Прошедший ордер учитывается в exposure: после ответа биржи зовите
+    /// on_order_accepted или on_order_failed
+    #[allow(clippy::too_many_arguments)]
+    pub fn check_order(
+        &self,
+        instance_id: Option<&str>,
+        api_key: &str,
+        symbol: &str,
+        side: &str,
+        price: f64,
+        qty: f64,
+        order_type: u8,
+    ) -> Result<(), RiskReject> {
+        if self.kill_switch.load(Ordering::Relaxed) {
+            return Err(RiskReject::new(ERR_KILL_SWITCH, "Kill switch is active"));
+        }
+
+        let result = self.check_limits(instance_id, api_key, symbol, side, price, qty, order_type);
+
+        match &result {
+            Ok(()) => {
+                self.add_exposure(api_key, &symbol.to_uppercase(), is_buy(side), qty.abs());
+                add_in_flight(&self.key_in_flight, api_key, 1);
+                if let Some(instance_id) = instance_id {
+                    add_in_flight(&self.instance_in_flight, base_slot(instance_id), 1);
+                }
+            }
+            Err(reject) => {
+                if self.kill_switch_on_violation {
+                    self.trip_kill_switch(&format!("Risk violation: {}", reject.message));
+                }
+            }
+        }
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn check_limits(
+        &self,
+        instance_id: Option<&str>,
+        api_key: &str,
+        symbol: &str,
+        side: &str,
+        price: f64,
+        qty: f64,
+        order_type: u8,
+    ) -> Result<(), RiskReject> {
+        let symbol = symbol.to_uppercase();
+        let day = current_day();
+
+        let key_limits = self.key_limits.get(api_key)
+            .map(|l| l.clone())
+            .unwrap_or_else(|| self.defaults.lock().unwrap().clone());
+
+        let key_open = self.key_open_orders.get(api_key).map(|s| s.len()).unwrap_or(0)
+            + self.key_in_flight.get(api_key).map(|n| *n).unwrap_or(0);
+        let key_pnl = self.key_pnl.get(api_key).map(|p| p.today(day)).unwrap_or(0.0);
+
+        self.check_scope(
+            &format!("key {}", key_id(api_key)),
+            &key_limits, api_key, &symbol, side, price, qty, order_type, key_open, key_pnl,
+        )?;
+
+        if let Some(instance_id) = instance_id {
+            if let Some(limits) = self.effective_instance_limits(instance_id) {
+                let base = base_slot(instance_id);
+                let open = self.instance_open_orders.get(base).map(|s| s.len()).unwrap_or(0)
+                    + self.instance_in_flight.get(base).map(|n| *n).unwrap_or(0);
+                let pnl = self.instance_pnl.get(base).map(|p| p.today(day)).unwrap_or(0.0);
+
+                self.check_scope(
+                    &format!("instance {}", instance_id),
+                    &limits, api_key, &symbol, side, price, qty, order_type, open, pnl,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn check_scope(
+        &self,
+        scope: &str,
+        limits: &RiskLimits,
+        api_key: &str,
+        symbol: &str,
+        side: &str,
+        price: f64,
+        qty: f64,
+        order_type: u8,
+        open_orders: usize,
+        daily_pnl: f64,
+    ) -> Result<(), RiskReject> {
+        if let Some(max_loss) = limits.max_daily_loss {
+            if daily_pnl <= -max_loss {
+                return Err(RiskReject::new(
+                    ERR_DAILY_LOSS,
+                    format!("{}: daily loss {:.2} reached limit {:.2}", scope, -daily_pnl, max_loss),
+                ));
+            }
+        }
+
+        if let Some(max_open) = limits.max_open_orders {
+            if open_orders >= max_open {
+                return Err(RiskReject::new(
+                    ERR_MAX_OPEN_ORDERS,
+                    format!("{}: {} open orders, limit {}", scope, open_orders, max_open),
+                ));
+            }
+        }
+
+        if let Some(max_notional) = limits.max_order_notional {
+            // MARKET ордер идёт без цены - берём последнюю известную
+            let ref_price = if order_type == 1 || price <= 0.0 {
+                match self.last_price.get(symbol) {
+                    Some(p) => *p,
+                    None => return Err(RiskReject::new(
+                        ERR_NO_REFERENCE_PRICE,
+                        format!("{}: no reference price for {} (subscribe to market data)", scope, symbol),
+                    )),
+                }
+            } else {
+                price
+            };
+
+            let notional = ref_price * qty.abs();
+            if notional > max_notional {
+                return Err(RiskReject::new(
+                    ERR_MAX_NOTIONAL,
+                    format!("{}: notional {:.2} exceeds {:.2}", scope, notional, max_notional),
+                ));
+            }
+        }
+
+        // Позиция ключа, а не инстанса (см. RiskLimits::max_position_qty)
+        if let Some(max_pos) = limits.max_position_qty {
+            let key = (api_key.to_string(), symbol.to_string());
+            let current = self.positions.get(&key).map(|p| *p).unwrap_or(0.0);
+            // Ордера той же стороны в полёте и в стакане считаем уже исполненными
+            let (buys, sells) = self.exposure.get(&key).map(|e| *e).unwrap_or((0.0, 0.0));
+            let (pending, signed) = if is_buy(side) { (buys, qty.abs()) } else { (-sells, -qty.abs()) };
+            let committed = current + pending;
+            let projected = committed + signed;
+
+            // Уменьшение позиции разрешаем всегда
+            if projected.abs() > max_pos && projected.abs() > committed.abs() {
+                return Err(RiskReject::new(
+                    ERR_MAX_POSITION,
+                    format!(
+                        "{}: position {} ({:+} in open orders) -> {} exceeds {}",
+                        scope, current, pending, projected, max_pos
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // УЧЁТ ОРДЕРОВ
+    // ═══════════════════════════════════════════════════════════
+
+    /// Биржа приняла ордер (ответ на order.place). Остаток ордера переходит
+    /// из "в полёте" в открытые; исполнения до ack не вычитаются, лишнее
+    /// снимется при закрытии ордера
+    #[allow(clippy::too_many_arguments)]
+    pub fn on_order_accepted(
+        &self,
+        instance_id: Option<&str>,
+        api_key: &str,
+        symbol: &str,
+        side: &str,
+        qty: f64,
+        order_id: i64,
+        status: &str,
+    ) {
+        let symbol = symbol.to_uppercase();
+        add_in_flight(&self.key_in_flight, api_key, -1);
+        if let Some(instance_id) = instance_id {
+            add_in_flight(&self.instance_in_flight, base_slot(instance_id), -1);
+        }
+        // Замок держим до вставки: иначе закрытие между проверкой и вставкой потеряется
+        let closed = self.recently_closed.lock().unwrap();
+        if closed.contains(order_id)
+            || matches!(status, "FILLED" | "CANCELED" | "EXPIRED" | "REJECTED" | "EXPIRED_IN_MATCH")
+        {
+            self.add_exposure(api_key, &symbol, is_buy(side), -qty.abs());
+            return;
+        }
+        self.order_exposure.insert(order_id, OpenExposure {
+            api_key: api_key.to_string(),
+            symbol,
+            buy: is_buy(side),
+            remaining: qty.abs(),
+        });
+
+        self.key_open_orders.entry(api_key.to_string()).or_default().insert(order_id);
+
+        if let Some(instance_id) = instance_id.map(base_slot) {
+            self.instance_open_orders.entry(instance_id.to_string()).or_default().insert(order_id);
+            self.order_owner.insert(order_id, instance_id.to_string());
+        }
+    }
+
+    /// Ордер не принят (ошибка в ответе на order.place или не отправлен
+    /// после проверки): снимаем его из exposure и из ордеров в полёте
+    pub fn on_order_failed(&self, instance_id: Option<&str>, api_key: &str, symbol: &str, side: &str, qty: f64) {
+        add_in_flight(&self.key_in_flight, api_key, -1);
+        if let Some(instance_id) = instance_id {
+            add_in_flight(&self.instance_in_flight, base_slot(instance_id), -1);
+        }
+        self.add_exposure(api_key, &symbol.to_uppercase(), is_buy(side), -qty.abs());
+    }
+
+    /// Ордер отменён (ответ на order.cancel) или закрыт по user data
+    pub fn on_order_closed(&self, api_key: &str, order_id: i64) {
+        let mut closed = self.recently_closed.lock().unwrap();
+        closed.insert(order_id);
+        if let Some((_, e)) = self.order_exposure.remove(&order_id) {
+            self.add_exposure(&e.api_key, &e.symbol, e.buy, -e.remaining);
+        }
+        if let Some(set) = self.key_open_orders.get(api_key) {
+            set.remove(&order_id);
+        }
+        if let Some((_, instance_id)) = self.order_owner.remove(&order_id) {
+            if let Some(set) = self.instance_open_orders.get(&instance_id) {
+                set.remove(&order_id);
+            }
+        }
+    }
+
+    /// delta < 0 - снять объём (не ниже нуля)
+    fn add_exposure(&self, api_key: &str, symbol: &str, buy: bool, delta: f64) {
+        let mut e = self.exposure.entry((api_key.to_string(), symbol.to_string())).or_default();
+        let side = if buy { &mut e.0 } else { &mut e.1 };
+        *side = (*side + delta).max(0.0);
+    }
+
+    async fn market_loop(self: Arc<Self>, mut rx: broadcast::Receiver<CEvent>) {
+        loop {
+            match rx.recv().await {
+                Ok(event) => match event.event_type {
+                    EVENT_BOOK_TICKER => {
+                        let bt = unsafe { &event.data.book_ticker };
+                        self.update_price(bt.symbol_str(), bt.mid_price());
+                    }
+                    EVENT_TRADE => {
+                        let t = unsafe { &event.data.trade };
+                        self.update_price(t.symbol_str(), t.price);
+                    }
+                    _ => {}
+                },
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    fn update_price(&self, symbol: &str, price: f64) {
+        if let Some(mut p) = self.last_price.get_mut(symbol) {
+            *p = price;
+        } else {
+            self.last_price.insert(symbol.to_string(), price);
+        }
+    }
+
+    async fn user_data_loop(self: Arc<Self>, mut rx: broadcast::Receiver<UserDataUpdate>) {
+        loop {
+            match rx.recv().await {
+                Ok(update) => self.on_user_data(&update),
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("🛡️ Risk lagged {} user data events", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    fn on_user_data(&self, update: &UserDataUpdate) {
+        let api_key = update.api_key.as_ref();
+
+        match &update.event {
+            UserDataEvent::OrderTradeUpdate(o) => {
+                let owner = self.order_owner.get(&o.order_id).map(|e| e.clone());
+
+                if o.status == "NEW" && owner.is_none() {
+                    // Ордер выставлен не через ядро - всё равно учитываем на уровне ключа
+                    self.key_open_orders.entry(api_key.to_string()).or_default().insert(o.order_id);
+                }
+
+                if o.is_fill() {
+                    // Исполненное уходит из exposure в позицию (ACCOUNT_UPDATE)
+                    let filled = self.order_exposure.get_mut(&o.order_id).map(|mut e| {
+                        let q = o.last_qty().min(e.remaining);
+                        e.remaining -= q;
+                        (e.api_key.clone(), e.symbol.clone(), e.buy, q)
+                    });
+                    if let Some((key, symbol, buy, q)) = filled {
+                        self.add_exposure(&key, &symbol, buy, -q);
+                    }
+
+                    let pnl = o.realized() - o.fee_usdt();
+                    let day = current_day();
+                    self.key_pnl.entry(api_key.to_string()).or_default().add(day, pnl);
+                    if let Some(instance_id) = &owner {
+                        self.instance_pnl.entry(instance_id.clone()).or_default().add(day, pnl);
+                    }
+                }
+
+                if o.is_terminal() {
+                    self.on_order_closed(api_key, o.order_id);
+                }
+            }
+
+            UserDataEvent::AccountUpdate(a) => {
+                for p in &a.positions {
+                    if p.position_side != "BOTH" && !p.position_side.is_empty() {
+                        continue;
+                    }
+                    let amt: f64 = p.position_amt.parse().unwrap_or(0.0);
+                    self.positions.insert((api_key.to_string(), p.symbol.clone()), amt);
+                }
+            }
+
+            _ => {}
+        }
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // KILL SWITCH
+    // ═══════════════════════════════════════════════════════════
+
+    pub fn trip_kill_switch(&self, reason: &str) {
+        if !self.kill_switch.swap(true, Ordering::SeqCst) {
+            *self.kill_reason.lock().unwrap() = Some((reason.to_string(), chrono::Utc::now().timestamp()));
+            tracing::error!("🚨 KILL SWITCH TRIPPED: {}", reason);
+            notifications::notify(NotifyKind::KillSwitch, None, format!("Kill switch tripped: {}", reason));
+        }
+    }
+
+    pub fn reset_kill_switch(&self) {
+        self.kill_switch.store(false, Ordering::SeqCst);
+        *self.kill_reason.lock().unwrap() = None;
+        tracing::warn!("🟢 Kill switch reset");
+    }
+
+    pub fn kill_switch_active(&self) -> bool {
+        self.kill_switch.load(Ordering::Relaxed)
+    }
+
+    pub fn kill_switch_state(&self) -> KillSwitchState {
+        let reason = self.kill_reason.lock().unwrap().clone();
+        KillSwitchState {
+            active: self.kill_switch.load(Ordering::Relaxed),
+            reason: reason.as_ref().map(|(r, _)| r.clone()),
+            since: reason.map(|(_, t)| t),
+        }
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // НАСТРОЙКА ЛИМИТОВ
+    // ═══════════════════════════════════════════════════════════
+
+    pub fn set_default_limits(&self, limits: RiskLimits) {
+        *self.defaults.lock().unwrap() = limits;
+    }
+
+    pub fn set_key_limits(&self, api_key: &str, limits: RiskLimits) {
+        self.key_limits.insert(api_key.to_string(), limits);
+    }
+
+    pub fn set_instance_limits(&self, instance_id: &str, limits: RiskLimits) {
+        self.instance_limits.insert(base_slot(instance_id).to_string(), limits);
+    }
+
+    /// Лимиты из запроса запуска: поверх лимитов инстанса,
+    /// действуют до завершения этого запуска
+    pub fn set_run_limits(&self, instance_id: &str, limits: &RiskLimits) {
+        self.run_limits.insert(instance_id.to_string(), limits.clone());
+    }
+
+    /// Запуск завершён: снова действуют только лимиты инстанса
+    pub fn clear_run_limits(&self, instance_id: &str) {
+        self.run_limits.remove(instance_id);
+    }
+
+    /// Лимиты инстанса с лимитами текущего запуска поверх
+    fn effective_instance_limits(&self, instance_id: &str) -> Option<RiskLimits> {
+        let base = self.instance_limits.get(base_slot(instance_id)).map(|l| l.clone());
+        match (base, self.run_limits.get(instance_id)) {
+            (Some(base), Some(run)) => Some(base.overlay(&run)),
+            (None, Some(run)) => Some(run.clone()),
+            (base, None) => base,
+        }
+    }
+
+    pub fn status(&self) -> RiskStatus {
+        let day = current_day();
+
+        let mut key_ids: Vec<String> = self.key_open_orders.iter().map(|e| e.key().clone()).collect();
+        key_ids.extend(self.key_pnl.iter().map(|e| e.key().clone()));
+        key_ids.extend(self.positions.iter().map(|e| e.key().0.clone()));
+        key_ids.sort();
+        key_ids.dedup();
+
+        let key_stats = key_ids.iter().map(|k| ScopeStats {
+            id: key_id(k),
+            open_orders: self.key_open_orders.get(k).map(|s| s.len()).unwrap_or(0),
+            daily_realized_pnl: self.key_pnl.get(k).map(|p| p.today(day)).unwrap_or(0.0),
+            positions: self.positions.iter()
+                .filter(|e| &e.key().0 == k && *e.value() != 0.0)
+                .map(|e| (e.key().1.clone(), *e.value()))
+                .collect(),
+        }).collect();
+
+        let mut instance_ids: Vec<String> = self.instance_open_orders.iter().map(|e| e.key().clone()).collect();
+        instance_ids.extend(self.instance_pnl.iter().map(|e| e.key().clone()));
+        instance_ids.sort();
+        instance_ids.dedup();
+
+        let instance_stats = instance_ids.iter().map(|id| ScopeStats {
+            id: id.clone(),
+            open_orders: self.instance_open_orders.get(id).map(|s| s.len()).unwrap_or(0),
+            daily_realized_pnl: self.instance_pnl.get(id).map(|p| p.today(day)).unwrap_or(0.0),
+            positions: HashMap::new(),
+        }).collect();
+
+        RiskStatus {
+            kill_switch: self.kill_switch_state(),
+            kill_switch_on_violation: self.kill_switch_on_violation,
+            defaults: self.defaults.lock().unwrap().clone(),
+            keys: self.key_limits.iter().map(|e| (key_id(e.key()), e.value().clone())).collect(),
+            instances: self.instance_limits.iter().map(|e| e.key().clone())
+                .chain(self.run_limits.iter().map(|e| e.key().clone()))
+                .filter_map(|id| Some((id.clone(), self.effective_instance_limits(&id)?)))
+                .collect(),
+            key_stats,
+            instance_stats,
+        }
+    }
+}
+
+fn current_day() -> i64 {
+    chrono::Utc::now().timestamp() / 86_400
+}
+
+fn is_buy(side: &str) -> bool {
+    !side.eq_ignore_ascii_case("SELL")
+}
+
+/// delta < 0 - ответ биржи пришёл (не ниже нуля)
+fn add_in_flight(map: &DashMap<String, usize>, id: &str, delta: isize) {
+    let mut n = map.entry(id.to_string()).or_default();
+    *n = n.saturating_add_signed(delta);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager(config: RiskConfig) -> Arc<RiskManager> {
+        let (_, market_rx) = broadcast::channel(1);
+        let (_, user_rx) = broadcast::channel(1);
+        RiskManager::new(config, market_rx, user_rx)
+    }
+
+    fn limits(max_open_orders: usize) -> RiskLimits {
+        RiskLimits { max_open_orders: Some(max_open_orders), ..Default::default() }
+    }
+
+    fn place(risk: &RiskManager, instance_id: Option<&str>) -> Result<(), i32> {
+        risk.check_order(instance_id, "key", "BTCUSDT", "BUY", 100.0, 1.0, 0).map_err(|r| r.code)
+    }
+
+    fn fill(order_id: i64, status: &str, qty: &str, realized: &str) -> UserDataUpdate {
+        let o: crate::user_data::RawOrderUpdate = serde_json::from_value(serde_json::json!({
+            "s": "BTCUSDT", "c": "x", "S": "BUY", "o": "LIMIT", "q": "1", "p": "100",
+            "x": "TRADE", "X": status, "i": order_id, "l": qty, "z": qty, "L": "100",
+            "T": 0, "rp": realized,
+        }))
+        .unwrap();
+        UserDataUpdate { api_key: "key".into(), event: UserDataEvent::OrderTradeUpdate(Box::new(o)) }
+    }
+
+    fn position_limit(max_pos: f64) -> RiskConfig {
+        RiskConfig {
+            defaults: RiskLimits { max_position_qty: Some(max_pos), ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn orders_in_flight_and_open_count_toward_position() {
+        let risk = manager(position_limit(2.5));
+
+        assert_eq!(place(&risk, None), Ok(()));
+        risk.on_order_accepted(None, "key", "BTCUSDT", "BUY", 1.0, 1, "NEW");
+        assert_eq!(place(&risk, None), Ok(()));
+        // 1 в стакане + 1 в полёте + 1 новый > 2.5
+        assert_eq!(place(&risk, None), Err(ERR_MAX_POSITION));
+
+        // Закрытие без исполнения освобождает лимит
+        risk.on_order_closed("key", 1);
+        assert_eq!(place(&risk, None), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn ack_after_terminal_update_is_ignored() {
+        let risk = manager(RiskConfig { defaults: limits(1), ..Default::default() });
+
+        assert_eq!(place(&risk, None), Ok(()));
+        // FILLED из user data раньше ответа на order.place
+        risk.on_user_data(&fill(5, "FILLED", "1", "0"));
+        risk.on_order_accepted(None, "key", "BTCUSDT", "BUY", 1.0, 5, "NEW");
+
+        assert_eq!(place(&risk, None), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn notional_needs_reference_price_for_market_orders() {
+        let risk = manager(RiskConfig {
+            defaults: RiskLimits { max_order_notional: Some(150.0), ..Default::default() },
+            ..Default::default()
+        });
+
+        assert!(risk.check_order(None, "key", "BTCUSDT", "BUY", 100.0, 1.0, 0).is_ok());
+        let code = |r: Result<(), RiskReject>| r.map_err(|r| r.code);
+        assert_eq!(code(risk.check_order(None, "key", "BTCUSDT", "BUY", 100.0, 2.0, 0)), Err(ERR_MAX_NOTIONAL));
+        assert_eq!(code(risk.check_order(None, "key", "BTCUSDT", "BUY", 0.0, 1.0, 1)), Err(ERR_NO_REFERENCE_PRICE));
+
+        risk.update_price("BTCUSDT", 200.0);
+        assert_eq!(code(risk.check_order(None, "key", "BTCUSDT", "BUY", 0.0, 1.0, 1)), Err(ERR_MAX_NOTIONAL));
+        assert!(risk.check_order(None, "key", "BTCUSDT", "BUY", 0.0, 0.5, 1).is_ok());
+    }
+
+    #[tokio::test]
+    async fn daily_loss_blocks_new_orders() {
+        let risk = manager(RiskConfig {
+            defaults: RiskLimits { max_daily_loss: Some(10.0), ..Default::default() },
+            ..Default::default()
+        });
+
+        risk.on_user_data(&fill(6, "PARTIALLY_FILLED", "0.5", "-6"));
+        assert_eq!(place(&risk, None), Ok(()));
+        risk.on_user_data(&fill(6, "FILLED", "1", "-4"));
+        assert_eq!(place(&risk, None), Err(ERR_DAILY_LOSS));
+    }
+
+    #[tokio::test]
+    async fn violation_trips_kill_switch_when_configured() {
+        let risk = manager(RiskConfig {
+            kill_switch_on_violation: true,
+            ..position_limit(0.5)
+        });
+
+        assert_eq!(place(&risk, None), Err(ERR_MAX_POSITION));
+        assert!(risk.kill_switch_active());
+        // Kill switch отклоняет даже ордер, проходящий лимиты
+        assert_eq!(
+            risk.check_order(None, "key", "BTCUSDT", "BUY", 100.0, 0.1, 0).map_err(|r| r.code),
+            Err(ERR_KILL_SWITCH),
+        );
+
+        risk.reset_kill_switch();
+        assert!(risk.check_order(None, "key", "BTCUSDT", "BUY", 100.0, 0.1, 0).is_ok());
+    }
+
+    #[tokio::test]
+    async fn key_max_open_orders_counts_orders_in_flight() {
+        let risk = manager(RiskConfig { defaults: limits(3), ..Default::default() });
+
+        for _ in 0..3 {
+            assert_eq!(place(&risk, None), Ok(()));
+        }
+        assert_eq!(place(&risk, None), Err(ERR_MAX_OPEN_ORDERS));
+
+        // Отказ биржи освобождает место, ack переводит ордер в открытые
+        risk.on_order_failed(None, "key", "BTCUSDT", "BUY", 1.0);
+        risk.on_order_accepted(None, "key", "BTCUSDT", "BUY", 1.0, 1, "NEW");
+        assert_eq!(place(&risk, None), Ok(()));
+        assert_eq!(place(&risk, None), Err(ERR_MAX_OPEN_ORDERS));
+
+        risk.on_order_closed("key", 1);
+        assert_eq!(place(&risk, None), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn instance_max_open_orders_counts_orders_in_flight() {
+        let risk = manager(RiskConfig {
+            instances: HashMap::from([("a".to_string(), limits(2))]),
+            ..Default::default()
+        });
+
+        assert_eq!(place(&risk, Some("a")), Ok(()));
+        assert_eq!(place(&risk, Some("a")), Ok(()));
+        assert_eq!(place(&risk, Some("a")), Err(ERR_MAX_OPEN_ORDERS));
+        // Лимит инстанса не касается других инстансов на том же ключе
+        assert_eq!(place(&risk, Some("b")), Ok(()));
+
+        risk.on_order_failed(Some("a"), "key", "BTCUSDT", "BUY", 1.0);
+        assert_eq!(place(&risk, Some("a")), Ok(()));
+        risk.on_order_accepted(Some("a"), "key", "BTCUSDT", "BUY", 1.0, 1, "NEW");
+        assert_eq!(place(&risk, Some("a")), Err(ERR_MAX_OPEN_ORDERS));
+    }
+
+    #[tokio::test]
+    async fn instance_max_position_is_account_wide() {
+        let max_pos = RiskLimits { max_position_qty: Some(1.5), ..Default::default() };
+        let risk = manager(RiskConfig {
+            instances: HashMap::from([("a".to_string(), max_pos.clone()), ("b".to_string(), max_pos)]),
+            ..Default::default()
+        });
+
+        assert_eq!(place(&risk, Some("a")), Ok(()));
+        // Покупка "a" в полёте уже занимает лимит позиции ключа
+        assert_eq!(place(&risk, Some("b")), Err(ERR_MAX_POSITION));
+        assert!(risk.check_order(Some("b"), "key", "BTCUSDT", "SELL", 100.0, 1.0, 0).is_ok());
+    }
+
+    #[tokio::test]
+    async fn run_limits_do_not_overwrite_runtime_limits() {
+        let risk = manager(RiskConfig {
+            instances: HashMap::from([("a".to_string(), limits(5))]),
+            ..Default::default()
+        });
+        // Заданы через /api/risk уже после старта
+        risk.set_instance_limits("a", limits(3));
+        risk.set_run_limits("a", &limits(1));
+
+        assert_eq!(place(&risk, Some("a")), Ok(()));
+        assert_eq!(place(&risk, Some("a")), Err(ERR_MAX_OPEN_ORDERS));
+
+        risk.clear_run_limits("a");
+        assert_eq!(place(&risk, Some("a")), Ok(()));
+        assert_eq!(place(&risk, Some("a")), Ok(()));
+        assert_eq!(place(&risk, Some("a")), Err(ERR_MAX_OPEN_ORDERS));
+    }
+
+    #[tokio::test]
+    async fn swapped_instance_keeps_limits_and_daily_loss() {
+        let risk = manager(RiskConfig::default());
+        risk.set_instance_limits("a", RiskLimits {
+            max_open_orders: Some(2),
+            max_daily_loss: Some(10.0),
+            ..Default::default()
+        });
+
+        assert_eq!(place(&risk, Some("a")), Ok(()));
+        risk.on_order_accepted(Some("a"), "key", "BTCUSDT", "BUY", 1.0, 1, "NEW");
+        // Новый слот делит лимит открытых ордеров со старым
+        assert_eq!(place(&risk, Some("a@green")), Ok(()));
+        assert_eq!(place(&risk, Some("a@green")), Err(ERR_MAX_OPEN_ORDERS));
+
+        // Убыток ордера старого слота засчитывается новому
+        risk.on_user_data(&fill(1, "FILLED", "1", "-12"));
+        risk.on_order_failed(Some("a@green"), "key", "BTCUSDT", "BUY", 1.0);
+        assert_eq!(place(&risk, Some("a@green")), Err(ERR_DAILY_LOSS));
+    }
+}
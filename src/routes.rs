@@ -1 +1,118 @@
-pub mod strategy;
\ No newline at end of file
+// src/routes.rs
+
+use axum::{http::StatusCode, Json};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::exchange_data::ExchangeData;
+use crate::exchange_trade::ExchangeTrade;
+use crate::ffi_types::CEvent;
+use crate::history::History;
+use crate::keystore::Keystore;
+use crate::orders::OrderManager;
+use crate::pnl::PnlTracker;
+use crate::rate_limit::RateLimiter;
+use crate::risk::RiskManager;
+use crate::scheduler::Scheduler;
+use crate::strategies::compile::CompileQueue;
+use crate::strategies::manager::StrategyRunner;
+use crate::strategies::storage::StrategyStorage;
+use crate::time_sync::TimeSync;
+use crate::user_data::UserDataManager;
+
+pub mod strategy;
+pub mod risk;
+pub mod user_data;
+pub mod rate_limit;
+pub mod alerts;
+pub mod orders;
+pub mod history;
+pub mod pnl;
+pub mod reports;
+pub mod latency;
+pub mod events;
+pub mod keys;
+pub mod time;
+pub mod endpoints;
+pub mod recorder;
+pub mod data;
+pub mod bench;
+pub mod ping;
+pub mod compile;
+pub mod schedules;
+pub mod funding;
+pub mod kv;
+pub mod streams;
+pub mod execution;
+pub mod brackets;
+pub mod positions;
+pub mod health;
+pub mod debug;
+pub mod trade_ws;
+pub mod groups;
+pub mod analytics;
+pub mod metrics;
+
+// ═══════════════════════════════════════════════════════════
+// STATE
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Clone)]
+pub struct AppState {
+    pub storage: Arc<StrategyStorage>,
+    pub runner: Arc<StrategyRunner>,
+    pub event_tx: broadcast::Sender<CEvent>,
+    pub risk: Arc<RiskManager>,
+    pub user_data: Arc<UserDataManager>,
+    pub rate_limiter: Arc<RateLimiter>,
+    pub orders: Arc<OrderManager>,
+    /// None если history выключена в конфиге
+    pub history: Option<Arc<History>>,
+    pub pnl: Arc<PnlTracker>,
+    /// None - мастер-ключ не настроен, алиасов нет
+    pub keystore: Option<Arc<Keystore>>,
+    pub time_sync: Arc<TimeSync>,
+    pub market: Arc<ExchangeData>,
+    pub trade: Arc<ExchangeTrade>,
+    pub compiler: Arc<CompileQueue>,
+    pub scheduler: Arc<Scheduler>,
+}
+
+// ═══════════════════════════════════════════════════════════
+// RESPONSES
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Serialize)]
+pub struct ApiResult<T = ()> {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<T>,
+}
+
+impl<T: Serialize> ApiResult<T> {
+    fn ok(data: T) -> (StatusCode, Json<Self>) {
+        (StatusCode::OK, Json(Self { ok: true, error: None, data: Some(data) }))
+    }
+    
+    fn created(data: T) -> (StatusCode, Json<Self>) {
+        (StatusCode::CREATED, Json(Self { ok: true, error: None, data: Some(data) }))
+    }
+    
+    // Generic error - работает для любого T
+    fn err(status: StatusCode, msg: impl Into<String>) -> (StatusCode, Json<Self>) {
+        (status, Json(Self { ok: false, error: Some(msg.into()), data: None }))
+    }
+}
+
+impl ApiResult<()> {
+    fn ok_empty() -> (StatusCode, Json<Self>) {
+        (StatusCode::OK, Json(Self { ok: true, error: None, data: None }))
+    }
+    
+    fn created_empty() -> (StatusCode, Json<Self>) {
+        (StatusCode::CREATED, Json(Self { ok: true, error: None, data: None }))
+    }
+}
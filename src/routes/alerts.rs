@@ -0,0 +1,36 @@
+// src/routes/alerts.rs
+
+use axum::{
+    http::StatusCode,
+    routing::{get, post},
+    extract::Json,
+    Router,
+};
+
+use crate::alerts::{self, Alert};
+use crate::notifications::{self, NotifyKind};
+use crate::routes::{ApiResult, AppState};
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/alerts", get(list))
+        .route("/notifications/test", post(test_notification))
+        .with_state(state)
+}
+
+async fn list() -> Json<Vec<Alert>> {
+    Json(alerts::recent())
+}
+
+/// Пробное уведомление во все каналы: проверить токен бота и chat_id
+async fn test_notification() -> (StatusCode, Json<ApiResult>) {
+    if !notifications::is_enabled() {
+        return ApiResult::err(StatusCode::BAD_REQUEST, "No notification sinks configured");
+    }
+    notifications::notify(NotifyKind::Custom, None, "Test notification from hftcore");
+    ApiResult::ok_empty()
+}
@@ -0,0 +1,70 @@
+// src/routes/analytics.rs
+
+use axum::{
+    http::StatusCode,
+    routing::get,
+    extract::{Json, Path, Query},
+    Router,
+};
+use serde::Deserialize;
+
+use crate::open_interest::{self, OpenInterest};
+use crate::routes::{ApiResult, AppState};
+use crate::volume_profile::{self, VwapSnapshot};
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/analytics/vwap", get(list))
+        .route("/analytics/vwap/:symbol", get(one))
+        .route("/analytics/open-interest", get(open_interest_list))
+        .route("/analytics/open-interest/:symbol", get(open_interest_one))
+        .with_state(state)
+}
+
+#[derive(Deserialize)]
+struct OneQuery {
+    /// Вместе с footprint (объём по каждой цене окна)
+    #[serde(default)]
+    levels: bool,
+}
+
+/// VWAP по всем символам с подпиской на trade, по убыванию объёма
+async fn list() -> (StatusCode, Json<ApiResult<Vec<VwapSnapshot>>>) {
+    if !volume_profile::enabled() {
+        return ApiResult::err(StatusCode::CONFLICT, "Volume profile is disabled (volume_profile.enabled)");
+    }
+    ApiResult::ok(volume_profile::list())
+}
+
+/// VWAP символа, ?levels=true - с footprint
+async fn one(Path(symbol): Path<String>, Query(q): Query<OneQuery>) -> (StatusCode, Json<ApiResult<VwapSnapshot>>) {
+    if !volume_profile::enabled() {
+        return ApiResult::err(StatusCode::CONFLICT, "Volume profile is disabled (volume_profile.enabled)");
+    }
+    match volume_profile::snapshot(&symbol, q.levels) {
+        Some(snapshot) => ApiResult::ok(snapshot),
+        None => ApiResult::err(
+            StatusCode::NOT_FOUND,
+            format!("No trades for '{}' yet (subscribe to its trade stream)", symbol.to_uppercase()),
+        ),
+    }
+}
+
+/// Последний опрос open interest и long/short ratio по всем символам
+async fn open_interest_list() -> Json<Vec<OpenInterest>> {
+    Json(open_interest::list())
+}
+
+async fn open_interest_one(Path(symbol): Path<String>) -> (StatusCode, Json<ApiResult<OpenInterest>>) {
+    match open_interest::get(&symbol) {
+        Some(info) => ApiResult::ok(info),
+        None => ApiResult::err(
+            StatusCode::NOT_FOUND,
+            format!("No open interest for '{}' (open_interest.enabled, symbols)", symbol.to_uppercase()),
+        ),
+    }
+}
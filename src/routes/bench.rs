@@ -0,0 +1,38 @@
+// src/routes/bench.rs
+
+use axum::{
+    routing::post,
+    extract::{State, Json},
+    http::StatusCode,
+    Router,
+};
+
+use crate::bench::{self, BenchRequest, BenchResult};
+use crate::routes::{ApiResult, AppState};
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/bench/run", post(run))
+        .with_state(state)
+}
+
+/// Прогон синтетических событий через fan-out без подключения к Binance
+async fn run(
+    State(s): State<AppState>,
+    Json(req): Json<BenchRequest>,
+) -> (StatusCode, Json<ApiResult<BenchResult>>) {
+    // Синтетика идёт через тот же fan-out, что и live market data
+    if !s.runner.list().is_empty() {
+        return ApiResult::err(StatusCode::CONFLICT, "Stop running instances before benchmarking");
+    }
+
+    match tokio::task::spawn_blocking(move || bench::run(&req)).await {
+        Ok(Ok(result)) => ApiResult::ok(result),
+        Ok(Err(e)) => ApiResult::err(StatusCode::BAD_REQUEST, e.to_string()),
+        Err(e) => ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
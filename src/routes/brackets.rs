@@ -0,0 +1,49 @@
+// src/routes/brackets.rs
+
+use axum::{
+    http::StatusCode,
+    routing::get,
+    extract::{Json, Path, Query},
+    Router,
+};
+use serde::Deserialize;
+
+use crate::brackets::{self, BracketInfo};
+use crate::routes::{ApiResult, AppState};
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/brackets", get(list))
+        .route("/brackets/:id", get(get_bracket).delete(cancel))
+        .with_state(state)
+}
+
+#[derive(Deserialize)]
+struct ListQuery {
+    instance: Option<String>,
+}
+
+/// Bracket-ордера стратегий, новые первыми (?instance= - только этого инстанса)
+async fn list(Query(q): Query<ListQuery>) -> Json<Vec<BracketInfo>> {
+    Json(brackets::list(q.instance.as_deref()))
+}
+
+async fn get_bracket(Path(id): Path<u64>) -> (StatusCode, Json<ApiResult<BracketInfo>>) {
+    match brackets::get(id) {
+        Some(info) => ApiResult::ok(info),
+        None => ApiResult::err(StatusCode::NOT_FOUND, format!("Bracket #{} not found", id)),
+    }
+}
+
+/// Снимает вход и защитные ордера; открытая позиция остаётся
+async fn cancel(Path(id): Path<u64>) -> (StatusCode, Json<ApiResult>) {
+    match brackets::cancel(id) {
+        Some(true) => ApiResult::ok_empty(),
+        Some(false) => ApiResult::err(StatusCode::CONFLICT, format!("Bracket #{} already finished", id)),
+        None => ApiResult::err(StatusCode::NOT_FOUND, format!("Bracket #{} not found", id)),
+    }
+}
@@ -0,0 +1,47 @@
+// src/routes/compile.rs
+
+use axum::{
+    routing::get,
+    extract::{State, Path, Query, Json},
+    http::StatusCode,
+    Router,
+};
+use serde::Deserialize;
+
+use crate::routes::{ApiResult, AppState};
+use crate::strategies::compile::CompileJob;
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/compile-jobs", get(list))
+        .route("/compile-jobs/:id", get(get_job))
+        .with_state(state)
+}
+
+#[derive(Deserialize)]
+struct JobQuery {
+    /// Отдать вывод cargo начиная с этой строки (output_offset + output.len()
+    /// из прошлого ответа) - для опроса прогресса без повторов
+    #[serde(default)]
+    since: usize,
+}
+
+/// Задания без вывода cargo
+async fn list(State(s): State<AppState>) -> Json<Vec<CompileJob>> {
+    Json(s.compiler.list())
+}
+
+async fn get_job(
+    State(s): State<AppState>,
+    Path(id): Path<u64>,
+    Query(q): Query<JobQuery>,
+) -> (StatusCode, Json<ApiResult<CompileJob>>) {
+    match s.compiler.get(id) {
+        Some(job) => ApiResult::ok(job.since(q.since)),
+        None => ApiResult::err(StatusCode::NOT_FOUND, format!("Compile job #{} not found", id)),
+    }
+}
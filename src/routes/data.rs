@@ -0,0 +1,43 @@
+// src/routes/data.rs
+
+use axum::{
+    routing::{get, post},
+    extract::{Path, Json},
+    http::StatusCode,
+    Router,
+};
+
+use crate::data::{self, DownloadJob, DownloadRequest};
+use crate::routes::{ApiResult, AppState};
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/data/download", post(download))
+        .route("/data/downloads", get(list))
+        .route("/data/downloads/:id", get(get_job))
+        .with_state(state)
+}
+
+/// Запускает загрузку архивов Binance Vision в каталог записей.
+/// Прогресс - GET /api/data/downloads/{id}
+async fn download(Json(req): Json<DownloadRequest>) -> (StatusCode, Json<ApiResult<DownloadJob>>) {
+    match data::start(req) {
+        Ok(job) => ApiResult::created(job),
+        Err(e) => ApiResult::err(StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
+
+async fn list() -> Json<Vec<DownloadJob>> {
+    Json(data::list())
+}
+
+async fn get_job(Path(id): Path<u64>) -> (StatusCode, Json<ApiResult<DownloadJob>>) {
+    match data::get(id) {
+        Some(job) => ApiResult::ok(job),
+        None => ApiResult::err(StatusCode::NOT_FOUND, format!("Download #{} not found", id)),
+    }
+}
@@ -0,0 +1,31 @@
+// src/routes/debug.rs
+
+use axum::{
+    http::StatusCode,
+    routing::get,
+    extract::Json,
+    Router,
+};
+
+use crate::raw_capture::{self, RawMessage};
+use crate::routes::{ApiResult, AppState};
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/debug/rawmessages", get(list).delete(clear))
+        .with_state(state)
+}
+
+/// Сообщения market data и user data, которые не удалось разобрать
+async fn list() -> Json<Vec<RawMessage>> {
+    Json(raw_capture::recent())
+}
+
+async fn clear() -> (StatusCode, Json<ApiResult>) {
+    raw_capture::clear();
+    ApiResult::ok_empty()
+}
@@ -0,0 +1,31 @@
+// src/routes/endpoints.rs
+
+use axum::{
+    routing::{get, post},
+    extract::Json,
+    Router,
+};
+
+use crate::endpoints::{self, EndpointStats};
+use crate::routes::AppState;
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/endpoints", get(list))
+        .route("/endpoints/probe", post(probe_now))
+        .with_state(state)
+}
+
+async fn list() -> Json<Vec<EndpointStats>> {
+    Json(endpoints::snapshot())
+}
+
+/// Внеочередной замер всех кандидатов
+async fn probe_now() -> Json<Vec<EndpointStats>> {
+    endpoints::probe_all().await;
+    Json(endpoints::snapshot())
+}
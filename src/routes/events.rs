@@ -0,0 +1,149 @@
+// src/routes/events.rs
+
+use axum::{
+    routing::get,
+    extract::{State, Query},
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    response::Response,
+    response::sse::{Event, KeepAlive, Sse},
+    Router,
+};
+use futures_util::stream::{self, Stream};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use tokio::sync::broadcast;
+
+use crate::ffi_types::CEvent;
+use crate::lifecycle::{self, LifecycleEvent};
+use crate::routes::AppState;
+
+// ═══════════════════════════════════════════════════════════
+// REQUESTS
+// ═══════════════════════════════════════════════════════════
+
+/// ?symbols=BTCUSDT,SOLUSDT&types=bookTicker,trade
+/// Пустой фильтр - все значения
+#[derive(Debug, Default, Deserialize)]
+pub struct EventsQuery {
+    pub symbols: Option<String>,
+    pub types: Option<String>,
+}
+
+struct EventFilter {
+    symbols: HashSet<String>,
+    types: HashSet<String>,
+}
+
+impl EventFilter {
+    fn new(q: &EventsQuery) -> Self {
+        let split = |s: &Option<String>, upper: bool| -> HashSet<String> {
+            s.as_deref()
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(|v| if upper { v.to_uppercase() } else { v.to_string() })
+                .collect()
+        };
+        Self {
+            symbols: split(&q.symbols, true),
+            types: split(&q.types, false),
+        }
+    }
+
+    fn matches(&self, event: &CEvent) -> bool {
+        (self.types.is_empty() || self.types.contains(event.type_name()))
+            && (self.symbols.is_empty() || self.symbols.contains(event.symbol_str()))
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/ws/events", get(events_ws))
+        .with_state(state)
+}
+
+/// Под /api: события жизненного цикла инстансов
+pub fn api_routes(state: AppState) -> Router {
+    Router::new()
+        .route("/events/sse", get(lifecycle_sse))
+        .with_state(state)
+}
+
+async fn events_ws(
+    State(s): State<AppState>,
+    Query(q): Query<EventsQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let rx = s.event_tx.subscribe();
+    let filter = EventFilter::new(&q);
+    ws.on_upgrade(move |socket| stream_events(socket, rx, filter))
+}
+
+async fn stream_events(mut socket: WebSocket, mut rx: broadcast::Receiver<CEvent>, filter: EventFilter) {
+    tracing::info!(
+        "📡 Events stream opened (symbols={:?}, types={:?})",
+        filter.symbols, filter.types
+    );
+
+    loop {
+        tokio::select! {
+            msg = rx.recv() => match msg {
+                Ok(event) if filter.matches(&event) => {
+                    let text = event.as_json().to_string();
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                // Медленный клиент не должен тормозить ядро - пропускаем
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("📡 Events stream lagged {} events", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    tracing::info!("📡 Events stream closed");
+}
+
+// ═══════════════════════════════════════════════════════════
+// LIFECYCLE (SSE)
+// ═══════════════════════════════════════════════════════════
+
+/// started / stopped / crashed / restarted / compile_finished.
+/// Имя SSE-события = kind, data = LifecycleEvent в JSON
+async fn lifecycle_sse() -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let rx = lifecycle::subscribe();
+
+    let events = stream::unfold(rx, |mut rx| async move {
+        loop {
+            match rx.recv().await {
+                Ok(event) => return Some((Ok(to_sse(&event)), rx)),
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("🔄 Lifecycle SSE lagged {} events", n);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    });
+
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+fn to_sse(event: &LifecycleEvent) -> Event {
+    Event::default()
+        .event(event.kind.name())
+        .data(serde_json::to_string(event).unwrap_or_default())
+}
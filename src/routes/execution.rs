@@ -0,0 +1,71 @@
+// src/routes/execution.rs
+
+use axum::{
+    http::StatusCode,
+    routing::get,
+    extract::{Json, Path, Query},
+    Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::execution::{self, AlgoInfo, AlgoParams, ERR_ALGO_LIMIT};
+use crate::keystore;
+use crate::routes::{ApiResult, AppState};
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/execute", get(list).post(start))
+        .route("/execute/:id", get(get_algo).delete(cancel))
+        .with_state(state)
+}
+
+#[derive(Deserialize)]
+struct ExecuteRequest {
+    /// Alias ключей из keystore
+    account: String,
+    #[serde(flatten)]
+    params: AlgoParams,
+}
+
+#[derive(Deserialize)]
+struct ListQuery {
+    instance: Option<String>,
+}
+
+/// Родительский ордер: TWAP или iceberg
+async fn start(Json(req): Json<ExecuteRequest>) -> (StatusCode, Json<ApiResult<AlgoInfo>>) {
+    let Some(creds) = keystore::account(&req.account) else {
+        return ApiResult::err(StatusCode::NOT_FOUND, format!("Unknown account '{}'", req.account));
+    };
+    match execution::start(Arc::new(creds), None, req.params) {
+        Ok(info) => ApiResult::created(info),
+        Err(reject) if reject.code == ERR_ALGO_LIMIT => ApiResult::err(StatusCode::TOO_MANY_REQUESTS, reject.message),
+        Err(reject) => ApiResult::err(StatusCode::BAD_REQUEST, reject.message),
+    }
+}
+
+/// Алгоритмы, новые первыми (?instance= - только этого инстанса)
+async fn list(Query(q): Query<ListQuery>) -> Json<Vec<AlgoInfo>> {
+    Json(execution::list(q.instance.as_deref()))
+}
+
+async fn get_algo(Path(id): Path<u64>) -> (StatusCode, Json<ApiResult<AlgoInfo>>) {
+    match execution::get(id) {
+        Some(info) => ApiResult::ok(info),
+        None => ApiResult::err(StatusCode::NOT_FOUND, format!("Algo #{} not found", id)),
+    }
+}
+
+/// Останавливает алгоритм и снимает его стоящие дочерние ордера
+async fn cancel(Path(id): Path<u64>) -> (StatusCode, Json<ApiResult>) {
+    match execution::cancel(id) {
+        Some(true) => ApiResult::ok_empty(),
+        Some(false) => ApiResult::err(StatusCode::CONFLICT, format!("Algo #{} already finished", id)),
+        None => ApiResult::err(StatusCode::NOT_FOUND, format!("Algo #{} not found", id)),
+    }
+}
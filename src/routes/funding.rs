@@ -0,0 +1,61 @@
+// src/routes/funding.rs
+
+use axum::{
+    http::StatusCode,
+    routing::get,
+    extract::{Json, Query},
+    Router,
+};
+use serde::Deserialize;
+
+use crate::funding::{self, FundingInfo, FundingRate};
+use crate::routes::{ApiResult, AppState};
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/funding", get(list))
+        .route("/funding/next", get(next))
+        .route("/funding/rates", get(rates))
+        .with_state(state)
+}
+
+#[derive(Deserialize)]
+struct NextQuery {
+    symbol: String,
+}
+
+#[derive(Deserialize)]
+struct RatesQuery {
+    /// Первые N по |ставке|
+    limit: Option<usize>,
+    /// Только |ставка| >= min_abs (0.0005 = 0.05%)
+    min_abs: Option<f64>,
+}
+
+/// Календарь по всем символам, ближайшие funding первыми
+async fn list() -> Json<Vec<FundingInfo>> {
+    Json(funding::list())
+}
+
+/// Ближайший funding по символу (время биржи)
+async fn next(Query(q): Query<NextQuery>) -> (StatusCode, Json<ApiResult<FundingInfo>>) {
+    match funding::next(&q.symbol, chrono::Utc::now().timestamp_millis()) {
+        Some(info) => ApiResult::ok(info),
+        None => ApiResult::err(StatusCode::NOT_FOUND, format!("No funding time for '{}' yet", q.symbol.to_uppercase())),
+    }
+}
+
+/// USDT-M перпетуалы по убыванию |ставки| (прогноз на ближайший funding)
+async fn rates(Query(q): Query<RatesQuery>) -> Json<Vec<FundingRate>> {
+    let min_abs = q.min_abs.unwrap_or(0.0);
+    let rates = funding::rates()
+        .into_iter()
+        .filter(|r| r.info.funding_rate.is_some_and(|rate| rate.abs() >= min_abs))
+        .take(q.limit.unwrap_or(usize::MAX))
+        .collect();
+    Json(rates)
+}
@@ -0,0 +1,228 @@
+// src/routes/groups.rs
+
+use axum::{
+    http::StatusCode,
+    routing::{get, post},
+    extract::{Json, Path, State},
+    Router,
+};
+use serde::Serialize;
+
+use crate::routes::strategy::compiled_lib;
+use crate::routes::{ApiResult, AppState};
+use crate::strategies::groups;
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/groups", get(list))
+        .route("/groups/:name", get(get_group))
+        .route("/groups/:name/stop", post(stop))
+        .route("/groups/:name/pause", post(pause))
+        .route("/groups/:name/start", post(start))
+        .with_state(state)
+}
+
+// ═══════════════════════════════════════════════════════════
+// TYPES
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Serialize)]
+pub struct GroupMember {
+    pub instance_id: String,
+    pub strategy_id: String,
+    pub symbol: String,
+    pub running: bool,
+    pub trading_paused: bool,
+}
+
+/// Сумма по работающим участникам группы
+#[derive(Debug, Serialize)]
+pub struct GroupStats {
+    pub name: String,
+    pub members: usize,
+    pub running: usize,
+    pub paused: usize,
+    pub orders_placed: u64,
+    pub orders_filled: u64,
+    pub orders_rejected: u64,
+    /// Событий, потерянных при переполнении каналов
+    pub events_dropped: u64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub fees: f64,
+    pub net_pnl: f64,
+    pub instances: Vec<GroupMember>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GroupFailure {
+    pub instance_id: String,
+    pub error: String,
+}
+
+/// Итог массовой операции: что сделано и что не вышло
+#[derive(Debug, Serialize)]
+pub struct GroupActionResult {
+    pub group: String,
+    pub done: Vec<String>,
+    pub failed: Vec<GroupFailure>,
+}
+
+impl GroupActionResult {
+    fn new(group: &str) -> Self {
+        Self { group: group.to_string(), done: Vec::new(), failed: Vec::new() }
+    }
+
+    fn record(&mut self, instance_id: String, result: anyhow::Result<()>) {
+        match result {
+            Ok(()) => self.done.push(instance_id),
+            Err(e) => self.failed.push(GroupFailure { instance_id, error: e.to_string() }),
+        }
+    }
+}
+
+fn stats(s: &AppState, name: &str) -> Option<GroupStats> {
+    let members = groups::members(name);
+    if members.is_empty() {
+        return None;
+    }
+    let mut out = GroupStats {
+        name: name.to_string(),
+        members: members.len(),
+        running: 0,
+        paused: 0,
+        orders_placed: 0,
+        orders_filled: 0,
+        orders_rejected: 0,
+        events_dropped: 0,
+        realized_pnl: 0.0,
+        unrealized_pnl: 0.0,
+        fees: 0.0,
+        net_pnl: 0.0,
+        instances: Vec::with_capacity(members.len()),
+    };
+    for (instance_id, member) in members {
+        let info = s.runner.get(&instance_id);
+        let trading_paused = info.as_ref().is_some_and(|i| i.breaker.trading_paused);
+        if let Some(info) = &info {
+            out.running += 1;
+            out.paused += usize::from(trading_paused);
+            out.orders_placed += info.stats.orders_placed;
+            out.orders_filled += info.stats.orders_filled;
+            out.orders_rejected += info.stats.orders_rejected;
+            out.events_dropped += info.channel.dropped;
+        }
+        // PnL остаётся и после остановки участника
+        if let Some(pnl) = s.pnl.instance(&instance_id) {
+            out.realized_pnl += pnl.realized_pnl;
+            out.unrealized_pnl += pnl.unrealized_pnl;
+            out.fees += pnl.fees;
+            out.net_pnl += pnl.net_pnl;
+        }
+        out.instances.push(GroupMember {
+            instance_id,
+            strategy_id: member.strategy_id,
+            symbol: member.symbol,
+            running: info.is_some(),
+            trading_paused,
+        });
+    }
+    Some(out)
+}
+
+// ═══════════════════════════════════════════════════════════
+// HANDLERS
+// ═══════════════════════════════════════════════════════════
+
+async fn list(State(s): State<AppState>) -> Json<Vec<GroupStats>> {
+    Json(groups::names().iter().filter_map(|name| stats(&s, name)).collect())
+}
+
+async fn get_group(
+    State(s): State<AppState>,
+    Path(name): Path<String>,
+) -> (StatusCode, Json<ApiResult<GroupStats>>) {
+    match stats(&s, &name) {
+        Some(stats) => ApiResult::ok(stats),
+        None => ApiResult::err(StatusCode::NOT_FOUND, format!("Group '{}' not found", name)),
+    }
+}
+
+/// Останавливает всех работающих участников параллельно. Группа остаётся:
+/// её можно запустить обратно через start
+async fn stop(
+    State(s): State<AppState>,
+    Path(name): Path<String>,
+) -> (StatusCode, Json<ApiResult<GroupActionResult>>) {
+    let members = groups::members(&name);
+    if members.is_empty() {
+        return ApiResult::err(StatusCode::NOT_FOUND, format!("Group '{}' not found", name));
+    }
+    let running: Vec<String> = members.into_keys().filter(|id| s.runner.get(id).is_some()).collect();
+    tracing::info!("🛑 Stopping group '{}': {} instances", name, running.len());
+
+    let results = futures_util::future::join_all(running.iter().map(|id| s.runner.stop(id))).await;
+    let mut out = GroupActionResult::new(&name);
+    for (id, result) in running.into_iter().zip(results) {
+        out.record(id, result);
+    }
+    ApiResult::ok(out)
+}
+
+/// Ставит торговлю участников на паузу: стратегии работают и получают
+/// события, но новые ордера отклоняются (отмены проходят)
+async fn pause(
+    State(s): State<AppState>,
+    Path(name): Path<String>,
+) -> (StatusCode, Json<ApiResult<GroupActionResult>>) {
+    let members = groups::members(&name);
+    if members.is_empty() {
+        return ApiResult::err(StatusCode::NOT_FOUND, format!("Group '{}' not found", name));
+    }
+    let reason = format!("paused with group '{}'", name);
+    let mut out = GroupActionResult::new(&name);
+    for id in members.into_keys().filter(|id| s.runner.get(id).is_some()) {
+        let result = s.runner.pause_trading(&id, &reason);
+        out.record(id, result);
+    }
+    ApiResult::ok(out)
+}
+
+/// Снимает паузу с работающих участников и запускает остановленных
+/// с теми же параметрами и опциями
+async fn start(
+    State(s): State<AppState>,
+    Path(name): Path<String>,
+) -> (StatusCode, Json<ApiResult<GroupActionResult>>) {
+    let members = groups::members(&name);
+    if members.is_empty() {
+        return ApiResult::err(StatusCode::NOT_FOUND, format!("Group '{}' not found", name));
+    }
+    let mut out = GroupActionResult::new(&name);
+    for (id, member) in members {
+        if let Some(info) = s.runner.get(&id) {
+            if info.breaker.trading_paused {
+                let result = s.runner.resume_trading(&id);
+                out.record(id, result);
+            }
+            continue;
+        }
+        let lib_path = match compiled_lib(&s, &member.strategy_id).await {
+            Ok(path) => path,
+            Err((_, e)) => {
+                out.record(id, Err(anyhow::anyhow!(e)));
+                continue;
+            }
+        };
+        let result = s.runner
+            .start(member.strategy_id, member.symbol, lib_path, member.params.into_inner(), member.options)
+            .await
+            .map(|_| ());
+        out.record(id, result);
+    }
+    ApiResult::ok(out)
+}
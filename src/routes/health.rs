@@ -0,0 +1,36 @@
+// src/routes/health.rs
+
+use axum::{extract::Json, routing::get, Router};
+use serde::Serialize;
+
+use crate::latency::{self, DegradedStage};
+use crate::routes::AppState;
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .with_state(state)
+}
+
+#[derive(Serialize)]
+struct Health {
+    /// "ok" | "degraded"
+    status: &'static str,
+    /// Стадии latency за пределами бюджета (latency.budgets_ms)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    degraded: Vec<DegradedStage>,
+}
+
+/// Ядро живо. degraded - данные или ответы биржи приходят медленнее
+/// бюджета: стратегиям, которым важна свежесть, лучше не торговать
+async fn healthz() -> Json<Health> {
+    let degraded = latency::degraded();
+    Json(Health {
+        status: if degraded.is_empty() { "ok" } else { "degraded" },
+        degraded,
+    })
+}
@@ -0,0 +1,55 @@
+// src/routes/history.rs
+
+use axum::{
+    http::StatusCode,
+    routing::get,
+    extract::{Json, State, Query},
+    Router,
+};
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::history::{FillRow, FundingRow, History, HistoryQuery};
+use crate::routes::{ApiResult, AppState};
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/history/fills", get(fills))
+        .route("/history/funding", get(funding))
+        .with_state(state)
+}
+
+async fn fills(
+    State(s): State<AppState>,
+    Query(q): Query<HistoryQuery>,
+) -> (StatusCode, Json<ApiResult<Vec<FillRow>>>) {
+    query(s.history, move |h| h.fills(&q)).await
+}
+
+async fn funding(
+    State(s): State<AppState>,
+    Query(q): Query<HistoryQuery>,
+) -> (StatusCode, Json<ApiResult<Vec<FundingRow>>>) {
+    query(s.history, move |h| h.funding(&q)).await
+}
+
+/// SQLite блокирующий - запросы выполняем вне async runtime
+async fn query<T, F>(history: Option<Arc<History>>, f: F) -> (StatusCode, Json<ApiResult<T>>)
+where
+    T: Serialize + Send + 'static,
+    F: FnOnce(&History) -> anyhow::Result<T> + Send + 'static,
+{
+    let Some(history) = history else {
+        return ApiResult::err(StatusCode::SERVICE_UNAVAILABLE, "History is disabled");
+    };
+
+    match tokio::task::spawn_blocking(move || f(&history)).await {
+        Ok(Ok(rows)) => ApiResult::ok(rows),
+        Ok(Err(e)) => ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        Err(e) => ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
@@ -0,0 +1,73 @@
+// src/routes/keys.rs
+
+use axum::{
+    http::StatusCode,
+    routing::{get, post, delete},
+    extract::{Json, State, Path},
+    Router,
+};
+use serde::Deserialize;
+
+use crate::keystore::{Credentials, KeyInfo};
+use crate::routes::{ApiResult, AppState};
+
+/// Ответ, когда мастер-ключ не настроен
+const DISABLED: &str = "Keystore is disabled: set HFT_MASTER_KEY or keystore.master_key_path";
+
+// ═══════════════════════════════════════════════════════════
+// REQUESTS
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Deserialize)]
+pub struct PutKeyRequest {
+    pub alias: String,
+    pub api_key: String,
+    pub secret_key: String,
+}
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/keys", get(list))
+        .route("/keys", post(put))
+        .route("/keys/:alias", delete(remove))
+        .with_state(state)
+}
+
+/// Только алиасы и первые 8 символов api_key - секреты наружу не отдаются
+async fn list(State(s): State<AppState>) -> Json<Vec<KeyInfo>> {
+    Json(s.keystore.as_ref().map(|k| k.list()).unwrap_or_default())
+}
+
+async fn put(
+    State(s): State<AppState>,
+    Json(req): Json<PutKeyRequest>,
+) -> (StatusCode, Json<ApiResult<KeyInfo>>) {
+    let credentials = Credentials {
+        api_key: req.api_key,
+        secret_key: req.secret_key,
+    };
+    let Some(keystore) = &s.keystore else {
+        return ApiResult::err(StatusCode::SERVICE_UNAVAILABLE, DISABLED);
+    };
+    match keystore.put(&req.alias, credentials) {
+        Ok(info) => ApiResult::created(info),
+        Err(e) => ApiResult::err(StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
+
+async fn remove(
+    State(s): State<AppState>,
+    Path(alias): Path<String>,
+) -> (StatusCode, Json<ApiResult>) {
+    let Some(keystore) = &s.keystore else {
+        return ApiResult::err(StatusCode::SERVICE_UNAVAILABLE, DISABLED);
+    };
+    match keystore.remove(&alias) {
+        Ok(_) => ApiResult::ok_empty(),
+        Err(e) => ApiResult::err(StatusCode::NOT_FOUND, e.to_string()),
+    }
+}
@@ -0,0 +1,70 @@
+// src/routes/kv.rs
+
+use axum::{
+    http::StatusCode,
+    routing::{delete, get},
+    extract::{Json, Path},
+    Router,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use crate::kv::{self, KvScope};
+use crate::routes::{ApiResult, AppState};
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/kv", get(list))
+        .route("/kv/:strategy_id", get(entries).delete(clear))
+        .route("/kv/:strategy_id/:key", delete(remove).put(put))
+        .with_state(state)
+}
+
+#[derive(Deserialize)]
+struct PutRequest {
+    value: String,
+}
+
+/// Стратегии с сохранённым состоянием
+async fn list() -> Json<Vec<KvScope>> {
+    Json(kv::list())
+}
+
+async fn entries(Path(strategy_id): Path<String>) -> (StatusCode, Json<ApiResult<BTreeMap<String, String>>>) {
+    match kv::entries(&strategy_id) {
+        Some(entries) => ApiResult::ok(entries),
+        None => ApiResult::err(StatusCode::NOT_FOUND, format!("No state for strategy '{}'", strategy_id)),
+    }
+}
+
+/// Правка состояния вручную (например, перед запуском). Работающий инстанс
+/// увидит новое значение при следующем kv_get
+async fn put(
+    Path((strategy_id, key)): Path<(String, String)>,
+    Json(req): Json<PutRequest>,
+) -> (StatusCode, Json<ApiResult>) {
+    match kv::put(&strategy_id, &key, &req.value) {
+        Ok(_) => ApiResult::ok_empty(),
+        Err(e) => ApiResult::err(StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
+
+async fn remove(Path((strategy_id, key)): Path<(String, String)>) -> (StatusCode, Json<ApiResult>) {
+    if kv::remove(&strategy_id, &key) {
+        ApiResult::ok_empty()
+    } else {
+        ApiResult::err(StatusCode::NOT_FOUND, format!("Key '{}' not found", key))
+    }
+}
+
+async fn clear(Path(strategy_id): Path<String>) -> (StatusCode, Json<ApiResult>) {
+    if kv::clear(&strategy_id) {
+        ApiResult::ok_empty()
+    } else {
+        ApiResult::err(StatusCode::NOT_FOUND, format!("No state for strategy '{}'", strategy_id))
+    }
+}
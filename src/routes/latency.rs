@@ -0,0 +1,37 @@
+// src/routes/latency.rs
+
+use axum::{
+    routing::{get, delete},
+    extract::Json,
+    http::StatusCode,
+    Router,
+};
+
+use crate::latency::{self, StageStats, TradeMethodStats};
+use crate::routes::{ApiResult, AppState};
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/latency", get(stats))
+        .route("/latency", delete(reset))
+        .route("/latency/trade", get(trade))
+        .with_state(state)
+}
+
+async fn stats() -> Json<Vec<StageStats>> {
+    Json(latency::snapshot())
+}
+
+/// Round trip trade WS по методам: запись в сокет -> ответ
+async fn trade() -> Json<Vec<TradeMethodStats>> {
+    Json(latency::trade_snapshot())
+}
+
+async fn reset() -> (StatusCode, Json<ApiResult>) {
+    latency::reset();
+    ApiResult::ok_empty()
+}
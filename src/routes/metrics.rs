@@ -0,0 +1,21 @@
+// src/routes/metrics.rs
+
+use axum::{http::header, response::IntoResponse, routing::get, Router};
+
+use crate::routes::AppState;
+use crate::strategies::metrics;
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/metrics", get(scrape))
+        .with_state(state)
+}
+
+/// Prometheus text format: счётчики инстансов и метрики стратегий (emit_metric)
+async fn scrape() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], metrics::render())
+}
@@ -0,0 +1,59 @@
+// src/routes/orders.rs
+
+use axum::{
+    http::StatusCode,
+    routing::get,
+    extract::{Json, State, Path, Query},
+    Router,
+};
+use serde::Deserialize;
+
+use crate::orders::{OrderFilter, OrderRecord};
+use crate::routes::{ApiResult, AppState};
+
+// ═══════════════════════════════════════════════════════════
+// REQUESTS
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Deserialize)]
+pub struct OrdersQuery {
+    #[serde(default)]
+    pub instance: Option<String>,
+    #[serde(default)]
+    pub symbol: Option<String>,
+    /// Только ордера в стакане (без завершённых)
+    #[serde(default)]
+    pub open: bool,
+}
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/orders", get(list_orders))
+        .route("/orders/:client_order_id", get(get_order))
+        .with_state(state)
+}
+
+async fn list_orders(
+    State(s): State<AppState>,
+    Query(q): Query<OrdersQuery>,
+) -> Json<Vec<OrderRecord>> {
+    let filter = OrderFilter {
+        instance_id: q.instance.as_deref(),
+        symbol: q.symbol.as_deref(),
+        open_only: q.open,
+    };
+    Json(s.orders.list(&filter))
+}
+
+async fn get_order(
+    State(s): State<AppState>,
+    Path(client_order_id): Path<String>,
+) -> Result<Json<OrderRecord>, (StatusCode, Json<ApiResult<OrderRecord>>)> {
+    s.orders.get(&client_order_id)
+        .map(Json)
+        .ok_or_else(|| ApiResult::<OrderRecord>::err(StatusCode::NOT_FOUND, "Not found"))
+}
@@ -0,0 +1,207 @@
+// src/routes/ping.rs
+
+use axum::{
+    routing::post,
+    extract::{State, Json},
+    http::StatusCode,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::exchange_trade::{Command, ExchangeTrade, WireTimings, WireTrace};
+use crate::ffi_types::EVENT_BOOK_TICKER;
+use crate::keystore;
+use crate::routes::{ApiResult, AppState};
+
+/// Сколько ждём bookTicker и ответ биржи
+const PRICE_TIMEOUT: Duration = Duration::from_secs(5);
+const ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/ping/order", post(ping_order))
+        .with_state(state)
+}
+
+// ═══════════════════════════════════════════════════════════
+// ЗАПРОС / ОТВЕТ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Deserialize)]
+struct PingOrderRequest {
+    symbol: String,
+    /// Alias из keystore
+    account: String,
+    quantity: f64,
+    /// Насколько ниже bid ставим BUY: 0.5 - половина цены, не исполнится
+    #[serde(default = "default_price_offset")]
+    price_offset: f64,
+    /// Шаг цены символа, к нему округляется тестовая цена
+    #[serde(default = "default_tick_size")]
+    tick_size: f64,
+}
+
+fn default_price_offset() -> f64 {
+    0.5
+}
+
+fn default_tick_size() -> f64 {
+    0.01
+}
+
+#[derive(Serialize)]
+struct RoundTrip {
+    /// send_command -> callback с ответом
+    total_ms: f64,
+    #[serde(flatten)]
+    stages: WireTimings,
+}
+
+#[derive(Serialize)]
+struct PingOrderResult {
+    symbol: String,
+    order_id: i64,
+    current_bid: f64,
+    test_price: f64,
+    place: RoundTrip,
+    cancel: Option<RoundTrip>,
+}
+
+// ═══════════════════════════════════════════════════════════
+// HANDLER
+// ═══════════════════════════════════════════════════════════
+
+/// Ставит лимитный BUY далеко от рынка и сразу отменяет его,
+/// меряя каждую стадию обоих запросов
+async fn ping_order(
+    State(s): State<AppState>,
+    Json(req): Json<PingOrderRequest>,
+) -> (StatusCode, Json<ApiResult<PingOrderResult>>) {
+    if !(req.price_offset > 0.0 && req.price_offset < 1.0) {
+        return ApiResult::err(StatusCode::BAD_REQUEST, "price_offset must be in (0, 1)");
+    }
+    if !(req.quantity > 0.0 && req.tick_size > 0.0) {
+        return ApiResult::err(StatusCode::BAD_REQUEST, "quantity and tick_size must be > 0");
+    }
+    let Some(creds) = keystore::account(&req.account) else {
+        return ApiResult::err(StatusCode::NOT_FOUND, format!("Unknown account '{}'", req.account));
+    };
+    let symbol = req.symbol.to_uppercase();
+
+    tracing::info!("🏓 Starting order ping test on {}...", symbol);
+
+    // 1. Текущий bid из потока bookTicker
+    let Some(current_bid) = wait_bid(&s, &symbol).await else {
+        return ApiResult::err(
+            StatusCode::REQUEST_TIMEOUT,
+            format!("No {} bookTicker data. Subscribe first.", symbol),
+        );
+    };
+
+    // 2. Безопасная цена далеко под рынком
+    let test_price = ((current_bid * (1.0 - req.price_offset)) / req.tick_size).round() * req.tick_size;
+    if test_price <= 0.0 {
+        return ApiResult::err(StatusCode::BAD_REQUEST, "Test price rounds to zero, lower price_offset");
+    }
+    tracing::info!("Bid: {:.8}, Test price: {:.8}", current_bid, test_price);
+
+    // 3. Выставляем ордер
+    let place_cmd = Command::SendLimitOrder {
+        api_key: creds.api_key.clone(),
+        secret_key: creds.secret_key.clone(),
+        symbol: symbol.clone(),
+        price: test_price,
+        qty: req.quantity,
+        side: "BUY".to_string(),
+        client_order_id: None,
+    };
+    let (response, place) = round_trip(&s.trade, place_cmd).await;
+    let Some(response) = response else {
+        return ApiResult::err(StatusCode::REQUEST_TIMEOUT, "Order placement timed out");
+    };
+    if response.get("error").is_some() {
+        return ApiResult::err(StatusCode::BAD_GATEWAY, format!("Order failed: {}", response));
+    }
+    let Some(order_id) = response["result"]["orderId"].as_i64() else {
+        return ApiResult::err(StatusCode::BAD_GATEWAY, format!("No orderId in response: {}", response));
+    };
+    tracing::info!("✅ Order placed in {:.2}ms | ID: {}", place.total_ms, order_id);
+
+    // 4. Отменяем ордер
+    let cancel_cmd = Command::CancelLimitOrder {
+        api_key: creds.api_key,
+        secret_key: creds.secret_key,
+        symbol: symbol.clone(),
+        order_id: order_id.to_string(),
+    };
+    let (response, cancel) = round_trip(&s.trade, cancel_cmd).await;
+    let cancel = match response {
+        Some(_) => {
+            tracing::info!("✅ Cancelled in {:.2}ms", cancel.total_ms);
+            Some(cancel)
+        }
+        None => {
+            tracing::warn!("⚠️ Ping order {} cancel timed out, check open orders", order_id);
+            None
+        }
+    };
+
+    ApiResult::ok(PingOrderResult {
+        symbol,
+        order_id,
+        current_bid,
+        test_price,
+        place,
+        cancel,
+    })
+}
+
+async fn wait_bid(s: &AppState, symbol: &str) -> Option<f64> {
+    let mut rx = s.event_tx.subscribe();
+    let deadline = tokio::time::Instant::now() + PRICE_TIMEOUT;
+    loop {
+        let event = tokio::time::timeout_at(deadline, rx.recv()).await.ok()?;
+        let event = match event {
+            Ok(e) => e,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(_) => return None,
+        };
+        if event.event_type != EVENT_BOOK_TICKER {
+            continue;
+        }
+        let bt = unsafe { &event.data.book_ticker };
+        if bt.symbol_str().eq_ignore_ascii_case(symbol) {
+            return Some(bt.bid_price);
+        }
+    }
+}
+
+/// Отправляет команду с трассировкой и ждёт ответ биржи
+async fn round_trip(trade: &ExchangeTrade, cmd: Command) -> (Option<Value>, RoundTrip) {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let tx = Mutex::new(Some(tx));
+    let trace = Arc::new(WireTrace::default());
+    let started = Instant::now();
+
+    trade
+        .send_command_traced(cmd, trace.clone(), move |resp: Value| {
+            if let Some(sender) = tx.lock().unwrap().take() {
+                let _ = sender.send(resp);
+            }
+        })
+        .await;
+
+    let response = tokio::time::timeout(ACK_TIMEOUT, rx).await.ok().and_then(Result::ok);
+    let result = RoundTrip {
+        total_ms: started.elapsed().as_secs_f64() * 1000.0,
+        stages: trace.timings(),
+    };
+    (response, result)
+}
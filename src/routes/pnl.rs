@@ -0,0 +1,161 @@
+// src/routes/pnl.rs
+
+use axum::{
+    http::StatusCode,
+    routing::get,
+    extract::{Json, State, Path, Query},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+use crate::history::RealizedRow;
+use crate::pnl::PnlSummary;
+use crate::routes::{ApiResult, AppState};
+
+// ═══════════════════════════════════════════════════════════
+// REQUESTS / RESPONSES
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Deserialize)]
+pub struct PnlQuery {
+    /// Unix ms, включительно
+    #[serde(default)]
+    pub from: Option<i64>,
+    /// Unix ms, не включительно
+    #[serde(default)]
+    pub to: Option<i64>,
+}
+
+#[derive(Serialize)]
+pub struct PnlReport {
+    /// "live" - с момента запуска ядра, "history" - из SQLite за период
+    pub source: &'static str,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    pub instances: Vec<PnlSummary>,
+    pub keys: Vec<PnlSummary>,
+}
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/pnl", get(report))
+        .route("/instances/:instance_id/pnl", get(instance_pnl))
+        .with_state(state)
+}
+
+async fn instance_pnl(
+    State(s): State<AppState>,
+    Path(instance_id): Path<String>,
+) -> (StatusCode, Json<ApiResult<PnlSummary>>) {
+    match s.pnl.instance(&instance_id) {
+        Some(pnl) => ApiResult::ok(pnl),
+        None if s.runner.get(&instance_id).is_some() => {
+            // Инстанс работает, но ещё ничего не исполнил
+            ApiResult::ok(PnlSummary { id: instance_id, ..Default::default() })
+        }
+        None => ApiResult::err(StatusCode::NOT_FOUND, "No PnL for instance"),
+    }
+}
+
+async fn report(
+    State(s): State<AppState>,
+    Query(q): Query<PnlQuery>,
+) -> (StatusCode, Json<ApiResult<PnlReport>>) {
+    if q.from.is_none() && q.to.is_none() {
+        return ApiResult::ok(PnlReport {
+            source: "live",
+            from: None,
+            to: None,
+            instances: s.pnl.instances(),
+            keys: s.pnl.keys(),
+        });
+    }
+
+    let Some(history) = s.history.clone() else {
+        return ApiResult::err(StatusCode::SERVICE_UNAVAILABLE, "History is disabled, from/to not supported");
+    };
+
+    let from = q.from.unwrap_or(0);
+    let to = q.to.unwrap_or(i64::MAX);
+
+    let rows = tokio::task::spawn_blocking(move || -> anyhow::Result<_> {
+        Ok((
+            history.realized(from, to, true)?,
+            history.realized(from, to, false)?,
+            history.funding_by_key(from, to)?,
+        ))
+    })
+    .await;
+
+    let (by_instance, by_key, funding) = match rows {
+        Ok(Ok(rows)) => rows,
+        Ok(Err(e)) => return ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        Err(e) => return ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+
+    let funding: BTreeMap<String, f64> = funding.into_iter().collect();
+
+    let instances = merge(by_instance, s.pnl.unrealized_by_instance(), &BTreeMap::new());
+    let keys = merge(by_key, s.pnl.unrealized_by_key(), &funding);
+
+    ApiResult::ok(PnlReport {
+        source: "history",
+        from: q.from,
+        to: q.to,
+        instances,
+        keys,
+    })
+}
+
+/// Realized из истории + текущий unrealized
+fn merge(
+    realized: Vec<RealizedRow>,
+    mut unrealized: BTreeMap<String, f64>,
+    funding: &BTreeMap<String, f64>,
+) -> Vec<PnlSummary> {
+    let mut out: Vec<PnlSummary> = realized
+        .into_iter()
+        .map(|r| {
+            let unrealized_pnl = unrealized.remove(&r.id).unwrap_or(0.0);
+            let funding = funding.get(&r.id).copied();
+            PnlSummary {
+                net_pnl: r.realized_pnl - r.fees + funding.unwrap_or(0.0) + unrealized_pnl,
+                id: r.id,
+                realized_pnl: r.realized_pnl,
+                fees: r.fees,
+                funding,
+                unrealized_pnl,
+                fills: r.fills,
+                symbols: Vec::new(),
+            }
+        })
+        .collect();
+
+    // Открытые позиции без fills за период
+    out.extend(unrealized.into_iter().map(|(id, unrealized_pnl)| {
+        let funding = funding.get(&id).copied();
+        PnlSummary {
+            net_pnl: unrealized_pnl + funding.unwrap_or(0.0),
+            id,
+            funding,
+            unrealized_pnl,
+            ..Default::default()
+        }
+    }));
+
+    // Только funding: позиция закрыта до периода, а выплаты в нём были
+    let seen: HashSet<String> = out.iter().map(|s| s.id.clone()).collect();
+    out.extend(funding.iter().filter(|(id, _)| !seen.contains(*id)).map(|(id, &funding)| PnlSummary {
+        net_pnl: funding,
+        id: id.clone(),
+        funding: Some(funding),
+        ..Default::default()
+    }));
+
+    out
+}
@@ -0,0 +1,77 @@
+// src/routes/positions.rs
+
+use axum::{
+    http::StatusCode,
+    routing::{get, post},
+    extract::{Json, Query},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::keystore;
+use crate::positions::{self, PositionInfo};
+use crate::routes::{ApiResult, AppState};
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/positions", get(list))
+        .route("/positions/close", post(close))
+        .with_state(state)
+}
+
+#[derive(Deserialize)]
+struct ListQuery {
+    /// Alias ключей из keystore
+    account: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CloseRequest {
+    account: String,
+    symbol: String,
+}
+
+#[derive(Serialize)]
+struct CloseResponse {
+    symbol: String,
+    /// Размер отправленного reduce-only market
+    quantity: f64,
+}
+
+/// Ненулевые позиции по ключам с запущенным user data stream
+async fn list(Query(q): Query<ListQuery>) -> (StatusCode, Json<ApiResult<Vec<PositionInfo>>>) {
+    let api_key = match q.account.as_deref() {
+        Some(account) => match keystore::account(account) {
+            Some(creds) => Some(creds.api_key),
+            None => return ApiResult::err(StatusCode::NOT_FOUND, format!("Unknown account '{}'", account)),
+        },
+        None => None,
+    };
+    ApiResult::ok(positions::list(api_key.as_deref()))
+}
+
+/// Закрывает позицию reduce-only market-ордером на её размер
+async fn close(Json(req): Json<CloseRequest>) -> (StatusCode, Json<ApiResult<CloseResponse>>) {
+    let Some(creds) = keystore::account(&req.account) else {
+        return ApiResult::err(StatusCode::NOT_FOUND, format!("Unknown account '{}'", req.account));
+    };
+    let symbol = req.symbol.to_uppercase();
+    let api_key = creds.api_key.clone();
+    let quantity = positions::close(Arc::new(creds), None, &symbol, |result| {
+        if !result.success {
+            tracing::warn!("📐 Position close rejected ({})", result.error_code);
+        }
+    });
+    if quantity == 0.0 && positions::get(&api_key, &symbol).is_none() {
+        return ApiResult::err(StatusCode::SERVICE_UNAVAILABLE, format!("{} position is not known yet, retry", symbol));
+    }
+    if quantity == 0.0 {
+        return ApiResult::err(StatusCode::CONFLICT, format!("No open {} position", symbol));
+    }
+    ApiResult::ok(CloseResponse { symbol, quantity })
+}
@@ -0,0 +1,24 @@
+// src/routes/rate_limit.rs
+
+use axum::{
+    routing::get,
+    extract::{Json, State},
+    Router,
+};
+
+use crate::rate_limit::RateLimitStatus;
+use crate::routes::AppState;
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/ratelimits", get(status))
+        .with_state(state)
+}
+
+async fn status(State(s): State<AppState>) -> Json<RateLimitStatus> {
+    Json(s.rate_limiter.status())
+}
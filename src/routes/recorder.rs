@@ -0,0 +1,84 @@
+// src/routes/recorder.rs
+
+use axum::{
+    routing::{get, post},
+    extract::{State, Json},
+    http::StatusCode,
+    Router,
+};
+use serde::Deserialize;
+
+use crate::recorder::{self, RecordingFile, SessionInfo};
+use crate::routes::{ApiResult, AppState};
+use crate::symbols;
+
+// ═══════════════════════════════════════════════════════════
+// REQUESTS
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Deserialize)]
+pub struct StartRecordingRequest {
+    pub symbol: String,
+    /// Писать ещё и исходный JSON из WS
+    #[serde(default)]
+    pub raw: bool,
+}
+
+#[derive(Deserialize)]
+pub struct StopRecordingRequest {
+    pub symbol: String,
+}
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/recorder", get(list))
+        .route("/recorder/files", get(files))
+        .route("/recorder/start", post(start))
+        .route("/recorder/stop", post(stop))
+        .with_state(state)
+}
+
+async fn list() -> Json<Vec<SessionInfo>> {
+    Json(recorder::list())
+}
+
+async fn files() -> Json<Vec<RecordingFile>> {
+    Json(recorder::files())
+}
+
+/// Начинает запись и подписывает символ на bookTicker + trades
+async fn start(
+    State(s): State<AppState>,
+    Json(req): Json<StartRecordingRequest>,
+) -> (StatusCode, Json<ApiResult<SessionInfo>>) {
+    if let Err(e) = symbols::check(&req.symbol) {
+        return ApiResult::err(StatusCode::BAD_REQUEST, e.to_string());
+    }
+    let info = match recorder::start(&req.symbol, req.raw) {
+        Ok(info) => info,
+        Err(e) => return ApiResult::err(StatusCode::CONFLICT, e.to_string()),
+    };
+
+    let subscribed = async {
+        s.market.subscribe_bookticker(&req.symbol).await?;
+        s.market.subscribe_trades(&req.symbol).await
+    };
+    if let Err(e) = subscribed.await {
+        recorder::stop(&req.symbol);
+        return ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    }
+
+    ApiResult::ok(info)
+}
+
+/// Останавливает запись. Подписки не снимаются - ими могут пользоваться стратегии
+async fn stop(Json(req): Json<StopRecordingRequest>) -> (StatusCode, Json<ApiResult<SessionInfo>>) {
+    match recorder::stop(&req.symbol) {
+        Some(info) => ApiResult::ok(info),
+        None => ApiResult::err(StatusCode::NOT_FOUND, format!("'{}' is not being recorded", req.symbol)),
+    }
+}
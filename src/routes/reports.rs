@@ -0,0 +1,76 @@
+// src/routes/reports.rs
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    extract::{Json, State, Query},
+    Router,
+};
+use serde::Deserialize;
+
+use crate::reports;
+use crate::routes::{ApiResult, AppState};
+
+// ═══════════════════════════════════════════════════════════
+// REQUESTS
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Deserialize)]
+pub struct ReportQuery {
+    /// Unix ms, включительно
+    #[serde(default)]
+    pub from: Option<i64>,
+    /// Unix ms, не включительно
+    #[serde(default)]
+    pub to: Option<i64>,
+    #[serde(default)]
+    pub format: Option<ReportFormat>,
+}
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/reports/daily", get(daily))
+        .with_state(state)
+}
+
+async fn daily(
+    State(s): State<AppState>,
+    Query(q): Query<ReportQuery>,
+) -> Response {
+    let Some(history) = s.history.clone() else {
+        return ApiResult::<()>::err(StatusCode::SERVICE_UNAVAILABLE, "History is disabled").into_response();
+    };
+
+    let from = q.from.unwrap_or(0);
+    let to = q.to.unwrap_or(i64::MAX);
+
+    let rows = match tokio::task::spawn_blocking(move || reports::daily(&history, from, to)).await {
+        Ok(Ok(rows)) => rows,
+        Ok(Err(e)) => return ApiResult::<()>::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        Err(e) => return ApiResult::<()>::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    match q.format {
+        Some(ReportFormat::Csv) => (
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (header::CONTENT_DISPOSITION, "attachment; filename=\"daily_report.csv\""),
+            ],
+            reports::to_csv(&rows),
+        )
+            .into_response(),
+        _ => Json(rows).into_response(),
+    }
+}
@@ -0,0 +1,82 @@
+// src/routes/risk.rs
+
+use axum::{
+    http::StatusCode,
+    routing::{get, post, put, delete},
+    extract::{Json, State},
+    Router,
+};
+use serde::Deserialize;
+
+use crate::risk::{RiskLimits, RiskStatus};
+use crate::routes::{ApiResult, AppState};
+
+// ═══════════════════════════════════════════════════════════
+// REQUESTS
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimitsScope {
+    Default,
+    Key,
+    Instance,
+}
+
+#[derive(Deserialize)]
+pub struct LimitsRequest {
+    pub scope: LimitsScope,
+    /// api_key для scope=key, instance_id для scope=instance
+    #[serde(default)]
+    pub id: Option<String>,
+    pub limits: RiskLimits,
+}
+
+#[derive(Deserialize)]
+pub struct KillSwitchRequest {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/risk", get(status))
+        .route("/risk/limits", put(set_limits))
+        .route("/risk/kill-switch", post(trip_kill_switch))
+        .route("/risk/kill-switch", delete(reset_kill_switch))
+        .with_state(state)
+}
+
+async fn status(State(s): State<AppState>) -> Json<RiskStatus> {
+    Json(s.risk.status())
+}
+
+async fn set_limits(
+    State(s): State<AppState>,
+    Json(req): Json<LimitsRequest>,
+) -> (StatusCode, Json<ApiResult>) {
+    match (req.scope, req.id) {
+        (LimitsScope::Default, _) => s.risk.set_default_limits(req.limits),
+        (LimitsScope::Key, Some(api_key)) => s.risk.set_key_limits(&api_key, req.limits),
+        (LimitsScope::Instance, Some(instance_id)) => s.risk.set_instance_limits(&instance_id, req.limits),
+        (_, None) => return ApiResult::err(StatusCode::BAD_REQUEST, "Missing 'id' for scope"),
+    }
+    ApiResult::ok_empty()
+}
+
+async fn trip_kill_switch(
+    State(s): State<AppState>,
+    Json(req): Json<KillSwitchRequest>,
+) -> (StatusCode, Json<ApiResult>) {
+    s.risk.trip_kill_switch(req.reason.as_deref().unwrap_or("Manual"));
+    ApiResult::ok_empty()
+}
+
+async fn reset_kill_switch(State(s): State<AppState>) -> (StatusCode, Json<ApiResult>) {
+    s.risk.reset_kill_switch();
+    ApiResult::ok_empty()
+}
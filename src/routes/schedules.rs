@@ -0,0 +1,90 @@
+// src/routes/schedules.rs
+
+use axum::{
+    http::StatusCode,
+    routing::get,
+    extract::{Json, State, Path},
+    Router,
+};
+use serde_json::Value;
+
+use crate::routes::{ApiResult, AppState};
+use crate::routes::strategy::check_params;
+use crate::scheduler::{Schedule, ScheduleInfo};
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/schedules", get(list).post(create))
+        .route("/schedules/:id", get(get_one).put(update).delete(remove))
+        .with_state(state)
+}
+
+async fn list(State(s): State<AppState>) -> Json<Vec<ScheduleInfo>> {
+    Json(s.scheduler.list())
+}
+
+async fn get_one(
+    State(s): State<AppState>,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<ApiResult<ScheduleInfo>>) {
+    match s.scheduler.get(&id) {
+        Some(info) => ApiResult::ok(info),
+        None => ApiResult::err(StatusCode::NOT_FOUND, format!("Schedule '{}' not found", id)),
+    }
+}
+
+async fn create(
+    State(s): State<AppState>,
+    Json(schedule): Json<Schedule>,
+) -> (StatusCode, Json<ApiResult<ScheduleInfo>>) {
+    if s.scheduler.get(&schedule.id).is_some() {
+        return ApiResult::err(StatusCode::CONFLICT, format!("Schedule '{}' already exists", schedule.id));
+    }
+    match save(&s, schedule) {
+        Ok(info) => ApiResult::created(info),
+        Err((code, e)) => ApiResult::err(code, e),
+    }
+}
+
+/// Создаёт или заменяет расписание целиком
+async fn update(
+    State(s): State<AppState>,
+    Path(id): Path<String>,
+    Json(mut schedule): Json<Schedule>,
+) -> (StatusCode, Json<ApiResult<ScheduleInfo>>) {
+    schedule.id = id;
+    match save(&s, schedule) {
+        Ok(info) => ApiResult::ok(info),
+        Err((code, e)) => ApiResult::err(code, e),
+    }
+}
+
+/// Инстанс, запущенный расписанием и ещё работающий, останавливается
+async fn remove(
+    State(s): State<AppState>,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<ApiResult>) {
+    match s.scheduler.remove(&id) {
+        Ok(_) => ApiResult::ok_empty(),
+        Err(e) => ApiResult::err(StatusCode::NOT_FOUND, e.to_string()),
+    }
+}
+
+/// Те же проверки, что и у ручного запуска: ошибку лучше увидеть сейчас,
+/// а не алертом в момент старта
+fn save(s: &AppState, schedule: Schedule) -> Result<ScheduleInfo, (StatusCode, String)> {
+    if !s.storage.exists(&schedule.strategy_id) {
+        return Err((StatusCode::NOT_FOUND, format!("Strategy '{}' not found", schedule.strategy_id)));
+    }
+    if let Some(account) = schedule.params.expose().get("account").and_then(Value::as_str) {
+        if !s.keystore.as_ref().is_some_and(|k| k.contains(account)) {
+            return Err((StatusCode::BAD_REQUEST, format!("Unknown account '{}'", account)));
+        }
+    }
+    check_params(s, &schedule.strategy_id, schedule.params.expose())?;
+    s.scheduler.put(schedule).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+}
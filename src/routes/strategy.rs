@@ -1,30 +1,49 @@
 // src/routes/strategy.rs
 
 use axum::{
-    http::StatusCode,
+    body::Bytes,
+    http::{header, StatusCode},
     routing::{get, post, put, delete},
-    extract::{Json, State, Path},
+    extract::{DefaultBodyLimit, Json, State, Path, Query},
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
+    response::{IntoResponse, Response},
     Router,
 };
+use tokio::sync::broadcast;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::sync::Arc;
-use tokio::sync::broadcast;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
 
-use crate::strategies::storage::StrategyStorage;
-use crate::strategies::manager::{StrategyRunner, InstanceInfo};
-use crate::ffi_types::CEvent;
+use crate::ffi_types::SIGNAL_PAYLOAD_LEN;
+use crate::kv;
+use crate::orders::{instance_tag, OrderFilter, OrderRecord};
+use crate::routes::{ApiResult, AppState};
+use crate::strategies::capacity::CapacityStatus;
+use crate::strategies::compile::{CompileJob, JobStatus};
+use crate::strategies::diagnostics::Diagnostic;
+use crate::strategies::disk::{DiskUsage, PruneReport};
+use crate::strategies::dry_run::{self, DryRunResult, FixtureInfo, TestEvent};
+use crate::strategies::schema;
+use crate::strategies::shadow::{self, ShadowReport};
+use crate::strategies::storage::{ManifestUpdate, StrategyManifest, TemplateInfo};
+use crate::strategies::logs::{self, LogLine};
+use crate::strategies::manager::{InstanceInfo, InstanceOptions, InstanceStatus};
+use crate::strategies::quarantine::{self, QuarantinedInstance};
+use crate::strategies::runs::{self, RunRecord};
 
-// ═══════════════════════════════════════════════════════════
-// STATE
-// ═══════════════════════════════════════════════════════════
+/// Предел размера загружаемой библиотеки (release-сборка с LTO - единицы MB)
+const MAX_ARTIFACT_SIZE: usize = 64 * 1024 * 1024;
 
-#[derive(Clone)]
-pub struct AppState {
-    pub storage: Arc<StrategyStorage>,
-    pub runner: Arc<StrategyRunner>,
-    pub event_tx: broadcast::Sender<CEvent>,
-}
+/// Предел архива POST /strategies/import (код, manifest, схема, зависимости)
+const MAX_IMPORT_SIZE: usize = 4 * 1024 * 1024;
+
+/// Заготовка для POST /strategies без code и template
+const DEFAULT_TEMPLATE: &str = "blank";
+
+/// Предел длительности POST /strategies/{id}/test
+const MAX_TEST_TIMEOUT_MS: u64 = 60_000;
 
 // ═══════════════════════════════════════════════════════════
 // REQUESTS
@@ -33,12 +52,53 @@ pub struct AppState {
 #[derive(Deserialize)]
 pub struct CreateRequest {
     pub id: String,
-    pub code: String,
+    /// Без code берётся заготовка template (по умолчанию "blank")
+    #[serde(default)]
+    pub code: Option<String>,
+    /// Имя из GET /api/strategy-templates
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Крейты сверх шаблона: {"statrs": "0.17"}, только из compile.allowed_dependencies
+    #[serde(default)]
+    pub dependencies: Option<BTreeMap<String, String>>,
+    /// description, author, version, tags
+    #[serde(flatten)]
+    pub manifest: ManifestUpdate,
 }
 
 #[derive(Deserialize)]
 pub struct CodeRequest {
     pub code: String,
+    /// None - зависимости не меняются, {} - убрать все дополнительные
+    #[serde(default)]
+    pub dependencies: Option<BTreeMap<String, String>>,
+    /// Не переданные поля описания остаются как были
+    #[serde(flatten)]
+    pub manifest: ManifestUpdate,
+}
+
+#[derive(Deserialize)]
+pub struct ListQuery {
+    /// "arb,funding" - стратегии со всеми перечисленными тегами
+    #[serde(default)]
+    pub tags: Option<String>,
+    /// Подстрока id или описания, без учёта регистра
+    #[serde(default)]
+    pub q: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ImportQuery {
+    /// id новой стратегии; без него - имя папки в архиве
+    #[serde(default)]
+    pub id: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct FormatRequest {
+    /// Несохранённый код из редактора; без него - код стратегии с диска
+    #[serde(default)]
+    pub code: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -46,44 +106,109 @@ pub struct StartRequest {
     pub symbol: String,
     #[serde(default)]
     pub params: Value,
+    /// recv_mode, cpu_core, channel_capacity, overflow
+    #[serde(flatten)]
+    pub options: InstanceOptions,
 }
 
-// ═══════════════════════════════════════════════════════════
-// RESPONSES
-// ═══════════════════════════════════════════════════════════
+#[derive(Deserialize)]
+pub struct TestRequest {
+    pub symbol: String,
+    #[serde(default)]
+    pub params: Value,
+    /// Сценарий: [{"type": "book_ticker", "bid": 100, "ask": 100.1}, {"type": "wait", "ms": 500}, ...]
+    #[serde(default)]
+    pub events: Option<Vec<TestEvent>>,
+    /// Или имя из GET /api/strategy-fixtures
+    #[serde(default)]
+    pub fixture: Option<String>,
+    /// Сколько ждать ордеров после последнего события
+    #[serde(default = "default_settle_ms")]
+    pub settle_ms: u64,
+    #[serde(default = "default_test_timeout_ms")]
+    pub timeout_ms: u64,
+}
 
-#[derive(Serialize)]
-pub struct ApiResult<T = ()> {
-    pub ok: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub error: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub data: Option<T>,
+fn default_settle_ms() -> u64 {
+    200
 }
 
-impl<T: Serialize> ApiResult<T> {
-    fn ok(data: T) -> (StatusCode, Json<Self>) {
-        (StatusCode::OK, Json(Self { ok: true, error: None, data: Some(data) }))
-    }
-    
-    fn created(data: T) -> (StatusCode, Json<Self>) {
-        (StatusCode::CREATED, Json(Self { ok: true, error: None, data: Some(data) }))
-    }
-    
-    // Generic error - работает для любого T
-    fn err(status: StatusCode, msg: impl Into<String>) -> (StatusCode, Json<Self>) {
-        (status, Json(Self { ok: false, error: Some(msg.into()), data: None }))
-    }
+fn default_test_timeout_ms() -> u64 {
+    10_000
 }
 
-impl ApiResult<()> {
-    fn ok_empty() -> (StatusCode, Json<Self>) {
-        (StatusCode::OK, Json(Self { ok: true, error: None, data: None }))
-    }
-    
-    fn created_empty() -> (StatusCode, Json<Self>) {
-        (StatusCode::CREATED, Json(Self { ok: true, error: None, data: None }))
-    }
+#[derive(Deserialize)]
+pub struct LogsQuery {
+    #[serde(default = "default_tail")]
+    pub tail: usize,
+}
+
+fn default_tail() -> usize {
+    500
+}
+
+#[derive(Deserialize)]
+pub struct InstancesQuery {
+    #[serde(default)]
+    pub strategy: Option<String>,
+    #[serde(default)]
+    pub symbol: Option<String>,
+    #[serde(default)]
+    pub status: Option<InstanceStatus>,
+    #[serde(default)]
+    pub group: Option<String>,
+    /// instance_id (по умолчанию), started_at, symbol, strategy;
+    /// "-started_at" - по убыванию
+    #[serde(default)]
+    pub sort: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+#[derive(Deserialize)]
+pub struct InstanceOrdersQuery {
+    #[serde(default)]
+    pub symbol: Option<String>,
+    /// Сколько последних завершённых ордеров вернуть
+    #[serde(default = "default_recent")]
+    pub recent: usize,
+}
+
+fn default_recent() -> usize {
+    50
+}
+
+#[derive(Deserialize)]
+pub struct RunsQuery {
+    #[serde(default)]
+    pub strategy: Option<String>,
+    #[serde(default)]
+    pub symbol: Option<String>,
+    /// Сколько последних запусков вернуть
+    #[serde(default = "default_runs_limit")]
+    pub limit: usize,
+}
+
+fn default_runs_limit() -> usize {
+    100
+}
+
+// ═══════════════════════════════════════════════════════════
+// RESPONSES
+// ═══════════════════════════════════════════════════════════
+
+/// Ордера инстанса глазами order manager ядра
+#[derive(Serialize)]
+pub struct InstanceOrders {
+    pub instance_id: String,
+    /// clientOrderId ордеров инстанса начинаются с hft-{tag}-
+    pub tag: String,
+    /// В стакане или ждут ответа биржи
+    pub open: Vec<OrderRecord>,
+    /// Завершённые, новые первыми
+    pub recent: Vec<OrderRecord>,
 }
 
 #[derive(Serialize)]
@@ -91,20 +216,50 @@ pub struct StrategyDetail {
     pub id: String,
     pub code: String,
     pub compiled: bool,
+    /// Загружена готовая библиотека, исходника нет
+    pub prebuilt: bool,
+    #[serde(flatten)]
+    pub manifest: StrategyManifest,
+    /// Крейты сверх шаблона
+    pub dependencies: BTreeMap<String, String>,
     pub instances: Vec<InstanceInfo>,
 }
 
+#[derive(Serialize)]
+pub struct CheckResult {
+    pub success: bool,
+    pub errors: Vec<String>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Serialize)]
+pub struct FormatResult {
+    pub code: String,
+    /// Форматирование что-то изменило
+    pub changed: bool,
+}
+
 #[derive(Serialize)]
 pub struct StrategyListItem {
     pub id: String,
     pub compiled: bool,
     pub instances: usize,
+    #[serde(flatten)]
+    pub manifest: StrategyManifest,
+    pub modified_at: Option<i64>,
+    pub compiled_at: Option<i64>,
+    pub code_size: u64,
+    /// Последняя сборка с запуска ядра (задания живут только в памяти)
+    pub last_compile: Option<LastCompile>,
 }
 
 #[derive(Serialize)]
-pub struct CompileResult {
-    pub success: bool,
-    pub errors: Vec<String>,
+pub struct LastCompile {
+    pub job_id: u64,
+    pub status: JobStatus,
+    pub queued_at: i64,
+    pub finished_at: Option<i64>,
+    pub errors: usize,
 }
 
 // ═══════════════════════════════════════════════════════════
@@ -116,19 +271,51 @@ pub fn routes(state: AppState) -> Router {
         // Стратегии
         .route("/strategies", get(list_strategies))
         .route("/strategies", post(create_strategy))
+        .route(
+            "/strategies/import",
+            post(import_strategy).layer(DefaultBodyLimit::max(MAX_IMPORT_SIZE)),
+        )
+        .route("/strategy-templates", get(list_templates))
         .route("/strategies/:id", get(get_strategy))
         .route("/strategies/:id", delete(delete_strategy))
         .route("/strategies/:id/code", put(update_code))
+        .route("/strategies/:id/manifest", put(update_manifest))
+        .route("/strategies/:id/export", get(export_strategy))
+        .route("/strategies/:id/schema", get(get_schema).put(set_schema))
         .route("/strategies/:id/compile", post(compile))
+        .route("/strategies/:id/check", post(check))
+        .route("/strategies/:id/lint", post(lint))
+        .route("/strategies/:id/format", post(format))
+        .route(
+            "/strategies/:id/artifact",
+            post(upload_artifact).layer(DefaultBodyLimit::max(MAX_ARTIFACT_SIZE)),
+        )
         
         // Запуск/остановка
         .route("/strategies/:id/start", post(start))
+        .route("/strategies/:id/test", post(dry_run))
+        .route("/strategy-fixtures", get(list_fixtures))
         .route("/strategies/:id/stop", post(stop_all))
         .route("/strategies/:id/stop/:symbol", post(stop_one))
         
         // Инстансы
         .route("/instances", get(list_instances))
+        .route("/instances/history", get(list_runs))
+        .route("/quarantine", get(list_quarantined))
+        .route("/capacity", get(capacity))
+        
+        // Диск
+        .route("/disk", get(disk_usage))
+        .route("/disk/prune", post(disk_prune))
         .route("/instances/:instance_id/stop", post(stop_instance))
+        .route("/instances/:instance_id/resume", post(resume_instance))
+        .route("/instances/:instance_id/signal", post(signal_instance))
+        .route("/instances/:instance_id/restart", post(restart_instance))
+        .route("/instances/:instance_id/swap", post(swap_instance))
+        .route("/instances/:instance_id/shadow", post(start_shadow).get(shadow_report))
+        .route("/instances/:instance_id/orders", get(instance_orders))
+        .route("/instances/:instance_id/logs", get(instance_logs))
+        .route("/instances/:instance_id/logs/ws", get(instance_logs_ws))
         .route("/instances/:instance_id", get(get_instance))
         
         .with_state(state)
@@ -138,30 +325,76 @@ pub fn routes(state: AppState) -> Router {
 // СТРАТЕГИИ
 // ═══════════════════════════════════════════════════════════
 
-async fn list_strategies(State(s): State<AppState>) -> Json<Vec<StrategyListItem>> {
-    let list = s.storage.list().unwrap_or_default();
+async fn list_strategies(
+    State(s): State<AppState>,
+    Query(q): Query<ListQuery>,
+) -> Json<Vec<StrategyListItem>> {
+    let mut list = s.storage.list().unwrap_or_default();
+    list.sort_by(|a, b| a.id.cmp(&b.id));
     
-    Json(list.into_iter().map(|info| {
-        StrategyListItem {
-            id: info.id.clone(),
-            compiled: info.compiled,
+    let tags: Vec<String> = q.tags.as_deref().unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(String::from)
+        .collect();
+    let search = q.q.as_deref().map(str::trim).unwrap_or_default().to_lowercase();
+    
+    Json(list.into_iter()
+        .filter(|info| info.manifest.has_tags(&tags))
+        .filter(|info| {
+            search.is_empty()
+                || info.id.to_lowercase().contains(&search)
+                || info.manifest.description.to_lowercase().contains(&search)
+        })
+        .map(|info| StrategyListItem {
             instances: s.runner.list_for(&info.id).len(),
-        }
-    }).collect())
+            last_compile: s.compiler.latest(&info.id).map(|job| LastCompile {
+                job_id: job.id,
+                status: job.status,
+                queued_at: job.queued_at,
+                finished_at: job.finished_at,
+                errors: job.errors.len(),
+            }),
+            id: info.id,
+            compiled: info.compiled,
+            manifest: info.manifest,
+            modified_at: info.modified_at,
+            compiled_at: info.compiled_at,
+            code_size: info.code_size,
+        })
+        .collect())
 }
 
 
 async fn create_strategy(
     State(s): State<AppState>,
     Json(req): Json<CreateRequest>,
-) -> (StatusCode, Json<ApiResult>) {
-    if let Err(e) = s.storage.create(&req.id, &req.code) {
+) -> (StatusCode, Json<ApiResult<CompileJob>>) {
+    let code = match (req.code, req.template) {
+        (Some(_), Some(_)) => {
+            return ApiResult::err(StatusCode::BAD_REQUEST, "Pass either code or template, not both");
+        }
+        (Some(code), None) => code,
+        (None, template) => match s.storage.template_code(template.as_deref().unwrap_or(DEFAULT_TEMPLATE)) {
+            Ok(code) => code,
+            Err(e) => return ApiResult::err(StatusCode::BAD_REQUEST, e.to_string()),
+        },
+    };
+    
+    if let Err(e) = s.storage.create(&req.id, &code, req.dependencies.as_ref(), &req.manifest) {
         return ApiResult::err(StatusCode::BAD_REQUEST, e.to_string());
     }
     
-    let _ = s.storage.compile(&req.id);
-    
-    ApiResult::created_empty()
+    // Сборка идёт в фоне, прогресс - GET /api/compile-jobs/{id}
+    match s.compiler.enqueue(&req.id) {
+        Ok(job) => ApiResult::created(job),
+        Err(e) => ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+async fn list_templates(State(s): State<AppState>) -> Json<Vec<TemplateInfo>> {
+    Json(s.storage.templates())
 }
 
 async fn get_strategy(
@@ -175,7 +408,12 @@ async fn get_strategy(
     let compiled = s.storage.get_lib_path(&id).is_ok();
     let instances = s.runner.list_for(&id);
     
-    Ok(ApiResult::ok(StrategyDetail { id, code, compiled, instances }))
+    let dependencies = s.storage.get_dependencies(&id);
+    
+    let prebuilt = s.storage.is_prebuilt(&id);
+    let manifest = s.storage.get_manifest(&id);
+    
+    Ok(ApiResult::ok(StrategyDetail { id, code, compiled, prebuilt, manifest, dependencies, instances }))
 }
 
 async fn delete_strategy(
@@ -186,7 +424,10 @@ async fn delete_strategy(
     s.runner.stop_all(&id).await;
     
     match s.storage.delete(&id) {
-        Ok(_) => ApiResult::ok_empty(),
+        Ok(_) => {
+            kv::clear(&id);
+            ApiResult::ok_empty()
+        }
         Err(e) => ApiResult::err(StatusCode::NOT_FOUND, e.to_string()),
     }
 }
@@ -195,38 +436,184 @@ async fn update_code(
     State(s): State<AppState>,
     Path(id): Path<String>,
     Json(req): Json<CodeRequest>,
-) -> (StatusCode, Json<ApiResult<CompileResult>>) {
+) -> (StatusCode, Json<ApiResult<CompileJob>>) {
     if !s.runner.list_for(&id).is_empty() {
         return ApiResult::err(StatusCode::CONFLICT, "Stop all instances first");
     }
     
-    if let Err(e) = s.storage.update_code(&id, &req.code) {
-        return ApiResult::err(StatusCode::NOT_FOUND, e.to_string());
+    if !s.storage.exists(&id) {
+        return ApiResult::err(StatusCode::NOT_FOUND, "Strategy not found");
+    }
+    
+    if let Err(e) = s.storage.update_code(&id, &req.code, req.dependencies.as_ref(), &req.manifest) {
+        return ApiResult::err(StatusCode::BAD_REQUEST, e.to_string());
     }
     
-    match s.storage.compile(&id) {
-        Ok(r) => ApiResult::ok(CompileResult { success: r.success, errors: r.errors }),
+    match s.compiler.enqueue(&id) {
+        Ok(job) => ApiResult::ok(job),
         Err(e) => ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
     }
 }
 
+/// Описание и теги без изменения кода (можно при запущенных инстансах)
+async fn update_manifest(
+    State(s): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<ManifestUpdate>,
+) -> (StatusCode, Json<ApiResult<StrategyManifest>>) {
+    match s.storage.update_manifest(&id, &req) {
+        Ok(manifest) => ApiResult::ok(manifest),
+        Err(e) => ApiResult::err(StatusCode::NOT_FOUND, e.to_string()),
+    }
+}
+
+async fn get_schema(
+    State(s): State<AppState>,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<ApiResult<Value>>) {
+    match s.storage.get_schema(&id) {
+        Ok(Some(schema)) => ApiResult::ok(schema),
+        Ok(None) => ApiResult::err(StatusCode::NOT_FOUND, format!("Strategy '{}' declares no params schema", id)),
+        Err(e) => ApiResult::err(StatusCode::NOT_FOUND, e.to_string()),
+    }
+}
+
+/// schema.json поверх params_schema() библиотеки; null - удалить файл
+async fn set_schema(
+    State(s): State<AppState>,
+    Path(id): Path<String>,
+    Json(schema): Json<Value>,
+) -> (StatusCode, Json<ApiResult<()>>) {
+    let schema = (!schema.is_null()).then_some(schema);
+    match s.storage.set_schema(&id, schema.as_ref()) {
+        Ok(()) => ApiResult::ok_empty(),
+        Err(e) => ApiResult::err(StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
+
+/// Ставит сборку в очередь и сразу возвращает задание
 async fn compile(
     State(s): State<AppState>,
     Path(id): Path<String>,
-) -> (StatusCode, Json<ApiResult<CompileResult>>) {
-    match s.storage.compile(&id) {
-        Ok(r) => {
-            let status = if r.success { StatusCode::OK } else { StatusCode::BAD_REQUEST };
-            (status, Json(ApiResult {
-                ok: r.success,
-                error: if r.success { None } else { Some("Compilation failed".into()) },
-                data: Some(CompileResult { success: r.success, errors: r.errors }),
-            }))
-        }
+) -> (StatusCode, Json<ApiResult<CompileJob>>) {
+    match s.compiler.enqueue(&id) {
+        Ok(job) => ApiResult::created(job),
         Err(e) => ApiResult::err(StatusCode::NOT_FOUND, e.to_string()),
     }
 }
 
+/// cargo check: ошибки компиляции без release-сборки
+async fn check(
+    State(s): State<AppState>,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<ApiResult<CheckResult>>) {
+    run_check(s, id, false).await
+}
+
+/// cargo clippy: то же + предупреждения линтера
+async fn lint(
+    State(s): State<AppState>,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<ApiResult<CheckResult>>) {
+    run_check(s, id, true).await
+}
+
+async fn run_check(s: AppState, id: String, lint: bool) -> (StatusCode, Json<ApiResult<CheckResult>>) {
+    if !s.storage.exists(&id) {
+        return ApiResult::err(StatusCode::NOT_FOUND, "Strategy not found");
+    }
+    match s.compiler.run_blocking(move |storage| storage.check(&id, lint)).await {
+        Ok(Ok(r)) => ApiResult::ok(CheckResult { success: r.success, errors: r.errors, diagnostics: r.diagnostics }),
+        Ok(Err(e)) => ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        Err(e) => ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// rustfmt кода стратегии. Результат только возвращается - сохранить через PUT /code
+async fn format(
+    State(s): State<AppState>,
+    Path(id): Path<String>,
+    body: Option<Json<FormatRequest>>,
+) -> (StatusCode, Json<ApiResult<FormatResult>>) {
+    if !s.storage.exists(&id) {
+        return ApiResult::err(StatusCode::NOT_FOUND, "Strategy not found");
+    }
+    let code = match body.and_then(|Json(req)| req.code) {
+        Some(code) => code,
+        None => match s.storage.get_code(&id) {
+            Ok(code) => code,
+            Err(e) => return ApiResult::err(StatusCode::NOT_FOUND, e.to_string()),
+        },
+    };
+    let input = code.clone();
+    match s.compiler.run_blocking(move |storage| storage.format(&input)).await {
+        Ok(Ok(formatted)) => ApiResult::ok(FormatResult { changed: formatted != code, code: formatted }),
+        Ok(Err(e)) => ApiResult::err(StatusCode::BAD_REQUEST, e.to_string()),
+        Err(e) => ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Zip с кодом, описанием, схемой и зависимостями для POST /strategies/import
+async fn export_strategy(
+    State(s): State<AppState>,
+    Path(id): Path<String>,
+) -> Response {
+    if !s.storage.exists(&id) {
+        return ApiResult::<()>::err(StatusCode::NOT_FOUND, "Strategy not found").into_response();
+    }
+    match s.storage.export(&id) {
+        Ok(bytes) => (
+            [
+                (header::CONTENT_TYPE, "application/zip".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.zip\"", id)),
+            ],
+            bytes,
+        )
+            .into_response(),
+        Err(e) => ApiResult::<()>::err(StatusCode::BAD_REQUEST, e.to_string()).into_response(),
+    }
+}
+
+/// Стратегия из архива export (тело запроса - zip). Сборка ставится в
+/// очередь сразу, как при создании
+async fn import_strategy(
+    State(s): State<AppState>,
+    Query(q): Query<ImportQuery>,
+    body: Bytes,
+) -> (StatusCode, Json<ApiResult<CompileJob>>) {
+    if body.is_empty() {
+        return ApiResult::err(StatusCode::BAD_REQUEST, "Empty body, send the zip archive");
+    }
+    let storage = s.storage.clone();
+    let id = match tokio::task::spawn_blocking(move || storage.import(&body, q.id.as_deref())).await {
+        Ok(Ok(id)) => id,
+        Ok(Err(e)) => return ApiResult::err(StatusCode::BAD_REQUEST, format!("{:#}", e)),
+        Err(e) => return ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    };
+    match s.compiler.enqueue(&id) {
+        Ok(job) => ApiResult::created(job),
+        Err(e) => ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Готовая библиотека из CI пользователя (тело запроса - файл .so/.dll/.dylib).
+/// Стратегия создаётся, если её нет; запущенные инстансы получат новую версию после рестарта
+async fn upload_artifact(
+    State(s): State<AppState>,
+    Path(id): Path<String>,
+    body: Bytes,
+) -> (StatusCode, Json<ApiResult>) {
+    if body.is_empty() {
+        return ApiResult::err(StatusCode::BAD_REQUEST, "Empty body, send the library file");
+    }
+    let storage = s.storage.clone();
+    match tokio::task::spawn_blocking(move || storage.install_artifact(&id, &body)).await {
+        Ok(Ok(_)) => ApiResult::created_empty(),
+        Ok(Err(e)) => ApiResult::err(StatusCode::BAD_REQUEST, format!("{:#}", e)),
+        Err(e) => ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
 async fn start(
     State(s): State<AppState>,
     Path(id): Path<String>,
@@ -237,32 +624,114 @@ async fn start(
     }
     tracing::info!("Starting strategy {} on {}", id, req.symbol);
     
-    let lib_path = match s.storage.get_lib_path(&id) {
-        Ok(p) => p,
-        Err(_) => {
-            match s.storage.compile(&id) {
-                Ok(r) if r.success => r.lib_path.unwrap(),
-                Ok(r) => return ApiResult::err(
-                    StatusCode::BAD_REQUEST, 
-                    format!("Compilation failed: {}", r.errors.join("; "))
-                ),
-                Err(e) => return ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
-            }
+    // "account": "main" - alias из keystore, ключи в params не нужны
+    if let Some(account) = req.params.get("account").and_then(Value::as_str) {
+        if !s.keystore.as_ref().is_some_and(|k| k.contains(account)) {
+            return ApiResult::err(StatusCode::BAD_REQUEST, format!("Unknown account '{}'", account));
         }
+    }
+    
+    if let Err(e) = req.options.validate() {
+        return ApiResult::err(StatusCode::BAD_REQUEST, e.to_string());
+    }
+    
+    let lib_path = match compiled_lib(&s, &id).await {
+        Ok(p) => p,
+        Err((code, e)) => return ApiResult::err(code, e),
     };
+    if let Err((code, e)) = check_params(&s, &id, &req.params) {
+        return ApiResult::err(code, e);
+    }
     
     match s.runner.start(
         id,
         req.symbol,
         lib_path,
-        s.event_tx.subscribe(),
         req.params,
+        req.options,
     ).await {
         Ok(info) => ApiResult::ok(info),
         Err(e) => ApiResult::err(StatusCode::BAD_REQUEST, e.to_string()),
     }
 }
 
+/// Прогон на сценарии событий в изолированном инстансе: какие ордера
+/// стратегия поставила бы. На биржу ничего не уходит
+async fn dry_run(
+    State(s): State<AppState>,
+    Path(id): Path<String>,
+    Json(req): Json<TestRequest>,
+) -> (StatusCode, Json<ApiResult<DryRunResult>>) {
+    if !s.storage.exists(&id) {
+        return ApiResult::err(StatusCode::NOT_FOUND, "Strategy not found");
+    }
+    let events = match (req.events, req.fixture) {
+        (Some(_), Some(_)) => return ApiResult::err(StatusCode::BAD_REQUEST, "Pass either events or fixture, not both"),
+        (Some(events), None) => events,
+        (None, Some(name)) => match dry_run::fixture(&name) {
+            Ok(f) => f.events,
+            Err(e) => return ApiResult::err(StatusCode::BAD_REQUEST, e.to_string()),
+        },
+        (None, None) => return ApiResult::err(StatusCode::BAD_REQUEST, "Pass events or fixture"),
+    };
+    if req.timeout_ms == 0 || req.timeout_ms > MAX_TEST_TIMEOUT_MS {
+        return ApiResult::err(StatusCode::BAD_REQUEST, format!("timeout_ms must be in 1..={}", MAX_TEST_TIMEOUT_MS));
+    }
+    
+    let lib_path = match compiled_lib(&s, &id).await {
+        Ok(p) => p,
+        Err((code, e)) => return ApiResult::err(code, e),
+    };
+    if let Err((code, e)) = check_params(&s, &id, &req.params) {
+        return ApiResult::err(code, e);
+    }
+    
+    let settle = Duration::from_millis(req.settle_ms);
+    let timeout = Duration::from_millis(req.timeout_ms);
+    match dry_run::run(&id, lib_path, &req.symbol, &req.params, events, settle, timeout).await {
+        Ok(result) => ApiResult::ok(result),
+        Err(e) => ApiResult::err(StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
+
+async fn list_fixtures() -> Json<Vec<FixtureInfo>> {
+    Json(dry_run::fixtures())
+}
+
+/// Путь к собранной библиотеке; без неё - сборка и ожидание
+pub(crate) async fn compiled_lib(s: &AppState, id: &str) -> Result<PathBuf, (StatusCode, String)> {
+    if let Ok(p) = s.storage.get_lib_path(id) {
+        return Ok(p);
+    }
+    let job = s.compiler.enqueue(id)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    match s.compiler.wait(job.id).await {
+        Some(job) if job.status == JobStatus::Done => s.storage.get_lib_path(id)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+        Some(job) => Err((
+            StatusCode::BAD_REQUEST,
+            format!("Compilation failed: {}", job.errors.join("; ")),
+        )),
+        None => Err((StatusCode::INTERNAL_SERVER_ERROR, "Compile job lost".to_string())),
+    }
+}
+
+/// Проверка params по схеме стратегии: опечатка в имени параметра
+/// иначе молча превращается в Default
+pub(crate) fn check_params(s: &AppState, id: &str, params: &Value) -> Result<(), (StatusCode, String)> {
+    let schema = match s.storage.get_schema(id) {
+        Ok(Some(schema)) => schema,
+        Ok(None) => return Ok(()),
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Params schema: {}", e))),
+    };
+    let params = if params.is_null() { serde_json::json!({}) } else { params.clone() };
+    let errors = schema::validate(&schema, &params);
+    if !errors.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, format!("Invalid params: {}", errors.join("; "))));
+    }
+    Ok(())
+}
+
 async fn stop_all(
     State(s): State<AppState>,
     Path(id): Path<String>,
@@ -281,6 +750,7 @@ async fn stop_one(
     Path((id, symbol)): Path<(String, String)>,
 ) -> (StatusCode, Json<ApiResult>) {
     let instance_id = format!("{}:{}", id, symbol.to_uppercase());
+    let instance_id = s.runner.active_slot(&instance_id).unwrap_or(instance_id);
     
     match s.runner.stop(&instance_id).await {
         Ok(_) => ApiResult::ok_empty(),
@@ -292,12 +762,209 @@ async fn stop_instance(
     State(s): State<AppState>,
     Path(instance_id): Path<String>,
 ) -> (StatusCode, Json<ApiResult>) {
+    let instance_id = s.runner.active_slot(&instance_id).unwrap_or(instance_id);
     match s.runner.stop(&instance_id).await {
         Ok(_) => ApiResult::ok_empty(),
         Err(e) => ApiResult::err(StatusCode::NOT_FOUND, e.to_string()),
     }
 }
 
+/// Произвольный JSON в канал работающей стратегии (EVENT_SIGNAL), без перезапуска
+async fn signal_instance(
+    State(s): State<AppState>,
+    Path(instance_id): Path<String>,
+    Json(payload): Json<Value>,
+) -> (StatusCode, Json<ApiResult>) {
+    let Some(instance_id) = s.runner.active_slot(&instance_id) else {
+        return ApiResult::err(StatusCode::NOT_FOUND, format!("Instance '{}' not found", instance_id));
+    };
+    let payload = payload.to_string();
+    if payload.len() > SIGNAL_PAYLOAD_LEN {
+        return ApiResult::err(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Signal payload is {} bytes, max {}", payload.len(), SIGNAL_PAYLOAD_LEN),
+        );
+    }
+    match s.runner.signal(&instance_id, &payload) {
+        Ok(_) => ApiResult::ok_empty(),
+        Err(e) => ApiResult::err(StatusCode::CONFLICT, e.to_string()),
+    }
+}
+
+async fn resume_instance(
+    State(s): State<AppState>,
+    Path(instance_id): Path<String>,
+) -> (StatusCode, Json<ApiResult>) {
+    match s.runner.resume_trading(&instance_id) {
+        Ok(_) => ApiResult::ok_empty(),
+        Err(e) => ApiResult::err(StatusCode::CONFLICT, e.to_string()),
+    }
+}
+
+async fn restart_instance(
+    State(s): State<AppState>,
+    Path(instance_id): Path<String>,
+) -> (StatusCode, Json<ApiResult<InstanceInfo>>) {
+    let Some(info) = s.runner.get(&instance_id) else {
+        return ApiResult::err(StatusCode::NOT_FOUND, "Instance not found");
+    };
+    let lib_path = match s.storage.get_lib_path(&info.strategy_id) {
+        Ok(p) => p,
+        Err(e) => return ApiResult::err(StatusCode::CONFLICT, e.to_string()),
+    };
+    
+    match s.runner.restart(&instance_id, lib_path).await {
+        Ok(info) => ApiResult::ok(info),
+        Err(e) => ApiResult::err(StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SwapQuery {
+    /// Сколько ждать первого события нового инстанса
+    #[serde(default = "default_ready_timeout")]
+    pub ready_timeout_secs: u64,
+}
+
+fn default_ready_timeout() -> u64 {
+    30
+}
+
+/// Замена без простоя: новый инстанс из последней сборки с теми же параметрами
+/// в другом слоте (id с @green или без), старый останавливается, когда новый готов
+async fn swap_instance(
+    State(s): State<AppState>,
+    Path(instance_id): Path<String>,
+    Query(q): Query<SwapQuery>,
+) -> (StatusCode, Json<ApiResult<InstanceInfo>>) {
+    let Some(info) = s.runner.get(&instance_id) else {
+        return ApiResult::err(StatusCode::NOT_FOUND, "Instance not found");
+    };
+    let lib_path = match s.storage.get_lib_path(&info.strategy_id) {
+        Ok(p) => p,
+        Err(e) => return ApiResult::err(StatusCode::CONFLICT, e.to_string()),
+    };
+    
+    let ready_timeout = Duration::from_secs(q.ready_timeout_secs);
+    match s.runner.swap(&instance_id, lib_path, ready_timeout).await {
+        Ok(info) => ApiResult::ok(info),
+        Err(e) => ApiResult::err(StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ShadowRequest {
+    /// Какую стратегию проверять (по умолчанию - ту же, последнюю сборку)
+    pub strategy_id: Option<String>,
+    /// По умолчанию - параметры живого инстанса
+    pub params: Option<Value>,
+    pub match_window_ms: Option<u64>,
+}
+
+/// Тень живого инстанса: те же события, ордера только записываются и
+/// сравниваются с ордерами живого (GET .../shadow)
+async fn start_shadow(
+    State(s): State<AppState>,
+    Path(instance_id): Path<String>,
+    body: Option<Json<ShadowRequest>>,
+) -> (StatusCode, Json<ApiResult<InstanceInfo>>) {
+    let req = body.map(|Json(req)| req).unwrap_or_default();
+    let Some(live) = s.runner.get(&instance_id) else {
+        return ApiResult::err(StatusCode::NOT_FOUND, "Instance not found");
+    };
+    let strategy_id = req.strategy_id.unwrap_or(live.strategy_id);
+    if !s.storage.exists(&strategy_id) {
+        return ApiResult::err(StatusCode::NOT_FOUND, "Strategy not found");
+    }
+    let lib_path = match compiled_lib(&s, &strategy_id).await {
+        Ok(p) => p,
+        Err((code, e)) => return ApiResult::err(code, e),
+    };
+    let Some(params) = req.params.or_else(|| s.runner.start_params(&instance_id)) else {
+        return ApiResult::err(StatusCode::NOT_FOUND, "Instance not found");
+    };
+    if let Err((code, e)) = check_params(&s, &strategy_id, &params) {
+        return ApiResult::err(code, e);
+    }
+    
+    let window = req.match_window_ms.unwrap_or(shadow::DEFAULT_MATCH_WINDOW_MS);
+    match s.runner.start_shadow(&instance_id, strategy_id, lib_path, params, window).await {
+        Ok(info) => ApiResult::created(info),
+        Err(e) => ApiResult::err(StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
+
+/// Сравнение ордеров живого инстанса и его тени с запуска тени
+async fn shadow_report(
+    State(s): State<AppState>,
+    Path(instance_id): Path<String>,
+) -> (StatusCode, Json<ApiResult<ShadowReport>>) {
+    let Some(pair) = shadow::get(&instance_id) else {
+        return ApiResult::err(StatusCode::NOT_FOUND, format!("No shadow for '{}'", instance_id));
+    };
+    let orders = |id: &str| s.orders.list(&OrderFilter { instance_id: Some(id), symbol: None, open_only: false });
+    let running = (s.runner.get(&pair.live_id).is_some(), s.runner.get(&pair.shadow_id).is_some());
+    ApiResult::ok(shadow::compare(&pair, &orders(&pair.live_id), &orders(&pair.shadow_id), running))
+}
+
+async fn instance_logs(
+    Path(instance_id): Path<String>,
+    Query(q): Query<LogsQuery>,
+) -> (StatusCode, Json<ApiResult<Vec<LogLine>>>) {
+    match logs::tail(&instance_id, q.tail) {
+        Some(lines) => ApiResult::ok(lines),
+        None => ApiResult::err(StatusCode::NOT_FOUND, "No logs for instance"),
+    }
+}
+
+/// Живой лог инстанса: сначала последние tail строк, затем новые по мере появления
+async fn instance_logs_ws(
+    Path(instance_id): Path<String>,
+    Query(q): Query<LogsQuery>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    ws.on_upgrade(move |socket| stream_logs(socket, instance_id, q.tail))
+}
+
+async fn stream_logs(mut socket: WebSocket, instance_id: String, tail: usize) {
+    // Подписываемся до отправки хвоста, чтобы не потерять строки между ними
+    let mut rx = logs::subscribe();
+
+    for line in logs::tail(&instance_id, tail).unwrap_or_default() {
+        if send_log_line(&mut socket, &line).await.is_err() {
+            return;
+        }
+    }
+
+    loop {
+        tokio::select! {
+            msg = rx.recv() => match msg {
+                Ok(live) if *live.instance_id == *instance_id => {
+                    if send_log_line(&mut socket, &live.line).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!("📜 Log stream '{}' lagged {} lines", instance_id, n);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+async fn send_log_line(socket: &mut WebSocket, line: &LogLine) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(line).unwrap_or_default();
+    socket.send(Message::Text(text)).await
+}
+
 async fn get_instance(
     State(s): State<AppState>,
     Path(instance_id): Path<String>,
@@ -307,12 +974,134 @@ async fn get_instance(
         .ok_or_else(|| ApiResult::<InstanceInfo>::err(StatusCode::NOT_FOUND, "Not found"))
 }
 
+/// Открытые и последние ордера инстанса. Остановленный инстанс тоже
+/// показывается, если у него остались ордера
+async fn instance_orders(
+    State(s): State<AppState>,
+    Path(instance_id): Path<String>,
+    Query(q): Query<InstanceOrdersQuery>,
+) -> (StatusCode, Json<ApiResult<InstanceOrders>>) {
+    let filter = OrderFilter {
+        instance_id: Some(&instance_id),
+        symbol: q.symbol.as_deref(),
+        open_only: false,
+    };
+    let (open, mut recent): (Vec<_>, Vec<_>) = s.orders.list(&filter)
+        .into_iter()
+        .partition(|o| !o.state.is_terminal());
+    if open.is_empty() && recent.is_empty() && s.runner.get(&instance_id).is_none() {
+        return ApiResult::err(StatusCode::NOT_FOUND, format!("Instance '{}' not found", instance_id));
+    }
+    recent.truncate(q.recent);
+    ApiResult::ok(InstanceOrders {
+        tag: instance_tag(&instance_id),
+        instance_id,
+        open,
+        recent,
+    })
+}
+
 // ═══════════════════════════════════════════════════════════
 // ИНСТАНСЫ
 // ═══════════════════════════════════════════════════════════
 
-async fn list_instances(State(s): State<AppState>) -> Json<Vec<InstanceInfo>> {
-    Json(s.runner.list())
+/// Инстансы с фильтрами и страницами. Сколько подошло под фильтр
+/// до limit/offset - в заголовке X-Total-Count
+async fn list_instances(
+    State(s): State<AppState>,
+    Query(q): Query<InstancesQuery>,
+) -> Response {
+    let sort = q.sort.as_deref().unwrap_or("instance_id");
+    let (field, descending) = match sort.strip_prefix('-') {
+        Some(field) => (field, true),
+        None => (sort, false),
+    };
+    let compare: fn(&InstanceInfo, &InstanceInfo) -> std::cmp::Ordering = match field {
+        "instance_id" => |a, b| a.instance_id.cmp(&b.instance_id),
+        "started_at" => |a, b| a.started_at.cmp(&b.started_at).then_with(|| a.instance_id.cmp(&b.instance_id)),
+        "symbol" => |a, b| a.symbol.cmp(&b.symbol).then_with(|| a.instance_id.cmp(&b.instance_id)),
+        "strategy" => |a, b| a.strategy_id.cmp(&b.strategy_id).then_with(|| a.instance_id.cmp(&b.instance_id)),
+        _ => {
+            return ApiResult::<()>::err(
+                StatusCode::BAD_REQUEST,
+                format!("Unknown sort '{}', use instance_id, started_at, symbol or strategy", field),
+            )
+            .into_response();
+        }
+    };
+
+    let mut list: Vec<InstanceInfo> = s.runner.list().into_iter()
+        .filter(|i| q.strategy.as_deref().is_none_or(|id| i.strategy_id == id))
+        .filter(|i| q.symbol.as_deref().is_none_or(|sym| i.symbol.eq_ignore_ascii_case(sym)))
+        .filter(|i| q.status.is_none_or(|status| i.status == status))
+        .filter(|i| q.group.as_deref().is_none_or(|g| i.options.group.as_deref() == Some(g)))
+        .collect();
+    list.sort_by(|a, b| if descending { compare(b, a) } else { compare(a, b) });
+
+    let total = list.len();
+    let page: Vec<InstanceInfo> = list.into_iter()
+        .skip(q.offset)
+        .take(q.limit.unwrap_or(usize::MAX))
+        .collect();
+    ([("x-total-count", total.to_string())], Json(page)).into_response()
+}
+
+/// Завершённые запуски инстансов, новые первыми
+async fn list_runs(Query(q): Query<RunsQuery>) -> Response {
+    // С history это запрос к SQLite
+    let rows = tokio::task::spawn_blocking(move || {
+        runs::list(q.strategy.as_deref(), q.symbol.as_deref(), q.limit)
+    })
+    .await;
+    match rows {
+        Ok(Ok(rows)) => Json::<Vec<RunRecord>>(rows).into_response(),
+        Ok(Err(e)) => ApiResult::<()>::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        Err(e) => ApiResult::<()>::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// Инстансы, снятые по stop_timeout_ms, чей поток ещё не вышел из run()
+async fn list_quarantined() -> Json<Vec<QuarantinedInstance>> {
+    Json(quarantine::list())
+}
+
+/// Потолки инстансов и ордеров в секунду, сколько работает и сколько ордеров срезано
+async fn capacity(State(s): State<AppState>) -> Json<CapacityStatus> {
+    Json(s.runner.capacity())
+}
+
+// ═══════════════════════════════════════════════════════════
+// ДИСК
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+pub struct PruneQuery {
+    /// Удалить весь кэш сборки, а не только устаревший и сверх квот
+    #[serde(default)]
+    pub all: bool,
+}
+
+/// Сколько занимает каждая стратегия (код, кэш, библиотека) и общий target/
+async fn disk_usage(State(s): State<AppState>) -> (StatusCode, Json<ApiResult<DiskUsage>>) {
+    let storage = s.storage.clone();
+    match tokio::task::spawn_blocking(move || storage.disk_usage()).await {
+        Ok(Ok(usage)) => ApiResult::ok(usage),
+        Ok(Err(e)) => ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        Err(e) => ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+/// Чистка кэша сборки сейчас, не дожидаясь janitor. Ждёт идущие сборки
+async fn disk_prune(
+    State(s): State<AppState>,
+    Query(q): Query<PruneQuery>,
+) -> (StatusCode, Json<ApiResult<PruneReport>>) {
+    let storage = s.storage.clone();
+    match tokio::task::spawn_blocking(move || storage.prune(q.all)).await {
+        Ok(Ok(report)) => ApiResult::ok(report),
+        Ok(Err(e)) => ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        Err(e) => ApiResult::err(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
 }
 
 // async fn get_instance(
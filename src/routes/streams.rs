@@ -0,0 +1,39 @@
+// src/routes/streams.rs
+
+use axum::{
+    routing::get,
+    extract::{Json, State},
+    Router,
+};
+
+use crate::exchange_data::{ExchangeSubscriptions, MarketDataStats, StreamSubscription};
+use crate::routes::AppState;
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/streams", get(list))
+        .route("/streams/exchange", get(exchange))
+        .route("/marketdata/stats", get(stats))
+        .with_state(state)
+}
+
+/// Подписки market data и кто их держит (API или инстансы стратегий)
+async fn list(State(s): State<AppState>) -> Json<Vec<StreamSubscription>> {
+    Json(s.market.subscriptions())
+}
+
+/// Что подписано на самой бирже по последнему LIST_SUBSCRIPTIONS.
+/// null - ответа ещё не было (сокет не молчал 5 секунд или не подключён)
+async fn exchange(State(s): State<AppState>) -> Json<Option<ExchangeSubscriptions>> {
+    Json(s.market.exchange_subscriptions())
+}
+
+/// Темп, время последнего события и ошибки разбора по символу и потоку:
+/// видно, что trade замолчал, пока bookTicker ещё идёт
+async fn stats(State(s): State<AppState>) -> Json<MarketDataStats> {
+    Json(s.market.stats().await)
+}
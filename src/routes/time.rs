@@ -0,0 +1,33 @@
+// src/routes/time.rs
+
+use axum::{
+    routing::{get, post},
+    extract::{State, Json},
+    http::StatusCode,
+    Router,
+};
+
+use crate::routes::{ApiResult, AppState};
+use crate::time_sync::TimeStatus;
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/time", get(status))
+        .route("/time/sync", post(sync_now))
+        .with_state(state)
+}
+
+async fn status(State(s): State<AppState>) -> Json<TimeStatus> {
+    Json(s.time_sync.status())
+}
+
+async fn sync_now(State(s): State<AppState>) -> (StatusCode, Json<ApiResult<TimeStatus>>) {
+    match s.time_sync.sync().await {
+        Ok(_) => ApiResult::ok(s.time_sync.status()),
+        Err(e) => ApiResult::err(StatusCode::BAD_GATEWAY, e.to_string()),
+    }
+}
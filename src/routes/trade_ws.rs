@@ -0,0 +1,25 @@
+// src/routes/trade_ws.rs
+
+use axum::{
+    routing::get,
+    extract::{Json, State},
+    Router,
+};
+
+use crate::exchange_trade::TradeQueueStats;
+use crate::routes::AppState;
+
+// ═══════════════════════════════════════════════════════════
+// ROUTER
+// ═══════════════════════════════════════════════════════════
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/tradews/queues", get(queues))
+        .with_state(state)
+}
+
+/// Очереди, backlog и inflight соединений trade WS, выброшенные по TTL
+async fn queues(State(s): State<AppState>) -> Json<TradeQueueStats> {
+    Json(s.trade.queue_stats())
+}
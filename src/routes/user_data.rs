@@ -0,0 +1,111 @@
+// src/routes/user_data.rs
+
+use axum::{
+    http::StatusCode,
+    routing::{get, post, delete},
+    extract::{Json, State, Path},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::routes::{ApiResult, AppState};
+use crate::user_data::{AccountOverview, StreamInfo};
+
+/// api_key или account (alias из keystore)
+#[derive(Deserialize)]
+pub struct StreamRequest {
+    #[serde(default)]
+    pub api_key: String,
+    pub account: Option<String>,
+}
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/userdata/streams", get(list_streams))
+        .route("/userdata/streams", post(start_stream))
+        .route("/userdata/streams/:id", delete(stop_stream))
+        .route("/userdata/overview", get(overview))
+        .with_state(state)
+}
+
+async fn list_streams(State(s): State<AppState>) -> Json<Vec<StreamInfo>> {
+    Json(s.user_data.list())
+}
+
+#[derive(Serialize)]
+struct Overview {
+    accounts: Vec<AccountOverview>,
+    totals: Totals,
+}
+
+/// Сумма по всем аккаунтам
+#[derive(Serialize)]
+struct Totals {
+    streams: usize,
+    connected: usize,
+    balances: BTreeMap<String, f64>,
+    unrealized_pnl: f64,
+    open_positions: usize,
+    /// Сумма recent_funding по активам
+    recent_funding: BTreeMap<String, f64>,
+}
+
+/// Консоль оператора для нескольких (суб)аккаунтов на одном ядре
+async fn overview(State(s): State<AppState>) -> Json<Overview> {
+    let aliases: HashMap<String, String> = s.keystore.iter()
+        .flat_map(|k| k.list())
+        .map(|k| (k.key, k.alias))
+        .collect();
+
+    let mut accounts = s.user_data.overview();
+    let mut totals = Totals {
+        streams: accounts.len(),
+        connected: 0,
+        balances: BTreeMap::new(),
+        unrealized_pnl: 0.0,
+        open_positions: 0,
+        recent_funding: BTreeMap::new(),
+    };
+    for account in &mut accounts {
+        account.alias = aliases.get(&account.stream.id).cloned();
+        totals.connected += account.stream.connected as usize;
+        for (asset, balance) in &account.balances {
+            *totals.balances.entry(asset.clone()).or_default() += balance;
+        }
+        totals.unrealized_pnl += account.unrealized_pnl;
+        totals.open_positions += account.positions.len();
+        for payment in &account.recent_funding {
+            *totals.recent_funding.entry(payment.asset.clone()).or_default() += payment.amount;
+        }
+    }
+
+    Json(Overview { accounts, totals })
+}
+
+async fn start_stream(
+    State(s): State<AppState>,
+    Json(req): Json<StreamRequest>,
+) -> (StatusCode, Json<ApiResult<StreamInfo>>) {
+    let api_key = match &req.account {
+        Some(account) => match s.keystore.as_ref().and_then(|k| k.get(account)) {
+            Some(creds) => creds.api_key,
+            None => return ApiResult::err(StatusCode::NOT_FOUND, format!("Unknown account '{}'", account)),
+        },
+        None => req.api_key,
+    };
+    match s.user_data.start_stream(&api_key) {
+        Ok(info) => ApiResult::created(info),
+        Err(e) => ApiResult::err(StatusCode::CONFLICT, e.to_string()),
+    }
+}
+
+async fn stop_stream(
+    State(s): State<AppState>,
+    Path(id): Path<String>,
+) -> (StatusCode, Json<ApiResult>) {
+    match s.user_data.stop_stream(&id).await {
+        Ok(_) => ApiResult::ok_empty(),
+        Err(e) => ApiResult::err(StatusCode::NOT_FOUND, e.to_string()),
+    }
+}
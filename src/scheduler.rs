@@ -0,0 +1,467 @@
+// src/scheduler.rs
+
+use anyhow::Context;
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc, Weekday};
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::alerts::{self, AlertLevel};
+use crate::files;
+use crate::redact;
+use crate::strategies::compile::{CompileQueue, JobStatus};
+use crate::strategies::manager::{InstanceOptions, StrategyRunner};
+use crate::strategies::schema;
+use crate::strategies::storage::StrategyStorage;
+
+/// Как часто сверяем расписания с часами
+const TICK: std::time::Duration = std::time::Duration::from_secs(1);
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    /// Файл с расписаниями (JSON)
+    pub path: String,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self { path: "./strategies/schedules.json".to_string() }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ПРАВИЛА
+// ═══════════════════════════════════════════════════════════
+
+/// Время суток UTC, "HH:MM"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay {
+    /// Минуты от полуночи
+    minutes: u32,
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let parsed = s.split_once(':').and_then(|(h, m)| {
+            if !(1..=2).contains(&h.len()) || m.len() != 2 {
+                return None;
+            }
+            let (h, m) = (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?);
+            (h < 24 && m < 60).then_some(h * 60 + m)
+        });
+        parsed
+            .map(|minutes| Self { minutes })
+            .ok_or_else(|| format!("invalid time '{}', expected HH:MM", s))
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(t: TimeOfDay) -> Self {
+        format!("{:02}:{:02}", t.minutes / 60, t.minutes % 60)
+    }
+}
+
+impl TimeOfDay {
+    fn on(self, day: NaiveDate) -> DateTime<Utc> {
+        let midnight = day.and_hms_opt(0, 0, 0).expect("valid midnight").and_utc();
+        midnight + chrono::Duration::minutes(self.minutes as i64)
+    }
+}
+
+/// Окно работы. stop <= start - окно через полночь (23:55 - 00:05)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Window {
+    pub start: TimeOfDay,
+    pub stop: TimeOfDay,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    /// В PUT /api/schedules/:id берётся из пути
+    #[serde(default)]
+    pub id: String,
+    pub strategy_id: String,
+    pub symbol: String,
+    /// Параметры запуска. Ключи - только через "account" (alias keystore):
+    /// файл расписаний не шифруется, секреты в нём были бы открытым текстом
+    #[serde(default)]
+    pub params: redact::Params,
+    #[serde(default)]
+    pub options: InstanceOptions,
+    pub windows: Vec<Window>,
+    /// "mon".."sun" - дни, в которые окно начинается. Пусто - каждый день
+    #[serde(default)]
+    pub weekdays: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Schedule {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.id.is_empty() || !self.id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            anyhow::bail!("Schedule id must be non-empty and contain only [A-Za-z0-9_-]");
+        }
+        if self.symbol.is_empty() || !self.symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+            anyhow::bail!("Invalid symbol '{}'", self.symbol);
+        }
+        if self.params.has_secrets() {
+            anyhow::bail!("Secret fields in params are not stored in schedules, pass keys via \"account\" (keystore alias)");
+        }
+        if self.windows.is_empty() {
+            anyhow::bail!("At least one window is required");
+        }
+        if let Some(w) = self.windows.iter().find(|w| w.start == w.stop) {
+            anyhow::bail!("Window start and stop are equal ({})", String::from(w.start));
+        }
+        for day in &self.weekdays {
+            day.parse::<Weekday>().map_err(|_| anyhow::anyhow!("Invalid weekday '{}'", day))?;
+        }
+        self.options.validate()
+    }
+
+    pub fn instance_id(&self) -> String {
+        format!("{}:{}", self.strategy_id, self.symbol.to_uppercase())
+    }
+
+    fn runs_on(&self, day: NaiveDate) -> bool {
+        self.weekdays.is_empty()
+            || self.weekdays.iter().any(|d| d.parse::<Weekday>().is_ok_and(|w| w == day.weekday()))
+    }
+
+    /// Интервалы окон, начинающихся в этот день
+    fn intervals(&self, day: NaiveDate) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        if !self.runs_on(day) {
+            return Vec::new();
+        }
+        self.windows
+            .iter()
+            .map(|w| {
+                let stop_day = if w.stop.minutes > w.start.minutes { day } else { day + Days::new(1) };
+                (w.start.on(day), w.stop.on(stop_day))
+            })
+            .collect()
+    }
+
+    /// Интервалы со вчерашнего дня (окно через полночь) на неделю вперёд
+    fn upcoming(&self, now: DateTime<Utc>) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let today = now.date_naive();
+        let mut all: Vec<_> = (0..=8)
+            .filter_map(|i| (today - Days::new(1)).checked_add_days(Days::new(i)))
+            .flat_map(|day| self.intervals(day))
+            .filter(|(_, stop)| *stop > now)
+            .collect();
+        all.sort_by_key(|(start, _)| *start);
+        all
+    }
+
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.upcoming(now).iter().any(|(start, _)| *start <= now)
+    }
+
+    /// (ближайший старт, ближайшая остановка), Unix ms
+    fn next(&self, now: DateTime<Utc>) -> (Option<i64>, Option<i64>) {
+        let upcoming = self.upcoming(now);
+        let next_start = upcoming.iter().find(|(start, _)| *start > now).map(|(start, _)| start.timestamp_millis());
+        let next_stop = match upcoming.iter().filter(|(start, _)| *start <= now).map(|(_, stop)| *stop).max() {
+            Some(stop) => Some(stop.timestamp_millis()),
+            None => upcoming.first().map(|(_, stop)| stop.timestamp_millis()),
+        };
+        (next_start, next_stop)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// СОСТОЯНИЕ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleAction {
+    /// "start" | "stop"
+    pub action: &'static str,
+    /// Unix ms
+    pub time: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct ScheduleState {
+    /// Было ли расписание в окне на прошлой проверке: действуем только на переходе,
+    /// чтобы не перезапускать инстанс, остановленный вручную посреди окна
+    active: bool,
+    last_action: Option<ScheduleAction>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduleInfo {
+    #[serde(flatten)]
+    pub schedule: Schedule,
+    pub active: bool,
+    pub next_start: Option<i64>,
+    pub next_stop: Option<i64>,
+    pub last_action: Option<ScheduleAction>,
+}
+
+// ═══════════════════════════════════════════════════════════
+// ПЛАНИРОВЩИК
+// ═══════════════════════════════════════════════════════════
+
+/// Запускает и останавливает инстансы по расписаниям (UTC)
+pub struct Scheduler {
+    path: String,
+    schedules: DashMap<String, Schedule>,
+    states: DashMap<String, ScheduleState>,
+    /// Действия одного расписания идут по очереди: stop, пришедший пока
+    /// start ещё собирает стратегию, иначе не нашёл бы инстанс
+    actions: DashMap<String, Arc<tokio::sync::Mutex<()>>>,
+    runner: Arc<StrategyRunner>,
+    storage: Arc<StrategyStorage>,
+    compiler: Arc<CompileQueue>,
+    file_lock: Mutex<()>,
+}
+
+impl Scheduler {
+    pub fn new(
+        config: &SchedulerConfig,
+        runner: Arc<StrategyRunner>,
+        storage: Arc<StrategyStorage>,
+        compiler: Arc<CompileQueue>,
+    ) -> anyhow::Result<Arc<Self>> {
+        let mut stored = BTreeMap::<String, Schedule>::new();
+        if Path::new(&config.path).exists() {
+            let content = std::fs::read_to_string(&config.path)?;
+            stored = serde_json::from_str(&content)
+                .with_context(|| format!("Invalid schedules file '{}'", config.path))?;
+        }
+        // Файл мог остаться от версии, которая хранила ключи в params: такое
+        // расписание выключается, а файл сразу перезаписывается уже с масками
+        let mut purged = false;
+        for schedule in stored.values_mut().filter(|s| s.params.has_secrets()) {
+            tracing::error!(
+                "❌ Schedule '{}' has secret fields in params, disabled: pass keys via \"account\"",
+                schedule.id
+            );
+            schedule.enabled = false;
+            purged = true;
+        }
+        let schedules: DashMap<_, _> = stored.into_iter().collect();
+        tracing::info!("🗓️ Scheduler loaded: {} schedules", schedules.len());
+
+        let scheduler = Arc::new(Self {
+            path: config.path.clone(),
+            schedules,
+            states: DashMap::new(),
+            actions: DashMap::new(),
+            runner,
+            storage,
+            compiler,
+            file_lock: Mutex::new(()),
+        });
+        if purged {
+            scheduler.save()?;
+        }
+
+        let weak = Arc::downgrade(&scheduler);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(TICK).await;
+                let Some(scheduler) = weak.upgrade() else { break };
+                scheduler.tick(Utc::now());
+            }
+        });
+
+        Ok(scheduler)
+    }
+
+    pub fn list(&self) -> Vec<ScheduleInfo> {
+        let mut list: Vec<_> = self.schedules.iter().map(|s| self.info(s.value())).collect();
+        list.sort_by(|a, b| a.schedule.id.cmp(&b.schedule.id));
+        list
+    }
+
+    pub fn get(&self, id: &str) -> Option<ScheduleInfo> {
+        self.schedules.get(id).map(|s| self.info(s.value()))
+    }
+
+    /// Создаёт или заменяет расписание. Действие - на ближайшей проверке
+    pub fn put(self: &Arc<Self>, mut schedule: Schedule) -> anyhow::Result<ScheduleInfo> {
+        schedule.validate()?;
+        schedule.symbol = schedule.symbol.to_uppercase();
+        if !self.storage.exists(&schedule.strategy_id) {
+            anyhow::bail!("Strategy '{}' not found", schedule.strategy_id);
+        }
+        let id = schedule.id.clone();
+        let instance_id = schedule.instance_id();
+        let replaced = self.schedules.insert(id.clone(), schedule);
+        self.save()?;
+
+        // Расписание перенесли на другую стратегию/символ: старый инстанс
+        // останавливаем, новый запустится на ближайшей проверке
+        if let Some(old) = replaced.filter(|old| old.instance_id() != instance_id) {
+            if self.states.remove(&id).is_some_and(|(_, st)| st.active) {
+                self.spawn_action(old, false);
+            }
+        }
+        tracing::info!("🗓️ Schedule '{}' saved", id);
+        self.get(&id).context("schedule vanished")
+    }
+
+    /// Удаляет расписание; инстанс, запущенный им и ещё работающий, останавливается
+    pub fn remove(self: &Arc<Self>, id: &str) -> anyhow::Result<()> {
+        let Some((_, schedule)) = self.schedules.remove(id) else {
+            anyhow::bail!("Schedule '{}' not found", id);
+        };
+        self.save()?;
+        if self.states.remove(id).is_some_and(|(_, st)| st.active) {
+            self.spawn_action(schedule, false);
+        }
+        tracing::info!("🗓️ Schedule '{}' removed", id);
+        Ok(())
+    }
+
+    fn info(&self, schedule: &Schedule) -> ScheduleInfo {
+        let now = Utc::now();
+        let (next_start, next_stop) = if schedule.enabled { schedule.next(now) } else { (None, None) };
+        let state = self.states.get(&schedule.id).map(|s| s.clone()).unwrap_or_default();
+        ScheduleInfo {
+            schedule: schedule.clone(),
+            active: state.active,
+            next_start,
+            next_stop,
+            last_action: state.last_action,
+        }
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        let _guard = self.file_lock.lock().unwrap();
+        let stored: BTreeMap<String, Schedule> = self.schedules
+            .iter()
+            .map(|s| (s.key().clone(), s.value().clone()))
+            .collect();
+
+        files::write_atomic(&self.path, serde_json::to_string_pretty(&stored)?.as_bytes())?;
+        Ok(())
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // ИСПОЛНЕНИЕ
+    // ═══════════════════════════════════════════════════════════
+
+    fn tick(self: &Arc<Self>, now: DateTime<Utc>) {
+        let schedules: Vec<Schedule> = self.schedules.iter().map(|s| s.value().clone()).collect();
+        for schedule in schedules {
+            let active = schedule.enabled && schedule.is_active(now);
+            let mut state = self.states.entry(schedule.id.clone()).or_default();
+            if state.active == active {
+                continue;
+            }
+            state.active = active;
+            drop(state);
+            self.spawn_action(schedule, active);
+        }
+    }
+
+    /// Должен ли инстанс schedule сейчас работать
+    fn wanted(&self, schedule: &Schedule) -> bool {
+        self.states.get(&schedule.id).is_some_and(|s| s.active)
+            && self.schedules.get(&schedule.id).is_some_and(|s| s.instance_id() == schedule.instance_id())
+    }
+
+    fn spawn_action(self: &Arc<Self>, schedule: Schedule, start: bool) {
+        let scheduler = self.clone();
+        let queue = self.actions.entry(schedule.id.clone()).or_default().clone();
+        tokio::spawn(async move {
+            let _turn = queue.lock().await;
+            let action = if start { "start" } else { "stop" };
+            // Пока ждали очереди, окно успело смениться: действие устарело,
+            // нужное уже поставлено следом
+            if scheduler.wanted(&schedule) != start {
+                tracing::info!("🗓️ Schedule '{}': {} '{}' superseded", schedule.id, action, schedule.instance_id());
+                return;
+            }
+            tracing::info!("🗓️ Schedule '{}': {} '{}'", schedule.id, action, schedule.instance_id());
+
+            let result = if start {
+                scheduler.start(&schedule).await
+            } else {
+                scheduler.stop(&schedule).await
+            };
+            if let Err(e) = &result {
+                alerts::emit(
+                    AlertLevel::Warning,
+                    "scheduler",
+                    format!("Schedule '{}' failed to {} '{}': {}", schedule.id, action, schedule.instance_id(), e),
+                );
+            }
+
+            if let Some(mut state) = scheduler.states.get_mut(&schedule.id) {
+                state.last_action = Some(ScheduleAction {
+                    action,
+                    time: Utc::now().timestamp_millis(),
+                    error: result.err().map(|e| e.to_string()),
+                });
+            }
+        });
+    }
+
+    async fn start(&self, schedule: &Schedule) -> anyhow::Result<()> {
+        let lib_path = self.lib_path(&schedule.strategy_id).await?;
+
+        // Схема могла поменяться после сохранения расписания
+        if let Some(params_schema) = self.storage.get_schema(&schedule.strategy_id)? {
+            let params = schedule.params.expose();
+            let params = if params.is_null() { serde_json::json!({}) } else { params.clone() };
+            let errors = schema::validate(&params_schema, &params);
+            if !errors.is_empty() {
+                anyhow::bail!("Invalid params: {}", errors.join("; "));
+            }
+        }
+
+        self.runner
+            .start(
+                schedule.strategy_id.clone(),
+                schedule.symbol.clone(),
+                lib_path,
+                schedule.params.expose().clone(),
+                schedule.options.clone(),
+            )
+            .await
+            .map(|_| ())
+    }
+
+    async fn stop(&self, schedule: &Schedule) -> anyhow::Result<()> {
+        // После swap инстанс работает в другом слоте
+        let Some(instance_id) = self.runner.active_slot(&schedule.instance_id()) else {
+            // Уже остановлен вручную или упал
+            return Ok(());
+        };
+        self.runner.stop(&instance_id).await
+    }
+
+    /// Собранная библиотека; если её нет - собирает
+    async fn lib_path(&self, strategy_id: &str) -> anyhow::Result<PathBuf> {
+        if let Ok(path) = self.storage.get_lib_path(strategy_id) {
+            return Ok(path);
+        }
+        let job = self.compiler.enqueue(strategy_id)?;
+        match self.compiler.wait(job.id).await {
+            Some(job) if job.status == JobStatus::Done => self.storage.get_lib_path(strategy_id),
+            Some(job) => anyhow::bail!("Compilation failed: {}", job.errors.join("; ")),
+            None => anyhow::bail!("Compile job lost"),
+        }
+    }
+}
@@ -4,14 +4,37 @@
 
 // pub use storage::{Strategy, StrategyStorage};
 // pub use manager::StrategyRunner;
-// pub use order::init_trading;
+// pub use order::{init_trading, init_risk};
 
 
 pub mod storage;
 pub mod manager;
 pub mod order;
+pub mod breaker;
+pub mod logs;
+pub mod staging;
+pub mod replay;
+pub mod compile;
+pub mod diagnostics;
+pub mod schema;
+pub mod dry_run;
+pub mod intents;
+pub mod stats;
+pub mod watchdog;
+pub mod quarantine;
+pub mod timers;
+pub mod streams;
+pub mod dedup;
+pub mod retry;
+pub mod groups;
+pub mod capacity;
+pub mod sandbox;
+pub mod disk;
+pub mod shadow;
+pub mod metrics;
+pub mod runs;
 
 // Re-exports
 pub use storage::StrategyStorage;
 pub use manager::{StrategyRunner};
-pub use order::init_trading;
\ No newline at end of file
+pub use order::{init_trading, init_risk, init_breaker, init_orders};
\ No newline at end of file
@@ -0,0 +1,168 @@
+// src/strategies/breaker.rs
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::alerts::{self, AlertLevel};
+
+// ═══════════════════════════════════════════════════════════
+// CIRCUIT BREAKER
+// ═══════════════════════════════════════════════════════════
+
+/// Торговля инстанса приостановлена circuit breaker'ом
+pub const ERR_CIRCUIT_OPEN: i32 = -9300;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct BreakerConfig {
+    /// Сколько ошибок подряд выдерживаем до паузы (0 = выключено)
+    pub max_consecutive_failures: u32,
+}
+
+impl Default for BreakerConfig {
+    fn default() -> Self {
+        Self { max_consecutive_failures: 10 }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BreakerState {
+    pub consecutive_failures: u32,
+    pub trading_paused: bool,
+    pub pause_reason: Option<String>,
+    pub paused_at: Option<i64>,
+}
+
+/// Локальные отказы ядра (риск, rate limit, breaker) на биржу не уходят
+/// и в счётчик ошибок не попадают
+fn is_local_reject(code: i32) -> bool {
+    (-9399..=-9100).contains(&code)
+}
+
+/// Ставит торговлю инстанса на паузу после N неудачных ордеров подряд,
+/// чтобы неверный api_key не слал тысячи заведомо падающих запросов.
+/// Отмены ордеров не блокируются.
+pub struct CircuitBreaker {
+    config: BreakerConfig,
+    states: DashMap<String, BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: BreakerConfig) -> Arc<Self> {
+        Arc::new(Self {
+            config,
+            states: DashMap::new(),
+        })
+    }
+
+    /// Err(reason) если торговля инстанса на паузе
+    pub fn check(&self, instance_id: &str) -> Result<(), String> {
+        match self.states.get(instance_id) {
+            Some(s) if s.trading_paused => Err(s.pause_reason.clone().unwrap_or_default()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn on_success(&self, instance_id: &str) {
+        if let Some(mut s) = self.states.get_mut(instance_id) {
+            s.consecutive_failures = 0;
+        }
+    }
+
+    pub fn on_failure(&self, instance_id: &str, error_code: i32) {
+        if self.config.max_consecutive_failures == 0 || is_local_reject(error_code) {
+            return;
+        }
+
+        let mut s = self.states.entry(instance_id.to_string()).or_default();
+        s.consecutive_failures += 1;
+
+        if s.trading_paused || s.consecutive_failures < self.config.max_consecutive_failures {
+            return;
+        }
+
+        let reason = format!(
+            "{} consecutive order failures (last error {})",
+            s.consecutive_failures, error_code
+        );
+        s.trading_paused = true;
+        s.pause_reason = Some(reason.clone());
+        s.paused_at = Some(chrono::Utc::now().timestamp());
+        drop(s);
+
+        alerts::emit(
+            AlertLevel::Critical,
+            "strategy",
+            format!("Trading paused for '{}': {}", instance_id, reason),
+        );
+    }
+
+    /// Ручная пауза (например, всей группы). false если уже на паузе.
+    /// Отказы в это время в счётчик ошибок не идут: ERR_CIRCUIT_OPEN локальный
+    pub fn pause(&self, instance_id: &str, reason: &str) -> bool {
+        let mut s = self.states.entry(instance_id.to_string()).or_default();
+        if s.trading_paused {
+            return false;
+        }
+        s.trading_paused = true;
+        s.pause_reason = Some(reason.to_string());
+        s.paused_at = Some(chrono::Utc::now().timestamp());
+        tracing::info!("⏸️ Trading paused for '{}': {}", instance_id, reason);
+        true
+    }
+
+    /// Снимает паузу. false если инстанс не был на паузе.
+    pub fn resume(&self, instance_id: &str) -> bool {
+        match self.states.get_mut(instance_id) {
+            Some(mut s) if s.trading_paused => {
+                *s = BreakerState::default();
+                tracing::info!("▶️ Trading resumed for '{}'", instance_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn state(&self, instance_id: &str) -> BreakerState {
+        self.states
+            .get(instance_id)
+            .map(|s| s.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn reset(&self, instance_id: &str) {
+        self.states.remove(instance_id);
+    }
+
+    /// Состояние from переходит к instance_id (blue-green swap)
+    pub fn inherit(&self, instance_id: &str, from: &str) {
+        match self.states.get(from).map(|s| s.clone()) {
+            Some(state) => {
+                self.states.insert(instance_id.to_string(), state);
+            }
+            None => self.reset(instance_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swap_keeps_tripped_breaker() {
+        let breaker = CircuitBreaker::new(BreakerConfig { max_consecutive_failures: 2 });
+        breaker.on_failure("a", -2019);
+        breaker.on_failure("a", -2019);
+        assert!(breaker.check("a").is_err());
+
+        breaker.inherit("a@green", "a");
+        assert!(breaker.check("a@green").is_err());
+
+        // Слот без состояния наследует чистый breaker
+        breaker.on_failure("b@green", -2019);
+        breaker.inherit("b@green", "b");
+        assert_eq!(breaker.state("b@green").consecutive_failures, 0);
+    }
+}
@@ -0,0 +1,150 @@
+// src/strategies/capacity.rs
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+// ═══════════════════════════════════════════════════════════
+// ПОТОЛКИ НАГРУЗКИ
+// ═══════════════════════════════════════════════════════════
+//
+// Скрипт, который в цикле запускает стратегию на всех символах, или
+// стратегия, стреляющая ордером на каждый тик, кладут машину и аккаунт
+// раньше, чем сработает rate limiter Binance. Здесь общие потолки: число
+// работающих инстансов (всего и на стратегию) и ордеров в секунду от всех
+// стратегий вместе. Ордера через HTTP /order/* не ограничиваются: это
+// ручное управление, в том числе аварийное.
+
+/// Ордер не отправлен: превышен общий потолок ордеров в секунду
+pub const ERR_THROUGHPUT_LIMIT: i32 = -9303;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CapacityConfig {
+    /// Работающих инстансов всего (0 - без ограничения)
+    pub max_instances: usize,
+    /// Работающих инстансов одной стратегии (0 - без ограничения)
+    pub max_instances_per_strategy: usize,
+    /// Live-ордеров в секунду от всех стратегий и алгоритмов ядра
+    /// (0 - без ограничения). Paper, replay и dry run не считаются
+    pub max_orders_per_sec: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CapacityStatus {
+    #[serde(flatten)]
+    pub config: CapacityConfig,
+    pub running: usize,
+    /// Ордеров, отклонённых с ERR_THROUGHPUT_LIMIT с запуска
+    pub throttled: u64,
+}
+
+/// Токены на секунду вперёд: пачка до max_orders_per_sec проходит сразу
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    /// Пополняет токены за прошедшее время и берёт один
+    fn take(&mut self, limit: f64, now: Instant) -> bool {
+        self.tokens = (self.tokens + now.duration_since(self.last).as_secs_f64() * limit).min(limit);
+        self.last = now;
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+static CONFIG: OnceLock<CapacityConfig> = OnceLock::new();
+static BUCKET: Mutex<Option<Bucket>> = Mutex::new(None);
+static THROTTLED: AtomicU64 = AtomicU64::new(0);
+
+pub fn init(config: CapacityConfig) {
+    CONFIG.set(config).ok();
+}
+
+fn config() -> CapacityConfig {
+    CONFIG.get().cloned().unwrap_or_default()
+}
+
+/// Можно ли запустить ещё один инстанс стратегии. running - сколько работает
+/// всего, running_strategy - сколько из них этой стратегии
+pub fn check_start(strategy_id: &str, running: usize, running_strategy: usize) -> anyhow::Result<()> {
+    let config = config();
+    if config.max_instances > 0 && running >= config.max_instances {
+        anyhow::bail!("Instance limit reached: {} running (capacity.max_instances)", running);
+    }
+    if config.max_instances_per_strategy > 0 && running_strategy >= config.max_instances_per_strategy {
+        anyhow::bail!(
+            "Instance limit for '{}' reached: {} running (capacity.max_instances_per_strategy)",
+            strategy_id, running_strategy,
+        );
+    }
+    Ok(())
+}
+
+/// Берёт токен на live-ордер. false - потолок ордеров в секунду исчерпан
+pub fn try_order() -> bool {
+    let limit = CONFIG.get().map_or(0, |c| c.max_orders_per_sec);
+    if limit == 0 {
+        return true;
+    }
+    let (limit, now) = (limit as f64, Instant::now());
+    let mut bucket = BUCKET.lock().unwrap();
+    let taken = bucket.get_or_insert(Bucket { tokens: limit, last: now }).take(limit, now);
+    if !taken {
+        THROTTLED.fetch_add(1, Ordering::Relaxed);
+    }
+    taken
+}
+
+pub fn status(running: usize) -> CapacityStatus {
+    CapacityStatus {
+        config: config(),
+        running,
+        throttled: THROTTLED.load(Ordering::Relaxed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn burst_up_to_limit_then_refill() {
+        let start = Instant::now();
+        let mut bucket = Bucket { tokens: 5.0, last: start };
+
+        for _ in 0..5 {
+            assert!(bucket.take(5.0, start));
+        }
+        assert!(!bucket.take(5.0, start));
+        // Отказ токен не тратит: через 200ms при 5/s набирается ровно один
+        assert!(bucket.take(5.0, start + Duration::from_millis(200)));
+        assert!(!bucket.take(5.0, start + Duration::from_millis(200)));
+    }
+
+    #[test]
+    fn idle_time_does_not_exceed_limit() {
+        let start = Instant::now();
+        let mut bucket = Bucket { tokens: 0.0, last: start };
+        let later = start + Duration::from_secs(60);
+
+        for _ in 0..3 {
+            assert!(bucket.take(3.0, later));
+        }
+        assert!(!bucket.take(3.0, later));
+    }
+
+    #[test]
+    fn no_limit_without_config() {
+        // capacity::init в тестах не вызывается: потолок выключен
+        assert!((0..1_000).all(|_| try_order()));
+        assert!(check_start("s", 1_000, 1_000).is_ok());
+    }
+}
@@ -0,0 +1,229 @@
+// src/strategies/compile.rs
+
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::{Notify, Semaphore};
+
+use crate::strategies::diagnostics::Diagnostic;
+use crate::strategies::storage::{CompileConfig, StrategyStorage};
+
+/// Сколько завершённых заданий помним для GET /api/compile-jobs
+const MAX_JOBS: usize = 100;
+
+/// Хвост вывода cargo на задание
+const MAX_OUTPUT_LINES: usize = 5000;
+
+// ═══════════════════════════════════════════════════════════
+// ЗАДАНИЯ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn finished(self) -> bool {
+        matches!(self, JobStatus::Done | JobStatus::Failed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompileJob {
+    pub id: u64,
+    pub strategy_id: String,
+    pub status: JobStatus,
+    /// Ошибки компилятора (или причина, по которой cargo не запустился)
+    pub errors: Vec<String>,
+    /// Ошибки и предупреждения с позициями в коде стратегии (для подсветки в редакторе)
+    pub diagnostics: Vec<Diagnostic>,
+    /// Номер первой строки в output: старые строки сверх MAX_OUTPUT_LINES выброшены
+    pub output_offset: usize,
+    /// Строки stderr cargo по мере сборки
+    pub output: Vec<String>,
+    pub queued_at: i64,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+}
+
+impl CompileJob {
+    /// Копия задания со строками вывода начиная с since (для опроса прогресса)
+    pub fn since(&self, since: usize) -> Self {
+        let skip = since.saturating_sub(self.output_offset).min(self.output.len());
+        Self {
+            output_offset: self.output_offset + skip,
+            output: self.output[skip..].to_vec(),
+            ..self.clone()
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ОЧЕРЕДЬ
+// ═══════════════════════════════════════════════════════════
+
+/// Фоновая компиляция стратегий: HTTP-обработчик только ставит задание,
+/// cargo build идёт в spawn_blocking не больше max_parallel одновременно
+pub struct CompileQueue {
+    storage: Arc<StrategyStorage>,
+    slots: Semaphore,
+    jobs: DashMap<u64, CompileJob>,
+    next_id: AtomicU64,
+    /// Будит wait() при завершении любого задания
+    finished: Notify,
+}
+
+impl CompileQueue {
+    pub fn new(storage: Arc<StrategyStorage>, config: &CompileConfig) -> Arc<Self> {
+        Arc::new(Self {
+            storage,
+            slots: Semaphore::new(config.max_parallel.max(1)),
+            jobs: DashMap::new(),
+            next_id: AtomicU64::new(1),
+            finished: Notify::new(),
+        })
+    }
+
+    /// Ставит стратегию в очередь. Если она уже ждёт сборки - возвращает
+    /// существующее задание: вторая сборка того же кода ничего не даст
+    pub fn enqueue(self: &Arc<Self>, strategy_id: &str) -> anyhow::Result<CompileJob> {
+        if !self.storage.exists(strategy_id) {
+            anyhow::bail!("Strategy '{}' not found", strategy_id);
+        }
+        if let Some(job) = self.jobs.iter().find(|j| j.strategy_id == strategy_id && j.status == JobStatus::Queued) {
+            return Ok(job.clone());
+        }
+
+        self.prune();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let job = CompileJob {
+            id,
+            strategy_id: strategy_id.to_string(),
+            status: JobStatus::Queued,
+            errors: Vec::new(),
+            diagnostics: Vec::new(),
+            output_offset: 0,
+            output: Vec::new(),
+            queued_at: chrono::Utc::now().timestamp_millis(),
+            started_at: None,
+            finished_at: None,
+        };
+        self.jobs.insert(id, job.clone());
+        tracing::info!("📦 Compile job #{} queued for '{}'", id, strategy_id);
+
+        let queue = self.clone();
+        tokio::spawn(async move { queue.run(id).await });
+        Ok(job)
+    }
+
+    pub fn get(&self, id: u64) -> Option<CompileJob> {
+        self.jobs.get(&id).map(|j| j.clone())
+    }
+
+    /// Последнее задание стратегии без вывода (None - с запуска ядра не собиралась)
+    pub fn latest(&self, strategy_id: &str) -> Option<CompileJob> {
+        self.jobs.iter()
+            .filter(|j| j.strategy_id == strategy_id)
+            .max_by_key(|j| j.id)
+            .map(|j| j.since(usize::MAX))
+    }
+
+    /// Задания без вывода, новые первыми
+    pub fn list(&self) -> Vec<CompileJob> {
+        let mut jobs: Vec<_> = self.jobs.iter().map(|j| j.since(usize::MAX)).collect();
+        jobs.sort_by_key(|j| std::cmp::Reverse(j.id));
+        jobs
+    }
+
+    /// Ждёт завершения задания (не блокируя worker)
+    pub async fn wait(&self, id: u64) -> Option<CompileJob> {
+        loop {
+            let notified = self.finished.notified();
+            let job = self.get(id)?;
+            if job.status.finished() {
+                return Some(job);
+            }
+            notified.await;
+        }
+    }
+
+    /// Синхронная работа с кодом стратегии (cargo check/clippy, rustfmt)
+    /// в spawn_blocking под тем же лимитом max_parallel, что и сборки
+    pub async fn run_blocking<T, F>(&self, f: F) -> anyhow::Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&StrategyStorage) -> T + Send + 'static,
+    {
+        let _permit = self.slots.acquire().await?;
+        let storage = self.storage.clone();
+        Ok(tokio::task::spawn_blocking(move || f(&storage)).await?)
+    }
+
+    async fn run(self: Arc<Self>, id: u64) {
+        let Ok(_permit) = self.slots.acquire().await else { return };
+        let Some(strategy_id) = self.update(id, |job| {
+            job.status = JobStatus::Running;
+            job.started_at = Some(chrono::Utc::now().timestamp_millis());
+        }) else { return };
+
+        let queue = self.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            queue.storage.compile(&strategy_id, &mut |line| {
+                queue.update(id, |job| {
+                    job.output.push(line.to_string());
+                    if job.output.len() > MAX_OUTPUT_LINES {
+                        job.output.remove(0);
+                        job.output_offset += 1;
+                    }
+                });
+            })
+        })
+        .await;
+
+        self.update(id, |job| {
+            job.finished_at = Some(chrono::Utc::now().timestamp_millis());
+            match result {
+                Ok(Ok(r)) => {
+                    job.status = if r.success { JobStatus::Done } else { JobStatus::Failed };
+                    job.errors = r.errors;
+                    job.diagnostics = r.diagnostics;
+                }
+                Ok(Err(e)) => {
+                    job.status = JobStatus::Failed;
+                    job.errors = vec![e.to_string()];
+                }
+                Err(e) => {
+                    job.status = JobStatus::Failed;
+                    job.errors = vec![format!("Compile task panicked: {}", e)];
+                }
+            }
+            tracing::info!("📦 Compile job #{} for '{}': {:?}", id, job.strategy_id, job.status);
+        });
+        self.finished.notify_waiters();
+    }
+
+    /// Меняет задание, возвращает id его стратегии
+    fn update(&self, id: u64, f: impl FnOnce(&mut CompileJob)) -> Option<String> {
+        let mut job = self.jobs.get_mut(&id)?;
+        f(&mut job);
+        Some(job.strategy_id.clone())
+    }
+
+    /// Забывает самые старые завершённые задания
+    fn prune(&self) {
+        let mut finished: Vec<u64> = self.jobs.iter().filter(|j| j.status.finished()).map(|j| j.id).collect();
+        if finished.len() < MAX_JOBS {
+            return;
+        }
+        finished.sort_unstable();
+        for id in &finished[..finished.len() + 1 - MAX_JOBS] {
+            self.jobs.remove(id);
+        }
+    }
+}
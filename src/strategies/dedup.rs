@@ -0,0 +1,164 @@
+// src/strategies/dedup.rs
+
+use dashmap::DashMap;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::sync::{LazyLock, OnceLock};
+
+// ═══════════════════════════════════════════════════════════
+// ПОДАВЛЕНИЕ ДУБЛЕЙ
+// ═══════════════════════════════════════════════════════════
+//
+// Стратегия может выстрелить дважды (два ENTRY-триггера в одну
+// миллисекунду на границе часов). Одинаковый (symbol, side, type,
+// price, qty) ордер того же инстанса внутри окна отклоняется локально,
+// до risk и отправки.
+
+/// Повтор ордера внутри окна дедупликации
+pub const ERR_DUPLICATE_ORDER: i32 = -9302;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DedupConfig {
+    /// Окно по умолчанию для всех инстансов (0 = выключено).
+    /// Запуск может задать своё через dedup_window_ms
+    pub window_ms: u64,
+}
+
+/// Лимит запомненных ордеров инстанса: при ордере каждый тик окно
+/// не должно превращаться в линейный поиск по тысячам записей
+const MAX_RECENT: usize = 256;
+
+#[derive(Clone, PartialEq)]
+struct OrderKey {
+    symbol: String,
+    side: String,
+    order_type: u8,
+    price: u64,
+    quantity: u64,
+}
+
+struct Window {
+    window_ns: u64,
+    /// (ордер, время отправки в ns), старые впереди
+    recent: VecDeque<(OrderKey, u64)>,
+}
+
+static CONFIG: OnceLock<DedupConfig> = OnceLock::new();
+static WINDOWS: LazyLock<DashMap<String, Window>> = LazyLock::new(DashMap::new);
+
+pub fn init(config: DedupConfig) {
+    CONFIG.set(config).ok();
+}
+
+/// Включает окно для инстанса: своё из запроса запуска или из конфига
+pub fn register(instance_id: &str, window_ms: Option<u64>) {
+    let window_ms = window_ms.unwrap_or_else(|| CONFIG.get().map(|c| c.window_ms).unwrap_or(0));
+    if window_ms == 0 {
+        return;
+    }
+    WINDOWS.insert(instance_id.to_string(), Window {
+        window_ns: window_ms * 1_000_000,
+        recent: VecDeque::new(),
+    });
+}
+
+pub fn unregister(instance_id: &str) {
+    WINDOWS.remove(instance_id);
+}
+
+/// true - такой же ордер инстанса уже был внутри окна, этот отклонить.
+/// Иначе ордер запоминается. now_ns - часы инстанса (виртуальные в replay)
+pub fn is_duplicate(
+    instance_id: &str,
+    symbol: &str,
+    side: &str,
+    order_type: u8,
+    price: f64,
+    quantity: f64,
+    now_ns: u64,
+) -> bool {
+    if WINDOWS.is_empty() {
+        return false;
+    }
+    let Some(mut window) = WINDOWS.get_mut(instance_id) else { return false };
+    let window_ns = window.window_ns;
+    while window.recent.front().is_some_and(|(_, t)| now_ns.saturating_sub(*t) >= window_ns) {
+        window.recent.pop_front();
+    }
+
+    let key = OrderKey {
+        symbol: symbol.to_uppercase(),
+        side: side.to_uppercase(),
+        order_type,
+        // MARKET без цены: цена в ключ не входит
+        price: if order_type == 1 { 0 } else { price.to_bits() },
+        quantity: quantity.to_bits(),
+    };
+    if window.recent.iter().any(|(k, _)| *k == key) {
+        return true;
+    }
+    if window.recent.len() >= MAX_RECENT {
+        window.recent.pop_front();
+    }
+    window.recent.push_back((key, now_ns));
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: u64 = 1_000_000;
+
+    fn order(instance_id: &str, price: f64, now_ns: u64) -> bool {
+        is_duplicate(instance_id, "BTCUSDT", "BUY", 0, price, 0.01, now_ns)
+    }
+
+    #[test]
+    fn same_order_inside_window_is_duplicate() {
+        register("dedup-window", Some(100));
+
+        assert!(!order("dedup-window", 65000.0, 0));
+        assert!(order("dedup-window", 65000.0, 50 * MS));
+        // Другая цена - другой ордер
+        assert!(!order("dedup-window", 65001.0, 50 * MS));
+        // Регистр символа и стороны не важен
+        assert!(is_duplicate("dedup-window", "btcusdt", "buy", 0, 65000.0, 0.01, 60 * MS));
+        // Окно отсчитывается от первого ордера
+        assert!(!order("dedup-window", 65000.0, 100 * MS));
+    }
+
+    #[test]
+    fn market_orders_ignore_price() {
+        register("dedup-market", Some(100));
+
+        assert!(!is_duplicate("dedup-market", "BTCUSDT", "SELL", 1, 0.0, 0.01, 0));
+        assert!(is_duplicate("dedup-market", "BTCUSDT", "SELL", 1, 65000.0, 0.01, MS));
+        assert!(!is_duplicate("dedup-market", "BTCUSDT", "SELL", 1, 0.0, 0.02, MS));
+    }
+
+    #[test]
+    fn disabled_window_lets_everything_through() {
+        register("dedup-off", Some(0));
+        assert!(!order("dedup-off", 65000.0, 0));
+        assert!(!order("dedup-off", 65000.0, 0));
+
+        register("dedup-stopped", Some(100));
+        assert!(!order("dedup-stopped", 65000.0, 0));
+        unregister("dedup-stopped");
+        assert!(!order("dedup-stopped", 65000.0, 0));
+    }
+
+    #[test]
+    fn window_keeps_at_most_max_recent_orders() {
+        register("dedup-full", Some(1_000));
+
+        for i in 0..=MAX_RECENT {
+            assert!(!order("dedup-full", 1.0 + i as f64, 0));
+        }
+        // Самый старый вытеснен, последний помнится
+        assert!(!order("dedup-full", 1.0, 0));
+        assert!(order("dedup-full", 1.0 + MAX_RECENT as f64, 0));
+    }
+}
@@ -0,0 +1,145 @@
+// src/strategies/diagnostics.rs
+
+use serde::{Deserialize, Serialize};
+
+/// Сколько строк добавляет storage перед кодом пользователя в src/lib.rs
+/// ("mod types;", "use types::*;", пустая строка)
+pub const PRELUDE_LINES: usize = 3;
+
+// ═══════════════════════════════════════════════════════════
+// ДИАГНОСТИКА
+// ═══════════════════════════════════════════════════════════
+
+/// Сообщение компилятора в координатах кода пользователя (как он его прислал)
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    /// error, warning, note, help
+    pub level: String,
+    pub message: String,
+    /// Код ошибки rustc/clippy (E0425, clippy::needless_return)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    /// "code" - код стратегии, "types.rs" - шаблон ядра;
+    /// None - вне файлов стратегии (сгенерированная преамбула, зависимости)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// 1-based, как в редакторе
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_column: Option<usize>,
+    /// Исходная строка с подчёркнутым местом
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span_text: Option<String>,
+    /// Подпись rustc под подчёркиванием ("not found in this scope")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Полный текст, как его печатает cargo
+    pub rendered: String,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.level == "error"
+    }
+
+    /// Короткая строка для errors: "error[E0425]: ... (code:3:5)"
+    pub fn summary(&self) -> String {
+        let code = self.code.as_deref().map(|c| format!("[{}]", c)).unwrap_or_default();
+        match (&self.file, self.line, self.column) {
+            (Some(file), Some(line), Some(col)) => {
+                format!("{}{}: {} ({}:{}:{})", self.level, code, self.message, file, line, col)
+            }
+            _ => format!("{}{}: {}", self.level, code, self.message),
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// CARGO --message-format=json
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<RustcMessage>,
+}
+
+#[derive(Deserialize)]
+struct RustcMessage {
+    level: String,
+    message: String,
+    code: Option<RustcCode>,
+    #[serde(default)]
+    spans: Vec<RustcSpan>,
+    rendered: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RustcCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    line_start: usize,
+    line_end: usize,
+    column_start: usize,
+    column_end: usize,
+    is_primary: bool,
+    #[serde(default)]
+    text: Vec<RustcSpanLine>,
+    label: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RustcSpanLine {
+    text: String,
+}
+
+/// Одна строка stdout cargo. None - не сообщение компилятора
+/// (compiler-artifact, build-finished) или служебное ("aborting due to...")
+pub fn parse(line: &str) -> Option<Diagnostic> {
+    let msg: CargoMessage = serde_json::from_str(line).ok()?;
+    if msg.reason != "compiler-message" {
+        return None;
+    }
+    let m = msg.message?;
+    if m.level == "failure-note" || (m.spans.is_empty() && m.message.starts_with("aborting due to")) {
+        return None;
+    }
+
+    let mut d = Diagnostic {
+        level: m.level,
+        message: m.message,
+        code: m.code.map(|c| c.code),
+        file: None,
+        line: None,
+        column: None,
+        end_line: None,
+        end_column: None,
+        span_text: None,
+        label: None,
+        rendered: m.rendered.unwrap_or_default(),
+    };
+
+    let Some(span) = m.spans.iter().find(|s| s.is_primary) else { return Some(d) };
+    let (file, shift) = match span.file_name.as_str() {
+        "src/lib.rs" if span.line_start > PRELUDE_LINES => ("code", PRELUDE_LINES),
+        "src/types.rs" => ("types.rs", 0),
+        _ => return Some(d),
+    };
+    d.file = Some(file.to_string());
+    d.line = Some(span.line_start - shift);
+    d.column = Some(span.column_start);
+    d.end_line = Some(span.line_end.saturating_sub(shift).max(1));
+    d.end_column = Some(span.column_end);
+    d.span_text = span.text.first().map(|t| t.text.clone());
+    d.label = span.label.clone();
+    Some(d)
+}
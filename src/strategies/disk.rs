@@ -0,0 +1,274 @@
+// src/strategies/disk.rs
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::strategies::storage::StrategyStorage;
+
+// ═══════════════════════════════════════════════════════════
+// ДИСК СТРАТЕГИЙ
+// ═══════════════════════════════════════════════════════════
+//
+// Каждая сборка оставляет в target/ сотни мегабайт: зависимости,
+// инкрементальный кэш, артефакты cargo check. Стратегии правят и
+// пересобирают, старые стратегии не собирают месяцами, а их кэш лежит.
+// Janitor удаляет кэш стратегий, которые давно не собирали, и следит за
+// квотами. Библиотека (то, что запускается) не удаляется никогда:
+// без кэша следующая сборка просто дольше.
+
+pub const MB: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiskConfig {
+    /// Потолок на стратегию: код, её кэш сборки (и доля в общем target/)
+    /// и библиотека, MB (0 - без лимита)
+    pub max_strategy_mb: u64,
+    /// Потолок на strategies/db вместе с общим target/, MB (0 - без лимита)
+    pub max_total_mb: u64,
+    /// Кэш стратегии, которую не собирали столько дней, удаляется.
+    /// Общий кэш зависимостей - когда не собирали ничего (0 - не по возрасту)
+    pub stale_days: u64,
+    /// Период janitor, секунд (0 - чистка только через POST /api/disk/prune)
+    pub janitor_interval_secs: u64,
+}
+
+impl Default for DiskConfig {
+    fn default() -> Self {
+        Self {
+            max_strategy_mb: 0,
+            max_total_mb: 0,
+            stale_days: 7,
+            janitor_interval_secs: 3600,
+        }
+    }
+}
+
+impl DiskConfig {
+    pub fn max_strategy_bytes(&self) -> Option<u64> {
+        (self.max_strategy_mb > 0).then(|| self.max_strategy_mb * MB)
+    }
+
+    pub fn max_total_bytes(&self) -> Option<u64> {
+        (self.max_total_mb > 0).then(|| self.max_total_mb * MB)
+    }
+
+    /// Сборка с mtime last_build (unix ms) устарела
+    pub fn is_stale(&self, last_build: Option<i64>) -> bool {
+        if self.stale_days == 0 {
+            return false;
+        }
+        let cutoff = chrono::Utc::now().timestamp_millis() - (self.stale_days * 86_400_000) as i64;
+        last_build.is_none_or(|ms| ms < cutoff)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StrategyDiskUsage {
+    pub id: String,
+    /// Код, Cargo.toml, manifest и прочее вне target/
+    pub source_bytes: u64,
+    /// Кэш сборки: target/ стратегии без библиотеки и её артефакты в общем target/
+    pub cache_bytes: u64,
+    pub library_bytes: u64,
+    pub total_bytes: u64,
+    /// Unix ms последней записи в кэш или библиотеку (None - не собиралась)
+    pub last_build: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskUsage {
+    /// Самые большие первыми
+    pub strategies: Vec<StrategyDiskUsage>,
+    /// Общий target/ без артефактов самих стратегий: зависимости
+    pub shared_cache_bytes: u64,
+    pub total_bytes: u64,
+    pub max_strategy_bytes: Option<u64>,
+    pub max_total_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PruneReport {
+    pub freed_bytes: u64,
+    /// Стратегии, чей кэш удалён
+    pub strategies: Vec<String>,
+    /// Удалён общий кэш зависимостей
+    pub shared_cache: bool,
+}
+
+/// Файл или каталог кэша сборки
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    pub path: PathBuf,
+    pub bytes: u64,
+    /// Unix ms
+    pub modified: Option<i64>,
+}
+
+impl Artifact {
+    pub fn scan(path: PathBuf) -> Self {
+        let (bytes, modified) = size_of(&path);
+        Self { path, bytes, modified }
+    }
+
+    pub fn remove(&self) -> std::io::Result<()> {
+        if self.path.is_dir() {
+            fs::remove_dir_all(&self.path)
+        } else {
+            fs::remove_file(&self.path)
+        }
+    }
+}
+
+/// Что занимает на диске одна стратегия
+#[derive(Debug, Clone)]
+pub struct StrategyScan {
+    pub id: String,
+    pub source_bytes: u64,
+    pub library: Option<Artifact>,
+    pub cache: Vec<Artifact>,
+}
+
+impl StrategyScan {
+    pub fn usage(&self) -> StrategyDiskUsage {
+        let cache_bytes = self.cache.iter().map(|a| a.bytes).sum();
+        let library_bytes = self.library.as_ref().map_or(0, |a| a.bytes);
+        StrategyDiskUsage {
+            id: self.id.clone(),
+            source_bytes: self.source_bytes,
+            cache_bytes,
+            library_bytes,
+            total_bytes: self.source_bytes + cache_bytes + library_bytes,
+            last_build: self.cache.iter().chain(&self.library).filter_map(|a| a.modified).max(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Scan {
+    pub strategies: Vec<StrategyScan>,
+    /// Общий target/ без артефактов стратегий
+    pub shared: Vec<Artifact>,
+}
+
+impl Scan {
+    pub fn shared_bytes(&self) -> u64 {
+        self.shared.iter().map(|a| a.bytes).sum()
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.strategies.iter().map(|s| s.usage().total_bytes).sum::<u64>() + self.shared_bytes()
+    }
+
+    /// Последняя запись в любой кэш: общий кэш нужен, пока собирают хоть что-то
+    pub fn last_build(&self) -> Option<i64> {
+        let strategies = self.strategies.iter().filter_map(|s| s.usage().last_build);
+        self.shared.iter().filter_map(|a| a.modified).chain(strategies).max()
+    }
+}
+
+impl PruneReport {
+    /// Удаляет кэш стратегии (owner) или общий (None), один раз за отчёт
+    pub fn remove(&mut self, owner: Option<&str>, artifacts: &[Artifact]) {
+        let done = match owner {
+            Some(id) => self.strategies.iter().any(|s| s == id),
+            None => self.shared_cache,
+        };
+        if done || artifacts.is_empty() {
+            return;
+        }
+        for artifact in artifacts {
+            match artifact.remove() {
+                Ok(()) => self.freed_bytes += artifact.bytes,
+                Err(e) => tracing::warn!("🧹 Failed to remove {:?}: {}", artifact.path, e),
+            }
+        }
+        match owner {
+            Some(id) => self.strategies.push(id.to_string()),
+            None => self.shared_cache = true,
+        }
+    }
+}
+
+/// Размер файла или каталога (рекурсивно, без перехода по симлинкам)
+/// и самый свежий mtime внутри
+pub fn size_of(path: &Path) -> (u64, Option<i64>) {
+    let Ok(meta) = fs::symlink_metadata(path) else { return (0, None) };
+    let mut modified = meta.modified().ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64);
+    if !meta.is_dir() {
+        return (meta.len(), modified);
+    }
+    let mut bytes = 0;
+    for entry in fs::read_dir(path).into_iter().flatten().flatten() {
+        let (b, m) = size_of(&entry.path());
+        bytes += b;
+        modified = modified.max(m);
+    }
+    (bytes, modified)
+}
+
+/// Хэш cargo в имени: 16 hex (deps, .fingerprint) или base36 (incremental).
+/// Цифра обязательна, чтобы crossbeam-channel не считался крейтом crossbeam
+fn is_hash(s: &str) -> bool {
+    s.len() >= 8
+        && s.chars().all(|c| c.is_ascii_digit() || c.is_ascii_lowercase())
+        && s.chars().any(|c| c.is_ascii_digit())
+}
+
+/// Чья это запись общего target/: имя крейта стратегии совпадает с её id.
+/// cdylib: libca.so, ca.d; check и кэш: libca-<hash>.rmeta, ca-<hash>,
+/// incremental/ca-<base36>.
+/// Префикс lib бывает только у библиотек, не у .d и не у каталогов
+pub fn artifact_owner<'a>(name: &str, ids: &[&'a str]) -> Option<&'a str> {
+    let (stem, ext) = name.split_once('.').unwrap_or((name, ""));
+    let crate_name = |stem: &str| -> Option<String> {
+        match stem.rsplit_once('-') {
+            Some((base, hash)) if is_hash(hash) => Some(base.to_string()),
+            Some(_) => None,
+            None => Some(stem.to_string()),
+        }
+    };
+    let mut candidates = Vec::with_capacity(2);
+    if let Some(name) = crate_name(stem) {
+        candidates.push(name);
+    }
+    if !ext.is_empty() && ext != "d" {
+        if let Some(name) = stem.strip_prefix("lib").and_then(crate_name) {
+            candidates.push(name);
+        }
+    }
+    ids.iter().copied().find(|id| candidates.iter().any(|c| c == id))
+}
+
+// ═══════════════════════════════════════════════════════════
+// JANITOR
+// ═══════════════════════════════════════════════════════════
+
+/// Периодическая чистка: кэш устаревших стратегий и квоты
+pub fn spawn_janitor(storage: Arc<StrategyStorage>, config: DiskConfig) {
+    if config.janitor_interval_secs == 0 {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(config.janitor_interval_secs));
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let storage = storage.clone();
+            match tokio::task::spawn_blocking(move || storage.prune(false)).await {
+                Ok(Ok(report)) if report.freed_bytes > 0 => tracing::info!(
+                    "🧹 Disk janitor freed {} MB (strategies: {:?}, shared cache: {})",
+                    report.freed_bytes / MB, report.strategies, report.shared_cache,
+                ),
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => tracing::warn!("🧹 Disk janitor failed: {}", e),
+                Err(e) => tracing::warn!("🧹 Disk janitor panicked: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,414 @@
+// src/strategies/dry_run.rs
+
+use crossbeam::channel::bounded;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::ffi_types::{
+    pack_str, CBookTicker, CCustomSignal, CEvent, CEventData, CTrade, Sequencer,
+    EVENT_BOOK_TICKER, EVENT_SIGNAL, EVENT_TRADE, SIGNAL_PAYLOAD_LEN,
+};
+use crate::kv;
+use crate::latency;
+use crate::paper::{self, PaperStats};
+use crate::strategies::logs::{self, LogLine};
+use crate::strategies::manager::{RecvMode, StrategyRunner};
+use crate::strategies::order::{self, OrderCallback};
+use crate::strategies::{storage, timers};
+
+/// Заготовки событий: copy_into_strategies/fixtures/{name}.json
+const FIXTURES_DIR: &str = "copy_into_strategies/fixtures";
+
+/// Предел событий одного прогона
+pub const MAX_EVENTS: usize = 10_000;
+
+/// Сколько ждём выхода из run() после stop_flag сверх timeout_ms
+const STOP_GRACE: Duration = Duration::from_secs(2);
+
+static NEXT_RUN: AtomicU64 = AtomicU64::new(1);
+
+// ═══════════════════════════════════════════════════════════
+// СОБЫТИЯ СЦЕНАРИЯ
+// ═══════════════════════════════════════════════════════════
+
+/// Событие сценария в JSON. symbol по умолчанию - символ прогона
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TestEvent {
+    BookTicker {
+        #[serde(default)]
+        symbol: Option<String>,
+        bid: f64,
+        ask: f64,
+        #[serde(default = "default_qty")]
+        bid_qty: f64,
+        #[serde(default = "default_qty")]
+        ask_qty: f64,
+    },
+    Trade {
+        #[serde(default)]
+        symbol: Option<String>,
+        price: f64,
+        /// Отрицательный - покупатель был maker'ом (как в CTrade)
+        qty: f64,
+    },
+    /// Сигнал извне, как из POST /api/instances/:id/signal
+    Signal { payload: serde_json::Value },
+    /// Пауза в подаче событий (для логики по времени)
+    Wait { ms: u64 },
+}
+
+fn default_qty() -> f64 {
+    1.0
+}
+
+impl TestEvent {
+    /// CEvent со временем "сейчас"; None - пауза (или сигнал длиннее буфера - отсеян в run)
+    fn to_c(&self, default_symbol: &str) -> Option<CEvent> {
+        let now_ns = latency::now_ns();
+        let time = (now_ns / 1_000_000) as i64;
+        let (event_type, data) = match self {
+            TestEvent::BookTicker { symbol, bid, ask, bid_qty, ask_qty } => {
+                let (symbol, symbol_len) = pack_str::<16>(symbol.as_deref().unwrap_or(default_symbol));
+                let book_ticker = CBookTicker {
+                    symbol,
+                    symbol_len,
+                    bid_price: *bid,
+                    ask_price: *ask,
+                    bid_qty: *bid_qty,
+                    ask_qty: *ask_qty,
+                    time,
+                };
+                (EVENT_BOOK_TICKER, CEventData { book_ticker })
+            }
+            TestEvent::Trade { symbol, price, qty } => {
+                let (symbol, symbol_len) = pack_str::<16>(symbol.as_deref().unwrap_or(default_symbol));
+                let trade = CTrade { symbol, symbol_len, price: *price, qty: *qty, time };
+                (EVENT_TRADE, CEventData { trade })
+            }
+            TestEvent::Signal { payload } => {
+                let signal = CCustomSignal::new(&payload.to_string(), time)?;
+                (EVENT_SIGNAL, CEventData { signal })
+            }
+            TestEvent::Wait { .. } => return None,
+        };
+        Some(CEvent { event_type, data, received_at_ns: now_ns, seq: 0 })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fixture {
+    #[serde(default)]
+    pub description: String,
+    pub events: Vec<TestEvent>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FixtureInfo {
+    pub name: String,
+    pub description: String,
+    pub events: usize,
+}
+
+/// Заготовки сценариев по имени
+pub fn fixtures() -> Vec<FixtureInfo> {
+    let Ok(entries) = std::fs::read_dir(FIXTURES_DIR) else {
+        return Vec::new();
+    };
+    let mut result: Vec<FixtureInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            let name = path.file_stem()?.to_str()?.to_string();
+            let fixture = read_fixture(&path).ok()?;
+            Some(FixtureInfo { name, description: fixture.description, events: fixture.events.len() })
+        })
+        .collect();
+    result.sort_by(|a, b| a.name.cmp(&b.name));
+    result
+}
+
+pub fn fixture(name: &str) -> anyhow::Result<Fixture> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        anyhow::bail!("Invalid fixture name '{}'", name);
+    }
+    let path = Path::new(FIXTURES_DIR).join(format!("{}.json", name));
+    if !path.exists() {
+        let known: Vec<String> = fixtures().into_iter().map(|f| f.name).collect();
+        anyhow::bail!("Unknown fixture '{}', available: {}", name, known.join(", "));
+    }
+    read_fixture(&path)
+}
+
+fn read_fixture(path: &Path) -> anyhow::Result<Fixture> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&text)?)
+}
+
+// ═══════════════════════════════════════════════════════════
+// ПЕРЕХВАТ ОРДЕРОВ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum OrderCall {
+    Place {
+        symbol: String,
+        side: String,
+        order_type: &'static str,
+        price: f64,
+        quantity: f64,
+    },
+    Cancel {
+        symbol: String,
+        order_id: i64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CapturedCall {
+    /// Номер последнего отданного события сценария (None - до первого)
+    pub after_event: Option<usize>,
+    /// От начала прогона
+    pub elapsed_ms: f64,
+    #[serde(flatten)]
+    pub call: OrderCall,
+}
+
+struct Capture {
+    started: Instant,
+    /// Сколько событий отдано стратегии
+    fed: AtomicUsize,
+    calls: Mutex<Vec<CapturedCall>>,
+}
+
+impl Capture {
+    fn record(&self, call: OrderCall) {
+        let fed = self.fed.load(Ordering::Relaxed);
+        self.calls.lock().unwrap().push(CapturedCall {
+            after_event: fed.checked_sub(1),
+            elapsed_ms: self.started.elapsed().as_secs_f64() * 1000.0,
+            call,
+        });
+    }
+}
+
+/// instance_id прогона -> записанные вызовы
+static CAPTURES: LazyLock<DashMap<String, Arc<Capture>>> = LazyLock::new(DashMap::new);
+
+fn current_capture() -> Option<Arc<Capture>> {
+    let id = order::current_instance()?;
+    CAPTURES.get(&id).map(|c| c.clone())
+}
+
+unsafe fn c_str(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    CStr::from_ptr(ptr).to_string_lossy().into_owned()
+}
+
+/// PlaceOrderFn прогона: записывает вызов и отдаёт ордер симулятору
+/// (счёт paper зарегистрирован на инстанс, на биржу ничего не уходит)
+#[allow(clippy::too_many_arguments)]
+unsafe extern "C" fn capture_place(
+    api_key: *const c_char,
+    secret_key: *const c_char,
+    symbol: *const c_char,
+    price: f64,
+    quantity: f64,
+    side: *const c_char,
+    order_type: u8,
+    callback: OrderCallback,
+) {
+    if let Some(capture) = current_capture() {
+        capture.record(OrderCall::Place {
+            symbol: c_str(symbol),
+            side: c_str(side),
+            order_type: if order_type == 1 { "MARKET" } else { "LIMIT" },
+            price,
+            quantity,
+        });
+    }
+    order::place_order(api_key, secret_key, symbol, price, quantity, side, order_type, callback);
+}
+
+unsafe extern "C" fn capture_cancel(
+    api_key: *const c_char,
+    secret_key: *const c_char,
+    symbol: *const c_char,
+    order_id: i64,
+    callback: OrderCallback,
+) {
+    if let Some(capture) = current_capture() {
+        capture.record(OrderCall::Cancel { symbol: c_str(symbol), order_id });
+    }
+    order::cancel_order(api_key, secret_key, symbol, order_id, callback);
+}
+
+// ═══════════════════════════════════════════════════════════
+// ПРОГОН
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DryRunResult {
+    pub instance_id: String,
+    /// Код возврата run(); None - стратегия не вышла за отведённое время
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub events_sent: usize,
+    pub duration_ms: f64,
+    /// Вызовы place/cancel в порядке поступления
+    pub orders: Vec<CapturedCall>,
+    /// Итог симулятора (исполнения по событиям сценария)
+    pub paper: PaperStats,
+    pub logs: Vec<LogLine>,
+}
+
+/// Запускает библиотеку в изолированном инстансе (не виден в /api/instances,
+/// ордера только в симулятор), отдаёт события по одному и останавливает
+/// стратегию через settle после последнего
+pub async fn run(
+    strategy_id: &str,
+    lib_path: PathBuf,
+    symbol: &str,
+    params: &serde_json::Value,
+    events: Vec<TestEvent>,
+    settle: Duration,
+    timeout: Duration,
+) -> anyhow::Result<DryRunResult> {
+    if events.len() > MAX_EVENTS {
+        anyhow::bail!("Too many events: {} (max {})", events.len(), MAX_EVENTS);
+    }
+    if let Some(i) = events.iter().position(|e| matches!(e, TestEvent::Signal { payload } if payload.to_string().len() > SIGNAL_PAYLOAD_LEN)) {
+        anyhow::bail!("Event #{}: signal payload exceeds {} bytes", i, SIGNAL_PAYLOAD_LEN);
+    }
+    let symbol = symbol.to_uppercase();
+    let instance_id = format!("{}:{}:test-{}", strategy_id, symbol, NEXT_RUN.fetch_add(1, Ordering::Relaxed));
+    let params_json = serde_json::to_string(params)?;
+
+    let lib = Arc::new(storage::open_library(&lib_path, true)?);
+    let run_fn: crate::strategies::manager::RunFn = unsafe { *lib.get(b"run")? };
+
+    let (tx, rx) = bounded::<CEvent>(events.len() + 1024);
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    let capture = Arc::new(Capture {
+        started: Instant::now(),
+        fed: AtomicUsize::new(0),
+        calls: Mutex::new(Vec::new()),
+    });
+
+    logs::clear(&instance_id);
+    // Счёт симулятора - до запуска: любой путь ордера инстанса (в т.ч. staging) уходит в него
+    let account = paper::register(&instance_id, tx.clone(), true);
+    CAPTURES.insert(instance_id.clone(), capture.clone());
+    kv::sandbox(&instance_id);
+    timers::register(&instance_id, tx.clone(), false);
+    tracing::info!("🧪 Dry run '{}': {} events", instance_id, events.len());
+
+    let mut task = {
+        let (instance_id, symbol, stop_flag) = (instance_id.clone(), symbol.clone(), stop_flag.clone());
+        tokio::task::spawn_blocking(move || {
+            StrategyRunner::run_strategy(
+                instance_id,
+                lib,
+                run_fn,
+                rx,
+                symbol,
+                params_json,
+                stop_flag,
+                RecvMode::Sleep,
+                (capture_place, capture_cancel),
+            )
+        })
+    };
+
+    let deadline = Instant::now() + timeout;
+    let feeder = {
+        let (symbol, stop_flag, capture, account) = (symbol.clone(), stop_flag.clone(), capture.clone(), account.clone());
+        tokio::task::spawn_blocking(move || {
+            let seqs = Sequencer::default();
+            let running = || !stop_flag.load(Ordering::Relaxed) && Instant::now() < deadline;
+
+            for event in &events {
+                if let TestEvent::Wait { ms } = event {
+                    std::thread::sleep(Duration::from_millis(*ms).min(deadline.saturating_duration_since(Instant::now())));
+                    continue;
+                }
+                // По одному: ордер должен видеть, на какое событие он поставлен
+                while !tx.is_empty() && running() {
+                    std::thread::sleep(Duration::from_micros(200));
+                }
+                let Some(mut event) = event.to_c(&symbol) else { continue };
+                if !running() {
+                    break;
+                }
+                seqs.stamp(&mut event);
+                let fills = account.on_market(&event);
+                if std::iter::once(event).chain(fills).any(|e| tx.try_send(e).is_err()) {
+                    break;
+                }
+                capture.fed.fetch_add(1, Ordering::Relaxed);
+            }
+
+            while !tx.is_empty() && running() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            std::thread::sleep(settle.min(deadline.saturating_duration_since(Instant::now())));
+            stop_flag.store(true, Ordering::Relaxed);
+        })
+    };
+    let _ = feeder.await;
+
+    let (exit_code, timed_out) = match tokio::time::timeout(STOP_GRACE, &mut task).await {
+        Ok(Ok(code)) => (Some(code), false),
+        Ok(Err(e)) => {
+            logs::push(&instance_id, logs::LOG_ERROR, &format!("Strategy panicked: {}", e));
+            (None, false)
+        }
+        Err(_) => (None, true),
+    };
+
+    let stats = account.stats();
+    let calls = std::mem::take(&mut *capture.calls.lock().unwrap());
+    let logs = logs::tail(&instance_id, usize::MAX).unwrap_or_default();
+    if timed_out {
+        // Поток стратегии ещё жив: счёт paper снимаем только после выхода
+        // из run(), иначе его следующие ордера пошли бы на биржу
+        tracing::warn!("⚠️ Dry run '{}' did not stop in time, leaving it sandboxed", instance_id);
+        let instance_id = instance_id.clone();
+        tokio::spawn(async move {
+            let _ = task.await;
+            release(&instance_id);
+            tracing::info!("🧪 Dry run '{}' finally stopped", instance_id);
+        });
+    } else {
+        release(&instance_id);
+    }
+
+    Ok(DryRunResult {
+        instance_id,
+        exit_code,
+        timed_out,
+        events_sent: capture.fed.load(Ordering::Relaxed),
+        duration_ms: capture.started.elapsed().as_secs_f64() * 1000.0,
+        orders: calls,
+        paper: stats,
+        logs,
+    })
+}
+
+fn release(instance_id: &str) {
+    paper::unregister(instance_id);
+    CAPTURES.remove(instance_id);
+    logs::clear(instance_id);
+}
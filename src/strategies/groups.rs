@@ -0,0 +1,70 @@
+// src/strategies/groups.rs
+
+use dashmap::DashMap;
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+use crate::redact;
+use crate::strategies::manager::{other_slot, InstanceOptions};
+
+/// Длина имени группы
+pub const MAX_GROUP_LEN: usize = 64;
+
+// ═══════════════════════════════════════════════════════════
+// ГРУППЫ ИНСТАНСОВ
+// ═══════════════════════════════════════════════════════════
+//
+// Одна стратегия на 15 символах - это 15 инстансов, которые останавливают,
+// ставят на паузу и смотрят вместе. Инстанс попадает в группу опцией
+// запуска "group". Группа помнит, с чем запускался каждый участник, поэтому
+// остановленную группу можно запустить обратно одним запросом. Участник
+// уходит из группы, когда его id запускают без group или в другой группе.
+
+/// С чем участник был запущен в последний раз
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub strategy_id: String,
+    pub symbol: String,
+    /// Исходные параметры - для повторного запуска (expose)
+    pub params: redact::Params,
+    pub options: InstanceOptions,
+}
+
+/// group -> instance_id -> участник
+static GROUPS: LazyLock<DashMap<String, BTreeMap<String, Member>>> = LazyLock::new(DashMap::new);
+
+pub fn validate_name(name: &str) -> anyhow::Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_GROUP_LEN
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        anyhow::bail!("group must be 1..={} chars of [A-Za-z0-9._-]", MAX_GROUP_LEN);
+    }
+    Ok(())
+}
+
+/// Запоминает запуск участника (или убирает инстанс из групп, если group нет)
+pub fn register(instance_id: &str, member: Member) {
+    let group = member.options.group.clone();
+    // Другой слот blue-green - тот же участник: после swap остаётся один
+    let sibling = other_slot(instance_id);
+    GROUPS.retain(|name, members| {
+        members.remove(instance_id);
+        members.remove(&sibling);
+        !members.is_empty() || Some(name) == group.as_ref()
+    });
+    if let Some(group) = group {
+        GROUPS.entry(group).or_default().insert(instance_id.to_string(), member);
+    }
+}
+
+/// Участники группы (пусто - группы нет)
+pub fn members(group: &str) -> BTreeMap<String, Member> {
+    GROUPS.get(group).map(|m| m.clone()).unwrap_or_default()
+}
+
+pub fn names() -> Vec<String> {
+    let mut names: Vec<String> = GROUPS.iter().map(|e| e.key().clone()).collect();
+    names.sort();
+    names
+}
@@ -0,0 +1,160 @@
+// src/strategies/intents.rs
+
+use dashmap::{DashMap, DashSet};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, LazyLock, OnceLock};
+use tokio::sync::broadcast;
+
+use crate::ffi_types::{CEvent, EVENT_BOOK_TICKER, EVENT_TRADE};
+use crate::orders::{MarketContext, OrderManager};
+use crate::strategies::logs;
+use crate::strategies::order::OrderResult;
+
+/// Отмена намерения, которого нет (или оно уже закрыто)
+pub const ERR_DRY_RUN_UNKNOWN_ORDER: i32 = -9510;
+
+/// Синтетические orderId начинаются отсюда: с биржевыми не пересекаются
+const SYNTHETIC_ORDER_ID_BASE: i64 = 1 << 62;
+
+// ═══════════════════════════════════════════════════════════
+// DRY RUN ЖИВЫХ ИНСТАНСОВ
+// ═══════════════════════════════════════════════════════════
+//
+// Инстанс с "dry_run": true получает live market data, но place_order
+// не уходит на биржу: намерение с рынком на этот момент пишется в order
+// manager (GET /api/orders?instance=...), стратегия получает успешный
+// callback с синтетическим orderId и может его отменить.
+
+static ORDER_MANAGER: OnceLock<Arc<OrderManager>> = OnceLock::new();
+
+/// Инстансы в режиме dry run
+static INSTANCES: LazyLock<DashSet<String>> = LazyLock::new(DashSet::new);
+
+static NEXT_ORDER_ID: AtomicI64 = AtomicI64::new(SYNTHETIC_ORDER_ID_BASE);
+
+#[derive(Clone, Copy, Default)]
+struct Quote {
+    bid: Option<f64>,
+    ask: Option<f64>,
+    /// Unix ms последнего bookTicker
+    quote_time: i64,
+    last_trade: Option<f64>,
+}
+
+/// symbol -> последние котировки
+static QUOTES: LazyLock<DashMap<String, Quote>> = LazyLock::new(DashMap::new);
+
+pub fn init(orders: Arc<OrderManager>, market_rx: broadcast::Receiver<CEvent>) {
+    ORDER_MANAGER.set(orders).ok();
+    tokio::spawn(market_loop(market_rx));
+}
+
+/// С этого момента ордера инстанса только записываются
+pub fn register(instance_id: &str) {
+    INSTANCES.insert(instance_id.to_string());
+}
+
+/// Снимает dry run и закрывает оставшиеся намерения
+pub fn unregister(instance_id: &str) {
+    if INSTANCES.remove(instance_id).is_none() {
+        return;
+    }
+    let expired = ORDER_MANAGER.get().map_or(0, |orders| orders.expire_dry_run(instance_id));
+    if expired > 0 {
+        tracing::info!("📝 '{}' dry run finished, {} open intents expired", instance_id, expired);
+    }
+}
+
+pub fn is_active(instance_id: &str) -> bool {
+    !INSTANCES.is_empty() && INSTANCES.contains(instance_id)
+}
+
+/// Записывает намерение вместо order.place
+pub fn place(
+    instance_id: &str,
+    api_key: &str,
+    symbol: &str,
+    side: &str,
+    order_type: u8,
+    price: f64,
+    quantity: f64,
+) -> OrderResult {
+    let order_id = NEXT_ORDER_ID.fetch_add(1, Ordering::Relaxed);
+    let context = context(symbol);
+
+    if let Some(orders) = ORDER_MANAGER.get() {
+        let cid = orders.next_client_id(Some(instance_id));
+        orders.on_request(&cid, Some(instance_id), api_key, symbol, side, order_type, price, quantity);
+        orders.on_dry_run(&cid, order_id, context);
+    }
+
+    let kind = if order_type == 1 { "MARKET" } else { "LIMIT" };
+    let market = match (context.bid, context.ask) {
+        (Some(bid), Some(ask)) => format!("bid {} / ask {}", bid, ask),
+        _ => "no quotes yet".to_string(),
+    };
+    logs::push(
+        instance_id,
+        logs::LOG_INFO,
+        &format!("Dry run: {} {} {} {} @ {} ({}), order {}", side, quantity, symbol, kind, price, market, order_id),
+    );
+
+    OrderResult { success: true, order_id, error_code: 0 }
+}
+
+/// Отменяет записанное намерение вместо order.cancel
+pub fn cancel(instance_id: &str, order_id: i64) -> OrderResult {
+    // Настоящий ордер dry-run инстанс отменить не может: ядро бы пометило
+    // его отменённым, хотя на бирже он остался
+    let canceled = order_id >= SYNTHETIC_ORDER_ID_BASE
+        && ORDER_MANAGER.get().is_some_and(|orders| orders.on_cancel_ack(order_id));
+    if !canceled {
+        return OrderResult { success: false, order_id: -1, error_code: ERR_DRY_RUN_UNKNOWN_ORDER };
+    }
+    logs::push(instance_id, logs::LOG_INFO, &format!("Dry run: cancel order {}", order_id));
+    OrderResult { success: true, order_id, error_code: 0 }
+}
+
+fn context(symbol: &str) -> MarketContext {
+    let Some(q) = QUOTES.get(&symbol.to_uppercase()).map(|q| *q) else {
+        return MarketContext::default();
+    };
+    MarketContext {
+        bid: q.bid,
+        ask: q.ask,
+        last_trade: q.last_trade,
+        quote_age_ms: q.bid.map(|_| chrono::Utc::now().timestamp_millis() - q.quote_time),
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// КОТИРОВКИ
+// ═══════════════════════════════════════════════════════════
+
+async fn market_loop(mut rx: broadcast::Receiver<CEvent>) {
+    loop {
+        let event = match rx.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        // Котировки нужны только пока есть dry-run инстансы
+        if INSTANCES.is_empty() {
+            continue;
+        }
+        match event.event_type {
+            EVENT_BOOK_TICKER => {
+                let bt = unsafe { &event.data.book_ticker };
+                let mut q = QUOTES.entry(bt.symbol_str().to_string()).or_default();
+                q.bid = Some(bt.bid_price);
+                q.ask = Some(bt.ask_price);
+                q.quote_time = chrono::Utc::now().timestamp_millis();
+            }
+            EVENT_TRADE => {
+                let t = unsafe { &event.data.trade };
+                QUOTES.entry(t.symbol_str().to_string()).or_default().last_trade = Some(t.price);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,93 @@
+// src/strategies/logs.rs
+
+use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, LazyLock, Mutex};
+use tokio::sync::broadcast;
+
+/// Сколько последних строк храним на инстанс
+const MAX_LINES: usize = 5000;
+
+pub const LOG_DEBUG: u8 = 0;
+pub const LOG_INFO: u8 = 1;
+pub const LOG_WARN: u8 = 2;
+pub const LOG_ERROR: u8 = 3;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLine {
+    /// Unix ms
+    pub time: i64,
+    pub level: &'static str,
+    pub message: String,
+}
+
+/// instance_id -> последние строки лога
+static LOGS: LazyLock<DashMap<String, Mutex<VecDeque<LogLine>>>> = LazyLock::new(DashMap::new);
+
+/// Живой поток строк всех инстансов (для WebSocket)
+static LIVE: LazyLock<broadcast::Sender<LiveLine>> = LazyLock::new(|| broadcast::channel(4096).0);
+
+#[derive(Debug, Clone)]
+pub struct LiveLine {
+    pub instance_id: Arc<str>,
+    pub line: LogLine,
+}
+
+fn level_name(level: u8) -> &'static str {
+    match level {
+        LOG_DEBUG => "DEBUG",
+        LOG_INFO => "INFO",
+        LOG_WARN => "WARN",
+        _ => "ERROR",
+    }
+}
+
+/// Строка от стратегии: в буфер инстанса и в лог ядра
+pub fn write(instance_id: &str, level: u8, message: &str) {
+    match level {
+        LOG_DEBUG => tracing::debug!("[{}] {}", instance_id, message),
+        LOG_INFO => tracing::info!("[{}] {}", instance_id, message),
+        LOG_WARN => tracing::warn!("[{}] {}", instance_id, message),
+        _ => tracing::error!("[{}] {}", instance_id, message),
+    }
+    push(instance_id, level, message);
+}
+
+/// Добавляет строку только в буфер инстанса
+pub fn push(instance_id: &str, level: u8, message: &str) {
+    let line = LogLine {
+        time: chrono::Utc::now().timestamp_millis(),
+        level: level_name(level),
+        message: message.to_string(),
+    };
+
+    if LIVE.receiver_count() > 0 {
+        let _ = LIVE.send(LiveLine { instance_id: Arc::from(instance_id), line: line.clone() });
+    }
+
+    let buffer = LOGS.entry(instance_id.to_string()).or_default();
+    let mut lines = buffer.lock().unwrap();
+    if lines.len() >= MAX_LINES {
+        lines.pop_front();
+    }
+    lines.push_back(line);
+}
+
+/// Подписка на новые строки (фильтрация по instance_id - на стороне подписчика)
+pub fn subscribe() -> broadcast::Receiver<LiveLine> {
+    LIVE.subscribe()
+}
+
+/// Последние tail строк (старые первыми). None - инстанс ничего не писал
+pub fn tail(instance_id: &str, tail: usize) -> Option<Vec<LogLine>> {
+    let buffer = LOGS.get(instance_id)?;
+    let lines = buffer.lock().unwrap();
+    let skip = lines.len().saturating_sub(tail);
+    Some(lines.iter().skip(skip).cloned().collect())
+}
+
+/// Новый запуск инстанса начинается с чистого буфера
+pub fn clear(instance_id: &str) {
+    LOGS.remove(instance_id);
+}
@@ -1,19 +1,57 @@
 // src/strategies/manager.rs
 
 use libloading::Library;
-use tokio::sync::broadcast;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 use std::sync::atomic::{AtomicBool, Ordering};
 use tokio::task::JoinHandle;
 use dashmap::DashMap;
 use anyhow::Result;
-use crossbeam::channel::{bounded, Receiver, Sender};
+use crossbeam::channel::{bounded, Receiver};
 use std::ffi::CString;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::ffi_types::CEvent;
-use crate::strategies::order::{PlaceOrderFn, CancelOrderFn, place_order, cancel_order};
+use crate::ffi_types::{CCustomSignal, CEvent, CEventData, EVENT_SIGNAL, SIGNAL_PAYLOAD_LEN};
+use crate::lifecycle::{self, LifecycleEvent, LifecycleKind};
+use crate::affinity;
+use crate::alerts::{self, AlertLevel};
+use crate::execution;
+use crate::fanout::{self, OverflowPolicy, Subscriber};
+use crate::kv;
+use crate::latency;
+use crate::redact;
+use crate::risk::RiskLimits;
+use crate::symbols;
+use crate::strategies::breaker::{BreakerState, CircuitBreaker};
+use crate::paper::{self, PaperStats};
+use crate::strategies::{capacity, dedup, groups, intents, logs, metrics, quarantine, replay, retry, runs, shadow, staging, stats, storage, streams, timers};
+use crate::strategies::capacity::CapacityStatus;
+use crate::strategies::retry::RetryPolicy;
+use crate::strategies::stats::{InstanceCounters, InstanceStats};
+use crate::strategies::watchdog::{StallAction, Verdict, WatchdogConfig, WatchdogState};
+use crate::strategies::replay::ReplaySource;
+use crate::strategies::order::{PlaceOrderFn, CancelOrderFn, HostApi, HOST_API, place_order, cancel_order, risk_manager, set_clock, set_current_instance, set_recv_mode};
+
+/// Второй слот blue-green: swap переводит "strategy:SYMBOL" в
+/// "strategy:SYMBOL@green" и обратно
+pub const GREEN_SLOT: &str = "@green";
+
+/// Как часто swap проверяет готовность нового инстанса
+const READY_POLL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Id того же инстанса в другом слоте blue-green
+pub fn other_slot(instance_id: &str) -> String {
+    match instance_id.strip_suffix(GREEN_SLOT) {
+        Some(blue) => blue.to_string(),
+        None => format!("{}{}", instance_id, GREEN_SLOT),
+    }
+}
+
+/// Id инстанса без слота blue-green. Под ним хранится то, что должно
+/// пережить swap: лимиты риска, убыток за день, ордера инстанса
+pub fn base_slot(instance_id: &str) -> &str {
+    instance_id.strip_suffix(GREEN_SLOT).unwrap_or(instance_id)
+}
 
 #[repr(C)]
 pub struct StrategyConfig {
@@ -21,154 +59,508 @@ pub struct StrategyConfig {
     pub symbol_len: u8,
     pub params_json: *const std::os::raw::c_char,
     pub stop_flag: *const AtomicBool,
+    pub host: *const HostApi,
 }
 
-type RunFn = unsafe extern "C" fn(
+pub(crate) type RunFn = unsafe extern "C" fn(
     rx: *mut Receiver<CEvent>,
     place_order: PlaceOrderFn,
     cancel_order: CancelOrderFn,
     config: StrategyConfig,
 ) -> i32;
 
+/// Как стратегия ждёт события (подсказка для хелпера recv_event в types.rs)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecvMode {
+    /// recv_timeout: поток спит, пробуждение - десятки микросекунд
+    #[default]
+    Sleep,
+    /// try_recv + spin_loop: ядро занято на 100%, пробуждение - единицы микросекунд.
+    /// Имеет смысл только с выделенным ядром под стратегию
+    Spin,
+}
+
+impl RecvMode {
+    pub fn as_u8(self) -> u8 {
+        match self {
+            RecvMode::Sleep => 0,
+            RecvMode::Spin => 1,
+        }
+    }
+}
+
+/// Куда уходят ордера инстанса
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TradingMode {
+    /// На биржу
+    #[default]
+    Live,
+    /// В симулятор (paper.rs) на live market data
+    Paper,
+}
+
+/// Опции запуска инстанса (поля StartRequest рядом с symbol/params)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InstanceOptions {
+    pub recv_mode: RecvMode,
+    /// Ядро CPU для потока стратегии (null - не закреплять)
+    pub cpu_core: Option<usize>,
+    /// Ёмкость канала событий стратегии
+    pub channel_capacity: usize,
+    /// "drop-newest" | "drop-oldest" | "block"
+    pub overflow: OverflowPolicy,
+    /// "live" | "paper". Replay всегда торгует в симуляторе
+    pub mode: TradingMode,
+    /// {"replay": "BTCUSDT/2024-01-01.bin", "speed": 10.0} - события из записи.
+    /// null - live market data
+    pub source: Option<ReplaySource>,
+    /// Ордера только записываются в order manager (с рынком на момент
+    /// решения), на биржу ничего не уходит. Только для live market data
+    pub dry_run: bool,
+    /// Сколько ждать выхода из run() после stop, прежде чем снять инстанс
+    /// принудительно (его поток при этом уходит в карантин)
+    pub stop_timeout_ms: u64,
+    /// Risk-лимиты этого запуска (max_position_qty, max_order_notional,
+    /// max_open_orders, max_daily_loss) поверх лимитов инстанса (risk.instances
+    /// из конфига или /api/risk), снимаются по завершении запуска.
+    /// Проверяются ядром до подписи ордера; max_position_qty считается
+    /// по позиции всего ключа, а не только этого инстанса
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limits: Option<RiskLimits>,
+    /// Окно подавления одинаковых ордеров, ms (0 - выключить,
+    /// null - dedup.window_ms из конфига)
+    pub dedup_window_ms: Option<u64>,
+    /// {"max_retries": 2, "backoff_ms": 100, "codes": [-1001, -1008]} -
+    /// повтор ордеров стратегии при сбоях связи (null - не повторять)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry: Option<RetryPolicy>,
+    /// Группа для массовых stop/pause/start и общей статистики
+    /// (/api/groups/{name}). null - сам по себе
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+impl Default for InstanceOptions {
+    fn default() -> Self {
+        Self {
+            recv_mode: RecvMode::default(),
+            cpu_core: None,
+            channel_capacity: 8192,
+            overflow: OverflowPolicy::default(),
+            mode: TradingMode::default(),
+            source: None,
+            dry_run: false,
+            stop_timeout_ms: 10_000,
+            limits: None,
+            dedup_window_ms: None,
+            retry: None,
+            group: None,
+        }
+    }
+}
+
+/// Верхняя граница channel_capacity: 1M событий ~ 150MB на инстанс
+const MAX_CHANNEL_CAPACITY: usize = 1 << 20;
+
+/// Границы stop_timeout_ms
+const MIN_STOP_TIMEOUT_MS: u64 = 100;
+const MAX_STOP_TIMEOUT_MS: u64 = 600_000;
+
+impl InstanceOptions {
+    pub fn validate(&self) -> Result<()> {
+        if let Some(core) = self.cpu_core.filter(|c| !affinity::is_valid(*c)) {
+            anyhow::bail!("CPU core {} not available", core);
+        }
+        if !(1..=MAX_CHANNEL_CAPACITY).contains(&self.channel_capacity) {
+            anyhow::bail!("channel_capacity must be 1..={}", MAX_CHANNEL_CAPACITY);
+        }
+        if !(MIN_STOP_TIMEOUT_MS..=MAX_STOP_TIMEOUT_MS).contains(&self.stop_timeout_ms) {
+            anyhow::bail!("stop_timeout_ms must be {}..={}", MIN_STOP_TIMEOUT_MS, MAX_STOP_TIMEOUT_MS);
+        }
+        if let Some(source) = &self.source {
+            source.validate()?;
+        }
+        if let Some(limits) = &self.limits {
+            limits.validate()?;
+        }
+        if let Some(retry) = &self.retry {
+            retry.validate()?;
+        }
+        if let Some(group) = &self.group {
+            groups::validate_name(group)?;
+        }
+        if self.dry_run && (self.mode == TradingMode::Paper || self.source.is_some()) {
+            anyhow::bail!("dry_run works only with live mode and live market data");
+        }
+        Ok(())
+    }
+
+    /// live, paper, dry_run, replay - для /metrics и истории запусков
+    pub fn mode_name(&self) -> &'static str {
+        match (&self.source, self.dry_run, self.mode) {
+            (Some(_), _, _) => "replay",
+            (None, true, _) => "dry_run",
+            (None, false, TradingMode::Paper) => "paper",
+            (None, false, TradingMode::Live) => "live",
+        }
+    }
+}
+
+/// Состояние канала событий инстанса
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChannelStats {
+    /// Событий, положенных в канал fan-out'ом с запуска (replay не считается)
+    pub received: u64,
+    /// Событий в очереди сейчас
+    pub queued: usize,
+    /// Потеряно из-за переполнения (для block - по таймауту)
+    pub dropped: u64,
+}
+
+/// Состояние работающего инстанса для списков и фильтров
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InstanceStatus {
+    #[default]
+    Running,
+    /// Торговля на паузе (breaker или вручную)
+    Paused,
+    /// Watchdog считает стратегию зависшей
+    Stalled,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct InstanceInfo {
     pub instance_id: String,
     pub strategy_id: String,
     pub symbol: String,
-    pub params: serde_json::Value,
+    /// Параметры запуска: в ответ API и логи попадают с замаскированными секретами
+    pub params: redact::Params,
+    #[serde(flatten)]
+    pub options: InstanceOptions,
+    pub channel: ChannelStats,
+    /// Счёт симулятора (paper/replay)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub paper: Option<PaperStats>,
+    /// Счётчики ордеров, возраст последнего события и CPU потока (раз в секунду)
+    pub stats: InstanceStats,
     pub started_at: i64,
+    pub status: InstanceStatus,
+    #[serde(flatten)]
+    pub breaker: BreakerState,
+    #[serde(flatten)]
+    pub watchdog: WatchdogState,
 }
 
 struct RunningInstance {
     info: InstanceInfo,
-    _lib: Arc<Library>,
+    lib: Arc<Library>,
+    /// Для перезапуска watchdog'ом
+    lib_path: PathBuf,
     stop_flag: Arc<AtomicBool>,
     task: JoinHandle<i32>,
-    bridge_task: JoinHandle<()>,
+    /// None - инстанс на replay, fan-out его не кормит
+    subscription: Option<Arc<Subscriber>>,
+    counters: Arc<InstanceCounters>,
 }
 
 pub struct StrategyRunner {
     instances: Arc<DashMap<String, RunningInstance>>,
+    breaker: Arc<CircuitBreaker>,
+    watchdog: WatchdogConfig,
+    /// Проверка потолков и вставка инстанса - одним шагом, иначе
+    /// параллельные запуски проскочат capacity.max_instances
+    start_lock: std::sync::Mutex<()>,
 }
 
 impl StrategyRunner {
-    pub fn new() -> Arc<Self> {
+    pub fn new(breaker: Arc<CircuitBreaker>, watchdog: WatchdogConfig) -> Arc<Self> {
         let runner = Arc::new(Self {
             instances: Arc::new(DashMap::new()),
+            breaker,
+            watchdog,
+            start_lock: std::sync::Mutex::new(()),
         });
         
-        let instances = runner.instances.clone();
+        let weak = Arc::downgrade(&runner);
         tokio::spawn(async move {
-            tracing::info!("🧹 Cleanup loop started");
-            Self::cleanup_loop(instances).await;
+            tracing::info!("🧹 Cleanup/monitor loop started");
+            Self::cleanup_loop(weak).await;
         });
         
         runner
     }
     
-    async fn cleanup_loop(instances: Arc<DashMap<String, RunningInstance>>) {
+    async fn cleanup_loop(runner: Weak<Self>) {
         let mut check_count = 0u64;
         
         loop {
             tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
             check_count += 1;
+            let Some(runner) = runner.upgrade() else { break };
+            let instances = &runner.instances;
+            let stall_ms = runner.watchdog.stall_secs as i64 * 1000;
             
             // Логируем каждые 10 секунд что cleanup работает
-            if check_count % 10 == 0 {
+            if check_count.is_multiple_of(10) {
                 let count = instances.len();
                 if count > 0 {
                     tracing::debug!("🧹 Cleanup check #{}: {} instances", check_count, count);
                 }
             }
             
-            // Собираем завершённые
+            // Собираем завершённые, у работающих обновляем статистику и пульс
             let mut finished = Vec::new();
+            let mut stalled = Vec::new();
             
-            for entry in instances.iter() {
-                let id = entry.key();
-                let inst = entry.value();
+            for mut entry in instances.iter_mut() {
+                let id = entry.key().clone();
+                let inst = entry.value_mut();
+                inst.info.stats = Self::sample(inst);
                 
-                let task_finished = inst.task.is_finished();
-                let bridge_finished = inst.bridge_task.is_finished();
+                if inst.task.is_finished() {
+                    tracing::info!("🔍 Instance '{}': task=DONE", id);
+                    finished.push(id);
+                    continue;
+                }
                 
-                if task_finished {
-                    tracing::info!(
-                        "🔍 Instance '{}': task={}, bridge={}", 
-                        id, 
-                        if task_finished { "DONE" } else { "running" },
-                        if bridge_finished { "DONE" } else { "running" }
-                    );
-                    finished.push(id.clone());
+                // Replay ждёт стратегию сам, следим только за live-каналом
+                let Some(sub) = inst.subscription.as_ref().filter(|_| stall_ms > 0) else { continue };
+                let queued = sub.queued();
+                let consumed = sub.received().saturating_sub(queued as u64);
+                let now = inst.info.stats.sampled_at;
+                match inst.info.watchdog.observe(consumed, queued, inst.counters.heartbeats(), now, stall_ms) {
+                    Some(Verdict::Stalled) => stalled.push(id),
+                    Some(Verdict::Recovered) => {
+                        tracing::info!("💓 '{}' is consuming events again", id);
+                        logs::push(&id, logs::LOG_INFO, "Watchdog: strategy recovered");
+                    }
+                    None => {}
                 }
             }
             
+            for id in stalled {
+                runner.on_stall(id);
+            }
+            
             // Удаляем завершённые
             for id in finished {
                 if let Some((_, inst)) = instances.remove(&id) {
-                    // Останавливаем bridge если ещё работает
-                    if !inst.bridge_task.is_finished() {
-                        inst.stop_flag.store(true, Ordering::Relaxed);
-                        inst.bridge_task.abort();
-                    }
-                    
                     // Получаем exit code
-                    let code = match inst.task.await {
-                        Ok(c) => Some(c),
+                    let mut event = match inst.task.await {
+                        Ok(0) => LifecycleEvent::new(LifecycleKind::Stopped, &inst.info.strategy_id)
+                            .exit_code(Some(0)),
+                        Ok(c) => LifecycleEvent::new(LifecycleKind::Crashed, &inst.info.strategy_id)
+                            .exit_code(Some(c)),
                         Err(e) => {
                             tracing::error!("❌ Task '{}' panicked: {:?}", id, e);
-                            None
+                            LifecycleEvent::new(LifecycleKind::Crashed, &inst.info.strategy_id)
+                                .message(inst.info.params.scrub(&format!("panicked: {}", e)))
                         }
                     };
+                    event = event.instance(&id);
+                    let outcome = if event.kind == LifecycleKind::Stopped { runs::RunOutcome::Stopped } else { runs::RunOutcome::Crashed };
+                    runs::finish(&id, outcome, event.exit_code, event.message.clone());
                     
-                    tracing::info!("🧹 Cleaned '{}' (exit: {:?})", id, code);
+                    tracing::info!("🧹 Cleaned '{}' (exit: {:?})", id, event.exit_code);
+                    lifecycle::emit(event);
                 }
             }
         }
     }
     
+    /// Алерт о зависшем инстансе и действие из конфига watchdog'а
+    fn on_stall(self: &Arc<Self>, instance_id: String) {
+        let Some((strategy_id, lib_path, queued)) = self.instances.get(&instance_id).map(|e| {
+            let queued = e.subscription.as_ref().map_or(0, |s| s.queued());
+            (e.info.strategy_id.clone(), e.lib_path.clone(), queued)
+        }) else { return };
+        
+        let message = format!(
+            "'{}' stalled: no events consumed and no heartbeat for {}s ({} queued)",
+            instance_id, self.watchdog.stall_secs, queued
+        );
+        alerts::emit(AlertLevel::Critical, "watchdog", message.clone());
+        logs::push(&instance_id, logs::LOG_ERROR, &format!("Watchdog: {}", message));
+        lifecycle::emit(
+            LifecycleEvent::new(LifecycleKind::Stalled, &strategy_id)
+                .instance(&instance_id)
+                .message(message),
+        );
+        
+        let runner = self.clone();
+        match self.watchdog.action {
+            StallAction::Alert => {}
+            StallAction::Stop => {
+                tokio::spawn(async move {
+                    if let Err(e) = runner.stop(&instance_id).await {
+                        tracing::error!("❌ Watchdog failed to stop '{}': {}", instance_id, e);
+                    }
+                });
+            }
+            StallAction::Restart => {
+                tokio::spawn(async move {
+                    if let Err(e) = runner.restart(&instance_id, lib_path).await {
+                        tracing::error!("❌ Watchdog failed to restart '{}': {}", instance_id, e);
+                    }
+                });
+            }
+        }
+    }
+    
     pub async fn start(
         &self,
         strategy_id: String,
         symbol: String,
         lib_path: PathBuf,
-        event_rx: broadcast::Receiver<CEvent>,
         params: serde_json::Value,
+        options: InstanceOptions,
     ) -> Result<InstanceInfo> {
         let instance_id = format!("{}:{}", strategy_id, symbol.to_uppercase());
-        
+        self.launch(instance_id, strategy_id, symbol, lib_path, params.into(), options, false).await
+    }
+    
+    /// Запуск под заданным id (слот blue или green). swap - второй слот
+    /// занят заменяемым инстансом: он не мешает и не считается в capacity
+    #[allow(clippy::too_many_arguments)]
+    async fn launch(
+        &self,
+        instance_id: String,
+        strategy_id: String,
+        symbol: String,
+        lib_path: PathBuf,
+        params: redact::Params,
+        options: InstanceOptions,
+        swap: bool,
+    ) -> Result<InstanceInfo> {
+        // До вставки в instances ниже нет await: std-мьютекс здесь можно держать
+        let _start_guard = self.start_lock.lock().unwrap();
         if self.instances.contains_key(&instance_id) {
             anyhow::bail!("Instance '{}' already running", instance_id);
         }
+        let sibling = other_slot(&instance_id);
+        if !swap && self.instances.contains_key(&sibling) {
+            anyhow::bail!("Instance '{}' already running", sibling);
+        }
+        let running_strategy = self.instances.iter()
+            .filter(|e| e.value().info.strategy_id == strategy_id)
+            .count();
+        let replaced = usize::from(swap);
+        capacity::check_start(
+            &strategy_id,
+            self.instances.len().saturating_sub(replaced),
+            running_strategy.saturating_sub(replaced),
+        )?;
+        if quarantine::contains(&instance_id) {
+            anyhow::bail!("Instance '{}' is quarantined: its previous run has not exited yet", instance_id);
+        }
         
-        let params_json = serde_json::to_string(&params)?;
+        // Replay-файл может быть записан по символу, которого уже нет на бирже
+        if options.source.is_none() {
+            symbols::check(&symbol)?;
+        }
+        
+        let replay = options.source.as_ref()
+            .map(|src| src.validate().map(|path| (path, src.speed)))
+            .transpose()?;
+        
+        let params_json = serde_json::to_string(params.expose())?;
         
-        tracing::info!("📦 Starting '{}' with params: {}", instance_id, params_json);
+        // Новый запуск - чистый счётчик ошибок и лог. Замена продолжает
+        // заменяемый инстанс: сработавший breaker остаётся в силе
+        if swap {
+            self.breaker.inherit(&instance_id, &sibling);
+        } else {
+            self.breaker.reset(&instance_id);
+        }
+        logs::clear(&instance_id);
+        
+        tracing::info!("📦 Starting '{}' with params: {}", instance_id, params);
+        logs::push(&instance_id, logs::LOG_INFO, &format!("Starting with params: {}", params));
         
-        let lib: Arc<Library> = Arc::new(unsafe { Library::new(&lib_path)? });
+        let lib: Arc<Library> = Arc::new(storage::open_library(&lib_path, true)?);
         let run_fn: RunFn = unsafe { *lib.get(b"run")? };
         
-        let (sync_tx, sync_rx) = bounded::<CEvent>(8192);
+        let (sync_tx, sync_rx) = bounded::<CEvent>(options.channel_capacity);
         let stop_flag = Arc::new(AtomicBool::new(false));
         
-        // Bridge task
-        let bridge_task = {
-            let instance_id = instance_id.clone();
-            let stop_flag = stop_flag.clone();
-            
-            tokio::spawn(async move {
-                Self::bridge_loop(instance_id, event_rx, sync_tx, stop_flag).await;
-            })
+        let timer_tx = sync_tx.clone();
+        // Источник событий - до регистраций ниже: если replay не поднялся,
+        // за инстансом не остаётся ни лимитов, ни dedup, ни группы
+        let subscription = match replay {
+            Some((path, speed)) => {
+                let clock = replay::register_clock(&instance_id, &path)?;
+                let account = paper::register(&instance_id, sync_tx.clone(), false);
+                let spawned = {
+                    let instance_id = instance_id.clone();
+                    let stop_flag = stop_flag.clone();
+                    std::thread::Builder::new()
+                        .name(format!("replay-{}", instance_id))
+                        .spawn(move || replay::run(&instance_id, path, speed, sync_tx, account, clock, stop_flag))
+                };
+                if let Err(e) = spawned {
+                    replay::unregister_clock(&instance_id);
+                    paper::unregister(&instance_id);
+                    return Err(e.into());
+                }
+                None
+            }
+            None => {
+                let account = (options.mode == TradingMode::Paper)
+                    .then(|| paper::register(&instance_id, sync_tx.clone(), true));
+                // Канал стратегии пополняется напрямую из WS reader'ов
+                let last_tick = latency::register_instance(&instance_id);
+                Some(fanout::subscribe(&instance_id, sync_tx, &sync_rx, options.overflow, last_tick, account))
+            }
         };
         
+        if options.dry_run {
+            intents::register(&instance_id);
+        }
+        if let (Some(limits), Some(risk)) = (&options.limits, risk_manager()) {
+            risk.set_run_limits(&instance_id, limits);
+        }
+        dedup::register(&instance_id, options.dedup_window_ms);
+        retry::register(&instance_id, options.retry.as_ref(), &lib);
+        groups::register(&instance_id, groups::Member {
+            strategy_id: strategy_id.clone(),
+            symbol: symbol.clone(),
+            params: params.clone(),
+            options: options.clone(),
+        });
+        
+        let (recv_mode, cpu_core) = (options.recv_mode, options.cpu_core);
+        let counters = stats::register(&instance_id);
+        let mode = options.mode_name();
+        metrics::register(&instance_id, metrics::Labels {
+            strategy: strategy_id.clone(),
+            symbol: symbol.to_uppercase(),
+            mode,
+        });
+        runs::begin(&instance_id, &strategy_id, &symbol, mode, &params);
+        // Replay и dry run не должны менять состояние, которое увидит live
+        if options.source.is_some() || options.dry_run {
+            kv::sandbox(&instance_id);
+        }
+        timers::register(&instance_id, timer_tx, options.source.is_some());
+        if options.source.is_none() {
+            streams::register(&instance_id);
+        }
+        
         // Strategy task
         let task = {
             let instance_id = instance_id.clone();
             let symbol = symbol.clone();
             let lib = lib.clone();
             let stop_flag = stop_flag.clone();
+            let subscription = subscription.clone();
+            let counters = counters.clone();
             
             tokio::task::spawn_blocking(move || {
-                let result = Self::run_strategy(
+                let run = || Self::run_strategy(
                     instance_id.clone(), 
                     lib, 
                     run_fn, 
@@ -176,7 +568,27 @@ impl StrategyRunner {
                     symbol, 
                     params_json,
                     stop_flag,
+                    recv_mode,
+                    (place_order, cancel_order),
                 );
+                let result = match cpu_core {
+                    // Поток пула spawn_blocking потом достанется другим задачам,
+                    // поэтому закрепляем не его, а отдельный поток на время run()
+                    Some(core) => std::thread::scope(|s| {
+                        // place_order делает tokio::spawn: без контекста runtime
+                        // паника в extern "C" роняет весь процесс
+                        let handle = tokio::runtime::Handle::current();
+                        s.spawn(move || {
+                            let _runtime = handle.enter();
+                            affinity::pin_current(core);
+                            run()
+                        })
+                        .join()
+                        .unwrap_or_else(|e| std::panic::resume_unwind(e))
+                    }),
+                    None => run(),
+                };
+                Self::detach(&instance_id, &subscription, &counters);
                 
                 tracing::info!("📤 Task '{}' returning {}", instance_id, result);
                 result
@@ -188,66 +600,171 @@ impl StrategyRunner {
             strategy_id,
             symbol,
             params,
+            options,
+            channel: ChannelStats::default(),
+            paper: None,
+            stats: InstanceStats::default(),
             started_at: chrono::Utc::now().timestamp(),
+            status: InstanceStatus::Running,
+            breaker: BreakerState::default(),
+            watchdog: WatchdogState::default(),
         };
         
         self.instances.insert(instance_id.clone(), RunningInstance {
             info: info.clone(),
-            _lib: lib,
+            lib,
+            lib_path,
             stop_flag,
             task,
-            bridge_task,
+            subscription,
+            counters,
         });
         
         tracing::info!("✅ Instance '{}' started", instance_id);
+        lifecycle::emit(LifecycleEvent::new(LifecycleKind::Started, &info.strategy_id).instance(&instance_id));
         Ok(info)
     }
     
-    async fn bridge_loop(
-        instance_id: String,
-        mut event_rx: broadcast::Receiver<CEvent>,
-        sync_tx: Sender<CEvent>,
-        stop_flag: Arc<AtomicBool>,
-    ) {
-        tracing::debug!("🌉 Bridge '{}' started", instance_id);
-        let mut dropped = 0u64;
+    /// Останавливает инстанс и запускает заново с теми же параметрами
+    pub async fn restart(
+        &self,
+        instance_id: &str,
+        lib_path: PathBuf,
+    ) -> Result<InstanceInfo> {
+        let info = self.instances.get(instance_id)
+            .map(|e| e.info.clone())
+            .ok_or_else(|| anyhow::anyhow!("Instance '{}' not found", instance_id))?;
         
-        loop {
-            if stop_flag.load(Ordering::Relaxed) {
-                tracing::debug!("🌉 Bridge '{}' stopping (flag)", instance_id);
-                break;
+        self.stop(instance_id).await?;
+        let info = self
+            .launch(instance_id.to_string(), info.strategy_id, info.symbol, lib_path, info.params, info.options, false)
+            .await?;
+        
+        lifecycle::emit(LifecycleEvent::new(LifecycleKind::Restarted, &info.strategy_id).instance(instance_id));
+        Ok(info)
+    }
+    
+    /// Blue-green замена: копия инстанса из lib_path запускается во втором
+    /// слоте, и только когда она забрала первое событие (или позвала
+    /// heartbeat), старый ставится на паузу и останавливается. До этого
+    /// торгуют оба. Не дождались за ready_timeout - копия останавливается,
+    /// старый работает дальше
+    pub async fn swap(
+        &self,
+        instance_id: &str,
+        lib_path: PathBuf,
+        ready_timeout: std::time::Duration,
+    ) -> Result<InstanceInfo> {
+        let info = self.instances.get(instance_id)
+            .map(|e| e.info.clone())
+            .ok_or_else(|| anyhow::anyhow!("Instance '{}' not found", instance_id))?;
+        if info.options.source.is_some() {
+            anyhow::bail!("Instance '{}' runs on replay, swap is for live instances", instance_id);
+        }
+        
+        let member = groups::Member {
+            strategy_id: info.strategy_id.clone(),
+            symbol: info.symbol.clone(),
+            params: info.params.clone(),
+            options: info.options.clone(),
+        };
+        let new_id = other_slot(instance_id);
+        tracing::info!("🔀 Swapping '{}' -> '{}'", instance_id, new_id);
+        self.launch(new_id.clone(), info.strategy_id.clone(), info.symbol, lib_path, info.params, info.options, true)
+            .await?;
+        
+        if let Err(e) = self.wait_ready(&new_id, ready_timeout).await {
+            if self.instances.contains_key(&new_id) {
+                let _ = self.stop(&new_id).await;
             }
-            
-            // Используем select с таймаутом чтобы проверять флаг
-            match tokio::time::timeout(
-                tokio::time::Duration::from_millis(100),
-                event_rx.recv()
-            ).await {
-                Ok(Ok(event)) => {
-                    if sync_tx.try_send(event).is_err() {
-                        dropped += 1;
-                        if dropped % 1000 == 0 {
-                            tracing::warn!("⚠️ '{}' lagging: {} dropped", instance_id, dropped);
-                        }
-                    }
-                }
-                Ok(Err(broadcast::error::RecvError::Closed)) => {
-                    tracing::debug!("🌉 Bridge '{}' stopping (closed)", instance_id);
-                    break;
-                }
-                Ok(Err(broadcast::error::RecvError::Lagged(n))) => {
-                    tracing::warn!("'{}' lagged {} msgs", instance_id, n);
+            // Группа снова указывает на старый инстанс
+            groups::register(instance_id, member);
+            anyhow::bail!("Swap of '{}' aborted, it keeps running: {}", instance_id, e);
+        }
+        
+        // Пауза сразу: пока старый выходит из run(), новых ордеров от него нет
+        let _ = self.breaker.pause(instance_id, &format!("swapped out for '{}'", new_id));
+        if let Err(e) = self.stop(instance_id).await {
+            anyhow::bail!("'{}' is running, but stopping '{}' failed: {}", new_id, instance_id, e);
+        }
+        
+        lifecycle::emit(
+            LifecycleEvent::new(LifecycleKind::Swapped, &info.strategy_id)
+                .instance(&new_id)
+                .message(format!("replaced '{}'", instance_id)),
+        );
+        self.get(&new_id).ok_or_else(|| anyhow::anyhow!("Instance '{}' exited right after swap", new_id))
+    }
+    
+    /// Тень живого инстанса (см. shadow.rs): dry-run копия strategy_id из
+    /// lib_path с id {live}@shadow, её ордера только записываются
+    pub async fn start_shadow(
+        &self,
+        live_id: &str,
+        strategy_id: String,
+        lib_path: PathBuf,
+        params: serde_json::Value,
+        match_window_ms: u64,
+    ) -> Result<InstanceInfo> {
+        let Some(live) = self.get(live_id) else {
+            anyhow::bail!("Instance '{}' not found", live_id);
+        };
+        if live_id.ends_with(shadow::SHADOW_SUFFIX) {
+            anyhow::bail!("Instance '{}' is a shadow itself", live_id);
+        }
+        if live.options.source.is_some() {
+            anyhow::bail!("Instance '{}' runs on replay, shadow is for live instances", live_id);
+        }
+        // Ядро живого и группа - его, тень не должна их делить
+        let options = InstanceOptions {
+            dry_run: true,
+            group: None,
+            cpu_core: None,
+            ..live.options
+        };
+        let shadow_id = shadow::shadow_id(live_id);
+        let started_at = chrono::Utc::now().timestamp_millis();
+        let info = self
+            .launch(shadow_id.clone(), strategy_id.clone(), live.symbol, lib_path, params.into(), options, false)
+            .await?;
+        shadow::register(shadow::ShadowPair {
+            live_id: live_id.to_string(),
+            shadow_id,
+            shadow_strategy_id: strategy_id,
+            started_at,
+            match_window_ms,
+        });
+        Ok(info)
+    }
+    
+    /// Ждёт, пока стратегия заберёт из очереди первое событие или позовёт heartbeat
+    async fn wait_ready(&self, instance_id: &str, timeout: std::time::Duration) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            {
+                let Some(inst) = self.instances.get(instance_id) else {
+                    anyhow::bail!("'{}' stopped before it was ready", instance_id);
+                };
+                if inst.task.is_finished() {
+                    anyhow::bail!("'{}' exited before it was ready", instance_id);
                 }
-                Err(_) => {
-                    // Таймаут - проверяем флаг на следующей итерации
+                let consumed = inst.subscription.as_ref()
+                    .map_or(0, |sub| sub.received().saturating_sub(sub.queued() as u64));
+                if consumed > 0 || inst.counters.heartbeats() > 0 {
+                    return Ok(());
                 }
             }
+            if tokio::time::Instant::now() >= deadline {
+                anyhow::bail!("'{}' processed no events in {}s", instance_id, timeout.as_secs());
+            }
+            tokio::time::sleep(READY_POLL).await;
         }
-        
-        tracing::debug!("🌉 Bridge '{}' stopped", instance_id);
     }
     
-    fn run_strategy(
+    /// Вызывает run() библиотеки в текущем потоке. orders - функции ордеров,
+    /// которые получит стратегия (для dry run - перехватчики)
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn run_strategy(
         instance_id: String,
         lib: Arc<Library>,
         run_fn: RunFn,
@@ -255,6 +772,8 @@ impl StrategyRunner {
         symbol: String,
         params_json: String,
         stop_flag: Arc<AtomicBool>,
+        recv_mode: RecvMode,
+        orders: (PlaceOrderFn, CancelOrderFn),
     ) -> i32 {
         tracing::info!("🚀 Strategy thread '{}' started", instance_id);
         
@@ -270,22 +789,66 @@ impl StrategyRunner {
             symbol_len: len as u8,
             params_json: params_cstring.as_ptr(),
             stop_flag: Arc::as_ptr(&stop_flag),
+            host: &HOST_API,
         };
         
         let rx_ptr = Box::into_raw(Box::new(sync_rx));
         
-        let result = unsafe { run_fn(rx_ptr, place_order, cancel_order, config) };
+        // Поток из пула spawn_blocking переиспользуется - обязательно сбрасываем после run
+        set_current_instance(Some(instance_id.clone()));
+        set_recv_mode(recv_mode.as_u8());
+        set_clock(replay::clock(&instance_id));
+        stats::bind_thread(&instance_id, true);
+        let result = unsafe { run_fn(rx_ptr, orders.0, orders.1, config) };
+        stats::bind_thread(&instance_id, false);
+        set_current_instance(None);
+        set_recv_mode(0);
+        set_clock(None);
+        staging::clear(&instance_id);
+        kv::release(&instance_id);
+        timers::unregister(&instance_id);
+        streams::unregister(&instance_id);
+        execution::cancel_instance(&instance_id);
         
-        // Ставим флаг чтобы bridge остановился
         stop_flag.store(true, Ordering::Relaxed);
         
         unsafe { let _ = Box::from_raw(rx_ptr); }
         drop(lib);
         
         tracing::info!("🏁 Strategy thread '{}' finished (code={})", instance_id, result);
+        let level = if result == 0 { logs::LOG_INFO } else { logs::LOG_ERROR };
+        logs::push(&instance_id, level, &format!("Finished (code={})", result));
         result
     }
     
+    /// Отключает канал стратегии от fan-out и симулятора
+    fn detach(instance_id: &str, subscription: &Option<Arc<Subscriber>>, counters: &Arc<InstanceCounters>) {
+        if let Some(sub) = subscription {
+            fanout::unsubscribe(sub);
+            latency::unregister_instance(instance_id, sub.last_tick());
+        }
+        replay::unregister_clock(instance_id);
+        intents::unregister(instance_id);
+        dedup::unregister(instance_id);
+        retry::unregister(instance_id);
+        if let Some(risk) = risk_manager() {
+            risk.clear_run_limits(instance_id);
+        }
+        stats::unregister(instance_id, counters);
+        let account = paper::unregister(instance_id);
+        runs::settle(instance_id, counters, account.as_ref().map(|a| a.stats()).as_ref());
+        // Итог симуляции остаётся в логе инстанса после его завершения
+        if let Some(account) = account {
+            let stats = account.stats();
+            let summary = format!(
+                "Paper result: {} fills, volume {:.2}, fees {:.4}, realized PnL {:.4}, {} open orders",
+                stats.fills, stats.volume, stats.fees, stats.realized_pnl, stats.open_orders
+            );
+            tracing::info!("🧪 '{}' {}", instance_id, summary);
+            logs::push(instance_id, logs::LOG_INFO, &summary);
+        }
+    }
+    
     pub async fn stop(&self, instance_id: &str) -> Result<()> {
         let entry = self.instances.get(instance_id)
             .ok_or_else(|| anyhow::anyhow!("Instance '{}' not found", instance_id))?;
@@ -293,11 +856,12 @@ impl StrategyRunner {
         tracing::info!("🛑 Stopping '{}'...", instance_id);
         
         entry.stop_flag.store(true, Ordering::Relaxed);
+        let timeout_ms = entry.info.options.stop_timeout_ms;
         
         drop(entry);
         
         // Ждём очистки
-        for i in 0..100 {
+        for i in 0..timeout_ms.div_ceil(100) {
             if !self.instances.contains_key(instance_id) {
                 tracing::info!("✅ '{}' stopped after {}ms", instance_id, i * 100);
                 return Ok(());
@@ -305,10 +869,27 @@ impl StrategyRunner {
             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
         }
         
-        // Force remove
+        // Force remove: поток стратегии не убить, поэтому он уходит в карантин
+        // до выхода из run() - его ордера отклоняются, библиотека не выгружается
         if let Some((_, inst)) = self.instances.remove(instance_id) {
-            inst.bridge_task.abort();
-            tracing::warn!("⚠️ '{}' force removed", instance_id);
+            Self::detach(instance_id, &inst.subscription, &inst.counters);
+            quarantine::add(instance_id, &inst.info.strategy_id, inst.lib.clone());
+            
+            let message = format!("force removed, strategy did not exit in {}ms; thread quarantined", timeout_ms);
+            alerts::emit(AlertLevel::Critical, "runner", format!("'{}' {}", instance_id, message));
+            logs::push(instance_id, logs::LOG_ERROR, &message);
+            runs::finish(instance_id, runs::RunOutcome::ForceRemoved, None, Some(message.clone()));
+            lifecycle::emit(
+                LifecycleEvent::new(LifecycleKind::Stopped, &inst.info.strategy_id)
+                    .instance(instance_id)
+                    .message(message),
+            );
+            
+            let instance_id = instance_id.to_string();
+            tokio::spawn(async move {
+                let _ = inst.task.await;
+                quarantine::release(&instance_id);
+            });
         }
         
         Ok(())
@@ -327,21 +908,140 @@ impl StrategyRunner {
         to_stop
     }
     
+    /// Снимок счётчиков для monitor loop. CPU% - за интервал с прошлого снимка
+    fn sample(inst: &RunningInstance) -> InstanceStats {
+        let now = chrono::Utc::now().timestamp_millis();
+        let prev = &inst.info.stats;
+        let counters = &inst.counters;
+
+        // Paper/replay исполняет симулятор, user data про эти ордера не приходит
+        let orders_filled = paper::account(&inst.info.instance_id)
+            .map_or_else(|| counters.filled(), |a| a.stats().fills);
+        let last_event_age_ms = inst.subscription.as_ref()
+            .map(|sub| sub.last_tick().load(Ordering::Relaxed))
+            .filter(|&tick| tick > 0)
+            .map(|tick| latency::now_ns().saturating_sub(tick) / 1_000_000);
+
+        let cpu_time_ms = counters.cpu_time_ns().map(|ns| ns as f64 / 1e6);
+        let cpu_percent = match (cpu_time_ms, prev.cpu_time_ms) {
+            (Some(cpu), Some(prev_cpu)) if now > prev.sampled_at => {
+                Some((cpu - prev_cpu) / (now - prev.sampled_at) as f64 * 100.0)
+            }
+            _ => None,
+        };
+
+        InstanceStats {
+            orders_placed: counters.placed(),
+            orders_filled,
+            orders_rejected: counters.rejected(),
+            last_event_age_ms,
+            // Поток уже отвязан (стратегия завершилась) - оставляем последнее значение
+            cpu_time_ms: cpu_time_ms.or(prev.cpu_time_ms),
+            cpu_percent,
+            sampled_at: now,
+        }
+    }
+    
+    /// InstanceInfo с актуальным состоянием circuit breaker
+    fn snapshot(&self, inst: &RunningInstance) -> InstanceInfo {
+        let mut info = inst.info.clone();
+        info.breaker = self.breaker.state(&info.instance_id);
+        info.paper = paper::account(&info.instance_id).map(|a| a.stats());
+        info.status = if info.watchdog.stalled {
+            InstanceStatus::Stalled
+        } else if info.breaker.trading_paused {
+            InstanceStatus::Paused
+        } else {
+            InstanceStatus::Running
+        };
+        if let Some(sub) = &inst.subscription {
+            info.channel = ChannelStats {
+                received: sub.received(),
+                queued: sub.queued(),
+                dropped: sub.dropped(),
+            };
+        }
+        info
+    }
+    
     pub fn list(&self) -> Vec<InstanceInfo> {
-        self.instances.iter().map(|e| e.value().info.clone()).collect()
+        self.instances.iter().map(|e| self.snapshot(e.value())).collect()
     }
     
     pub fn list_for(&self, strategy_id: &str) -> Vec<InstanceInfo> {
         self.instances.iter()
             .filter(|e| e.value().info.strategy_id == strategy_id)
-            .map(|e| e.value().info.clone())
+            .map(|e| self.snapshot(e.value()))
             .collect()
     }
     
     pub fn get(&self, instance_id: &str) -> Option<InstanceInfo> {
-        self.instances.get(instance_id).map(|e| e.value().info.clone())
+        self.instances.get(instance_id).map(|e| self.snapshot(e.value()))
+    }
+    
+    /// Исходные параметры запуска (с секретами) - для копий инстанса, не для ответа API
+    pub fn start_params(&self, instance_id: &str) -> Option<serde_json::Value> {
+        self.instances.get(instance_id).map(|e| e.info.params.expose().clone())
+    }
+    
+    /// Какой из слотов blue-green инстанса сейчас работает
+    pub fn active_slot(&self, instance_id: &str) -> Option<String> {
+        [instance_id.to_string(), other_slot(instance_id)]
+            .into_iter()
+            .find(|id| self.instances.contains_key(id))
+    }
+    
+    /// Кладёт сигнал (JSON до SIGNAL_PAYLOAD_LEN байт) в канал стратегии
+    pub fn signal(&self, instance_id: &str, payload: &str) -> Result<()> {
+        let entry = self.instances.get(instance_id)
+            .ok_or_else(|| anyhow::anyhow!("Instance '{}' not found", instance_id))?;
+        let Some(sub) = entry.subscription.as_ref() else {
+            anyhow::bail!("Instance '{}' runs on replay, signals are not supported", instance_id);
+        };
+
+        let now = chrono::Utc::now();
+        let signal = CCustomSignal::new(payload, now.timestamp_millis())
+            .ok_or_else(|| anyhow::anyhow!("Signal payload exceeds {} bytes", SIGNAL_PAYLOAD_LEN))?;
+        let event = CEvent {
+            event_type: EVENT_SIGNAL,
+            data: CEventData { signal },
+            received_at_ns: now.timestamp_nanos_opt().unwrap_or(0) as u64,
+            seq: 0,
+        };
+        if !sub.send_signal(event) {
+            anyhow::bail!("Strategy channel is full, signal dropped");
+        }
+        logs::push(instance_id, logs::LOG_INFO, &format!("Signal received ({} bytes)", payload.len()));
+        Ok(())
+    }
+
+    /// Поставить торговлю на паузу вручную (снимается как пауза breaker'а)
+    pub fn pause_trading(&self, instance_id: &str, reason: &str) -> Result<()> {
+        if !self.instances.contains_key(instance_id) {
+            anyhow::bail!("Instance '{}' not found", instance_id);
+        }
+        if !self.breaker.pause(instance_id, reason) {
+            anyhow::bail!("Trading for '{}' is already paused", instance_id);
+        }
+        Ok(())
+    }
+
+    /// Снять паузу circuit breaker'а
+    pub fn resume_trading(&self, instance_id: &str) -> Result<()> {
+        if !self.instances.contains_key(instance_id) {
+            anyhow::bail!("Instance '{}' not found", instance_id);
+        }
+        if !self.breaker.resume(instance_id) {
+            anyhow::bail!("Trading for '{}' is not paused", instance_id);
+        }
+        Ok(())
+    }
+    
+    pub fn capacity(&self) -> CapacityStatus {
+        capacity::status(self.instances.len())
     }
     
+    #[allow(dead_code)]
     pub fn is_running(&self, instance_id: &str) -> bool {
         self.instances.contains_key(instance_id)
     }
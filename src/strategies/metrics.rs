@@ -0,0 +1,221 @@
+// src/strategies/metrics.rs
+
+use dashmap::DashMap;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{LazyLock, Mutex};
+
+use crate::fanout;
+use crate::strategies::stats;
+
+pub const METRIC_COUNTER: u8 = 0;
+pub const METRIC_GAUGE: u8 = 1;
+pub const METRIC_SUMMARY: u8 = 2;
+
+/// Разных метрик у одного инстанса: метрика на каждый символ или ордер
+/// раздувает /metrics и Prometheus
+const MAX_METRICS_PER_INSTANCE: usize = 64;
+const MAX_NAME_LEN: usize = 64;
+
+/// Префикс метрик стратегий: не пересекаются с метриками ядра
+const STRATEGY_PREFIX: &str = "strategy_";
+
+// ═══════════════════════════════════════════════════════════
+// МЕТРИКИ СТРАТЕГИЙ
+// ═══════════════════════════════════════════════════════════
+//
+// Стратегия отдаёт свои числа через emit_metric (FFI), а не печатает
+// print_stats() при остановке: ядро хранит их с метками инстанса и отдаёт
+// на GET /metrics (Prometheus text format) вместе со счётчиками ядра по
+// инстансам. Метрики остановленного инстанса остаются до его следующего
+// запуска: последний scrape видит итог.
+
+#[derive(Debug, Clone, Copy)]
+enum Metric {
+    /// Только растёт: emit добавляет value
+    Counter(f64),
+    /// emit заменяет значение
+    Gauge(f64),
+    /// emit - наблюдение (задержка, размер): отдаются _sum и _count
+    Summary { sum: f64, count: u64 },
+}
+
+impl Metric {
+    fn kind(&self) -> u8 {
+        match self {
+            Self::Counter(_) => METRIC_COUNTER,
+            Self::Gauge(_) => METRIC_GAUGE,
+            Self::Summary { .. } => METRIC_SUMMARY,
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            Self::Counter(_) => "counter",
+            Self::Gauge(_) => "gauge",
+            Self::Summary { .. } => "summary",
+        }
+    }
+}
+
+/// Метки инстанса в /metrics
+#[derive(Debug, Clone)]
+pub struct Labels {
+    pub strategy: String,
+    pub symbol: String,
+    /// live, paper, dry_run, replay
+    pub mode: &'static str,
+}
+
+struct InstanceMetrics {
+    labels: Labels,
+    metrics: Mutex<BTreeMap<String, Metric>>,
+}
+
+/// instance_id -> метки и метрики
+static INSTANCES: LazyLock<DashMap<String, InstanceMetrics>> = LazyLock::new(DashMap::new);
+
+/// Новый запуск: метрики прошлого запуска с тем же id сбрасываются
+pub fn register(instance_id: &str, labels: Labels) {
+    INSTANCES.insert(instance_id.to_string(), InstanceMetrics { labels, metrics: Mutex::new(BTreeMap::new()) });
+}
+
+/// [a-zA-Z_][a-zA-Z0-9_]*, как имя метрики Prometheus
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Метрика инстанса. false - неверное имя, тип или значение, у имени уже
+/// другой тип (у этого или другого инстанса) или метрик слишком много
+pub fn emit(instance_id: &str, name: &str, value: f64, kind: u8) -> bool {
+    if !valid_name(name) || !value.is_finite() || (kind == METRIC_COUNTER && value < 0.0) {
+        return false;
+    }
+    let Some(known) = INSTANCES.get(instance_id).map(|i| i.metrics.lock().unwrap().contains_key(name)) else {
+        return false;
+    };
+    // У имени один тип на весь /metrics: Prometheus не примет два # TYPE.
+    // Чужие метрики смотрим только на первом emit имени и ничего своего не держа
+    if !known {
+        let conflict = INSTANCES.iter().any(|i| {
+            i.key() != instance_id && i.metrics.lock().unwrap().get(name).is_some_and(|m| m.kind() != kind)
+        });
+        if conflict {
+            return false;
+        }
+    }
+    let Some(instance) = INSTANCES.get(instance_id) else { return false };
+    let mut metrics = instance.metrics.lock().unwrap();
+    if !metrics.contains_key(name) && metrics.len() >= MAX_METRICS_PER_INSTANCE {
+        return false;
+    }
+    let metric = metrics.entry(name.to_string()).or_insert(match kind {
+        METRIC_COUNTER => Metric::Counter(0.0),
+        METRIC_GAUGE => Metric::Gauge(0.0),
+        METRIC_SUMMARY => Metric::Summary { sum: 0.0, count: 0 },
+        _ => return false,
+    });
+    match metric {
+        Metric::Counter(total) if kind == METRIC_COUNTER => *total += value,
+        Metric::Gauge(current) if kind == METRIC_GAUGE => *current = value,
+        Metric::Summary { sum, count } if kind == METRIC_SUMMARY => {
+            *sum += value;
+            *count += 1;
+        }
+        _ => return false,
+    }
+    true
+}
+
+// ═══════════════════════════════════════════════════════════
+// PROMETHEUS
+// ═══════════════════════════════════════════════════════════
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn labels(instance_id: &str, labels: Option<&Labels>) -> String {
+    match labels {
+        Some(l) => format!(
+            "instance_id=\"{}\",strategy=\"{}\",symbol=\"{}\",mode=\"{}\"",
+            escape(instance_id), escape(&l.strategy), escape(&l.symbol), l.mode,
+        ),
+        None => format!("instance_id=\"{}\"", escape(instance_id)),
+    }
+}
+
+/// Строки одной метрики под общим HELP/TYPE
+struct Family {
+    help: &'static str,
+    kind: &'static str,
+    /// (суффикс имени: _sum/_count у summary, метки, значение)
+    samples: Vec<(&'static str, String, f64)>,
+}
+
+#[derive(Default)]
+struct Exposition {
+    families: BTreeMap<String, Family>,
+}
+
+impl Exposition {
+    fn add(&mut self, name: &str, help: &'static str, kind: &'static str, sample: (&'static str, String, f64)) {
+        self.families.entry(name.to_string())
+            .or_insert_with(|| Family { help, kind, samples: Vec::new() })
+            .samples.push(sample);
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        for (name, family) in &self.families {
+            let _ = writeln!(out, "# HELP {} {}", name, family.help);
+            let _ = writeln!(out, "# TYPE {} {}", name, family.kind);
+            for (suffix, labels, value) in &family.samples {
+                let _ = writeln!(out, "{}{}{{{}}} {}", name, suffix, labels, value);
+            }
+        }
+        out
+    }
+}
+
+/// Текст для GET /metrics: счётчики ядра по инстансам и метрики стратегий
+pub fn render() -> String {
+    let label_map: BTreeMap<String, Labels> = INSTANCES.iter().map(|i| (i.key().clone(), i.labels.clone())).collect();
+    let instance_labels = |id: &str| labels(id, label_map.get(id));
+    let mut out = Exposition::default();
+
+    for (id, counters) in stats::all() {
+        let l = instance_labels(&id);
+        out.add("hft_instance_orders_placed_total", "Orders sent by the instance", "counter", ("", l.clone(), counters.placed() as f64));
+        out.add("hft_instance_orders_filled_total", "Orders of the instance filled", "counter", ("", l.clone(), counters.filled() as f64));
+        out.add("hft_instance_orders_rejected_total", "Orders of the instance rejected", "counter", ("", l.clone(), counters.rejected() as f64));
+        out.add("hft_instance_heartbeats_total", "Explicit heartbeats from the strategy", "counter", ("", l, counters.heartbeats() as f64));
+    }
+    for sub in fanout::subscribers().iter() {
+        let l = instance_labels(sub.instance_id());
+        out.add("hft_instance_events_received_total", "Events put into the instance channel", "counter", ("", l.clone(), sub.received() as f64));
+        out.add("hft_instance_events_dropped_total", "Events lost on instance channel overflow", "counter", ("", l.clone(), sub.dropped() as f64));
+        out.add("hft_instance_events_queued", "Events waiting in the instance channel", "gauge", ("", l, sub.queued() as f64));
+    }
+
+    const HELP: &str = "Strategy metric (emit_metric)";
+    for instance in INSTANCES.iter() {
+        let l = labels(instance.key(), Some(&instance.labels));
+        for (name, metric) in instance.metrics.lock().unwrap().iter() {
+            let name = format!("{}{}", STRATEGY_PREFIX, name);
+            match *metric {
+                // Счётчики Prometheus по соглашению оканчиваются на _total
+                Metric::Counter(v) if !name.ends_with("_total") => out.add(&format!("{}_total", name), HELP, "counter", ("", l.clone(), v)),
+                Metric::Counter(v) | Metric::Gauge(v) => out.add(&name, HELP, metric.type_name(), ("", l.clone(), v)),
+                Metric::Summary { sum, count } => {
+                    out.add(&name, HELP, "summary", ("_sum", l.clone(), sum));
+                    out.add(&name, HELP, "summary", ("_count", l.clone(), count as f64));
+                }
+            }
+        }
+    }
+    out.render()
+}
@@ -1,9 +1,30 @@
 // src/strategies/trading.rs
 
+use std::cell::{Cell, RefCell};
 use std::ffi::CStr;
 use std::os::raw::c_char;
-use std::sync::{Arc, OnceLock};
-use crate::exchange_trade::ExchangeTrade;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use crate::audit::{self, AUDIT_INSTANCE};
+use crate::brackets::{cancel_bracket, place_bracket};
+use crate::exchange_trade::{ExchangeTrade, PreparedOrder, PRIORITY};
+use crate::execution::{cancel_algo_order, place_algo_order};
+use crate::funding;
+use crate::keystore::{self, Credentials};
+use crate::kv;
+use crate::positions::{close_position, get_position};
+use crate::volume_profile::{get_volume_profile, get_vwap};
+use crate::latency;
+use crate::notifications::{self, NotifyKind};
+use crate::ffi_types::{CAlgoOrder, CBracket, COrder, COrderTemplate, CVolumeLevel, CVwap};
+use crate::order_errors::ERR_NO_ORDER_ID;
+use crate::orders::{OrderFilter, OrderManager};
+use crate::paper;
+use crate::risk::RiskManager;
+use crate::strategies::breaker::{CircuitBreaker, ERR_CIRCUIT_OPEN};
+use crate::strategies::{capacity, dedup, intents, logs, metrics, quarantine, replay, retry, stats, timers};
+use crate::strategies::staging::{fire_staged, stage_orders, unstage};
+use crate::strategies::streams::{subscribe_stream, unsubscribe_stream};
 
 // ═══════════════════════════════════════════════════════════
 // ГЛОБАЛЬНЫЙ TRADE MANAGER (инициализируется один раз)
@@ -15,6 +36,97 @@ pub fn init_trading(manager: Arc<ExchangeTrade>) {
     TRADE_MANAGER.set(manager).ok();
 }
 
+static RISK_MANAGER: OnceLock<Arc<RiskManager>> = OnceLock::new();
+
+pub fn init_risk(risk: Arc<RiskManager>) {
+    RISK_MANAGER.set(risk).ok();
+}
+
+pub(crate) fn risk_manager() -> Option<&'static Arc<RiskManager>> {
+    RISK_MANAGER.get()
+}
+
+static ORDER_MANAGER: OnceLock<Arc<OrderManager>> = OnceLock::new();
+
+pub fn init_orders(orders: Arc<OrderManager>) {
+    ORDER_MANAGER.set(orders).ok();
+}
+
+static BREAKER: OnceLock<Arc<CircuitBreaker>> = OnceLock::new();
+
+pub fn init_breaker(breaker: Arc<CircuitBreaker>) {
+    BREAKER.set(breaker).ok();
+}
+
+// ═══════════════════════════════════════════════════════════
+// ПРИВЯЗКА К ИНСТАНСУ
+// ═══════════════════════════════════════════════════════════
+
+// Стратегия вызывает place_order из своего потока, поэтому instance_id
+// храним в thread-local: ABI стратегий при этом не меняется.
+thread_local! {
+    static CURRENT_INSTANCE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+pub fn set_current_instance(instance_id: Option<String>) {
+    CURRENT_INSTANCE.with(|c| *c.borrow_mut() = instance_id);
+}
+
+pub fn current_instance() -> Option<String> {
+    CURRENT_INSTANCE.with(|c| c.borrow().clone())
+}
+
+thread_local! {
+    static RECV_MODE: Cell<u8> = const { Cell::new(0) };
+}
+
+thread_local! {
+    /// Ордер из place_priority_order: в очереди trade WS идёт первым
+    static PRIORITY_ORDER: Cell<bool> = const { Cell::new(false) };
+}
+
+thread_local! {
+    /// Повтор по RetryPolicy: ордер уже учтён в stats первой попыткой
+    static RETRY_ORDER: Cell<bool> = const { Cell::new(false) };
+}
+
+thread_local! {
+    /// Параметры ордера, собранные при stage_orders: route_order отправляет
+    /// их вместо сборки заново. Повтор по RetryPolicy идёт обычным путём
+    pub(crate) static PREPARED_ORDER: RefCell<Option<Arc<PreparedOrder>>> = const { RefCell::new(None) };
+}
+
+/// Режим ожидания событий для потока run() (RecvMode::as_u8)
+pub fn set_recv_mode(mode: u8) {
+    RECV_MODE.with(|m| m.set(mode));
+}
+
+thread_local! {
+    static CLOCK: RefCell<Option<Arc<AtomicU64>>> = const { RefCell::new(None) };
+}
+
+/// Виртуальные часы инстанса на replay (None - реальное время).
+/// Возвращает прежние, чтобы их можно было восстановить
+pub fn set_clock(clock: Option<Arc<AtomicU64>>) -> Option<Arc<AtomicU64>> {
+    CLOCK.with(|c| c.replace(clock))
+}
+
+/// Текущее виртуальное время потока, Unix ns
+fn virtual_now_ns() -> Option<u64> {
+    CLOCK.with(|c| c.borrow().as_ref().map(|clock| clock.load(Ordering::Relaxed)))
+}
+
+/// Вызывает callback стратегии с выставленным instance_id (и часами replay),
+/// чтобы ордера из callback'ов тоже атрибутировались инстансу
+pub(crate) fn invoke_callback(instance_id: &Option<String>, callback: OrderCallback, result: OrderResult) {
+    let prev = current_instance();
+    set_current_instance(instance_id.clone());
+    let prev_clock = set_clock(instance_id.as_deref().and_then(replay::clock));
+    unsafe { callback(result); }
+    set_clock(prev_clock);
+    set_current_instance(prev);
+}
+
 // ═══════════════════════════════════════════════════════════
 // C-ТИПЫ
 // ═══════════════════════════════════════════════════════════
@@ -29,11 +141,84 @@ pub struct OrderResult {
 
 pub type OrderCallback = unsafe extern "C" fn(result: OrderResult);
 
+/// Таблица функций ядра, доступных стратегии (StrategyConfig.host).
+/// Новые функции добавляются только в конец; size позволяет стратегии
+/// проверить, что нужная функция есть в этой версии ядра.
+#[repr(C)]
+pub struct HostApi {
+    pub size: u32,
+    pub get_orders: GetOrdersFn,
+    pub log: LogFn,
+    pub server_time_ms: TimeFn,
+    pub time_offset_ms: TimeFn,
+    pub next_funding_time_ms: FundingTimeFn,
+    pub stage_orders: StageOrdersFn,
+    pub fire_staged: FireStagedFn,
+    pub unstage: UnstageFn,
+    pub recv_mode: RecvModeFn,
+    pub now_ns: TimeFn,
+    pub heartbeat: HeartbeatFn,
+    pub funding_interval_ms: FundingTimeFn,
+    pub notify: NotifyFn,
+    pub kv_get: KvGetFn,
+    pub kv_set: KvSetFn,
+    pub kv_delete: KvDeleteFn,
+    pub schedule_timer: ScheduleTimerFn,
+    pub cancel_timer: CancelTimerFn,
+    pub subscribe_stream: StreamFn,
+    pub unsubscribe_stream: StreamFn,
+    pub place_algo_order: PlaceAlgoFn,
+    pub cancel_algo_order: CancelAlgoFn,
+    pub place_bracket: PlaceBracketFn,
+    pub cancel_bracket: CancelBracketFn,
+    pub get_position: GetPositionFn,
+    pub close_position: ClosePositionFn,
+    pub place_priority_order: PlaceOrderFn,
+    pub get_vwap: GetVwapFn,
+    pub get_volume_profile: GetVolumeProfileFn,
+    pub emit_metric: EmitMetricFn,
+}
+
+pub static HOST_API: HostApi = HostApi {
+    size: std::mem::size_of::<HostApi>() as u32,
+    get_orders,
+    log: host_log,
+    server_time_ms,
+    time_offset_ms,
+    next_funding_time_ms,
+    stage_orders,
+    fire_staged,
+    unstage,
+    recv_mode,
+    now_ns,
+    heartbeat,
+    funding_interval_ms,
+    notify: host_notify,
+    kv_get,
+    kv_set,
+    kv_delete,
+    schedule_timer,
+    cancel_timer,
+    subscribe_stream,
+    unsubscribe_stream,
+    place_algo_order,
+    cancel_algo_order,
+    place_bracket,
+    cancel_bracket,
+    get_position,
+    close_position,
+    place_priority_order,
+    get_vwap,
+    get_volume_profile,
+    emit_metric,
+};
+
 // ═══════════════════════════════════════════════════════════
 // FFI ФУНКЦИИ (экспортируются в DLL)
 // ═══════════════════════════════════════════════════════════
 
 #[no_mangle]
+#[allow(clippy::too_many_arguments)]
 pub unsafe extern "C" fn place_order(
     api_key: *const c_char,
     secret_key: *const c_char,
@@ -44,16 +229,276 @@ pub unsafe extern "C" fn place_order(
     order_type: u8,        // 0 = LIMIT, 1 = MARKET
     callback: OrderCallback,
 ) {
-    let manager = TRADE_MANAGER.get().expect("Trading not initialized");
-    
     let api_key = CStr::from_ptr(api_key).to_str().unwrap();
     let secret_key = CStr::from_ptr(secret_key).to_str().unwrap();
     let symbol = CStr::from_ptr(symbol).to_str().unwrap();
     let side = CStr::from_ptr(side).to_str().unwrap();
 
-    
+    // Вместо api_key стратегия может передать alias из keystore
+    let creds = Arc::new(keystore::resolve(api_key, secret_key));
+    submit_order(creds, symbol, side, order_type, price, quantity, callback);
+}
+
+/// place_order вне очереди: выход из позиции или снижение риска не ждёт,
+/// пока сокет разберёт накопившиеся обычные ордера (сетку и т.п.).
+/// Отмены и reduce-only ордера ядра приоритетны всегда
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn place_priority_order(
+    api_key: *const c_char,
+    secret_key: *const c_char,
+    symbol: *const c_char,
+    price: f64,
+    quantity: f64,
+    side: *const c_char,
+    order_type: u8,
+    callback: OrderCallback,
+) {
+    PRIORITY_ORDER.with(|p| p.set(true));
+    place_order(api_key, secret_key, symbol, price, quantity, side, order_type, callback);
+    PRIORITY_ORDER.with(|p| p.set(false));
+}
+
+/// Общий путь ордера от стратегии (place_order и staged-шаблоны):
+/// circuit breaker, risk, order manager, отправка, callback
+pub(crate) fn submit_order(
+    creds: Arc<Credentials>,
+    symbol: &str,
+    side: &str,
+    order_type: u8,
+    price: f64,
+    quantity: f64,
+    callback: OrderCallback,
+) {
+    let instance_id = current_instance();
+    let callback_instance = instance_id.clone();
+
+    if let Some(id) = instance_id.as_deref() {
+        if dedup::is_duplicate(id, symbol, side, order_type, price, quantity, now_ns() as u64) {
+            let order = describe(symbol, side, quantity);
+            logs::push(id, logs::LOG_WARN, &format!("Duplicate order suppressed: {}", order));
+            audit::rejected(
+                None, instance_id.clone(), "order.place",
+                place_params(&creds.api_key, symbol, side, order_type, price, quantity),
+                dedup::ERR_DUPLICATE_ORDER, "duplicate order within dedup window",
+            );
+            stats::order_placed(Some(id));
+            stats::order_rejected(Some(id));
+            let result = OrderResult { success: false, order_id: -1, error_code: dedup::ERR_DUPLICATE_ORDER };
+            tokio::spawn(async move {
+                invoke_callback(&callback_instance, callback, result);
+            });
+            return;
+        }
+    }
+
+    let priority = PRIORITY_ORDER.with(|p| p.get());
+    route_with_retry(creds, instance_id, symbol.to_string(), side.to_string(), order_type, price, quantity, priority, 0, None, callback);
+}
+
+/// route_order с повтором по RetryPolicy инстанса: ошибка, которую политика
+/// повторяет, до стратегии не доходит, пока не кончатся попытки.
+/// pending - повтор, ради которого идёт эта отправка: держит библиотеку
+/// стратегии загруженной до вызова callback
+#[allow(clippy::too_many_arguments)]
+fn route_with_retry(
+    creds: Arc<Credentials>,
+    instance_id: Option<String>,
+    symbol: String,
+    side: String,
+    order_type: u8,
+    price: f64,
+    quantity: f64,
+    priority: bool,
+    attempt: u32,
+    pending: Option<retry::Retry>,
+    callback: OrderCallback,
+) {
+    let retry_creds = creds.clone();
+    let (retry_symbol, retry_side) = (symbol.clone(), side.clone());
+    let callback_instance = instance_id.clone();
+
+    // route_order берёт приоритет из thread-local, а повтор идёт из задачи tokio
+    let prev_priority = PRIORITY_ORDER.with(|p| p.replace(priority));
+    let prev_retry = RETRY_ORDER.with(|r| r.replace(attempt > 0));
+    route_order(creds, instance_id, &symbol, &side, order_type, price, quantity, None, move |result| {
+        let next = match callback_instance.as_deref() {
+            Some(id) if !result.success => retry::next_attempt(id, result.error_code, attempt),
+            _ => None,
+        };
+        let (Some(next), Some(id)) = (next, callback_instance.as_deref()) else {
+            invoke_callback(&callback_instance, callback, result);
+            drop(pending);
+            return;
+        };
+
+        logs::push(id, logs::LOG_WARN, &format!(
+            "Order {} failed ({}), retry {} in {}ms",
+            describe(&retry_symbol, &retry_side, quantity), result.error_code, attempt + 1, next.delay.as_millis(),
+        ));
+        tokio::spawn(async move {
+            tokio::time::sleep(next.delay).await;
+            // Инстанс остановлен или перезапущен за паузу: ордер от его имени
+            // уже не отправляем, стратегии - исходная ошибка
+            let current = callback_instance.as_deref().is_some_and(|id| retry::is_current(id, &next));
+            if !current {
+                invoke_callback(&callback_instance, callback, result);
+                return;
+            }
+            route_with_retry(
+                retry_creds, callback_instance, retry_symbol, retry_side,
+                order_type, price, quantity, priority, attempt + 1, Some(next), callback,
+            );
+        });
+    });
+    PRIORITY_ORDER.with(|p| p.set(prev_priority));
+    RETRY_ORDER.with(|r| r.set(prev_retry));
+}
+
+/// То же для ордеров ядра (дочерние ордера execution): инстанс задан явно,
+/// результат приходит в on_result. client_order_id - выданный заранее
+/// (execution помнит дочерний ордер до отправки), None - выдать новый
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn route_order(
+    creds: Arc<Credentials>,
+    instance_id: Option<String>,
+    symbol: &str,
+    side: &str,
+    order_type: u8,
+    price: f64,
+    quantity: f64,
+    client_order_id: Option<String>,
+    on_result: impl FnOnce(OrderResult) + Send + 'static,
+) {
+    let manager = TRADE_MANAGER.get().expect("Trading not initialized");
+    let api_key = creds.api_key.as_str();
+    let prepared = PREPARED_ORDER.with(|p| p.take());
+
+    if !RETRY_ORDER.with(|r| r.get()) {
+        stats::order_placed(instance_id.as_deref());
+    }
+
+    // Поток инстанса, снятого по таймауту остановки, торговать не должен
+    if instance_id.as_deref().is_some_and(quarantine::contains) {
+        tracing::warn!("⛔ Order from quarantined instance '{}' rejected", instance_id.as_deref().unwrap_or("-"));
+        let result = OrderResult { success: false, order_id: -1, error_code: quarantine::ERR_INSTANCE_QUARANTINED };
+        tokio::spawn(async move {
+            on_result(result);
+        });
+        return;
+    }
+
+    // Paper/replay: ордер не уходит на биржу и не трогает risk/order manager
+    if let Some(account) = instance_id.as_deref().and_then(paper::account) {
+        let result = account.place(symbol, side, order_type, price, quantity);
+        if !result.success {
+            stats::order_rejected(instance_id.as_deref());
+        }
+        tokio::spawn(async move {
+            on_result(result);
+        });
+        return;
+    }
+
+    // Dry run: намерение пишется в order manager, на биржу ничего не уходит
+    if let Some(id) = instance_id.as_deref().filter(|id| intents::is_active(id)) {
+        let result = intents::place(id, api_key, symbol, side, order_type, price, quantity);
+        tokio::spawn(async move {
+            on_result(result);
+        });
+        return;
+    }
+
+    if let Some(id) = instance_id.as_deref() {
+        latency::record_order(id);
+    }
+
+    if let (Some(breaker), Some(id)) = (BREAKER.get(), instance_id.as_deref()) {
+        if let Err(reason) = breaker.check(id) {
+            tracing::warn!("⛔ Order rejected by circuit breaker [{}]: {}", id, reason);
+            audit::rejected(
+                None, instance_id.clone(), "order.place",
+                place_params(api_key, symbol, side, order_type, price, quantity),
+                ERR_CIRCUIT_OPEN, &reason,
+            );
+            stats::order_rejected(instance_id.as_deref());
+            notify_rejected(instance_id.as_deref(), &describe(symbol, side, quantity), ERR_CIRCUIT_OPEN, &reason);
+            let result = OrderResult { success: false, order_id: -1, error_code: ERR_CIRCUIT_OPEN };
+            tokio::spawn(async move {
+                on_result(result);
+            });
+            return;
+        }
+    }
+
+    if let Some(risk) = RISK_MANAGER.get() {
+        if let Err(reject) = risk.check_order(
+            instance_id.as_deref(), api_key, symbol, side, price, quantity, order_type,
+        ) {
+            tracing::warn!(
+                "🛡️ Order rejected by risk [{}]: {}",
+                instance_id.as_deref().unwrap_or("-"),
+                reject.message
+            );
+            audit::rejected(
+                None, instance_id.clone(), "order.place",
+                place_params(api_key, symbol, side, order_type, price, quantity),
+                reject.code, &reject.message,
+            );
+            stats::order_rejected(instance_id.as_deref());
+            notify_rejected(instance_id.as_deref(), &describe(symbol, side, quantity), reject.code, &reject.message);
+            let result = OrderResult { success: false, order_id: -1, error_code: reject.code };
+            tokio::spawn(async move {
+                on_result(result);
+            });
+            return;
+        }
+    }
+
+    // Токен потолка ядра - только ордеру, прошедшему risk: отклонённые risk
+    // ордера не должны съедать пропускную способность других инстансов
+    if !capacity::try_order() {
+        let reason = "global order throughput limit reached (capacity.max_orders_per_sec)";
+        tracing::warn!("⛔ Order throttled [{}]: {}", instance_id.as_deref().unwrap_or("-"), reason);
+        audit::rejected(
+            None, instance_id.clone(), "order.place",
+            place_params(api_key, symbol, side, order_type, price, quantity),
+            capacity::ERR_THROUGHPUT_LIMIT, reason,
+        );
+        // Risk уже учёл ордер в exposure и в ордерах в полёте
+        if let Some(risk) = RISK_MANAGER.get() {
+            risk.on_order_failed(instance_id.as_deref(), api_key, symbol, side, quantity);
+        }
+        stats::order_rejected(instance_id.as_deref());
+        let result = OrderResult { success: false, order_id: -1, error_code: capacity::ERR_THROUGHPUT_LIMIT };
+        tokio::spawn(async move {
+            on_result(result);
+        });
+        return;
+    }
+
+    // clientOrderId генерирует ядро - по нему order manager склеивает ack и user data
+    let client_order_id = ORDER_MANAGER.get().map(|orders| {
+        let cid = client_order_id.unwrap_or_else(|| orders.next_client_id(instance_id.as_deref()));
+        orders.on_request(&cid, instance_id.as_deref(), api_key, symbol, side, order_type, price, quantity);
+        cid
+    });
+
+    let api_key_owned = api_key.to_string();
+    let (symbol, side) = (symbol.to_string(), side.to_string());
     let manager = manager.clone();
-    tokio::spawn(async move {
+    // Обработчик ответа биржи - Fn, результат отдаётся один раз
+    let on_result = Mutex::new(Some(on_result));
+    let audit_instance = instance_id.clone();
+    let priority = PRIORITY_ORDER.with(|p| p.get());
+    tokio::spawn(PRIORITY.scope(priority, AUDIT_INSTANCE.scope(audit_instance, async move {
+        let (api_key, secret_key) = (creds.api_key.as_str(), creds.secret_key.as_str());
+        let (symbol, side) = (symbol.as_str(), side.as_str());
+        let cid = client_order_id.clone();
+
+        let order_desc = describe(symbol, side, quantity);
+        let (risk_symbol, risk_side) = (symbol.to_string(), side.to_string());
+
         // Общий обработчик ответа
         let handle_resp = move |resp: serde_json::Value| {
             let result = if let Some(error) = resp.get("error") {
@@ -63,6 +508,15 @@ pub unsafe extern "C" fn place_order(
                     error_code: error["code"].as_i64().unwrap_or(-1) as i32,
                 }
             } else if let Some(order_id) = resp["result"]["orderId"].as_i64() {
+                let status = resp["result"]["status"].as_str().unwrap_or("NEW");
+                if let Some(risk) = RISK_MANAGER.get() {
+                    risk.on_order_accepted(
+                        instance_id.as_deref(), &api_key_owned, &risk_symbol, &risk_side, quantity, order_id, status,
+                    );
+                }
+                if let (Some(orders), Some(cid)) = (ORDER_MANAGER.get(), cid.as_deref()) {
+                    orders.on_ack(cid, order_id, status);
+                }
                 OrderResult {
                     success: true,
                     order_id,
@@ -72,13 +526,37 @@ pub unsafe extern "C" fn place_order(
                 OrderResult {
                     success: false,
                     order_id: -1,
-                    error_code: -9998,
+                    error_code: ERR_NO_ORDER_ID,
                 }
             };
-            unsafe { callback(result); }
+            if !result.success {
+                if let Some(risk) = RISK_MANAGER.get() {
+                    risk.on_order_failed(instance_id.as_deref(), &api_key_owned, &risk_symbol, &risk_side, quantity);
+                }
+                stats::order_rejected(instance_id.as_deref());
+                let reason = resp["error"]["msg"].as_str().unwrap_or("no orderId in response");
+                notify_rejected(instance_id.as_deref(), &order_desc, result.error_code, reason);
+                if let (Some(orders), Some(cid)) = (ORDER_MANAGER.get(), cid.as_deref()) {
+                    orders.on_ack_error(cid, result.error_code);
+                }
+            }
+            if let (Some(breaker), Some(id)) = (BREAKER.get(), instance_id.as_deref()) {
+                if result.success {
+                    breaker.on_success(id);
+                } else {
+                    breaker.on_failure(id, result.error_code);
+                }
+            }
+            if let Some(on_result) = on_result.lock().unwrap().take() {
+                on_result(result);
+            }
         };
 
-        if order_type == 1 {
+        if let Some(order) = prepared {
+            manager
+                .send_prepared_order(api_key, secret_key, order, price, client_order_id.as_deref(), handle_resp)
+                .await;
+        } else if order_type == 1 {
             // MARKET
             manager
                 .send_market_order(
@@ -87,6 +565,7 @@ pub unsafe extern "C" fn place_order(
                     symbol,
                     quantity,
                     side,
+                    client_order_id.as_deref(),
                     handle_resp,
                 )
                 .await;
@@ -100,11 +579,38 @@ pub unsafe extern "C" fn place_order(
                     price,
                     quantity,
                     side,
+                    client_order_id.as_deref(),
                     handle_resp,
                 )
                 .await;
         }
-    });
+    })));
+}
+
+/// "BUY 0.01 BTCUSDT" для уведомлений
+fn describe(symbol: &str, side: &str, quantity: f64) -> String {
+    format!("{} {} {}", side, quantity, symbol)
+}
+
+/// Уведомление об отказе в ордере (breaker, risk, биржа)
+fn notify_rejected(instance_id: Option<&str>, order: &str, code: i32, reason: &str) {
+    notifications::notify(
+        NotifyKind::OrderRejected,
+        instance_id,
+        format!("Order {} rejected ({}): {}", order, code, reason),
+    );
+}
+
+/// Параметры order.place для audit log (без секретов)
+fn place_params(api_key: &str, symbol: &str, side: &str, order_type: u8, price: f64, quantity: f64) -> serde_json::Value {
+    serde_json::json!({
+        "apiKey": crate::user_data::key_id(api_key),
+        "symbol": symbol,
+        "side": side,
+        "type": if order_type == 1 { "MARKET" } else { "LIMIT" },
+        "price": price,
+        "quantity": quantity,
+    })
 }
 
 #[no_mangle]
@@ -115,16 +621,52 @@ pub unsafe extern "C" fn cancel_order(
     order_id: i64,
     callback: OrderCallback,
 ) {
-    let manager = TRADE_MANAGER.get().expect("Trading not initialized");
-    
     let api_key = CStr::from_ptr(api_key).to_str().unwrap();
     let secret_key = CStr::from_ptr(secret_key).to_str().unwrap();
     let symbol = CStr::from_ptr(symbol).to_str().unwrap();
-    
+
+    let instance_id = current_instance();
+    let callback_instance = instance_id.clone();
+    let creds = keystore::resolve(api_key, secret_key);
+    route_cancel(creds, instance_id, symbol, order_id, move |result| {
+        invoke_callback(&callback_instance, callback, result);
+    });
+}
+
+/// Отмена с явно заданным инстансом (cancel_order и execution)
+pub(crate) fn route_cancel(
+    creds: Credentials,
+    instance_id: Option<String>,
+    symbol: &str,
+    order_id: i64,
+    on_result: impl FnOnce(OrderResult) + Send + 'static,
+) {
+    let manager = TRADE_MANAGER.get().expect("Trading not initialized");
+
+    if let Some(account) = instance_id.as_deref().and_then(paper::account) {
+        let result = account.cancel(order_id);
+        tokio::spawn(async move {
+            on_result(result);
+        });
+        return;
+    }
+
+    if let Some(id) = instance_id.as_deref().filter(|id| intents::is_active(id)) {
+        let result = intents::cancel(id, order_id);
+        tokio::spawn(async move {
+            on_result(result);
+        });
+        return;
+    }
+
+    let api_key_owned = creds.api_key.clone();
+    let symbol = symbol.to_string();
     let manager = manager.clone();
-    tokio::spawn(async move {
+    let on_result = Mutex::new(Some(on_result));
+    let audit_instance = instance_id.clone();
+    tokio::spawn(AUDIT_INSTANCE.scope(audit_instance, async move {
         manager.cancel_limit_order(
-            api_key, secret_key, symbol, &order_id.to_string(),
+            &creds.api_key, &creds.secret_key, &symbol, &order_id.to_string(),
             move |resp| {
                 let result = if resp.get("error").is_some() {
                     OrderResult {
@@ -133,15 +675,209 @@ pub unsafe extern "C" fn cancel_order(
                         error_code: resp["error"]["code"].as_i64().unwrap_or(-1) as i32,
                     }
                 } else {
+                    if let Some(risk) = RISK_MANAGER.get() {
+                        risk.on_order_closed(&api_key_owned, order_id);
+                    }
+                    if let Some(orders) = ORDER_MANAGER.get() {
+                        orders.on_cancel_ack(order_id);
+                    }
                     OrderResult { success: true, order_id, error_code: 0 }
                 };
-                unsafe { callback(result); }
+                if let Some(on_result) = on_result.lock().unwrap().take() {
+                    on_result(result);
+                }
             },
         ).await;
-    });
+    }));
+}
+
+/// Ордера текущего инстанса, новые первыми.
+/// symbol = NULL - все символы. Пишет не больше max записей в out,
+/// возвращает число записанных.
+#[no_mangle]
+pub unsafe extern "C" fn get_orders(
+    symbol: *const c_char,
+    open_only: bool,
+    out: *mut COrder,
+    max: usize,
+) -> usize {
+    let (Some(orders), Some(instance_id)) = (ORDER_MANAGER.get(), current_instance()) else {
+        return 0;
+    };
+    if out.is_null() || max == 0 {
+        return 0;
+    }
+
+    let symbol = if symbol.is_null() {
+        None
+    } else {
+        CStr::from_ptr(symbol).to_str().ok()
+    };
+
+    let filter = OrderFilter {
+        instance_id: Some(&instance_id),
+        symbol,
+        open_only,
+    };
+
+    let list = orders.list_c(&filter);
+    let n = list.len().min(max);
+    std::ptr::copy_nonoverlapping(list.as_ptr(), out, n);
+    n
+}
+
+/// Строка лога стратегии (UTF-8, без завершающего нуля).
+/// Попадает в буфер текущего инстанса и в лог ядра.
+pub unsafe extern "C" fn host_log(level: u8, msg: *const u8, len: usize) {
+    if msg.is_null() {
+        return;
+    }
+    let bytes = std::slice::from_raw_parts(msg, len);
+    let message = String::from_utf8_lossy(bytes);
+    let instance_id = current_instance();
+    logs::write(instance_id.as_deref().unwrap_or("-"), level, message.trim_end());
+}
+
+/// Уведомление от стратегии в каналы ядра (Telegram, webhook).
+/// Чаще custom_min_interval_ms от одного инстанса - отбрасывается
+pub unsafe extern "C" fn host_notify(msg: *const u8, len: usize) {
+    if msg.is_null() {
+        return;
+    }
+    let Some(instance_id) = current_instance() else { return };
+    let message = String::from_utf8_lossy(std::slice::from_raw_parts(msg, len));
+    if !notifications::custom(&instance_id, message.trim_end()) {
+        tracing::debug!("📣 Notification from '{}' dropped", instance_id);
+    }
+}
+
+/// Значение из KV-состояния стратегии. Возвращает длину значения, -1 - ключа нет.
+/// Значение копируется в out, только если помещается в cap байт
+pub unsafe extern "C" fn kv_get(key: *const u8, key_len: usize, out: *mut u8, cap: usize) -> i64 {
+    let (Some(instance_id), Some(key)) = (current_instance(), utf8(key, key_len)) else {
+        return -1;
+    };
+    let Some(value) = kv::get(&instance_id, key) else { return -1 };
+    if !out.is_null() && value.len() <= cap {
+        std::ptr::copy_nonoverlapping(value.as_ptr(), out, value.len());
+    }
+    value.len() as i64
+}
+
+/// Записывает значение (UTF-8). false - лимиты kv, причина в логе инстанса
+pub unsafe extern "C" fn kv_set(key: *const u8, key_len: usize, value: *const u8, value_len: usize) -> bool {
+    let Some(instance_id) = current_instance() else { return false };
+    let (Some(key), Some(value)) = (utf8(key, key_len), utf8(value, value_len)) else {
+        logs::push(&instance_id, logs::LOG_WARN, "KV set rejected: key and value must be UTF-8");
+        return false;
+    };
+    match kv::set(&instance_id, key, value) {
+        Ok(_) => true,
+        Err(e) => {
+            logs::push(&instance_id, logs::LOG_WARN, &format!("KV set '{}' rejected: {}", key, e));
+            false
+        }
+    }
+}
+
+/// false - ключа не было
+pub unsafe extern "C" fn kv_delete(key: *const u8, key_len: usize) -> bool {
+    let (Some(instance_id), Some(key)) = (current_instance(), utf8(key, key_len)) else {
+        return false;
+    };
+    kv::delete(&instance_id, key)
+}
+
+/// Метрика стратегии для GET /metrics с метками инстанса (kind: METRIC_*).
+/// false - неверное имя, значение или тип, либо лимит метрик инстанса
+pub unsafe extern "C" fn emit_metric(name: *const u8, name_len: usize, value: f64, kind: u8) -> bool {
+    let (Some(instance_id), Some(name)) = (current_instance(), utf8(name, name_len)) else {
+        return false;
+    };
+    metrics::emit(&instance_id, name, value, kind)
+}
+
+/// Таймер: через delay_ms по часам стратегии в её канал придёт EVENT_TIMER
+/// с этим timer_id. Тот же timer_id ещё раз - перенос. false - лимит таймеров
+pub extern "C" fn schedule_timer(delay_ms: u64, timer_id: u64) -> bool {
+    let Some(instance_id) = current_instance() else { return false };
+    let now = virtual_now_ns().unwrap_or_else(latency::now_ns);
+    timers::schedule(&instance_id, timer_id, now.saturating_add(delay_ms.saturating_mul(1_000_000)))
+}
+
+/// false - таймер не взведён или уже сработал
+pub extern "C" fn cancel_timer(timer_id: u64) -> bool {
+    current_instance().is_some_and(|instance_id| timers::cancel(&instance_id, timer_id))
+}
+
+unsafe fn utf8<'a>(ptr: *const u8, len: usize) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    std::str::from_utf8(std::slice::from_raw_parts(ptr, len)).ok()
+}
+
+/// Offset часов ядра относительно Binance (server - local), ms.
+/// Обновляется фоновой пересинхронизацией.
+pub extern "C" fn time_offset_ms() -> i64 {
+    TRADE_MANAGER.get().map_or(0, |t| t.get_time_offset())
+}
+
+/// Текущее время биржи, Unix ms: локальное время + offset.
+/// На replay - время записи (offset уже был учтён биржей при записи)
+pub extern "C" fn server_time_ms() -> i64 {
+    match virtual_now_ns() {
+        Some(ns) => (ns / 1_000_000) as i64,
+        None => chrono::Utc::now().timestamp_millis() + time_offset_ms(),
+    }
 }
 
+/// Часы стратегии, Unix ns: реальное время или время проигрываемой записи.
+/// Стратегии, которые берут время отсюда, а не из Local::now(), можно гонять на replay
+pub extern "C" fn now_ns() -> i64 {
+    virtual_now_ns().unwrap_or_else(latency::now_ns) as i64
+}
 
+/// Режим ожидания событий инстанса: 0 = sleep, 1 = spin.
+/// Имеет смысл только в потоке run()
+pub extern "C" fn recv_mode() -> u8 {
+    RECV_MODE.with(|m| m.get())
+}
+
+/// Признак жизни для watchdog'а раннера. Нужен стратегиям, которые надолго
+/// перестают забирать события из очереди (ждут funding, считают)
+pub extern "C" fn heartbeat() {
+    stats::heartbeat(current_instance().as_deref());
+}
+
+/// Время следующего funding по символу (время биржи, Unix ms) из календаря ядра.
+/// 0 - ещё не загружено или у символа нет funding.
+/// На replay - ближайшая граница текущего интервала символа после времени записи
+/// (историю интервалов funding по символам ядро не хранит).
+pub unsafe extern "C" fn next_funding_time_ms(symbol: *const c_char) -> i64 {
+    let Some(symbol) = c_symbol(symbol) else { return 0 };
+    if let Some(ns) = virtual_now_ns() {
+        return funding::next_boundary(symbol, (ns / 1_000_000) as i64);
+    }
+    funding::next_funding_time(symbol).unwrap_or(0)
+}
+
+/// Интервал funding по символу, ms (8h, у части символов 4h или 1h).
+/// 0 - символ не известен календарю (на replay - всегда интервал)
+pub unsafe extern "C" fn funding_interval_ms(symbol: *const c_char) -> i64 {
+    let Some(symbol) = c_symbol(symbol) else { return 0 };
+    if virtual_now_ns().is_none() && funding::next_funding_time(symbol).is_none() {
+        return 0;
+    }
+    funding::interval_ms(symbol)
+}
+
+unsafe fn c_symbol<'a>(symbol: *const c_char) -> Option<&'a str> {
+    if symbol.is_null() {
+        return None;
+    }
+    CStr::from_ptr(symbol).to_str().ok()
+}
 
 pub type PlaceOrderFn = unsafe extern "C" fn(
     api_key: *const c_char,
@@ -160,4 +896,82 @@ pub type CancelOrderFn = unsafe extern "C" fn(
     symbol: *const c_char,
     order_id: i64,
     callback: OrderCallback,
-);
\ No newline at end of file
+);
+
+pub type GetOrdersFn = unsafe extern "C" fn(
+    symbol: *const c_char,
+    open_only: bool,
+    out: *mut COrder,
+    max: usize,
+) -> usize;
+
+pub type LogFn = unsafe extern "C" fn(level: u8, msg: *const u8, len: usize);
+
+pub type TimeFn = extern "C" fn() -> i64;
+
+pub type FundingTimeFn = unsafe extern "C" fn(symbol: *const c_char) -> i64;
+
+pub type RecvModeFn = extern "C" fn() -> u8;
+
+pub type StageOrdersFn = unsafe extern "C" fn(
+    api_key: *const c_char,
+    secret_key: *const c_char,
+    templates: *const COrderTemplate,
+    count: usize,
+) -> i64;
+
+pub type FireStagedFn = unsafe extern "C" fn(
+    stage_id: i64,
+    prices: *const f64,
+    count: usize,
+    callback: OrderCallback,
+) -> i32;
+
+pub type UnstageFn = extern "C" fn(stage_id: i64) -> bool;
+
+pub type HeartbeatFn = extern "C" fn();
+
+pub type NotifyFn = unsafe extern "C" fn(msg: *const u8, len: usize);
+
+pub type KvGetFn = unsafe extern "C" fn(key: *const u8, key_len: usize, out: *mut u8, cap: usize) -> i64;
+
+pub type KvSetFn = unsafe extern "C" fn(key: *const u8, key_len: usize, value: *const u8, value_len: usize) -> bool;
+
+pub type KvDeleteFn = unsafe extern "C" fn(key: *const u8, key_len: usize) -> bool;
+
+pub type ScheduleTimerFn = extern "C" fn(delay_ms: u64, timer_id: u64) -> bool;
+
+pub type CancelTimerFn = extern "C" fn(timer_id: u64) -> bool;
+
+pub type StreamFn = unsafe extern "C" fn(symbol: *const c_char, stream_type: u8) -> bool;
+
+pub type PlaceAlgoFn = unsafe extern "C" fn(
+    api_key: *const c_char,
+    secret_key: *const c_char,
+    order: *const CAlgoOrder,
+) -> i64;
+
+pub type CancelAlgoFn = extern "C" fn(algo_id: i64) -> bool;
+
+pub type PlaceBracketFn = unsafe extern "C" fn(
+    api_key: *const c_char,
+    secret_key: *const c_char,
+    bracket: *const CBracket,
+) -> i64;
+
+pub type CancelBracketFn = extern "C" fn(bracket_id: i64) -> bool;
+
+pub type GetPositionFn = unsafe extern "C" fn(api_key: *const c_char, symbol: *const c_char) -> f64;
+
+pub type ClosePositionFn = unsafe extern "C" fn(
+    api_key: *const c_char,
+    secret_key: *const c_char,
+    symbol: *const c_char,
+    callback: OrderCallback,
+) -> f64;
+
+pub type EmitMetricFn = unsafe extern "C" fn(name: *const u8, name_len: usize, value: f64, kind: u8) -> bool;
+
+pub type GetVwapFn = unsafe extern "C" fn(symbol: *const c_char, out: *mut CVwap) -> bool;
+
+pub type GetVolumeProfileFn = unsafe extern "C" fn(symbol: *const c_char, out: *mut CVolumeLevel, max: usize) -> usize;
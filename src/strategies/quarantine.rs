@@ -0,0 +1,69 @@
+// src/strategies/quarantine.rs
+
+use dashmap::DashMap;
+use libloading::Library;
+use serde::Serialize;
+use std::sync::{Arc, LazyLock};
+
+// ═══════════════════════════════════════════════════════════
+// КАРАНТИН
+// ═══════════════════════════════════════════════════════════
+//
+// Поток dylib-стратегии нельзя убить: если run() не вернулся за
+// stop_timeout_ms, инстанс снимается с учёта, а его поток продолжает
+// исполняться. Пока он жив, instance_id в карантине: ордера с этим id
+// отклоняются, новый инстанс с тем же id не запускается, библиотека
+// не выгружается (код ещё исполняется).
+
+/// Ордер от инстанса, снятого по таймауту остановки
+pub const ERR_INSTANCE_QUARANTINED: i32 = -9301;
+
+struct Entry {
+    strategy_id: String,
+    since: i64,
+    /// Держим, пока поток не вышел из run()
+    _lib: Arc<Library>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QuarantinedInstance {
+    pub instance_id: String,
+    pub strategy_id: String,
+    /// Unix ms
+    pub since: i64,
+}
+
+static QUARANTINED: LazyLock<DashMap<String, Entry>> = LazyLock::new(DashMap::new);
+
+pub fn add(instance_id: &str, strategy_id: &str, lib: Arc<Library>) {
+    QUARANTINED.insert(instance_id.to_string(), Entry {
+        strategy_id: strategy_id.to_string(),
+        since: chrono::Utc::now().timestamp_millis(),
+        _lib: lib,
+    });
+}
+
+/// Поток вышел из run(): ордера и перезапуск снова разрешены
+pub fn release(instance_id: &str) {
+    if let Some((_, entry)) = QUARANTINED.remove(instance_id) {
+        let secs = (chrono::Utc::now().timestamp_millis() - entry.since) / 1000;
+        tracing::info!("🔓 '{}' left quarantine after {}s: strategy thread exited", instance_id, secs);
+    }
+}
+
+pub fn contains(instance_id: &str) -> bool {
+    !QUARANTINED.is_empty() && QUARANTINED.contains_key(instance_id)
+}
+
+pub fn list() -> Vec<QuarantinedInstance> {
+    let mut list: Vec<_> = QUARANTINED
+        .iter()
+        .map(|e| QuarantinedInstance {
+            instance_id: e.key().clone(),
+            strategy_id: e.strategy_id.clone(),
+            since: e.since,
+        })
+        .collect();
+    list.sort_by_key(|q| q.since);
+    list
+}
@@ -0,0 +1,159 @@
+// src/strategies/replay.rs
+
+use crossbeam::channel::{SendTimeoutError, Sender};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use dashmap::DashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
+
+use crate::ffi_types::CEvent;
+use crate::paper::PaperAccount;
+use crate::recorder::{self, Record, RecordReader};
+use crate::strategies::timers;
+
+/// Источник событий инстанса: запись рекордера вместо live market data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaySource {
+    /// Файл из GET /api/recorder/files ("BTCUSDT/2024-01-01.bin")
+    pub replay: String,
+    /// Множитель скорости: 1.0 - реальный темп, 0 - так быстро, как читает стратегия
+    #[serde(default = "default_speed")]
+    pub speed: f64,
+}
+
+fn default_speed() -> f64 {
+    1.0
+}
+
+impl ReplaySource {
+    pub fn validate(&self) -> anyhow::Result<PathBuf> {
+        if !self.speed.is_finite() || self.speed < 0.0 {
+            anyhow::bail!("speed must be >= 0");
+        }
+        recorder::resolve(&self.replay)
+    }
+}
+
+/// instance_id -> виртуальные часы (received_at_ns последнего отданного события)
+static CLOCKS: LazyLock<DashMap<String, Arc<AtomicU64>>> = LazyLock::new(DashMap::new);
+
+/// Часы инстанса на replay; для live - None
+pub fn clock(instance_id: &str) -> Option<Arc<AtomicU64>> {
+    if CLOCKS.is_empty() {
+        return None;
+    }
+    CLOCKS.get(instance_id).map(|c| c.clone())
+}
+
+/// Заводит часы инстанса на время первой записи файла:
+/// стратегия может спросить время ещё до первого события
+pub fn register_clock(instance_id: &str, path: &Path) -> anyhow::Result<Arc<AtomicU64>> {
+    let first = RecordReader::open(path)?
+        .next()
+        .transpose()?
+        .ok_or_else(|| anyhow::anyhow!("Recording '{}' is empty", path.display()))?;
+    let clock = Arc::new(AtomicU64::new(first.received_at_ns()));
+    CLOCKS.insert(instance_id.to_string(), clock.clone());
+    Ok(clock)
+}
+
+pub fn unregister_clock(instance_id: &str) {
+    CLOCKS.remove(instance_id);
+}
+
+/// Проигрывает запись в канал стратегии. Задержки между событиями
+/// берутся из received_at_ns и делятся на speed, после каждого события
+/// в канал идут исполнения, которые оно вызвало в симуляторе.
+/// Когда события кончились и стратегия их разобрала - выставляет stop_flag.
+pub fn run(
+    instance_id: &str,
+    path: PathBuf,
+    speed: f64,
+    tx: Sender<CEvent>,
+    paper: Arc<PaperAccount>,
+    clock: Arc<AtomicU64>,
+    stop_flag: Arc<AtomicBool>,
+) {
+    let reader = match RecordReader::open(&path) {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::error!("❌ Replay '{}' failed: {}", instance_id, e);
+            stop_flag.store(true, Ordering::Relaxed);
+            return;
+        }
+    };
+
+    tracing::info!("⏯️ Replay '{}' from {} (speed={})", instance_id, path.display(), speed);
+    let started = Instant::now();
+    let mut first_ns = None;
+    let mut sent = 0u64;
+
+    for record in reader {
+        if stop_flag.load(Ordering::Relaxed) {
+            break;
+        }
+        let record = match record {
+            Ok(r) => r,
+            Err(e) => {
+                tracing::warn!("⏯️ Replay '{}' stopped on bad record: {}", instance_id, e);
+                break;
+            }
+        };
+
+        if speed > 0.0 {
+            let first = *first_ns.get_or_insert(record.received_at_ns());
+            let offset = record.received_at_ns().saturating_sub(first) as f64 / speed;
+            let due = started + Duration::from_nanos(offset as u64);
+            let now = Instant::now();
+            if due > now {
+                std::thread::sleep(due - now);
+            }
+        } else {
+            // Без пауз симулятор ушёл бы вперёд стратегии на весь канал и исполнял
+            // её ордера по ценам, которых она ещё не видела: отдаём по одному событию
+            while !tx.is_empty() && !stop_flag.load(Ordering::Relaxed) {
+                std::thread::yield_now();
+            }
+        }
+
+        // Сырой JSON стратегии не нужен
+        let Record::Event(event) = record else { continue };
+        // Таймеры стратегии, чей срок наступил раньше события, - до него
+        for timer in timers::take_due(instance_id, event.received_at_ns) {
+            clock.store(timer.received_at_ns, Ordering::Relaxed);
+            if !send(&tx, timer, &stop_flag) {
+                break;
+            }
+        }
+        clock.store(event.received_at_ns, Ordering::Relaxed);
+        let fills = paper.on_market(&event);
+        if !std::iter::once(event).chain(fills).all(|e| send(&tx, e, &stop_flag)) {
+            break;
+        }
+        sent += 1;
+    }
+
+    // Даём стратегии дочитать хвост, потом останавливаем
+    while !tx.is_empty() && !stop_flag.load(Ordering::Relaxed) {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    stop_flag.store(true, Ordering::Relaxed);
+
+    tracing::info!(
+        "⏹️ Replay '{}' finished: {} events in {:.1}s",
+        instance_id, sent, started.elapsed().as_secs_f64()
+    );
+}
+
+/// Replay ничего не теряет: ждём места в канале, пока инстанс жив
+fn send(tx: &Sender<CEvent>, mut event: CEvent, stop_flag: &AtomicBool) -> bool {
+    loop {
+        match tx.send_timeout(event, Duration::from_millis(100)) {
+            Ok(()) => return true,
+            Err(SendTimeoutError::Timeout(e)) if !stop_flag.load(Ordering::Relaxed) => event = e,
+            Err(_) => return false,
+        }
+    }
+}
@@ -0,0 +1,209 @@
+// src/strategies/retry.rs
+
+use dashmap::DashMap;
+use libloading::Library;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use crate::order_errors;
+
+// ═══════════════════════════════════════════════════════════
+// ПОВТОР ОРДЕРОВ ПРИ СБОЯХ СВЯЗИ
+// ═══════════════════════════════════════════════════════════
+//
+// Перегрузка биржи (-1001, -1008) или лимиты запросов роняют ордер, и
+// funding-стратегия просто пропускает вход. Политика запуска (retry в
+// StartRequest) позволяет ядру повторить такой ордер само, прежде чем
+// отдавать ошибку в callback стратегии. Повтор снова проходит breaker и
+// risk, но не dedup: это тот же ордер. Коды, после которых статус ордера
+// неизвестен (-9700 обрыв, -9703 таймаут, -1006/-1007), не повторяются:
+// повтор уходит с новым clientOrderId, и принятый биржей ордер встал бы дважды.
+
+const MAX_RETRIES: u32 = 10;
+const MAX_BACKOFF_MS: u64 = 60_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Повторов после первой попытки
+    pub max_retries: u32,
+    /// Пауза перед первым повтором, дальше удваивается
+    pub backoff_ms: u64,
+    /// Потолок паузы
+    pub max_backoff_ms: u64,
+    /// Коды, которые повторяем. Пусто - retryable из order_errors
+    /// (-1001, -1003, -1008, -1015, -1021, -9200, -9303)
+    pub codes: Vec<i32>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            backoff_ms: 100,
+            max_backoff_ms: 2_000,
+            codes: Vec::new(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.max_retries > MAX_RETRIES {
+            anyhow::bail!("retry.max_retries must be 0..={}", MAX_RETRIES);
+        }
+        if self.backoff_ms > MAX_BACKOFF_MS || self.max_backoff_ms > MAX_BACKOFF_MS {
+            anyhow::bail!("retry backoff must be <= {}ms", MAX_BACKOFF_MS);
+        }
+        if self.codes.contains(&0) {
+            anyhow::bail!("retry.codes must not contain 0");
+        }
+        if let Some(code) = self.codes.iter().find(|c| order_errors::is_status_unknown(**c)) {
+            anyhow::bail!("retry.codes must not contain {}: order status is unknown, a retry could place it twice", code);
+        }
+        Ok(())
+    }
+
+    fn retries(&self, code: i32) -> bool {
+        if self.codes.is_empty() {
+            order_errors::is_retryable(code)
+        } else {
+            self.codes.contains(&code)
+        }
+    }
+
+    /// Пауза перед повтором номер attempt (с 1)
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ms = self.backoff_ms.saturating_mul(1 << (attempt - 1).min(16));
+        Duration::from_millis(ms.min(self.max_backoff_ms.max(self.backoff_ms)))
+    }
+}
+
+struct Registered {
+    policy: RetryPolicy,
+    lib: Arc<Library>,
+}
+
+static POLICIES: LazyLock<DashMap<String, Registered>> = LazyLock::new(DashMap::new);
+
+/// Запланированный повтор. Держит библиотеку инстанса загруженной: за время
+/// паузы инстанс могут остановить, а callback стратегии всё равно вызовется
+pub struct Retry {
+    pub delay: Duration,
+    lib: Arc<Library>,
+}
+
+pub fn register(instance_id: &str, policy: Option<&RetryPolicy>, lib: &Arc<Library>) {
+    if let Some(policy) = policy.filter(|p| p.max_retries > 0) {
+        POLICIES.insert(instance_id.to_string(), Registered { policy: policy.clone(), lib: lib.clone() });
+    }
+}
+
+pub fn unregister(instance_id: &str) {
+    POLICIES.remove(instance_id);
+}
+
+/// Следующая попытка или None - отдать ошибку стратегии.
+/// attempt - номер уже сделанного повтора (0 - была первая отправка).
+/// Остановленный инстанс снят с учёта и больше не повторяет
+pub fn next_attempt(instance_id: &str, error_code: i32, attempt: u32) -> Option<Retry> {
+    if POLICIES.is_empty() {
+        return None;
+    }
+    let entry = POLICIES.get(instance_id)?;
+    let policy = &entry.policy;
+    (attempt < policy.max_retries && policy.retries(error_code))
+        .then(|| Retry { delay: policy.backoff(attempt + 1), lib: entry.lib.clone() })
+}
+
+/// Инстанс, запланировавший повтор, всё ещё запущен: за паузу его не
+/// остановили и не перезапустили под тем же id с новой библиотекой
+pub fn is_current(instance_id: &str, retry: &Retry) -> bool {
+    POLICIES.get(instance_id).is_some_and(|entry| Arc::ptr_eq(&entry.lib, &retry.lib))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(codes: Vec<i32>) -> RetryPolicy {
+        RetryPolicy { codes, ..Default::default() }
+    }
+
+    /// Библиотека-заглушка: сам процесс
+    fn lib() -> Arc<Library> {
+        Arc::new(libloading::os::unix::Library::this().into())
+    }
+
+    fn delay(instance_id: &str, error_code: i32, attempt: u32) -> Option<Duration> {
+        next_attempt(instance_id, error_code, attempt).map(|r| r.delay)
+    }
+
+    #[test]
+    fn status_unknown_codes_are_rejected() {
+        assert!(policy(vec![-1001, -1008]).validate().is_ok());
+        for code in [-9700, -9703, -1006, -1007] {
+            assert!(policy(vec![-1001, code]).validate().is_err(), "{} accepted", code);
+        }
+        assert!(policy(vec![0]).validate().is_err());
+        assert!(RetryPolicy { max_retries: MAX_RETRIES + 1, ..Default::default() }.validate().is_err());
+        assert!(RetryPolicy { backoff_ms: MAX_BACKOFF_MS + 1, ..Default::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let p = RetryPolicy { backoff_ms: 100, max_backoff_ms: 350, ..Default::default() };
+        assert_eq!(p.backoff(1), Duration::from_millis(100));
+        assert_eq!(p.backoff(2), Duration::from_millis(200));
+        assert_eq!(p.backoff(3), Duration::from_millis(350));
+        assert_eq!(p.backoff(40), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn default_codes_retry_only_retryable_errors() {
+        register("retry-default", Some(&RetryPolicy::default()), &lib());
+
+        assert_eq!(delay("retry-default", -1008, 0), Some(Duration::from_millis(100)));
+        assert_eq!(delay("retry-default", -1008, 1), Some(Duration::from_millis(200)));
+        // Попытки кончились
+        assert_eq!(delay("retry-default", -1008, 2), None);
+        // Статус неизвестен или биржа отклонила ордер по существу
+        assert_eq!(delay("retry-default", -9700, 0), None);
+        assert_eq!(delay("retry-default", -2019, 0), None);
+    }
+
+    #[test]
+    fn explicit_codes_replace_defaults() {
+        register("retry-codes", Some(&policy(vec![-2019])), &lib());
+
+        assert!(next_attempt("retry-codes", -2019, 0).is_some());
+        assert_eq!(delay("retry-codes", -1008, 0), None);
+    }
+
+    #[test]
+    fn unregistered_instances_do_not_retry() {
+        register("retry-zero", Some(&RetryPolicy { max_retries: 0, ..Default::default() }), &lib());
+        assert_eq!(delay("retry-zero", -1008, 0), None);
+
+        register("retry-stopped", Some(&RetryPolicy::default()), &lib());
+        unregister("retry-stopped");
+        assert_eq!(delay("retry-stopped", -1008, 0), None);
+    }
+
+    #[test]
+    fn restarted_instance_drops_pending_retry() {
+        let first = lib();
+        register("retry-restart", Some(&RetryPolicy::default()), &first);
+        let retry = next_attempt("retry-restart", -1008, 0).unwrap();
+        assert!(is_current("retry-restart", &retry));
+
+        // Остановлен за время паузы
+        unregister("retry-restart");
+        assert!(!is_current("retry-restart", &retry));
+
+        // Перезапущен под тем же id: повтор принадлежит прежнему запуску
+        register("retry-restart", Some(&RetryPolicy::default()), &lib());
+        assert!(!is_current("retry-restart", &retry));
+    }
+}
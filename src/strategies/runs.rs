@@ -0,0 +1,367 @@
+// src/strategies/runs.rs
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::files;
+use crate::history::History;
+use crate::paper::{self, PaperStats};
+use crate::pnl::PnlTracker;
+use crate::redact;
+use crate::strategies::logs;
+use crate::strategies::stats::{self, InstanceCounters};
+
+/// Изменения пишутся на диск не чаще раза в столько
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Начало строки, которой run_strategy отмечает выход из run(): не причина падения
+const FINISHED_LINE: &str = "Finished (code=";
+
+/// Сколько последних строк лога смотрим в поисках причины падения
+const REASON_LOOKBACK: usize = 100;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RunsConfig {
+    /// Сохранять историю на диск (иначе живёт до перезапуска ядра)
+    pub persist: bool,
+    /// Файл истории (JSON). С включённым [history] запуски хранятся в его
+    /// базе, файл и max_runs не используются
+    pub path: String,
+    /// Сколько последних запусков хранить
+    pub max_runs: usize,
+}
+
+impl Default for RunsConfig {
+    fn default() -> Self {
+        Self {
+            persist: true,
+            path: "./strategies/runs.json".to_string(),
+            max_runs: 1000,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ИСТОРИЯ ЗАПУСКОВ
+// ═══════════════════════════════════════════════════════════
+//
+// Завершённый инстанс пропадает из /api/instances, а вместе с ним его
+// счётчики и итог. Здесь каждый запуск оставляет запись: когда начался и
+// закончился, код выхода, причина падения, ордера и PnL. Счётчики снимаются
+// в detach (пока paper-счёт ещё есть), запись закрывается, когда ядро узнаёт
+// код выхода. PnL live-запуска - по PnL tracker за время запуска: исполнения,
+// пришедшие после остановки, в запись не попадают. С включённым [history]
+// записи идут в его SQLite, иначе - в память и JSON-файл.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RunOutcome {
+    /// run() вернул 0
+    Stopped,
+    /// Ненулевой код или паника
+    Crashed,
+    /// Не вышел за stop_timeout_ms, поток ушёл в карантин
+    ForceRemoved,
+    /// Ядро остановилось, пока инстанс работал
+    Interrupted,
+}
+
+impl RunOutcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Stopped => "stopped",
+            Self::Crashed => "crashed",
+            Self::ForceRemoved => "force_removed",
+            Self::Interrupted => "interrupted",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        [Self::Stopped, Self::Crashed, Self::ForceRemoved, Self::Interrupted]
+            .into_iter()
+            .find(|o| o.as_str() == s)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub instance_id: String,
+    pub strategy_id: String,
+    pub symbol: String,
+    /// live, paper, dry_run, replay
+    pub mode: String,
+    /// Unix ms
+    pub started_at: i64,
+    pub stopped_at: i64,
+    pub outcome: RunOutcome,
+    /// None - паника или принудительное удаление
+    pub exit_code: Option<i32>,
+    /// Паника, последняя ошибка в логе инстанса или причина удаления
+    pub reason: Option<String>,
+    pub orders_placed: u64,
+    pub orders_filled: u64,
+    pub orders_rejected: u64,
+    /// None - dry run: исполнений не было
+    pub realized_pnl: Option<f64>,
+    pub fees: Option<f64>,
+}
+
+/// Итог запуска, снятый в detach
+struct Totals {
+    stopped_at: i64,
+    placed: u64,
+    filled: u64,
+    rejected: u64,
+    /// (realized, fees)
+    pnl: Option<(f64, f64)>,
+}
+
+/// Запуск, который ещё работает или ждёт кода выхода
+struct Pending {
+    strategy_id: String,
+    symbol: String,
+    mode: &'static str,
+    started_at: i64,
+    /// (realized, fees) инстанса в PnL tracker на старте: tracker копит их
+    /// с запуска ядра, в том числе за прошлые запуски с тем же id
+    baseline: Option<(f64, f64)>,
+    totals: Option<Totals>,
+    /// Причина из лога или паники может процитировать секрет из параметров
+    params: redact::Params,
+}
+
+/// Завершённые запуски, старые первыми (без history)
+static RUNS: LazyLock<Mutex<VecDeque<RunRecord>>> = LazyLock::new(|| Mutex::new(VecDeque::new()));
+
+/// instance_id -> текущий запуск
+static PENDING: LazyLock<DashMap<String, Pending>> = LazyLock::new(DashMap::new);
+
+static CONFIG: OnceLock<RunsConfig> = OnceLock::new();
+
+static PNL: OnceLock<Arc<PnlTracker>> = OnceLock::new();
+
+static HISTORY: OnceLock<Arc<History>> = OnceLock::new();
+
+static DIRTY: AtomicBool = AtomicBool::new(false);
+
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Загружает сохранённую историю и запускает фоновую запись.
+/// Битый файл не мешает старту: он откладывается в сторону, история
+/// начинается заново. history - база [history], если включена: тогда
+/// запуски пишутся в неё
+pub fn init(config: RunsConfig, pnl: Arc<PnlTracker>, history: Option<Arc<History>>) {
+    PNL.set(pnl).ok();
+    if let Some(history) = history {
+        tracing::info!("🧾 Run history in the history database");
+        HISTORY.set(history).ok();
+        CONFIG.set(config).ok();
+        return;
+    }
+
+    if config.persist && Path::new(&config.path).exists() {
+        match load(&config.path) {
+            Ok(stored) => *RUNS.lock().unwrap() = stored,
+            Err(e) => {
+                let aside = format!("{}.corrupt-{}", config.path, chrono::Utc::now().timestamp_millis());
+                match std::fs::rename(&config.path, &aside) {
+                    Ok(()) => tracing::error!("❌ {}, moved to '{}', starting empty", e, aside),
+                    Err(re) => tracing::error!("❌ {}, starting empty (failed to move it aside: {})", e, re),
+                }
+            }
+        }
+    }
+    tracing::info!(
+        "🧾 Run history: {} runs{}",
+        RUNS.lock().unwrap().len(),
+        if config.persist { "" } else { " (in-memory)" }
+    );
+
+    if config.persist {
+        tokio::spawn(async {
+            loop {
+                tokio::time::sleep(FLUSH_INTERVAL).await;
+                if DIRTY.swap(false, Ordering::Relaxed) {
+                    if let Err(e) = flush() {
+                        DIRTY.store(true, Ordering::Relaxed);
+                        tracing::error!("❌ Run history flush failed: {}", e);
+                    }
+                }
+            }
+        });
+    }
+    CONFIG.set(config).ok();
+}
+
+fn load(path: &str) -> anyhow::Result<VecDeque<RunRecord>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read run history '{}': {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| anyhow::anyhow!("Invalid run history '{}': {}", path, e))
+}
+
+fn config() -> RunsConfig {
+    CONFIG.get().cloned().unwrap_or_default()
+}
+
+/// (realized, fees) инстанса в PnL tracker
+fn tracked_pnl(instance_id: &str) -> Option<(f64, f64)> {
+    let pnl = PNL.get()?;
+    Some(pnl.instance(instance_id).map_or((0.0, 0.0), |s| (s.realized_pnl, s.fees)))
+}
+
+/// Новый запуск инстанса
+pub fn begin(instance_id: &str, strategy_id: &str, symbol: &str, mode: &'static str, params: &redact::Params) {
+    PENDING.insert(instance_id.to_string(), Pending {
+        strategy_id: strategy_id.to_string(),
+        symbol: symbol.to_uppercase(),
+        mode,
+        started_at: chrono::Utc::now().timestamp_millis(),
+        baseline: if mode == "live" { tracked_pnl(instance_id) } else { None },
+        totals: None,
+        params: params.clone(),
+    });
+}
+
+/// Итог запуска при отключении инстанса. paper - счёт симулятора (paper/replay)
+pub fn settle(instance_id: &str, counters: &InstanceCounters, paper: Option<&PaperStats>) {
+    // После force remove запись уже закрыта: поток из карантина её не трогает
+    let Some(mut pending) = PENDING.get_mut(instance_id) else { return };
+    let pnl = match paper {
+        Some(stats) => Some((stats.realized_pnl, stats.fees)),
+        None => pending.baseline.and_then(|(realized, fees)| {
+            tracked_pnl(instance_id).map(|(r, f)| (r - realized, f - fees))
+        }),
+    };
+    pending.totals = Some(Totals {
+        stopped_at: chrono::Utc::now().timestamp_millis(),
+        placed: counters.placed(),
+        filled: paper.map_or_else(|| counters.filled(), |s| s.fills),
+        rejected: counters.rejected(),
+        pnl,
+    });
+}
+
+/// Последняя ошибка в логе инстанса, кроме строки о коде выхода
+fn last_error(instance_id: &str) -> Option<String> {
+    logs::tail(instance_id, REASON_LOOKBACK)?
+        .into_iter()
+        .rev()
+        .find(|l| l.level == "ERROR" && !l.message.starts_with(FINISHED_LINE))
+        .map(|l| l.message)
+}
+
+/// Закрывает запись запуска. Без reason у упавшего берётся последняя ошибка из лога
+pub fn finish(instance_id: &str, outcome: RunOutcome, exit_code: Option<i32>, reason: Option<String>) {
+    let Some(record) = close(instance_id, outcome, exit_code, reason) else { return };
+    match HISTORY.get() {
+        Some(history) => history.record_run(record),
+        None => remember(record),
+    }
+}
+
+fn close(instance_id: &str, outcome: RunOutcome, exit_code: Option<i32>, reason: Option<String>) -> Option<RunRecord> {
+    let (_, pending) = PENDING.remove(instance_id)?;
+    let reason = match outcome {
+        RunOutcome::Crashed => reason.or_else(|| last_error(instance_id)),
+        _ => reason,
+    }
+    .map(|r| pending.params.scrub(&r));
+    let totals = pending.totals.unwrap_or(Totals {
+        stopped_at: chrono::Utc::now().timestamp_millis(),
+        placed: 0,
+        filled: 0,
+        rejected: 0,
+        pnl: None,
+    });
+    Some(RunRecord {
+        instance_id: instance_id.to_string(),
+        strategy_id: pending.strategy_id,
+        symbol: pending.symbol,
+        mode: pending.mode.to_string(),
+        started_at: pending.started_at,
+        stopped_at: totals.stopped_at,
+        outcome,
+        exit_code,
+        reason,
+        orders_placed: totals.placed,
+        orders_filled: totals.filled,
+        orders_rejected: totals.rejected,
+        realized_pnl: totals.pnl.map(|(realized, _)| realized),
+        fees: totals.pnl.map(|(_, fees)| fees),
+    })
+}
+
+fn remember(record: RunRecord) {
+    let max_runs = config().max_runs.max(1);
+    let mut runs = RUNS.lock().unwrap();
+    runs.push_back(record);
+    while runs.len() > max_runs {
+        runs.pop_front();
+    }
+    DIRTY.store(true, Ordering::Relaxed);
+}
+
+/// Завершённые запуски, новые первыми. С history - запрос к SQLite,
+/// вызывать вне async runtime
+pub fn list(strategy_id: Option<&str>, symbol: Option<&str>, limit: usize) -> anyhow::Result<Vec<RunRecord>> {
+    if let Some(history) = HISTORY.get() {
+        return history.runs(strategy_id, symbol, limit);
+    }
+    Ok(RUNS.lock().unwrap()
+        .iter()
+        .rev()
+        .filter(|r| strategy_id.is_none_or(|id| r.strategy_id == id))
+        .filter(|r| symbol.is_none_or(|s| r.symbol.eq_ignore_ascii_case(s)))
+        .take(limit)
+        .cloned()
+        .collect())
+}
+
+/// Закрывает записи ещё работающих запусков и записывает историю
+/// (при остановке ядра)
+pub fn shutdown() {
+    let counters: HashMap<String, Arc<InstanceCounters>> = stats::all().into_iter().collect();
+    let ids: Vec<String> = PENDING.iter().map(|e| e.key().clone()).collect();
+    let mut interrupted = Vec::new();
+    for id in &ids {
+        if let Some(counters) = counters.get(id) {
+            let paper = paper::account(id).map(|a| a.stats());
+            settle(id, counters, paper.as_ref());
+        }
+        interrupted.extend(close(id, RunOutcome::Interrupted, None, Some("Core shutdown".to_string())));
+    }
+
+    if let Some(history) = HISTORY.get() {
+        if let Err(e) = history.write_runs(interrupted) {
+            tracing::error!("❌ Run history write failed: {}", e);
+        }
+        return;
+    }
+    for record in interrupted {
+        remember(record);
+    }
+
+    if config().persist && DIRTY.swap(false, Ordering::Relaxed) {
+        if let Err(e) = flush() {
+            tracing::error!("❌ Run history flush failed: {}", e);
+        }
+    }
+}
+
+fn flush() -> anyhow::Result<()> {
+    let config = config();
+    let _guard = FILE_LOCK.lock().unwrap();
+    let content = serde_json::to_string_pretty(&*RUNS.lock().unwrap())?;
+    files::write_atomic(&config.path, content.as_bytes())?;
+    Ok(())
+}
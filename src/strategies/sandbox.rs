@@ -0,0 +1,201 @@
+// src/strategies/sandbox.rs
+
+use serde::Deserialize;
+use std::process::{Child, Command};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::time::Duration;
+
+// ═══════════════════════════════════════════════════════════
+// ПЕСОЧНИЦА СБОРКИ
+// ═══════════════════════════════════════════════════════════
+//
+// cargo build исполняет чужой код ещё до запуска стратегии: build.rs и
+// proc-macro зависимостей и самой стратегии. Без ограничений это код с
+// правами ядра, без лимита времени и памяти. Здесь cargo (и всё, что он
+// запускает) получает потолки rlimit, свою группу процессов, которую
+// убивает таймаут, и по настройке - другого пользователя и сеть без
+// интерфейсов. cgroup задаётся обёрткой (systemd-run --scope -p MemoryMax=...).
+// Загруженную библиотеку это не изолирует: она работает в процессе ядра.
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SandboxConfig {
+    /// Сборка дольше - убивается вся группа процессов cargo (0 - без лимита)
+    pub timeout_secs: u64,
+    /// RLIMIT_AS на cargo, rustc и build-скрипты, MB (0 - без лимита).
+    /// rustc резервирует много адресного пространства: меньше 2048 не ставить
+    pub memory_mb: u64,
+    /// RLIMIT_CPU, секунд процессорного времени на процесс (0 - без лимита)
+    pub cpu_secs: u64,
+    /// RLIMIT_NPROC (0 - без лимита). Считаются все процессы пользователя,
+    /// так что имеет смысл только вместе с uid
+    pub max_processes: u64,
+    /// Запускать cargo от этого uid/gid. Ядро должно работать от root, а
+    /// каталоги стратегий, target и CARGO_HOME - быть доступны этому пользователю
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    /// false - без сети: CARGO_NET_OFFLINE и пустой network namespace.
+    /// Зависимости должны быть уже скачаны в CARGO_HOME
+    pub network: bool,
+    /// Не передавать cargo окружение ядра, кроме PATH, HOME, LANG,
+    /// CARGO_HOME и RUSTUP_HOME
+    pub clear_env: bool,
+    /// CARGO_HOME для сборки (например, свой для uid)
+    pub cargo_home: Option<String>,
+    /// Команда-обёртка перед cargo: ["systemd-run", "--scope", "-q", "-p", "MemoryMax=4G", "--"]
+    pub wrapper: Vec<String>,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: 900,
+            memory_mb: 0,
+            cpu_secs: 0,
+            max_processes: 0,
+            uid: None,
+            gid: None,
+            network: true,
+            clear_env: false,
+            cargo_home: None,
+            wrapper: Vec::new(),
+        }
+    }
+}
+
+/// Переменные, которые остаются при clear_env
+const KEPT_ENV: &[&str] = &["PATH", "HOME", "LANG", "CARGO_HOME", "RUSTUP_HOME"];
+
+impl SandboxConfig {
+    /// Команда cargo (через wrapper, если задан) с ограничениями песочницы
+    pub fn command(&self) -> Command {
+        let mut cmd = match self.wrapper.split_first() {
+            Some((program, args)) => {
+                let mut cmd = Command::new(program);
+                cmd.args(args).arg("cargo");
+                cmd
+            }
+            None => Command::new("cargo"),
+        };
+
+        if self.clear_env {
+            cmd.env_clear();
+            for name in KEPT_ENV {
+                if let Ok(value) = std::env::var(name) {
+                    cmd.env(name, value);
+                }
+            }
+        }
+        if let Some(home) = &self.cargo_home {
+            cmd.env("CARGO_HOME", home);
+        }
+        if !self.network {
+            cmd.env("CARGO_NET_OFFLINE", "true");
+        }
+
+        #[cfg(unix)]
+        self.restrict(&mut cmd);
+        cmd
+    }
+
+    #[cfg(unix)]
+    fn restrict(&self, cmd: &mut Command) {
+        use std::os::unix::process::CommandExt;
+
+        // Своя группа процессов: таймаут убивает и rustc, и build-скрипты
+        cmd.process_group(0);
+
+        let limits = [
+            (libc::RLIMIT_AS, self.memory_mb.saturating_mul(1024 * 1024)),
+            (libc::RLIMIT_CPU, self.cpu_secs),
+            (libc::RLIMIT_NPROC, self.max_processes),
+        ];
+        let (uid, gid, network) = (self.uid, self.gid, self.network);
+        // Между fork и exec: только async-signal-safe вызовы, без аллокаций.
+        // Пользователя меняем последним - unshare и rlimit требуют прав
+        unsafe {
+            cmd.pre_exec(move || {
+                for (resource, value) in limits {
+                    if value > 0 {
+                        let limit = libc::rlimit { rlim_cur: value as _, rlim_max: value as _ };
+                        if libc::setrlimit(resource, &limit) != 0 {
+                            return Err(std::io::Error::last_os_error());
+                        }
+                    }
+                }
+                if !network {
+                    // Без root новый network namespace доступен только внутри user namespace
+                    let flags = if libc::geteuid() == 0 {
+                        libc::CLONE_NEWNET
+                    } else {
+                        libc::CLONE_NEWUSER | libc::CLONE_NEWNET
+                    };
+                    if libc::unshare(flags) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                if let Some(gid) = gid.or(uid) {
+                    let groups = [gid as libc::gid_t];
+                    if libc::setgroups(1, groups.as_ptr()) != 0 || libc::setgid(gid as libc::gid_t) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                if let Some(uid) = uid {
+                    if libc::setuid(uid as libc::uid_t) != 0 {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+    }
+
+    /// Таймер сборки. Сработав, убивает группу процессов cargo;
+    /// снимается, когда Deadline уходит из области видимости
+    pub fn deadline(&self, child: &Child) -> Deadline {
+        let expired = Arc::new(AtomicBool::new(false));
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+        if self.timeout_secs > 0 {
+            let timeout = Duration::from_secs(self.timeout_secs);
+            let pid = child.id();
+            let expired = expired.clone();
+            std::thread::spawn(move || {
+                if done_rx.recv_timeout(timeout) == Err(mpsc::RecvTimeoutError::Timeout) {
+                    expired.store(true, Ordering::Relaxed);
+                    kill_group(pid);
+                }
+            });
+        }
+        Deadline { expired, _done: done_tx, timeout_secs: self.timeout_secs }
+    }
+}
+
+pub struct Deadline {
+    expired: Arc<AtomicBool>,
+    /// Drop закрывает канал и будит таймер раньше срока
+    _done: mpsc::Sender<()>,
+    timeout_secs: u64,
+}
+
+impl Deadline {
+    /// Сообщение об ошибке, если сборку убил таймаут
+    pub fn expired(&self) -> Option<String> {
+        self.expired.load(Ordering::Relaxed)
+            .then(|| format!("Build killed after {}s (compile.sandbox.timeout_secs)", self.timeout_secs))
+    }
+}
+
+#[cfg(unix)]
+fn kill_group(pid: u32) {
+    // Группа создана process_group(0): её id равен pid cargo
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_group(pid: u32) {
+    let _ = Command::new("taskkill").args(["/F", "/T", "/PID", &pid.to_string()]).status();
+}
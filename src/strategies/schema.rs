@@ -0,0 +1,151 @@
+// src/strategies/schema.rs
+
+use serde_json::Value;
+
+// ═══════════════════════════════════════════════════════════
+// СХЕМА ПАРАМЕТРОВ
+// ═══════════════════════════════════════════════════════════
+//
+// Подмножество JSON Schema: type, properties, required,
+// additionalProperties, enum, minimum, maximum, exclusiveMinimum,
+// exclusiveMaximum, items, minItems, maxItems, minLength, maxLength.
+//
+// В отличие от JSON Schema, лишние поля объекта по умолчанию запрещены
+// ("additionalProperties": true разрешает) - опечатка в имени параметра
+// должна останавливать запуск, а не молча давать Default.
+
+/// Проверяет, что схема - объект с известными типами
+pub fn check_schema(schema: &Value) -> anyhow::Result<()> {
+    let Some(obj) = schema.as_object() else {
+        anyhow::bail!("Schema must be a JSON object");
+    };
+    for t in type_names(schema) {
+        if !matches!(t, "object" | "array" | "string" | "number" | "integer" | "boolean" | "null") {
+            anyhow::bail!("Unknown schema type '{}'", t);
+        }
+    }
+    if let Some(props) = obj.get("properties") {
+        let Some(props) = props.as_object() else {
+            anyhow::bail!("'properties' must be an object");
+        };
+        for (name, prop) in props {
+            check_schema(prop).map_err(|e| anyhow::anyhow!("{}: {}", name, e))?;
+        }
+    }
+    if let Some(items) = obj.get("items") {
+        check_schema(items).map_err(|e| anyhow::anyhow!("items: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Все нарушения схемы в params ("$.order_size: expected number, got string").
+/// Пусто - параметры подходят
+pub fn validate(schema: &Value, params: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at(schema, params, "$", &mut errors);
+    errors
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let types = type_names(schema);
+    if !types.is_empty() && !types.iter().any(|t| is_type(value, t)) {
+        errors.push(format!("{}: expected {}, got {}", path, types.join(" | "), type_of(value)));
+        return;
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let list: Vec<String> = allowed.iter().map(Value::to_string).collect();
+            errors.push(format!("{}: must be one of {}", path, list.join(", ")));
+        }
+    }
+
+    match value {
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or(f64::NAN);
+            let bound = |key: &str| schema.get(key).and_then(Value::as_f64);
+            if let Some(min) = bound("minimum").filter(|min| n < *min) {
+                errors.push(format!("{}: must be >= {}", path, min));
+            }
+            if let Some(max) = bound("maximum").filter(|max| n > *max) {
+                errors.push(format!("{}: must be <= {}", path, max));
+            }
+            if let Some(min) = bound("exclusiveMinimum").filter(|min| n <= *min) {
+                errors.push(format!("{}: must be > {}", path, min));
+            }
+            if let Some(max) = bound("exclusiveMaximum").filter(|max| n >= *max) {
+                errors.push(format!("{}: must be < {}", path, max));
+            }
+        }
+        Value::String(s) => {
+            let len = s.chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64).filter(|min| len < *min) {
+                errors.push(format!("{}: length must be >= {}", path, min));
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64).filter(|max| len > *max) {
+                errors.push(format!("{}: length must be <= {}", path, max));
+            }
+        }
+        Value::Array(items) => {
+            let len = items.len() as u64;
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64).filter(|min| len < *min) {
+                errors.push(format!("{}: must have >= {} items", path, min));
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64).filter(|max| len > *max) {
+                errors.push(format!("{}: must have <= {} items", path, max));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_at(item_schema, item, &format!("{}[{}]", path, i), errors);
+                }
+            }
+        }
+        Value::Object(fields) => {
+            let props = schema.get("properties").and_then(Value::as_object);
+            if let Some(required) = schema.get("required").and_then(Value::as_array) {
+                for name in required.iter().filter_map(Value::as_str) {
+                    if !fields.contains_key(name) {
+                        errors.push(format!("{}.{}: required", path, name));
+                    }
+                }
+            }
+            let additional = schema.get("additionalProperties").and_then(Value::as_bool).unwrap_or(false);
+            for (name, field) in fields {
+                match props.and_then(|p| p.get(name)) {
+                    Some(prop) => validate_at(prop, field, &format!("{}.{}", path, name), errors),
+                    None if !additional => errors.push(format!("{}.{}: unknown field", path, name)),
+                    None => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// "type": "number" или "type": ["number", "null"]
+fn type_names(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn is_type(value: &Value, t: &str) -> bool {
+    match t {
+        "integer" => value.as_i64().is_some() || value.as_u64().is_some(),
+        "number" => value.is_number(),
+        other => type_of(value) == other,
+    }
+}
+
+fn type_of(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
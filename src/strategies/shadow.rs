@@ -0,0 +1,173 @@
+// src/strategies/shadow.rs
+
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::LazyLock;
+
+use crate::orders::OrderRecord;
+
+/// Суффикс id теневого инстанса: "strategy:SYMBOL" -> "strategy:SYMBOL@shadow"
+pub const SHADOW_SUFFIX: &str = "@shadow";
+
+/// Ордер и намерение тени считаются одним решением, если разошлись не больше чем на
+pub const DEFAULT_MATCH_WINDOW_MS: u64 = 1000;
+
+// ═══════════════════════════════════════════════════════════
+// ТЕНЕВОЙ ЗАПУСК
+// ═══════════════════════════════════════════════════════════
+//
+// Перед заменой стратегии переписанной версией хочется знать, что она
+// торгует так же. Тень - dry-run инстанс рядом с живым: те же события
+// рынка, её ордера только записываются (intents.rs). Отчёт сопоставляет
+// ордера живого инстанса с намерениями тени с момента запуска тени.
+// Исполнения тень не получает: после первой сделки их пути могут разойтись.
+
+/// Тень живого инстанса
+#[derive(Debug, Clone)]
+pub struct ShadowPair {
+    pub live_id: String,
+    pub shadow_id: String,
+    pub shadow_strategy_id: String,
+    /// Unix ms запуска тени: ордера живого раньше не сравниваются
+    pub started_at: i64,
+    pub match_window_ms: u64,
+}
+
+/// live instance_id -> его последняя тень (остаётся после остановки ради отчёта)
+static PAIRS: LazyLock<DashMap<String, ShadowPair>> = LazyLock::new(DashMap::new);
+
+pub fn shadow_id(live_id: &str) -> String {
+    format!("{}{}", live_id, SHADOW_SUFFIX)
+}
+
+pub fn register(pair: ShadowPair) {
+    PAIRS.insert(pair.live_id.clone(), pair);
+}
+
+pub fn get(live_id: &str) -> Option<ShadowPair> {
+    PAIRS.get(live_id).map(|p| p.clone())
+}
+
+// ═══════════════════════════════════════════════════════════
+// СРАВНЕНИЕ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderBrief {
+    pub client_order_id: String,
+    pub side: String,
+    pub order_type: String,
+    pub price: f64,
+    pub quantity: f64,
+    pub created_at: i64,
+}
+
+impl From<&OrderRecord> for OrderBrief {
+    fn from(o: &OrderRecord) -> Self {
+        Self {
+            client_order_id: o.client_order_id.clone(),
+            side: o.side.clone(),
+            order_type: o.order_type.clone(),
+            price: o.price,
+            quantity: o.orig_qty,
+            created_at: o.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderPair {
+    pub live: OrderBrief,
+    pub shadow: OrderBrief,
+    /// Тень позже живого (отрицательное - раньше), ms
+    pub delay_ms: i64,
+    /// (тень - живой) / живой, б.п. None - у одного из них нет цены (MARKET)
+    pub price_diff_bps: Option<f64>,
+    pub qty_diff: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShadowReport {
+    pub live_instance_id: String,
+    pub shadow_instance_id: String,
+    pub shadow_strategy_id: String,
+    pub started_at: i64,
+    pub live_running: bool,
+    pub shadow_running: bool,
+    pub match_window_ms: u64,
+    pub live_orders: usize,
+    pub shadow_orders: usize,
+    pub matched: usize,
+    /// matched / max(live, shadow); 1.0 - тень решает так же
+    pub match_rate: f64,
+    /// Среднее |price_diff_bps| по парам с ценой
+    pub mean_abs_price_diff_bps: Option<f64>,
+    /// Пар с разным количеством
+    pub qty_mismatches: usize,
+    pub pairs: Vec<OrderPair>,
+    /// Ордера живого, которых тень не повторила
+    pub live_only: Vec<OrderBrief>,
+    /// Намерения тени, которых не было у живого
+    pub shadow_only: Vec<OrderBrief>,
+}
+
+/// Сопоставляет ордера живого и тени с запуска тени: та же сторона и тип,
+/// ближайший по времени в пределах окна. Каждый ордер - не больше чем в одной паре
+pub fn compare(pair: &ShadowPair, live: &[OrderRecord], shadow: &[OrderRecord], running: (bool, bool)) -> ShadowReport {
+    // Намерения прошлой тени с тем же id тоже в order manager
+    let since = |o: &&OrderRecord| o.created_at >= pair.started_at;
+    let mut live: Vec<&OrderRecord> = live.iter().filter(since).collect();
+    let mut shadow: Vec<&OrderRecord> = shadow.iter().filter(since).collect();
+    live.sort_by_key(|o| o.created_at);
+    shadow.sort_by_key(|o| o.created_at);
+
+    let window = pair.match_window_ms as i64;
+    let mut taken = vec![false; shadow.len()];
+    let mut pairs = Vec::new();
+    let mut live_only = Vec::new();
+    for l in &live {
+        let best = shadow.iter().enumerate()
+            .filter(|(i, s)| !taken[*i] && s.side == l.side && s.order_type == l.order_type)
+            .map(|(i, s)| (i, s.created_at - l.created_at))
+            .filter(|(_, delay)| delay.abs() <= window)
+            .min_by_key(|(_, delay)| delay.abs());
+        let Some((i, delay_ms)) = best else {
+            live_only.push(OrderBrief::from(*l));
+            continue;
+        };
+        taken[i] = true;
+        let s = shadow[i];
+        pairs.push(OrderPair {
+            live: OrderBrief::from(*l),
+            shadow: OrderBrief::from(s),
+            delay_ms,
+            price_diff_bps: (l.price > 0.0 && s.price > 0.0).then(|| (s.price - l.price) / l.price * 10_000.0),
+            qty_diff: s.orig_qty - l.orig_qty,
+        });
+    }
+    let shadow_only: Vec<OrderBrief> = shadow.iter().zip(&taken)
+        .filter(|(_, taken)| !**taken)
+        .map(|(s, _)| OrderBrief::from(*s))
+        .collect();
+
+    let diffs: Vec<f64> = pairs.iter().filter_map(|p| p.price_diff_bps).map(f64::abs).collect();
+    let most = live.len().max(shadow.len());
+    ShadowReport {
+        live_instance_id: pair.live_id.clone(),
+        shadow_instance_id: pair.shadow_id.clone(),
+        shadow_strategy_id: pair.shadow_strategy_id.clone(),
+        started_at: pair.started_at,
+        live_running: running.0,
+        shadow_running: running.1,
+        match_window_ms: pair.match_window_ms,
+        live_orders: live.len(),
+        shadow_orders: shadow.len(),
+        matched: pairs.len(),
+        match_rate: if most == 0 { 1.0 } else { pairs.len() as f64 / most as f64 },
+        mean_abs_price_diff_bps: (!diffs.is_empty()).then(|| diffs.iter().sum::<f64>() / diffs.len() as f64),
+        qty_mismatches: pairs.iter().filter(|p| p.qty_diff.abs() > 1e-9).count(),
+        pairs,
+        live_only,
+        shadow_only,
+    }
+}
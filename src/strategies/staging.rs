@@ -0,0 +1,161 @@
+// src/strategies/staging.rs
+
+use dashmap::DashMap;
+use std::os::raw::c_char;
+use std::ffi::CStr;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, LazyLock};
+
+use crate::exchange_trade::PreparedOrder;
+use crate::ffi_types::COrderTemplate;
+use crate::keystore::{self, Credentials};
+use crate::strategies::order::{current_instance, submit_order, OrderCallback, PREPARED_ORDER};
+use crate::symbols;
+
+/// Шаблон отклонён: пустой/битый/неизвестный бирже символ, side/type вне диапазона, qty <= 0
+pub const ERR_STAGE_INVALID: i32 = -9400;
+/// Нет такого stage_id у текущего инстанса
+pub const ERR_STAGE_NOT_FOUND: i32 = -9401;
+
+/// Ограничение на число шаблонов в одном stage
+const MAX_TEMPLATES: usize = 256;
+
+// ═══════════════════════════════════════════════════════════
+// STAGED ORDERS
+// ═══════════════════════════════════════════════════════════
+
+// Стратегии вроде funding_grid знают всю сетку заранее, кроме опорной цены.
+// Ключи, символы и объёмы проверяются один раз при stage_orders, там же
+// собираются параметры order.place без цены (PreparedOrder). В момент
+// триггера остаются цена, risk и подпись: submit_order отправляет готовые
+// параметры вместо сборки заново.
+
+struct StagedOrder {
+    order_type: u8,
+    prepared: Arc<PreparedOrder>,
+}
+
+struct Stage {
+    instance_id: Option<String>,
+    creds: Arc<Credentials>,
+    orders: Arc<[StagedOrder]>,
+}
+
+static STAGES: LazyLock<DashMap<i64, Stage>> = LazyLock::new(DashMap::new);
+static NEXT_ID: AtomicI64 = AtomicI64::new(1);
+
+fn parse_template(t: &COrderTemplate) -> Option<StagedOrder> {
+    let symbol = t.symbol_str().filter(|s| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric()))?;
+    let side = match t.side {
+        0 => "BUY",
+        1 => "SELL",
+        _ => return None,
+    };
+    if t.order_type > 1 || !(t.quantity.is_finite() && t.quantity > 0.0) {
+        return None;
+    }
+    if let Err(e) = symbols::check(symbol) {
+        tracing::warn!("📌 Order template rejected: {}", e);
+        return None;
+    }
+    Some(StagedOrder {
+        order_type: t.order_type,
+        prepared: Arc::new(PreparedOrder::new(symbol, side, t.order_type == 0, t.quantity)),
+    })
+}
+
+/// Регистрирует шаблоны ордеров текущего инстанса.
+/// Возвращает stage_id (> 0) или ERR_STAGE_INVALID.
+pub unsafe extern "C" fn stage_orders(
+    api_key: *const c_char,
+    secret_key: *const c_char,
+    templates: *const COrderTemplate,
+    count: usize,
+) -> i64 {
+    if api_key.is_null() || secret_key.is_null() || templates.is_null()
+        || count == 0 || count > MAX_TEMPLATES
+    {
+        return ERR_STAGE_INVALID as i64;
+    }
+    let (Ok(api_key), Ok(secret_key)) = (CStr::from_ptr(api_key).to_str(), CStr::from_ptr(secret_key).to_str()) else {
+        return ERR_STAGE_INVALID as i64;
+    };
+
+    let orders: Option<Vec<StagedOrder>> = std::slice::from_raw_parts(templates, count)
+        .iter()
+        .map(parse_template)
+        .collect();
+    let Some(orders) = orders else {
+        return ERR_STAGE_INVALID as i64;
+    };
+
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let instance_id = current_instance();
+    tracing::debug!("📌 Staged {} orders (stage={}, instance={:?})", orders.len(), id, instance_id);
+    STAGES.insert(id, Stage {
+        instance_id,
+        creds: Arc::new(keystore::resolve(api_key, secret_key)),
+        orders: orders.into(),
+    });
+    id
+}
+
+/// Отправляет ордера stage: prices[i] - цена i-го шаблона.
+/// NaN - пропустить шаблон; цена MARKET используется только для risk-проверок.
+/// Stage остаётся зарегистрированным, callback вызывается на каждый ордер.
+/// Возвращает число отправленных ордеров или ERR_STAGE_*.
+pub unsafe extern "C" fn fire_staged(
+    stage_id: i64,
+    prices: *const f64,
+    count: usize,
+    callback: OrderCallback,
+) -> i32 {
+    // Guard DashMap не держим во время submit_order: callback может вызвать unstage
+    let instance_id = current_instance();
+    let Some((creds, orders)) = STAGES
+        .get(&stage_id)
+        .filter(|stage| stage.instance_id == instance_id)
+        .map(|stage| (stage.creds.clone(), stage.orders.clone()))
+    else {
+        return ERR_STAGE_NOT_FOUND;
+    };
+    if prices.is_null() || count > orders.len() {
+        return ERR_STAGE_INVALID;
+    }
+
+    let prices = std::slice::from_raw_parts(prices, count);
+    let mut sent = 0;
+    for (order, &price) in orders.iter().zip(prices) {
+        if price.is_nan() {
+            continue;
+        }
+        let prepared = &order.prepared;
+        PREPARED_ORDER.with(|p| p.replace(Some(prepared.clone())));
+        submit_order(
+            creds.clone(),
+            &prepared.symbol,
+            prepared.side,
+            order.order_type,
+            price,
+            prepared.qty,
+            callback,
+        );
+        // Не дошёл до route_order (дубликат) - не должен уйти со следующим ордером
+        PREPARED_ORDER.with(|p| p.take());
+        sent += 1;
+    }
+    sent
+}
+
+/// Удаляет stage текущего инстанса. false - не найден
+pub extern "C" fn unstage(stage_id: i64) -> bool {
+    let instance_id = current_instance();
+    STAGES
+        .remove_if(&stage_id, |_, stage| stage.instance_id == instance_id)
+        .is_some()
+}
+
+/// Сбрасывает все stage инстанса (после завершения run)
+pub fn clear(instance_id: &str) {
+    STAGES.retain(|_, stage| stage.instance_id.as_deref() != Some(instance_id));
+}
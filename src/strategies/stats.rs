@@ -0,0 +1,130 @@
+// src/strategies/stats.rs
+
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock};
+
+// ═══════════════════════════════════════════════════════════
+// СЧЁТЧИКИ ИНСТАНСА
+// ═══════════════════════════════════════════════════════════
+
+/// Счётчики ордеров и поток run() одного инстанса. Пишут путь ордера
+/// и order manager, читает monitor loop раннера
+#[derive(Default)]
+pub struct InstanceCounters {
+    placed: AtomicU64,
+    filled: AtomicU64,
+    rejected: AtomicU64,
+    /// Явные вызовы heartbeat из стратегии
+    heartbeats: AtomicU64,
+    /// tid потока run() (Linux), 0 - стратегия сейчас не исполняется
+    thread_id: AtomicU32,
+}
+
+impl InstanceCounters {
+    pub fn placed(&self) -> u64 {
+        self.placed.load(Ordering::Relaxed)
+    }
+
+    pub fn filled(&self) -> u64 {
+        self.filled.load(Ordering::Relaxed)
+    }
+
+    pub fn rejected(&self) -> u64 {
+        self.rejected.load(Ordering::Relaxed)
+    }
+
+    pub fn heartbeats(&self) -> u64 {
+        self.heartbeats.load(Ordering::Relaxed)
+    }
+
+    /// Процессорное время потока стратегии, ns (None - поток не известен)
+    pub fn cpu_time_ns(&self) -> Option<u64> {
+        match self.thread_id.load(Ordering::Relaxed) {
+            0 => None,
+            tid => thread_cpu_ns(tid),
+        }
+    }
+}
+
+/// Снимок, который monitor loop раннера кладёт в InstanceInfo раз в секунду
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct InstanceStats {
+    pub orders_placed: u64,
+    pub orders_filled: u64,
+    /// Отказы breaker'а, risk, симулятора и ошибки биржи на order.place
+    pub orders_rejected: u64,
+    /// Сколько ms назад стратегии положено последнее событие (None - ещё не было)
+    pub last_event_age_ms: Option<u64>,
+    /// Процессорное время потока run() с запуска
+    pub cpu_time_ms: Option<f64>,
+    /// Загрузка ядра потоком run() за последний интервал, %
+    pub cpu_percent: Option<f64>,
+    /// Unix ms снимка
+    pub sampled_at: i64,
+}
+
+/// instance_id -> счётчики
+static COUNTERS: LazyLock<DashMap<String, Arc<InstanceCounters>>> = LazyLock::new(DashMap::new);
+
+pub fn register(instance_id: &str) -> Arc<InstanceCounters> {
+    let counters = Arc::new(InstanceCounters::default());
+    COUNTERS.insert(instance_id.to_string(), counters.clone());
+    counters
+}
+
+/// Снимает регистрацию, если инстанс не перезапущен с тем же id
+pub fn unregister(instance_id: &str, counters: &Arc<InstanceCounters>) {
+    COUNTERS.remove_if(instance_id, |_, current| Arc::ptr_eq(current, counters));
+}
+
+/// Счётчики всех зарегистрированных инстансов
+pub fn all() -> Vec<(String, Arc<InstanceCounters>)> {
+    COUNTERS.iter().map(|c| (c.key().clone(), c.value().clone())).collect()
+}
+
+fn with(instance_id: Option<&str>, f: impl FnOnce(&InstanceCounters)) {
+    if let Some(c) = instance_id.and_then(|id| COUNTERS.get(id)) {
+        f(&c);
+    }
+}
+
+pub fn order_placed(instance_id: Option<&str>) {
+    with(instance_id, |c| { c.placed.fetch_add(1, Ordering::Relaxed); });
+}
+
+pub fn order_rejected(instance_id: Option<&str>) {
+    with(instance_id, |c| { c.rejected.fetch_add(1, Ordering::Relaxed); });
+}
+
+pub fn order_filled(instance_id: Option<&str>) {
+    with(instance_id, |c| { c.filled.fetch_add(1, Ordering::Relaxed); });
+}
+
+pub fn heartbeat(instance_id: Option<&str>) {
+    with(instance_id, |c| { c.heartbeats.fetch_add(1, Ordering::Relaxed); });
+}
+
+/// Запоминает текущий поток как поток run() инстанса (bind = false - отвязать:
+/// поток вернулся в пул spawn_blocking и считает уже чужое время)
+pub fn bind_thread(instance_id: &str, bind: bool) {
+    let tid = if bind { current_tid().unwrap_or(0) } else { 0 };
+    with(Some(instance_id), |c| c.thread_id.store(tid, Ordering::Relaxed));
+}
+
+// ═══════════════════════════════════════════════════════════
+// /proc
+// ═══════════════════════════════════════════════════════════
+
+/// tid текущего потока: /proc/thread-self -> "<pid>/task/<tid>"
+fn current_tid() -> Option<u32> {
+    let link = std::fs::read_link("/proc/thread-self").ok()?;
+    link.file_name()?.to_str()?.parse().ok()
+}
+
+/// Первое поле schedstat - время на CPU в ns
+fn thread_cpu_ns(tid: u32) -> Option<u64> {
+    let schedstat = std::fs::read_to_string(format!("/proc/self/task/{}/schedstat", tid)).ok()?;
+    schedstat.split_whitespace().next()?.parse().ok()
+}
@@ -1,10 +1,90 @@
 // src/strategies/storage.rs
 
+use std::collections::BTreeMap;
+use std::ffi::CStr;
 use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::raw::c_char;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Command, Stdio};
+use std::sync::RwLock;
+use std::time::Instant;
 use anyhow::{Result, Context};
-use serde::Serialize;
+use libloading::Library;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::ffi_types::STRATEGY_ABI_VERSION;
+use crate::lifecycle::{self, LifecycleEvent, LifecycleKind};
+use crate::strategies::diagnostics::{self, Diagnostic};
+use crate::strategies::schema;
+use crate::strategies::disk::{self, Artifact, DiskConfig, DiskUsage, PruneReport, Scan, StrategyDiskUsage, StrategyScan, MB};
+use crate::strategies::sandbox::SandboxConfig;
+
+/// Дополнительные зависимости стратегии (имя -> версия), рядом с Cargo.toml
+const DEPENDENCIES_FILE: &str = "dependencies.json";
+
+/// Описание стратегии: StrategyManifest
+const MANIFEST_FILE: &str = "manifest.json";
+
+/// Схема параметров запуска (см. schema.rs). Без файла - из params_schema() библиотеки
+const SCHEMA_FILE: &str = "schema.json";
+
+/// Код стратегии в архиве export/import (на диске - src/lib.rs с преамбулой)
+const ARCHIVE_CODE: &str = "lib.rs";
+
+/// Предел одного файла в импортируемом архиве
+const MAX_ARCHIVE_FILE: usize = 1024 * 1024;
+
+/// Добавляется перед кодом пользователя в src/lib.rs
+/// (diagnostics::PRELUDE_LINES строк - сдвиг номеров строк в ошибках)
+const CODE_PRELUDE: &str = "mod types;\nuse types::*;\n\n";
+
+/// Binance api/secret key - 64 символа [A-Za-z0-9]; берём с запасом
+const MIN_SECRET_LEN: usize = 40;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CompileConfig {
+    /// Общий CARGO_TARGET_DIR для всех стратегий: зависимости (crossbeam, serde,
+    /// chrono) собираются один раз, дальше пересобирается только сама стратегия.
+    /// None - у каждой стратегии свой target/
+    pub shared_target_dir: Option<String>,
+    /// Сколько cargo build идёт одновременно (с общим target/ cargo всё
+    /// равно ждёт блокировку каталога)
+    pub max_parallel: usize,
+    /// Крейты, которые стратегия может добавить к шаблону: имя -> разрешённые версии
+    /// (первая - по умолчанию, если в запросе версия пустая)
+    pub allowed_dependencies: BTreeMap<String, Vec<String>>,
+    /// Ограничения для cargo: таймаут, rlimit, пользователь, сеть
+    pub sandbox: SandboxConfig,
+    /// Квоты и чистка кэша сборки
+    pub disk: DiskConfig,
+}
+
+impl Default for CompileConfig {
+    fn default() -> Self {
+        Self {
+            shared_target_dir: Some("./strategies/target".to_string()),
+            max_parallel: 1,
+            allowed_dependencies: [
+                ("statrs", &["0.17", "0.16"][..]),
+                ("ta", &["0.5"]),
+                ("rand", &["0.8"]),
+                ("libm", &["0.2"]),
+            ]
+            .into_iter()
+            .map(|(name, versions)| (name.to_string(), versions.iter().map(|v| v.to_string()).collect()))
+            .collect(),
+            sandbox: SandboxConfig::default(),
+            disk: DiskConfig::default(),
+        }
+    }
+}
 
 // ═══════════════════════════════════════════════════════════
 // ТИПЫ
@@ -15,15 +95,86 @@ use serde::Serialize;
 pub struct StrategyInfo {
     pub id: String,
     pub compiled: bool,
+    pub manifest: StrategyManifest,
+    /// Unix ms последнего изменения кода (None - загруженная библиотека без исходника)
+    pub modified_at: Option<i64>,
+    /// Unix ms сборки библиотеки, которую получит следующий запуск
+    pub compiled_at: Option<i64>,
+    /// Размер кода пользователя, байт
+    pub code_size: u64,
+}
+
+/// Заготовка кода из copy_into_strategies/templates/{name}.rs
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateInfo {
+    pub name: String,
+    /// Первая строка-комментарий файла
+    pub description: String,
+}
+
+/// Описание стратегии (manifest.json в папке стратегии)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StrategyManifest {
+    pub description: String,
+    pub author: String,
+    /// Версия стратегии, задаёт пользователь ("1.2.0")
+    pub version: String,
+    /// В нижнем регистре, без повторов
+    pub tags: Vec<String>,
+    /// Unix ms
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Изменения описания из запроса: None - поле не трогаем
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ManifestUpdate {
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub version: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+impl StrategyManifest {
+    fn apply(&mut self, update: &ManifestUpdate) {
+        if let Some(v) = &update.description {
+            self.description = v.trim().to_string();
+        }
+        if let Some(v) = &update.author {
+            self.author = v.trim().to_string();
+        }
+        if let Some(v) = &update.version {
+            self.version = v.trim().to_string();
+        }
+        if let Some(tags) = &update.tags {
+            self.tags.clear();
+            for tag in tags.iter().map(|t| t.trim().to_lowercase()).filter(|t| !t.is_empty()) {
+                if !self.tags.contains(&tag) {
+                    self.tags.push(tag);
+                }
+            }
+        }
+        self.updated_at = chrono::Utc::now().timestamp_millis();
+    }
+    
+    /// Есть ли у стратегии все теги
+    pub fn has_tags(&self, tags: &[String]) -> bool {
+        tags.iter().all(|t| self.tags.iter().any(|own| own.eq_ignore_ascii_case(t)))
+    }
 }
 
 /// Результат компиляции
 #[derive(Debug)]
 pub struct CompilationResult {
     pub success: bool,
+    #[allow(dead_code)]
     pub lib_path: Option<PathBuf>,
+    #[allow(dead_code)]
     pub output: String,
     pub errors: Vec<String>,
+    /// Ошибки и предупреждения компилятора с позициями в коде стратегии
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 // ═══════════════════════════════════════════════════════════
@@ -33,10 +184,17 @@ pub struct CompilationResult {
 pub struct StrategyStorage {
     base_path: PathBuf,
     templates_path: PathBuf,
+    /// Общий CARGO_TARGET_DIR (абсолютный), None - target/ в папке стратегии
+    shared_target: Option<PathBuf>,
+    allowed_dependencies: BTreeMap<String, Vec<String>>,
+    sandbox: SandboxConfig,
+    disk: DiskConfig,
+    /// Сборки держат на чтение, чистка кэша - на запись
+    cache_lock: RwLock<()>,
 }
 
 impl StrategyStorage {
-    pub fn new(base_path: &str) -> Result<Self> {
+    pub fn new(base_path: &str, config: &CompileConfig) -> Result<Self> {
         let base = PathBuf::from(base_path);
         let templates = PathBuf::from("copy_into_strategies");
         
@@ -52,9 +210,27 @@ impl StrategyStorage {
             anyhow::bail!("Missing: copy_into_strategies/Cargo.toml");
         }
         
+        let shared_target = match &config.shared_target_dir {
+            Some(path) => {
+                fs::create_dir_all(path)?;
+                let path = fs::canonicalize(path)?;
+                tracing::info!("📦 Shared strategy target dir: {:?}", path);
+                Some(path)
+            }
+            None => None,
+        };
+        
         tracing::info!("✅ StrategyStorage initialized at {:?}", base);
         
-        Ok(Self { base_path: base, templates_path: templates })
+        Ok(Self {
+            base_path: base,
+            templates_path: templates,
+            shared_target,
+            allowed_dependencies: config.allowed_dependencies.clone(),
+            sandbox: config.sandbox.clone(),
+            disk: config.disk.clone(),
+            cache_lock: RwLock::new(()),
+        })
     }
     
     // ═══════════════════════════════════════════════════════════
@@ -62,19 +238,29 @@ impl StrategyStorage {
     // ═══════════════════════════════════════════════════════════
     
     /// Создать стратегию
-    pub fn create(&self, id: &str, code: &str) -> Result<()> {
+    pub fn create(
+        &self,
+        id: &str,
+        code: &str,
+        dependencies: Option<&BTreeMap<String, String>>,
+        manifest: &ManifestUpdate,
+    ) -> Result<()> {
         let dir = self.base_path.join(id);
         
         if dir.exists() {
             anyhow::bail!("Strategy '{}' already exists", id);
         }
+        check_secrets(code)?;
+        let dependencies = self.resolve_dependencies(dependencies.unwrap_or(&BTreeMap::new()))?;
+        self.ensure_quota(None, code.len() as u64)?;
         
         fs::create_dir_all(dir.join("src"))?;
         
         // Копируем шаблоны
-        self.copy_cargo_toml(&dir, id)?;
+        self.copy_cargo_toml(&dir, id, &dependencies)?;
         self.copy_types(&dir)?;
         self.save_code(&dir, code)?;
+        self.save_manifest(&dir, manifest)?;
         
         tracing::info!("✅ Strategy '{}' created", id);
         Ok(())
@@ -89,23 +275,141 @@ impl StrategyStorage {
         self.load_code(&dir)
     }
     
-    /// Обновить код стратегии
-    pub fn update_code(&self, id: &str, code: &str) -> Result<()> {
+    /// Обновить код стратегии. dependencies: None - оставить как были
+    pub fn update_code(
+        &self,
+        id: &str,
+        code: &str,
+        dependencies: Option<&BTreeMap<String, String>>,
+        manifest: &ManifestUpdate,
+    ) -> Result<()> {
         let dir = self.base_path.join(id);
         if !dir.exists() {
             anyhow::bail!("Strategy '{}' not found", id);
         }
-        
-        // Проверяем types.rs
-        if !dir.join("src/types.rs").exists() {
-            self.copy_types(&dir)?;
+        check_secrets(code)?;
+        if let Some(dependencies) = dependencies {
+            let dependencies = self.resolve_dependencies(dependencies)?;
+            self.copy_cargo_toml(&dir, id, &dependencies)?;
+        } else if is_prebuilt(&dir) {
+            // Загруженная библиотека получает исходник - дальше собирается как обычно
+            fs::create_dir_all(dir.join("src"))?;
+            self.copy_cargo_toml(&dir, id, &BTreeMap::new())?;
         }
         
+        // types.rs всегда обновляем - ABI должен совпадать с ядром
+        self.copy_types(&dir)?;
+        
         self.save_code(&dir, code)?;
+        self.save_manifest(&dir, manifest)?;
         tracing::info!("✏️ Strategy '{}' code updated", id);
         Ok(())
     }
     
+    /// Описание стратегии; у созданных до manifest.json - пустое
+    pub fn get_manifest(&self, id: &str) -> StrategyManifest {
+        load_manifest(&self.base_path.join(id))
+    }
+    
+    /// Меняет только описание, код и сборка не трогаются
+    pub fn update_manifest(&self, id: &str, update: &ManifestUpdate) -> Result<StrategyManifest> {
+        let dir = self.base_path.join(id);
+        if !dir.exists() {
+            anyhow::bail!("Strategy '{}' not found", id);
+        }
+        self.save_manifest(&dir, update)
+    }
+    
+    /// Схема параметров: schema.json в папке стратегии, иначе экспорт
+    /// params_schema() собранной библиотеки. None - стратегия её не объявила
+    pub fn get_schema(&self, id: &str) -> Result<Option<Value>> {
+        let dir = self.base_path.join(id);
+        if !dir.exists() {
+            anyhow::bail!("Strategy '{}' not found", id);
+        }
+        
+        let path = dir.join(SCHEMA_FILE);
+        if path.exists() {
+            let schema: Value = serde_json::from_str(&fs::read_to_string(&path)?)
+                .with_context(|| format!("Invalid {}", SCHEMA_FILE))?;
+            return Ok(Some(schema));
+        }
+        
+        match self.get_lib_path(id) {
+            Ok(lib_path) => library_schema(&lib_path),
+            Err(_) => Ok(None),
+        }
+    }
+    
+    /// Записывает schema.json (None - удалить, схема снова берётся из библиотеки)
+    pub fn set_schema(&self, id: &str, schema: Option<&Value>) -> Result<()> {
+        let dir = self.base_path.join(id);
+        if !dir.exists() {
+            anyhow::bail!("Strategy '{}' not found", id);
+        }
+        let path = dir.join(SCHEMA_FILE);
+        match schema {
+            Some(schema) => {
+                schema::check_schema(schema)?;
+                fs::write(&path, serde_json::to_string_pretty(schema)?)?;
+            }
+            None if path.exists() => fs::remove_file(&path)?,
+            None => {}
+        }
+        Ok(())
+    }
+    
+    // ═══════════════════════════════════════════════════════════
+    // ЗАГОТОВКИ КОДА
+    // ═══════════════════════════════════════════════════════════
+    
+    /// Заготовки, из которых можно создать стратегию (по имени)
+    pub fn templates(&self) -> Vec<TemplateInfo> {
+        let Ok(entries) = fs::read_dir(self.templates_path.join("templates")) else {
+            return Vec::new();
+        };
+        let mut result: Vec<TemplateInfo> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension()? != "rs" {
+                    return None;
+                }
+                let name = path.file_stem()?.to_str()?.to_string();
+                let description = fs::read_to_string(&path)
+                    .ok()?
+                    .lines()
+                    .next()
+                    .and_then(|l| l.strip_prefix("//"))
+                    .map(|l| l.trim().to_string())
+                    .unwrap_or_default();
+                Some(TemplateInfo { name, description })
+            })
+            .collect();
+        result.sort_by(|a, b| a.name.cmp(&b.name));
+        result
+    }
+    
+    /// Код заготовки в том виде, в каком его присылает пользователь (без преамбулы)
+    pub fn template_code(&self, name: &str) -> Result<String> {
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            anyhow::bail!("Invalid template name '{}'", name);
+        }
+        let path = self.templates_path.join("templates").join(format!("{}.rs", name));
+        fs::read_to_string(&path).map_err(|_| {
+            let known: Vec<String> = self.templates().into_iter().map(|t| t.name).collect();
+            anyhow::anyhow!("Unknown template '{}', available: {}", name, known.join(", "))
+        })
+    }
+    
+    /// Дополнительные зависимости стратегии (пусто - только шаблонные)
+    pub fn get_dependencies(&self, id: &str) -> BTreeMap<String, String> {
+        fs::read_to_string(self.base_path.join(id).join(DEPENDENCIES_FILE))
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default()
+    }
+    
     /// Удалить стратегию
     pub fn delete(&self, id: &str) -> Result<()> {
         let dir = self.base_path.join(id);
@@ -114,6 +418,11 @@ impl StrategyStorage {
         }
         
         fs::remove_dir_all(&dir)?;
+        // Кэш стратегии в общем target/ больше никому не нужен
+        let (owned, _) = self.scan_shared(&[id]);
+        for artifact in owned.values().flatten() {
+            let _ = artifact.remove();
+        }
         tracing::info!("🗑️ Strategy '{}' deleted", id);
         Ok(())
     }
@@ -126,10 +435,17 @@ impl StrategyStorage {
             let entry = entry?;
             if entry.path().is_dir() {
                 if let Some(id) = entry.file_name().to_str() {
-                    let compiled = self.get_lib_path(id).is_ok();
+                    let dir = entry.path();
+                    let code = dir.join("src/lib.rs");
+                    let compiled_at = modified_ms(&self.lib_path_for(&dir, id));
                     result.push(StrategyInfo {
                         id: id.to_string(),
-                        compiled,
+                        compiled: compiled_at.is_some(),
+                        manifest: load_manifest(&dir),
+                        modified_at: modified_ms(&code),
+                        compiled_at,
+                        code_size: fs::metadata(&code)
+                            .map_or(0, |m| m.len().saturating_sub(CODE_PRELUDE.len() as u64)),
                     });
                 }
             }
@@ -143,74 +459,316 @@ impl StrategyStorage {
         self.base_path.join(id).exists()
     }
     
+    // ═══════════════════════════════════════════════════════════
+    // АРХИВЫ
+    // ═══════════════════════════════════════════════════════════
+    
+    /// Zip для переноса на другой сервер: {id}/lib.rs (без преамбулы),
+    /// manifest.json, а также schema.json и dependencies.json, если есть
+    pub fn export(&self, id: &str) -> Result<Vec<u8>> {
+        let dir = self.base_path.join(id);
+        if !dir.exists() {
+            anyhow::bail!("Strategy '{}' not found", id);
+        }
+        if is_prebuilt(&dir) {
+            anyhow::bail!("Strategy '{}' has no source code, only a library", id);
+        }
+        
+        let mut files = vec![
+            (ARCHIVE_CODE, self.load_code(&dir)?.into_bytes()),
+            (MANIFEST_FILE, serde_json::to_vec_pretty(&load_manifest(&dir))?),
+        ];
+        for name in [SCHEMA_FILE, DEPENDENCIES_FILE] {
+            if let Ok(bytes) = fs::read(dir.join(name)) {
+                files.push((name, bytes));
+            }
+        }
+        
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        for (name, bytes) in files {
+            zip.start_file(format!("{}/{}", id, name), options)?;
+            zip.write_all(&bytes)?;
+        }
+        Ok(zip.finish()?.into_inner())
+    }
+    
+    /// Создаёт стратегию из архива export. id - из папки в архиве, если не
+    /// задан явно. Проверки те же, что при create: секреты, зависимости, схема
+    pub fn import(&self, bytes: &[u8], id: Option<&str>) -> Result<String> {
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(bytes)).context("Not a zip archive")?;
+        
+        let mut folder: Option<String> = None;
+        let mut files: BTreeMap<String, String> = BTreeMap::new();
+        for i in 0..zip.len() {
+            let entry = zip.by_index(i)?;
+            if entry.is_dir() {
+                continue;
+            }
+            let path = entry.name().to_string();
+            let (parent, name) = match path.rsplit_once('/') {
+                Some((parent, name)) => (Some(parent), name),
+                None => (None, path.as_str()),
+            };
+            if ![ARCHIVE_CODE, MANIFEST_FILE, SCHEMA_FILE, DEPENDENCIES_FILE].contains(&name) {
+                continue;
+            }
+            if entry.size() > MAX_ARCHIVE_FILE as u64 {
+                anyhow::bail!("'{}' exceeds {} bytes", path, MAX_ARCHIVE_FILE);
+            }
+            if let Some(parent) = parent.filter(|p| !p.contains('/')) {
+                folder.get_or_insert_with(|| parent.to_string());
+            }
+            let mut content = String::new();
+            std::io::Read::take(entry, MAX_ARCHIVE_FILE as u64).read_to_string(&mut content)
+                .with_context(|| format!("'{}' is not UTF-8 text", path))?;
+            files.insert(name.to_string(), content);
+        }
+        
+        let id = id.map(str::to_string).or(folder)
+            .context("Archive has no strategy folder, pass ?id=")?;
+        check_id(&id)?;
+        let code = files.get(ARCHIVE_CODE).with_context(|| format!("Archive has no {}", ARCHIVE_CODE))?;
+        let parse = |name: &str| -> Result<Option<Value>> {
+            files.get(name)
+                .map(|text| serde_json::from_str(text).with_context(|| format!("Invalid {}", name)))
+                .transpose()
+        };
+        let manifest: StrategyManifest = parse(MANIFEST_FILE)?.map(serde_json::from_value).transpose()?.unwrap_or_default();
+        let dependencies: Option<BTreeMap<String, String>> =
+            parse(DEPENDENCIES_FILE)?.map(serde_json::from_value).transpose()?;
+        let schema = parse(SCHEMA_FILE)?;
+        if let Some(schema) = &schema {
+            schema::check_schema(schema)?;
+        }
+        
+        let update = ManifestUpdate {
+            description: Some(manifest.description),
+            author: Some(manifest.author),
+            version: Some(manifest.version),
+            tags: Some(manifest.tags),
+        };
+        self.create(&id, code, dependencies.as_ref(), &update)?;
+        if let Err(e) = self.set_schema(&id, schema.as_ref()) {
+            let _ = self.delete(&id);
+            return Err(e);
+        }
+        tracing::info!("📥 Strategy '{}' imported", id);
+        Ok(id)
+    }
+    
     // ═══════════════════════════════════════════════════════════
     // КОМПИЛЯЦИЯ
     // ═══════════════════════════════════════════════════════════
     
-    pub fn compile(&self, id: &str) -> Result<CompilationResult> {
+    /// Компиляция с построчной выдачей stderr cargo (прогресс для compile-jobs).
+    /// Блокирующая - вызывается из CompileQueue
+    pub fn compile(&self, id: &str, on_line: &mut dyn FnMut(&str)) -> Result<CompilationResult> {
         let dir = self.base_path.join(id);
         if !dir.exists() {
             anyhow::bail!("Strategy '{}' not found", id);
         }
+        if is_prebuilt(&dir) {
+            anyhow::bail!("Strategy '{}' is a prebuilt library without source, upload a new artifact instead", id);
+        }
+        
+        let result = self.ensure_quota(Some(id), 0).and_then(|_| {
+            let _cache = self.cache_lock.read().unwrap();
+            self.build(&dir, id, on_line)
+        });
+        
+        let event = LifecycleEvent::new(LifecycleKind::CompileFinished, id);
+        lifecycle::emit(match &result {
+            Ok(r) if r.success => event.success(true),
+            Ok(r) => event.success(false).message(r.errors.first().cloned().unwrap_or_default()),
+            Err(e) => event.success(false).message(e.to_string()),
+        });
         
+        result
+    }
+    
+    fn build(&self, dir: &Path, id: &str, on_line: &mut dyn FnMut(&str)) -> Result<CompilationResult> {
         tracing::info!("📦 Compiling '{}'...", id);
         
-        let output = Command::new("cargo")
-            .args(["build", "--release", "--manifest-path"])
-            .arg(dir.join("Cargo.toml"))
-            .output()
-            .context("Failed to run cargo")?;
+        self.copy_types(dir)?;
+        
+        let started = Instant::now();
+        let mut result = self.run_cargo(dir, &["build", "--release"], on_line)?;
         
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let combined = format!("{}\n{}", stdout, stderr);
-        
-        if output.status.success() {
-            let lib_path = self.lib_path_for(&dir, id);
-            if lib_path.exists() {
-                tracing::info!("✅ Compiled: {:?}", lib_path);
-                Ok(CompilationResult {
-                    success: true,
-                    lib_path: Some(lib_path),
-                    output: combined,
-                    errors: vec![],
-                })
-            } else {
+        if result.success {
+            let lib_path = self.lib_path_for(dir, id);
+            if let Some(shared) = &self.shared_target {
+                self.uplift(&shared.join("release").join(lib_name(id)), &lib_path)?;
+            }
+            if !lib_path.exists() {
                 anyhow::bail!("Library not found after compilation: {:?}", lib_path);
             }
+            tracing::info!("✅ Compiled in {:.1}s: {:?}", started.elapsed().as_secs_f64(), lib_path);
+            result.lib_path = Some(lib_path);
         } else {
-            let errors = self.parse_errors(&stderr);
             tracing::error!("❌ Compilation failed for '{}'", id);
-            Ok(CompilationResult {
-                success: false,
-                lib_path: None,
-                output: combined,
-                errors,
-            })
         }
+        Ok(result)
+    }
+    
+    /// cargo check (lint = false) или cargo clippy без release-сборки:
+    /// быстрая проверка кода перед compile. Блокирующая
+    pub fn check(&self, id: &str, lint: bool) -> Result<CompilationResult> {
+        let dir = self.base_path.join(id);
+        if !dir.exists() {
+            anyhow::bail!("Strategy '{}' not found", id);
+        }
+        if is_prebuilt(&dir) {
+            anyhow::bail!("Strategy '{}' is a prebuilt library without source", id);
+        }
+        self.copy_types(&dir)?;
+        self.ensure_quota(Some(id), 0)?;
+        
+        let subcommand = if lint { "clippy" } else { "check" };
+        tracing::info!("🔍 cargo {} '{}'...", subcommand, id);
+        let _cache = self.cache_lock.read().unwrap();
+        self.run_cargo(&dir, &[subcommand], &mut |_| {})
+    }
+    
+    /// Прогоняет код через rustfmt (код стратегии без преамбулы, на диск не пишет)
+    pub fn format(&self, code: &str) -> Result<String> {
+        let mut child = Command::new("rustfmt")
+            .args(["--edition", "2021", "--emit", "stdout"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run rustfmt")?;
+        
+        let mut stdin = child.stdin.take().context("rustfmt stdin")?;
+        let input = code.to_string();
+        // Пишем из отдельного потока: rustfmt может начать выводить, не дочитав вход
+        let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+        let output = child.wait_with_output().context("Failed to wait for rustfmt")?;
+        let _ = writer.join();
+        
+        if !output.status.success() {
+            anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
     }
     
-    // pub fn check(&self, id: &str) -> Result<CompilationResult> {
-    //     let dir = self.base_path.join(id);
-    //     if !dir.exists() {
-    //         anyhow::bail!("Strategy '{}' not found", id);
-    //     }
+    /// Запускает cargo с --message-format=json в папке стратегии
+    fn run_cargo(&self, dir: &Path, args: &[&str], on_line: &mut dyn FnMut(&str)) -> Result<CompilationResult> {
+        let mut cmd = self.sandbox.command();
+        cmd.args(args)
+            .args(["--message-format=json", "--manifest-path"])
+            .arg(dir.join("Cargo.toml"));
+        if let Some(shared) = &self.shared_target {
+            cmd.env("CARGO_TARGET_DIR", shared);
+        }
+        let mut child = cmd
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run cargo")?;
+        let deadline = self.sandbox.deadline(&child);
+        
+        // stdout (JSON-сообщения) и stderr (прогресс) читаем параллельно,
+        // чтобы cargo не встал на полном pipe; вывод идёт в on_line по мере прихода
+        let (line_tx, line_rx) = std::sync::mpsc::channel::<(bool, String)>();
+        let stdout = child.stdout.take().context("cargo stdout")?;
+        let stderr = child.stderr.take().context("cargo stderr")?;
+        for (is_json, pipe) in [(true, Box::new(stdout) as Box<dyn std::io::Read + Send>), (false, Box::new(stderr))] {
+            let line_tx = line_tx.clone();
+            std::thread::spawn(move || {
+                for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+                    if line_tx.send((is_json, line)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(line_tx);
+        
+        let mut combined = String::new();
+        let mut stderr = String::new();
+        let mut diagnostics = Vec::new();
+        for (is_json, line) in line_rx {
+            if !is_json {
+                on_line(&line);
+                stderr.push_str(&line);
+                stderr.push('\n');
+                combined.push_str(&line);
+                combined.push('\n');
+                continue;
+            }
+            // Текст диагностики cargo больше не печатает в stderr - отдаём rendered
+            let Some(d) = diagnostics::parse(&line) else { continue };
+            for rendered in d.rendered.lines() {
+                on_line(rendered);
+            }
+            combined.push_str(&d.rendered);
+            diagnostics.push(d);
+        }
+        let success = child.wait().context("Failed to wait for cargo")?.success();
+        
+        // Ошибки самого cargo (Cargo.toml, зависимости) приходят только в stderr
+        let mut errors: Vec<String> = diagnostics.iter()
+            .filter(|d| d.is_error())
+            .map(Diagnostic::summary)
+            .collect();
+        if !success && errors.is_empty() {
+            errors = self.parse_errors(&stderr);
+        }
+        if let Some(timeout) = deadline.expired() {
+            on_line(&timeout);
+            combined.push_str(&timeout);
+            combined.push('\n');
+            errors.push(timeout);
+        }
         
-    //     let output = Command::new("cargo")
-    //         .args(["check", "--manifest-path"])
-    //         .arg(dir.join("Cargo.toml"))
-    //         .output()?;
+        Ok(CompilationResult {
+            success,
+            lib_path: None,
+            output: combined,
+            errors,
+            diagnostics,
+        })
+    }
+    
+    /// Устанавливает готовую библиотеку (.so/.dll/.dylib из CI пользователя).
+    /// Стратегии без исходника создаются. Библиотека проверяется загрузкой:
+    /// есть run и strategy_abi_version совпадает с ядром
+    pub fn install_artifact(&self, id: &str, bytes: &[u8]) -> Result<PathBuf> {
+        check_id(id)?;
+        self.ensure_quota(Some(id), bytes.len() as u64)?;
+        let dir = self.base_path.join(id);
+        let created = !dir.exists();
+        let lib_path = self.lib_path_for(&dir, id);
+        fs::create_dir_all(lib_path.parent().context("lib dir")?)?;
         
-    //     let stderr = String::from_utf8_lossy(&output.stderr);
-    //     let combined = format!("{}\n{}", String::from_utf8_lossy(&output.stdout), stderr);
+        let upload = lib_path.with_extension("upload");
+        let checked = fs::write(&upload, bytes)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| open_library(&upload, true).map(drop));
+        if let Err(e) = checked {
+            let _ = fs::remove_file(&upload);
+            if created {
+                let _ = fs::remove_dir_all(&dir);
+            }
+            return Err(e);
+        }
+        // rename, а не запись по месту: запущенные инстансы держат старый файл
+        fs::rename(&upload, &lib_path)?;
+        if created {
+            self.save_manifest(&dir, &ManifestUpdate::default())?;
+        }
         
-    //     Ok(CompilationResult {
-    //         success: output.status.success(),
-    //         lib_path: None,
-    //         output: combined,
-    //         errors: if output.status.success() { vec![] } else { self.parse_errors(&stderr) },
-    //     })
-    // }
+        tracing::info!("📤 Strategy '{}' artifact installed ({} bytes)", id, bytes.len());
+        lifecycle::emit(LifecycleEvent::new(LifecycleKind::CompileFinished, id).success(true).message("artifact uploaded"));
+        Ok(lib_path)
+    }
+    
+    /// Стратегия из загруженной библиотеки, без исходника
+    pub fn is_prebuilt(&self, id: &str) -> bool {
+        is_prebuilt(&self.base_path.join(id))
+    }
     
     pub fn get_lib_path(&self, id: &str) -> Result<PathBuf> {
         let dir = self.base_path.join(id);
@@ -222,18 +780,216 @@ impl StrategyStorage {
             anyhow::bail!("Not compiled. Run compile first.")
         }
     }
-    
+
+    // ═══════════════════════════════════════════════════════════
+    // ДИСК
+    // ═══════════════════════════════════════════════════════════
+
+    /// Сколько занимают стратегии и общий target/. Блокирующая (обход каталогов)
+    pub fn disk_usage(&self) -> Result<DiskUsage> {
+        let scan = self.scan()?;
+        let mut strategies: Vec<StrategyDiskUsage> = scan.strategies.iter().map(StrategyScan::usage).collect();
+        strategies.sort_by_key(|s| std::cmp::Reverse(s.total_bytes));
+        Ok(DiskUsage {
+            strategies,
+            shared_cache_bytes: scan.shared_bytes(),
+            total_bytes: scan.total_bytes(),
+            max_strategy_bytes: self.disk.max_strategy_bytes(),
+            max_total_bytes: self.disk.max_total_bytes(),
+        })
+    }
+
+    /// Удаляет кэш сборки: устаревший (disk.stale_days) и сверх квот,
+    /// all - весь. Библиотеки остаются. Ждёт окончания идущих сборок
+    pub fn prune(&self, all: bool) -> Result<PruneReport> {
+        let _cache = self.cache_lock.write().unwrap();
+        let scan = self.scan()?;
+        let mut report = PruneReport::default();
+
+        let max_strategy = self.disk.max_strategy_bytes();
+        for s in &scan.strategies {
+            let usage = s.usage();
+            let over = max_strategy.is_some_and(|max| usage.total_bytes > max);
+            if all || over || self.disk.is_stale(usage.last_build) {
+                report.remove(Some(&s.id), &s.cache);
+            }
+        }
+        if all || self.disk.is_stale(scan.last_build()) {
+            report.remove(None, &scan.shared);
+        }
+
+        // Квота на всё: кэш удаляется целиком, следующие сборки будут с нуля
+        if let Some(max) = self.disk.max_total_bytes() {
+            let left = scan.total_bytes().saturating_sub(report.freed_bytes);
+            if left > max {
+                tracing::warn!("🧹 Strategies use {} MB of {} MB, removing all build caches", left / MB, max / MB);
+                for s in &scan.strategies {
+                    report.remove(Some(&s.id), &s.cache);
+                }
+                report.remove(None, &scan.shared);
+            }
+        }
+        Ok(report)
+    }
+
+    /// Квоты disk.max_strategy_mb (для id) и disk.max_total_mb с учётом extra
+    /// новых байт. Сначала удаляет кэш, ошибка - если не хватило и этого
+    fn ensure_quota(&self, id: Option<&str>, extra: u64) -> Result<()> {
+        let (max_strategy, max_total) = (self.disk.max_strategy_bytes(), self.disk.max_total_bytes());
+        if max_strategy.is_none() && max_total.is_none() {
+            return Ok(());
+        }
+        let over = |usage: &DiskUsage| {
+            let strategy = id
+                .and_then(|id| usage.strategies.iter().find(|s| s.id == id))
+                .map(|s| s.total_bytes + extra)
+                .filter(|used| max_strategy.is_some_and(|max| *used > max));
+            let total = Some(usage.total_bytes + extra).filter(|used| max_total.is_some_and(|max| *used > max));
+            (strategy, total)
+        };
+
+        let (strategy, total) = over(&self.disk_usage()?);
+        if strategy.is_none() && total.is_none() {
+            return Ok(());
+        }
+        if total.is_some() {
+            self.prune(true)?;
+        } else if let Some(id) = id {
+            let _cache = self.cache_lock.write().unwrap();
+            if let Some(s) = self.scan()?.strategies.iter().find(|s| s.id == id) {
+                PruneReport::default().remove(Some(id), &s.cache);
+            }
+        }
+
+        match over(&self.disk_usage()?) {
+            (Some(used), _) => anyhow::bail!(
+                "Disk quota exceeded: strategy '{}' needs {} MB of {} MB (compile.disk.max_strategy_mb)",
+                id.unwrap_or_default(), used.div_ceil(MB), self.disk.max_strategy_mb,
+            ),
+            (_, Some(used)) => anyhow::bail!(
+                "Disk quota exceeded: strategies need {} MB of {} MB (compile.disk.max_total_mb)",
+                used.div_ceil(MB), self.disk.max_total_mb,
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    fn scan(&self) -> Result<Scan> {
+        let mut strategies = Vec::new();
+        for entry in fs::read_dir(&self.base_path)? {
+            let entry = entry?;
+            let dir = entry.path();
+            let Some(id) = entry.file_name().to_str().map(String::from) else { continue };
+            if !dir.is_dir() {
+                continue;
+            }
+            let lib_path = self.lib_path_for(&dir, &id);
+            let mut scan = StrategyScan { id, source_bytes: 0, library: None, cache: Vec::new() };
+            for item in fs::read_dir(&dir)?.flatten() {
+                if item.file_name() != "target" {
+                    scan.source_bytes += disk::size_of(&item.path()).0;
+                    continue;
+                }
+                for target_item in fs::read_dir(item.path())?.flatten() {
+                    if target_item.path() != lib_path.parent().unwrap_or(&lib_path) {
+                        scan.cache.push(Artifact::scan(target_item.path()));
+                        continue;
+                    }
+                    for file in fs::read_dir(target_item.path())?.flatten() {
+                        if file.path() == lib_path {
+                            scan.library = Some(Artifact::scan(file.path()));
+                        } else {
+                            scan.cache.push(Artifact::scan(file.path()));
+                        }
+                    }
+                }
+            }
+            strategies.push(scan);
+        }
+
+        let ids: Vec<&str> = strategies.iter().map(|s| s.id.as_str()).collect();
+        let (owned, shared) = self.scan_shared(&ids);
+        for (id, artifacts) in owned {
+            if let Some(s) = strategies.iter_mut().find(|s| s.id == id) {
+                s.cache.extend(artifacts);
+            }
+        }
+        Ok(Scan { strategies, shared })
+    }
+
+    /// Общий target/: записи стратегий ids и остальное (зависимости)
+    fn scan_shared(&self, ids: &[&str]) -> (BTreeMap<String, Vec<Artifact>>, Vec<Artifact>) {
+        let mut owned: BTreeMap<String, Vec<Artifact>> = BTreeMap::new();
+        let mut other = Vec::new();
+        let Some(shared) = &self.shared_target else { return (owned, other) };
+
+        // target/{release,debug}/{файлы, deps/*, .fingerprint/*, build/*, incremental/*}
+        let mut entries = Vec::new();
+        for profile in fs::read_dir(shared).into_iter().flatten().flatten() {
+            if !profile.path().is_dir() {
+                other.push(Artifact::scan(profile.path()));
+                continue;
+            }
+            for item in fs::read_dir(profile.path()).into_iter().flatten().flatten() {
+                if item.path().is_dir() {
+                    entries.extend(fs::read_dir(item.path()).into_iter().flatten().flatten());
+                } else {
+                    entries.push(item);
+                }
+            }
+        }
+        for entry in entries {
+            let artifact = Artifact::scan(entry.path());
+            match disk::artifact_owner(&entry.file_name().to_string_lossy(), ids) {
+                Some(id) => owned.entry(id.to_string()).or_default().push(artifact),
+                None => other.push(artifact),
+            }
+        }
+        (owned, other)
+    }
+
     // ═══════════════════════════════════════════════════════════
     // HELPERS
     // ═══════════════════════════════════════════════════════════
     
-    fn copy_cargo_toml(&self, dir: &Path, id: &str) -> Result<()> {
-        let content = fs::read_to_string(self.templates_path.join("Cargo.toml"))?
-            .replace("{{STRATEGY_NAME}}", id);
+    fn copy_cargo_toml(&self, dir: &Path, id: &str, dependencies: &BTreeMap<String, String>) -> Result<()> {
+        let template = fs::read_to_string(self.templates_path.join("Cargo.toml"))?;
+        if !dependencies.is_empty() && !template.contains("{{EXTRA_DEPENDENCIES}}") {
+            anyhow::bail!("Cargo.toml template has no {{{{EXTRA_DEPENDENCIES}}}} placeholder");
+        }
+        let extra: String = dependencies.iter()
+            .map(|(name, version)| format!("{} = \"{}\"\n", name, version))
+            .collect();
+        let content = template
+            .replace("{{STRATEGY_NAME}}", id)
+            .replace("{{EXTRA_DEPENDENCIES}}\n", &extra)
+            .replace("{{EXTRA_DEPENDENCIES}}", &extra);
         fs::write(dir.join("Cargo.toml"), content)?;
+        fs::write(dir.join(DEPENDENCIES_FILE), serde_json::to_string_pretty(dependencies)?)?;
         Ok(())
     }
     
+    /// Сверяет запрошенные крейты с whitelist, пустая версия - первая разрешённая.
+    /// Имена и версии берутся только из whitelist, в Cargo.toml не попадает ничего чужого
+    fn resolve_dependencies(&self, requested: &BTreeMap<String, String>) -> Result<BTreeMap<String, String>> {
+        let mut resolved = BTreeMap::new();
+        for (name, version) in requested {
+            let Some((name, allowed)) = self.allowed_dependencies.get_key_value(name) else {
+                let names: Vec<&str> = self.allowed_dependencies.keys().map(String::as_str).collect();
+                anyhow::bail!("Crate '{}' is not allowed. Allowed: {}", name, names.join(", "));
+            };
+            let version = match version.trim() {
+                "" => allowed.first(),
+                v => allowed.iter().find(|a| a.as_str() == v),
+            };
+            let Some(version) = version else {
+                anyhow::bail!("Crate '{}': version must be one of {}", name, allowed.join(", "));
+            };
+            resolved.insert(name.clone(), version.clone());
+        }
+        Ok(resolved)
+    }
+    
     fn copy_types(&self, dir: &Path) -> Result<()> {
         fs::copy(
             self.templates_path.join("types.rs"),
@@ -243,28 +999,47 @@ impl StrategyStorage {
     }
     
     fn save_code(&self, dir: &Path, code: &str) -> Result<()> {
-        let full = format!("mod types;\nuse types::*;\n\n{}", code);
+        let full = format!("{}{}", CODE_PRELUDE, code);
         fs::write(dir.join("src/lib.rs"), full)?;
         Ok(())
     }
     
+    fn save_manifest(&self, dir: &Path, update: &ManifestUpdate) -> Result<StrategyManifest> {
+        let mut manifest = load_manifest(dir);
+        manifest.apply(update);
+        if manifest.created_at == 0 {
+            manifest.created_at = manifest.updated_at;
+        }
+        fs::write(dir.join(MANIFEST_FILE), serde_json::to_string_pretty(&manifest)?)?;
+        Ok(manifest)
+    }
+    
     fn load_code(&self, dir: &Path) -> Result<String> {
+        if is_prebuilt(dir) {
+            return Ok(String::new());
+        }
         let content = fs::read_to_string(dir.join("src/lib.rs"))?;
         Ok(content
-            .strip_prefix("mod types;\nuse types::*;\n\n")
+            .strip_prefix(CODE_PRELUDE)
             .unwrap_or(&content)
             .to_string())
     }
     
     fn lib_path_for(&self, dir: &Path, id: &str) -> PathBuf {
-        let name = if cfg!(target_os = "windows") {
-            format!("{}.dll", id)
-        } else if cfg!(target_os = "macos") {
-            format!("lib{}.dylib", id)
-        } else {
-            format!("lib{}.so", id)
-        };
-        dir.join("target/release").join(name)
+        dir.join("target/release").join(lib_name(id))
+    }
+    
+    /// Копирует библиотеку из общего target/ в папку стратегии. Через
+    /// временный файл и rename: запущенный инстанс держит старый файл
+    /// замапленным, перезапись по месту уронила бы его
+    fn uplift(&self, from: &Path, to: &Path) -> Result<()> {
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = to.with_extension("tmp");
+        fs::copy(from, &tmp).with_context(|| format!("Library not found after compilation: {:?}", from))?;
+        fs::rename(&tmp, to)?;
+        Ok(())
     }
     
     fn parse_errors(&self, stderr: &str) -> Vec<String> {
@@ -273,4 +1048,115 @@ impl StrategyStorage {
             .map(String::from)
             .collect()
     }
-}
\ No newline at end of file
+}
+
+fn load_manifest(dir: &Path) -> StrategyManifest {
+    fs::read_to_string(dir.join(MANIFEST_FILE))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// id становится именем папки и крейта: только [A-Za-z0-9_]
+fn check_id(id: &str) -> Result<()> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        anyhow::bail!("Invalid strategy id '{}'", id);
+    }
+    Ok(())
+}
+
+/// mtime файла в unix ms (None - файла нет)
+fn modified_ms(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let since_epoch = modified.duration_since(std::time::UNIX_EPOCH).ok()?;
+    Some(since_epoch.as_millis() as i64)
+}
+
+fn is_prebuilt(dir: &Path) -> bool {
+    dir.exists() && !dir.join("Cargo.toml").exists()
+}
+
+/// Загружает библиотеку стратегии и сверяет ABI. strict - отказывать и библиотекам
+/// без strategy_abi_version (собранным до её появления): для загрузок и запуска -
+/// всегда, без strict только чтение params_schema
+pub fn open_library(path: &Path, strict: bool) -> Result<Library> {
+    let lib = unsafe { Library::new(path) }
+        .with_context(|| format!("Failed to load {:?}", path))?;
+    
+    let version = unsafe { lib.get::<unsafe extern "C" fn() -> u32>(b"strategy_abi_version") }
+        .ok()
+        .map(|f| unsafe { f() });
+    match version {
+        Some(v) if v == STRATEGY_ABI_VERSION => {}
+        Some(v) => anyhow::bail!(
+            "Strategy ABI version {} does not match core version {}. Rebuild with the current types.rs",
+            v, STRATEGY_ABI_VERSION
+        ),
+        None if strict => anyhow::bail!(
+            "Library does not export strategy_abi_version. Recompile the strategy (POST /api/strategies/:id/compile) or build it with the current types.rs"
+        ),
+        None => tracing::warn!("⚠️ {:?} has no strategy_abi_version, recompile it", path),
+    }
+    
+    if unsafe { lib.get::<unsafe extern "C" fn()>(b"run") }.is_err() {
+        anyhow::bail!("Library does not export run()");
+    }
+    Ok(lib)
+}
+
+/// JSON из экспорта `params_schema() -> *const c_char` (нуль-терминированная строка)
+fn library_schema(path: &Path) -> Result<Option<Value>> {
+    let lib = open_library(path, false)?;
+    let Ok(func) = (unsafe { lib.get::<unsafe extern "C" fn() -> *const c_char>(b"params_schema") }) else {
+        return Ok(None);
+    };
+    let ptr = unsafe { func() };
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    let json = unsafe { CStr::from_ptr(ptr) }.to_str()?;
+    let schema: Value = serde_json::from_str(json).context("params_schema() returned invalid JSON")?;
+    schema::check_schema(&schema).context("params_schema()")?;
+    Ok(Some(schema))
+}
+
+fn lib_name(id: &str) -> String {
+    if cfg!(target_os = "windows") {
+        format!("{}.dll", id)
+    } else if cfg!(target_os = "macos") {
+        format!("lib{}.dylib", id)
+    } else {
+        format!("lib{}.so", id)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ПРОВЕРКА КОДА
+// ═══════════════════════════════════════════════════════════
+
+/// Отклоняет код с захардкоженными ключами: длинная строка из букв и цифр
+/// внутри литерала. Ключи хранятся в keystore, стратегия получает alias
+/// через params "account".
+fn check_secrets(code: &str) -> Result<()> {
+    for (n, line) in code.lines().enumerate() {
+        let code_part = line.split("//").next().unwrap_or(line);
+        
+        for literal in code_part.split('"').skip(1).step_by(2) {
+            let suspicious = literal
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .any(|token| {
+                    token.len() >= MIN_SECRET_LEN
+                        && token.chars().any(|c| c.is_ascii_digit())
+                        && token.chars().any(|c| c.is_ascii_alphabetic())
+                });
+            
+            if suspicious {
+                anyhow::bail!(
+                    "Line {}: looks like a hardcoded API key. Store keys via POST /api/keys and pass \"account\" in params",
+                    n + 1
+                );
+            }
+        }
+    }
+    Ok(())
+}
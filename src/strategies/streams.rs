@@ -0,0 +1,106 @@
+// src/strategies/streams.rs
+
+use dashmap::DashMap;
+use std::collections::BTreeSet;
+use std::os::raw::c_char;
+use std::ffi::CStr;
+use std::sync::{Arc, LazyLock, OnceLock};
+
+use crate::exchange_data::{ExchangeData, StreamKind};
+use crate::strategies::logs;
+use crate::strategies::order::current_instance;
+use crate::symbols;
+
+/// Потоков, которые инстанс может держать сам
+pub const MAX_STREAMS: usize = 32;
+
+// ═══════════════════════════════════════════════════════════
+// ПОДПИСКИ СТРАТЕГИЙ
+// ═══════════════════════════════════════════════════════════
+
+// Стратегия, которая ходит по символам (сканер), подписывается на нужные
+// потоки сама. Подписки держатся в ExchangeData от имени instance_id
+// и снимаются, когда инстанс останавливается.
+
+static MARKET: OnceLock<Arc<ExchangeData>> = OnceLock::new();
+
+/// instance_id -> (symbol, поток). Есть только у live/paper инстансов:
+/// на replay и dry run события приходят не с биржи
+static INSTANCES: LazyLock<DashMap<String, BTreeSet<(String, StreamKind)>>> = LazyLock::new(DashMap::new);
+
+pub fn init(market: Arc<ExchangeData>) {
+    MARKET.set(market).ok();
+}
+
+pub fn register(instance_id: &str) {
+    INSTANCES.insert(instance_id.to_string(), BTreeSet::new());
+}
+
+/// Снимает все подписки инстанса
+pub fn unregister(instance_id: &str) {
+    let Some((_, held)) = INSTANCES.remove(instance_id) else { return };
+    if let (Some(market), false) = (MARKET.get(), held.is_empty()) {
+        market.release_all(instance_id);
+        tracing::info!("📡 Released {} streams of '{}'", held.len(), instance_id);
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// FFI
+// ═══════════════════════════════════════════════════════════
+
+/// Подписка на поток символа: stream_type 0 = bookTicker, 1 = trade.
+/// События приходят в общий канал стратегии. false - инстанс не live,
+/// неизвестный поток или символ, превышен MAX_STREAMS
+pub unsafe extern "C" fn subscribe_stream(symbol: *const c_char, stream_type: u8) -> bool {
+    let Some((instance_id, symbol, stream)) = parse(symbol, stream_type) else { return false };
+    let Some(market) = MARKET.get() else { return false };
+    let Some(mut held) = INSTANCES.get_mut(&instance_id) else { return false };
+
+    let key = (symbol, stream);
+    if held.contains(&key) {
+        return true;
+    }
+    if held.len() >= MAX_STREAMS {
+        logs::push(&instance_id, logs::LOG_WARN, &format!("Stream subscription rejected: max {} streams", MAX_STREAMS));
+        return false;
+    }
+    if let Err(e) = symbols::check(&key.0) {
+        logs::push(&instance_id, logs::LOG_WARN, &format!("Stream subscription rejected: {}", e));
+        return false;
+    }
+    match market.acquire(&instance_id, &key.0, stream) {
+        Ok(()) => {
+            held.insert(key);
+            true
+        }
+        Err(e) => {
+            tracing::warn!("📡 '{}' failed to subscribe {}: {}", instance_id, key.0, e);
+            false
+        }
+    }
+}
+
+/// false - инстанс не держал эту подписку
+pub unsafe extern "C" fn unsubscribe_stream(symbol: *const c_char, stream_type: u8) -> bool {
+    let Some((instance_id, symbol, stream)) = parse(symbol, stream_type) else { return false };
+    let Some(market) = MARKET.get() else { return false };
+    let Some(mut held) = INSTANCES.get_mut(&instance_id) else { return false };
+
+    if !held.remove(&(symbol.clone(), stream)) {
+        return false;
+    }
+    market.release(&instance_id, &symbol, stream).unwrap_or(false)
+}
+
+unsafe fn parse(symbol: *const c_char, stream_type: u8) -> Option<(String, String, StreamKind)> {
+    let instance_id = current_instance()?;
+    if symbol.is_null() {
+        return None;
+    }
+    let symbol = CStr::from_ptr(symbol).to_str().ok()?.trim().to_uppercase();
+    if symbol.is_empty() || !symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    Some((instance_id, symbol, StreamKind::from_u8(stream_type)?))
+}
@@ -0,0 +1,159 @@
+// src/strategies/timers.rs
+
+use crossbeam::channel::Sender;
+use dashmap::DashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, LazyLock, Mutex};
+use std::time::Duration;
+
+use crate::ffi_types::{CEvent, CEventData, CTimer, EVENT_TIMER};
+use crate::latency;
+use crate::strategies::logs;
+
+/// Взведённых таймеров на инстанс
+pub const MAX_TIMERS: usize = 64;
+
+// ═══════════════════════════════════════════════════════════
+// ТАЙМЕРЫ ИНСТАНСОВ
+// ═══════════════════════════════════════════════════════════
+
+// Стратегия заводит таймер и продолжает разбирать события; в срок
+// в её канал приходит EVENT_TIMER. Повторный schedule с тем же timer_id
+// переносит таймер, cancel - снимает.
+
+struct InstanceTimers {
+    tx: Sender<CEvent>,
+    /// Replay: таймеры срабатывают по часам записи (take_due из replay::run)
+    virtual_clock: bool,
+    /// timer_id -> due_ns
+    armed: Mutex<HashMap<u64, u64>>,
+    fired: AtomicU64,
+}
+
+static INSTANCES: LazyLock<DashMap<String, Arc<InstanceTimers>>> = LazyLock::new(DashMap::new);
+
+/// Очередь таймеров реального времени по всем инстансам: (due_ns, instance_id, timer_id)
+struct Wheel {
+    heap: Mutex<BinaryHeap<Reverse<(u64, String, u64)>>>,
+    wake: Condvar,
+}
+
+static WHEEL: LazyLock<Arc<Wheel>> = LazyLock::new(|| {
+    let wheel = Arc::new(Wheel { heap: Mutex::new(BinaryHeap::new()), wake: Condvar::new() });
+    let driver = wheel.clone();
+    std::thread::Builder::new()
+        .name("strategy-timers".to_string())
+        .spawn(move || drive(&driver))
+        .expect("Failed to spawn timer thread");
+    wheel
+});
+
+/// Канал инстанса для таймеров. virtual_clock - инстанс на replay
+pub fn register(instance_id: &str, tx: Sender<CEvent>, virtual_clock: bool) {
+    INSTANCES.insert(instance_id.to_string(), Arc::new(InstanceTimers {
+        tx,
+        virtual_clock,
+        armed: Mutex::new(HashMap::new()),
+        fired: AtomicU64::new(0),
+    }));
+}
+
+/// После выхода из run(): взведённые таймеры больше не сработают
+pub fn unregister(instance_id: &str) {
+    INSTANCES.remove(instance_id);
+}
+
+/// Взводит (или переносит) таймер на due_ns по часам стратегии.
+/// false - инстанс без таймеров или превышен MAX_TIMERS
+pub fn schedule(instance_id: &str, timer_id: u64, due_ns: u64) -> bool {
+    let Some(timers) = INSTANCES.get(instance_id).map(|t| t.clone()) else { return false };
+    {
+        let mut armed = timers.armed.lock().unwrap();
+        if !armed.contains_key(&timer_id) && armed.len() >= MAX_TIMERS {
+            return false;
+        }
+        armed.insert(timer_id, due_ns);
+    }
+    if !timers.virtual_clock {
+        WHEEL.heap.lock().unwrap().push(Reverse((due_ns, instance_id.to_string(), timer_id)));
+        WHEEL.wake.notify_one();
+    }
+    true
+}
+
+/// false - таймер не был взведён (или уже сработал)
+pub fn cancel(instance_id: &str, timer_id: u64) -> bool {
+    INSTANCES
+        .get(instance_id)
+        .is_some_and(|t| t.armed.lock().unwrap().remove(&timer_id).is_some())
+}
+
+/// Replay: снимает таймеры со сроком <= until_ns, по возрастанию срока.
+/// fired_ns = due_ns - часы записи перед отправкой выставляет replay
+pub fn take_due(instance_id: &str, until_ns: u64) -> Vec<CEvent> {
+    let Some(timers) = INSTANCES.get(instance_id).map(|t| t.clone()) else { return Vec::new() };
+    let mut due: Vec<(u64, u64)> = {
+        let mut armed = timers.armed.lock().unwrap();
+        let due: Vec<_> = armed.iter().filter(|(_, d)| **d <= until_ns).map(|(id, d)| (*d, *id)).collect();
+        for (_, id) in &due {
+            armed.remove(id);
+        }
+        due
+    };
+    due.sort_unstable();
+    due.into_iter().map(|(due_ns, timer_id)| event(&timers, timer_id, due_ns, due_ns)).collect()
+}
+
+fn event(timers: &InstanceTimers, timer_id: u64, due_ns: u64, fired_ns: u64) -> CEvent {
+    CEvent {
+        event_type: EVENT_TIMER,
+        data: CEventData {
+            timer: CTimer { timer_id, due_ns: due_ns as i64, fired_ns: fired_ns as i64 },
+        },
+        received_at_ns: fired_ns,
+        seq: timers.fired.fetch_add(1, Ordering::Relaxed) + 1,
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ПОТОК ТАЙМЕРОВ
+// ═══════════════════════════════════════════════════════════
+
+fn drive(wheel: &Wheel) {
+    let mut heap = wheel.heap.lock().unwrap();
+    loop {
+        let now = latency::now_ns();
+        let wait = match heap.peek() {
+            None => None,
+            Some(Reverse((due, _, _))) if *due > now => Some(Duration::from_nanos(due - now)),
+            Some(_) => {
+                let Some(Reverse((due, instance_id, timer_id))) = heap.pop() else { continue };
+                drop(heap);
+                fire(&instance_id, timer_id, due, now);
+                heap = wheel.heap.lock().unwrap();
+                continue;
+            }
+        };
+        heap = match wait {
+            Some(wait) => wheel.wake.wait_timeout(heap, wait).unwrap().0,
+            None => wheel.wake.wait(heap).unwrap(),
+        };
+    }
+}
+
+fn fire(instance_id: &str, timer_id: u64, due_ns: u64, now_ns: u64) {
+    let Some(timers) = INSTANCES.get(instance_id).map(|t| t.clone()) else { return };
+    // Таймер сняли или перенесли - запись в очереди устарела
+    {
+        let mut armed = timers.armed.lock().unwrap();
+        if armed.get(&timer_id) != Some(&due_ns) {
+            return;
+        }
+        armed.remove(&timer_id);
+    }
+    if timers.tx.try_send(event(&timers, timer_id, due_ns, now_ns)).is_err() {
+        logs::push(instance_id, logs::LOG_WARN, &format!("Timer {} dropped: channel full", timer_id));
+    }
+}
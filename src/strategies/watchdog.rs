@@ -0,0 +1,91 @@
+// src/strategies/watchdog.rs
+
+use serde::{Deserialize, Serialize};
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+/// Что делать с зависшим инстансом
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StallAction {
+    /// Только алерт и пометка stalled
+    #[default]
+    Alert,
+    /// Остановить (поток зависшей стратегии при этом не завершится)
+    Stop,
+    /// Остановить и запустить заново с теми же параметрами. Если поток
+    /// не вышел за stop_timeout_ms, инстанс в карантине и запуск не удастся
+    Restart,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WatchdogConfig {
+    /// Сколько секунд стратегия может не забирать события из очереди
+    /// (и не звать heartbeat), прежде чем считается зависшей. 0 - выключено
+    pub stall_secs: u64,
+    pub action: StallAction,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            stall_secs: 30,
+            action: StallAction::Alert,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// СОСТОЯНИЕ ИНСТАНСА
+// ═══════════════════════════════════════════════════════════
+
+/// Что изменилось после очередной проверки
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Stalled,
+    Recovered,
+}
+
+/// Пульс стратегии: события уходят из её очереди или она сама зовёт heartbeat.
+/// Зависшей считается, только пока в очереди есть события - тихий рынок
+/// или простой без подписки не тревога
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct WatchdogState {
+    pub stalled: bool,
+    /// Unix ms, с которого стратегия не подаёт признаков жизни
+    pub stalled_since: Option<i64>,
+    /// (забрано событий, heartbeat'ов) на прошлой проверке
+    #[serde(skip)]
+    pulse: (u64, u64),
+    /// Unix ms последнего признака жизни (или пустой очереди)
+    #[serde(skip)]
+    alive_at: i64,
+}
+
+impl WatchdogState {
+    /// consumed - сколько событий стратегия забрала из очереди с запуска,
+    /// queued - сколько ждёт сейчас, beats - вызовы heartbeat
+    pub fn observe(&mut self, consumed: u64, queued: usize, beats: u64, now: i64, stall_ms: i64) -> Option<Verdict> {
+        let pulse = (consumed, beats);
+        if queued == 0 || pulse != self.pulse || self.alive_at == 0 {
+            self.pulse = pulse;
+            self.alive_at = now;
+            if self.stalled {
+                self.stalled = false;
+                self.stalled_since = None;
+                return Some(Verdict::Recovered);
+            }
+            return None;
+        }
+
+        if !self.stalled && now - self.alive_at >= stall_ms {
+            self.stalled = true;
+            self.stalled_since = Some(self.alive_at);
+            return Some(Verdict::Stalled);
+        }
+        None
+    }
+}
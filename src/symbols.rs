@@ -0,0 +1,214 @@
+// src/symbols.rs
+
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+
+use crate::decimal;
+
+const EXCHANGE_INFO_URL: &str = "https://fapi.binance.com/fapi/v1/exchangeInfo";
+
+/// Листинги и делистинги редки: раз в час достаточно
+const REFRESH_SECS: u64 = 3600;
+/// Пока список не загружен ни разу, пробуем чаще
+const RETRY_SECS: u64 = 30;
+
+/// Подсказок в ошибке
+const MAX_SUGGESTIONS: usize = 5;
+/// Опечатка - не больше стольких правок
+const MAX_DISTANCE: usize = 2;
+
+// ═══════════════════════════════════════════════════════════
+// СИМВОЛЫ БИРЖИ
+// ═══════════════════════════════════════════════════════════
+//
+// Подписка на "BTCUSTD" биржей принимается молча, и стратегия просто не
+// получает данных. Поэтому символы подписок и запусков сверяются со списком
+// из exchangeInfo. Пока список не загружен (нет сети на старте), проверка
+// пропускает всё: не знать символы - не повод не запускаться.
+
+/// SYMBOL -> status из exchangeInfo (TRADING, SETTLING, PENDING_TRADING, ...)
+static SYMBOLS: LazyLock<DashMap<String, String>> = LazyLock::new(DashMap::new);
+
+/// SYMBOL -> знаков после запятой в tickSize/stepSize (decimal::price/qty)
+static PRECISION: LazyLock<DashMap<String, Precision>> = LazyLock::new(DashMap::new);
+
+/// Unix ms последней загрузки, 0 - не загружали
+static LOADED_AT: AtomicI64 = AtomicI64::new(0);
+
+/// Точность цены и количества символа из фильтров exchangeInfo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Precision {
+    /// Знаков в tickSize (PRICE_FILTER)
+    pub price: usize,
+    /// Знаков в stepSize (LOT_SIZE)
+    pub qty: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum SymbolError {
+    Unknown { symbol: String, suggestions: Vec<String> },
+    NotTrading { symbol: String, status: String },
+}
+
+impl std::fmt::Display for SymbolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unknown { symbol, suggestions } if suggestions.is_empty() => {
+                write!(f, "Unknown symbol '{}'", symbol)
+            }
+            Self::Unknown { symbol, suggestions } => {
+                write!(f, "Unknown symbol '{}', did you mean: {}", symbol, suggestions.join(", "))
+            }
+            Self::NotTrading { symbol, status } => write!(f, "Symbol '{}' is not trading (status {})", symbol, status),
+        }
+    }
+}
+
+impl std::error::Error for SymbolError {}
+
+/// Символ есть на бирже и торгуется. Регистр не важен
+pub fn check(symbol: &str) -> Result<(), SymbolError> {
+    if LOADED_AT.load(Ordering::Relaxed) == 0 {
+        return Ok(());
+    }
+    let symbol = symbol.to_uppercase();
+    match SYMBOLS.get(&symbol) {
+        Some(status) if status.as_str() == "TRADING" => Ok(()),
+        Some(status) => Err(SymbolError::NotTrading { symbol, status: status.clone() }),
+        None => {
+            let suggestions = suggest(&symbol);
+            Err(SymbolError::Unknown { symbol, suggestions })
+        }
+    }
+}
+
+/// Точность символа или None, если exchangeInfo ещё не загружен или
+/// символа в нём нет. Зовётся при сборке каждого ордера - без аллокаций,
+/// пока символ уже в верхнем регистре
+pub fn precision(symbol: &str) -> Option<Precision> {
+    if symbol.bytes().any(|b| b.is_ascii_lowercase()) {
+        return PRECISION.get(&symbol.to_ascii_uppercase()).map(|p| *p);
+    }
+    PRECISION.get(symbol).map(|p| *p)
+}
+
+/// Похожие торгуемые символы: сначала с меньшим числом правок
+fn suggest(symbol: &str) -> Vec<String> {
+    let mut close: Vec<(usize, String)> = SYMBOLS
+        .iter()
+        .filter(|e| e.value() == "TRADING")
+        .filter_map(|e| {
+            let candidate = e.key();
+            let distance = distance(symbol, candidate);
+            // "BTC" -> BTCUSDT, BTCUSDC: префикс тоже считаем близким
+            let prefix = symbol.len() >= 3 && candidate.starts_with(symbol);
+            (distance <= MAX_DISTANCE || prefix).then(|| (distance, candidate.clone()))
+        })
+        .collect();
+    close.sort();
+    close.into_iter().take(MAX_SUGGESTIONS).map(|(_, s)| s).collect()
+}
+
+/// Расстояние Дамерау-Левенштейна (перестановка соседних букв - одна правка)
+fn distance(a: &str, b: &str) -> usize {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    let mut rows = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut d = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d = d.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = d;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+// ═══════════════════════════════════════════════════════════
+// ОБНОВЛЕНИЕ
+// ═══════════════════════════════════════════════════════════
+
+/// Фоновая загрузка списка символов из GET /fapi/v1/exchangeInfo
+pub fn spawn_refresh() {
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+        loop {
+            let wait = match refresh(&client).await {
+                Ok(n) => {
+                    tracing::info!("🔤 Exchange symbols loaded: {}", n);
+                    REFRESH_SECS
+                }
+                Err(e) => {
+                    tracing::warn!("🔤 Exchange symbols refresh failed: {}", e);
+                    if LOADED_AT.load(Ordering::Relaxed) == 0 { RETRY_SECS } else { REFRESH_SECS }
+                }
+            };
+            tokio::time::sleep(Duration::from_secs(wait)).await;
+        }
+    });
+}
+
+async fn refresh(client: &reqwest::Client) -> anyhow::Result<usize> {
+    let info: serde_json::Value = client
+        .get(EXCHANGE_INFO_URL)
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let Some(items) = info["symbols"].as_array() else {
+        anyhow::bail!("Unexpected response from {}", EXCHANGE_INFO_URL);
+    };
+    let fresh: HashMap<String, (String, Option<Precision>)> = items
+        .iter()
+        .filter_map(|item| {
+            let symbol = item["symbol"].as_str()?;
+            let status = item["status"].as_str().unwrap_or("UNKNOWN");
+            Some((symbol.to_string(), (status.to_string(), parse_precision(item))))
+        })
+        .collect();
+    if fresh.is_empty() {
+        anyhow::bail!("exchangeInfo has no symbols");
+    }
+
+    // Делистинг из exchangeInfo пропадает
+    SYMBOLS.retain(|symbol, _| fresh.contains_key(symbol));
+    PRECISION.retain(|symbol, _| fresh.contains_key(symbol));
+    for (symbol, (status, precision)) in fresh {
+        if let Some(precision) = precision {
+            PRECISION.insert(symbol.clone(), precision);
+        }
+        SYMBOLS.insert(symbol, status);
+    }
+    LOADED_AT.store(chrono::Utc::now().timestamp_millis(), Ordering::Relaxed);
+    Ok(SYMBOLS.len())
+}
+
+/// tickSize из PRICE_FILTER и stepSize из LOT_SIZE символа exchangeInfo
+fn parse_precision(item: &serde_json::Value) -> Option<Precision> {
+    let filters = item["filters"].as_array()?;
+    let filter = |kind: &str, field: &str| {
+        filters
+            .iter()
+            .find(|f| f["filterType"] == kind)
+            .and_then(|f| f[field].as_str())
+            .map(decimal::step_decimals)
+    };
+    Some(Precision {
+        price: filter("PRICE_FILTER", "tickSize")?,
+        qty: filter("LOT_SIZE", "stepSize")?,
+    })
+}
@@ -0,0 +1,207 @@
+// src/time_sync.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::alerts::{self, AlertLevel};
+use crate::exchange_trade::ExchangeTrade;
+
+/// Сколько точек offset храним для оценки дрейфа
+const HISTORY_LEN: usize = 288;
+
+/// Offset, который ставим если самая первая синхронизация не удалась:
+/// timestamp в прошлом Binance принимает (в пределах recvWindow), в будущем - нет (-1021)
+const FALLBACK_OFFSET_MS: i64 = -1000;
+
+// ═══════════════════════════════════════════════════════════
+// КОНФИГ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TimeSyncConfig {
+    /// Период пересинхронизации (0 = только при старте)
+    pub interval_secs: u64,
+    /// Замеров на одну синхронизацию, берётся медиана
+    pub samples: usize,
+    /// Алерт, если offset за одну синхронизацию сдвинулся больше чем на столько
+    pub drift_alert_ms: i64,
+}
+
+impl Default for TimeSyncConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 300,
+            samples: 5,
+            drift_alert_ms: 250,
+        }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// СОСТОЯНИЕ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct OffsetPoint {
+    /// Unix ms
+    pub time: i64,
+    pub offset_ms: i64,
+    pub rtt_ms: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TimeStatus {
+    /// Текущий offset, применяемый к подписи запросов
+    pub offset_ms: i64,
+    /// RTT медианного замера последней успешной синхронизации
+    pub rtt_ms: Option<i64>,
+    /// Unix ms последней попытки
+    pub last_sync: Option<i64>,
+    /// Unix ms последней успешной синхронизации
+    pub last_success: Option<i64>,
+    pub last_error: Option<String>,
+    pub syncs: u64,
+    pub failures: u64,
+    /// Изменение offset при последней синхронизации
+    pub last_change_ms: Option<i64>,
+    /// Дрейф локальных часов относительно Binance по истории замеров
+    pub drift_ms_per_hour: Option<f64>,
+    pub history: Vec<OffsetPoint>,
+}
+
+#[derive(Default)]
+struct State {
+    status: TimeStatus,
+    history: VecDeque<OffsetPoint>,
+}
+
+// ═══════════════════════════════════════════════════════════
+// TIME SYNC
+// ═══════════════════════════════════════════════════════════
+
+/// Периодическая синхронизация времени с Binance.
+///
+/// Дрейф часов за несколько дней ломает подпись (-1021) - ровно тогда,
+/// когда funding-стратегиям нужно успеть в нужную секунду.
+pub struct TimeSync {
+    trade: Arc<ExchangeTrade>,
+    config: TimeSyncConfig,
+    state: Mutex<State>,
+}
+
+impl TimeSync {
+    /// Первая синхронизация (блокирует старт) + фоновая пересинхронизация
+    pub async fn start(trade: Arc<ExchangeTrade>, config: TimeSyncConfig) -> Arc<Self> {
+        let sync = Arc::new(Self {
+            trade,
+            config,
+            state: Mutex::new(State::default()),
+        });
+
+        match sync.sync().await {
+            Ok(offset) if offset.abs() > 1000 => tracing::warn!("⚠️ Large time offset: {}ms", offset),
+            Ok(offset) => tracing::info!("✅ Time offset: {}ms", offset),
+            Err(e) => {
+                tracing::error!("❌ Time sync failed: {}", e);
+                sync.trade.set_time_offset(FALLBACK_OFFSET_MS);
+            }
+        }
+
+        if sync.config.interval_secs > 0 {
+            let sync = sync.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(sync.config.interval_secs));
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = sync.sync().await {
+                        tracing::warn!("⏰ Time re-sync failed: {}", e);
+                    }
+                }
+            });
+        }
+
+        sync
+    }
+
+    /// Синхронизирует сейчас, возвращает новый offset
+    pub async fn sync(&self) -> anyhow::Result<i64> {
+        let result = self.trade.sync_time(self.config.samples).await;
+        let now = chrono::Utc::now().timestamp_millis();
+
+        let mut state = self.state.lock().unwrap();
+        state.status.last_sync = Some(now);
+
+        let sample = match result {
+            Ok(s) => s,
+            Err(e) => {
+                state.status.failures += 1;
+                state.status.last_error = Some(e.to_string());
+                return Err(e);
+            }
+        };
+
+        let change = state.history.back().map(|p| sample.offset_ms - p.offset_ms);
+
+        if state.history.len() >= HISTORY_LEN {
+            state.history.pop_front();
+        }
+        state.history.push_back(OffsetPoint {
+            time: now,
+            offset_ms: sample.offset_ms,
+            rtt_ms: sample.rtt_ms,
+        });
+
+        let drift = drift_per_hour(&state.history);
+        let status = &mut state.status;
+        status.rtt_ms = Some(sample.rtt_ms);
+        status.last_success = Some(now);
+        status.last_error = None;
+        status.syncs += 1;
+        status.last_change_ms = change;
+        status.drift_ms_per_hour = drift;
+        drop(state);
+
+        if let Some(change) = change.filter(|c| c.abs() > self.config.drift_alert_ms) {
+            alerts::emit(
+                AlertLevel::Warning,
+                "time",
+                format!("Clock offset jumped by {}ms (now {}ms)", change, sample.offset_ms),
+            );
+        }
+
+        Ok(sample.offset_ms)
+    }
+
+    pub fn status(&self) -> TimeStatus {
+        let state = self.state.lock().unwrap();
+        let mut status = state.status.clone();
+        status.offset_ms = self.trade.get_time_offset();
+        status.history = state.history.iter().copied().collect();
+        status
+    }
+}
+
+/// Наклон offset(t) методом наименьших квадратов, ms/час
+fn drift_per_hour(history: &VecDeque<OffsetPoint>) -> Option<f64> {
+    if history.len() < 2 {
+        return None;
+    }
+
+    let t0 = history.front()?.time;
+    let n = history.len() as f64;
+    let hours: Vec<f64> = history.iter().map(|p| (p.time - t0) as f64 / 3_600_000.0).collect();
+    let mean_t = hours.iter().sum::<f64>() / n;
+    let mean_o = history.iter().map(|p| p.offset_ms as f64).sum::<f64>() / n;
+
+    let (mut cov, mut var) = (0.0, 0.0);
+    for (t, p) in hours.iter().zip(history) {
+        cov += (t - mean_t) * (p.offset_ms as f64 - mean_o);
+        var += (t - mean_t).powi(2);
+    }
+
+    (var > 0.0).then(|| cov / var)
+}
@@ -0,0 +1,840 @@
+// src/user_data.rs
+
+use dashmap::DashMap;
+use futures_util::{SinkExt, StreamExt};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{
+    atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering},
+    Arc, Mutex,
+};
+use std::time::SystemTime;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout, Duration, Instant};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::alerts::{self, AlertLevel};
+use crate::net;
+use crate::positions::{self, PositionInfo};
+use crate::raw_capture;
+use crate::strategies::order::time_offset_ms;
+use crate::fanout;
+use crate::ffi_types::{
+    pack_str, CAccountUpdate, CEvent, CEventData, COrderUpdate, CUnknownUserData, Sequencer,
+    EVENT_ACCOUNT_UPDATE, EVENT_ORDER_UPDATE, EVENT_USER_DATA_UNKNOWN,
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+// ═══════════════════════════════════════════════════════════
+// RAW ТИПЫ (десериализация JSON user data stream)
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Deserialize)]
+struct RawOrderEnvelope {
+    #[serde(rename = "E")]
+    event_time: i64,
+    #[serde(rename = "o")]
+    order: RawOrderUpdate,
+}
+
+/// Поле "o" события ORDER_TRADE_UPDATE
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawOrderUpdate {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "c")]
+    pub client_order_id: String,
+    #[serde(rename = "S")]
+    pub side: String,
+    #[serde(rename = "o")]
+    pub order_type: String,
+    #[serde(rename = "q")]
+    pub orig_qty: String,
+    #[serde(rename = "p")]
+    pub price: String,
+    #[serde(rename = "ap", default)]
+    pub avg_price: String,
+    #[serde(rename = "x")]
+    pub exec_type: String,
+    #[serde(rename = "X")]
+    pub status: String,
+    #[serde(rename = "i")]
+    pub order_id: i64,
+    #[serde(rename = "l")]
+    pub last_filled_qty: String,
+    #[serde(rename = "z")]
+    pub cum_filled_qty: String,
+    #[serde(rename = "L")]
+    pub last_filled_price: String,
+    #[serde(rename = "N", default)]
+    pub commission_asset: Option<String>,
+    #[serde(rename = "n", default)]
+    pub commission: Option<String>,
+    #[serde(rename = "T")]
+    pub trade_time: i64,
+    /// Id сделки (0 - не исполнение)
+    #[serde(rename = "t", default)]
+    pub trade_id: i64,
+    #[serde(rename = "R", default)]
+    pub reduce_only: bool,
+    #[serde(rename = "rp", default)]
+    pub realized_pnl: String,
+    #[serde(skip)]
+    pub event_time: i64,
+}
+
+impl RawOrderUpdate {
+    pub fn last_qty(&self) -> f64 {
+        self.last_filled_qty.parse().unwrap_or(0.0)
+    }
+
+    pub fn last_price(&self) -> f64 {
+        self.last_filled_price.parse().unwrap_or(0.0)
+    }
+
+    pub fn realized(&self) -> f64 {
+        self.realized_pnl.parse().unwrap_or(0.0)
+    }
+
+    /// Комиссия в USDT (комиссии в других активах не учитываем)
+    pub fn fee_usdt(&self) -> f64 {
+        match (&self.commission_asset, &self.commission) {
+            (Some(asset), Some(n)) if asset == "USDT" => n.parse().unwrap_or(0.0),
+            _ => 0.0,
+        }
+    }
+
+    pub fn is_fill(&self) -> bool {
+        self.exec_type == "TRADE"
+    }
+
+    /// Ордер больше не висит в стакане
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self.status.as_str(),
+            "FILLED" | "CANCELED" | "EXPIRED" | "REJECTED" | "EXPIRED_IN_MATCH"
+        )
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawAccountEnvelope {
+    #[serde(rename = "E")]
+    event_time: i64,
+    #[serde(rename = "a")]
+    account: RawAccountUpdate,
+}
+
+/// Поле "a" события ACCOUNT_UPDATE
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawAccountUpdate {
+    #[serde(rename = "m")]
+    pub reason: String,
+    #[serde(rename = "B", default)]
+    pub balances: Vec<RawBalance>,
+    #[serde(rename = "P", default)]
+    pub positions: Vec<RawPosition>,
+    #[serde(skip)]
+    pub event_time: i64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawBalance {
+    #[serde(rename = "a")]
+    pub asset: String,
+    #[serde(rename = "wb")]
+    pub wallet_balance: String,
+    #[serde(rename = "bc", default)]
+    pub balance_change: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RawPosition {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "pa")]
+    pub position_amt: String,
+    #[serde(rename = "ep")]
+    pub entry_price: String,
+    #[serde(rename = "up", default)]
+    pub unrealized_pnl: String,
+    #[serde(rename = "ps", default)]
+    pub position_side: String,
+}
+
+/// Позиция из REST GET /fapi/v2/positionRisk
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawPositionRisk {
+    pub symbol: String,
+    pub position_amt: String,
+    pub entry_price: String,
+    #[serde(rename = "unRealizedProfit", default)]
+    pub unrealized_pnl: String,
+    #[serde(default)]
+    pub position_side: String,
+    /// Время последнего изменения позиции (ms), 0 - не менялась
+    #[serde(default)]
+    pub update_time: i64,
+}
+
+// ═══════════════════════════════════════════════════════════
+// СОБЫТИЯ
+// ═══════════════════════════════════════════════════════════
+
+#[derive(Debug, Clone)]
+pub enum UserDataEvent {
+    OrderTradeUpdate(Box<RawOrderUpdate>),
+    AccountUpdate(RawAccountUpdate),
+    ListenKeyExpired,
+    /// Остальные события: имя из "e", JSON как пришёл и "E" (unix ms)
+    Unknown { name: String, raw: String, time: i64 },
+}
+
+/// Событие user data с привязкой к аккаунту
+#[derive(Debug, Clone)]
+pub struct UserDataUpdate {
+    pub api_key: Arc<str>,
+    pub event: UserDataEvent,
+}
+
+/// Короткий идентификатор ключа (первые 8 символов) - безопасно показывать в API и логах
+pub fn key_id(api_key: &str) -> String {
+    api_key.chars().take(8).collect()
+}
+
+// ═══════════════════════════════════════════════════════════
+// МЕНЕДЖЕР
+// ═══════════════════════════════════════════════════════════
+
+/// Binance требует keepalive раз в 60 минут, шлём каждые 30
+const KEEPALIVE_EVERY: Duration = Duration::from_secs(30 * 60);
+
+/// После неудачного keepalive повторяем чаще, пока ключ ещё жив
+const KEEPALIVE_RETRY: Duration = Duration::from_secs(60);
+
+/// Столько неудачных keepalive подряд - ключ считаем потерянным:
+/// берём новый и переподключаемся, не дожидаясь, пока WS умрёт сам
+const MAX_KEEPALIVE_FAILURES: u64 = 3;
+
+struct StreamState {
+    api_key: Arc<str>,
+    connected: AtomicBool,
+    events: AtomicU64,
+    started_at: i64,
+    /// Unix s, 0 - ещё не было
+    listen_key_at: AtomicI64,
+    last_keepalive_at: AtomicI64,
+    /// Неудачных keepalive подряд
+    keepalive_failures: AtomicU64,
+    /// listenKeyExpired от биржи
+    expirations: AtomicU64,
+    /// Переподключений с новым ключом (истечение или отказ keepalive)
+    resubscribes: AtomicU64,
+    order_updates: AtomicU64,
+    account_updates: AtomicU64,
+    other_events: AtomicU64,
+    account: Mutex<AccountView>,
+}
+
+/// Сколько последних выплат funding помнит аккаунт
+const RECENT_FUNDING: usize = 20;
+
+/// Состояние аккаунта по ACCOUNT_UPDATE - для /userdata/overview
+#[derive(Default)]
+struct AccountView {
+    /// asset -> wallet balance. ACCOUNT_UPDATE несёт только изменившиеся активы
+    balances: BTreeMap<String, f64>,
+    /// Время биржи последнего изменения балансов, ms
+    balances_at: i64,
+    /// Старые впереди
+    funding: VecDeque<FundingPayment>,
+}
+
+impl AccountView {
+    fn apply(&mut self, a: &RawAccountUpdate) {
+        for b in &a.balances {
+            if let Ok(balance) = b.wallet_balance.parse() {
+                self.balances.insert(b.asset.clone(), balance);
+            }
+        }
+        if !a.balances.is_empty() {
+            self.balances_at = a.event_time;
+        }
+
+        if a.reason != "FUNDING_FEE" {
+            return;
+        }
+        // В FUNDING_FEE позиция одна - та, по которой списали (в cross пусто)
+        let symbol = a.positions.first().map(|p| p.symbol.clone()).unwrap_or_default();
+        for b in &a.balances {
+            let Ok(amount) = b.balance_change.parse::<f64>() else { continue };
+            if self.funding.len() >= RECENT_FUNDING {
+                self.funding.pop_front();
+            }
+            self.funding.push_back(FundingPayment {
+                symbol: symbol.clone(),
+                asset: b.asset.clone(),
+                amount,
+                time: a.event_time,
+            });
+        }
+    }
+}
+
+struct StreamHandle {
+    state: Arc<StreamState>,
+    task: JoinHandle<()>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamInfo {
+    pub id: String,
+    pub connected: bool,
+    pub events: u64,
+    pub started_at: i64,
+    /// Когда получен текущий listenKey, unix s
+    pub listen_key_at: Option<i64>,
+    /// Последний успешный keepalive, unix s
+    pub last_keepalive_at: Option<i64>,
+    pub keepalive_failures: u64,
+    pub expirations: u64,
+    pub resubscribes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FundingPayment {
+    /// Пусто, если биржа не указала позицию
+    pub symbol: String,
+    pub asset: String,
+    /// < 0 - заплатили, > 0 - получили
+    pub amount: f64,
+    /// Время биржи, ms
+    pub time: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountOverview {
+    pub stream: StreamInfo,
+    /// Alias из keystore, если ключ там есть
+    pub alias: Option<String>,
+    /// asset -> wallet balance из ACCOUNT_UPDATE (пусто до первого апдейта)
+    pub balances: BTreeMap<String, f64>,
+    pub balances_at: Option<i64>,
+    pub positions: Vec<PositionInfo>,
+    pub unrealized_pnl: f64,
+    /// Новые первыми
+    pub recent_funding: Vec<FundingPayment>,
+    pub order_updates: u64,
+    pub account_updates: u64,
+    pub other_events: u64,
+}
+
+pub struct UserDataManager {
+    rest_url: String,
+    ws_base: String,
+    http: reqwest::Client,
+    streams: DashMap<String, StreamHandle>,
+    event_tx: broadcast::Sender<CEvent>,
+    seqs: Sequencer,
+    pub updates_tx: broadcast::Sender<UserDataUpdate>,
+}
+
+impl UserDataManager {
+    pub fn new(rest_url: String, ws_base: String, event_tx: broadcast::Sender<CEvent>) -> Arc<Self> {
+        let (updates_tx, _) = broadcast::channel::<UserDataUpdate>(4096);
+
+        Arc::new(Self {
+            rest_url,
+            ws_base,
+            http: reqwest::Client::new(),
+            streams: DashMap::new(),
+            event_tx,
+            seqs: Sequencer::default(),
+            updates_tx,
+        })
+    }
+
+    /// Запускает user data stream для ключа (listenKey + WS + keepalive)
+    pub fn start_stream(self: &Arc<Self>, api_key: &str) -> anyhow::Result<StreamInfo> {
+        let id = key_id(api_key);
+        if self.streams.contains_key(&id) {
+            anyhow::bail!("Stream '{}' already running", id);
+        }
+
+        let state = Arc::new(StreamState {
+            api_key: Arc::from(api_key),
+            connected: AtomicBool::new(false),
+            events: AtomicU64::new(0),
+            started_at: chrono::Utc::now().timestamp(),
+            listen_key_at: AtomicI64::new(0),
+            last_keepalive_at: AtomicI64::new(0),
+            keepalive_failures: AtomicU64::new(0),
+            expirations: AtomicU64::new(0),
+            resubscribes: AtomicU64::new(0),
+            order_updates: AtomicU64::new(0),
+            account_updates: AtomicU64::new(0),
+            other_events: AtomicU64::new(0),
+            account: Mutex::new(AccountView::default()),
+        });
+
+        let task = {
+            let mgr = self.clone();
+            let state = state.clone();
+            let id = id.clone();
+            tokio::spawn(async move {
+                mgr.run_stream(id, state).await;
+            })
+        };
+
+        let info = Self::info_of(&id, &state);
+        self.streams.insert(id.clone(), StreamHandle { state, task });
+
+        tracing::info!("👤 User data stream '{}' started", id);
+        Ok(info)
+    }
+
+    pub async fn stop_stream(&self, id: &str) -> anyhow::Result<()> {
+        let (_, handle) = self.streams.remove(id)
+            .ok_or_else(|| anyhow::anyhow!("Stream '{}' not found", id))?;
+
+        handle.task.abort();
+        if let Err(e) = self.close_listen_key(&handle.state.api_key).await {
+            tracing::warn!("⚠️ Failed to close listenKey for '{}': {}", id, e);
+        }
+
+        tracing::info!("👤 User data stream '{}' stopped", id);
+        Ok(())
+    }
+
+    pub fn has_stream(&self, api_key: &str) -> bool {
+        self.has_stream_id(&key_id(api_key))
+    }
+
+    /// По короткому идентификатору ключа (key_id)
+    pub fn has_stream_id(&self, id: &str) -> bool {
+        self.streams.contains_key(id)
+    }
+
+    pub fn list(&self) -> Vec<StreamInfo> {
+        self.streams.iter()
+            .map(|e| Self::info_of(e.key(), &e.value().state))
+            .collect()
+    }
+
+    fn info_of(id: &str, state: &StreamState) -> StreamInfo {
+        StreamInfo {
+            id: id.to_string(),
+            connected: state.connected.load(Ordering::Relaxed),
+            events: state.events.load(Ordering::Relaxed),
+            started_at: state.started_at,
+            listen_key_at: Some(state.listen_key_at.load(Ordering::Relaxed)).filter(|t| *t > 0),
+            last_keepalive_at: Some(state.last_keepalive_at.load(Ordering::Relaxed)).filter(|t| *t > 0),
+            keepalive_failures: state.keepalive_failures.load(Ordering::Relaxed),
+            expirations: state.expirations.load(Ordering::Relaxed),
+            resubscribes: state.resubscribes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Сводка по всем user data stream'ам: балансы, позиции, funding, счётчики
+    pub fn overview(&self) -> Vec<AccountOverview> {
+        let mut list: Vec<AccountOverview> = self.streams.iter()
+            .map(|e| {
+                let state = &e.value().state;
+                let positions = positions::list(Some(&state.api_key));
+                let account = state.account.lock().unwrap();
+                AccountOverview {
+                    stream: Self::info_of(e.key(), state),
+                    alias: None,
+                    balances: account.balances.clone(),
+                    balances_at: Some(account.balances_at).filter(|t| *t > 0),
+                    unrealized_pnl: positions.iter().fold(0.0, |sum, p| sum + p.unrealized_pnl),
+                    positions,
+                    recent_funding: account.funding.iter().rev().cloned().collect(),
+                    order_updates: state.order_updates.load(Ordering::Relaxed),
+                    account_updates: state.account_updates.load(Ordering::Relaxed),
+                    other_events: state.other_events.load(Ordering::Relaxed),
+                }
+            })
+            .collect();
+        list.sort_by(|a, b| a.stream.id.cmp(&b.stream.id));
+        list
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // LISTEN KEY (REST)
+    // ═══════════════════════════════════════════════════════════
+
+    async fn create_listen_key(&self, api_key: &str) -> anyhow::Result<String> {
+        let resp: Value = self.http
+            .post(format!("{}/fapi/v1/listenKey", self.rest_url))
+            .header("X-MBX-APIKEY", api_key)
+            .timeout(std::time::Duration::from_secs(5))
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        resp["listenKey"]
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| anyhow::anyhow!("No listenKey in response: {}", resp))
+    }
+
+    async fn keepalive_listen_key(&self, api_key: &str) -> anyhow::Result<()> {
+        let resp = self.http
+            .put(format!("{}/fapi/v1/listenKey", self.rest_url))
+            .header("X-MBX-APIKEY", api_key)
+            .timeout(std::time::Duration::from_secs(5))
+            .send()
+            .await?;
+
+        if !resp.status().is_success() {
+            anyhow::bail!("Keepalive failed: HTTP {}", resp.status());
+        }
+        Ok(())
+    }
+
+    async fn close_listen_key(&self, api_key: &str) -> anyhow::Result<()> {
+        self.http
+            .delete(format!("{}/fapi/v1/listenKey", self.rest_url))
+            .header("X-MBX-APIKEY", api_key)
+            .timeout(std::time::Duration::from_secs(5))
+            .send()
+            .await?;
+        Ok(())
+    }
+
+    /// Позиции ключа по всем символам (подписанный REST)
+    pub async fn position_risk(&self, api_key: &str, secret_key: &str) -> anyhow::Result<Vec<RawPositionRisk>> {
+        let ts = chrono::Utc::now().timestamp_millis() + time_offset_ms();
+        let query = format!("recvWindow=5000&timestamp={}", ts);
+        let mut mac = HmacSha256::new_from_slice(secret_key.as_bytes())?;
+        mac.update(query.as_bytes());
+        let signature = hex::encode(mac.finalize().into_bytes());
+
+        let resp = self.http
+            .get(format!("{}/fapi/v2/positionRisk?{}&signature={}", self.rest_url, query, signature))
+            .header("X-MBX-APIKEY", api_key)
+            .timeout(std::time::Duration::from_secs(5))
+            .send()
+            .await
+            .map_err(|e| e.without_url())?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            anyhow::bail!("positionRisk failed: HTTP {}: {}", status, body);
+        }
+        Ok(resp.json().await.map_err(|e| e.without_url())?)
+    }
+
+    // ═══════════════════════════════════════════════════════════
+    // WS LOOP
+    // ═══════════════════════════════════════════════════════════
+
+    async fn run_stream(self: Arc<Self>, id: String, state: Arc<StreamState>) {
+        loop {
+            let listen_key = match self.create_listen_key(&state.api_key).await {
+                Ok(k) => {
+                    state.listen_key_at.store(chrono::Utc::now().timestamp(), Ordering::Relaxed);
+                    k
+                }
+                Err(e) => {
+                    tracing::error!("❌ listenKey for '{}' failed: {}", id, e);
+                    sleep(Duration::from_secs(5)).await;
+                    continue;
+                }
+            };
+
+            let url = format!("{}/{}", self.ws_base, listen_key);
+            match net::connect(&url).await {
+                Ok((ws, _)) => {
+                    tracing::info!("👤 User data '{}' connected", id);
+                    state.connected.store(true, Ordering::Relaxed);
+                    let (mut write, mut read) = ws.split();
+
+                    let keepalive = sleep(KEEPALIVE_EVERY);
+                    tokio::pin!(keepalive);
+                    // Ключ потерян - переподключаемся сразу. POST listenKey продлит
+                    // ключ, если он на самом деле жив, или выдаст новый
+                    let mut resubscribe = false;
+
+                    loop {
+                        tokio::select! {
+                            _ = &mut keepalive => {
+                                match self.keepalive_listen_key(&state.api_key).await {
+                                    Ok(()) => {
+                                        state.last_keepalive_at.store(chrono::Utc::now().timestamp(), Ordering::Relaxed);
+                                        state.keepalive_failures.store(0, Ordering::Relaxed);
+                                        keepalive.as_mut().reset(Instant::now() + KEEPALIVE_EVERY);
+                                    }
+                                    Err(e) => {
+                                        let failures = state.keepalive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+                                        tracing::warn!("⚠️ Keepalive for '{}' failed ({}/{}): {}", id, failures, MAX_KEEPALIVE_FAILURES, e);
+                                        if failures >= MAX_KEEPALIVE_FAILURES {
+                                            alerts::emit(
+                                                AlertLevel::Warning,
+                                                "user_data",
+                                                format!("Stream '{}': {} keepalive failures, resubscribing with a new listenKey", id, failures),
+                                            );
+                                            resubscribe = true;
+                                            break;
+                                        }
+                                        keepalive.as_mut().reset(Instant::now() + KEEPALIVE_RETRY);
+                                    }
+                                }
+                            }
+
+                            msg = timeout(Duration::from_secs(60), read.next()) => {
+                                match msg {
+                                    Ok(Some(Ok(Message::Text(txt)))) => {
+                                        state.events.fetch_add(1, Ordering::Relaxed);
+                                        if !self.handle_text(&state, txt) {
+                                            tracing::warn!("⚠️ listenKey for '{}' expired", id);
+                                            state.expirations.fetch_add(1, Ordering::Relaxed);
+                                            resubscribe = true;
+                                            break;
+                                        }
+                                    }
+                                    Ok(Some(Ok(Message::Ping(data)))) => {
+                                        if write.send(Message::Pong(data)).await.is_err() {
+                                            break;
+                                        }
+                                    }
+                                    Ok(Some(Ok(Message::Close(cf)))) => {
+                                        tracing::warn!("User data '{}' closed: {:?}", id, cf);
+                                        break;
+                                    }
+                                    Ok(Some(Ok(_))) => {}
+                                    Ok(Some(Err(e))) => {
+                                        tracing::error!("User data '{}' read error: {}", id, e);
+                                        break;
+                                    }
+                                    Ok(None) => {
+                                        tracing::warn!("User data '{}' stream ended", id);
+                                        break;
+                                    }
+                                    Err(_) => {
+                                        // Тишина в user data - нормально, проверяем соединение пингом
+                                        if write.send(Message::Ping(Vec::new())).await.is_err() {
+                                            break;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    state.connected.store(false, Ordering::Relaxed);
+                    if resubscribe {
+                        let _ = write.send(Message::Close(None)).await;
+                        state.resubscribes.fetch_add(1, Ordering::Relaxed);
+                        state.keepalive_failures.store(0, Ordering::Relaxed);
+                        tracing::info!("🔑 User data '{}' resubscribing with a new listenKey", id);
+                        continue;
+                    }
+                    tracing::info!("User data '{}' reconnecting in 2s...", id);
+                    sleep(Duration::from_secs(2)).await;
+                }
+                Err(e) => {
+                    state.connected.store(false, Ordering::Relaxed);
+                    tracing::error!("User data '{}' connect error: {:?}", id, e);
+                    sleep(Duration::from_secs(3)).await;
+                }
+            }
+        }
+    }
+
+    /// Разбирает сообщение и рассылает его. Возвращает false если listenKey истёк.
+    fn handle_text(&self, state: &StreamState, txt: String) -> bool {
+        let received_at_ns = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64;
+
+        let event = match Self::parse_event(&txt) {
+            Ok(e) => e,
+            Err(e) => {
+                tracing::error!("User data parse error: {}", e);
+                raw_capture::capture("user_data", &e, &txt, true);
+                return true;
+            }
+        };
+
+        for mut c_event in parse_to_c_event(&event, received_at_ns) {
+            self.seqs.stamp(&mut c_event);
+            fanout::publish(c_event);
+            let _ = self.event_tx.send(c_event);
+        }
+
+        match &event {
+            UserDataEvent::OrderTradeUpdate(_) => {
+                state.order_updates.fetch_add(1, Ordering::Relaxed);
+            }
+            UserDataEvent::AccountUpdate(a) => {
+                state.account_updates.fetch_add(1, Ordering::Relaxed);
+                state.account.lock().unwrap().apply(a);
+            }
+            UserDataEvent::ListenKeyExpired => {}
+            UserDataEvent::Unknown { name, .. } => {
+                state.other_events.fetch_add(1, Ordering::Relaxed);
+                tracing::debug!("User data event '{}' passed through as unknown", name);
+            }
+        }
+
+        let expired = matches!(event, UserDataEvent::ListenKeyExpired);
+        let _ = self.updates_tx.send(UserDataUpdate { api_key: state.api_key.clone(), event });
+        !expired
+    }
+
+    fn parse_event(txt: &str) -> anyhow::Result<UserDataEvent> {
+        let v: Value = serde_json::from_str(txt)?;
+        let name = v["e"].as_str().unwrap_or_default().to_string();
+
+        let event = match name.as_str() {
+            "ORDER_TRADE_UPDATE" => {
+                let env: RawOrderEnvelope = serde_json::from_value(v)?;
+                let mut order = env.order;
+                order.event_time = env.event_time;
+                UserDataEvent::OrderTradeUpdate(Box::new(order))
+            }
+            "ACCOUNT_UPDATE" => {
+                let env: RawAccountEnvelope = serde_json::from_value(v)?;
+                let mut account = env.account;
+                account.event_time = env.event_time;
+                UserDataEvent::AccountUpdate(account)
+            }
+            "listenKeyExpired" => UserDataEvent::ListenKeyExpired,
+            _ => UserDataEvent::Unknown {
+                time: v["E"].as_i64().unwrap_or(0),
+                name,
+                raw: txt.to_string(),
+            },
+        };
+        Ok(event)
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// КОНВЕРТАЦИЯ В C-ТИПЫ
+// ═══════════════════════════════════════════════════════════
+
+pub fn parse_to_c_event(event: &UserDataEvent, received_at_ns: u64) -> Vec<CEvent> {
+    match event {
+        UserDataEvent::OrderTradeUpdate(o) => {
+            let (symbol, symbol_len) = pack_str::<16>(&o.symbol);
+            let (client_order_id, client_order_id_len) = pack_str::<36>(&o.client_order_id);
+
+            vec![CEvent {
+                event_type: EVENT_ORDER_UPDATE,
+                data: CEventData {
+                    order_update: COrderUpdate {
+                        symbol,
+                        symbol_len,
+                        client_order_id,
+                        client_order_id_len,
+                        order_id: o.order_id,
+                        side: if o.side == "BUY" { 0 } else { 1 },
+                        order_type: match o.order_type.as_str() {
+                            "LIMIT" => 0,
+                            "MARKET" => 1,
+                            "STOP_MARKET" => 2,
+                            "TAKE_PROFIT_MARKET" => 3,
+                            _ => 255,
+                        },
+                        exec_type: match o.exec_type.as_str() {
+                            "NEW" => 0,
+                            "TRADE" => 1,
+                            "CANCELED" => 2,
+                            "EXPIRED" => 3,
+                            "AMENDMENT" => 4,
+                            _ => 255,
+                        },
+                        status: match o.status.as_str() {
+                            "NEW" => 0,
+                            "PARTIALLY_FILLED" => 1,
+                            "FILLED" => 2,
+                            "CANCELED" => 3,
+                            "EXPIRED" => 4,
+                            "REJECTED" => 5,
+                            _ => 255,
+                        },
+                        reduce_only: o.reduce_only,
+                        price: o.price.parse().unwrap_or(0.0),
+                        orig_qty: o.orig_qty.parse().unwrap_or(0.0),
+                        last_filled_qty: o.last_qty(),
+                        last_filled_price: o.last_price(),
+                        cum_filled_qty: o.cum_filled_qty.parse().unwrap_or(0.0),
+                        avg_price: o.avg_price.parse().unwrap_or(0.0),
+                        commission: o.commission.as_deref().and_then(|n| n.parse().ok()).unwrap_or(0.0),
+                        realized_pnl: o.realized(),
+                        time: o.trade_time,
+                    }
+                },
+                received_at_ns,
+                seq: 0,
+            }]
+        }
+
+        UserDataEvent::AccountUpdate(a) => {
+            let reason = match a.reason.as_str() {
+                "ORDER" => 0,
+                "FUNDING_FEE" => 1,
+                _ => 255,
+            };
+            let usdt = a.balances.iter().find(|b| b.asset == "USDT");
+            let wallet_balance = usdt.and_then(|b| b.wallet_balance.parse().ok()).unwrap_or(0.0);
+            let balance_change = usdt.and_then(|b| b.balance_change.parse().ok()).unwrap_or(0.0);
+
+            let make = |symbol: &str, position_amt: f64, entry_price: f64, unrealized_pnl: f64| {
+                let (symbol, symbol_len) = pack_str::<16>(symbol);
+                CEvent {
+                    event_type: EVENT_ACCOUNT_UPDATE,
+                    data: CEventData {
+                        account_update: CAccountUpdate {
+                            symbol,
+                            symbol_len,
+                            reason,
+                            position_amt,
+                            entry_price,
+                            unrealized_pnl,
+                            wallet_balance,
+                            balance_change,
+                            time: a.event_time,
+                        }
+                    },
+                    received_at_ns,
+                    seq: 0,
+                }
+            };
+
+            if a.positions.is_empty() {
+                return vec![make("", 0.0, 0.0, 0.0)];
+            }
+
+            a.positions.iter()
+                .map(|p| make(
+                    &p.symbol,
+                    p.position_amt.parse().unwrap_or(0.0),
+                    p.entry_price.parse().unwrap_or(0.0),
+                    p.unrealized_pnl.parse().unwrap_or(0.0),
+                ))
+                .collect()
+        }
+
+        UserDataEvent::Unknown { name, raw, time } => vec![CEvent {
+            event_type: EVENT_USER_DATA_UNKNOWN,
+            data: CEventData { unknown_user_data: CUnknownUserData::new(name, raw, *time) },
+            received_at_ns,
+            seq: 0,
+        }],
+
+        UserDataEvent::ListenKeyExpired => Vec::new(),
+    }
+}
@@ -0,0 +1,250 @@
+// src/volume_profile.rs
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::sync::{LazyLock, OnceLock};
+
+use crate::ffi_types::{pack_str, CTrade, CVolumeLevel, CVwap};
+use crate::strategies::order::current_instance;
+use crate::strategies::replay;
+
+/// Цена уровня footprint в целых единицах: ключ без сравнения f64
+const PRICE_SCALE: f64 = 1e8;
+
+// ═══════════════════════════════════════════════════════════
+// ПРОФИЛЬ ОБЪЁМА
+// ═══════════════════════════════════════════════════════════
+//
+// Сделки символа за скользящее окно: объём покупок и продаж тейкеров по
+// каждой цене (footprint) и VWAP. Исполнение "держаться у VWAP" берёт
+// снимок через FFI (get_vwap, get_volume_profile) или GET /api/analytics/vwap,
+// а не копит сделки само. Считается только по символам с подпиской на trade.
+// Окно идёт по времени биржи. На replay снимков нет: профиль - живого рынка.
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct VolumeProfileConfig {
+    /// Собирать профиль из потока trade
+    pub enabled: bool,
+    /// Окно VWAP и footprint, секунд
+    pub window_secs: u64,
+}
+
+impl Default for VolumeProfileConfig {
+    fn default() -> Self {
+        Self { enabled: false, window_secs: 300 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct VolumeLevel {
+    pub price: f64,
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+    pub trades: u32,
+}
+
+impl VolumeLevel {
+    pub fn volume(&self) -> f64 {
+        self.buy_volume + self.sell_volume
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VwapSnapshot {
+    pub symbol: String,
+    /// 0.0 - сделок в окне нет
+    pub vwap: f64,
+    pub volume: f64,
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+    /// Цена с наибольшим объёмом (point of control)
+    pub poc_price: f64,
+    pub high: f64,
+    pub low: f64,
+    pub trades: u32,
+    pub window_ms: i64,
+    /// Время биржи последней сделки, unix ms
+    pub time: i64,
+    /// Footprint по возрастанию цены (только в ответе по одному символу)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub levels: Option<Vec<VolumeLevel>>,
+}
+
+/// Сделки символа в окне и суммы по ним
+#[derive(Default)]
+struct Profile {
+    /// (время биржи ms, цена, qty со знаком: + покупка тейкера)
+    trades: VecDeque<(i64, f64, f64)>,
+    levels: BTreeMap<i64, VolumeLevel>,
+    notional: f64,
+    buy_volume: f64,
+    sell_volume: f64,
+    last_time: i64,
+}
+
+impl Profile {
+    /// sign = 1.0 - сделка входит в окно, -1.0 - выходит из него
+    fn add(&mut self, price: f64, qty: f64, sign: f64) {
+        let key = (price * PRICE_SCALE).round() as i64;
+        let level = self.levels.entry(key).or_insert(VolumeLevel { price, ..Default::default() });
+        let volume = qty.abs() * sign;
+        if qty > 0.0 {
+            level.buy_volume += volume;
+            self.buy_volume += volume;
+        } else {
+            level.sell_volume += volume;
+            self.sell_volume += volume;
+        }
+        if sign > 0.0 {
+            level.trades += 1;
+        } else {
+            level.trades -= 1;
+            if level.trades == 0 {
+                self.levels.remove(&key);
+            }
+        }
+        self.notional += price * volume;
+    }
+
+    fn push(&mut self, time: i64, price: f64, qty: f64, window_ms: i64) {
+        self.add(price, qty, 1.0);
+        self.trades.push_back((time, price, qty));
+        self.last_time = self.last_time.max(time);
+
+        let cutoff = self.last_time - window_ms;
+        while let Some(&(time, price, qty)) = self.trades.front() {
+            if time > cutoff {
+                break;
+            }
+            self.add(price, qty, -1.0);
+            self.trades.pop_front();
+        }
+        if self.trades.is_empty() {
+            // Не копим ошибку округления сумм
+            *self = Self { last_time: self.last_time, ..Default::default() };
+        }
+    }
+
+    fn snapshot(&self, symbol: &str, window_ms: i64, with_levels: bool) -> VwapSnapshot {
+        let volume = self.buy_volume + self.sell_volume;
+        let poc = self.levels.values().max_by(|a, b| a.volume().total_cmp(&b.volume()));
+        VwapSnapshot {
+            symbol: symbol.to_string(),
+            vwap: if volume > 0.0 { self.notional / volume } else { 0.0 },
+            volume,
+            buy_volume: self.buy_volume,
+            sell_volume: self.sell_volume,
+            poc_price: poc.map_or(0.0, |l| l.price),
+            high: self.levels.values().next_back().map_or(0.0, |l| l.price),
+            low: self.levels.values().next().map_or(0.0, |l| l.price),
+            trades: self.trades.len() as u32,
+            window_ms,
+            time: self.last_time,
+            levels: with_levels.then(|| self.levels.values().copied().collect()),
+        }
+    }
+}
+
+static CONFIG: OnceLock<VolumeProfileConfig> = OnceLock::new();
+static PROFILES: LazyLock<DashMap<String, Profile>> = LazyLock::new(DashMap::new);
+
+pub fn init(config: VolumeProfileConfig) {
+    CONFIG.set(config).ok();
+}
+
+fn window_ms() -> Option<i64> {
+    CONFIG.get().filter(|c| c.enabled).map(|c| (c.window_secs * 1000) as i64)
+}
+
+/// Сделка из потока trade (reader market data)
+pub fn on_trade(trade: &CTrade) {
+    let Some(window_ms) = window_ms() else { return };
+    if trade.price <= 0.0 || trade.qty == 0.0 {
+        return;
+    }
+    PROFILES.entry(trade.symbol_str().to_string())
+        .or_default()
+        .push(trade.time, trade.price, trade.qty, window_ms);
+}
+
+/// VWAP символа; levels - вместе с footprint
+pub fn snapshot(symbol: &str, levels: bool) -> Option<VwapSnapshot> {
+    let window_ms = window_ms()?;
+    let symbol = symbol.to_uppercase();
+    PROFILES.get(&symbol).map(|p| p.snapshot(&symbol, window_ms, levels))
+}
+
+/// VWAP по всем символам, по убыванию объёма
+pub fn list() -> Vec<VwapSnapshot> {
+    let Some(window_ms) = window_ms() else { return Vec::new() };
+    let mut list: Vec<_> = PROFILES.iter().map(|p| p.snapshot(p.key(), window_ms, false)).collect();
+    list.sort_by(|a, b| b.volume.total_cmp(&a.volume));
+    list
+}
+
+pub fn enabled() -> bool {
+    window_ms().is_some()
+}
+
+// ═══════════════════════════════════════════════════════════
+// FFI
+// ═══════════════════════════════════════════════════════════
+
+unsafe fn live_symbol<'a>(symbol: *const c_char) -> Option<&'a str> {
+    if symbol.is_null() || current_instance().as_deref().and_then(replay::clock).is_some() {
+        return None;
+    }
+    CStr::from_ptr(symbol).to_str().ok()
+}
+
+/// Снимок VWAP символа. false - профиль выключен, сделок не было или это replay
+pub unsafe extern "C" fn get_vwap(symbol: *const c_char, out: *mut CVwap) -> bool {
+    let Some(symbol) = live_symbol(symbol) else { return false };
+    let Some(s) = snapshot(symbol, false).filter(|_| !out.is_null()) else { return false };
+    let (symbol, symbol_len) = pack_str::<16>(&s.symbol);
+    *out = CVwap {
+        symbol,
+        symbol_len,
+        vwap: s.vwap,
+        volume: s.volume,
+        buy_volume: s.buy_volume,
+        sell_volume: s.sell_volume,
+        poc_price: s.poc_price,
+        high: s.high,
+        low: s.low,
+        trades: s.trades,
+        window_ms: s.window_ms,
+        time: s.time,
+    };
+    true
+}
+
+/// Footprint символа по возрастанию цены: не больше max уровней, ближайших к
+/// последней цене. Возвращает, сколько записано в out
+pub unsafe extern "C" fn get_volume_profile(symbol: *const c_char, out: *mut CVolumeLevel, max: usize) -> usize {
+    let Some(symbol) = live_symbol(symbol) else { return 0 };
+    if out.is_null() || max == 0 {
+        return 0;
+    }
+    let Some(profile) = PROFILES.get(&symbol.to_uppercase()) else { return 0 };
+    let Some(&(_, last_price, _)) = profile.trades.back() else { return 0 };
+
+    let levels: Vec<&VolumeLevel> = profile.levels.values().collect();
+    // Окно из max уровней вокруг последней цены
+    let center = levels.partition_point(|l| l.price < last_price);
+    let start = center.saturating_sub(max / 2).min(levels.len().saturating_sub(max));
+    let levels = &levels[start..(start + max).min(levels.len())];
+    for (i, l) in levels.iter().enumerate() {
+        *out.add(i) = CVolumeLevel {
+            price: l.price,
+            buy_volume: l.buy_volume,
+            sell_volume: l.sell_volume,
+            trades: l.trades,
+        };
+    }
+    levels.len()
+}
@@ -18,10 +18,15 @@ struct Params {
     max_orders: u64,
     #[serde(default)]
     enable_logging: bool,
+    /// Alias ключей из keystore ядра
+    #[serde(default = "default_account")]
+    account: String,
 }
 
 fn default_max_orders() -> u64 { 0 }
 
+fn default_account() -> String { "main".to_string() }
+
 impl Default for Params {
     fn default() -> Self {
         Self {
@@ -30,6 +35,7 @@ impl Default for Params {
             price_offset_percent: -1.0,
             max_orders: 1,
             enable_logging: false,
+            account: default_account(),
         }
     }
 }
@@ -121,8 +127,8 @@ pub extern "C" fn run(
     println!("🚀 Bid Change Tracker started");
     let rx = unsafe { &*rx_ptr };
     let mut strategy = Strategy::new(&config);
-    let api_key = CString::new("ay0LdRfUbErVi6jTAanIjiuASqId3oLQYibIwCRQY3OLKiKdHCVGvLQgtH9X5wWm").expect("Invalid API key");
-    let secret_key = CString::new("3AJ2HfjBkNPKwfsPswkzAkqVx6Jawm3xSQTOMKJ1ib5e4Wa9DEM5ddvfhDjeqPO4").expect("Invalid secret key");
+    let api_key = CString::new(strategy.params.account.clone()).expect("Invalid account");
+    let secret_key = CString::new("").unwrap();
     let symbol = CString::new(strategy.symbol.clone()).expect("Invalid symbol");
     let side = CString::new("BUY").expect("Invalid side");
     println!("📡 Listening for {} events...\n", strategy.symbol);
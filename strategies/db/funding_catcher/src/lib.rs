@@ -22,8 +22,6 @@ struct EntryPoint {
 #[derive(Debug, Clone, Deserialize)]
 struct StrategyParams {
     entries: Vec<EntryPoint>,
-    target_hour: u8,
-    target_minute: u8,
     #[serde(default = "default_exit_delay_ms")]
     exit_delay_ms: u64,
     #[serde(default)]
@@ -64,30 +62,23 @@ struct ScheduledEntry {
     executed: bool,
 }
 
-fn build_schedule(
-    now: chrono::DateTime<Local>,
-    hour: u8,
-    minute: u8,
-    entries: &[EntryPoint],
-    exit_delay_ms: u64,
-) -> (Vec<ScheduledEntry>, chrono::DateTime<Local>, chrono::DateTime<Local>) {
-    let today = now.date_naive();
-    let target_naive = today
-        .and_hms_opt(hour as u32, minute as u32, 0)
-        .unwrap();
-
-    let mut funding_time = Local.from_local_datetime(&target_naive).unwrap();
+/// Время биржи (на replay - время записи) в локальной зоне
+fn exchange_now(config: &StrategyConfig) -> chrono::DateTime<Local> {
+    Local.timestamp_millis_opt(config.server_time_ms()).unwrap()
+}
 
-    // Если самый ранний вход уже прошёл - следующий день
+/// Ближайший funding из календаря ядра, к самому раннему входу в который ещё успеваем
+fn next_funding(config: &StrategyConfig, entries: &[EntryPoint]) -> Option<chrono::DateTime<Local>> {
     let max_before = entries.iter().map(|e| e.seconds_before).max().unwrap_or(0);
-    let earliest = funding_time - ChronoDuration::seconds(max_before as i64);
-    
-    if earliest <= now {
-        let tomorrow = today + ChronoDuration::days(1);
-        let target_naive = tomorrow.and_hms_opt(hour as u32, minute as u32, 0).unwrap();
-        funding_time = Local.from_local_datetime(&target_naive).unwrap();
-    }
+    let funding_ms = config.next_funding_after(config.symbol(), config.server_time_ms() + max_before as i64 * 1000)?;
+    Local.timestamp_millis_opt(funding_ms).single()
+}
 
+fn build_schedule(
+    funding_time: chrono::DateTime<Local>,
+    entries: &[EntryPoint],
+    exit_delay_ms: u64,
+) -> (Vec<ScheduledEntry>, chrono::DateTime<Local>) {
     let exit_time = funding_time + ChronoDuration::milliseconds(exit_delay_ms as i64);
 
     let mut schedule: Vec<ScheduledEntry> = entries.iter()
@@ -101,7 +92,7 @@ fn build_schedule(
     // Сортируем по времени
     schedule.sort_by_key(|s| s.time);
 
-    (schedule, funding_time, exit_time)
+    (schedule, exit_time)
 }
 
 // ═══════════════════════════════════════════════════════════
@@ -155,7 +146,7 @@ pub extern "C" fn run(
     let buy_ptr = buy_c.as_ptr();
     let sell_ptr = sell_c.as_ptr();
 
-    println!("🚀 FundingCatcher | {} | {:02}:{:02}", symbol, params.target_hour, params.target_minute);
+    println!("🚀 FundingCatcher | {}", symbol);
     println!("   Entries: {:?}", params.entries.iter()
         .map(|e| format!("-{}s: {}", e.seconds_before, e.quantity))
         .collect::<Vec<_>>());
@@ -169,11 +160,12 @@ pub extern "C" fn run(
             break;
         }
 
-        let now = Local::now();
-        let (mut schedule, funding_time, exit_time) = build_schedule(
-            now,
-            params.target_hour,
-            params.target_minute,
+        let Some(funding_time) = next_funding(&config, &params.entries) else {
+            eprintln!("❌ No funding time for {}", symbol);
+            return -5;
+        };
+        let (mut schedule, exit_time) = build_schedule(
+            funding_time,
             &params.entries,
             params.exit_delay_ms,
         );
@@ -201,7 +193,7 @@ pub extern "C" fn run(
                 }
             }
 
-            let now = Local::now();
+            let now = exchange_now(&config);
 
             // ═══════════════════════════════════════════════════════════
             // ENTRIES
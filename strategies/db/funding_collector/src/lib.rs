@@ -12,6 +12,10 @@ use chrono::{Local, TimeZone, Duration as ChronoDuration};
 
 static STOP_FLAG: AtomicBool = AtomicBool::new(false);
 
+const TIMER_ENTRY: u64 = 1;
+const TIMER_EXIT: u64 = 2;
+const TIMER_FINISH: u64 = 3;
+
 // Статическое хранилище для строк - живут всё время работы программы
 static API_KEY_C: OnceLock<CString> = OnceLock::new();
 static SECRET_KEY_C: OnceLock<CString> = OnceLock::new();
@@ -22,8 +26,6 @@ static SELL_SIDE_C: OnceLock<CString> = OnceLock::new();
 #[derive(Debug, Clone, Deserialize)]
 struct StrategyParams {
     order_qty: f64,
-    target_hour: u8,
-    target_minute: u8,
     #[serde(default = "default_pre_seconds")]
     pre_seconds: u64,
     #[serde(default = "default_exit_delay_ms")]
@@ -43,8 +45,6 @@ impl Default for StrategyParams {
     fn default() -> Self {
         Self {
             order_qty: 0.0,
-            target_hour: 0,
-            target_minute: 0,
             pre_seconds: default_pre_seconds(),
             exit_delay_ms: default_exit_delay_ms(),
             repeat: false,
@@ -74,31 +74,37 @@ unsafe extern "C" fn on_exit_placed(result: OrderResult) {
     println!("🔔 on_exit_placed CALLBACK FINISHED");
 }
 
+/// Время биржи (на replay - время записи) в локальной зоне
+fn exchange_now(config: &StrategyConfig) -> chrono::DateTime<Local> {
+    Local.timestamp_millis_opt(config.server_time_ms()).unwrap()
+}
+
+/// Вход и выход вокруг ближайшего funding из календаря ядра,
+/// к которому ещё успеваем войти. None - ядро не знает времени funding
 fn compute_next_times(
-    now: chrono::DateTime<Local>,
-    hour: u8,
-    minute: u8,
+    config: &StrategyConfig,
     pre_seconds: u64,
     exit_delay_ms: u64,
-) -> (chrono::DateTime<Local>, chrono::DateTime<Local>) {
-    let today = now.date_naive();
-    let mut target_naive = today
-        .and_hms_opt(hour as u32, minute as u32, 0)
-        .unwrap_or_else(|| today.and_hms_opt(0, 0, 0).unwrap());
-
-    let mut target = Local.from_local_datetime(&target_naive).unwrap();
-
-    if target <= now {
-        target_naive = (today + ChronoDuration::days(1))
-            .and_hms_opt(hour as u32, minute as u32, 0)
-            .unwrap();
-        target = Local.from_local_datetime(&target_naive).unwrap();
-    }
+) -> Option<(chrono::DateTime<Local>, chrono::DateTime<Local>, chrono::DateTime<Local>)> {
+    let after_ms = config.server_time_ms() + pre_seconds as i64 * 1000;
+    let funding_ms = config.next_funding_after(config.symbol(), after_ms)?;
+    let target = Local.timestamp_millis_opt(funding_ms).single()?;
 
     let entry_time = target - ChronoDuration::seconds(pre_seconds as i64);
     let exit_time = target + ChronoDuration::milliseconds(exit_delay_ms as i64);
 
-    (entry_time, exit_time)
+    Some((target, entry_time, exit_time))
+}
+
+/// Таймеры ядра на вход и выход (время биржи)
+fn arm_timers(
+    config: &StrategyConfig,
+    entry_time: chrono::DateTime<Local>,
+    exit_time: chrono::DateTime<Local>,
+) -> bool {
+    let now_ms = config.server_time_ms();
+    let delay = |at: chrono::DateTime<Local>| (at.timestamp_millis() - now_ms).max(0) as u64;
+    config.schedule_timer(delay(entry_time), TIMER_ENTRY) && config.schedule_timer(delay(exit_time), TIMER_EXIT)
 }
 
 /// Инициализирует статические CString'и
@@ -185,146 +191,151 @@ pub extern "C" fn run(
 
     println!("✅ Static strings initialized successfully");
 
-    let mut now = Local::now();
-    let (mut entry_time, mut exit_time) = compute_next_times(
-        now,
-        params.target_hour,
-        params.target_minute,
+    let Some((target, mut entry_time, mut exit_time)) = compute_next_times(
+        &config,
         params.pre_seconds,
         params.exit_delay_ms,
-    );
+    ) else {
+        println!("❌ ERROR: No funding time for {}", symbol);
+        return -3;
+    };
 
     println!(
-        "🕒 Next funding target at {:02}:{:02}, entry at {}, exit at {}",
-        params.target_hour,
-        params.target_minute,
+        "🕒 Next funding at {}, entry at {}, exit at {}",
+        target.format("%Y-%m-%d %H:%M:%S"),
         entry_time.format("%Y-%m-%d %H:%M:%S%.3f"),
         exit_time.format("%Y-%m-%d %H:%M:%S%.3f"),
     );
 
+    if !arm_timers(&config, entry_time, exit_time) {
+        println!("❌ ERROR: Core does not support timers");
+        return -4;
+    }
+
     let mut entry_sent = false;
     let mut exit_sent = false;
 
     while !STOP_FLAG.load(Ordering::Relaxed) {
-        match rx.recv_timeout(Duration::from_millis(100)) {
-            Ok(event) => {
-                // Можно добавить обработку событий если нужно
-                println!("📨 Received event");
-            }
-            Err(crossbeam::channel::RecvTimeoutError::Timeout) => {
-                // Нормальный таймаут, продолжаем
-            }
+        let event = match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(event) => event,
+            Err(crossbeam::channel::RecvTimeoutError::Timeout) => continue,
             Err(crossbeam::channel::RecvTimeoutError::Disconnected) => {
                 println!("⚠️ Event channel disconnected");
                 break;
             }
+        };
+        if event.event_type != EVENT_TIMER {
+            // Можно добавить обработку событий если нужно
+            println!("📨 Received event");
+            continue;
         }
 
-        now = Local::now();
-
-        // ENTRY логика
-        if !entry_sent && now >= entry_time && now < exit_time {
-            println!("⏰ ENTRY time reached: {}", now.format("%Y-%m-%d %H:%M:%S%.3f"));
-            
-            if params.order_qty > 0.0 
-                && !params.api_key.is_empty() 
-                && !params.secret_key.is_empty() 
-            {
-                println!(
-                    "📥 ENTRY: sending MARKET BUY {} {} at {}",
-                    params.order_qty,
-                    symbol,
-                    now.format("%Y-%m-%d %H:%M:%S%.3f"),
-                );
-                
-                println!("🔄 Calling place_order for ENTRY...");
-                unsafe {
-                    place_order(
-                        api_key_ptr,
-                        secret_key_ptr,
-                        symbol_ptr,
-                        0.0,
+        let now = exchange_now(&config);
+
+        match unsafe { event.data.timer.timer_id } {
+            // ENTRY логика
+            TIMER_ENTRY if !entry_sent => {
+                println!("⏰ ENTRY time reached: {}", now.format("%Y-%m-%d %H:%M:%S%.3f"));
+
+                if params.order_qty > 0.0
+                    && !params.api_key.is_empty()
+                    && !params.secret_key.is_empty()
+                {
+                    println!(
+                        "📥 ENTRY: sending MARKET BUY {} {} at {}",
                         params.order_qty,
-                        buy_side_ptr,
-                        1,
-                        on_entry_placed,
+                        symbol,
+                        now.format("%Y-%m-%d %H:%M:%S%.3f"),
                     );
+
+                    println!("🔄 Calling place_order for ENTRY...");
+                    unsafe {
+                        place_order(
+                            api_key_ptr,
+                            secret_key_ptr,
+                            symbol_ptr,
+                            0.0,
+                            params.order_qty,
+                            buy_side_ptr,
+                            1,
+                            on_entry_placed,
+                        );
+                    }
+                    println!("✅ place_order for ENTRY returned");
+                } else {
+                    println!("⚠️ ENTRY conditions not met, skipping");
                 }
-                println!("✅ place_order for ENTRY returned");
-                
-                entry_sent = true;
-            } else {
-                println!("⚠️ ENTRY conditions not met, skipping");
                 entry_sent = true;
             }
-        }
 
-        // EXIT логика
-        if entry_sent && !exit_sent && now >= exit_time {
-            println!("⏰ EXIT time reached: {}", now.format("%Y-%m-%d %H:%M:%S%.3f"));
-            
-            if params.order_qty > 0.0 
-                && !params.api_key.is_empty() 
-                && !params.secret_key.is_empty() 
-            {
-                println!(
-                    "📤 EXIT: sending MARKET SELL {} {} at {}",
-                    params.order_qty,
-                    symbol,
-                    now.format("%Y-%m-%d %H:%M:%S%.3f"),
-                );
-                
-                println!("🔄 Calling place_order for EXIT...");
-                unsafe {
-                    place_order(
-                        api_key_ptr,
-                        secret_key_ptr,
-                        symbol_ptr,
-                        0.0,
+            // EXIT логика
+            TIMER_EXIT if entry_sent && !exit_sent => {
+                println!("⏰ EXIT time reached: {}", now.format("%Y-%m-%d %H:%M:%S%.3f"));
+
+                if params.order_qty > 0.0
+                    && !params.api_key.is_empty()
+                    && !params.secret_key.is_empty()
+                {
+                    println!(
+                        "📤 EXIT: sending MARKET SELL {} {} at {}",
                         params.order_qty,
-                        sell_side_ptr,
-                        1,
-                        on_exit_placed,
+                        symbol,
+                        now.format("%Y-%m-%d %H:%M:%S%.3f"),
                     );
+
+                    println!("🔄 Calling place_order for EXIT...");
+                    unsafe {
+                        place_order(
+                            api_key_ptr,
+                            secret_key_ptr,
+                            symbol_ptr,
+                            0.0,
+                            params.order_qty,
+                            sell_side_ptr,
+                            1,
+                            on_exit_placed,
+                        );
+                    }
+                    println!("✅ place_order for EXIT returned");
+                } else {
+                    println!("⚠️ EXIT conditions not met, skipping");
                 }
-                println!("✅ place_order for EXIT returned");
-                
-                exit_sent = true;
-            } else {
-                println!("⚠️ EXIT conditions not met, skipping");
                 exit_sent = true;
+
+                if params.repeat {
+                    let Some((_, new_entry, new_exit)) = compute_next_times(
+                        &config,
+                        params.pre_seconds,
+                        params.exit_delay_ms,
+                    ) else {
+                        println!("❌ ERROR: No funding time for {}", symbol);
+                        break;
+                    };
+                    entry_time = new_entry;
+                    exit_time = new_exit;
+                    entry_sent = false;
+                    exit_sent = false;
+                    arm_timers(&config, entry_time, exit_time);
+
+                    println!(
+                        "🔁 Next cycle: entry at {}, exit at {}",
+                        entry_time.format("%Y-%m-%d %H:%M:%S%.3f"),
+                        exit_time.format("%Y-%m-%d %H:%M:%S%.3f"),
+                    );
+                } else {
+                    // Даём время на завершение всех асинхронных операций,
+                    // продолжая разбирать события
+                    println!("✅ One-shot mode: waiting before exit...");
+                    config.schedule_timer(2000, TIMER_FINISH);
+                }
             }
 
-            // Ждём чтобы callback успел выполниться
-            println!("⏳ Waiting for callback to complete...");
-            std::thread::sleep(Duration::from_millis(500));
-
-            if params.repeat {
-                now = Local::now();
-                let (new_entry, new_exit) = compute_next_times(
-                    now,
-                    params.target_hour,
-                    params.target_minute,
-                    params.pre_seconds,
-                    params.exit_delay_ms,
-                );
-                entry_time = new_entry;
-                exit_time = new_exit;
-                entry_sent = false;
-                exit_sent = false;
-
-                println!(
-                    "🔁 Next cycle: entry at {}, exit at {}",
-                    entry_time.format("%Y-%m-%d %H:%M:%S%.3f"),
-                    exit_time.format("%Y-%m-%d %H:%M:%S%.3f"),
-                );
-            } else {
-                println!("✅ One-shot mode: waiting before exit...");
-                // Даём время на завершение всех асинхронных операций
-                std::thread::sleep(Duration::from_secs(2));
+            TIMER_FINISH => {
                 println!("✅ One-shot mode: finished after first funding cycle");
                 break;
             }
+
+            _ => {}
         }
     }
 
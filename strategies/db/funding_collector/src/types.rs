@@ -1,13 +1,103 @@
+// copy_into_strategies/types.rs
 // Auto-generated types - DO NOT EDIT
-// Copied from template at strategy creation
+// Перезаписывается ядром при каждой компиляции, чтобы ABI совпадал с сервером
+#![allow(dead_code)]
+
 use std::os::raw::c_char;
+use std::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+use std::time::Duration;
+
+use crossbeam::channel::{Receiver, RecvTimeoutError, TryRecvError};
+
+// ═══════════════════════════════════════════════════════════
+// ABI
+// ═══════════════════════════════════════════════════════════
+
+/// Версия раскладки CEvent/StrategyConfig и сигнатуры run. Ядро не грузит
+/// библиотеку с другой версией (HostApi расширяется через size и её не меняет)
+pub const STRATEGY_ABI_VERSION: u32 = 1;
+
+#[no_mangle]
+pub extern "C" fn strategy_abi_version() -> u32 {
+    STRATEGY_ABI_VERSION
+}
+
+// ═══════════════════════════════════════════════════════════
+// EVENTS
+// ═══════════════════════════════════════════════════════════
+
+pub const EVENT_BOOK_TICKER: u8 = 0;
+pub const EVENT_TRADE: u8 = 1;
+pub const EVENT_ORDER_UPDATE: u8 = 2;
+pub const EVENT_ACCOUNT_UPDATE: u8 = 3;
+pub const EVENT_FUNDING_RATE: u8 = 4;
+pub const EVENT_SIGNAL: u8 = 5;
+pub const EVENT_TIMER: u8 = 6;
+pub const EVENT_USER_DATA_UNKNOWN: u8 = 7;
+pub const EVENT_ANALYTICS: u8 = 8;
+pub const EVENT_OPEN_INTEREST: u8 = 9;
 
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct CEvent {
-    pub event_type: u8,
+    pub event_type: u8,        // EVENT_*
     pub data: CEventData,
-    pub received_at_ns: u64,
+    pub received_at_ns: u64,   // SystemTime::UNIX_EPOCH.as_nanos()
+    pub seq: u64,              // номер в потоке (event_type + символ), см. SeqTracker
+}
+
+impl CEvent {
+    /// Сколько прошло с чтения из WS ядром (задержка доставки до стратегии)
+    pub fn age_ns(&self) -> u64 {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+        now.saturating_sub(self.received_at_ns)
+    }
+
+    pub fn symbol_str(&self) -> &str {
+        unsafe {
+            match self.event_type {
+                EVENT_BOOK_TICKER => self.data.book_ticker.symbol_str(),
+                EVENT_TRADE => self.data.trade.symbol_str(),
+                EVENT_ORDER_UPDATE => self.data.order_update.symbol_str(),
+                EVENT_ACCOUNT_UPDATE => self.data.account_update.symbol_str(),
+                EVENT_FUNDING_RATE => self.data.funding_rate.symbol_str(),
+                EVENT_ANALYTICS => self.data.analytics.symbol_str(),
+                EVENT_OPEN_INTEREST => self.data.open_interest.symbol_str(),
+                _ => "",
+            }
+        }
+    }
+}
+
+/// Обнаружение потерянных событий по CEvent.seq.
+/// Пропуск значит, что канал стратегии переполнялся и часть событий
+/// выброшена: состояние (стакан, позиция) могло устареть - пересобрать его.
+#[derive(Default)]
+pub struct SeqTracker {
+    last: std::collections::HashMap<(u8, [u8; 16]), u64>,
+    /// Всего пропущено событий
+    pub missed: u64,
+}
+
+impl SeqTracker {
+    /// Сколько событий этого потока пропущено перед event (0 - без пропусков)
+    pub fn check(&mut self, event: &CEvent) -> u64 {
+        if event.seq == 0 {
+            return 0;
+        }
+        let mut symbol = [0u8; 16];
+        let bytes = event.symbol_str().as_bytes();
+        let len = bytes.len().min(16);
+        symbol[..len].copy_from_slice(&bytes[..len]);
+
+        let prev = self.last.insert((event.event_type, symbol), event.seq).unwrap_or(0);
+        let gap = if prev > 0 { event.seq.saturating_sub(prev + 1) } else { 0 };
+        self.missed += gap;
+        gap
+    }
 }
 
 #[repr(C)]
@@ -15,6 +105,14 @@ pub struct CEvent {
 pub union CEventData {
     pub book_ticker: CBookTicker,
     pub trade: CTrade,
+    pub order_update: COrderUpdate,
+    pub account_update: CAccountUpdate,
+    pub funding_rate: CFundingRate,
+    pub signal: CCustomSignal,
+    pub timer: CTimer,
+    pub unknown_user_data: CUnknownUserData,
+    pub analytics: CAnalytics,
+    pub open_interest: COpenInterest,
 }
 
 #[repr(C)]
@@ -33,6 +131,14 @@ impl CBookTicker {
     pub fn symbol_str(&self) -> &str {
         unsafe { std::str::from_utf8_unchecked(&self.symbol[..self.symbol_len as usize]) }
     }
+
+    pub fn mid_price(&self) -> f64 {
+        (self.bid_price + self.ask_price) / 2.0
+    }
+
+    pub fn spread(&self) -> f64 {
+        self.ask_price - self.bid_price
+    }
 }
 
 #[repr(C)]
@@ -41,7 +147,7 @@ pub struct CTrade {
     pub symbol: [u8; 16],
     pub symbol_len: u8,
     pub price: f64,
-    pub qty: f64,
+    pub qty: f64,              // ОТРИЦАТЕЛЬНЫЙ если is_maker=true
     pub time: i64,
 }
 
@@ -51,12 +157,285 @@ impl CTrade {
     }
 }
 
+/// ORDER_TRADE_UPDATE из user data stream
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct COrderUpdate {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub client_order_id: [u8; 36],
+    pub client_order_id_len: u8,
+    pub order_id: i64,
+    pub side: u8,          // 0 = BUY, 1 = SELL
+    pub order_type: u8,    // 0 = LIMIT, 1 = MARKET, 2 = STOP_MARKET, 3 = TAKE_PROFIT_MARKET, 255 = другое
+    pub exec_type: u8,     // 0 = NEW, 1 = TRADE, 2 = CANCELED, 3 = EXPIRED, 4 = AMENDMENT, 255 = другое
+    pub status: u8,        // 0 = NEW, 1 = PARTIALLY_FILLED, 2 = FILLED, 3 = CANCELED, 4 = EXPIRED, 5 = REJECTED, 255 = другое
+    pub reduce_only: bool,
+    pub price: f64,
+    pub orig_qty: f64,
+    pub last_filled_qty: f64,
+    pub last_filled_price: f64,
+    pub cum_filled_qty: f64,
+    pub avg_price: f64,
+    pub commission: f64,
+    pub realized_pnl: f64,
+    pub time: i64,
+}
+
+impl COrderUpdate {
+    pub fn symbol_str(&self) -> &str {
+        unsafe { std::str::from_utf8_unchecked(&self.symbol[..self.symbol_len as usize]) }
+    }
+
+    pub fn client_order_id_str(&self) -> &str {
+        unsafe { std::str::from_utf8_unchecked(&self.client_order_id[..self.client_order_id_len as usize]) }
+    }
+}
+
+/// ACCOUNT_UPDATE из user data stream (одно событие на каждую позицию)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CAccountUpdate {
+    pub symbol: [u8; 16],  // пустой если в апдейте нет позиций
+    pub symbol_len: u8,
+    pub reason: u8,        // 0 = ORDER, 1 = FUNDING_FEE, 255 = другое
+    pub position_amt: f64,
+    pub entry_price: f64,
+    pub unrealized_pnl: f64,
+    pub wallet_balance: f64,   // USDT
+    pub balance_change: f64,   // USDT
+    pub time: i64,
+}
+
+impl CAccountUpdate {
+    pub fn symbol_str(&self) -> &str {
+        unsafe { std::str::from_utf8_unchecked(&self.symbol[..self.symbol_len as usize]) }
+    }
+}
+
+/// Ставка funding пересекла порог сканера ядра (в любую сторону).
+/// Приходит по всем символам, не только по символу инстанса
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CFundingRate {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub above: bool,           // true - |ставка| >= порога, false - опустилась ниже
+    pub funding_rate: f64,     // прогноз на ближайший funding (0.0001 = 0.01%)
+    pub threshold: f64,
+    pub mark_price: f64,
+    pub next_funding_time: i64,  // unix ms, время биржи
+    pub time: i64,
+}
+
+impl CFundingRate {
+    pub fn symbol_str(&self) -> &str {
+        unsafe { std::str::from_utf8_unchecked(&self.symbol[..self.symbol_len as usize]) }
+    }
+}
+
+/// Сигнал извне: POST /api/instances/{id}/signal с JSON до 128 байт
+/// (TradingView alert, планировщик). Приходит только этому инстансу
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CCustomSignal {
+    pub payload: [u8; 128],    // JSON как прислали, UTF-8
+    pub payload_len: u16,
+    pub time: i64,             // unix ms приёма ядром
+}
+
+impl CCustomSignal {
+    pub fn payload_str(&self) -> &str {
+        let len = (self.payload_len as usize).min(self.payload.len());
+        std::str::from_utf8(&self.payload[..len]).unwrap_or("")
+    }
+
+    /// payload в свою структуру: let s: MySignal = signal.payload_json()?
+    pub fn payload_json<T: serde::de::DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_str(self.payload_str())
+    }
+}
+
+/// Сработал таймер из config.schedule_timer(). Время - часы стратегии (now_ns)
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CTimer {
+    pub timer_id: u64,
+    pub due_ns: i64,           // на когда был заведён
+    pub fired_ns: i64,         // когда поставлен в канал
+}
+
+/// Событие user data, которое ядро не разбирает (всё кроме ORDER_TRADE_UPDATE
+/// и ACCOUNT_UPDATE): имя из "e" и первые 96 байт JSON. Чтобы считать
+/// и логировать, что приходит от биржи мимо стратегии
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CUnknownUserData {
+    pub name: [u8; 32],        // "e", например "TRADE_LITE"
+    pub name_len: u8,
+    pub truncated: bool,       // JSON обрезан - целиком не разберётся
+    pub payload: [u8; 96],     // JSON как пришёл, UTF-8
+    pub payload_len: u16,
+    pub time: i64,             // "E" события, unix ms
+}
+
+impl CUnknownUserData {
+    pub fn name_str(&self) -> &str {
+        let len = (self.name_len as usize).min(self.name.len());
+        std::str::from_utf8(&self.name[..len]).unwrap_or("")
+    }
+
+    pub fn payload_str(&self) -> &str {
+        let len = (self.payload_len as usize).min(self.payload.len());
+        std::str::from_utf8(&self.payload[..len]).unwrap_or("")
+    }
+}
+
+/// Метрики символа от аналитики ядра (config.analytics.enabled): спред,
+/// дисбаланс стакана и потока сделок за 1s/5s - вместо своего VecDeque.
+/// Приходит после bookTicker или trade, который их изменил, не чаще
+/// min_interval_ms на символ; по всем символам, не только по символу инстанса
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CAnalytics {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub spread_bps: f64,       // (ask - bid) / mid, б.п.
+    pub mid_price: f64,
+    pub book_imbalance: f64,   // (bid_qty - ask_qty) / (bid_qty + ask_qty), -1..1
+    pub flow_imbalance_1s: f64,  // (buy - sell) / (buy + sell) объёма тейкеров, -1..1
+    pub flow_imbalance_5s: f64,
+    pub volume_1s: f64,
+    pub volume_5s: f64,
+    pub trades_1s: u32,
+    pub trades_5s: u32,
+    pub time: i64,             // unix ms, время биржи
+}
+
+impl CAnalytics {
+    pub fn symbol_str(&self) -> &str {
+        unsafe { std::str::from_utf8_unchecked(&self.symbol[..self.symbol_len as usize]) }
+    }
+}
+
+/// Open interest и long/short ratio топ-трейдеров из REST-опроса ядра
+/// (config.open_interest), раз в interval_secs по каждому символу - чтобы не
+/// входить в перекошенную толпу. Нули - значение ещё не получено
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct COpenInterest {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub open_interest: f64,    // в базовой монете
+    pub open_interest_change_percent: f64,  // с прошлого опроса
+    pub long_short_ratio: f64, // long / short позиций топ-трейдеров
+    pub long_account: f64,     // доля long, 0..1
+    pub short_account: f64,
+    pub ratio_time: i64,       // unix ms, начало периода ratio
+    pub time: i64,             // unix ms, время биржи open interest
+}
+
+impl COpenInterest {
+    pub fn symbol_str(&self) -> &str {
+        unsafe { std::str::from_utf8_unchecked(&self.symbol[..self.symbol_len as usize]) }
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// ORDERS
+// ═══════════════════════════════════════════════════════════
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct OrderResult {
     pub success: bool,
-    pub order_id: i64,
-    pub error_code: i32,
+    pub order_id: i64,         // -1 если ошибка
+    pub error_code: i32,       // Binance error code, локальный код (-9xxx) или 0
+}
+
+// Локальные отказы ядра (ордер не был отправлен на биржу)
+pub const ERR_KILL_SWITCH: i32 = -9100;
+pub const ERR_MAX_NOTIONAL: i32 = -9101;
+pub const ERR_MAX_POSITION: i32 = -9102;
+pub const ERR_MAX_OPEN_ORDERS: i32 = -9103;
+pub const ERR_DAILY_LOSS: i32 = -9104;
+pub const ERR_NO_REFERENCE_PRICE: i32 = -9105;
+pub const ERR_RATE_LIMITED: i32 = -9200;
+pub const ERR_IP_BANNED: i32 = -9201;
+/// Ордер простоял в очереди ядра дольше trade_ws.order_ttl_ms и не отправлен
+pub const ERR_ORDER_EXPIRED: i32 = -9202;
+pub const ERR_CIRCUIT_OPEN: i32 = -9300;
+pub const ERR_INSTANCE_QUARANTINED: i32 = -9301;
+pub const ERR_DUPLICATE_ORDER: i32 = -9302;
+/// Превышен общий потолок ордеров в секунду ядра (capacity.max_orders_per_sec)
+pub const ERR_THROUGHPUT_LIMIT: i32 = -9303;
+pub const ERR_STAGE_INVALID: i32 = -9400;
+pub const ERR_STAGE_NOT_FOUND: i32 = -9401;
+pub const ERR_ALGO_INVALID: i32 = -9600;
+pub const ERR_ALGO_LIMIT: i32 = -9601;
+pub const ERR_ALGO_UNSUPPORTED: i32 = -9602;
+pub const ERR_BRACKET_INVALID: i32 = -9610;
+pub const ERR_BRACKET_UNSUPPORTED: i32 = -9611;
+// Транспорт: запрос мог и дойти до биржи
+pub const ERR_DISCONNECTED: i32 = -9700;
+pub const ERR_SIGN_FAILED: i32 = -9701;
+pub const ERR_REST_FAILED: i32 = -9702;
+/// Ответа на запрос нет дольше trade_ws.request_timeout_ms: статус ордера неизвестен
+pub const ERR_REQUEST_TIMEOUT: i32 = -9703;
+/// Ядро останавливается: ордер не принят или снят из очереди без отправки
+pub const ERR_SHUTTING_DOWN: i32 = -9704;
+
+/// Категория ошибки ордера - та же, что в поле category ответов /order/*
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    Network = 1,
+    RateLimit = 2,
+    Timestamp = 3,
+    Auth = 4,
+    InvalidRequest = 5,
+    Filter = 6,
+    Margin = 7,
+    Rejected = 8,
+    UnknownOrder = 9,
+    Risk = 10,
+    Local = 11,
+    Unknown = 255,
+}
+
+pub fn error_category(code: i32) -> ErrorCategory {
+    use ErrorCategory::*;
+    match code {
+        -1001 | -1006 | -1007 | -1008 => Network,
+        ERR_DISCONNECTED | ERR_REST_FAILED | ERR_REQUEST_TIMEOUT => Network,
+        -1003 | -1015 | ERR_RATE_LIMITED | ERR_IP_BANNED | ERR_THROUGHPUT_LIMIT => RateLimit,
+        -1021 => Timestamp,
+        -1002 | -1022 | -2014 | -2015 | ERR_SIGN_FAILED => Auth,
+        -1013 | -1111 | -4014 | -4023 | -4164 => Filter,
+        -1199..=-1100 | -1014 | -1016 => InvalidRequest,
+        -2018 | -2019 | -2027 | -2028 => Margin,
+        -2011 | -2013 => UnknownOrder,
+        -2010 | -2020 | -2021 | -2022 | -5021 | -5022 => Rejected,
+        -4999..=-4000 => InvalidRequest,
+        -9199..=-9100 => Risk,
+        -9699..=-9202 | ERR_SHUTTING_DOWN => Local,
+        _ => Unknown,
+    }
+}
+
+/// Повтор того же ордера может пройти (ордер точно не исполнился)
+pub fn is_retryable(code: i32) -> bool {
+    matches!(code, -1001 | -1003 | -1008 | -1015 | -1021 | ERR_RATE_LIMITED | ERR_THROUGHPUT_LIMIT)
+}
+
+impl OrderResult {
+    pub fn category(&self) -> Option<ErrorCategory> {
+        (!self.success).then(|| error_category(self.error_code))
+    }
+
+    pub fn retryable(&self) -> bool {
+        !self.success && is_retryable(self.error_code)
+    }
 }
 
 pub type OrderCallback = unsafe extern "C" fn(result: OrderResult);
@@ -80,31 +459,883 @@ pub type CancelOrderFn = unsafe extern "C" fn(
     callback: OrderCallback,
 );
 
+// Состояния ордера в order manager ядра (COrder.state)
+/// Ответа ещё нет. С error_code != 0 - обрыв или таймаут: ордер мог встать
+pub const ORDER_PENDING: u8 = 0;
+pub const ORDER_NEW: u8 = 1;
+pub const ORDER_PARTIALLY_FILLED: u8 = 2;
+pub const ORDER_FILLED: u8 = 3;
+pub const ORDER_CANCELED: u8 = 4;
+pub const ORDER_EXPIRED: u8 = 5;
+pub const ORDER_REJECTED: u8 = 6;
+pub const ORDER_FAILED: u8 = 7;
+/// Нет user data stream: ядро не знает, чем кончился ордер
+pub const ORDER_STALE: u8 = 8;
+
+/// Снимок ордера, который ядро отслеживает за стратегию
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct COrder {
+    pub client_order_id: [u8; 36],
+    pub client_order_id_len: u8,
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub order_id: i64,     // -1 пока нет ответа биржи
+    pub side: u8,          // 0 = BUY, 1 = SELL
+    pub order_type: u8,    // 0 = LIMIT, 1 = MARKET, 2 = STOP_MARKET
+    pub state: u8,         // ORDER_*
+    pub price: f64,
+    pub orig_qty: f64,
+    pub filled_qty: f64,
+    pub avg_price: f64,
+    pub error_code: i32,
+    pub created_at: i64,   // unix ms
+    pub updated_at: i64,   // unix ms
+}
+
+impl COrder {
+    pub fn symbol_str(&self) -> &str {
+        unsafe { std::str::from_utf8_unchecked(&self.symbol[..self.symbol_len as usize]) }
+    }
+
+    pub fn client_order_id_str(&self) -> &str {
+        unsafe { std::str::from_utf8_unchecked(&self.client_order_id[..self.client_order_id_len as usize]) }
+    }
+
+    pub fn is_open(&self) -> bool {
+        matches!(self.state, ORDER_PENDING | ORDER_NEW | ORDER_PARTIALLY_FILLED)
+    }
+}
+
+pub type GetOrdersFn = unsafe extern "C" fn(
+    symbol: *const c_char, // NULL = все символы
+    open_only: bool,
+    out: *mut COrder,
+    max: usize,
+) -> usize;
+
+pub type LogFn = unsafe extern "C" fn(level: u8, msg: *const u8, len: usize);
+
+pub type TimeFn = extern "C" fn() -> i64;
+
+pub type FundingTimeFn = unsafe extern "C" fn(symbol: *const c_char) -> i64;
+
+/// VWAP символа за скользящее окно ядра (config.volume_profile): config.vwap()
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CVwap {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub vwap: f64,
+    pub volume: f64,
+    pub buy_volume: f64,   // объём покупок тейкеров
+    pub sell_volume: f64,
+    pub poc_price: f64,    // цена с наибольшим объёмом
+    pub high: f64,
+    pub low: f64,
+    pub trades: u32,
+    pub window_ms: i64,
+    pub time: i64,         // unix ms, время биржи последней сделки
+}
+
+impl CVwap {
+    pub fn symbol_str(&self) -> &str {
+        unsafe { std::str::from_utf8_unchecked(&self.symbol[..self.symbol_len as usize]) }
+    }
+}
+
+/// Объём сделок по одной цене за окно: config.volume_profile()
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CVolumeLevel {
+    pub price: f64,
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+    pub trades: u32,
+}
+
+/// Шаблон ордера для stage_orders: всё, кроме цены, известно заранее
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct COrderTemplate {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub side: u8,          // 0 = BUY, 1 = SELL
+    pub order_type: u8,    // 0 = LIMIT, 1 = MARKET
+    pub quantity: f64,
+}
+
+impl COrderTemplate {
+    /// side: "BUY" / "SELL", market: false = LIMIT.
+    /// Err - символ длиннее 15 байт: обрезанный ушёл бы не на тот рынок
+    pub fn new(symbol: &str, side: &str, market: bool, quantity: f64) -> Result<Self, String> {
+        let mut buf = [0u8; 16];
+        let len = symbol.len();
+        if len > 15 {
+            return Err(format!("Symbol '{}' is longer than 15 bytes", symbol));
+        }
+        buf[..len].copy_from_slice(symbol.as_bytes());
+        Ok(Self {
+            symbol: buf,
+            symbol_len: len as u8,
+            side: if side.eq_ignore_ascii_case("SELL") { 1 } else { 0 },
+            order_type: market as u8,
+            quantity,
+        })
+    }
+}
+
+/// Родительский ордер для place_algo_order: ядро режет его на дочерние
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CAlgoOrder {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub side: u8,          // 0 = BUY, 1 = SELL
+    pub algo: u8,          // 0 = TWAP, 1 = ICEBERG
+    pub slices: u32,       // TWAP: число частей
+    pub duration_secs: u64, // TWAP: на сколько растянуть
+    pub quantity: f64,
+    pub price: f64,        // TWAP: 0 = MARKET, иначе LIMIT; ICEBERG: цена клипов
+    pub clip_qty: f64,     // ICEBERG: видимый объём
+    pub step: f64,         // шаг количества символа, 0 = без округления
+}
+
+impl CAlgoOrder {
+    /// quantity равными частями за duration_secs; price = None - части MARKET
+    pub fn twap(symbol: &str, side: &str, quantity: f64, price: Option<f64>, duration_secs: u64, slices: u32) -> Self {
+        let mut order = Self::new(symbol, side, 0, quantity, price.unwrap_or(0.0));
+        order.duration_secs = duration_secs;
+        order.slices = slices;
+        order
+    }
+
+    /// Лимитные клипы по clip_qty, следующий - после исполнения предыдущего
+    pub fn iceberg(symbol: &str, side: &str, quantity: f64, price: f64, clip_qty: f64) -> Self {
+        let mut order = Self::new(symbol, side, 1, quantity, price);
+        order.clip_qty = clip_qty;
+        order
+    }
+
+    /// Шаг количества (stepSize символа): части округляются до него
+    pub fn with_step(mut self, step: f64) -> Self {
+        self.step = step;
+        self
+    }
+
+    fn new(symbol: &str, side: &str, algo: u8, quantity: f64, price: f64) -> Self {
+        let mut buf = [0u8; 16];
+        let len = symbol.len().min(15);
+        buf[..len].copy_from_slice(&symbol.as_bytes()[..len]);
+        Self {
+            symbol: buf,
+            symbol_len: len as u8,
+            side: if side.eq_ignore_ascii_case("SELL") { 1 } else { 0 },
+            algo,
+            slices: 0,
+            duration_secs: 0,
+            quantity,
+            price,
+            clip_qty: 0.0,
+            step: 0.0,
+        }
+    }
+}
+
+/// Вход + TP + SL для place_bracket: защита ставится от средней цены входа
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct CBracket {
+    pub symbol: [u8; 16],
+    pub symbol_len: u8,
+    pub side: u8,          // 0 = BUY, 1 = SELL
+    pub entry_type: u8,    // 0 = LIMIT, 1 = MARKET
+    pub quantity: f64,
+    pub price: f64,        // цена входа LIMIT
+    pub tp_pct: f64,       // TP в % от входа, 0 = без TP
+    pub sl_pct: f64,       // SL в % от входа, 0 = без SL
+    pub tick_size: f64,    // шаг цены символа, 0 = без округления
+}
+
+impl CBracket {
+    /// price = None - вход MARKET
+    pub fn new(symbol: &str, side: &str, quantity: f64, price: Option<f64>, tp_pct: f64, sl_pct: f64) -> Self {
+        let mut buf = [0u8; 16];
+        let len = symbol.len().min(15);
+        buf[..len].copy_from_slice(&symbol.as_bytes()[..len]);
+        Self {
+            symbol: buf,
+            symbol_len: len as u8,
+            side: if side.eq_ignore_ascii_case("SELL") { 1 } else { 0 },
+            entry_type: price.is_none() as u8,
+            quantity,
+            price: price.unwrap_or(0.0),
+            tp_pct,
+            sl_pct,
+            tick_size: 0.0,
+        }
+    }
+
+    /// Шаг цены (tickSize символа): TP и SL округляются до него
+    pub fn with_tick(mut self, tick_size: f64) -> Self {
+        self.tick_size = tick_size;
+        self
+    }
+}
+
+pub type RecvModeFn = extern "C" fn() -> u8;
+
+pub type StageOrdersFn = unsafe extern "C" fn(
+    api_key: *const c_char,
+    secret_key: *const c_char,
+    templates: *const COrderTemplate,
+    count: usize,
+) -> i64;
+
+pub type FireStagedFn = unsafe extern "C" fn(
+    stage_id: i64,
+    prices: *const f64,    // NaN = пропустить шаблон
+    count: usize,
+    callback: OrderCallback,
+) -> i32;
+
+pub type UnstageFn = extern "C" fn(stage_id: i64) -> bool;
+
+pub type HeartbeatFn = extern "C" fn();
+
+pub type NotifyFn = unsafe extern "C" fn(msg: *const u8, len: usize);
+
+/// Возвращает длину значения (-1 - нет ключа); копирует, только если влезает в cap
+pub type KvGetFn = unsafe extern "C" fn(key: *const u8, key_len: usize, out: *mut u8, cap: usize) -> i64;
+
+pub type KvSetFn = unsafe extern "C" fn(key: *const u8, key_len: usize, value: *const u8, value_len: usize) -> bool;
+
+pub type KvDeleteFn = unsafe extern "C" fn(key: *const u8, key_len: usize) -> bool;
+
+pub type ScheduleTimerFn = extern "C" fn(delay_ms: u64, timer_id: u64) -> bool;
+
+pub type CancelTimerFn = extern "C" fn(timer_id: u64) -> bool;
+
+pub type StreamFn = unsafe extern "C" fn(symbol: *const c_char, stream_type: u8) -> bool;
+
+pub type PlaceAlgoFn = unsafe extern "C" fn(
+    api_key: *const c_char,
+    secret_key: *const c_char,
+    order: *const CAlgoOrder,
+) -> i64;
+
+pub type CancelAlgoFn = extern "C" fn(algo_id: i64) -> bool;
+
+pub type PlaceBracketFn = unsafe extern "C" fn(
+    api_key: *const c_char,
+    secret_key: *const c_char,
+    bracket: *const CBracket,
+) -> i64;
+
+pub type CancelBracketFn = extern "C" fn(bracket_id: i64) -> bool;
+
+pub type GetPositionFn = unsafe extern "C" fn(api_key: *const c_char, symbol: *const c_char) -> f64;
+
+pub type ClosePositionFn = unsafe extern "C" fn(
+    api_key: *const c_char,
+    secret_key: *const c_char,
+    symbol: *const c_char,
+    callback: OrderCallback,
+) -> f64;
+
+pub type EmitMetricFn = unsafe extern "C" fn(name: *const u8, name_len: usize, value: f64, kind: u8) -> bool;
+
+pub type GetVwapFn = unsafe extern "C" fn(symbol: *const c_char, out: *mut CVwap) -> bool;
+
+pub type GetVolumeProfileFn = unsafe extern "C" fn(symbol: *const c_char, out: *mut CVolumeLevel, max: usize) -> usize;
+
+// Потоки market data для subscribe_stream
+pub const STREAM_BOOK_TICKER: u8 = 0;
+pub const STREAM_TRADE: u8 = 1;
+
+// Режим ожидания событий инстанса ("recv_mode" в запросе запуска)
+pub const RECV_SLEEP: u8 = 0;
+pub const RECV_SPIN: u8 = 1;
+
+// Тип метрики для emit_metric
+pub const METRIC_COUNTER: u8 = 0;  // value прибавляется
+pub const METRIC_GAUGE: u8 = 1;    // value заменяет текущее
+pub const METRIC_SUMMARY: u8 = 2;  // value - наблюдение: в /metrics _sum и _count
+
+pub const LOG_DEBUG: u8 = 0;
+pub const LOG_INFO: u8 = 1;
+pub const LOG_WARN: u8 = 2;
+pub const LOG_ERROR: u8 = 3;
+
+/// Функции ядра. Новые поля добавляются только в конец,
+/// size = размер структуры в версии ядра, запустившей стратегию.
+#[repr(C)]
+pub struct HostApi {
+    pub size: u32,
+    pub get_orders: GetOrdersFn,
+    pub log: LogFn,
+    pub server_time_ms: TimeFn,
+    pub time_offset_ms: TimeFn,
+    pub next_funding_time_ms: FundingTimeFn,
+    pub stage_orders: StageOrdersFn,
+    pub fire_staged: FireStagedFn,
+    pub unstage: UnstageFn,
+    pub recv_mode: RecvModeFn,
+    pub now_ns: TimeFn,
+    pub heartbeat: HeartbeatFn,
+    pub funding_interval_ms: FundingTimeFn,
+    pub notify: NotifyFn,
+    pub kv_get: KvGetFn,
+    pub kv_set: KvSetFn,
+    pub kv_delete: KvDeleteFn,
+    pub schedule_timer: ScheduleTimerFn,
+    pub cancel_timer: CancelTimerFn,
+    pub subscribe_stream: StreamFn,
+    pub unsubscribe_stream: StreamFn,
+    pub place_algo_order: PlaceAlgoFn,
+    pub cancel_algo_order: CancelAlgoFn,
+    pub place_bracket: PlaceBracketFn,
+    pub cancel_bracket: CancelBracketFn,
+    pub get_position: GetPositionFn,
+    pub close_position: ClosePositionFn,
+    pub place_priority_order: PlaceOrderFn,
+    pub get_vwap: GetVwapFn,
+    pub get_volume_profile: GetVolumeProfileFn,
+    pub emit_metric: EmitMetricFn,
+}
+
+impl HostApi {
+    /// Есть ли поле по смещению offset в версии ядра, запустившей стратегию
+    pub fn has(&self, offset: usize) -> bool {
+        (self.size as usize) > offset
+    }
+}
+
+// ═══════════════════════════════════════════════════════════
+// LOGGING
+// ═══════════════════════════════════════════════════════════
+
+static HOST: AtomicPtr<HostApi> = AtomicPtr::new(std::ptr::null_mut());
+
+/// Строка в лог инстанса (GET /api/instances/{id}/logs).
+/// Работает и из callback'ов; без поддержки в ядре - println!
+pub fn log(level: u8, msg: &str) {
+    let host = unsafe { HOST.load(Ordering::Relaxed).as_ref() };
+    match host {
+        Some(host) if host.has(std::mem::offset_of!(HostApi, log)) => unsafe {
+            (host.log)(level, msg.as_ptr(), msg.len());
+        },
+        _ => println!("{}", msg),
+    }
+}
+
+pub fn log_info(msg: &str) {
+    log(LOG_INFO, msg);
+}
+
+pub fn log_warn(msg: &str) {
+    log(LOG_WARN, msg);
+}
+
+pub fn log_error(msg: &str) {
+    log(LOG_ERROR, msg);
+}
+
+// ═══════════════════════════════════════════════════════════
+// CONFIG
+// ═══════════════════════════════════════════════════════════
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub struct StrategyConfig {
-    pub symbol: [u8; 16],           // "SOLUSDT"
+    pub symbol: [u8; 32],
     pub symbol_len: u8,
-    pub params_json: *const c_char, // JSON строка с параметрами
+    pub params_json: *const c_char,
+    pub stop_flag: *const AtomicBool,
+    pub host: *const HostApi,
 }
 
 impl StrategyConfig {
-    pub fn symbol_str(&self) -> &str {
+    pub fn symbol(&self) -> &str {
         unsafe { std::str::from_utf8_unchecked(&self.symbol[..self.symbol_len as usize]) }
     }
-    
-    /// Парсинг JSON параметров в любую структуру
-    pub fn parse_params<T: serde::de::DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+
+    pub fn symbol_str(&self) -> &str {
+        self.symbol()
+    }
+
+    pub fn should_stop(&self) -> bool {
+        if self.stop_flag.is_null() {
+            return false;
+        }
+        unsafe { (*self.stop_flag).load(Ordering::Relaxed) }
+    }
+
+    pub fn params_raw(&self) -> &str {
         if self.params_json.is_null() {
-            // Пустой JSON объект по умолчанию
-            return serde_json::from_str("{}");
+            return "{}";
+        }
+        unsafe {
+            std::ffi::CStr::from_ptr(self.params_json)
+                .to_str()
+                .unwrap_or("{}")
+        }
+    }
+
+    pub fn host(&self) -> Option<&HostApi> {
+        unsafe { self.host.as_ref() }
+    }
+
+    /// Подключает log()/log_info()/... к ядру. Вызвать в начале run()
+    pub fn init_logging(&self) {
+        HOST.store(self.host as *mut HostApi, Ordering::Relaxed);
+    }
+
+    /// Ордера этого инстанса из order manager ядра (новые первыми).
+    /// symbol = None - все символы.
+    pub fn orders(&self, symbol: Option<&str>, open_only: bool) -> Vec<COrder> {
+        let Some(host) = self.host() else { return Vec::new() };
+        let symbol = symbol.and_then(|s| std::ffi::CString::new(s).ok());
+        let symbol_ptr = symbol.as_ref().map_or(std::ptr::null(), |s| s.as_ptr());
+
+        let mut buf: Vec<COrder> = Vec::with_capacity(256);
+        unsafe {
+            let n = (host.get_orders)(symbol_ptr, open_only, buf.as_mut_ptr(), buf.capacity());
+            buf.set_len(n);
+        }
+        buf
+    }
+
+    pub fn open_orders(&self) -> Vec<COrder> {
+        self.orders(None, true)
+    }
+
+    /// Время биржи, Unix ms (локальное + offset, измеренный ядром).
+    /// Все расчёты "успеть к секунде funding" вести от него, не от Local::now()
+    pub fn server_time_ms(&self) -> i64 {
+        match self.host() {
+            Some(host) if host.has(std::mem::offset_of!(HostApi, server_time_ms)) => {
+                (host.server_time_ms)()
+            }
+            _ => std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as i64),
+        }
+    }
+
+    /// Часы стратегии, Unix ns. На live - реальное время, на replay - время
+    /// проигрываемой записи. Брать время отсюда (или из now_local()),
+    /// иначе логика "за N секунд до funding" не воспроизводится на истории
+    pub fn now_ns(&self) -> i64 {
+        match self.host() {
+            Some(host) if host.has(std::mem::offset_of!(HostApi, now_ns)) => (host.now_ns)(),
+            _ => std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_nanos() as i64),
+        }
+    }
+
+    pub fn now_ms(&self) -> i64 {
+        self.now_ns() / 1_000_000
+    }
+
+    /// now_ns() в локальной зоне - замена Local::now()
+    pub fn now_local(&self) -> chrono::DateTime<chrono::Local> {
+        chrono::DateTime::from_timestamp_nanos(self.now_ns()).with_timezone(&chrono::Local)
+    }
+
+    /// Offset часов относительно Binance (server - local), ms
+    pub fn time_offset_ms(&self) -> i64 {
+        match self.host() {
+            Some(host) if host.has(std::mem::offset_of!(HostApi, time_offset_ms)) => {
+                (host.time_offset_ms)()
+            }
+            _ => 0,
+        }
+    }
+
+    /// Следующий funding по символу (время биржи, Unix ms), если ядро его знает
+    pub fn next_funding_time_ms(&self, symbol: &str) -> Option<i64> {
+        let host = self.host()?;
+        if !host.has(std::mem::offset_of!(HostApi, next_funding_time_ms)) {
+            return None;
+        }
+        let symbol = std::ffi::CString::new(symbol).ok()?;
+        let time = unsafe { (host.next_funding_time_ms)(symbol.as_ptr()) };
+        (time > 0).then_some(time)
+    }
+
+    /// Интервал funding по символу, ms (8h, у части символов 4h или 1h)
+    pub fn funding_interval_ms(&self, symbol: &str) -> Option<i64> {
+        let host = self.host()?;
+        if !host.has(std::mem::offset_of!(HostApi, funding_interval_ms)) {
+            return None;
+        }
+        let symbol = std::ffi::CString::new(symbol).ok()?;
+        let interval = unsafe { (host.funding_interval_ms)(symbol.as_ptr()) };
+        (interval > 0).then_some(interval)
+    }
+
+    /// Первый funding по символу строго после after_ms (время биржи, Unix ms).
+    /// Считать расписание от него, а не от "00:00/08:00/16:00" в локальной зоне:
+    /// интервал у символов разный, и биржа его меняет
+    pub fn next_funding_after(&self, symbol: &str, after_ms: i64) -> Option<i64> {
+        let mut next = self.next_funding_time_ms(symbol)?;
+        if next <= after_ms {
+            let interval = self.funding_interval_ms(symbol).unwrap_or(8 * 3_600_000);
+            next += ((after_ms - next) / interval + 1) * interval;
+        }
+        Some(next)
+    }
+
+    /// Уведомление в Telegram/webhook ядра (если каналы настроены).
+    /// Ядро пропускает не больше одного уведомления в custom_min_interval_ms
+    pub fn notify(&self, message: &str) {
+        if let Some(host) = self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, notify))) {
+            unsafe { (host.notify)(message.as_ptr(), message.len()) };
+        }
+    }
+
+    /// Значение из состояния стратегии в ядре. Состояние общее для всех
+    /// инстансов стратегии и переживает перезапуск (GET /api/kv/{strategy_id}).
+    /// На replay и dry run запись идёт в копию, которая отбрасывается после остановки
+    pub fn kv_get(&self, key: &str) -> Option<String> {
+        let host = self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, kv_delete)))?;
+        let mut buf = vec![0u8; 256];
+        loop {
+            let len = unsafe { (host.kv_get)(key.as_ptr(), key.len(), buf.as_mut_ptr(), buf.len()) };
+            if len < 0 {
+                return None;
+            }
+            // Значение могли перезаписать между вызовами - повторяем, пока не влезет
+            if len as usize > buf.len() {
+                buf.resize(len as usize, 0);
+                continue;
+            }
+            buf.truncate(len as usize);
+            return String::from_utf8(buf).ok();
+        }
+    }
+
+    /// false - ядро без kv или лимит (размер значения, число ключей), причина в логе инстанса
+    pub fn kv_set(&self, key: &str, value: &str) -> bool {
+        match self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, kv_delete))) {
+            Some(host) => unsafe { (host.kv_set)(key.as_ptr(), key.len(), value.as_ptr(), value.len()) },
+            None => false,
+        }
+    }
+
+    pub fn kv_delete(&self, key: &str) -> bool {
+        match self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, kv_delete))) {
+            Some(host) => unsafe { (host.kv_delete)(key.as_ptr(), key.len()) },
+            None => false,
+        }
+    }
+
+    /// kv_get + JSON: let entry: Option<f64> = config.kv_get_json("entry_price")
+    pub fn kv_get_json<T: serde::de::DeserializeOwned>(&self, key: &str) -> Option<T> {
+        serde_json::from_str(&self.kv_get(key)?).ok()
+    }
+
+    pub fn kv_set_json<T: serde::Serialize>(&self, key: &str, value: &T) -> bool {
+        serde_json::to_string(value).is_ok_and(|json| self.kv_set(key, &json))
+    }
+
+    /// Через delay_ms в канал придёт EVENT_TIMER с этим timer_id - вместо
+    /// sleep и проверок времени на каждом событии. Тот же timer_id ещё раз -
+    /// перенос срока. На replay срабатывает по времени записи.
+    /// false - ядро без таймеров или взведено слишком много
+    pub fn schedule_timer(&self, delay_ms: u64, timer_id: u64) -> bool {
+        match self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, cancel_timer))) {
+            Some(host) => (host.schedule_timer)(delay_ms, timer_id),
+            None => false,
+        }
+    }
+
+    /// Таймер на момент по часам стратегии (Unix ms); прошедший - срабатывает сразу
+    pub fn schedule_timer_at(&self, at_ms: i64, timer_id: u64) -> bool {
+        self.schedule_timer((at_ms - self.now_ms()).max(0) as u64, timer_id)
+    }
+
+    /// false - таймер не взведён или уже сработал
+    pub fn cancel_timer(&self, timer_id: u64) -> bool {
+        match self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, cancel_timer))) {
+            Some(host) => (host.cancel_timer)(timer_id),
+            None => false,
+        }
+    }
+
+    /// Подписка на поток другого символа (STREAM_BOOK_TICKER / STREAM_TRADE):
+    /// его события пойдут в тот же rx. Снимается unsubscribe_stream или
+    /// остановкой инстанса. false - replay/dry run, ядро без подписок или лимит
+    pub fn subscribe_stream(&self, symbol: &str, stream_type: u8) -> bool {
+        self.stream_call(symbol, stream_type, true)
+    }
+
+    pub fn unsubscribe_stream(&self, symbol: &str, stream_type: u8) -> bool {
+        self.stream_call(symbol, stream_type, false)
+    }
+
+    fn stream_call(&self, symbol: &str, stream_type: u8, subscribe: bool) -> bool {
+        let Some(host) = self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, unsubscribe_stream))) else {
+            return false;
+        };
+        let Ok(symbol) = std::ffi::CString::new(symbol) else { return false };
+        let f = if subscribe { host.subscribe_stream } else { host.unsubscribe_stream };
+        unsafe { f(symbol.as_ptr(), stream_type) }
+    }
+
+    /// Режим ожидания событий, выбранный при запуске (RECV_SLEEP / RECV_SPIN)
+    pub fn recv_mode(&self) -> u8 {
+        match self.host() {
+            Some(host) if host.has(std::mem::offset_of!(HostApi, recv_mode)) => (host.recv_mode)(),
+            _ => RECV_SLEEP,
         }
-        
+    }
+
+    /// Метрика в GET /metrics ядра как strategy_<name> (счётчик - с _total)
+    /// с метками instance_id, strategy, symbol, mode - вместо print_stats()
+    /// при остановке. Имя -
+    /// [a-zA-Z_][a-zA-Z0-9_]*, не больше 64 разных на инстанс, у имени один тип.
+    /// false - не принята (или ядро старое)
+    pub fn emit_metric(&self, name: &str, value: f64, kind: u8) -> bool {
+        match self.host() {
+            Some(host) if host.has(std::mem::offset_of!(HostApi, emit_metric)) => unsafe {
+                (host.emit_metric)(name.as_ptr(), name.len(), value, kind)
+            },
+            _ => false,
+        }
+    }
+
+    /// Признак жизни для watchdog'а ядра. Стратегия, которая забирает события
+    /// из rx, звать его не обязана; нужен, если она надолго перестаёт читать
+    /// очередь (ждёт funding в sleep, долго считает) - иначе её сочтут зависшей
+    pub fn heartbeat(&self) {
+        if let Some(host) = self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, heartbeat))) {
+            (host.heartbeat)();
+        }
+    }
+
+    /// Следующее событие с учётом recv_mode.
+    /// None - событий не было ~100ms (или пора остановиться): проверить should_stop и звать снова.
+    ///
+    /// ```ignore
+    /// let mode = config.recv_mode();
+    /// while !config.should_stop() {
+    ///     let Some(event) = config.recv_event(rx, mode) else { continue };
+    ///     ...
+    /// }
+    /// ```
+    pub fn recv_event(&self, rx: &Receiver<CEvent>, mode: u8) -> Option<CEvent> {
+        if mode != RECV_SPIN {
+            return match rx.recv_timeout(Duration::from_millis(100)) {
+                Ok(event) => Some(event),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => {
+                    std::thread::sleep(Duration::from_millis(100));
+                    None
+                }
+            };
+        }
+
+        // Крутимся на своём ядре; stop_flag проверяем не на каждой итерации
+        let mut spins = 0u32;
+        loop {
+            match rx.try_recv() {
+                Ok(event) => return Some(event),
+                Err(TryRecvError::Empty) => std::hint::spin_loop(),
+                Err(TryRecvError::Disconnected) => return None,
+            }
+            spins = spins.wrapping_add(1);
+            if spins % 4096 == 0 && self.should_stop() {
+                return None;
+            }
+        }
+    }
+
+    /// Пачка событий: ждёт первое (как recv_event), затем без ожидания
+    /// забирает из очереди всё накопившееся, всего не больше max.
+    /// buf очищается; возвращает число событий (0 - таймаут/остановка).
+    ///
+    /// В пиках (секунда funding, каскады) одна проверка стопа и один
+    /// пересчёт на пачку вместо recv_timeout на каждое событие.
+    pub fn recv_many(&self, rx: &Receiver<CEvent>, mode: u8, buf: &mut Vec<CEvent>, max: usize) -> usize {
+        buf.clear();
+        if max == 0 {
+            return 0;
+        }
+        let Some(first) = self.recv_event(rx, mode) else { return 0 };
+        buf.push(first);
+        buf.extend(rx.try_iter().take(max - 1));
+        buf.len()
+    }
+
+    /// Регистрирует шаблоны ордеров заранее (ключи, символ, side, объём):
+    /// ядро проверяет их и собирает параметры ордеров один раз.
+    /// В момент триггера остаётся передать только цены в fire_staged.
+    /// None - ядро не поддерживает staging или шаблоны отклонены
+    /// (в том числе символ, которого нет на бирже)
+    pub fn stage_orders(&self, api_key: &str, secret_key: &str, templates: &[COrderTemplate]) -> Option<i64> {
+        let host = self.host()?;
+        if !host.has(std::mem::offset_of!(HostApi, unstage)) {
+            return None;
+        }
+        let api_key = std::ffi::CString::new(api_key).ok()?;
+        let secret_key = std::ffi::CString::new(secret_key).ok()?;
+        let id = unsafe {
+            (host.stage_orders)(api_key.as_ptr(), secret_key.as_ptr(), templates.as_ptr(), templates.len())
+        };
+        (id > 0).then_some(id)
+    }
+
+    /// Отправляет ордера stage: prices[i] - цена i-го шаблона (NaN - пропустить).
+    /// Возвращает число отправленных ордеров или ERR_STAGE_*
+    pub fn fire_staged(&self, stage_id: i64, prices: &[f64], callback: OrderCallback) -> i32 {
+        match self.host() {
+            Some(host) if host.has(std::mem::offset_of!(HostApi, fire_staged)) => unsafe {
+                (host.fire_staged)(stage_id, prices.as_ptr(), prices.len(), callback)
+            },
+            _ => ERR_STAGE_NOT_FOUND,
+        }
+    }
+
+    /// Удаляет stage (ядро также чистит их после завершения run)
+    pub fn unstage(&self, stage_id: i64) -> bool {
+        match self.host() {
+            Some(host) if host.has(std::mem::offset_of!(HostApi, unstage)) => (host.unstage)(stage_id),
+            _ => false,
+        }
+    }
+
+    /// Крупный объём частями вместо одного market в тонкую книгу:
+    /// ядро само режет его на дочерние ордера (они придут в ORDER_UPDATE).
+    /// Возвращает id алгоритма (> 0) или ERR_ALGO_*
+    pub fn place_algo_order(&self, api_key: &str, secret_key: &str, order: &CAlgoOrder) -> i64 {
+        let Some(host) = self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, cancel_algo_order))) else {
+            return ERR_ALGO_UNSUPPORTED as i64;
+        };
+        let (Ok(api_key), Ok(secret_key)) = (std::ffi::CString::new(api_key), std::ffi::CString::new(secret_key)) else {
+            return ERR_ALGO_INVALID as i64;
+        };
+        unsafe { (host.place_algo_order)(api_key.as_ptr(), secret_key.as_ptr(), order) }
+    }
+
+    /// Останавливает алгоритм, стоящие дочерние ордера снимаются.
+    /// Алгоритмы инстанса останавливаются и при его остановке
+    pub fn cancel_algo_order(&self, algo_id: i64) -> bool {
+        match self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, cancel_algo_order))) {
+            Some(host) => (host.cancel_algo_order)(algo_id),
+            None => false,
+        }
+    }
+
+    /// Вход с защитой одним вызовом: после исполнения входа ядро само ставит
+    /// reduce-only TP (LIMIT) и SL (STOP_MARKET), исполнение одного снимает
+    /// другой. Только live. Возвращает id bracket (> 0) или ERR_BRACKET_*
+    pub fn place_bracket(&self, api_key: &str, secret_key: &str, bracket: &CBracket) -> i64 {
+        let Some(host) = self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, cancel_bracket))) else {
+            return ERR_BRACKET_UNSUPPORTED as i64;
+        };
+        let (Ok(api_key), Ok(secret_key)) = (std::ffi::CString::new(api_key), std::ffi::CString::new(secret_key)) else {
+            return ERR_BRACKET_INVALID as i64;
+        };
+        unsafe { (host.place_bracket)(api_key.as_ptr(), secret_key.as_ptr(), bracket) }
+    }
+
+    /// Снимает вход и защитные ордера; открытая позиция остаётся.
+    /// После остановки инстанса TP и SL продолжают работать
+    pub fn cancel_bracket(&self, bracket_id: i64) -> bool {
+        match self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, cancel_bracket))) {
+            Some(host) => (host.cancel_bracket)(bracket_id),
+            None => false,
+        }
+    }
+
+    /// Позиция по символу (> 0 long, < 0 short) по учёту ядра: у paper -
+    /// счёта симулятора, иначе ключа по снимку REST, ACCOUNT_UPDATE и
+    /// исполнениям. Some(0.0) - позиции нет; None - она ещё неизвестна
+    /// (ядро запрашивает снимок, повторите позже) или ядро старое
+    pub fn get_position(&self, api_key: &str, symbol: &str) -> Option<f64> {
+        let host = self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, close_position)))?;
+        let (Ok(api_key), Ok(symbol)) = (std::ffi::CString::new(api_key), std::ffi::CString::new(symbol)) else {
+            return None;
+        };
+        let amount = unsafe { (host.get_position)(api_key.as_ptr(), symbol.as_ptr()) };
+        (!amount.is_nan()).then_some(amount)
+    }
+
+    /// Закрывает позицию reduce-only market-ордером ровно на её размер
+    /// по учёту ядра - без угадывания объёма выхода. Возвращает размер
+    /// ордера; 0.0 - позиции нет, она неизвестна или ядро старое,
+    /// callback не вызывается
+    pub fn close_position(&self, api_key: &str, secret_key: &str, symbol: &str, callback: OrderCallback) -> f64 {
+        let Some(host) = self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, close_position))) else {
+            return 0.0;
+        };
+        let (Ok(api_key), Ok(secret_key), Ok(symbol)) = (
+            std::ffi::CString::new(api_key),
+            std::ffi::CString::new(secret_key),
+            std::ffi::CString::new(symbol),
+        ) else {
+            return 0.0;
+        };
+        unsafe { (host.close_position)(api_key.as_ptr(), secret_key.as_ptr(), symbol.as_ptr(), callback) }
+    }
+
+    /// place_order вне очереди trade WS: для panic-выхода или снижения риска,
+    /// когда в очереди могут стоять десятки обычных ордеров. Отмены,
+    /// close_position и защитные ордера bracket приоритетны и так.
+    /// None - ядро старое, остаётся обычный place_order
+    pub fn priority_place_order(&self) -> Option<PlaceOrderFn> {
+        self.host()
+            .filter(|h| h.has(std::mem::offset_of!(HostApi, place_priority_order)))
+            .map(|h| h.place_priority_order)
+    }
+
+    /// VWAP символа за окно профиля объёма ядра - для исполнения у VWAP без
+    /// своей очереди сделок. None - профиль выключен (config.volume_profile),
+    /// по символу не было сделок (нужна подписка на trade), replay или ядро старое
+    pub fn vwap(&self, symbol: &str) -> Option<CVwap> {
+        let host = self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, get_vwap)))?;
+        let symbol = std::ffi::CString::new(symbol).ok()?;
+        let mut out = std::mem::MaybeUninit::<CVwap>::uninit();
+        unsafe { (host.get_vwap)(symbol.as_ptr(), out.as_mut_ptr()).then(|| out.assume_init()) }
+    }
+
+    /// Footprint символа за то же окно по возрастанию цены: до max уровней
+    /// вокруг последней сделки. Пусто - нет данных (см. vwap)
+    pub fn volume_profile(&self, symbol: &str, max: usize) -> Vec<CVolumeLevel> {
+        let Some(host) = self.host().filter(|h| h.has(std::mem::offset_of!(HostApi, get_volume_profile))) else {
+            return Vec::new();
+        };
+        let Ok(symbol) = std::ffi::CString::new(symbol) else { return Vec::new() };
+        let mut buf: Vec<CVolumeLevel> = Vec::with_capacity(max);
         unsafe {
-            let c_str = std::ffi::CStr::from_ptr(self.params_json);
-            let json_str = c_str.to_str().unwrap_or("{}");
-            serde_json::from_str(json_str)
+            let n = (host.get_volume_profile)(symbol.as_ptr(), buf.as_mut_ptr(), max);
+            buf.set_len(n);
         }
+        buf
     }
-}
\ No newline at end of file
+
+    /// Alias ключей из keystore ядра (params "account").
+    /// Передаётся в place_order/cancel_order вместо api_key, secret_key - пустая строка
+    pub fn account(&self) -> Option<String> {
+        let v: serde_json::Value = serde_json::from_str(self.params_raw()).ok()?;
+        v.get("account")?.as_str().map(str::to_string)
+    }
+
+    /// Парсинг JSON параметров в любую структуру.
+    ///
+    /// Чтобы ядро отклоняло запуск с неизвестными или неверными полями,
+    /// экспортируйте схему (подмножество JSON Schema, лишние поля запрещены):
+    ///
+    /// ```ignore
+    /// #[no_mangle]
+    /// pub extern "C" fn params_schema() -> *const c_char {
+    ///     c"{\"type\":\"object\",\"properties\":{\"qty\":{\"type\":\"number\",\"exclusiveMinimum\":0}}}".as_ptr()
+    /// }
+    /// ```
+    pub fn parse_params<T: serde::de::DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        serde_json::from_str(self.params_raw())
+    }
+}
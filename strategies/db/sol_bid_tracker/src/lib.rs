@@ -70,8 +70,9 @@ pub extern "C" fn run(
     let rx = unsafe { &*rx_ptr };
     let mut strategy = Strategy::new();
     
-    let api_key = CString::new("ay0LdRfUbErVi6jTAanIjiuASqId3oLQYibIwCRQY3OLKiKdHCVGvLQgtH9X5wWm").unwrap();
-    let secret_key = CString::new("3AJ2HfjBkNPKwfsPswkzAkqVx6Jawm3xSQTOMKJ1ib5e4Wa9DEM5ddvfhDjeqPO4").unwrap();
+    // alias из keystore ядра (POST /api/keys)
+    let api_key = CString::new("main").unwrap();
+    let secret_key = CString::new("").unwrap();
     let symbol = CString::new("SOLUSDT").unwrap();
     let side = CString::new("BUY").unwrap();
     let quantity = 1.0;